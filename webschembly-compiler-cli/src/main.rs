@@ -1,7 +1,11 @@
 use clap::Parser;
 use std::io::Write;
 use std::path::Path;
-use webschembly_compiler::compiler::{Compiler, FlatConfig};
+use webschembly_compiler::compiler::{self, Compiler, Config, FlatConfig};
+use webschembly_compiler::jit::JitConfig;
+use webschembly_compiler::jit::profile::JitProfile;
+use webschembly_compiler::wasm_generator::{self, SourceMapUrl};
+use webschembly_compiler_ir::Module;
 
 #[derive(Parser, Debug)]
 #[command(version)]
@@ -14,6 +18,17 @@ struct Args {
     ir: bool,
     #[arg(long, default_value = "false")]
     jit: bool,
+    #[arg(long, default_value = "false")]
+    continuation: bool,
+    // nameセクションとSource Map(<output>.map)を出力する
+    #[arg(long, default_value = "false")]
+    debug_info: bool,
+    // 実行時に書き出したJITのプロファイル(JSON)。--jitと併用する
+    #[arg(long, requires = "jit")]
+    jit_profile: Option<String>,
+    // プロファイルを元に全ての特殊化を事前に生成し、全ての入力を1つのモジュールにまとめて出力する
+    #[arg(long, conflicts_with_all = ["jit", "jit_profile"])]
+    aot_profile: Option<String>,
     #[arg(required = true)]
     inputs: Vec<String>,
 }
@@ -32,28 +47,73 @@ fn main() -> anyhow::Result<()> {
     let output_stem = output.file_stem().unwrap_or_default();
     let output_extension = output.extension().unwrap_or_default();
 
-    let mut compiler = Compiler::new(
-        FlatConfig {
-            enable_jit: args.jit,
-            enable_jit_optimization: true,
-            enable_jit_small_block_fusion: true,
-            enable_jit_large_block_fusion: true,
-            enable_jit_log: false,
+    let jit_profile = match args.jit_profile.as_ref().or(args.aot_profile.as_ref()) {
+        Some(path) => {
+            let profile: JitProfile = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+            if !profile.is_compatible() {
+                anyhow::bail!(
+                    "{} was generated by an incompatible version (version {})",
+                    path,
+                    profile.version
+                );
+            }
+            profile
         }
-        .into(),
-    );
+        None => JitProfile::default(),
+    };
+
+    let mut config: Config = FlatConfig {
+        enable_jit: args.jit,
+        enable_jit_optimization: true,
+        enable_jit_small_block_fusion: true,
+        enable_jit_large_block_fusion: true,
+        enable_jit_log: false,
+        jit_tier_up_threshold: JitConfig::new().tier_up_threshold,
+        enable_continuation: args.continuation,
+        enable_debug_info: args.debug_info,
+    }
+    .into();
+    let aot = args.aot_profile.is_some();
+    if aot {
+        config.jit = Some(JitConfig {
+            aot: true,
+            ..JitConfig::new()
+        });
+    }
+    let mut compiler = Compiler::with_jit_profile(config, jit_profile);
     let mut srcs = Vec::new();
 
     if !args.no_stdlib {
-        srcs.push((webschembly_compiler::stdlib::generate_stdlib(), true));
+        srcs.push((
+            "stdlib".to_string(),
+            webschembly_compiler::stdlib::generate_stdlib(args.continuation),
+            true,
+        ));
     }
 
     for input in args.inputs {
         let src = std::fs::read_to_string(&input)?;
-        srcs.push((src, false));
+        srcs.push((input, src, false));
+    }
+
+    let mut modules = Vec::new();
+    for (name, src, is_stdlib) in srcs {
+        let module = match compiler.compile_module(&src, Some(&name), is_stdlib) {
+            Ok(module) => module,
+            Err(err) => {
+                eprint!("{}", err.render(&src, Some(&name)));
+                std::process::exit(1);
+            }
+        };
+        modules.push(module);
     }
 
-    for (i, (src, is_stdlib)) in srcs.into_iter().enumerate() {
+    // AOTの出力は実行時にモジュールを追加しないので1つにまとめる
+    if aot {
+        modules = vec![compiler::link_modules(modules)];
+    }
+
+    for (i, module) in modules.into_iter().enumerate() {
         let mut output = output.clone();
         output.set_file_name(output_stem);
         if i != 0 {
@@ -61,17 +121,44 @@ fn main() -> anyhow::Result<()> {
         }
 
         output.add_extension(output_extension);
-        let mut o = std::fs::File::create(output)?;
+        write_module(&compiler, &module, &output, args.ir, aot)?;
+    }
+
+    Ok(())
+}
 
-        let module = compiler.compile_module(&src, is_stdlib)?;
+fn write_module(
+    compiler: &Compiler,
+    module: &Module,
+    output: &Path,
+    ir: bool,
+    aot: bool,
+) -> anyhow::Result<()> {
+    let mut o = std::fs::File::create(output)?;
 
-        if args.ir {
-            let s = module.display().to_string();
-            let bs = s.as_bytes();
-            o.write_all(bs)?;
-        } else {
-            let bs: Vec<u8> = webschembly_compiler::wasm_generator::generate(&module);
-            o.write_all(&bs)?;
+    if ir {
+        let s = module.display().to_string();
+        let bs = s.as_bytes();
+        o.write_all(bs)?;
+    } else {
+        let mut source_map_path = output.to_path_buf();
+        source_map_path.add_extension("map");
+        let mut config = compiler.wasm_generator_config();
+        // AOTの出力はランタイムのコンパイラなしで実行されるので位置情報を自身で持つ
+        config.embed_source_locs = aot;
+        if config.source_map.is_some() {
+            config.source_map = Some(SourceMapUrl::External(
+                source_map_path
+                    .file_name()
+                    .unwrap()
+                    .to_string_lossy()
+                    .into_owned(),
+            ));
+        }
+        let output = wasm_generator::generate_with_config(module, &config);
+        o.write_all(&output.wasm)?;
+        if let Some(source_map) = output.source_map {
+            std::fs::write(&source_map_path, source_map)?;
         }
     }
 
@@ -1,6 +1,8 @@
 use crate::{
+    cps::Cps,
     defined::Defined,
     desugared::Desugared,
+    macro_expander::MacroExpander,
     parsed::Parsed,
     tail_call::TailCall,
     used::{GlobalVarId, Used, VarIdGen},
@@ -14,6 +16,7 @@ pub type Final = Used<TailCall<Defined<Desugared<Parsed>>>>;
 #[derive(Debug)]
 pub struct ASTGenerator {
     var_id_gen: VarIdGen,
+    macro_expander: MacroExpander,
 }
 
 impl Default for ASTGenerator {
@@ -26,13 +29,25 @@ impl ASTGenerator {
     pub fn new() -> Self {
         Self {
             var_id_gen: VarIdGen::new(),
+            macro_expander: MacroExpander::new(),
         }
     }
 
-    pub fn gen_ast(&mut self, sexprs: Vec<LSExpr>) -> Result<Ast<Final>> {
+    // enable_continuationが有効な場合はcall/ccのためにCPS変換する
+    pub fn gen_ast(
+        &mut self,
+        sexprs: Vec<LSExpr>,
+        enable_continuation: bool,
+    ) -> Result<Ast<Final>> {
+        let sexprs = self.macro_expander.expand(sexprs)?;
         let parsed = Parsed::from_sexprs(sexprs)?;
         let desugared = Desugared::new().from_ast(parsed);
         let defined = Defined::from_ast(desugared)?;
+        let defined = if enable_continuation {
+            Cps::new().from_ast(defined)
+        } else {
+            defined
+        };
         let tail_call = TailCall::from_ast(defined);
         let used = Used::from_ast(tail_call, &mut self.var_id_gen)?;
         Ok(used)
@@ -0,0 +1,724 @@
+use std::rc::Rc;
+
+use webschembly_compiler_ast::*;
+use webschembly_compiler_locate::{L, LocatedValue, Span};
+// call/ccのためのCPS変換。Config::enable_continuationが有効なときだけ適用する
+// 全てのクロージャは第1引数に継続を受け取り、継続は1引数のクロージャとして表現される
+// 継続はトップレベルの式ごとに区切られる
+
+pub trait CpsPhase = AstPhase<
+        XAst = (),
+        XConst = (),
+        XDefine = !,
+        XLambda = (),
+        XCaseLambda = (),
+        XIf = (),
+        XCond = !,
+        XCall = (),
+        XVar = (),
+        XBegin = !,
+        XSet = (),
+        XLet = (),
+        XLetStar = !,
+        XLetRec = (),
+        XLetRecStar = (),
+        XNamedLet = !,
+        XDo = !,
+        XDelay = !,
+        XVector = (),
+        XUVector = (),
+        XQuote = !,
+        XCons = (),
+        XAnd = !,
+        XOr = !,
+        XExt = !,
+    > + 'static;
+
+const CPS_VAR_PREFIX: &str = "__cps_";
+
+#[derive(Debug)]
+pub struct Cps<P: CpsPhase> {
+    var_counter: usize,
+    _phase: std::marker::PhantomData<P>,
+}
+
+// ローカル変数のスコープ。組み込み関数の呼び出しかどうかの判定に使う
+#[derive(Debug)]
+struct ScopeNode {
+    name: String,
+    parent: Scope,
+}
+
+type Scope = Option<Rc<ScopeNode>>;
+
+fn scope_with(scope: &Scope, names: impl IntoIterator<Item = String>) -> Scope {
+    names.into_iter().fold(scope.clone(), |parent, name| {
+        Some(Rc::new(ScopeNode { name, parent }))
+    })
+}
+
+fn is_local(scope: &Scope, name: &str) -> bool {
+    let mut node = scope;
+    while let Some(n) = node {
+        if n.name == name {
+            return true;
+        }
+        node = &n.parent;
+    }
+    false
+}
+
+type MetaCont<P> = Box<dyn FnOnce(&mut Cps<P>, LExpr<P>) -> Vec<LExpr<P>>>;
+type ListCont<P> = Box<dyn FnOnce(&mut Cps<P>, Vec<LExpr<P>>) -> Vec<LExpr<P>>>;
+
+enum Cont<P: CpsPhase> {
+    // 継続を束縛した変数
+    Var(String),
+    // 変換時に展開される継続。継続の呼び出しを生成せず、値を受け取った後の式を直接生成する
+    // 受け取る式は副作用のない式か、継続を呼び出さない式
+    Meta(MetaCont<P>),
+}
+
+impl<P: CpsPhase> Default for Cps<P> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<P: CpsPhase> Cps<P> {
+    pub fn new() -> Self {
+        Self {
+            var_counter: 0,
+            _phase: std::marker::PhantomData,
+        }
+    }
+
+    pub fn from_ast(mut self, ast: Ast<P>) -> Ast<P> {
+        let mut exprs = Vec::new();
+        for expr in ast.exprs {
+            exprs.extend(self.cps_expr(expr, &None, Cont::Meta(Box::new(|_, v| vec![v]))));
+        }
+        Ast { x: ast.x, exprs }
+    }
+
+    fn fresh_var(&mut self, kind: &str) -> String {
+        let name = format!("{}{}_{}", CPS_VAR_PREFIX, kind, self.var_counter);
+        self.var_counter += 1;
+        name
+    }
+
+    fn apply_cont(&mut self, k: Cont<P>, value: LExpr<P>) -> Vec<LExpr<P>> {
+        match k {
+            Cont::Var(name) => {
+                let span = value.span;
+                vec![
+                    Expr::Call(
+                        (),
+                        Call {
+                            func: vec![Expr::Var((), name).with_span(span)],
+                            args: vec![vec![value]],
+                        },
+                    )
+                    .with_span(span),
+                ]
+            }
+            Cont::Meta(f) => f(self, value),
+        }
+    }
+
+    // 継続を値として渡せる式にする
+    fn reify_cont(&mut self, k: Cont<P>, span: Span) -> LExpr<P> {
+        match k {
+            Cont::Var(name) => Expr::Var((), name).with_span(span),
+            Cont::Meta(f) => {
+                let arg = self.fresh_var("v");
+                let body = f(self, Expr::Var((), arg.clone()).with_span(span));
+                Expr::Lambda(
+                    (),
+                    Lambda {
+                        args: vec![arg.with_span(span)],
+                        variadic_arg: None,
+                        body,
+                    },
+                )
+                .with_span(span)
+            }
+        }
+    }
+
+    // 継続を複数回参照するために変数に束縛する
+    fn share_cont(
+        &mut self,
+        k: Cont<P>,
+        span: Span,
+        body: impl FnOnce(&mut Self, String) -> Vec<LExpr<P>>,
+    ) -> Vec<LExpr<P>> {
+        match k {
+            Cont::Var(name) => body(self, name),
+            Cont::Meta(_) => {
+                let name = self.fresh_var("k");
+                let init = self.reify_cont(k, span);
+                let body = body(self, name.clone());
+                vec![
+                    Expr::Let(
+                        (),
+                        LetLike {
+                            bindings: vec![
+                                Binding {
+                                    name: name.with_span(span),
+                                    expr: vec![init],
+                                }
+                                .with_span(span),
+                            ],
+                            body,
+                        },
+                    )
+                    .with_span(span),
+                ]
+            }
+        }
+    }
+
+    fn cps_exprs(&mut self, exprs: Vec<LExpr<P>>, scope: &Scope, k: Cont<P>) -> Vec<LExpr<P>> {
+        let mut exprs = exprs.into_iter();
+        let Some(first) = exprs.next() else {
+            return self.apply_cont(k, Expr::Const((), Const::Nil).with_span(DUMMY_SPAN));
+        };
+        if exprs.len() == 0 {
+            return self.cps_expr(first, scope, k);
+        }
+        let rest = exprs.collect::<Vec<_>>();
+        let rest_scope = scope.clone();
+        self.cps_expr(
+            first,
+            scope,
+            Cont::Meta(Box::new(move |this, value| {
+                // 値を使わないので副作用のない式は捨てる
+                let mut result = if is_atom(&value) { vec![] } else { vec![value] };
+                result.extend(this.cps_exprs(rest, &rest_scope, k));
+                result
+            })),
+        )
+    }
+
+    fn cps_expr(&mut self, expr: LExpr<P>, scope: &Scope, k: Cont<P>) -> Vec<LExpr<P>> {
+        let span = expr.span;
+        match expr.value {
+            Expr::Const(x, lit) => self.apply_cont(k, Expr::Const(x, lit).with_span(span)),
+            Expr::Var(x, var) => self.apply_cont(k, Expr::Var(x, var).with_span(span)),
+            Expr::Lambda(x, lambda) => {
+                let lambda = self.cps_lambda(lambda, scope);
+                self.apply_cont(k, Expr::Lambda(x, lambda).with_span(span))
+            }
+            Expr::CaseLambda(x, case_lambda) => {
+                let case_lambda = self.cps_case_lambda(case_lambda, scope);
+                self.apply_cont(k, Expr::CaseLambda(x, case_lambda).with_span(span))
+            }
+            Expr::If(x, if_) => {
+                let scope_ = scope.clone();
+                self.cps_exprs(
+                    if_.cond,
+                    scope,
+                    Cont::Meta(Box::new(move |this, cond| {
+                        this.share_cont(k, span, |this, k| {
+                            let then = this.cps_exprs(if_.then, &scope_, Cont::Var(k.clone()));
+                            let els = this.cps_exprs(if_.els, &scope_, Cont::Var(k));
+                            vec![
+                                Expr::If(
+                                    x,
+                                    If {
+                                        cond: vec![cond],
+                                        then,
+                                        els,
+                                    },
+                                )
+                                .with_span(span),
+                            ]
+                        })
+                    })),
+                )
+            }
+            Expr::Call((), call) => self.cps_call(call, span, scope, k),
+            Expr::Set(x, set) => {
+                let name = set.name;
+                self.cps_exprs(
+                    set.expr,
+                    scope,
+                    Cont::Meta(Box::new(move |this, value| {
+                        this.apply_cont(
+                            k,
+                            Expr::Set(
+                                x,
+                                Set {
+                                    name,
+                                    expr: vec![value],
+                                },
+                            )
+                            .with_span(span),
+                        )
+                    })),
+                )
+            }
+            Expr::Let(x, let_like) => {
+                // 継続がletの束縛を参照しないように先に変数に束縛する
+                let scope = scope.clone();
+                self.share_cont(k, span, move |this, k| {
+                    let (names, inits): (Vec<_>, Vec<_>) = let_like
+                        .bindings
+                        .into_iter()
+                        .map(|binding| ((binding.value.name, binding.span), binding.value.expr))
+                        .unzip();
+                    let body_scope =
+                        scope_with(&scope, names.iter().map(|(name, _)| name.value.clone()));
+                    this.cps_list(
+                        inits,
+                        &scope,
+                        Box::new(move |this, values| {
+                            let body = this.cps_exprs(let_like.body, &body_scope, Cont::Var(k));
+                            vec![
+                                Expr::Let(
+                                    x,
+                                    LetLike {
+                                        bindings: names
+                                            .into_iter()
+                                            .zip(values)
+                                            .map(|((name, binding_span), value)| {
+                                                Binding {
+                                                    name,
+                                                    expr: vec![value],
+                                                }
+                                                .with_span(binding_span)
+                                            })
+                                            .collect(),
+                                        body,
+                                    },
+                                )
+                                .with_span(span),
+                            ]
+                        }),
+                    )
+                })
+            }
+            Expr::LetRec(x, let_like) => {
+                let scope = scope.clone();
+                self.share_cont(k, span, move |this, k| {
+                    let let_like = this.cps_let_rec(let_like, &scope, k);
+                    vec![Expr::LetRec(x, let_like).with_span(span)]
+                })
+            }
+            Expr::LetRecStar(x, let_like) => {
+                let scope = scope.clone();
+                self.share_cont(k, span, move |this, k| {
+                    let let_like = this.cps_let_rec(let_like, &scope, k);
+                    vec![Expr::LetRecStar(x, let_like).with_span(span)]
+                })
+            }
+            Expr::Vector(x, vec) => self.cps_list(
+                vec,
+                scope,
+                Box::new(move |this, values| {
+                    this.apply_cont(
+                        k,
+                        Expr::Vector(x, values.into_iter().map(|v| vec![v]).collect())
+                            .with_span(span),
+                    )
+                }),
+            ),
+            Expr::UVector(x, uvec) => {
+                let kind = uvec.kind;
+                self.cps_list(
+                    uvec.elements,
+                    scope,
+                    Box::new(move |this, values| {
+                        this.apply_cont(
+                            k,
+                            Expr::UVector(
+                                x,
+                                UVector {
+                                    kind,
+                                    elements: values.into_iter().map(|v| vec![v]).collect(),
+                                },
+                            )
+                            .with_span(span),
+                        )
+                    }),
+                )
+            }
+            Expr::Cons(x, cons) => self.cps_list(
+                vec![cons.car, cons.cdr],
+                scope,
+                Box::new(move |this, values| {
+                    let [car, cdr] = <[_; 2]>::try_from(values).ok().unwrap();
+                    this.apply_cont(
+                        k,
+                        Expr::Cons(
+                            x,
+                            Cons {
+                                car: vec![car],
+                                cdr: vec![cdr],
+                            },
+                        )
+                        .with_span(span),
+                    )
+                }),
+            ),
+            Expr::Define(x, _) => x,
+            Expr::Cond(x, _) => x,
+            Expr::Begin(x, _) => x,
+            Expr::LetStar(x, _) => x,
+            Expr::NamedLet(x, _, _) => x,
+            Expr::Do(x, _) => x,
+            Expr::Delay(x, _) => x,
+            Expr::Quote(x, _) => x,
+            Expr::And(x, _) => x,
+            Expr::Or(x, _) => x,
+            Expr::Ext(x) => x,
+        }
+    }
+
+    fn cps_lambda(&mut self, lambda: Lambda<P>, scope: &Scope) -> Lambda<P> {
+        let k = self.fresh_var("k");
+        let k_span = lambda.args.first().map_or_else(
+            || lambda.body.first().map_or(DUMMY_SPAN, |expr| expr.span),
+            |arg| arg.span,
+        );
+        let body_scope = scope_with(
+            scope,
+            lambda
+                .args
+                .iter()
+                .chain(lambda.variadic_arg.iter())
+                .map(|arg| arg.value.clone()),
+        );
+        let body = self.cps_exprs(lambda.body, &body_scope, Cont::Var(k.clone()));
+        Lambda {
+            args: std::iter::once(k.with_span(k_span))
+                .chain(lambda.args)
+                .collect(),
+            variadic_arg: lambda.variadic_arg,
+            body,
+        }
+    }
+
+    fn cps_case_lambda(&mut self, case_lambda: CaseLambda<P>, scope: &Scope) -> CaseLambda<P> {
+        CaseLambda {
+            clauses: case_lambda
+                .clauses
+                .into_iter()
+                .map(|clause| {
+                    clause
+                        .into_iter()
+                        .map(|expr| match expr.value {
+                            Expr::Lambda(x, lambda) => {
+                                Expr::Lambda(x, self.cps_lambda(lambda, scope)).with_span(expr.span)
+                            }
+                            _ => unreachable!("case-lambda clause must be a lambda"),
+                        })
+                        .collect()
+                })
+                .collect(),
+        }
+    }
+
+    // 初期化式がクロージャか定数ならそのまま残し、それ以外はset!で本体の先頭で初期化する
+    fn cps_let_rec(&mut self, let_like: LetLike<P>, scope: &Scope, k: String) -> LetLike<P> {
+        let scope = scope_with(
+            scope,
+            let_like
+                .bindings
+                .iter()
+                .map(|binding| binding.value.name.value.clone()),
+        );
+        let mut bindings = Vec::new();
+        let mut sets = Vec::new();
+        for binding in let_like.bindings {
+            let binding_span = binding.span;
+            let name = binding.value.name;
+            let expr = match <[_; 1]>::try_from(binding.value.expr) {
+                Ok([expr]) => match expr.value {
+                    Expr::Lambda(x, lambda) => {
+                        Expr::Lambda(x, self.cps_lambda(lambda, &scope)).with_span(expr.span)
+                    }
+                    Expr::CaseLambda(x, case_lambda) => {
+                        Expr::CaseLambda(x, self.cps_case_lambda(case_lambda, &scope))
+                            .with_span(expr.span)
+                    }
+                    Expr::Const(x, lit) => Expr::Const(x, lit).with_span(expr.span),
+                    value => {
+                        sets.push(
+                            Expr::Set(
+                                (),
+                                Set {
+                                    name: name.clone(),
+                                    expr: vec![value.with_span(expr.span)],
+                                },
+                            )
+                            .with_span(binding_span),
+                        );
+                        Expr::Const((), Const::Bool(false)).with_span(binding_span)
+                    }
+                },
+                Err(exprs) => {
+                    sets.push(
+                        Expr::Set(
+                            (),
+                            Set {
+                                name: name.clone(),
+                                expr: exprs,
+                            },
+                        )
+                        .with_span(binding_span),
+                    );
+                    Expr::Const((), Const::Bool(false)).with_span(binding_span)
+                }
+            };
+            bindings.push(
+                Binding {
+                    name,
+                    expr: vec![expr],
+                }
+                .with_span(binding_span),
+            );
+        }
+        sets.extend(let_like.body);
+        let body = self.cps_exprs(sets, &scope, Cont::Var(k));
+        LetLike { bindings, body }
+    }
+
+    fn cps_call(&mut self, call: Call<P>, span: Span, scope: &Scope, k: Cont<P>) -> Vec<LExpr<P>> {
+        match builtin_of(&call.func, scope) {
+            Some((_, Builtin::CallCc)) if call.args.len() == 1 => self.cps_list(
+                call.args,
+                scope,
+                Box::new(move |this, values| {
+                    let [func] = <[_; 1]>::try_from(values).ok().unwrap();
+                    this.share_cont(k, span, |_, k| {
+                        let k = || vec![Expr::Var((), k.clone()).with_span(span)];
+                        vec![
+                            Expr::Call(
+                                (),
+                                Call {
+                                    func: vec![func],
+                                    args: vec![k(), k()],
+                                },
+                            )
+                            .with_span(span),
+                        ]
+                    })
+                }),
+            ),
+            Some((_, Builtin::Resume)) if call.args.len() == 2 => self.cps_list(
+                call.args,
+                scope,
+                Box::new(move |_, values| {
+                    // 現在の継続は捨てる
+                    let [cont, value] = <[_; 2]>::try_from(values).ok().unwrap();
+                    vec![
+                        Expr::Call(
+                            (),
+                            Call {
+                                func: vec![cont],
+                                args: vec![vec![value]],
+                            },
+                        )
+                        .with_span(span),
+                    ]
+                }),
+            ),
+            Some((name, Builtin::Apply)) if call.args.len() == 2 => self.cps_list(
+                call.args,
+                scope,
+                Box::new(move |this, values| {
+                    // 継続を引数リストの先頭に追加する
+                    let [func, args] = <[_; 2]>::try_from(values).ok().unwrap();
+                    let k = this.reify_cont(k, span);
+                    vec![
+                        Expr::Call(
+                            (),
+                            Call {
+                                func: vec![Expr::Var((), name).with_span(span)],
+                                args: vec![
+                                    vec![func],
+                                    vec![
+                                        Expr::Cons(
+                                            (),
+                                            Cons {
+                                                car: vec![k],
+                                                cdr: vec![args],
+                                            },
+                                        )
+                                        .with_span(span),
+                                    ],
+                                ],
+                            },
+                        )
+                        .with_span(span),
+                    ]
+                }),
+            ),
+            Some((name, _)) => self.cps_list(
+                call.args,
+                scope,
+                Box::new(move |this, values| {
+                    this.apply_cont(
+                        k,
+                        Expr::Call(
+                            (),
+                            Call {
+                                func: vec![Expr::Var((), name).with_span(span)],
+                                args: values.into_iter().map(|v| vec![v]).collect(),
+                            },
+                        )
+                        .with_span(span),
+                    )
+                }),
+            ),
+            None => self.cps_list(
+                std::iter::once(call.func).chain(call.args).collect(),
+                scope,
+                Box::new(move |this, values| {
+                    let mut values = values.into_iter();
+                    let func = values.next().unwrap();
+                    let k = this.reify_cont(k, span);
+                    vec![
+                        Expr::Call(
+                            (),
+                            Call {
+                                func: vec![func],
+                                args: std::iter::once(vec![k])
+                                    .chain(values.map(|v| vec![v]))
+                                    .collect(),
+                            },
+                        )
+                        .with_span(span),
+                    ]
+                }),
+            ),
+        }
+    }
+
+    // 式のリストを左から順に評価し、値のリストをfに渡す
+    fn cps_list(
+        &mut self,
+        exprs: Vec<ExprBox<LExpr<P>>>,
+        scope: &Scope,
+        f: ListCont<P>,
+    ) -> Vec<LExpr<P>> {
+        self.cps_list_inner(exprs.into_iter(), Vec::new(), scope, f)
+    }
+
+    fn cps_list_inner(
+        &mut self,
+        mut exprs: std::vec::IntoIter<ExprBox<LExpr<P>>>,
+        mut values: Vec<LExpr<P>>,
+        scope: &Scope,
+        f: ListCont<P>,
+    ) -> Vec<LExpr<P>> {
+        let Some(expr) = exprs.next() else {
+            return f(self, values);
+        };
+        // 残りの式が継続を呼び出すなら、評価順を保つために値を変数に束縛する
+        let rest_direct = exprs
+            .as_slice()
+            .iter()
+            .all(|expr| is_direct_exprs(expr, scope));
+        let scope_ = scope.clone();
+        self.cps_exprs(
+            expr,
+            scope,
+            Cont::Meta(Box::new(move |this, value| {
+                if rest_direct || is_stable(&value) {
+                    values.push(value);
+                    this.cps_list_inner(exprs, values, &scope_, f)
+                } else {
+                    let span = value.span;
+                    let name = this.fresh_var("v");
+                    values.push(Expr::Var((), name.clone()).with_span(span));
+                    let body = this.cps_list_inner(exprs, values, &scope_, f);
+                    vec![
+                        Expr::Let(
+                            (),
+                            LetLike {
+                                bindings: vec![
+                                    Binding {
+                                        name: name.with_span(span),
+                                        expr: vec![value],
+                                    }
+                                    .with_span(span),
+                                ],
+                                body,
+                            },
+                        )
+                        .with_span(span),
+                    ]
+                }
+            })),
+        )
+    }
+}
+
+const DUMMY_SPAN: Span = Span {
+    start: webschembly_compiler_locate::Pos { line: 0, column: 0 },
+    end: webschembly_compiler_locate::Pos { line: 0, column: 0 },
+};
+
+// 特殊な変換が必要な組み込み関数の呼び出しか
+fn builtin_of<P: CpsPhase>(func: &ExprBox<LExpr<P>>, scope: &Scope) -> Option<(String, Builtin)> {
+    if let [
+        L {
+            value: Expr::Var(_, name),
+            ..
+        },
+    ] = func.as_slice()
+        && !is_local(scope, name)
+    {
+        Builtin::from_name(name).map(|builtin| (name.clone(), builtin))
+    } else {
+        None
+    }
+}
+
+fn is_atom<P: CpsPhase>(expr: &LExpr<P>) -> bool {
+    matches!(
+        expr.value,
+        Expr::Const(..) | Expr::Var(..) | Expr::Lambda(..) | Expr::CaseLambda(..)
+    )
+}
+
+// 後から評価しても値が変わらない式
+fn is_stable<P: CpsPhase>(expr: &LExpr<P>) -> bool {
+    match &expr.value {
+        Expr::Const(..) | Expr::Lambda(..) | Expr::CaseLambda(..) => true,
+        Expr::Var(_, name) => name.starts_with(CPS_VAR_PREFIX),
+        _ => false,
+    }
+}
+
+// 変換しても継続を呼び出さず、1つの式のまま残る式
+fn is_direct<P: CpsPhase>(expr: &LExpr<P>, scope: &Scope) -> bool {
+    match &expr.value {
+        Expr::Const(..) | Expr::Var(..) | Expr::Lambda(..) | Expr::CaseLambda(..) => true,
+        Expr::Set(_, set) => is_direct_exprs(&set.expr, scope),
+        Expr::Call(_, call) => match builtin_of(&call.func, scope) {
+            Some((_, Builtin::CallCc | Builtin::Resume | Builtin::Apply)) | None => false,
+            Some(_) => call.args.iter().all(|arg| is_direct_exprs(arg, scope)),
+        },
+        Expr::Vector(_, vec) => vec.iter().all(|expr| is_direct_exprs(expr, scope)),
+        Expr::UVector(_, uvec) => uvec
+            .elements
+            .iter()
+            .all(|expr| is_direct_exprs(expr, scope)),
+        Expr::Cons(_, cons) => {
+            is_direct_exprs(&cons.car, scope) && is_direct_exprs(&cons.cdr, scope)
+        }
+        _ => false,
+    }
+}
+
+fn is_direct_exprs<P: CpsPhase>(exprs: &ExprBox<LExpr<P>>, scope: &Scope) -> bool {
+    match exprs.as_slice() {
+        [] => true,
+        [expr] => is_direct(expr, scope),
+        _ => false,
+    }
+}
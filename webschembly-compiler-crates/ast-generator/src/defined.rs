@@ -1,5 +1,5 @@
 use webschembly_compiler_ast::*;
-use webschembly_compiler_error::{Result, compiler_error};
+use webschembly_compiler_error::{CompilerError, Diagnostic, ErrorCode, Result};
 use webschembly_compiler_locate::{Located, LocatedValue, Span};
 // defineをletrec or set!に変換
 
@@ -11,6 +11,7 @@ pub trait DefinedPrevPhase = AstPhase<
         XExt = !,
         XNamedLet = !,
         XDo = !,
+        XDelay = !,
         XAnd = !,
         XOr = !,
     >;
@@ -44,7 +45,16 @@ impl DefineContext {
 
 impl<P: DefinedPrevPhase> Defined<P> {
     pub fn from_ast(ast: Ast<P>) -> Result<Ast<Self>> {
-        let new_exprs = Self::from_exprs(ast.exprs, DefineContext::Global, &mut Vec::new())?;
+        let mut errors = Vec::new();
+        let new_exprs = Self::from_exprs(
+            ast.exprs,
+            DefineContext::Global,
+            &mut Vec::new(),
+            &mut errors,
+        );
+        if !errors.is_empty() {
+            return Err(CompilerError::new(errors));
+        }
         Ok(Ast {
             x: ast.x,
             exprs: new_exprs,
@@ -55,16 +65,15 @@ impl<P: DefinedPrevPhase> Defined<P> {
         expr: LExpr<P>,
         ctx: DefineContext,
         defines: &mut Vec<Located<Binding<Self>>>,
+        errors: &mut Vec<Diagnostic>,
         result: &mut Vec<LExpr<Self>>,
-    ) -> Result<()> {
+    ) {
         match expr.value {
             Expr::Const(x, lit) => {
                 result.push(Expr::Const(x, lit).with_span(expr.span));
-                Ok(())
             }
             Expr::Var(x, var) => {
                 result.push(Expr::Var(x, var).with_span(expr.span));
-                Ok(())
             }
             Expr::Define(_, def) => {
                 match ctx {
@@ -79,16 +88,20 @@ impl<P: DefinedPrevPhase> Defined<P> {
                                         def.expr,
                                         ctx.to_undefinable_if_local(),
                                         defines,
-                                    )?,
+                                        errors,
+                                    ),
                                 },
                             )
                             .with_span(expr.span),
                         );
-                        Ok(())
                     }
                     DefineContext::LocalDefinable => {
-                        let def_expr =
-                            Self::from_exprs(def.expr, ctx.to_undefinable_if_local(), defines)?;
+                        let def_expr = Self::from_exprs(
+                            def.expr,
+                            ctx.to_undefinable_if_local(),
+                            defines,
+                            errors,
+                        );
                         defines.push(
                             Binding {
                                 name: def.name,
@@ -96,16 +109,25 @@ impl<P: DefinedPrevPhase> Defined<P> {
                             }
                             .with_span(expr.span),
                         );
-                        Ok(())
                     }
-                    DefineContext::LocalUndefinable => Err(compiler_error!(
-                        "Define is not allowed in this context: {}",
-                        def.name.value
-                    )),
+                    DefineContext::LocalUndefinable => {
+                        // エラーを報告し、defineを無視して続行する
+                        errors.push(
+                            Diagnostic::error(
+                                ErrorCode::DefineNotAllowed,
+                                format!(
+                                    "define is not allowed in this context: {}",
+                                    def.name.value
+                                ),
+                            )
+                            .with_primary(expr.span, "")
+                            .with_note("internal defines must appear at the beginning of a body"),
+                        );
+                    }
                 }
             }
             Expr::Lambda(x, lambda) => {
-                let new_body = Self::from_exprs_new_scope(expr.span, lambda.body)?;
+                let new_body = Self::from_exprs_new_scope(expr.span, lambda.body, errors);
                 result.push(
                     Expr::Lambda(
                         x,
@@ -117,7 +139,28 @@ impl<P: DefinedPrevPhase> Defined<P> {
                     )
                     .with_span(expr.span),
                 );
-                Ok(())
+            }
+            Expr::CaseLambda(x, case_lambda) => {
+                result.push(
+                    Expr::CaseLambda(
+                        x,
+                        CaseLambda {
+                            clauses: case_lambda
+                                .clauses
+                                .into_iter()
+                                .map(|clause| {
+                                    Self::from_exprs(
+                                        clause,
+                                        ctx.to_undefinable_if_local(),
+                                        defines,
+                                        errors,
+                                    )
+                                })
+                                .collect(),
+                        },
+                    )
+                    .with_span(expr.span),
+                );
             }
             Expr::If(x, if_) => {
                 result.push(
@@ -128,27 +171,36 @@ impl<P: DefinedPrevPhase> Defined<P> {
                                 if_.cond,
                                 ctx.to_undefinable_if_local(),
                                 defines,
-                            )?,
+                                errors,
+                            ),
                             then: Self::from_exprs(
                                 if_.then,
                                 ctx.to_undefinable_if_local(),
                                 defines,
-                            )?,
-                            els: Self::from_exprs(if_.els, ctx.to_undefinable_if_local(), defines)?,
+                                errors,
+                            ),
+                            els: Self::from_exprs(
+                                if_.els,
+                                ctx.to_undefinable_if_local(),
+                                defines,
+                                errors,
+                            ),
                         },
                     )
                     .with_span(expr.span),
                 );
-                Ok(())
             }
             Expr::Cond(x, _) => x,
             Expr::Call(x, call) => {
-                let new_func = Self::from_exprs(call.func, ctx.to_undefinable_if_local(), defines)?;
+                let new_func =
+                    Self::from_exprs(call.func, ctx.to_undefinable_if_local(), defines, errors);
                 let new_args = call
                     .args
                     .into_iter()
-                    .map(|arg| Self::from_exprs(arg, ctx.to_undefinable_if_local(), defines))
-                    .collect::<Result<Vec<_>>>()?;
+                    .map(|arg| {
+                        Self::from_exprs(arg, ctx.to_undefinable_if_local(), defines, errors)
+                    })
+                    .collect::<Vec<_>>();
                 result.push(
                     Expr::Call(
                         x,
@@ -159,11 +211,11 @@ impl<P: DefinedPrevPhase> Defined<P> {
                     )
                     .with_span(expr.span),
                 );
-                Ok(())
             }
             Expr::Begin(x, _) => x,
             Expr::Set(_, set) => {
-                let new_expr = Self::from_exprs(set.expr, ctx.to_undefinable_if_local(), defines)?;
+                let new_expr =
+                    Self::from_exprs(set.expr, ctx.to_undefinable_if_local(), defines, errors);
                 result.push(
                     Expr::Set(
                         (),
@@ -174,22 +226,34 @@ impl<P: DefinedPrevPhase> Defined<P> {
                     )
                     .with_span(expr.span),
                 );
-                Ok(())
             }
             Expr::Let(x, let_like) => {
                 result.push(
-                    Expr::Let(x, Self::from_let_like(let_like, expr.span, ctx, defines)?)
-                        .with_span(expr.span),
+                    Expr::Let(
+                        x,
+                        Self::from_let_like(let_like, expr.span, ctx, defines, errors),
+                    )
+                    .with_span(expr.span),
                 );
-                Ok(())
             }
             Expr::LetStar(x, _) => x,
             Expr::LetRec(_, let_like) => {
                 result.push(
-                    Expr::LetRec((), Self::from_let_like(let_like, expr.span, ctx, defines)?)
-                        .with_span(expr.span),
+                    Expr::LetRec(
+                        (),
+                        Self::from_let_like(let_like, expr.span, ctx, defines, errors),
+                    )
+                    .with_span(expr.span),
+                );
+            }
+            Expr::LetRecStar(x, let_like) => {
+                result.push(
+                    Expr::LetRecStar(
+                        x,
+                        Self::from_let_like(let_like, expr.span, ctx, defines, errors),
+                    )
+                    .with_span(expr.span),
                 );
-                Ok(())
             }
 
             Expr::Vector(x, vec) => {
@@ -197,12 +261,13 @@ impl<P: DefinedPrevPhase> Defined<P> {
                     Expr::Vector(
                         x,
                         vec.into_iter()
-                            .map(|v| Self::from_exprs(v, ctx.to_undefinable_if_local(), defines))
-                            .collect::<Result<Vec<_>>>()?,
+                            .map(|v| {
+                                Self::from_exprs(v, ctx.to_undefinable_if_local(), defines, errors)
+                            })
+                            .collect::<Vec<_>>(),
                     )
                     .with_span(expr.span),
                 );
-                Ok(())
             }
             Expr::UVector(x, uvec) => {
                 result.push(
@@ -214,14 +279,18 @@ impl<P: DefinedPrevPhase> Defined<P> {
                                 .elements
                                 .into_iter()
                                 .map(|v| {
-                                    Self::from_exprs(v, ctx.to_undefinable_if_local(), defines)
+                                    Self::from_exprs(
+                                        v,
+                                        ctx.to_undefinable_if_local(),
+                                        defines,
+                                        errors,
+                                    )
                                 })
-                                .collect::<Result<Vec<_>>>()?,
+                                .collect::<Vec<_>>(),
                         },
                     )
                     .with_span(expr.span),
                 );
-                Ok(())
             }
             Expr::Quote(x, _) => x,
             Expr::Cons(x, cons) => {
@@ -233,17 +302,18 @@ impl<P: DefinedPrevPhase> Defined<P> {
                                 cons.car,
                                 ctx.to_undefinable_if_local(),
                                 defines,
-                            )?,
+                                errors,
+                            ),
                             cdr: Self::from_exprs(
                                 cons.cdr,
                                 ctx.to_undefinable_if_local(),
                                 defines,
-                            )?,
+                                errors,
+                            ),
                         },
                     )
                     .with_span(expr.span),
                 );
-                Ok(())
             }
             Expr::Ext(x) => x,
         }
@@ -253,29 +323,34 @@ impl<P: DefinedPrevPhase> Defined<P> {
         exprs: Vec<LExpr<P>>,
         mut ctx: DefineContext,
         defines: &mut Vec<Located<Binding<Self>>>,
-    ) -> Result<Vec<LExpr<Self>>> {
+        errors: &mut Vec<Diagnostic>,
+    ) -> Vec<LExpr<Self>> {
         let mut result = Vec::new();
         for expr in exprs {
             let is_define = matches!(expr.value, Expr::Define(_, _));
-            Self::from_expr(expr, ctx, defines, &mut result)?;
+            Self::from_expr(expr, ctx, defines, errors, &mut result);
             if !is_define {
                 // defineは先頭に連続して出現しないといけない
                 // beginは例外だが、desugerの時点でbeginは消されているので考慮しなくて良い
                 ctx = ctx.to_undefinable_if_local();
             }
         }
-        Ok(result)
+        result
     }
 
     // スコープを作る命令
     // 一つでもdefineがあれば全体をletrecで囲む
-    fn from_exprs_new_scope(span: Span, exprs: Vec<LExpr<P>>) -> Result<Vec<LExpr<Self>>> {
+    fn from_exprs_new_scope(
+        span: Span,
+        exprs: Vec<LExpr<P>>,
+        errors: &mut Vec<Diagnostic>,
+    ) -> Vec<LExpr<Self>> {
         let mut defines = Vec::new();
-        let exprs = Self::from_exprs(exprs, DefineContext::LocalDefinable, &mut defines)?;
+        let exprs = Self::from_exprs(exprs, DefineContext::LocalDefinable, &mut defines, errors);
         if defines.is_empty() {
-            Ok(exprs)
+            exprs
         } else {
-            Ok(vec![
+            vec![
                 Expr::LetRec(
                     (),
                     LetLike {
@@ -284,7 +359,7 @@ impl<P: DefinedPrevPhase> Defined<P> {
                     },
                 )
                 .with_span(span),
-            ])
+            ]
         }
     }
 
@@ -293,25 +368,27 @@ impl<P: DefinedPrevPhase> Defined<P> {
         span: Span,
         ctx: DefineContext,
         defines: &mut Vec<Located<Binding<Self>>>,
-    ) -> Result<LetLike<Self>> {
-        let new_body = Self::from_exprs_new_scope(span, let_like.body)?;
-        Ok(LetLike {
+        errors: &mut Vec<Diagnostic>,
+    ) -> LetLike<Self> {
+        let new_body = Self::from_exprs_new_scope(span, let_like.body, errors);
+        LetLike {
             bindings: let_like
                 .bindings
                 .into_iter()
                 .map(|binding| {
-                    Ok(Binding {
+                    Binding {
                         name: binding.value.name,
                         expr: Self::from_exprs(
                             binding.value.expr,
                             ctx.to_undefinable_if_local(),
                             defines,
-                        )?,
+                            errors,
+                        ),
                     }
-                    .with_span(binding.span))
+                    .with_span(binding.span)
                 })
-                .collect::<Result<Vec<_>>>()?,
+                .collect::<Vec<_>>(),
             body: new_body,
-        })
+        }
     }
 }
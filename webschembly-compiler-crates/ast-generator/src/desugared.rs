@@ -19,6 +19,7 @@ impl<P: DesugaredPrevPhase> ExtendAstPhase for Desugared<P> {
     type XCond = !;
     type XNamedLet = !;
     type XDo = !;
+    type XDelay = !;
     type XAnd = !;
     type XOr = !;
     type XIf = ();
@@ -30,7 +31,9 @@ impl<P: DesugaredPrevPhase> ExtendAstPhase for Desugared<P> {
     type XVector = ();
     type XUVector = ();
     type XLambda = ();
+    type XCaseLambda = ();
     type XLetRec = ();
+    type XLetRecStar = ();
 }
 
 impl<P: DesugaredPrevPhase> Default for Desugared<P> {
@@ -85,6 +88,19 @@ impl<P: DesugaredPrevPhase> Desugared<P> {
                 )
                 .with_span(expr.span),
             ),
+            Expr::CaseLambda(_, case_lambda) => exprs.push(
+                Expr::CaseLambda(
+                    (),
+                    CaseLambda {
+                        clauses: case_lambda
+                            .clauses
+                            .into_iter()
+                            .map(|clause| self.process_exprs(clause))
+                            .collect(),
+                    },
+                )
+                .with_span(expr.span),
+            ),
             Expr::If(_, if_) => exprs.push(
                 Expr::If(
                     (),
@@ -267,6 +283,8 @@ impl<P: DesugaredPrevPhase> Desugared<P> {
             Expr::LetRec(_, let_like) => {
                 exprs.push(Expr::LetRec((), self.process_let_like(let_like)).with_span(expr.span))
             }
+            Expr::LetRecStar(_, let_like) => exprs
+                .push(Expr::LetRecStar((), self.process_let_like(let_like)).with_span(expr.span)),
             Expr::NamedLet(_, name, let_like) => {
                 let func_var = name.value.clone();
                 let lambda = Expr::Lambda(
@@ -402,6 +420,36 @@ impl<P: DesugaredPrevPhase> Desugared<P> {
                 .with_span(expr.span);
                 exprs.push(letrec);
             }
+            Expr::Delay(_, delay) => {
+                // (delay expr) => (%make-lazy-promise (lambda () (%make-eager-promise expr)))
+                // (delay-force expr) => (%make-lazy-promise (lambda () expr))
+                // exprはthunkの末尾位置にあるため、delay-forceの連鎖はforceで反復的に評価される
+                let call = |func: &str, args: Vec<Vec<LExpr<Self>>>| {
+                    Expr::Call(
+                        (),
+                        Call {
+                            func: vec![Expr::Var((), func.to_string()).with_span(expr.span)],
+                            args,
+                        },
+                    )
+                    .with_span(expr.span)
+                };
+                let body = self.process_exprs(delay.expr);
+                let body = match delay.kind {
+                    DelayKind::Delay => vec![call("%make-eager-promise", vec![body])],
+                    DelayKind::DelayForce => body,
+                };
+                let thunk = Expr::Lambda(
+                    (),
+                    Lambda {
+                        args: Vec::new(),
+                        variadic_arg: None,
+                        body,
+                    },
+                )
+                .with_span(expr.span);
+                exprs.push(call("%make-lazy-promise", vec![vec![thunk]]));
+            }
             Expr::And(_, and_exprs) => {
                 if and_exprs.is_empty() {
                     exprs.push(Expr::Const((), Const::Bool(true)).with_span(expr.span));
@@ -521,6 +569,10 @@ impl<P: DesugaredPrevPhase> Desugared<P> {
         match sexpr.value {
             sexpr::SExpr::Bool(b) => Expr::Const((), Const::Bool(b)).with_span(sexpr.span),
             sexpr::SExpr::Int(i) => Expr::Const((), Const::Int(i)).with_span(sexpr.span),
+            sexpr::SExpr::BigInt(i) => Expr::Const((), Const::BigInt(i)).with_span(sexpr.span),
+            sexpr::SExpr::Rational(n, d) => {
+                Expr::Const((), Const::Rational(n, d)).with_span(sexpr.span)
+            }
             sexpr::SExpr::Float(f) => Expr::Const((), Const::Float(f)).with_span(sexpr.span),
             sexpr::SExpr::NaN => Expr::Const((), Const::NaN).with_span(sexpr.span),
             sexpr::SExpr::String(s) => Expr::Const((), Const::String(s)).with_span(sexpr.span),
@@ -550,6 +602,13 @@ impl<P: DesugaredPrevPhase> Desugared<P> {
                     kind: match kind {
                         sexpr::SUVectorKind::S64 => UVectorKind::S64,
                         sexpr::SUVectorKind::F64 => UVectorKind::F64,
+                        sexpr::SUVectorKind::U8 => UVectorKind::U8,
+                        sexpr::SUVectorKind::S8 => UVectorKind::S8,
+                        sexpr::SUVectorKind::U16 => UVectorKind::U16,
+                        sexpr::SUVectorKind::S16 => UVectorKind::S16,
+                        sexpr::SUVectorKind::U32 => UVectorKind::U32,
+                        sexpr::SUVectorKind::S32 => UVectorKind::S32,
+                        sexpr::SUVectorKind::F32 => UVectorKind::F32,
                     },
                     elements: elements
                         .into_iter()
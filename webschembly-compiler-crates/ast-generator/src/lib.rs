@@ -7,15 +7,19 @@
 )]
 
 mod ast_generator;
+mod cps;
 mod defined;
 mod desugared;
+mod macro_expander;
 mod parsed;
 mod tail_call;
 mod used;
 
 pub use ast_generator::*;
+pub use cps::*;
 pub use defined::*;
 pub use desugared::*;
+pub use macro_expander::*;
 pub use parsed::*;
 pub use tail_call::*;
 pub use used::*;
@@ -0,0 +1,1435 @@
+use std::{cell::RefCell, rc::Rc};
+
+use rustc_hash::{FxHashMap, FxHashSet};
+use webschembly_compiler_error::{Result, compiler_error};
+use webschembly_compiler_locate::{LocatedValue, Span};
+use webschembly_compiler_sexpr::{Cons, LSExpr, SExpr};
+
+// syntax-rulesによるマクロ展開
+// S式の段階で展開を行い、Parsedには特殊形式と通常の識別子のみが渡るようにする
+//
+// 健全性はリネームによって実現する
+// テンプレートから導入された識別子は展開ごとにエイリアス(`name#N`)に置き換えられ、
+// 展開結果の中で束縛されればそのまま別名の変数となり、自由であればマクロ定義時の環境で解決される
+// `#` はレキサが識別子として受け付けないので、ユーザーの識別子と衝突しない
+
+const SPECIAL_FORMS: &[&str] = &[
+    "quote",
+    "lambda",
+    "case-lambda",
+    "define",
+    "if",
+    "cond",
+    "let",
+    "let*",
+    "letrec",
+    "letrec*",
+    "do",
+    "delay",
+    "delay-force",
+    "begin",
+    "set!",
+    "and",
+    "or",
+    "define-syntax",
+    "let-syntax",
+    "letrec-syntax",
+    "syntax-rules",
+];
+
+// define-record-typeの展開に使う組み込みのマクロ
+// syntax-rulesでは一意なIDの生成やフィールド名の検索ができないので、ここで行う
+const RECORD_LAYOUT: &str = "%record-layout";
+
+#[derive(Debug, Clone)]
+enum Denotation {
+    // 出力時の変数名
+    Var(String),
+    Macro(Rc<SyntaxRules>),
+}
+
+#[derive(Debug, Default)]
+struct Scope {
+    parent: Env,
+    bindings: RefCell<FxHashMap<String, Denotation>>,
+}
+
+// Noneはトップレベル
+type Env = Option<Rc<Scope>>;
+
+fn new_scope(parent: &Env) -> Rc<Scope> {
+    Rc::new(Scope {
+        parent: parent.clone(),
+        bindings: RefCell::new(FxHashMap::default()),
+    })
+}
+
+#[derive(Debug)]
+struct Alias {
+    // エイリアスの元になった識別子(これ自体がエイリアスのこともある)
+    name: String,
+    // エイリアスを辿りきった元の名前
+    base: String,
+    // マクロ定義時の環境
+    env: Env,
+}
+
+#[derive(Debug)]
+struct SyntaxRules {
+    ellipsis: String,
+    literals: Vec<String>,
+    rules: Vec<(LSExpr, LSExpr)>,
+    env: Env,
+}
+
+#[derive(Debug, Clone)]
+enum Resolved {
+    Var(String),
+    Macro(Rc<SyntaxRules>),
+    Global(String),
+}
+
+impl Resolved {
+    fn is_same(&self, other: &Resolved) -> bool {
+        match (self, other) {
+            (Resolved::Var(a), Resolved::Var(b)) => a == b,
+            (Resolved::Macro(a), Resolved::Macro(b)) => Rc::ptr_eq(a, b),
+            (Resolved::Global(a), Resolved::Global(b)) => a == b,
+            _ => false,
+        }
+    }
+
+    fn special_form(&self) -> Option<&str> {
+        match self {
+            Resolved::Global(name) if SPECIAL_FORMS.contains(&name.as_str()) => Some(name),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum MatchBinding {
+    One(LSExpr),
+    Many(Vec<MatchBinding>),
+}
+
+type MatchBindings = FxHashMap<String, MatchBinding>;
+
+#[derive(Debug, Default)]
+pub struct MacroExpander {
+    // モジュールを跨いで保持される
+    global_macros: FxHashMap<String, Rc<SyntaxRules>>,
+    aliases: FxHashMap<String, Alias>,
+    alias_count: usize,
+    // マクロからグローバル変数として参照される名前
+    // ユーザーのローカル変数がこれらの名前を束縛する場合はリネームする
+    protected_names: FxHashSet<String>,
+    // 展開中にprotected_namesが増えたか
+    needs_retry: bool,
+    // 次に割り当てるレコード型のID。モジュールを跨いで一意にする
+    record_type_count: usize,
+}
+
+impl MacroExpander {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn expand(&mut self, sexprs: Vec<LSExpr>) -> Result<Vec<LSExpr>> {
+        let mut result = Vec::new();
+        for sexpr in sexprs {
+            loop {
+                let mut toplevel_result = Vec::new();
+                self.needs_retry = false;
+                self.expand_toplevel(sexpr.clone(), &mut toplevel_result)?;
+                // マクロが参照するグローバル変数がローカル変数に捕捉されていれば、
+                // そのローカル変数をリネームしてやり直す
+                if !self.needs_retry {
+                    result.extend(toplevel_result);
+                    break;
+                }
+            }
+        }
+        Ok(result)
+    }
+
+    fn expand_toplevel(&mut self, sexpr: LSExpr, result: &mut Vec<LSExpr>) -> Result<()> {
+        let sexpr = self.expand_head(sexpr, &None)?;
+        match self.special_form_of(&sexpr, &None).as_deref() {
+            Some("begin") => {
+                let (items, _) = split_list(sexpr);
+                for (item, _) in items.into_iter().skip(1) {
+                    self.expand_toplevel(item, result)?;
+                }
+            }
+            Some("define-syntax") => {
+                let (name, rules) = self.parse_define_syntax(sexpr, &None)?;
+                let name = self.base_name(&name).to_string();
+                self.global_macros.insert(name, rules);
+            }
+            Some("define") => {
+                result.push(self.expand_define(sexpr, &None)?);
+            }
+            _ => {
+                result.push(self.expand_expr(sexpr, &None)?);
+            }
+        }
+        Ok(())
+    }
+
+    fn resolve(&self, env: &Env, name: &str) -> Resolved {
+        let mut scope = env.clone();
+        while let Some(s) = scope {
+            if let Some(denotation) = s.bindings.borrow().get(name) {
+                return match denotation {
+                    Denotation::Var(var) => Resolved::Var(var.clone()),
+                    Denotation::Macro(rules) => Resolved::Macro(rules.clone()),
+                };
+            }
+            scope = s.parent.clone();
+        }
+
+        if let Some(alias) = self.aliases.get(name) {
+            self.resolve(&alias.env, &alias.name)
+        } else if let Some(rules) = self.global_macros.get(name) {
+            Resolved::Macro(rules.clone())
+        } else {
+            Resolved::Global(name.to_string())
+        }
+    }
+
+    fn base_name<'a>(&'a self, name: &'a str) -> &'a str {
+        match self.aliases.get(name) {
+            Some(alias) => &alias.base,
+            None => name,
+        }
+    }
+
+    fn new_alias(&mut self, name: &str, env: &Env) -> String {
+        let base = self.base_name(name).to_string();
+        let alias = format!("{}#{}", base, self.alias_count);
+        self.alias_count += 1;
+        self.aliases.insert(
+            alias.clone(),
+            Alias {
+                name: name.to_string(),
+                base,
+                env: env.clone(),
+            },
+        );
+        alias
+    }
+
+    // スコープに変数を束縛し、出力時の名前を返す
+    fn bind_var(&mut self, scope: &Rc<Scope>, name: &str) -> String {
+        let shadowing = matches!(self.resolve(&Some(scope.clone()), name), Resolved::Var(_));
+        let var = if self.aliases.contains_key(name) {
+            // エイリアスは一意なのでそのまま使う
+            name.to_string()
+        } else if shadowing || self.protected_names.contains(name) {
+            // マクロ定義時の環境にある外側の変数と区別するためにリネームする
+            self.new_alias(name, &None)
+        } else {
+            name.to_string()
+        };
+        scope
+            .bindings
+            .borrow_mut()
+            .insert(name.to_string(), Denotation::Var(var.clone()));
+        var
+    }
+
+    fn bind_var_sexpr(&mut self, scope: &Rc<Scope>, sexpr: LSExpr) -> Result<LSExpr> {
+        match sexpr.value {
+            SExpr::Symbol(name) => {
+                Ok(SExpr::Symbol(self.bind_var(scope, &name)).with_span(sexpr.span))
+            }
+            _ => Err(compiler_error!(Macro, sexpr.span, "Expected a symbol")),
+        }
+    }
+
+    fn special_form_of(&self, sexpr: &LSExpr, env: &Env) -> Option<String> {
+        match &sexpr.value {
+            SExpr::Cons(box Cons {
+                car:
+                    LSExpr {
+                        value: SExpr::Symbol(name),
+                        ..
+                    },
+                ..
+            }) => self
+                .resolve(env, name)
+                .special_form()
+                .map(|name| name.to_string()),
+            _ => None,
+        }
+    }
+
+    // 先頭がマクロ呼び出しでなくなるまで展開する
+    fn expand_head(&mut self, mut sexpr: LSExpr, env: &Env) -> Result<LSExpr> {
+        loop {
+            let rules = match &sexpr.value {
+                SExpr::Cons(box Cons {
+                    car:
+                        LSExpr {
+                            value: SExpr::Symbol(name),
+                            ..
+                        },
+                    ..
+                }) => match self.resolve(env, name) {
+                    Resolved::Macro(rules) => Some(rules),
+                    Resolved::Global(global) if global == RECORD_LAYOUT => None,
+                    _ => return Ok(sexpr),
+                },
+                _ => return Ok(sexpr),
+            };
+            sexpr = match rules {
+                Some(rules) => self.apply_macro(&rules, sexpr, env)?,
+                None => self.expand_record_layout(sexpr)?,
+            };
+        }
+    }
+
+    // (%record-layout k type (constructor field ...) pred (field accessor [modifier]) ...)
+    // => (k id size type (constructor (field index) ...) pred (index field accessor [modifier]) ...)
+    fn expand_record_layout(&mut self, sexpr: LSExpr) -> Result<LSExpr> {
+        let span = sexpr.span;
+        let invalid = || compiler_error!(Macro, span, "Invalid define-record-type expression");
+        let (mut items, tail) = split_list(sexpr);
+        if items.len() < 5 || !matches!(tail.value, SExpr::Nil) {
+            return Err(invalid());
+        }
+        let specs = items.split_off(5);
+        let field_names = specs
+            .iter()
+            .map(|(spec, _)| match &spec.value {
+                SExpr::Cons(box Cons {
+                    car:
+                        LSExpr {
+                            value: SExpr::Symbol(name),
+                            ..
+                        },
+                    ..
+                }) => Ok(name.clone()),
+                _ => Err(invalid()),
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let (constructor, constructor_span) = items.remove(3);
+        let (mut constructor_items, constructor_tail) = split_list(constructor);
+        if constructor_items.is_empty() {
+            return Err(invalid());
+        }
+        let constructor_name = constructor_items.remove(0);
+        let constructor_fields = constructor_items
+            .into_iter()
+            .map(|(field, field_span)| {
+                let index = match &field.value {
+                    SExpr::Symbol(name) => field_names.iter().position(|field| field == name),
+                    _ => None,
+                }
+                .ok_or_else(|| {
+                    compiler_error!(
+                        Macro,
+                        field.span,
+                        "define-record-type: unknown field in constructor"
+                    )
+                })?;
+                let index = SExpr::Int(index as i64).with_span(field.span);
+                Ok((vec_to_list(vec![field, index], field_span), field_span))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let constructor = join_list(
+            std::iter::once(constructor_name)
+                .chain(constructor_fields)
+                .collect(),
+            constructor_tail,
+        );
+        items.insert(3, (constructor, constructor_span));
+
+        let id = self.record_type_count;
+        self.record_type_count += 1;
+        items.remove(0);
+        items.insert(1, (SExpr::Int(id as i64).with_span(span), span));
+        items.insert(2, (SExpr::Int(specs.len() as i64).with_span(span), span));
+        items.extend(
+            specs
+                .into_iter()
+                .enumerate()
+                .map(|(index, (spec, spec_span))| {
+                    let index = SExpr::Int(index as i64).with_span(spec.span);
+                    (
+                        SExpr::Cons(Box::new(Cons::new(index, spec))).with_span(spec_span),
+                        spec_span,
+                    )
+                }),
+        );
+        Ok(join_list(items, tail))
+    }
+
+    fn expand_expr(&mut self, sexpr: LSExpr, env: &Env) -> Result<LSExpr> {
+        let sexpr = self.expand_head(sexpr, env)?;
+        match sexpr.value {
+            SExpr::Symbol(ref name) => match self.resolve(env, name) {
+                Resolved::Var(var) => Ok(SExpr::Symbol(var).with_span(sexpr.span)),
+                Resolved::Global(global) => {
+                    if self.aliases.contains_key(name)
+                        && matches!(self.resolve(env, &global), Resolved::Var(var) if var == global)
+                    {
+                        self.protected_names.insert(global.clone());
+                        self.needs_retry = true;
+                    }
+                    Ok(SExpr::Symbol(global).with_span(sexpr.span))
+                }
+                Resolved::Macro(_) => Err(compiler_error!(
+                    Macro,
+                    sexpr.span,
+                    "Syntax keyword used as a variable: {}",
+                    self.base_name(name)
+                )),
+            },
+            SExpr::Vector(_) | SExpr::UVector(_, _) => Ok(self.strip(sexpr)),
+            SExpr::Cons(_) => match self.special_form_of(&sexpr, env).as_deref() {
+                Some(name) => {
+                    let name = name.to_string();
+                    self.expand_special_form(&name, sexpr, env)
+                }
+                None => {
+                    let (items, tail) = split_list(sexpr);
+                    let items = items
+                        .into_iter()
+                        .map(|(item, span)| Ok((self.expand_expr(item, env)?, span)))
+                        .collect::<Result<Vec<_>>>()?;
+                    Ok(join_list(items, tail))
+                }
+            },
+            _ => Ok(sexpr),
+        }
+    }
+
+    fn expand_exprs(
+        &mut self,
+        items: impl IntoIterator<Item = (LSExpr, Span)>,
+        env: &Env,
+    ) -> Result<Vec<(LSExpr, Span)>> {
+        items
+            .into_iter()
+            .map(|(item, span)| Ok((self.expand_expr(item, env)?, span)))
+            .collect()
+    }
+
+    fn expand_special_form(&mut self, name: &str, sexpr: LSExpr, env: &Env) -> Result<LSExpr> {
+        let form_span = sexpr.span;
+        let (mut items, tail) = split_list(sexpr);
+        if !matches!(tail.value, SExpr::Nil) {
+            return Err(compiler_error!(
+                Macro,
+                form_span,
+                "Invalid {} expression",
+                name
+            ));
+        }
+        let (keyword, keyword_span) = items.remove(0);
+        let keyword = (
+            SExpr::Symbol(name.to_string()).with_span(keyword.span),
+            keyword_span,
+        );
+
+        match name {
+            "quote" => {
+                let items = items
+                    .into_iter()
+                    .map(|(item, span)| (self.strip(item), span))
+                    .collect::<Vec<_>>();
+                Ok(join_list(
+                    std::iter::once(keyword).chain(items).collect(),
+                    tail,
+                ))
+            }
+            "lambda" => {
+                if items.is_empty() {
+                    return Err(compiler_error!(
+                        Macro,
+                        form_span,
+                        "Invalid lambda expression"
+                    ));
+                }
+                let scope = new_scope(env);
+                let (formals, formals_span) = items.remove(0);
+                let formals = self.bind_formals(&scope, formals)?;
+                let body = self.expand_body(items, &Some(scope))?;
+                Ok(join_list(
+                    [keyword, (formals, formals_span)]
+                        .into_iter()
+                        .chain(body)
+                        .collect(),
+                    tail,
+                ))
+            }
+            "case-lambda" => {
+                let clauses = items
+                    .into_iter()
+                    .map(|(clause, clause_span)| {
+                        let (mut clause_items, clause_tail) = split_list(clause);
+                        if clause_items.is_empty() {
+                            return Err(compiler_error!(
+                                Macro,
+                                form_span,
+                                "Invalid case-lambda clause"
+                            ));
+                        }
+                        let scope = new_scope(env);
+                        let (formals, formals_span) = clause_items.remove(0);
+                        let formals = self.bind_formals(&scope, formals)?;
+                        let body = self.expand_body(clause_items, &Some(scope))?;
+                        Ok((
+                            join_list(
+                                std::iter::once((formals, formals_span))
+                                    .chain(body)
+                                    .collect(),
+                                clause_tail,
+                            ),
+                            clause_span,
+                        ))
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                Ok(join_list(
+                    std::iter::once(keyword).chain(clauses).collect(),
+                    tail,
+                ))
+            }
+            "define" => self.expand_define(
+                join_list(std::iter::once(keyword).chain(items).collect(), tail),
+                env,
+            ),
+            "if" | "begin" | "and" | "or" | "set!" | "delay" | "delay-force" => {
+                let items = self.expand_exprs(items, env)?;
+                Ok(join_list(
+                    std::iter::once(keyword).chain(items).collect(),
+                    tail,
+                ))
+            }
+            "cond" => {
+                let clauses = items
+                    .into_iter()
+                    .map(|(clause, span)| Ok((self.expand_cond_clause(clause, env)?, span)))
+                    .collect::<Result<Vec<_>>>()?;
+                Ok(join_list(
+                    std::iter::once(keyword).chain(clauses).collect(),
+                    tail,
+                ))
+            }
+            "let" | "let*" | "letrec" | "letrec*" => {
+                let mut head = vec![keyword];
+                let mut scope = new_scope(env);
+                if name == "let"
+                    && let Some((
+                        LSExpr {
+                            value: SExpr::Symbol(_),
+                            ..
+                        },
+                        _,
+                    )) = items.first()
+                {
+                    // named let
+                    let (loop_name, loop_name_span) = items.remove(0);
+                    head.push((self.bind_var_sexpr(&scope, loop_name)?, loop_name_span));
+                    scope = new_scope(&Some(scope));
+                }
+                if items.is_empty() {
+                    return Err(compiler_error!(
+                        Macro,
+                        form_span,
+                        "Invalid {} expression",
+                        name
+                    ));
+                }
+                let (bindings, bindings_span) = items.remove(0);
+                let (bindings, bindings_tail) = split_bindings(bindings)?;
+                let bindings = match name {
+                    "let" => bindings
+                        .into_iter()
+                        .map(|mut binding| {
+                            binding.init.0 = self.expand_expr(binding.init.0, env)?;
+                            binding.name.0 = self.bind_var_sexpr(&scope, binding.name.0)?;
+                            Ok(binding.join())
+                        })
+                        .collect::<Result<Vec<_>>>()?,
+                    "let*" => bindings
+                        .into_iter()
+                        .map(|mut binding| {
+                            binding.init.0 =
+                                self.expand_expr(binding.init.0, &Some(scope.clone()))?;
+                            scope = new_scope(&Some(scope.clone()));
+                            binding.name.0 = self.bind_var_sexpr(&scope, binding.name.0)?;
+                            Ok(binding.join())
+                        })
+                        .collect::<Result<Vec<_>>>()?,
+                    _ => {
+                        let mut bindings = bindings
+                            .into_iter()
+                            .map(|mut binding| {
+                                binding.name.0 = self.bind_var_sexpr(&scope, binding.name.0)?;
+                                Ok(binding)
+                            })
+                            .collect::<Result<Vec<_>>>()?;
+                        let letrec_env = Some(scope.clone());
+                        for binding in bindings.iter_mut() {
+                            let init = std::mem::replace(
+                                &mut binding.init.0,
+                                SExpr::Nil.with_span(binding.span),
+                            );
+                            binding.init.0 = self.expand_expr(init, &letrec_env)?;
+                        }
+                        bindings.into_iter().map(BindingParts::join).collect()
+                    }
+                };
+                head.push((join_list(bindings, bindings_tail), bindings_span));
+                let body = self.expand_body(items, &Some(scope))?;
+                Ok(join_list(head.into_iter().chain(body).collect(), tail))
+            }
+            "do" => {
+                if items.len() < 2 {
+                    return Err(compiler_error!(Macro, form_span, "Invalid do expression"));
+                }
+                let (bindings, bindings_span) = items.remove(0);
+                let (test, test_span) = items.remove(0);
+                let (bindings, bindings_tail) = split_list(bindings);
+                // 各束縛は (var init step) または (var init) の形
+                let scope = new_scope(env);
+                let bindings = bindings
+                    .into_iter()
+                    .map(|(binding, binding_span)| {
+                        let (parts, binding_tail) = split_list(binding);
+                        let mut parts = parts.into_iter();
+                        let (Some((name, name_span)), Some((init, init_span))) =
+                            (parts.next(), parts.next())
+                        else {
+                            return Err(compiler_error!(Macro, form_span, "Invalid binding"));
+                        };
+                        let init = self.expand_expr(init, env)?;
+                        let name = self.bind_var_sexpr(&scope, name)?;
+                        Ok((
+                            vec![(name, name_span), (init, init_span)],
+                            parts.collect::<Vec<_>>(),
+                            binding_tail,
+                            binding_span,
+                        ))
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                let scope = Some(scope);
+                let bindings = bindings
+                    .into_iter()
+                    .map(|(mut parts, steps, binding_tail, binding_span)| {
+                        parts.extend(self.expand_exprs(steps, &scope)?);
+                        Ok((join_list(parts, binding_tail), binding_span))
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                let (test_items, test_tail) = split_list(test);
+                let test_items = self.expand_exprs(test_items, &scope)?;
+                let body = self.expand_exprs(items, &scope)?;
+                Ok(join_list(
+                    [
+                        keyword,
+                        (join_list(bindings, bindings_tail), bindings_span),
+                        (join_list(test_items, test_tail), test_span),
+                    ]
+                    .into_iter()
+                    .chain(body)
+                    .collect(),
+                    tail,
+                ))
+            }
+            "let-syntax" | "letrec-syntax" => {
+                if items.is_empty() {
+                    return Err(compiler_error!(
+                        Macro,
+                        form_span,
+                        "Invalid {} expression",
+                        name
+                    ));
+                }
+                let (bindings, bindings_span) = items.remove(0);
+                let scope = new_scope(env);
+                let macro_env = if name == "letrec-syntax" {
+                    Some(scope.clone())
+                } else {
+                    env.clone()
+                };
+                let (bindings, _) = split_list(bindings);
+                for (binding, _) in bindings {
+                    let (parts, _) = split_list(binding);
+                    match <[_; 2]>::try_from(parts) {
+                        Ok(
+                            [
+                                (
+                                    LSExpr {
+                                        value: SExpr::Symbol(keyword_name),
+                                        ..
+                                    },
+                                    _,
+                                ),
+                                (spec, _),
+                            ],
+                        ) => {
+                            let rules = self.parse_syntax_rules(spec, &macro_env)?;
+                            scope
+                                .bindings
+                                .borrow_mut()
+                                .insert(keyword_name, Denotation::Macro(rules));
+                        }
+                        _ => {
+                            return Err(compiler_error!(
+                                Macro,
+                                form_span,
+                                "Invalid {} binding",
+                                name
+                            ));
+                        }
+                    }
+                }
+                let body = self.expand_body(items, &Some(scope))?;
+                // (let () body ...) に変換する
+                Ok(join_list(
+                    [
+                        (
+                            SExpr::Symbol("let".to_string()).with_span(keyword.0.span),
+                            keyword.1,
+                        ),
+                        (nil_like(bindings_span), bindings_span),
+                    ]
+                    .into_iter()
+                    .chain(body)
+                    .collect(),
+                    tail,
+                ))
+            }
+            "define-syntax" => Err(compiler_error!(
+                Macro,
+                form_span,
+                "define-syntax is not allowed in this context"
+            )),
+            "syntax-rules" => Err(compiler_error!(
+                Macro,
+                form_span,
+                "syntax-rules is not allowed in this context"
+            )),
+            _ => unreachable!("unknown special form: {}", name),
+        }
+    }
+
+    fn expand_cond_clause(&mut self, clause: LSExpr, env: &Env) -> Result<LSExpr> {
+        let (items, tail) = split_list(clause);
+        let items = items
+            .into_iter()
+            .enumerate()
+            .map(|(i, (item, span))| match &item.value {
+                SExpr::Symbol(name)
+                    if (i == 0 && self.is_global(env, name, "else"))
+                        || (i == 1 && self.is_global(env, name, "=>")) =>
+                {
+                    Ok((
+                        SExpr::Symbol(self.base_name(name).to_string()).with_span(item.span),
+                        span,
+                    ))
+                }
+                _ => Ok((self.expand_expr(item, env)?, span)),
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(join_list(items, tail))
+    }
+
+    fn is_global(&self, env: &Env, name: &str, expected: &str) -> bool {
+        matches!(self.resolve(env, name), Resolved::Global(global) if global == expected)
+    }
+
+    fn bind_formals(&mut self, scope: &Rc<Scope>, formals: LSExpr) -> Result<LSExpr> {
+        let (items, tail) = split_list(formals);
+        let items = items
+            .into_iter()
+            .map(|(item, span)| Ok((self.bind_var_sexpr(scope, item)?, span)))
+            .collect::<Result<Vec<_>>>()?;
+        let tail = match tail.value {
+            SExpr::Nil => tail,
+            _ => self.bind_var_sexpr(scope, tail)?,
+        };
+        Ok(join_list(items, tail))
+    }
+
+    fn expand_define(&mut self, sexpr: LSExpr, env: &Env) -> Result<LSExpr> {
+        let form_span = sexpr.span;
+        let (mut items, tail) = split_list(sexpr);
+        if items.len() < 2 || !matches!(tail.value, SExpr::Nil) {
+            return Err(compiler_error!(
+                Macro,
+                form_span,
+                "Invalid define expression"
+            ));
+        }
+        let (keyword, keyword_span) = items.remove(0);
+        let keyword = (
+            SExpr::Symbol("define".to_string()).with_span(keyword.span),
+            keyword_span,
+        );
+        let (target, target_span) = items.remove(0);
+        match target.value {
+            SExpr::Symbol(name) => {
+                let name = self.define_name(&name, env);
+                let body = self.expand_exprs(items, env)?;
+                Ok(join_list(
+                    [
+                        keyword,
+                        (SExpr::Symbol(name).with_span(target.span), target_span),
+                    ]
+                    .into_iter()
+                    .chain(body)
+                    .collect(),
+                    tail,
+                ))
+            }
+            SExpr::Cons(box Cons {
+                car:
+                    LSExpr {
+                        value: SExpr::Symbol(name),
+                        span: name_span,
+                    },
+                cdr: formals,
+            }) => {
+                let name = self.define_name(&name, env);
+                let scope = new_scope(env);
+                let formals = self.bind_formals(&scope, formals)?;
+                let body = self.expand_body(items, &Some(scope))?;
+                let target = SExpr::Cons(Box::new(Cons::new(
+                    SExpr::Symbol(name).with_span(name_span),
+                    formals,
+                )))
+                .with_span(target.span);
+                Ok(join_list(
+                    [keyword, (target, target_span)]
+                        .into_iter()
+                        .chain(body)
+                        .collect(),
+                    tail,
+                ))
+            }
+            _ => Err(compiler_error!(
+                Macro,
+                form_span,
+                "Invalid define expression"
+            )),
+        }
+    }
+
+    fn define_name(&mut self, name: &str, env: &Env) -> String {
+        if env.is_none() {
+            // トップレベルの定義はエイリアスでも元の名前で定義する
+            let name = self.base_name(name).to_string();
+            self.global_macros.remove(&name);
+            name
+        } else {
+            match self.resolve(env, name) {
+                Resolved::Var(var) => var,
+                _ => self.base_name(name).to_string(),
+            }
+        }
+    }
+
+    // lambdaなどの本体
+    // 内部定義を先に束縛してから各式を展開する
+    fn expand_body(
+        &mut self,
+        items: Vec<(LSExpr, Span)>,
+        env: &Env,
+    ) -> Result<Vec<(LSExpr, Span)>> {
+        let scope = env.clone().expect("body must have a scope");
+        let mut queue = items.into_iter().rev().collect::<Vec<_>>();
+        let mut forms = Vec::new();
+        while let Some((item, span)) = queue.pop() {
+            let item = self.expand_head(item, env)?;
+            match self.special_form_of(&item, env).as_deref() {
+                Some("begin") => {
+                    let (items, _) = split_list(item);
+                    queue.extend(items.into_iter().skip(1).rev());
+                }
+                Some("define-syntax") => {
+                    let (name, rules) = self.parse_define_syntax(item, env)?;
+                    scope
+                        .bindings
+                        .borrow_mut()
+                        .insert(name, Denotation::Macro(rules));
+                }
+                Some("define") => {
+                    match &item.value {
+                        SExpr::Cons(box Cons {
+                            cdr:
+                                LSExpr {
+                                    value:
+                                        SExpr::Cons(box Cons {
+                                            car:
+                                                LSExpr {
+                                                    value: SExpr::Symbol(name),
+                                                    ..
+                                                }
+                                                | LSExpr {
+                                                    value:
+                                                        SExpr::Cons(box Cons {
+                                                            car:
+                                                                LSExpr {
+                                                                    value: SExpr::Symbol(name),
+                                                                    ..
+                                                                },
+                                                            ..
+                                                        }),
+                                                    ..
+                                                },
+                                            ..
+                                        }),
+                                    ..
+                                },
+                            ..
+                        }) => {
+                            let name = name.clone();
+                            self.bind_var(&scope, &name);
+                        }
+                        _ => {
+                            return Err(compiler_error!(
+                                Macro,
+                                item.span,
+                                "Invalid define expression"
+                            ));
+                        }
+                    }
+                    forms.push((item, span));
+                }
+                _ => forms.push((item, span)),
+            }
+        }
+
+        forms
+            .into_iter()
+            .map(|(item, span)| {
+                if self.special_form_of(&item, env).as_deref() == Some("define") {
+                    Ok((self.expand_define(item, env)?, span))
+                } else {
+                    Ok((self.expand_expr(item, env)?, span))
+                }
+            })
+            .collect()
+    }
+
+    fn parse_define_syntax(
+        &mut self,
+        sexpr: LSExpr,
+        env: &Env,
+    ) -> Result<(String, Rc<SyntaxRules>)> {
+        let form_span = sexpr.span;
+        let (items, tail) = split_list(sexpr);
+        if !matches!(tail.value, SExpr::Nil) {
+            return Err(compiler_error!(
+                Macro,
+                form_span,
+                "Invalid define-syntax expression"
+            ));
+        }
+        match <[_; 3]>::try_from(items) {
+            Ok(
+                [
+                    _,
+                    (
+                        LSExpr {
+                            value: SExpr::Symbol(name),
+                            ..
+                        },
+                        _,
+                    ),
+                    (spec, _),
+                ],
+            ) => {
+                let rules = self.parse_syntax_rules(spec, env)?;
+                Ok((name, rules))
+            }
+            _ => Err(compiler_error!(
+                Macro,
+                form_span,
+                "Invalid define-syntax expression"
+            )),
+        }
+    }
+
+    fn parse_syntax_rules(&mut self, spec: LSExpr, env: &Env) -> Result<Rc<SyntaxRules>> {
+        let form_span = spec.span;
+        if self.special_form_of(&spec, env).as_deref() != Some("syntax-rules") {
+            return Err(compiler_error!(
+                Macro,
+                form_span,
+                "Expected a syntax-rules expression"
+            ));
+        }
+        let (mut items, tail) = split_list(spec);
+        if !matches!(tail.value, SExpr::Nil) || items.len() < 2 {
+            return Err(compiler_error!(
+                Macro,
+                form_span,
+                "Invalid syntax-rules expression"
+            ));
+        }
+        items.remove(0);
+        // (syntax-rules ellipsis (literal ...) rule ...) 形式にも対応する
+        let ellipsis = match &items[0].0.value {
+            SExpr::Symbol(ellipsis) => {
+                let ellipsis = ellipsis.clone();
+                items.remove(0);
+                ellipsis
+            }
+            _ => "...".to_string(),
+        };
+        if items.is_empty() {
+            return Err(compiler_error!(
+                Macro,
+                form_span,
+                "Invalid syntax-rules expression"
+            ));
+        }
+        let (literals, _) = items.remove(0);
+        let literals = literals
+            .value
+            .to_vec()
+            .ok_or_else(|| compiler_error!(Macro, form_span, "Expected a list of literals"))?
+            .into_iter()
+            .map(|literal| match literal.value {
+                SExpr::Symbol(s) => Ok(s),
+                _ => Err(compiler_error!(Macro, form_span, "Expected a symbol")),
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let rules = items
+            .into_iter()
+            .map(|(rule, _)| {
+                let (parts, _) = split_list(rule);
+                match <[_; 2]>::try_from(parts) {
+                    Ok([(pattern, _), (template, _)]) => Ok((pattern, template)),
+                    Err(_) => Err(compiler_error!(Macro, form_span, "Invalid syntax rule")),
+                }
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Rc::new(SyntaxRules {
+            ellipsis,
+            literals,
+            rules,
+            env: env.clone(),
+        }))
+    }
+
+    fn apply_macro(&mut self, rules: &SyntaxRules, sexpr: LSExpr, env: &Env) -> Result<LSExpr> {
+        let (name, form_args) = match &sexpr.value {
+            SExpr::Cons(box Cons {
+                car:
+                    LSExpr {
+                        value: SExpr::Symbol(name),
+                        ..
+                    },
+                cdr,
+            }) => (name.clone(), cdr),
+            _ => unreachable!(),
+        };
+        for (pattern, template) in rules.rules.iter() {
+            // パターンの先頭(マクロ名)は無視する
+            let pattern_args = match &pattern.value {
+                SExpr::Cons(box Cons { cdr, .. }) => cdr,
+                _ => {
+                    return Err(compiler_error!(
+                        Macro,
+                        pattern.span,
+                        "Invalid syntax rule pattern"
+                    ));
+                }
+            };
+            let mut binds = MatchBindings::default();
+            if self.match_pattern(rules, pattern_args, form_args, env, &mut binds) {
+                let mut renames = FxHashMap::default();
+                return self.expand_template(rules, template, &binds, &mut renames, true);
+            }
+        }
+        Err(compiler_error!(
+            Macro,
+            sexpr.span,
+            "No matching syntax rule for {}",
+            self.base_name(&name)
+        ))
+    }
+
+    fn is_ellipsis(rules: &SyntaxRules, sexpr: &LSExpr) -> bool {
+        matches!(&sexpr.value, SExpr::Symbol(s) if *s == rules.ellipsis)
+    }
+
+    fn match_pattern(
+        &self,
+        rules: &SyntaxRules,
+        pattern: &LSExpr,
+        form: &LSExpr,
+        env: &Env,
+        binds: &mut MatchBindings,
+    ) -> bool {
+        match &pattern.value {
+            SExpr::Symbol(s) if s == "_" => true,
+            SExpr::Symbol(s) if rules.literals.contains(s) => match &form.value {
+                SExpr::Symbol(f) => self.resolve(&rules.env, s).is_same(&self.resolve(env, f)),
+                _ => false,
+            },
+            SExpr::Symbol(s) => {
+                binds.insert(s.clone(), MatchBinding::One(form.clone()));
+                true
+            }
+            SExpr::Cons(box Cons {
+                car,
+                cdr:
+                    LSExpr {
+                        value:
+                            SExpr::Cons(box Cons {
+                                car: ellipsis,
+                                cdr: rest,
+                            }),
+                        ..
+                    },
+            }) if Self::is_ellipsis(rules, ellipsis) => {
+                let min_rest_len = proper_len(rest);
+                let mut form_items = Vec::new();
+                let mut cursor = form;
+                while let SExpr::Cons(box Cons { car, cdr }) = &cursor.value {
+                    form_items.push(car);
+                    cursor = cdr;
+                }
+                let Some(repeat) = form_items.len().checked_sub(min_rest_len) else {
+                    return false;
+                };
+
+                let mut matches = Vec::new();
+                let mut cursor = form;
+                for _ in 0..repeat {
+                    let SExpr::Cons(box Cons {
+                        car: item,
+                        cdr: next,
+                    }) = &cursor.value
+                    else {
+                        unreachable!()
+                    };
+                    let mut item_binds = MatchBindings::default();
+                    if !self.match_pattern(rules, car, item, env, &mut item_binds) {
+                        return false;
+                    }
+                    matches.push(item_binds);
+                    cursor = next;
+                }
+                for var in Self::pattern_vars(rules, car) {
+                    let items = matches
+                        .iter_mut()
+                        .map(|m| m.remove(&var).unwrap())
+                        .collect();
+                    binds.insert(var, MatchBinding::Many(items));
+                }
+                self.match_pattern(rules, rest, cursor, env, binds)
+            }
+            SExpr::Cons(box Cons { car, cdr }) => match &form.value {
+                SExpr::Cons(box Cons {
+                    car: form_car,
+                    cdr: form_cdr,
+                }) => {
+                    self.match_pattern(rules, car, form_car, env, binds)
+                        && self.match_pattern(rules, cdr, form_cdr, env, binds)
+                }
+                _ => false,
+            },
+            SExpr::Vector(patterns) => match &form.value {
+                SExpr::Vector(items) => self.match_pattern(
+                    rules,
+                    &vec_to_list(patterns.clone(), pattern.span),
+                    &vec_to_list(items.clone(), form.span),
+                    env,
+                    binds,
+                ),
+                _ => false,
+            },
+            SExpr::Nil => matches!(form.value, SExpr::Nil),
+            _ => datum_eq(pattern, form),
+        }
+    }
+
+    fn pattern_vars(rules: &SyntaxRules, pattern: &LSExpr) -> Vec<String> {
+        let mut vars = Vec::new();
+        Self::collect_pattern_vars(rules, pattern, &mut vars);
+        vars
+    }
+
+    fn collect_pattern_vars(rules: &SyntaxRules, pattern: &LSExpr, vars: &mut Vec<String>) {
+        match &pattern.value {
+            SExpr::Symbol(s) if s == "_" || *s == rules.ellipsis => {}
+            SExpr::Symbol(s) if rules.literals.contains(s) => {}
+            SExpr::Symbol(s) => vars.push(s.clone()),
+            SExpr::Cons(box Cons { car, cdr }) => {
+                Self::collect_pattern_vars(rules, car, vars);
+                Self::collect_pattern_vars(rules, cdr, vars);
+            }
+            SExpr::Vector(items) => {
+                for item in items {
+                    Self::collect_pattern_vars(rules, item, vars);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn expand_template(
+        &mut self,
+        rules: &SyntaxRules,
+        template: &LSExpr,
+        binds: &MatchBindings,
+        renames: &mut FxHashMap<String, String>,
+        use_ellipsis: bool,
+    ) -> Result<LSExpr> {
+        match &template.value {
+            SExpr::Symbol(s) => match binds.get(s) {
+                Some(MatchBinding::One(sexpr)) => Ok(sexpr.clone()),
+                Some(MatchBinding::Many(_)) => Err(compiler_error!(
+                    Macro,
+                    template.span,
+                    "Pattern variable used without ellipsis: {}",
+                    s
+                )),
+                None => {
+                    let alias = match renames.get(s) {
+                        Some(alias) => alias.clone(),
+                        None => {
+                            let alias = self.new_alias(s, &rules.env);
+                            renames.insert(s.clone(), alias.clone());
+                            alias
+                        }
+                    };
+                    Ok(SExpr::Symbol(alias).with_span(template.span))
+                }
+            },
+            // (... template) はエスケープ
+            SExpr::Cons(box Cons {
+                car: ellipsis,
+                cdr:
+                    LSExpr {
+                        value:
+                            SExpr::Cons(box Cons {
+                                car: escaped,
+                                cdr:
+                                    LSExpr {
+                                        value: SExpr::Nil, ..
+                                    },
+                            }),
+                        ..
+                    },
+            }) if use_ellipsis && Self::is_ellipsis(rules, ellipsis) => {
+                self.expand_template(rules, escaped, binds, renames, false)
+            }
+            SExpr::Cons(box Cons { car, cdr }) => {
+                let mut depth = 0;
+                let mut rest = cdr;
+                while use_ellipsis
+                    && let SExpr::Cons(box Cons {
+                        car: ellipsis,
+                        cdr: next,
+                    }) = &rest.value
+                    && Self::is_ellipsis(rules, ellipsis)
+                {
+                    depth += 1;
+                    rest = next;
+                }
+                let rest = self.expand_template(rules, rest, binds, renames, use_ellipsis)?;
+                if depth == 0 {
+                    let car = self.expand_template(rules, car, binds, renames, use_ellipsis)?;
+                    Ok(SExpr::Cons(Box::new(Cons::new(car, rest))).with_span(template.span))
+                } else {
+                    let items = self.expand_ellipsis(rules, car, binds, renames, depth)?;
+                    Ok(items.into_iter().rev().fold(rest, |cdr, car| {
+                        SExpr::Cons(Box::new(Cons::new(car, cdr))).with_span(template.span)
+                    }))
+                }
+            }
+            SExpr::Vector(items) => {
+                let list = self.expand_template(
+                    rules,
+                    &vec_to_list(items.clone(), template.span),
+                    binds,
+                    renames,
+                    use_ellipsis,
+                )?;
+                let items = list.value.to_vec().ok_or_else(|| {
+                    compiler_error!(Macro, template.span, "Invalid vector template")
+                })?;
+                Ok(SExpr::Vector(items).with_span(template.span))
+            }
+            _ => Ok(template.clone()),
+        }
+    }
+
+    fn expand_ellipsis(
+        &mut self,
+        rules: &SyntaxRules,
+        template: &LSExpr,
+        binds: &MatchBindings,
+        renames: &mut FxHashMap<String, String>,
+        depth: usize,
+    ) -> Result<Vec<LSExpr>> {
+        if depth == 0 {
+            return Ok(vec![
+                self.expand_template(rules, template, binds, renames, true)?,
+            ]);
+        }
+
+        let mut vars = Vec::new();
+        Self::collect_pattern_vars(rules, template, &mut vars);
+        let many_vars = vars
+            .into_iter()
+            .filter_map(|var| match binds.get(&var) {
+                Some(MatchBinding::Many(items)) => Some((var, items)),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        let Some(len) = many_vars.first().map(|(_, items)| items.len()) else {
+            return Err(compiler_error!(
+                Macro,
+                template.span,
+                "No pattern variables before ellipsis"
+            ));
+        };
+        if many_vars.iter().any(|(_, items)| items.len() != len) {
+            return Err(compiler_error!(
+                Macro,
+                template.span,
+                "Pattern variables with different lengths used in same ellipsis"
+            ));
+        }
+
+        let mut result = Vec::new();
+        for i in 0..len {
+            let mut item_binds = binds.clone();
+            for (var, items) in many_vars.iter() {
+                item_binds.insert(var.clone(), items[i].clone());
+            }
+            result.extend(self.expand_ellipsis(
+                rules,
+                template,
+                &item_binds,
+                renames,
+                depth - 1,
+            )?);
+        }
+        Ok(result)
+    }
+
+    // エイリアスを元の名前に戻す(quoteされたデータ用)
+    fn strip(&self, sexpr: LSExpr) -> LSExpr {
+        let span = sexpr.span;
+        match sexpr.value {
+            SExpr::Symbol(s) => SExpr::Symbol(self.base_name(&s).to_string()).with_span(span),
+            SExpr::Cons(box Cons { car, cdr }) => {
+                SExpr::Cons(Box::new(Cons::new(self.strip(car), self.strip(cdr)))).with_span(span)
+            }
+            SExpr::Vector(items) => {
+                SExpr::Vector(items.into_iter().map(|item| self.strip(item)).collect())
+                    .with_span(span)
+            }
+            SExpr::UVector(kind, items) => SExpr::UVector(
+                kind,
+                items.into_iter().map(|item| self.strip(item)).collect(),
+            )
+            .with_span(span),
+            value => value.with_span(span),
+        }
+    }
+}
+
+// (name init) の形の束縛
+struct BindingParts {
+    name: (LSExpr, Span),
+    init: (LSExpr, Span),
+    span: Span,
+    tail: LSExpr,
+}
+
+impl BindingParts {
+    fn join(self) -> (LSExpr, Span) {
+        (join_list(vec![self.name, self.init], self.tail), self.span)
+    }
+}
+
+fn split_bindings(bindings: LSExpr) -> Result<(Vec<BindingParts>, LSExpr)> {
+    let (items, tail) = split_list(bindings);
+    let bindings = items
+        .into_iter()
+        .map(|(binding, span)| {
+            let (parts, tail) = split_list(binding);
+            match <[_; 2]>::try_from(parts) {
+                Ok([name, init]) => Ok(BindingParts {
+                    name,
+                    init,
+                    span,
+                    tail,
+                }),
+                Err(_) => Err(compiler_error!(Macro, span, "Invalid binding")),
+            }
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok((bindings, tail))
+}
+
+fn nil_like(span: Span) -> LSExpr {
+    SExpr::Nil.with_span(span)
+}
+
+// リストを要素(とそのコンスセルのspan)と終端に分解する
+fn split_list(sexpr: LSExpr) -> (Vec<(LSExpr, Span)>, LSExpr) {
+    let mut items = Vec::new();
+    let mut cursor = sexpr;
+    loop {
+        match cursor.value {
+            SExpr::Cons(box Cons { car, cdr }) => {
+                items.push((car, cursor.span));
+                cursor = cdr;
+            }
+            value => {
+                return (items, value.with_span(cursor.span));
+            }
+        }
+    }
+}
+
+fn join_list(items: Vec<(LSExpr, Span)>, tail: LSExpr) -> LSExpr {
+    items.into_iter().rev().fold(tail, |cdr, (car, span)| {
+        SExpr::Cons(Box::new(Cons::new(car, cdr))).with_span(span)
+    })
+}
+
+fn vec_to_list(items: Vec<LSExpr>, span: Span) -> LSExpr {
+    join_list(
+        items.into_iter().map(|item| (item, span)).collect(),
+        nil_like(span),
+    )
+}
+
+fn proper_len(sexpr: &LSExpr) -> usize {
+    let mut len = 0;
+    let mut cursor = sexpr;
+    while let SExpr::Cons(box Cons { cdr, .. }) = &cursor.value {
+        len += 1;
+        cursor = cdr;
+    }
+    len
+}
+
+fn datum_eq(a: &LSExpr, b: &LSExpr) -> bool {
+    match (&a.value, &b.value) {
+        (SExpr::Bool(a), SExpr::Bool(b)) => a == b,
+        (SExpr::Int(a), SExpr::Int(b)) => a == b,
+        (SExpr::BigInt(a), SExpr::BigInt(b)) => a == b,
+        (SExpr::Rational(a1, a2), SExpr::Rational(b1, b2)) => a1 == b1 && a2 == b2,
+        (SExpr::Float(a), SExpr::Float(b)) => a == b,
+        (SExpr::String(a), SExpr::String(b)) => a == b,
+        (SExpr::Char(a), SExpr::Char(b)) => a == b,
+        (SExpr::Nil, SExpr::Nil) => true,
+        _ => false,
+    }
+}
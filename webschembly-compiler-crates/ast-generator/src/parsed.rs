@@ -11,6 +11,7 @@ impl AstPhase for Parsed {
     type XConst = ();
     type XDefine = ();
     type XLambda = ();
+    type XCaseLambda = ();
     type XIf = ();
     type XCond = ();
     type XCall = ();
@@ -21,7 +22,9 @@ impl AstPhase for Parsed {
     type XNamedLet = ();
     type XLetStar = ();
     type XLetRec = ();
+    type XLetRecStar = ();
     type XDo = ();
+    type XDelay = ();
     type XVector = ();
     type XUVector = ();
     type XQuote = ();
@@ -52,6 +55,16 @@ impl Parsed {
                 span,
                 ..
             } => Ok(Expr::Const((), Const::Int(i)).with_span(span)),
+            LSExpr {
+                value: SExpr::BigInt(i),
+                span,
+                ..
+            } => Ok(Expr::Const((), Const::BigInt(i)).with_span(span)),
+            LSExpr {
+                value: SExpr::Rational(n, d),
+                span,
+                ..
+            } => Ok(Expr::Const((), Const::Rational(n, d)).with_span(span)),
             LSExpr {
                 value: SExpr::Float(f),
                 span,
@@ -106,7 +119,11 @@ impl Parsed {
                 ..cdr
             ] => match cdr {
                 list_pattern![sexpr,] => Ok(Expr::Quote((), sexpr).with_span(span)),
-                _ => Err(compiler_error!("Invalid quote expression")),
+                _ => Err(compiler_error!(
+                    InvalidSyntax,
+                    span,
+                    "Invalid quote expression"
+                )),
             },
             list_pattern![
                 LSExpr {
@@ -146,7 +163,11 @@ impl Parsed {
                     },
                 )
                 .with_span(span)),
-                _ => Err(compiler_error!("Invalid define expression")),
+                _ => Err(compiler_error!(
+                    InvalidSyntax,
+                    span,
+                    "Invalid define expression"
+                )),
             },
             list_pattern![
                 LSExpr {
@@ -156,8 +177,39 @@ impl Parsed {
                 ..cdr
             ] => match cdr {
                 list_pattern![args, ..exprs] => Self::parse_lambda(span, args, exprs),
-                _ => Err(compiler_error!("Invalid lambda expression")),
+                _ => Err(compiler_error!(
+                    InvalidSyntax,
+                    span,
+                    "Invalid lambda expression"
+                )),
             },
+            list_pattern![
+                LSExpr {
+                    value: SExpr::Symbol("case-lambda"),
+                    ..
+                } => span,
+                ..cdr
+            ] => {
+                let clauses = cdr
+                    .value
+                    .to_vec()
+                    .ok_or_else(|| {
+                        compiler_error!(InvalidSyntax, span, "Expected a list of clauses")
+                    })?
+                    .into_iter()
+                    .map(|clause| match clause {
+                        list_pattern![args => clause_span, ..exprs] => {
+                            Ok(vec![Self::parse_lambda(clause_span, args, exprs)?])
+                        }
+                        clause => Err(compiler_error!(
+                            InvalidSyntax,
+                            clause.span,
+                            "Invalid case-lambda clause"
+                        )),
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                Ok(Expr::CaseLambda((), CaseLambda { clauses }).with_span(span))
+            }
             list_pattern![
                 LSExpr {
                     value: SExpr::Symbol("if"),
@@ -179,7 +231,11 @@ impl Parsed {
                     )
                     .with_span(span))
                 }
-                _ => Err(compiler_error!("Invalid if expression",)),
+                _ => Err(compiler_error!(
+                    InvalidSyntax,
+                    span,
+                    "Invalid if expression",
+                )),
             },
             list_pattern![
                 LSExpr {
@@ -191,7 +247,9 @@ impl Parsed {
                 let clauses = cdr
                     .value
                     .to_vec()
-                    .ok_or_else(|| compiler_error!("Expected a list of clauses"))?
+                    .ok_or_else(|| {
+                        compiler_error!(InvalidSyntax, span, "Expected a list of clauses")
+                    })?
                     .into_iter()
                     .map(|clause| match clause {
                         list_pattern![
@@ -204,7 +262,9 @@ impl Parsed {
                             let body = body
                                 .value
                                 .to_vec()
-                                .ok_or_else(|| compiler_error!("Invalid cond expression"))?
+                                .ok_or_else(|| {
+                                    compiler_error!(InvalidSyntax, span, "Invalid cond expression")
+                                })?
                                 .into_iter()
                                 .map(Self::from_sexpr)
                                 .collect::<Result<Vec<_>>>()?;
@@ -234,7 +294,9 @@ impl Parsed {
                             let body = body
                                 .value
                                 .to_vec()
-                                .ok_or_else(|| compiler_error!("Invalid cond expression"))?
+                                .ok_or_else(|| {
+                                    compiler_error!(InvalidSyntax, span, "Invalid cond expression")
+                                })?
                                 .into_iter()
                                 .map(Self::from_sexpr)
                                 .collect::<Result<Vec<_>>>()?;
@@ -243,7 +305,7 @@ impl Parsed {
                                 body,
                             })
                         }
-                        _ => Err(compiler_error!("Invalid cond clause")),
+                        _ => Err(compiler_error!(InvalidSyntax, span, "Invalid cond clause")),
                     })
                     .collect::<Result<Vec<_>>>()?;
                 Ok(Expr::Cond((), Cond { clauses }).with_span(span))
@@ -283,6 +345,13 @@ impl Parsed {
                 } => span,
                 ..cdr
             ] => Ok(Expr::LetRec((), Self::parse_let_like(cdr)?).with_span(span)),
+            list_pattern![
+                LSExpr {
+                    value: SExpr::Symbol("letrec*"),
+                    ..
+                } => span,
+                ..cdr
+            ] => Ok(Expr::LetRecStar((), Self::parse_let_like(cdr)?).with_span(span)),
             list_pattern![
                 LSExpr {
                     value: SExpr::Symbol("do"),
@@ -298,7 +367,9 @@ impl Parsed {
                 let bindings = bindings
                     .value
                     .to_vec()
-                    .ok_or_else(|| compiler_error!("Expected a list of bindings"))?
+                    .ok_or_else(|| {
+                        compiler_error!(InvalidSyntax, span, "Expected a list of bindings")
+                    })?
                     .into_iter()
                     .map(|binding| match binding {
                         list_pattern![
@@ -318,27 +389,37 @@ impl Parsed {
                                 list_pattern!(step,) => Some(vec![Self::from_sexpr(step)?]),
                                 _ => {
                                     return Err(compiler_error!(
+                                        InvalidSyntax,
+                                        binding.span,
                                         "Invalid do binding step expression"
                                     ));
                                 }
                             },
                         }
                         .with_span(binding.span)),
-                        _ => Err(compiler_error!("Invalid binding")),
+                        _ => Err(compiler_error!(
+                            InvalidSyntax,
+                            binding.span,
+                            "Invalid binding"
+                        )),
                     })
                     .collect::<Result<Vec<_>>>()?;
                 let test = Self::from_sexpr(test)?;
                 let exit_body = exit_body
                     .value
                     .to_vec()
-                    .ok_or_else(|| compiler_error!("Expected a list of expressions"))?
+                    .ok_or_else(|| {
+                        compiler_error!(InvalidSyntax, span, "Expected a list of expressions")
+                    })?
                     .into_iter()
                     .map(Self::from_sexpr)
                     .collect::<Result<Vec<_>>>()?;
                 let body = body
                     .value
                     .to_vec()
-                    .ok_or_else(|| compiler_error!("Expected a list of expressions"))?
+                    .ok_or_else(|| {
+                        compiler_error!(InvalidSyntax, span, "Expected a list of expressions")
+                    })?
                     .into_iter()
                     .map(Self::from_sexpr)
                     .collect::<Result<Vec<_>>>()?;
@@ -353,6 +434,32 @@ impl Parsed {
                 )
                 .with_span(span))
             }
+            list_pattern![
+                LSExpr {
+                    value: SExpr::Symbol(keyword @ ("delay" | "delay-force")),
+                    ..
+                } => span,
+                ..cdr
+            ] => match cdr {
+                list_pattern![expr,] => Ok(Expr::Delay(
+                    (),
+                    Delay {
+                        kind: if keyword == "delay" {
+                            DelayKind::Delay
+                        } else {
+                            DelayKind::DelayForce
+                        },
+                        expr: vec![Self::from_sexpr(expr)?],
+                    },
+                )
+                .with_span(span)),
+                _ => Err(compiler_error!(
+                    InvalidSyntax,
+                    span,
+                    "Invalid {} expression",
+                    keyword
+                )),
+            },
             list_pattern![
                 LSExpr {
                     value: SExpr::Symbol("begin"),
@@ -363,7 +470,9 @@ impl Parsed {
                 let exprs = exprs
                     .value
                     .to_vec()
-                    .ok_or_else(|| compiler_error!("Invalid begin expression"))?
+                    .ok_or_else(|| {
+                        compiler_error!(InvalidSyntax, span, "Invalid begin expression")
+                    })?
                     .into_iter()
                     .map(Self::from_sexpr)
                     .collect::<Result<Vec<_>>>()?;
@@ -393,7 +502,11 @@ impl Parsed {
                     )
                     .with_span(span))
                 }
-                _ => Err(compiler_error!("Invalid set! expression")),
+                _ => Err(compiler_error!(
+                    InvalidSyntax,
+                    span,
+                    "Invalid set! expression"
+                )),
             },
             list_pattern![
                 LSExpr {
@@ -405,7 +518,9 @@ impl Parsed {
                 let exprs = cdr
                     .value
                     .to_vec()
-                    .ok_or_else(|| compiler_error!("Expected a list of expressions"))?
+                    .ok_or_else(|| {
+                        compiler_error!(InvalidSyntax, span, "Expected a list of expressions")
+                    })?
                     .into_iter()
                     .map(|expr| Self::from_sexpr(expr).map(|e| vec![e]))
                     .collect::<Result<Vec<_>>>()?;
@@ -421,7 +536,9 @@ impl Parsed {
                 let exprs = cdr
                     .value
                     .to_vec()
-                    .ok_or_else(|| compiler_error!("Expected a list of expressions"))?
+                    .ok_or_else(|| {
+                        compiler_error!(InvalidSyntax, span, "Expected a list of expressions")
+                    })?
                     .into_iter()
                     .map(|expr| Self::from_sexpr(expr).map(|e| vec![e]))
                     .collect::<Result<Vec<_>>>()?;
@@ -432,7 +549,9 @@ impl Parsed {
                 let args = args
                     .value
                     .to_vec()
-                    .ok_or_else(|| compiler_error!("Expected a list of arguments"))?
+                    .ok_or_else(|| {
+                        compiler_error!(InvalidSyntax, span, "Expected a list of arguments")
+                    })?
                     .into_iter()
                     .map(|expr| Self::from_sexpr(expr).map(|e| vec![e]))
                     .collect::<Result<Vec<_>>>()?;
@@ -449,12 +568,15 @@ impl Parsed {
     }
 
     fn parse_let_like(sexpr: LSExpr) -> Result<LetLike<Self>> {
+        let span = sexpr.span;
         match sexpr {
             list_pattern![bindings, ..body] => {
                 let bindings = bindings
                     .value
                     .to_vec()
-                    .ok_or_else(|| compiler_error!("Expected a list of bindings"))?
+                    .ok_or_else(|| {
+                        compiler_error!(InvalidSyntax, span, "Expected a list of bindings")
+                    })?
                     .into_iter()
                     .map(|binding| match binding {
                         list_pattern![
@@ -468,20 +590,30 @@ impl Parsed {
                             expr: vec![Self::from_sexpr(expr)?],
                         }
                         .with_span(binding.span)),
-                        _ => Err(compiler_error!("Invalid binding")),
+                        _ => Err(compiler_error!(
+                            InvalidSyntax,
+                            binding.span,
+                            "Invalid binding"
+                        )),
                     })
                     .collect::<Result<Vec<_>>>()?;
                 let body = body
                     .value
                     .to_vec()
-                    .ok_or_else(|| compiler_error!("Expected a list of expressions"))?
+                    .ok_or_else(|| {
+                        compiler_error!(InvalidSyntax, span, "Expected a list of expressions")
+                    })?
                     .into_iter()
                     .map(Self::from_sexpr)
                     .collect::<Result<Vec<_>>>()?;
 
                 Ok(LetLike { bindings, body })
             }
-            _ => Err(compiler_error!("Invalid let-like expression")),
+            _ => Err(compiler_error!(
+                InvalidSyntax,
+                span,
+                "Invalid let-like expression"
+            )),
         }
     }
 
@@ -491,7 +623,11 @@ impl Parsed {
             .into_iter()
             .map(|arg| match arg.value {
                 SExpr::Symbol(s) => Ok(s.with_span(arg.span)),
-                _ => Err(compiler_error!("Expected a symbol")),
+                _ => Err(compiler_error!(
+                    InvalidSyntax,
+                    arg.span,
+                    "Expected a symbol"
+                )),
             })
             .collect::<Result<Vec<_>>>()?;
         let variadic_arg = match variadic_arg {
@@ -502,14 +638,18 @@ impl Parsed {
             LSExpr {
                 value: SExpr::Nil, ..
             } => None,
-            _ => {
-                return Err(compiler_error!("Invalid variadic argument"));
+            variadic_arg => {
+                return Err(compiler_error!(
+                    InvalidSyntax,
+                    variadic_arg.span,
+                    "Invalid variadic argument"
+                ));
             }
         };
         let exprs = exprs
             .value
             .to_vec()
-            .ok_or_else(|| compiler_error!("Expected a list of expressions"))?
+            .ok_or_else(|| compiler_error!(InvalidSyntax, span, "Expected a list of expressions"))?
             .into_iter()
             .map(Self::from_sexpr)
             .collect::<Result<Vec<_>>>()?;
@@ -23,6 +23,7 @@ pub trait TailCallPrevPhase = AstPhase<
         XCond = !,
         XNamedLet = !,
         XDo = !,
+        XDelay = !,
         XAnd = !,
         XOr = !,
     >;
@@ -53,6 +54,17 @@ impl<P: TailCallPrevPhase> TailCall<P> {
                 },
             )
             .with_span(expr.span),
+            Expr::CaseLambda(x, case_lambda) => Expr::CaseLambda(
+                x,
+                CaseLambda {
+                    clauses: case_lambda
+                        .clauses
+                        .into_iter()
+                        .map(|clause| Self::from_exprs(clause, false))
+                        .collect(),
+                },
+            )
+            .with_span(expr.span),
             Expr::If(x, if_) => Expr::If(
                 x,
                 If {
@@ -91,6 +103,9 @@ impl<P: TailCallPrevPhase> TailCall<P> {
             Expr::LetRec(x, let_like) => {
                 Expr::LetRec(x, Self::from_let_like(let_like, is_tail)).with_span(expr.span)
             }
+            Expr::LetRecStar(x, let_like) => {
+                Expr::LetRecStar(x, Self::from_let_like(let_like, is_tail)).with_span(expr.span)
+            }
             Expr::Vector(x, vec) => Expr::Vector(x, {
                 vec.into_iter()
                     .map(|expr| Self::from_exprs(expr, false))
@@ -1,7 +1,7 @@
 use rustc_hash::{FxHashMap, FxHashSet};
 use webschembly_compiler_ast::*;
-use webschembly_compiler_error::{Result, compiler_error};
-use webschembly_compiler_locate::{Located, LocatedValue};
+use webschembly_compiler_error::{CompilerError, Diagnostic, ErrorCode, Result};
+use webschembly_compiler_locate::{Located, LocatedValue, Span};
 
 pub trait UsedPrevPhase = AstPhase<
         XBegin = !,
@@ -12,6 +12,7 @@ pub trait UsedPrevPhase = AstPhase<
         XCond = !,
         XNamedLet = !,
         XDo = !,
+        XDelay = !,
         XAnd = !,
         XOr = !,
     >;
@@ -98,6 +99,8 @@ struct EnvLocalVar {
     id: LocalVarId,
     captured: bool,
     initialized: bool,
+    // 宣言位置
+    span: Span,
 }
 
 #[derive(Debug, Clone)]
@@ -132,6 +135,7 @@ pub struct VarIdGen {
     use_globals: FxHashSet<GlobalVarId>,
     local_metas: FxHashMap<LocalVarId, VarMeta>,
     global_metas: FxHashMap<GlobalVarId, VarMeta>,
+    errors: Vec<Diagnostic>,
 }
 
 impl Default for VarIdGen {
@@ -151,6 +155,7 @@ impl VarIdGen {
             use_globals: FxHashSet::default(),
             local_metas: FxHashMap::default(),
             global_metas: FxHashMap::default(),
+            errors: Vec::new(),
         }
     }
 
@@ -198,6 +203,15 @@ impl VarIdGen {
 
     fn reset_for_module(&mut self) {
         self.use_globals.clear();
+        self.errors.clear();
+    }
+
+    fn uninitialized_error(&mut self, message: String, span: Span, local_var: &EnvLocalVar) {
+        self.errors.push(
+            Diagnostic::error(ErrorCode::UninitializedVariable, message)
+                .with_primary(span, "")
+                .with_secondary(local_var.span, "defined here"),
+        );
     }
 }
 
@@ -214,11 +228,15 @@ impl<P: UsedPrevPhase> Used<P> {
                 var_id_gen,
                 &mut state,
                 &mut result,
-            )?;
+            );
             debug_assert!(state.captures.is_empty());
             defines.extend(state.defines);
         }
 
+        if !var_id_gen.errors.is_empty() {
+            return Err(CompilerError::new(std::mem::take(&mut var_id_gen.errors)));
+        }
+
         Ok(Ast {
             x: UsedAstR {
                 box_vars: var_id_gen
@@ -241,13 +259,17 @@ impl<P: UsedPrevPhase> Used<P> {
         var_id_gen: &mut VarIdGen,
         state: &mut LambdaState,
         result: &mut Vec<LExpr<Self>>,
-    ) -> Result<()> {
+    ) {
         match expr.value {
             Expr::Const(x, lit) => result.push(Expr::Const(x, lit).with_span(expr.span)),
             Expr::Var(_, var) => {
                 let var_id = if let Some(local_var) = ctx.env.get(&var) {
                     if !local_var.initialized {
-                        return Err(compiler_error!("use of uninitialized variable: {}", var));
+                        var_id_gen.uninitialized_error(
+                            format!("use of uninitialized variable: {}", var),
+                            expr.span,
+                            local_var,
+                        );
                     }
                     if local_var.captured {
                         state.captures.insert(local_var.id);
@@ -271,7 +293,7 @@ impl<P: UsedPrevPhase> Used<P> {
                 let args = lambda
                     .args
                     .iter()
-                    .map(|Located { value: arg, .. }| {
+                    .map(|Located { value: arg, span }| {
                         let id = var_id_gen.gen_local(VarMeta { name: arg.clone() });
                         new_ctx.env.insert(
                             arg.clone(),
@@ -279,6 +301,7 @@ impl<P: UsedPrevPhase> Used<P> {
                                 id,
                                 captured: false,
                                 initialized: true,
+                                span: *span,
                             },
                         );
                         id
@@ -288,7 +311,7 @@ impl<P: UsedPrevPhase> Used<P> {
                 let variadic_arg = lambda.variadic_arg.as_ref().map(
                     |Located {
                          value: variadic_arg,
-                         ..
+                         span,
                      }| {
                         let id = var_id_gen.gen_local(VarMeta {
                             name: variadic_arg.clone(),
@@ -299,6 +322,7 @@ impl<P: UsedPrevPhase> Used<P> {
                                 id,
                                 captured: false,
                                 initialized: true,
+                                span: *span,
                             },
                         );
                         id
@@ -307,7 +331,7 @@ impl<P: UsedPrevPhase> Used<P> {
 
                 let mut new_state = LambdaState::new();
 
-                let new_body = Self::from_exprs(lambda.body, &new_ctx, var_id_gen, &mut new_state)?;
+                let new_body = Self::from_exprs(lambda.body, &new_ctx, var_id_gen, &mut new_state);
 
                 {
                     // キャプチャリストを親ラムダが継承する
@@ -343,10 +367,18 @@ impl<P: UsedPrevPhase> Used<P> {
                     .with_span(expr.span),
                 );
             }
+            Expr::CaseLambda(x, case_lambda) => {
+                let clauses = case_lambda
+                    .clauses
+                    .into_iter()
+                    .map(|clause| Self::from_exprs(clause, ctx, var_id_gen, state))
+                    .collect::<Vec<_>>();
+                result.push(Expr::CaseLambda(x, CaseLambda { clauses }).with_span(expr.span));
+            }
             Expr::If(x, if_) => {
-                let new_cond = Self::from_exprs(if_.cond, ctx, var_id_gen, state)?;
-                let new_then = Self::from_exprs(if_.then, ctx, var_id_gen, state)?;
-                let new_els = Self::from_exprs(if_.els, ctx, var_id_gen, state)?;
+                let new_cond = Self::from_exprs(if_.cond, ctx, var_id_gen, state);
+                let new_then = Self::from_exprs(if_.then, ctx, var_id_gen, state);
+                let new_els = Self::from_exprs(if_.els, ctx, var_id_gen, state);
                 result.push(
                     Expr::If(
                         x,
@@ -361,12 +393,12 @@ impl<P: UsedPrevPhase> Used<P> {
             }
             Expr::Cond(x, _) => x,
             Expr::Call(x, call) => {
-                let new_func = Self::from_exprs(call.func, ctx, var_id_gen, state)?;
+                let new_func = Self::from_exprs(call.func, ctx, var_id_gen, state);
                 let new_args = call
                     .args
                     .into_iter()
                     .map(|arg| Self::from_exprs(arg, ctx, var_id_gen, state))
-                    .collect::<Result<Vec<_>>>()?;
+                    .collect::<Vec<_>>();
                 result.push(
                     Expr::Call(
                         x,
@@ -382,10 +414,11 @@ impl<P: UsedPrevPhase> Used<P> {
             Expr::Set(_, set) => {
                 let var_id = if let Some(local_var) = ctx.env.get(&set.name.value) {
                     if !local_var.initialized {
-                        return Err(compiler_error!(
-                            "set of uninitialized variable: {}",
-                            set.name.value
-                        ));
+                        var_id_gen.uninitialized_error(
+                            format!("set of uninitialized variable: {}", set.name.value),
+                            set.name.span,
+                            local_var,
+                        );
                     }
 
                     if local_var.captured {
@@ -396,7 +429,7 @@ impl<P: UsedPrevPhase> Used<P> {
                 } else {
                     VarId::Global(var_id_gen.global_var_id(&set.name.value))
                 };
-                let new_expr = Self::from_exprs(set.expr, ctx, var_id_gen, state)?;
+                let new_expr = Self::from_exprs(set.expr, ctx, var_id_gen, state);
                 result.push(
                     Expr::Set(
                         UsedSetR { var_id },
@@ -425,11 +458,12 @@ impl<P: UsedPrevPhase> Used<P> {
                             id,
                             captured: false,
                             initialized: true,
+                            span: name.span,
                         },
                     );
                     state.defines.push(id);
 
-                    let expr = Self::from_exprs(expr.clone(), ctx, var_id_gen, state)?;
+                    let expr = Self::from_exprs(expr.clone(), ctx, var_id_gen, state);
                     let set_expr = Expr::Set(
                         UsedSetR {
                             var_id: VarId::Local(id),
@@ -445,83 +479,22 @@ impl<P: UsedPrevPhase> Used<P> {
 
                 for expr in let_.body {
                     // stateは親のものを引き継ぐ
-                    Self::from_expr(expr, &new_ctx, var_id_gen, state, result)?;
+                    Self::from_expr(expr, &new_ctx, var_id_gen, state, result);
                 }
             }
             Expr::LetStar(x, _) => x,
             Expr::LetRec(_, letrec) => {
-                // TODO: letrecの定義式で同じletrecの変数を参照する場合、ラムダで囲わないとエラーにする必要がある
-                // (letrec ((a 1) (b (+ a 1))) b) のようなものは許されない。let*を使うべき
-
-                let mut new_ctx = ctx.clone();
-
-                for Located {
-                    value: Binding { name, .. },
-                    ..
-                } in letrec.bindings.iter()
-                {
-                    let id = var_id_gen.gen_local(VarMeta {
-                        name: name.value.clone(),
-                    });
-                    new_ctx.env.insert(
-                        name.value.clone(),
-                        EnvLocalVar {
-                            id,
-                            captured: false,
-                            initialized: false,
-                        },
-                    );
-                    state.defines.push(id);
-                }
-
-                let mut set_group = SetGroup {
-                    var_ids: Vec::new(),
-                    exprs: Vec::new(),
-                };
-
-                for Located {
-                    value: Binding { name, expr },
-                    span: binding_span,
-                } in letrec.bindings.iter()
-                {
-                    let var_id = new_ctx.env.get(&name.value).unwrap().id;
-                    let expr = Self::from_exprs(expr.clone(), &new_ctx, var_id_gen, state)?;
-                    let set_expr = Expr::Set(
-                        UsedSetR {
-                            var_id: VarId::Local(var_id),
-                        },
-                        Set {
-                            name: name.clone(),
-                            expr,
-                        },
-                    )
-                    .with_span(*binding_span);
-                    set_group.var_ids.push(var_id);
-                    set_group.exprs.push(set_expr);
-                }
-                result.push(Expr::Ext(UsedExtR::SetGroup(set_group)).with_span(expr.span));
-
-                // body評価時点では初期化される
-                for Located {
-                    value: Binding { name, .. },
-                    ..
-                } in letrec.bindings.iter()
-                {
-                    let var = new_ctx.env.get_mut(&name.value).unwrap();
-                    debug_assert!(!var.initialized);
-                    var.initialized = true;
-                }
-
-                for expr in letrec.body.into_iter() {
-                    // stateは親のものを引き継ぐ
-                    Self::from_expr(expr, &new_ctx, var_id_gen, state, result)?;
-                }
+                Self::from_letrec(letrec, expr.span, false, ctx, var_id_gen, state, result)
+            }
+            // 各束縛は定義式の評価後から参照できる
+            Expr::LetRecStar(_, letrec) => {
+                Self::from_letrec(letrec, expr.span, true, ctx, var_id_gen, state, result)
             }
             Expr::Vector(x, vec) => {
                 let new_vec = vec
                     .into_iter()
                     .map(|expr| Self::from_exprs(expr, ctx, var_id_gen, state))
-                    .collect::<Result<Vec<_>>>()?;
+                    .collect::<Vec<_>>();
                 result.push(Expr::Vector(x, new_vec).with_span(expr.span))
             }
             Expr::UVector(x, uvec) => result.push(
@@ -533,15 +506,15 @@ impl<P: UsedPrevPhase> Used<P> {
                             .elements
                             .into_iter()
                             .map(|expr| Self::from_exprs(expr, ctx, var_id_gen, state))
-                            .collect::<Result<Vec<_>>>()?,
+                            .collect::<Vec<_>>(),
                     },
                 )
                 .with_span(expr.span),
             ),
             Expr::Quote(x, _) => x,
             Expr::Cons(x, cons) => {
-                let new_car = Self::from_exprs(cons.car, ctx, var_id_gen, state)?;
-                let new_cdr = Self::from_exprs(cons.cdr, ctx, var_id_gen, state)?;
+                let new_car = Self::from_exprs(cons.car, ctx, var_id_gen, state);
+                let new_cdr = Self::from_exprs(cons.cdr, ctx, var_id_gen, state);
                 result.push(
                     Expr::Cons(
                         x,
@@ -555,8 +528,88 @@ impl<P: UsedPrevPhase> Used<P> {
             }
             Expr::Ext(x) => x,
         }
+    }
 
-        Ok(())
+    // sequentialならletrec*として扱う
+    fn from_letrec(
+        letrec: LetLike<P>,
+        span: Span,
+        sequential: bool,
+        ctx: &Context,
+        var_id_gen: &mut VarIdGen,
+        state: &mut LambdaState,
+        result: &mut Vec<LExpr<Self>>,
+    ) {
+        // TODO: letrecの定義式で同じletrecの変数を参照する場合、ラムダで囲わないとエラーにする必要がある
+        // (letrec ((a 1) (b (+ a 1))) b) のようなものは許されない。let*を使うべき
+
+        let mut new_ctx = ctx.clone();
+
+        for Located {
+            value: Binding { name, .. },
+            ..
+        } in letrec.bindings.iter()
+        {
+            let id = var_id_gen.gen_local(VarMeta {
+                name: name.value.clone(),
+            });
+            new_ctx.env.insert(
+                name.value.clone(),
+                EnvLocalVar {
+                    id,
+                    captured: false,
+                    initialized: false,
+                    span: name.span,
+                },
+            );
+            state.defines.push(id);
+        }
+
+        let mut set_group = SetGroup {
+            var_ids: Vec::new(),
+            exprs: Vec::new(),
+        };
+
+        for Located {
+            value: Binding { name, expr },
+            span: binding_span,
+        } in letrec.bindings.iter()
+        {
+            let var_id = new_ctx.env.get(&name.value).unwrap().id;
+            let expr = Self::from_exprs(expr.clone(), &new_ctx, var_id_gen, state);
+            let set_expr = Expr::Set(
+                UsedSetR {
+                    var_id: VarId::Local(var_id),
+                },
+                Set {
+                    name: name.clone(),
+                    expr,
+                },
+            )
+            .with_span(*binding_span);
+            set_group.var_ids.push(var_id);
+            set_group.exprs.push(set_expr);
+            if sequential {
+                new_ctx.env.get_mut(&name.value).unwrap().initialized = true;
+            }
+        }
+        result.push(Expr::Ext(UsedExtR::SetGroup(set_group)).with_span(span));
+
+        // body評価時点では初期化される
+        for Located {
+            value: Binding { name, .. },
+            ..
+        } in letrec.bindings.iter()
+        {
+            let var = new_ctx.env.get_mut(&name.value).unwrap();
+            debug_assert!(sequential || !var.initialized);
+            var.initialized = true;
+        }
+
+        for expr in letrec.body.into_iter() {
+            // stateは親のものを引き継ぐ
+            Self::from_expr(expr, &new_ctx, var_id_gen, state, result);
+        }
     }
 
     fn from_exprs(
@@ -564,11 +617,11 @@ impl<P: UsedPrevPhase> Used<P> {
         ctx: &Context,
         var_id_gen: &mut VarIdGen,
         state: &mut LambdaState,
-    ) -> Result<Vec<LExpr<Self>>> {
+    ) -> Vec<LExpr<Self>> {
         let mut result = Vec::new();
         for expr in exprs {
-            Self::from_expr(expr, ctx, var_id_gen, state, &mut result)?;
+            Self::from_expr(expr, ctx, var_id_gen, state, &mut result);
         }
-        Ok(result)
+        result
     }
 }
@@ -11,6 +11,13 @@ pub type LExpr<X> = L<Expr<X>>;
 pub enum UVectorKind {
     S64,
     F64,
+    U8,
+    S8,
+    U16,
+    S16,
+    U32,
+    S32,
+    F32,
 }
 
 pub trait AstPhaseX = std::fmt::Debug + Clone;
@@ -24,6 +31,7 @@ pub trait AstPhase: Sized + Clone + Debug {
     type XConst: AstPhaseX;
     type XDefine: AstPhaseX;
     type XLambda: AstPhaseX;
+    type XCaseLambda: AstPhaseX;
     type XIf: AstPhaseX;
     type XCond: AstPhaseX;
     type XCall: AstPhaseX;
@@ -33,8 +41,10 @@ pub trait AstPhase: Sized + Clone + Debug {
     type XLet: AstPhaseX;
     type XLetStar: AstPhaseX;
     type XLetRec: AstPhaseX;
+    type XLetRecStar: AstPhaseX;
     type XNamedLet: AstPhaseX;
     type XDo: AstPhaseX;
+    type XDelay: AstPhaseX;
     type XVector: AstPhaseX;
     type XUVector: AstPhaseX;
     type XQuote: AstPhaseX;
@@ -50,6 +60,7 @@ pub trait ExtendAstPhase: Sized + Clone + Debug {
     type XConst: AstPhaseX = <Self::Prev as AstPhase>::XConst;
     type XDefine: AstPhaseX = <Self::Prev as AstPhase>::XDefine;
     type XLambda: AstPhaseX = <Self::Prev as AstPhase>::XLambda;
+    type XCaseLambda: AstPhaseX = <Self::Prev as AstPhase>::XCaseLambda;
     type XIf: AstPhaseX = <Self::Prev as AstPhase>::XIf;
     type XCond: AstPhaseX = <Self::Prev as AstPhase>::XCond;
     type XCall: AstPhaseX = <Self::Prev as AstPhase>::XCall;
@@ -59,8 +70,10 @@ pub trait ExtendAstPhase: Sized + Clone + Debug {
     type XLet: AstPhaseX = <Self::Prev as AstPhase>::XLet;
     type XLetStar: AstPhaseX = <Self::Prev as AstPhase>::XLetStar;
     type XLetRec: AstPhaseX = <Self::Prev as AstPhase>::XLetRec;
+    type XLetRecStar: AstPhaseX = <Self::Prev as AstPhase>::XLetRecStar;
     type XNamedLet: AstPhaseX = <Self::Prev as AstPhase>::XNamedLet;
     type XDo: AstPhaseX = <Self::Prev as AstPhase>::XDo;
+    type XDelay: AstPhaseX = <Self::Prev as AstPhase>::XDelay;
     type XVector: AstPhaseX = <Self::Prev as AstPhase>::XVector;
     type XUVector: AstPhaseX = <Self::Prev as AstPhase>::XUVector;
     type XQuote: AstPhaseX = <Self::Prev as AstPhase>::XQuote;
@@ -75,6 +88,7 @@ impl<T: ExtendAstPhase> AstPhase for T {
     type XConst = T::XConst;
     type XDefine = T::XDefine;
     type XLambda = T::XLambda;
+    type XCaseLambda = T::XCaseLambda;
     type XIf = T::XIf;
     type XCond = T::XCond;
     type XCall = T::XCall;
@@ -84,8 +98,10 @@ impl<T: ExtendAstPhase> AstPhase for T {
     type XLet = T::XLet;
     type XLetStar = T::XLetStar;
     type XLetRec = T::XLetRec;
+    type XLetRecStar = T::XLetRecStar;
     type XNamedLet = T::XNamedLet;
     type XDo = T::XDo;
+    type XDelay = T::XDelay;
     type XVector = T::XVector;
     type XUVector = T::XUVector;
     type XQuote = T::XQuote;
@@ -113,6 +129,7 @@ where
     Var(X::XVar, String),
     Define(X::XDefine, Define<X>),
     Lambda(X::XLambda, Lambda<X>),
+    CaseLambda(X::XCaseLambda, CaseLambda<X>),
     If(X::XIf, If<X>),
     Cond(X::XCond, Cond<X>),
     Call(X::XCall, Call<X>),
@@ -121,8 +138,10 @@ where
     Let(X::XLet, LetLike<X>),
     LetStar(X::XLetStar, LetLike<X>),
     LetRec(X::XLetRec, LetLike<X>),
+    LetRecStar(X::XLetRecStar, LetLike<X>),
     NamedLet(X::XNamedLet, L<String>, LetLike<X>),
     Do(X::XDo, Do<X>),
+    Delay(X::XDelay, Delay<X>),
     Vector(X::XVector, Vec<ExprBox<LExpr<X>>>),
     UVector(X::XUVector, UVector<X>),
     Quote(X::XQuote, LSExpr),
@@ -136,6 +155,8 @@ where
 pub enum Const {
     Bool(bool),
     Int(i64),
+    BigInt(String),
+    Rational(String, String),
     Float(NotNan<f64>),
     NaN,
     String(String),
@@ -163,6 +184,15 @@ where
     pub body: Vec<LExpr<X>>,
 }
 
+#[derive(Debug, Clone)]
+pub struct CaseLambda<X>
+where
+    X: AstPhase,
+{
+    // 各節はLambda式
+    pub clauses: Vec<ExprBox<LExpr<X>>>,
+}
+
 #[derive(Debug, Clone)]
 pub struct If<X>
 where
@@ -266,6 +296,21 @@ where
     pub step: Option<ExprBox<LExpr<X>>>,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DelayKind {
+    Delay,
+    DelayForce,
+}
+
+#[derive(Debug, Clone)]
+pub struct Delay<X>
+where
+    X: AstPhase,
+{
+    pub kind: DelayKind,
+    pub expr: ExprBox<LExpr<X>>,
+}
+
 #[derive(Debug, Clone)]
 pub struct UVector<X>
 where
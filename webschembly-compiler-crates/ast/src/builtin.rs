@@ -17,6 +17,59 @@ pub enum Builtin {
     Remainder,
     #[strum(serialize = "modulo")]
     Modulo,
+    #[strum(serialize = "numerator")]
+    Numerator,
+    #[strum(serialize = "denominator")]
+    Denominator,
+    #[strum(serialize = "floor")]
+    Floor,
+    #[strum(serialize = "round")]
+    Round,
+    #[strum(serialize = "ceiling")]
+    Ceiling,
+    #[strum(serialize = "truncate")]
+    Truncate,
+    #[strum(serialize = "abs")]
+    Abs,
+    #[strum(serialize = "min")]
+    Min,
+    #[strum(serialize = "max")]
+    Max,
+    #[strum(serialize = "gcd")]
+    Gcd,
+    #[strum(serialize = "lcm")]
+    Lcm,
+    #[strum(serialize = "expt")]
+    Expt,
+    // 平方根の整数部分のみ返す。残りはstdlibで計算する
+    #[strum(serialize = "%exact-integer-sqrt")]
+    ExactIntegerSqrt,
+    #[strum(serialize = "sqrt")]
+    Sqrt,
+    #[strum(serialize = "exp")]
+    Exp,
+    #[strum(serialize = "log")]
+    Log,
+    #[strum(serialize = "sin")]
+    Sin,
+    #[strum(serialize = "cos")]
+    Cos,
+    #[strum(serialize = "tan")]
+    Tan,
+    #[strum(serialize = "asin")]
+    Asin,
+    #[strum(serialize = "acos")]
+    Acos,
+    #[strum(serialize = "atan")]
+    Atan,
+    #[strum(serialize = "exact?")]
+    IsExact,
+    #[strum(serialize = "inexact?")]
+    IsInexact,
+    #[strum(serialize = "exact->inexact")]
+    ExactToInexact,
+    #[strum(serialize = "inexact->exact")]
+    InexactToExact,
     #[strum(serialize = "write-char")]
     WriteChar,
     #[strum(serialize = "pair?")]
@@ -55,10 +108,52 @@ pub enum Builtin {
     IsS64Vector,
     #[strum(serialize = "f64vector?")]
     IsF64Vector,
+    #[strum(serialize = "u8vector?")]
+    IsU8Vector,
+    #[strum(serialize = "s8vector?")]
+    IsS8Vector,
+    #[strum(serialize = "u16vector?")]
+    IsU16Vector,
+    #[strum(serialize = "s16vector?")]
+    IsS16Vector,
+    #[strum(serialize = "u32vector?")]
+    IsU32Vector,
+    #[strum(serialize = "s32vector?")]
+    IsS32Vector,
+    #[strum(serialize = "f32vector?")]
+    IsF32Vector,
     #[strum(serialize = "make-s64vector")]
     MakeS64Vector,
     #[strum(serialize = "make-f64vector")]
     MakeF64Vector,
+    #[strum(serialize = "make-u8vector")]
+    MakeU8Vector,
+    #[strum(serialize = "make-s8vector")]
+    MakeS8Vector,
+    #[strum(serialize = "make-u16vector")]
+    MakeU16Vector,
+    #[strum(serialize = "make-s16vector")]
+    MakeS16Vector,
+    #[strum(serialize = "make-u32vector")]
+    MakeU32Vector,
+    #[strum(serialize = "make-s32vector")]
+    MakeS32Vector,
+    #[strum(serialize = "make-f32vector")]
+    MakeF32Vector,
+    // bytevectorはu8vectorと同じ型である
+    #[strum(serialize = "bytevector?")]
+    IsBytevector,
+    #[strum(serialize = "bytevector-length")]
+    BytevectorLength,
+    #[strum(serialize = "bytevector-u8-ref")]
+    BytevectorU8Ref,
+    #[strum(serialize = "bytevector-u8-set!")]
+    BytevectorU8Set,
+    // 範囲の指定はstdlibで行う
+    #[strum(serialize = "%utf8->string")]
+    Utf8ToString,
+    #[strum(serialize = "%string->utf8")]
+    StringToUtf8,
     #[strum(serialize = "eq?")]
     Eq,
     #[strum(serialize = "eqv?")]
@@ -91,6 +186,56 @@ pub enum Builtin {
     StringSet,
     #[strum(serialize = "string-length")]
     StringLength,
+    // 可変長の引数はstdlibで処理する
+    #[strum(serialize = "%string-append")]
+    StringAppend,
+    #[strum(serialize = "substring")]
+    Substring,
+    #[strum(serialize = "%make-string")]
+    MakeString,
+    #[strum(serialize = "list->string")]
+    ListToString,
+    #[strum(serialize = "string->list")]
+    StringToList,
+    #[strum(serialize = "string<?")]
+    StringLt,
+    #[strum(serialize = "string>?")]
+    StringGt,
+    #[strum(serialize = "string<=?")]
+    StringLe,
+    #[strum(serialize = "string>=?")]
+    StringGe,
+    #[strum(serialize = "string-ci=?")]
+    StringCiEq,
+    #[strum(serialize = "string-ci<?")]
+    StringCiLt,
+    #[strum(serialize = "string-ci>?")]
+    StringCiGt,
+    #[strum(serialize = "string-ci<=?")]
+    StringCiLe,
+    #[strum(serialize = "string-ci>=?")]
+    StringCiGe,
+    // 基数の省略はstdlibで処理する
+    #[strum(serialize = "%string->number")]
+    StringToNumber,
+    #[strum(serialize = "char->integer")]
+    CharToInteger,
+    #[strum(serialize = "integer->char")]
+    IntegerToChar,
+    #[strum(serialize = "char-upcase")]
+    CharUpcase,
+    #[strum(serialize = "char-downcase")]
+    CharDowncase,
+    #[strum(serialize = "char-alphabetic?")]
+    CharAlphabetic,
+    #[strum(serialize = "char-numeric?")]
+    CharNumeric,
+    #[strum(serialize = "char-whitespace?")]
+    CharWhitespace,
+    #[strum(serialize = "char-upper-case?")]
+    CharUpperCase,
+    #[strum(serialize = "char-lower-case?")]
+    CharLowerCase,
     #[strum(serialize = "<")]
     Lt,
     #[strum(serialize = ">")]
@@ -99,6 +244,90 @@ pub enum Builtin {
     Le,
     #[strum(serialize = ">=")]
     Ge,
+    // call/ccの実装用。CPS変換で呼び出し元の継続を受け渡す形に展開される
+    // (%call/cc f)はfに継続そのものを渡し、(%resume k v)は現在の継続を捨てて継続kにvを渡す
+    #[strum(serialize = "%call/cc")]
+    CallCc,
+    #[strum(serialize = "%resume")]
+    Resume,
+    #[strum(serialize = "error-object?")]
+    IsErrorObject,
+    #[strum(serialize = "error-object-message")]
+    ErrorObjectMessage,
+    #[strum(serialize = "error-object-irritants")]
+    ErrorObjectIrritants,
+    // raise/guardの実装用
+    #[strum(serialize = "%make-error-object")]
+    MakeErrorObject,
+    #[strum(serialize = "%raise")]
+    Raise,
+    #[strum(serialize = "%with-raise-handler")]
+    WithRaiseHandler,
+    #[strum(serialize = "%error-object-kind")]
+    ErrorObjectKind,
+    #[strum(serialize = "port?")]
+    IsPort,
+    // 省略可能なポート引数はstdlibで処理する
+    #[strum(serialize = "%input-port?")]
+    IsInputPort,
+    #[strum(serialize = "%output-port?")]
+    IsOutputPort,
+    #[strum(serialize = "open-input-string")]
+    OpenInputString,
+    #[strum(serialize = "open-output-string")]
+    OpenOutputString,
+    #[strum(serialize = "get-output-string")]
+    GetOutputString,
+    #[strum(serialize = "close-port")]
+    ClosePort,
+    #[strum(serialize = "%standard-port")]
+    StandardPort,
+    #[strum(serialize = "%read-char")]
+    ReadChar,
+    #[strum(serialize = "%peek-char")]
+    PeekChar,
+    #[strum(serialize = "%read")]
+    Read,
+    #[strum(serialize = "eof-object")]
+    EofObject,
+    #[strum(serialize = "%set-current-output-port!")]
+    SetCurrentOutputPort,
+    // 環境引数はstdlibで処理する
+    #[strum(serialize = "%eval")]
+    Eval,
+    // 可変長の引数はstdlibでリストにまとめる
+    #[strum(serialize = "%apply")]
+    Apply,
+    // 同値性の手続きから種類への変換はstdlibで行う
+    #[strum(serialize = "%make-hash-table")]
+    MakeHashTable,
+    #[strum(serialize = "hash-table?")]
+    IsHashTable,
+    // 見つからない場合の処理はstdlibで行う
+    #[strum(serialize = "%hash-table-ref")]
+    HashTableRef,
+    #[strum(serialize = "hash-table-set!")]
+    HashTableSet,
+    #[strum(serialize = "hash-table-delete!")]
+    HashTableDelete,
+    #[strum(serialize = "hash-table-count")]
+    HashTableCount,
+    #[strum(serialize = "hash-table->alist")]
+    HashTableToAlist,
+    // define-record-typeはstdlibのマクロで展開される
+    // レコード型のIDとフィールドのインデックスはリテラルで渡す
+    #[strum(serialize = "%make-record")]
+    MakeRecord,
+    #[strum(serialize = "%record-of?")]
+    IsRecordOf,
+    #[strum(serialize = "%record-ref")]
+    RecordRef,
+    #[strum(serialize = "%record-set!")]
+    RecordSet,
+    #[strum(serialize = "%record?")]
+    IsRecord,
+    #[strum(serialize = "%record-type")]
+    RecordType,
 }
 
 impl Builtin {
@@ -1,21 +1,165 @@
+mod render;
+
+use webschembly_compiler_locate::Span;
+
+// 一度割り当てた番号は変えない
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCode {
+    Lex,
+    Parse,
+    InvalidSyntax,
+    Macro,
+    DefineNotAllowed,
+    UninitializedVariable,
+}
+
+impl ErrorCode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ErrorCode::Lex => "E0001",
+            ErrorCode::Parse => "E0002",
+            ErrorCode::InvalidSyntax => "E0003",
+            ErrorCode::Macro => "E0004",
+            ErrorCode::DefineNotAllowed => "E0005",
+            ErrorCode::UninitializedVariable => "E0006",
+        }
+    }
+}
+
+impl std::fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
-pub struct CompilerError(pub String);
+pub struct Label {
+    pub span: Span,
+    pub message: String, // 空ならキャレットのみ表示する
+}
+
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub code: ErrorCode,
+    pub message: String,
+    pub primary: Option<Label>,
+    pub secondary: Vec<Label>,
+    pub notes: Vec<String>,
+}
+
+impl Diagnostic {
+    pub fn error(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Error,
+            code,
+            message: message.into(),
+            primary: None,
+            secondary: Vec::new(),
+            notes: Vec::new(),
+        }
+    }
+
+    pub fn with_primary(mut self, span: Span, message: impl Into<String>) -> Self {
+        self.primary = Some(Label {
+            span,
+            message: message.into(),
+        });
+        self
+    }
+
+    pub fn with_secondary(mut self, span: Span, message: impl Into<String>) -> Self {
+        self.secondary.push(Label {
+            span,
+            message: message.into(),
+        });
+        self
+    }
+
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.notes.push(note.into());
+        self
+    }
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}[{}]: {}", self.severity, self.code, self.message)?;
+        if let Some(primary) = &self.primary {
+            write!(f, " at {}", primary.span)?;
+        }
+        Ok(())
+    }
+}
+
+// 1つ以上のDiagnosticを持つ
+#[derive(Debug, Clone)]
+pub struct CompilerError {
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl CompilerError {
+    pub fn new(diagnostics: Vec<Diagnostic>) -> Self {
+        debug_assert!(!diagnostics.is_empty());
+        Self { diagnostics }
+    }
+
+    // ソースコードの該当箇所を含めて表示する
+    pub fn render(&self, source: &str, file_name: Option<&str>) -> String {
+        render::render_diagnostics(&self.diagnostics, source, file_name)
+    }
+}
+
+impl From<Diagnostic> for CompilerError {
+    fn from(diagnostic: Diagnostic) -> Self {
+        Self::new(vec![diagnostic])
+    }
+}
 
 impl std::fmt::Display for CompilerError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            CompilerError(message) => write!(f, "CompilerError: {}", message),
+        for (i, diagnostic) in self.diagnostics.iter().enumerate() {
+            if i != 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{}", diagnostic)?;
         }
+        Ok(())
     }
 }
 
 impl std::error::Error for CompilerError {}
 
+// compiler_error!(Code, span, "...") または compiler_error!(Code, "...")
 #[macro_export]
 macro_rules! compiler_error {
-    ($($arg:tt)*) => {
-        $crate::CompilerError(format!($($arg)*))
-    }
+    ($code:ident, $fmt:literal $($arg:tt)*) => {
+        $crate::CompilerError::from($crate::Diagnostic::error(
+            $crate::ErrorCode::$code,
+            format!($fmt $($arg)*),
+        ))
+    };
+    ($code:ident, $span:expr, $fmt:literal $($arg:tt)*) => {
+        $crate::CompilerError::from(
+            $crate::Diagnostic::error($crate::ErrorCode::$code, format!($fmt $($arg)*))
+                .with_primary($span, ""),
+        )
+    };
 }
 
 pub type Result<T> = std::result::Result<T, CompilerError>;
@@ -0,0 +1,150 @@
+use std::fmt::Write;
+
+use crate::{Diagnostic, Label};
+
+/*
+rustcと同様の形式で表示する
+
+error[E0006]: use of uninitialized variable: x
+ --> main.scm:2:13
+  |
+2 |   (define y x)
+  |             ^
+  = note: ...
+*/
+pub fn render_diagnostics(
+    diagnostics: &[Diagnostic],
+    source: &str,
+    file_name: Option<&str>,
+) -> String {
+    let lines = source.lines().collect::<Vec<_>>();
+    let mut result = String::new();
+    for (i, diagnostic) in diagnostics.iter().enumerate() {
+        if i != 0 {
+            result.push('\n');
+        }
+        render_diagnostic(&mut result, diagnostic, &lines, file_name);
+    }
+    result
+}
+
+fn render_diagnostic(
+    result: &mut String,
+    diagnostic: &Diagnostic,
+    lines: &[&str],
+    file_name: Option<&str>,
+) {
+    writeln!(
+        result,
+        "{}[{}]: {}",
+        diagnostic.severity, diagnostic.code, diagnostic.message
+    )
+    .unwrap();
+
+    let mut labels = diagnostic
+        .primary
+        .iter()
+        .map(|label| (label, '^'))
+        .chain(diagnostic.secondary.iter().map(|label| (label, '-')))
+        // 別のソース(include-stdlibなど)由来のspanや位置不明のspan(line=0)は表示できない
+        .filter(|(label, _)| (1..=lines.len()).contains(&label.span.start.line))
+        .collect::<Vec<_>>();
+    labels.sort_by_key(|(label, _)| (label.span.start.line, label.span.start.column));
+
+    let gutter_width = labels
+        .iter()
+        .map(|(label, _)| label.span.start.line.to_string().len())
+        .max()
+        .unwrap_or(0);
+
+    if let Some(primary) = &diagnostic.primary {
+        let pos = primary.span.start;
+        match file_name {
+            Some(file_name) => writeln!(result, "{:gutter_width$}--> {}:{}", "", file_name, pos),
+            None => writeln!(result, "{:gutter_width$}--> {}", "", pos),
+        }
+        .unwrap();
+    }
+
+    if !labels.is_empty() {
+        writeln!(result, "{:gutter_width$} |", "").unwrap();
+        let mut prev_line = None;
+        for (label, marker) in labels {
+            let line_no = label.span.start.line;
+            let line = lines[line_no - 1];
+            if prev_line != Some(line_no) {
+                writeln!(result, "{:>gutter_width$} | {}", line_no, line).unwrap();
+            }
+            prev_line = Some(line_no);
+            render_underline(result, label, marker, line, gutter_width);
+        }
+    }
+
+    for note in &diagnostic.notes {
+        writeln!(result, "{:gutter_width$} = note: {}", "", note).unwrap();
+    }
+}
+
+fn render_underline(
+    result: &mut String,
+    label: &Label,
+    marker: char,
+    line: &str,
+    gutter_width: usize,
+) {
+    let line_len = line.chars().count();
+    let start = label.span.start.column.saturating_sub(1).min(line_len);
+    // 複数行にまたがる場合は開始行の末尾まで
+    let end = if label.span.end.line == label.span.start.line {
+        label.span.end.column.saturating_sub(1).min(line_len)
+    } else {
+        line_len
+    };
+    let len = end.saturating_sub(start).max(1);
+    write!(
+        result,
+        "{:gutter_width$} | {}{}",
+        "",
+        " ".repeat(start),
+        marker.to_string().repeat(len)
+    )
+    .unwrap();
+    if !label.message.is_empty() {
+        write!(result, " {}", label.message).unwrap();
+    }
+    result.push('\n');
+}
+
+#[test]
+fn test_render_diagnostics() {
+    use crate::ErrorCode;
+    use webschembly_compiler_locate::{Pos, Span};
+
+    let source = "(define (f)\n  (define y x)\n  y)\n";
+    let diagnostic = Diagnostic::error(ErrorCode::DefineNotAllowed, "not allowed")
+        .with_primary(Span::new(Pos::new(2, 3), Pos::new(2, 15)), "here")
+        .with_secondary(Span::new(Pos::new(1, 1), Pos::new(3, 5)), "in this body")
+        .with_note("defines must come first");
+    assert_eq!(
+        render_diagnostics(&[diagnostic], source, Some("a.scm")),
+        "error[E0005]: not allowed
+ --> a.scm:2:3
+  |
+1 | (define (f)
+  | ----------- in this body
+2 |   (define y x)
+  |   ^^^^^^^^^^^^ here
+  = note: defines must come first
+"
+    );
+
+    // 位置不明のspanはスニペットを表示しない
+    let diagnostic = Diagnostic::error(ErrorCode::DefineNotAllowed, "not allowed")
+        .with_primary(Span::new(Pos::new(0, 0), Pos::new(0, 0)), "here");
+    assert_eq!(
+        render_diagnostics(&[diagnostic], source, None),
+        "error[E0005]: not allowed
+--> 0:0
+"
+    );
+}
@@ -16,7 +16,9 @@ impl FuncId {
 impl fmt::Display for Display<'_, FuncId> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "f{}", self.value.0)?;
-        // TODO: add function name
+        if let Some(meta) = self.meta.func_metas.get(&self.value) {
+            write!(f, "_{}", meta.name)?;
+        }
 
         Ok(())
     }
@@ -67,6 +69,26 @@ impl fmt::Display for Display<'_, GlobalId> {
     }
 }
 
+#[derive(Debug, Clone, Copy, From, Into, Hash, PartialEq, Eq)]
+pub struct SourceLocId(usize);
+
+impl SourceLocId {
+    pub fn display<'a>(&self, meta: &'a Meta) -> Display<'a, SourceLocId> {
+        Display { value: *self, meta }
+    }
+}
+
+impl fmt::Display for Display<'_, SourceLocId> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "loc{}", self.value.0)?;
+        if let Some(source_loc) = self.meta.source_locs.get(&self.value) {
+            write!(f, "_{}@{}", source_loc.func_name, source_loc.span.start)?;
+        }
+
+        Ok(())
+    }
+}
+
 #[derive(Debug, Clone, Copy, From, Into, Hash, PartialEq, Eq)]
 pub struct BasicBlockId(usize);
 
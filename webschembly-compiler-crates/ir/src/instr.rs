@@ -203,6 +203,9 @@ pub struct PhiIncomingValue {
     pub local: LocalId,
 }
 
+// IncrementBBLayoutCounterがランタイムに通知する間隔。2の冪でなければならない
+pub const BB_LAYOUT_COUNTER_INTERVAL: i64 = 64;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum BranchKind {
     Then,
@@ -218,6 +221,24 @@ impl fmt::Display for BranchKind {
     }
 }
 
+// IncrementBBLayoutCounterが数えるもの
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BBLayoutCounterKind {
+    // 特殊化されたBBに入った
+    Hit,
+    // 特殊化されたBBに入るための型検査に失敗した
+    GuardFailure,
+}
+
+impl fmt::Display for BBLayoutCounterKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BBLayoutCounterKind::Hit => write!(f, "hit"),
+            BBLayoutCounterKind::GuardFailure => write!(f, "guard_failure"),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum ExitInstr {
     Return(LocalId),
@@ -446,8 +467,23 @@ pub enum InstrKind {
         JitBasicBlockId,
         usize,
     ),
+    // 特殊化されたBBの実行回数や型検査の失敗回数を数える。第1引数は加算後のカウンタで、BB_LAYOUT_COUNTER_INTERVALの倍数になったときだけランタイムに通知する
+    IncrementBBLayoutCounter(
+        LocalId,
+        JitModuleId,
+        JitFuncId,
+        // env index
+        usize,
+        // func index
+        usize,
+        JitBasicBlockId,
+        // BB index
+        usize,
+        BBLayoutCounterKind,
+    ),
     Bool(bool),
     Int(i64),
+    BigInt(String), // 10進数表記。i64に収まらない値のみ
     Float(NotNan<f64>),
     NaN,
     String(String),
@@ -507,6 +543,70 @@ pub enum InstrKind {
     RemainderInt(LocalId, LocalId),
     ModuloInt(LocalId, LocalId),
     DivFloat(LocalId, LocalId),
+    // (int, int) -> bool: 対応するXxxIntがオーバーフローするか
+    AddIntOverflow(LocalId, LocalId),
+    SubIntOverflow(LocalId, LocalId),
+    MulIntOverflow(LocalId, LocalId),
+    // 0除算もQuotientIntでは計算できないので真になる
+    QuotientIntOverflow(LocalId, LocalId),
+    // (obj, obj) -> obj: int/bigintの汎用演算。結果はi64に収まればintになる
+    AddInteger(LocalId, LocalId),
+    SubInteger(LocalId, LocalId),
+    MulInteger(LocalId, LocalId),
+    QuotientInteger(LocalId, LocalId),
+    RemainderInteger(LocalId, LocalId),
+    ModuloInteger(LocalId, LocalId),
+    CmpInteger(LocalId, LocalId), // (obj, obj) -> int(-1, 0, 1)
+    Rational(LocalId, LocalId),   // (obj, obj) -> rational: 既約で分母が2以上の整数の組から生成する
+    // (obj, obj) -> obj: 正確数(int/bigint/rational)の汎用演算。分母が1になればint/bigintになる
+    AddRational(LocalId, LocalId),
+    SubRational(LocalId, LocalId),
+    MulRational(LocalId, LocalId),
+    DivRational(LocalId, LocalId),
+    CmpRational(LocalId, LocalId), // (obj, obj) -> int(-1, 0, 1)
+    Numerator(LocalId),            // rational -> obj
+    Denominator(LocalId),          // rational -> obj
+    FloorRational(LocalId),        // rational -> obj
+    RoundRational(LocalId),        // rational -> obj
+    RationalToFloat(LocalId),      // obj(bigint/rational) -> float
+    FloatToExact(LocalId),         // float -> obj
+    IntToFloat(LocalId),
+    FloorFloat(LocalId),
+    RoundFloat(LocalId),       // 偶数丸め
+    CeilingRational(LocalId),  // rational -> obj
+    TruncateRational(LocalId), // rational -> obj
+    CeilingFloat(LocalId),
+    TruncateFloat(LocalId),
+    AbsInt(LocalId), // i64::MINはラップアラウンドする
+    AbsFloat(LocalId),
+    MinInt(LocalId, LocalId),
+    MaxInt(LocalId, LocalId),
+    MinFloat(LocalId, LocalId),
+    MaxFloat(LocalId, LocalId),
+    // (int, int) -> int: XxxIntOverflowで確認してから使う
+    GcdInt(LocalId, LocalId),
+    LcmInt(LocalId, LocalId),
+    ExptInt(LocalId, LocalId),
+    // (int, int) -> bool: 対応するXxxIntがオーバーフローするか。ExptIntは指数が負の場合も真
+    GcdIntOverflow(LocalId, LocalId),
+    LcmIntOverflow(LocalId, LocalId),
+    ExptIntOverflow(LocalId, LocalId),
+    GcdInteger(LocalId, LocalId),     // (obj, obj) -> obj
+    LcmInteger(LocalId, LocalId),     // (obj, obj) -> obj
+    ExptRational(LocalId, LocalId),   // (obj, obj) -> obj: 指数は整数のみ
+    ExactIntegerSqrtInt(LocalId),     // int -> int: 負の数はエラー
+    ExactIntegerSqrtInteger(LocalId), // obj -> obj
+    SqrtFloat(LocalId),
+    ExpFloat(LocalId),
+    LogFloat(LocalId),
+    SinFloat(LocalId),
+    CosFloat(LocalId),
+    TanFloat(LocalId),
+    AsinFloat(LocalId),
+    AcosFloat(LocalId),
+    AtanFloat(LocalId),
+    Atan2Float(LocalId, LocalId),
+    ExptFloat(LocalId, LocalId),
     WriteChar(LocalId),
     Is(ValType, LocalId),
     VectorLength(LocalId),
@@ -526,6 +626,8 @@ pub enum InstrKind {
     SetCdr(LocalId, LocalId),
     SymbolToString(LocalId),
     IntToString(LocalId),
+    BigIntToString(LocalId),
+    RationalToString(LocalId),
     FloatToString(LocalId),
     EqInt(LocalId, LocalId),
     EqFloat(LocalId, LocalId),
@@ -535,6 +637,23 @@ pub enum InstrKind {
     StringCopy(LocalId),
     StringSet(LocalId, LocalId, LocalId),
     StringLength(LocalId),
+    StringAppend(LocalId, LocalId),       // (String, String) -> String
+    Substring(LocalId, LocalId, LocalId), // (String, Int, Int) -> String: バッファを共有する
+    MakeString(LocalId, LocalId),         // (Int, Char) -> String
+    ListToString(LocalId),                // Obj -> String: 文字の真リストでなければエラー
+    StringToList(LocalId),                // String -> Obj
+    CmpString(LocalId, LocalId),          // (String, String) -> int(-1, 0, 1)
+    CmpStringCi(LocalId, LocalId), // (String, String) -> int(-1, 0, 1): 大文字小文字を区別しない
+    StringToNumber(LocalId, LocalId), // (String, Int) -> Obj: 数値または#f
+    CharToInt(LocalId),            // Char -> Int
+    IntToChar(LocalId),            // Int -> Char
+    CharUpcase(LocalId),           // Char -> Char
+    CharDowncase(LocalId),         // Char -> Char
+    CharAlphabetic(LocalId),       // Char -> Bool
+    CharNumeric(LocalId),          // Char -> Bool
+    CharWhitespace(LocalId),       // Char -> Bool
+    CharUpperCase(LocalId),        // Char -> Bool
+    CharLowerCase(LocalId),        // Char -> Bool
     LtInt(LocalId, LocalId),
     LtFloat(LocalId, LocalId),
     GtInt(LocalId, LocalId),
@@ -543,12 +662,51 @@ pub enum InstrKind {
     LeFloat(LocalId, LocalId),
     GeInt(LocalId, LocalId),
     GeFloat(LocalId, LocalId),
+    MakeErrorObject(LocalId, LocalId), // (String, Obj) -> ErrorObject
+    ErrorObjectMessage(LocalId),       // ErrorObject -> String
+    ErrorObjectIrritants(LocalId),     // ErrorObject -> Obj
+    ErrorObjectKind(LocalId),          // ErrorObject -> Int
+    Raise(LocalId),                    // Obj -> Obj: 戻らない
+    WithRaiseHandler(LocalId, LocalId), // (Closure, Closure) -> Obj
+    OpenInputString(LocalId),          // String -> Port
+    OpenOutputString,                  // () -> Port
+    StandardPort(LocalId),             // Int -> Port
+    GetOutputString(LocalId),          // Port -> String
+    ClosePort(LocalId),                // Port -> Nil
+    IsInputPort(LocalId),              // Port -> Bool
+    IsOutputPort(LocalId),             // Port -> Bool
+    ReadChar(LocalId),                 // Port -> Obj: charまたはEOF
+    PeekChar(LocalId),                 // Port -> Obj: charまたはEOF
+    Read(LocalId),                     // Port -> Obj: データ、EOFまたはエラーオブジェクト
+    EofObject,                         // () -> Obj
+    DisplayPort(LocalId, LocalId),     // (String, Port) -> Nil
+    WriteCharPort(LocalId, LocalId),   // (Char, Port) -> Nil
+    SetCurrentOutputPort(LocalId),     // Port -> Nil
+    Eval(LocalId),                     // Obj -> Obj
+    SetEvalResult(LocalId),            // Obj -> Nil
+    MakeHashTable(LocalId),            // Int -> HashTable: 引数は同値性の種類
+    HashTableRef(LocalId, LocalId, LocalId), // (HashTable, Obj, Obj) -> Obj: なければ第3引数を返す
+    HashTableSet(LocalId, LocalId, LocalId), // (HashTable, Obj, Obj) -> Nil
+    HashTableDelete(LocalId, LocalId), // (HashTable, Obj) -> Nil
+    HashTableCount(LocalId),           // HashTable -> Int
+    HashTableToAlist(LocalId),         // HashTable -> Obj
+    MakeRecord(RecordTypeId, LocalId, usize), // Obj -> Record: 引数はレコード型、usizeはフィールドの数
+    IsRecord(LocalId),                        // Obj -> Bool: レコード型によらない
+    RecordType(LocalId),                      // Obj -> Obj: IsRecordで検査した値のみ渡せる
+    RecordRef(LocalId, usize),                // Record -> Obj
+    RecordSet(LocalId, usize, LocalId),       // (Record, Obj) -> Nil
+    Utf8ToString(LocalId),                    // UVector(U8) -> String
+    StringToUtf8(LocalId),                    // String -> UVector(U8)
+    // 以降の命令でエラーが発生したときにスタックトレースに表示する位置を設定する
+    SourceLoc(SourceLocId),
     VariadicArgs(Vec<LocalId>),
     VariadicArgsRef(LocalId, usize),
     VariadicArgsLength(LocalId),
     // ArgsVariadic(Vec<LocalId>, LocalId<Vector>)
     // VariadicArgs -> Cons | Nil
     VariadicArgsRest(LocalId, usize),
+    // Obj -> VariadicArgs: 真リストでなければエラー
+    ListToVariadicArgs(LocalId),
     CreateMutFuncRef(LocalId),                   // (FuncRef) -> MutFuncRef
     CreateEmptyMutFuncRef,                       // () -> MutFuncRef
     DerefMutFuncRef(LocalId),                    // (MutFuncRef) -> FuncRef
@@ -692,7 +850,39 @@ macro_rules! impl_InstrKind_local_usages {
                         | InstrKind::QuotientInt(a, b)
                         | InstrKind::RemainderInt(a, b)
                         | InstrKind::ModuloInt(a, b)
-                        | InstrKind::DivFloat(a, b) => {
+                        | InstrKind::DivFloat(a, b)
+                        | InstrKind::AddIntOverflow(a, b)
+                        | InstrKind::SubIntOverflow(a, b)
+                        | InstrKind::MulIntOverflow(a, b)
+                        | InstrKind::QuotientIntOverflow(a, b)
+                        | InstrKind::AddInteger(a, b)
+                        | InstrKind::SubInteger(a, b)
+                        | InstrKind::MulInteger(a, b)
+                        | InstrKind::QuotientInteger(a, b)
+                        | InstrKind::RemainderInteger(a, b)
+                        | InstrKind::ModuloInteger(a, b)
+                        | InstrKind::CmpInteger(a, b)
+                        | InstrKind::Rational(a, b)
+                        | InstrKind::AddRational(a, b)
+                        | InstrKind::SubRational(a, b)
+                        | InstrKind::MulRational(a, b)
+                        | InstrKind::DivRational(a, b)
+                        | InstrKind::CmpRational(a, b)
+                        | InstrKind::MinInt(a, b)
+                        | InstrKind::MaxInt(a, b)
+                        | InstrKind::MinFloat(a, b)
+                        | InstrKind::MaxFloat(a, b)
+                        | InstrKind::GcdInt(a, b)
+                        | InstrKind::LcmInt(a, b)
+                        | InstrKind::ExptInt(a, b)
+                        | InstrKind::GcdIntOverflow(a, b)
+                        | InstrKind::LcmIntOverflow(a, b)
+                        | InstrKind::ExptIntOverflow(a, b)
+                        | InstrKind::GcdInteger(a, b)
+                        | InstrKind::LcmInteger(a, b)
+                        | InstrKind::ExptRational(a, b)
+                        | InstrKind::Atan2Float(a, b)
+                        | InstrKind::ExptFloat(a, b) => {
                             yield (a, LocalUsedFlag::NonPhi);
                             yield (b, LocalUsedFlag::NonPhi);
                         }
@@ -743,6 +933,34 @@ macro_rules! impl_InstrKind_local_usages {
                         }
                         InstrKind::SymbolToString(id) => yield (id, LocalUsedFlag::NonPhi),
                         InstrKind::IntToString(id) => yield (id, LocalUsedFlag::NonPhi),
+                        InstrKind::BigIntToString(id) => yield (id, LocalUsedFlag::NonPhi),
+                        InstrKind::RationalToString(id) => yield (id, LocalUsedFlag::NonPhi),
+                        InstrKind::Numerator(id)
+                        | InstrKind::Denominator(id)
+                        | InstrKind::FloorRational(id)
+                        | InstrKind::RoundRational(id)
+                        | InstrKind::RationalToFloat(id)
+                        | InstrKind::FloatToExact(id)
+                        | InstrKind::IntToFloat(id)
+                        | InstrKind::FloorFloat(id)
+                        | InstrKind::RoundFloat(id)
+                        | InstrKind::CeilingRational(id)
+                        | InstrKind::TruncateRational(id)
+                        | InstrKind::CeilingFloat(id)
+                        | InstrKind::TruncateFloat(id)
+                        | InstrKind::AbsInt(id)
+                        | InstrKind::AbsFloat(id)
+                        | InstrKind::ExactIntegerSqrtInt(id)
+                        | InstrKind::ExactIntegerSqrtInteger(id)
+                        | InstrKind::SqrtFloat(id)
+                        | InstrKind::ExpFloat(id)
+                        | InstrKind::LogFloat(id)
+                        | InstrKind::SinFloat(id)
+                        | InstrKind::CosFloat(id)
+                        | InstrKind::TanFloat(id)
+                        | InstrKind::AsinFloat(id)
+                        | InstrKind::AcosFloat(id)
+                        | InstrKind::AtanFloat(id) => yield (id, LocalUsedFlag::NonPhi),
                         InstrKind::FloatToString(id) => yield (id, LocalUsedFlag::NonPhi),
                         InstrKind::StringRef(str_id, index_id) => {
                             yield (str_id, LocalUsedFlag::NonPhi);
@@ -757,6 +975,30 @@ macro_rules! impl_InstrKind_local_usages {
                             yield (char_id, LocalUsedFlag::NonPhi);
                         }
                         InstrKind::StringLength(id) => yield (id, LocalUsedFlag::NonPhi),
+                        InstrKind::Substring(str_id, start_id, end_id) => {
+                            yield (str_id, LocalUsedFlag::NonPhi);
+                            yield (start_id, LocalUsedFlag::NonPhi);
+                            yield (end_id, LocalUsedFlag::NonPhi);
+                        }
+                        InstrKind::StringAppend(a, b)
+                        | InstrKind::MakeString(a, b)
+                        | InstrKind::CmpString(a, b)
+                        | InstrKind::CmpStringCi(a, b)
+                        | InstrKind::StringToNumber(a, b) => {
+                            yield (a, LocalUsedFlag::NonPhi);
+                            yield (b, LocalUsedFlag::NonPhi);
+                        }
+                        InstrKind::ListToString(id)
+                        | InstrKind::StringToList(id)
+                        | InstrKind::CharToInt(id)
+                        | InstrKind::IntToChar(id)
+                        | InstrKind::CharUpcase(id)
+                        | InstrKind::CharDowncase(id)
+                        | InstrKind::CharAlphabetic(id)
+                        | InstrKind::CharNumeric(id)
+                        | InstrKind::CharWhitespace(id)
+                        | InstrKind::CharUpperCase(id)
+                        | InstrKind::CharLowerCase(id) => yield (id, LocalUsedFlag::NonPhi),
                         InstrKind::EqInt(a, b)
                         | InstrKind::EqFloat(a, b)
                         | InstrKind::EqChar(a, b)
@@ -772,6 +1014,60 @@ macro_rules! impl_InstrKind_local_usages {
                             yield (a, LocalUsedFlag::NonPhi);
                             yield (b, LocalUsedFlag::NonPhi);
                         }
+                        InstrKind::MakeErrorObject(message, irritants) => {
+                            yield (message, LocalUsedFlag::NonPhi);
+                            yield (irritants, LocalUsedFlag::NonPhi);
+                        }
+                        InstrKind::ErrorObjectMessage(id) => yield (id, LocalUsedFlag::NonPhi),
+                        InstrKind::ErrorObjectIrritants(id) => yield (id, LocalUsedFlag::NonPhi),
+                        InstrKind::ErrorObjectKind(id) => yield (id, LocalUsedFlag::NonPhi),
+                        InstrKind::Raise(id) => yield (id, LocalUsedFlag::NonPhi),
+                        InstrKind::WithRaiseHandler(thunk, handler) => {
+                            yield (thunk, LocalUsedFlag::NonPhi);
+                            yield (handler, LocalUsedFlag::NonPhi);
+                        }
+                        InstrKind::OpenInputString(id)
+                        | InstrKind::StandardPort(id)
+                        | InstrKind::GetOutputString(id)
+                        | InstrKind::ClosePort(id)
+                        | InstrKind::IsInputPort(id)
+                        | InstrKind::IsOutputPort(id)
+                        | InstrKind::ReadChar(id)
+                        | InstrKind::PeekChar(id)
+                        | InstrKind::Read(id)
+                        | InstrKind::SetCurrentOutputPort(id)
+                        | InstrKind::Eval(id)
+                        | InstrKind::SetEvalResult(id)
+                        | InstrKind::MakeHashTable(id)
+                        | InstrKind::HashTableCount(id)
+                        | InstrKind::HashTableToAlist(id)
+                        | InstrKind::MakeRecord(_, id, _)
+                        | InstrKind::IsRecord(id)
+                        | InstrKind::RecordType(id)
+                        | InstrKind::RecordRef(id, _)
+                        | InstrKind::Utf8ToString(id)
+                        | InstrKind::StringToUtf8(id)
+                        | InstrKind::ListToVariadicArgs(id) => yield (id, LocalUsedFlag::NonPhi),
+                        InstrKind::HashTableRef(table, key, value)
+                        | InstrKind::HashTableSet(table, key, value) => {
+                            yield (table, LocalUsedFlag::NonPhi);
+                            yield (key, LocalUsedFlag::NonPhi);
+                            yield (value, LocalUsedFlag::NonPhi);
+                        }
+                        InstrKind::HashTableDelete(table, key) => {
+                            yield (table, LocalUsedFlag::NonPhi);
+                            yield (key, LocalUsedFlag::NonPhi);
+                        }
+                        InstrKind::RecordSet(record, _, value) => {
+                            yield (record, LocalUsedFlag::NonPhi);
+                            yield (value, LocalUsedFlag::NonPhi);
+                        }
+                        InstrKind::OpenOutputString | InstrKind::EofObject => {}
+                        InstrKind::DisplayPort(val, port) | InstrKind::WriteCharPort(val, port) => {
+                            yield (val, LocalUsedFlag::NonPhi);
+                            yield (port, LocalUsedFlag::NonPhi);
+                        }
+                        InstrKind::SourceLoc(_) => {}
                         InstrKind::VariadicArgs(ids) => {
                             for id in ids {
                                 yield (id, LocalUsedFlag::NonPhi);
@@ -814,6 +1110,9 @@ macro_rules! impl_InstrKind_local_usages {
                             yield (func_id, LocalUsedFlag::NonPhi);
                             yield (env_index, LocalUsedFlag::NonPhi);
                         }
+                        InstrKind::IncrementBBLayoutCounter(counter, ..) => {
+                            yield (counter, LocalUsedFlag::NonPhi);
+                        }
 
 
                         InstrKind::Nop
@@ -822,6 +1121,7 @@ macro_rules! impl_InstrKind_local_usages {
                         | InstrKind::IncrementBranchCounter(..)
                         | InstrKind::Bool(..)
                         | InstrKind::Int(..)
+                        | InstrKind::BigInt(..)
                         | InstrKind::Float(..)
                         | InstrKind::NaN
                         | InstrKind::String(..)
@@ -909,6 +1209,7 @@ impl InstrKind {
             InstrKind::Nop
             | InstrKind::Bool(..)
             | InstrKind::Int(..)
+            | InstrKind::BigInt(..)
             | InstrKind::Float(..)
             | InstrKind::NaN
             | InstrKind::Nil
@@ -951,7 +1252,86 @@ impl InstrKind {
             | InstrKind::QuotientInt(..)
             | InstrKind::RemainderInt(..)
             | InstrKind::ModuloInt(..)
-            | InstrKind::DivFloat(..) => InstrKindPurelity::Pure,
+            | InstrKind::DivFloat(..)
+            | InstrKind::AddIntOverflow(..)
+            | InstrKind::SubIntOverflow(..)
+            | InstrKind::MulIntOverflow(..)
+            | InstrKind::QuotientIntOverflow(..)
+            // bigintは不変なオブジェクトである
+            | InstrKind::AddInteger(..)
+            | InstrKind::SubInteger(..)
+            | InstrKind::MulInteger(..)
+            | InstrKind::QuotientInteger(..)
+            | InstrKind::RemainderInteger(..)
+            | InstrKind::ModuloInteger(..)
+            | InstrKind::CmpInteger(..)
+            // rationalも不変なオブジェクトである
+            | InstrKind::Rational(..)
+            | InstrKind::AddRational(..)
+            | InstrKind::SubRational(..)
+            | InstrKind::MulRational(..)
+            | InstrKind::DivRational(..)
+            | InstrKind::CmpRational(..)
+            | InstrKind::Numerator(..)
+            | InstrKind::Denominator(..)
+            | InstrKind::FloorRational(..)
+            | InstrKind::RoundRational(..)
+            | InstrKind::RationalToFloat(..)
+            | InstrKind::FloatToExact(..)
+            | InstrKind::IntToFloat(..)
+            | InstrKind::FloorFloat(..)
+            | InstrKind::RoundFloat(..)
+            | InstrKind::CeilingRational(..)
+            | InstrKind::TruncateRational(..)
+            | InstrKind::CeilingFloat(..)
+            | InstrKind::TruncateFloat(..)
+            | InstrKind::AbsInt(..)
+            | InstrKind::AbsFloat(..)
+            | InstrKind::MinInt(..)
+            | InstrKind::MaxInt(..)
+            | InstrKind::MinFloat(..)
+            | InstrKind::MaxFloat(..)
+            | InstrKind::GcdInt(..)
+            | InstrKind::LcmInt(..)
+            | InstrKind::ExptInt(..)
+            | InstrKind::GcdIntOverflow(..)
+            | InstrKind::LcmIntOverflow(..)
+            | InstrKind::ExptIntOverflow(..)
+            | InstrKind::GcdInteger(..)
+            | InstrKind::LcmInteger(..)
+            | InstrKind::ExptRational(..)
+            | InstrKind::ExactIntegerSqrtInt(..)
+            | InstrKind::ExactIntegerSqrtInteger(..)
+            | InstrKind::SqrtFloat(..)
+            | InstrKind::ExpFloat(..)
+            | InstrKind::LogFloat(..)
+            | InstrKind::SinFloat(..)
+            | InstrKind::CosFloat(..)
+            | InstrKind::TanFloat(..)
+            | InstrKind::AsinFloat(..)
+            | InstrKind::AcosFloat(..)
+            | InstrKind::AtanFloat(..)
+            | InstrKind::Atan2Float(..)
+            | InstrKind::ExptFloat(..)
+            // error objectのフィールドは不変である
+            | InstrKind::ErrorObjectMessage(..)
+            | InstrKind::ErrorObjectIrritants(..)
+            | InstrKind::ErrorObjectKind(..)
+            // recordの型は不変である
+            | InstrKind::IsRecord(..)
+            | InstrKind::RecordType(..)
+            // portの種類は不変である
+            | InstrKind::IsInputPort(..)
+            | InstrKind::IsOutputPort(..)
+            | InstrKind::EofObject
+            | InstrKind::CharToInt(..)
+            | InstrKind::CharUpcase(..)
+            | InstrKind::CharDowncase(..)
+            | InstrKind::CharAlphabetic(..)
+            | InstrKind::CharNumeric(..)
+            | InstrKind::CharWhitespace(..)
+            | InstrKind::CharUpperCase(..)
+            | InstrKind::CharLowerCase(..) => InstrKindPurelity::Pure,
             // String/Cons/Vectorなどは可変なオブジェクトを生成するので純粋ではない
             InstrKind::String(..)
             | InstrKind::StringToSymbol(..)
@@ -961,10 +1341,24 @@ impl InstrKind {
             | InstrKind::MakeUVector(..)
             | InstrKind::MakeVector(..)
             | InstrKind::Cons(..)
+            | InstrKind::MakeErrorObject(..)
+            | InstrKind::MakeRecord(..)
+            | InstrKind::Utf8ToString(..)
+            | InstrKind::StringToUtf8(..)
+            | InstrKind::OpenInputString(..)
+            | InstrKind::OpenOutputString
+            | InstrKind::StandardPort(..)
+            | InstrKind::GetOutputString(..)
+            | InstrKind::PeekChar(..)
+            | InstrKind::MakeHashTable(..)
+            | InstrKind::HashTableRef(..)
+            | InstrKind::HashTableCount(..)
+            | InstrKind::HashTableToAlist(..)
             | InstrKind::CreateRef(..)
             | InstrKind::DerefRef(..)
             | InstrKind::VectorLength(..)
             | InstrKind::VectorRef(..)
+            | InstrKind::RecordRef(..)
             | InstrKind::UVectorLength(..) // vector/uvectorの長さは不変なのでpureでいいのでは？
             | InstrKind::UVectorRef(..)
             | InstrKind::Car(..)
@@ -973,10 +1367,22 @@ impl InstrKind {
             | InstrKind::GlobalGet(..)
             | InstrKind::SymbolToString(..)
             | InstrKind::IntToString(..)
+            | InstrKind::BigIntToString(..)
+            | InstrKind::RationalToString(..)
             | InstrKind::FloatToString(..)
             | InstrKind::StringRef(..)
             | InstrKind::StringCopy(..)
             | InstrKind::StringLength(..)
+            | InstrKind::StringAppend(..)
+            | InstrKind::Substring(..)
+            | InstrKind::MakeString(..)
+            | InstrKind::ListToString(..)
+            | InstrKind::StringToList(..)
+            | InstrKind::CmpString(..)
+            | InstrKind::CmpStringCi(..)
+            | InstrKind::StringToNumber(..)
+            // 不正なコードポイントはエラーになる
+            | InstrKind::IntToChar(..)
             | InstrKind::CreateMutFuncRef(..)
             | InstrKind::CreateEmptyMutFuncRef
             | InstrKind::DerefMutFuncRef(..)
@@ -990,6 +1396,7 @@ impl InstrKind {
             | InstrKind::InstantiateClosureFunc(..)
             | InstrKind::InstantiateBB(..)
             | InstrKind::IncrementBranchCounter(..)
+            | InstrKind::IncrementBBLayoutCounter(..)
             | InstrKind::SetRef(..)
             | InstrKind::SetMutFuncRef(..)
             | InstrKind::Call(..)
@@ -1000,11 +1407,26 @@ impl InstrKind {
             | InstrKind::WriteChar(..)
             | InstrKind::SetEntrypointTable(..)
             | InstrKind::VectorSet(..)
+            | InstrKind::RecordSet(..)
             | InstrKind::UVectorSet(..)
             | InstrKind::ClosureSetEnv(..)
             | InstrKind::SetCar(..)
             | InstrKind::SetCdr(..)
             | InstrKind::StringSet(..)
+            | InstrKind::Raise(..)
+            | InstrKind::WithRaiseHandler(..)
+            | InstrKind::ClosePort(..)
+            | InstrKind::ReadChar(..)
+            | InstrKind::Read(..)
+            | InstrKind::DisplayPort(..)
+            | InstrKind::WriteCharPort(..)
+            | InstrKind::SetCurrentOutputPort(..)
+            | InstrKind::Eval(..)
+            | InstrKind::SetEvalResult(..)
+            | InstrKind::HashTableSet(..)
+            | InstrKind::HashTableDelete(..)
+            | InstrKind::ListToVariadicArgs(..)
+            | InstrKind::SourceLoc(..)
              => InstrKindPurelity::Effectful,
         }
     }
@@ -1103,8 +1525,32 @@ impl fmt::Display for DisplayInFunc<'_, &'_ InstrKind> {
                     source_index,
                 )
             }
+            InstrKind::IncrementBBLayoutCounter(
+                counter,
+                module_id,
+                func_id,
+                env_index,
+                func_index,
+                bb_id,
+                index,
+                kind,
+            ) => {
+                write!(
+                    f,
+                    "increment_bb_layout_counter({}, {}, {}, {}, {}, {}, {}, {})",
+                    counter.display(self.meta),
+                    module_id.display(self.meta.meta),
+                    func_id.display(self.meta.meta),
+                    env_index,
+                    func_index,
+                    bb_id.display(self.meta.meta),
+                    index,
+                    kind,
+                )
+            }
             InstrKind::Bool(b) => write!(f, "{}", b),
             InstrKind::Int(i) => write!(f, "{}", i),
+            InstrKind::BigInt(i) => write!(f, "bigint({})", i),
             InstrKind::Float(fl) => write!(f, "{}", fl),
             InstrKind::NaN => write!(f, "nan"),
             InstrKind::String(s) => write!(f, "{:?}", s),
@@ -1339,6 +1785,94 @@ impl fmt::Display for DisplayInFunc<'_, &'_ InstrKind> {
                     b.display(self.meta)
                 )
             }
+            InstrKind::AddIntOverflow(a, b) => {
+                write!(
+                    f,
+                    "add_int_overflow({}, {})",
+                    a.display(self.meta),
+                    b.display(self.meta)
+                )
+            }
+            InstrKind::SubIntOverflow(a, b) => {
+                write!(
+                    f,
+                    "sub_int_overflow({}, {})",
+                    a.display(self.meta),
+                    b.display(self.meta)
+                )
+            }
+            InstrKind::MulIntOverflow(a, b) => {
+                write!(
+                    f,
+                    "mul_int_overflow({}, {})",
+                    a.display(self.meta),
+                    b.display(self.meta)
+                )
+            }
+            InstrKind::QuotientIntOverflow(a, b) => {
+                write!(
+                    f,
+                    "quotient_int_overflow({}, {})",
+                    a.display(self.meta),
+                    b.display(self.meta)
+                )
+            }
+            InstrKind::AddInteger(a, b) => {
+                write!(
+                    f,
+                    "add_integer({}, {})",
+                    a.display(self.meta),
+                    b.display(self.meta)
+                )
+            }
+            InstrKind::SubInteger(a, b) => {
+                write!(
+                    f,
+                    "sub_integer({}, {})",
+                    a.display(self.meta),
+                    b.display(self.meta)
+                )
+            }
+            InstrKind::MulInteger(a, b) => {
+                write!(
+                    f,
+                    "mul_integer({}, {})",
+                    a.display(self.meta),
+                    b.display(self.meta)
+                )
+            }
+            InstrKind::QuotientInteger(a, b) => {
+                write!(
+                    f,
+                    "quotient_integer({}, {})",
+                    a.display(self.meta),
+                    b.display(self.meta)
+                )
+            }
+            InstrKind::RemainderInteger(a, b) => {
+                write!(
+                    f,
+                    "remainder_integer({}, {})",
+                    a.display(self.meta),
+                    b.display(self.meta)
+                )
+            }
+            InstrKind::ModuloInteger(a, b) => {
+                write!(
+                    f,
+                    "modulo_integer({}, {})",
+                    a.display(self.meta),
+                    b.display(self.meta)
+                )
+            }
+            InstrKind::CmpInteger(a, b) => {
+                write!(
+                    f,
+                    "cmp_integer({}, {})",
+                    a.display(self.meta),
+                    b.display(self.meta)
+                )
+            }
             InstrKind::WriteChar(id) => write!(f, "write_char({})", id.display(self.meta)),
             InstrKind::Is(typ, id) => write!(f, "is<{}>({})", typ, id.display(self.meta)),
             InstrKind::VectorLength(id) => write!(f, "vector_length({})", id.display(self.meta)),
@@ -1418,7 +1952,187 @@ impl fmt::Display for DisplayInFunc<'_, &'_ InstrKind> {
                 write!(f, "symbol_to_string({})", id.display(self.meta))
             }
             InstrKind::IntToString(id) => write!(f, "int_to_string({})", id.display(self.meta)),
+            InstrKind::BigIntToString(id) => {
+                write!(f, "bigint_to_string({})", id.display(self.meta))
+            }
             InstrKind::FloatToString(id) => write!(f, "float_to_string({})", id.display(self.meta)),
+            InstrKind::RationalToString(id) => {
+                write!(f, "rational_to_string({})", id.display(self.meta))
+            }
+            InstrKind::Numerator(id) => write!(f, "numerator({})", id.display(self.meta)),
+            InstrKind::Denominator(id) => write!(f, "denominator({})", id.display(self.meta)),
+            InstrKind::FloorRational(id) => write!(f, "floor_rational({})", id.display(self.meta)),
+            InstrKind::RoundRational(id) => write!(f, "round_rational({})", id.display(self.meta)),
+            InstrKind::RationalToFloat(id) => {
+                write!(f, "rational_to_float({})", id.display(self.meta))
+            }
+            InstrKind::FloatToExact(id) => write!(f, "float_to_exact({})", id.display(self.meta)),
+            InstrKind::IntToFloat(id) => write!(f, "int_to_float({})", id.display(self.meta)),
+            InstrKind::FloorFloat(id) => write!(f, "floor_float({})", id.display(self.meta)),
+            InstrKind::RoundFloat(id) => write!(f, "round_float({})", id.display(self.meta)),
+            InstrKind::CeilingRational(id) => {
+                write!(f, "ceiling_rational({})", id.display(self.meta))
+            }
+            InstrKind::TruncateRational(id) => {
+                write!(f, "truncate_rational({})", id.display(self.meta))
+            }
+            InstrKind::CeilingFloat(id) => write!(f, "ceiling_float({})", id.display(self.meta)),
+            InstrKind::TruncateFloat(id) => write!(f, "truncate_float({})", id.display(self.meta)),
+            InstrKind::AbsInt(id) => write!(f, "abs_int({})", id.display(self.meta)),
+            InstrKind::AbsFloat(id) => write!(f, "abs_float({})", id.display(self.meta)),
+            InstrKind::ExactIntegerSqrtInt(id) => {
+                write!(f, "exact_integer_sqrt_int({})", id.display(self.meta))
+            }
+            InstrKind::ExactIntegerSqrtInteger(id) => {
+                write!(f, "exact_integer_sqrt_integer({})", id.display(self.meta))
+            }
+            InstrKind::SqrtFloat(id) => write!(f, "sqrt_float({})", id.display(self.meta)),
+            InstrKind::ExpFloat(id) => write!(f, "exp_float({})", id.display(self.meta)),
+            InstrKind::LogFloat(id) => write!(f, "log_float({})", id.display(self.meta)),
+            InstrKind::SinFloat(id) => write!(f, "sin_float({})", id.display(self.meta)),
+            InstrKind::CosFloat(id) => write!(f, "cos_float({})", id.display(self.meta)),
+            InstrKind::TanFloat(id) => write!(f, "tan_float({})", id.display(self.meta)),
+            InstrKind::AsinFloat(id) => write!(f, "asin_float({})", id.display(self.meta)),
+            InstrKind::AcosFloat(id) => write!(f, "acos_float({})", id.display(self.meta)),
+            InstrKind::AtanFloat(id) => write!(f, "atan_float({})", id.display(self.meta)),
+            InstrKind::MinInt(a, b) => write!(
+                f,
+                "min_int({}, {})",
+                a.display(self.meta),
+                b.display(self.meta)
+            ),
+            InstrKind::MaxInt(a, b) => write!(
+                f,
+                "max_int({}, {})",
+                a.display(self.meta),
+                b.display(self.meta)
+            ),
+            InstrKind::MinFloat(a, b) => write!(
+                f,
+                "min_float({}, {})",
+                a.display(self.meta),
+                b.display(self.meta)
+            ),
+            InstrKind::MaxFloat(a, b) => write!(
+                f,
+                "max_float({}, {})",
+                a.display(self.meta),
+                b.display(self.meta)
+            ),
+            InstrKind::GcdInt(a, b) => write!(
+                f,
+                "gcd_int({}, {})",
+                a.display(self.meta),
+                b.display(self.meta)
+            ),
+            InstrKind::LcmInt(a, b) => write!(
+                f,
+                "lcm_int({}, {})",
+                a.display(self.meta),
+                b.display(self.meta)
+            ),
+            InstrKind::ExptInt(a, b) => write!(
+                f,
+                "expt_int({}, {})",
+                a.display(self.meta),
+                b.display(self.meta)
+            ),
+            InstrKind::GcdIntOverflow(a, b) => write!(
+                f,
+                "gcd_int_overflow({}, {})",
+                a.display(self.meta),
+                b.display(self.meta)
+            ),
+            InstrKind::LcmIntOverflow(a, b) => write!(
+                f,
+                "lcm_int_overflow({}, {})",
+                a.display(self.meta),
+                b.display(self.meta)
+            ),
+            InstrKind::ExptIntOverflow(a, b) => write!(
+                f,
+                "expt_int_overflow({}, {})",
+                a.display(self.meta),
+                b.display(self.meta)
+            ),
+            InstrKind::GcdInteger(a, b) => write!(
+                f,
+                "gcd_integer({}, {})",
+                a.display(self.meta),
+                b.display(self.meta)
+            ),
+            InstrKind::LcmInteger(a, b) => write!(
+                f,
+                "lcm_integer({}, {})",
+                a.display(self.meta),
+                b.display(self.meta)
+            ),
+            InstrKind::ExptRational(a, b) => write!(
+                f,
+                "expt_rational({}, {})",
+                a.display(self.meta),
+                b.display(self.meta)
+            ),
+            InstrKind::Atan2Float(a, b) => write!(
+                f,
+                "atan2_float({}, {})",
+                a.display(self.meta),
+                b.display(self.meta)
+            ),
+            InstrKind::ExptFloat(a, b) => write!(
+                f,
+                "expt_float({}, {})",
+                a.display(self.meta),
+                b.display(self.meta)
+            ),
+            InstrKind::Rational(a, b) => {
+                write!(
+                    f,
+                    "rational({}, {})",
+                    a.display(self.meta),
+                    b.display(self.meta)
+                )
+            }
+            InstrKind::AddRational(a, b) => {
+                write!(
+                    f,
+                    "add_rational({}, {})",
+                    a.display(self.meta),
+                    b.display(self.meta)
+                )
+            }
+            InstrKind::SubRational(a, b) => {
+                write!(
+                    f,
+                    "sub_rational({}, {})",
+                    a.display(self.meta),
+                    b.display(self.meta)
+                )
+            }
+            InstrKind::MulRational(a, b) => {
+                write!(
+                    f,
+                    "mul_rational({}, {})",
+                    a.display(self.meta),
+                    b.display(self.meta)
+                )
+            }
+            InstrKind::DivRational(a, b) => {
+                write!(
+                    f,
+                    "div_rational({}, {})",
+                    a.display(self.meta),
+                    b.display(self.meta)
+                )
+            }
+            InstrKind::CmpRational(a, b) => {
+                write!(
+                    f,
+                    "cmp_rational({}, {})",
+                    a.display(self.meta),
+                    b.display(self.meta)
+                )
+            }
             InstrKind::StringRef(str_id, index_id) => {
                 write!(
                     f,
@@ -1433,6 +2147,60 @@ impl fmt::Display for DisplayInFunc<'_, &'_ InstrKind> {
             InstrKind::StringLength(id) => {
                 write!(f, "string_length({})", id.display(self.meta))
             }
+            InstrKind::StringAppend(a, b) => write!(
+                f,
+                "string_append({}, {})",
+                a.display(self.meta),
+                b.display(self.meta)
+            ),
+            InstrKind::Substring(str_id, start_id, end_id) => {
+                write!(
+                    f,
+                    "substring({}, {}, {})",
+                    str_id.display(self.meta),
+                    start_id.display(self.meta),
+                    end_id.display(self.meta)
+                )
+            }
+            InstrKind::MakeString(a, b) => write!(
+                f,
+                "make_string({}, {})",
+                a.display(self.meta),
+                b.display(self.meta)
+            ),
+            InstrKind::ListToString(id) => write!(f, "list_to_string({})", id.display(self.meta)),
+            InstrKind::StringToList(id) => write!(f, "string_to_list({})", id.display(self.meta)),
+            InstrKind::CmpString(a, b) => write!(
+                f,
+                "cmp_string({}, {})",
+                a.display(self.meta),
+                b.display(self.meta)
+            ),
+            InstrKind::CmpStringCi(a, b) => write!(
+                f,
+                "cmp_string_ci({}, {})",
+                a.display(self.meta),
+                b.display(self.meta)
+            ),
+            InstrKind::StringToNumber(a, b) => write!(
+                f,
+                "string_to_number({}, {})",
+                a.display(self.meta),
+                b.display(self.meta)
+            ),
+            InstrKind::CharToInt(id) => write!(f, "char_to_int({})", id.display(self.meta)),
+            InstrKind::IntToChar(id) => write!(f, "int_to_char({})", id.display(self.meta)),
+            InstrKind::CharUpcase(id) => write!(f, "char_upcase({})", id.display(self.meta)),
+            InstrKind::CharDowncase(id) => write!(f, "char_downcase({})", id.display(self.meta)),
+            InstrKind::CharAlphabetic(id) => {
+                write!(f, "char_alphabetic({})", id.display(self.meta))
+            }
+            InstrKind::CharNumeric(id) => write!(f, "char_numeric({})", id.display(self.meta)),
+            InstrKind::CharWhitespace(id) => {
+                write!(f, "char_whitespace({})", id.display(self.meta))
+            }
+            InstrKind::CharUpperCase(id) => write!(f, "char_upper_case({})", id.display(self.meta)),
+            InstrKind::CharLowerCase(id) => write!(f, "char_lower_case({})", id.display(self.meta)),
             InstrKind::StringSet(str_id, index_id, char_id) => {
                 write!(
                     f,
@@ -1530,6 +2298,138 @@ impl fmt::Display for DisplayInFunc<'_, &'_ InstrKind> {
                     b.display(self.meta)
                 )
             }
+            InstrKind::MakeErrorObject(message, irritants) => {
+                write!(
+                    f,
+                    "make_error_object({}, {})",
+                    message.display(self.meta),
+                    irritants.display(self.meta)
+                )
+            }
+            InstrKind::ErrorObjectMessage(id) => {
+                write!(f, "error_object_message({})", id.display(self.meta))
+            }
+            InstrKind::ErrorObjectIrritants(id) => {
+                write!(f, "error_object_irritants({})", id.display(self.meta))
+            }
+            InstrKind::Raise(id) => write!(f, "raise({})", id.display(self.meta)),
+            InstrKind::WithRaiseHandler(thunk, handler) => {
+                write!(
+                    f,
+                    "with_raise_handler({}, {})",
+                    thunk.display(self.meta),
+                    handler.display(self.meta)
+                )
+            }
+            InstrKind::ErrorObjectKind(id) => {
+                write!(f, "error_object_kind({})", id.display(self.meta))
+            }
+            InstrKind::OpenInputString(id) => {
+                write!(f, "open_input_string({})", id.display(self.meta))
+            }
+            InstrKind::OpenOutputString => write!(f, "open_output_string()"),
+            InstrKind::StandardPort(id) => write!(f, "standard_port({})", id.display(self.meta)),
+            InstrKind::GetOutputString(id) => {
+                write!(f, "get_output_string({})", id.display(self.meta))
+            }
+            InstrKind::ClosePort(id) => write!(f, "close_port({})", id.display(self.meta)),
+            InstrKind::IsInputPort(id) => write!(f, "is_input_port({})", id.display(self.meta)),
+            InstrKind::IsOutputPort(id) => {
+                write!(f, "is_output_port({})", id.display(self.meta))
+            }
+            InstrKind::ReadChar(id) => write!(f, "read_char({})", id.display(self.meta)),
+            InstrKind::PeekChar(id) => write!(f, "peek_char({})", id.display(self.meta)),
+            InstrKind::Read(id) => write!(f, "read({})", id.display(self.meta)),
+            InstrKind::EofObject => write!(f, "eof_object()"),
+            InstrKind::Eval(id) => write!(f, "eval({})", id.display(self.meta)),
+            InstrKind::SetEvalResult(id) => {
+                write!(f, "set_eval_result({})", id.display(self.meta))
+            }
+            InstrKind::MakeHashTable(kind) => {
+                write!(f, "make_hash_table({})", kind.display(self.meta))
+            }
+            InstrKind::HashTableRef(table, key, default) => {
+                write!(
+                    f,
+                    "hash_table_ref({}, {}, {})",
+                    table.display(self.meta),
+                    key.display(self.meta),
+                    default.display(self.meta)
+                )
+            }
+            InstrKind::HashTableSet(table, key, value) => {
+                write!(
+                    f,
+                    "hash_table_set({}, {}, {})",
+                    table.display(self.meta),
+                    key.display(self.meta),
+                    value.display(self.meta)
+                )
+            }
+            InstrKind::HashTableDelete(table, key) => {
+                write!(
+                    f,
+                    "hash_table_delete({}, {})",
+                    table.display(self.meta),
+                    key.display(self.meta)
+                )
+            }
+            InstrKind::HashTableCount(table) => {
+                write!(f, "hash_table_count({})", table.display(self.meta))
+            }
+            InstrKind::HashTableToAlist(table) => {
+                write!(f, "hash_table_to_alist({})", table.display(self.meta))
+            }
+            InstrKind::MakeRecord(record_type_id, typ, size) => {
+                write!(
+                    f,
+                    "make_record<{}>({}, {})",
+                    record_type_id,
+                    typ.display(self.meta),
+                    size
+                )
+            }
+            InstrKind::IsRecord(id) => write!(f, "is_record({})", id.display(self.meta)),
+            InstrKind::RecordType(id) => write!(f, "record_type({})", id.display(self.meta)),
+            InstrKind::RecordRef(id, index) => {
+                write!(f, "record_ref({}, {})", id.display(self.meta), index)
+            }
+            InstrKind::RecordSet(id, index, value) => {
+                write!(
+                    f,
+                    "record_set({}, {}, {})",
+                    id.display(self.meta),
+                    index,
+                    value.display(self.meta)
+                )
+            }
+            InstrKind::Utf8ToString(id) => write!(f, "utf8_to_string({})", id.display(self.meta)),
+            InstrKind::StringToUtf8(id) => write!(f, "string_to_utf8({})", id.display(self.meta)),
+            InstrKind::ListToVariadicArgs(id) => {
+                write!(f, "list_to_variadic_args({})", id.display(self.meta))
+            }
+            InstrKind::DisplayPort(val, port) => {
+                write!(
+                    f,
+                    "display_port({}, {})",
+                    val.display(self.meta),
+                    port.display(self.meta)
+                )
+            }
+            InstrKind::WriteCharPort(val, port) => {
+                write!(
+                    f,
+                    "write_char_port({}, {})",
+                    val.display(self.meta),
+                    port.display(self.meta)
+                )
+            }
+            InstrKind::SetCurrentOutputPort(id) => {
+                write!(f, "set_current_output_port({})", id.display(self.meta))
+            }
+            InstrKind::SourceLoc(id) => {
+                write!(f, "source_loc({})", id.display(self.meta.meta))
+            }
             InstrKind::VariadicArgs(ids) => {
                 write!(f, "variadic_args(")?;
                 for (i, id) in ids.iter().enumerate() {
@@ -362,6 +362,8 @@ impl Module {
             meta: Meta {
                 local_metas: FxHashMap::default(),
                 global_metas: FxHashMap::default(),
+                func_metas: FxHashMap::default(),
+                source_locs: FxHashMap::default(),
             },
         }
     }
@@ -381,6 +383,52 @@ impl Module {
 
         entry_func.extend_entry_bb(f);
     }
+
+    // otherの関数を全てこのモジュールに移し、otherのエントリー関数の新しいIDを返す
+    // どちらかでexportされているグローバルはexportとして扱う
+    pub fn merge(&mut self, other: Module) -> FuncId {
+        let mut func_ids = FxHashMap::default();
+        for func_id in other.funcs.keys() {
+            func_ids.insert(func_id, self.funcs.allocate_key());
+        }
+
+        for (_, mut func) in other.funcs {
+            func.id = func_ids[&func.id];
+            for bb in func.bbs.values_mut() {
+                for instr in bb.instrs.iter_mut() {
+                    for func_id in instr.kind.func_ids_mut() {
+                        *func_id = func_ids[func_id];
+                    }
+                    if let InstrKind::Terminator(terminator) = &mut instr.kind {
+                        for func_id in terminator.func_ids_mut() {
+                            *func_id = func_ids[func_id];
+                        }
+                    }
+                }
+            }
+            self.funcs.insert_node(func);
+        }
+
+        for (global_id, global) in other.globals {
+            let entry = self.globals.entry(global_id).or_insert(global);
+            if global.linkage == GlobalLinkage::Export {
+                entry.linkage = GlobalLinkage::Export;
+            }
+        }
+
+        for (func_id, func_meta) in other.meta.func_metas {
+            self.meta.func_metas.insert(func_ids[&func_id], func_meta);
+        }
+        for ((func_id, local_id), var_meta) in other.meta.local_metas {
+            self.meta
+                .local_metas
+                .insert((func_ids[&func_id], local_id), var_meta);
+        }
+        self.meta.global_metas.extend(other.meta.global_metas);
+        self.meta.source_locs.extend(other.meta.source_locs);
+
+        func_ids[&other.entry]
+    }
 }
 impl fmt::Display for Display<'_, &'_ Module> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -1,14 +1,31 @@
 use super::id::*;
 use rustc_hash::FxHashMap;
+use webschembly_compiler_locate::Span;
 
 #[derive(Debug, Clone)]
 pub struct VarMeta {
     pub name: String,
 }
+
+#[derive(Debug, Clone)]
+pub struct FuncMeta {
+    pub name: String,
+}
+
+// ランタイムエラー時のスタックトレースに表示する位置
+#[derive(Debug, Clone)]
+pub struct SourceLoc {
+    pub func_name: String,
+    pub file_name: Option<String>,
+    pub span: Span,
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct Meta {
     pub local_metas: FxHashMap<(FuncId, LocalId), VarMeta>,
     pub global_metas: FxHashMap<GlobalId, VarMeta>,
+    pub func_metas: FxHashMap<FuncId, FuncMeta>,
+    pub source_locs: FxHashMap<SourceLocId, SourceLoc>,
 }
 
 impl Meta {
@@ -40,7 +40,17 @@ impl LocalType {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Copy, derive_more::Display)]
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    Eq,
+    Hash,
+    Copy,
+    derive_more::Display,
+    serde::Serialize,
+    serde::Deserialize,
+)]
 pub enum Type {
     #[display("obj")]
     Obj,
@@ -65,7 +75,17 @@ impl From<ValType> for Type {
 
 // Objにアップキャスト可能な型
 // 基本的にSchemeの型に対応するがFuncRefなど例外もある
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Copy, derive_more::Display)]
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    Eq,
+    Hash,
+    Copy,
+    derive_more::Display,
+    serde::Serialize,
+    serde::Deserialize,
+)]
 pub enum ValType {
     #[display("nil")]
     Nil,
@@ -75,6 +95,10 @@ pub enum ValType {
     Char,
     #[display("int")]
     Int,
+    #[display("bigint")]
+    BigInt,
+    #[display("rational")]
+    Rational,
     #[display("float")]
     Float,
     #[display("string")]
@@ -89,21 +113,87 @@ pub enum ValType {
     UVector(UVectorKind),
     #[display("closure")]
     Closure,
+    #[display("error_object")]
+    ErrorObject,
+    #[display("port")]
+    Port,
+    #[display("hash_table")]
+    HashTable,
+    #[display("record<{0}>", _0)]
+    Record(RecordTypeId),
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Copy, derive_more::Display)]
+// define-record-typeの式ごとに、マクロ展開時に割り当てられる
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    Eq,
+    Hash,
+    Copy,
+    derive_more::Display,
+    derive_more::From,
+    derive_more::Into,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+pub struct RecordTypeId(usize);
+
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    Eq,
+    Hash,
+    Copy,
+    derive_more::Display,
+    serde::Serialize,
+    serde::Deserialize,
+)]
 pub enum UVectorKind {
     #[display("s64")]
     S64,
     #[display("f64")]
     F64,
+    #[display("u8")]
+    U8,
+    #[display("s8")]
+    S8,
+    #[display("u16")]
+    U16,
+    #[display("s16")]
+    S16,
+    #[display("u32")]
+    U32,
+    #[display("s32")]
+    S32,
+    #[display("f32")]
+    F32,
 }
 
 impl UVectorKind {
+    pub const ALL: [UVectorKind; 9] = [
+        UVectorKind::S64,
+        UVectorKind::F64,
+        UVectorKind::U8,
+        UVectorKind::S8,
+        UVectorKind::U16,
+        UVectorKind::S16,
+        UVectorKind::U32,
+        UVectorKind::S32,
+        UVectorKind::F32,
+    ];
+
     pub fn element_type(&self) -> ValType {
         match self {
-            UVectorKind::S64 => ValType::Int,
-            UVectorKind::F64 => ValType::Float,
+            UVectorKind::S64
+            | UVectorKind::U8
+            | UVectorKind::S8
+            | UVectorKind::U16
+            | UVectorKind::S16
+            | UVectorKind::U32
+            | UVectorKind::S32 => ValType::Int,
+            UVectorKind::F64 | UVectorKind::F32 => ValType::Float,
         }
     }
 }
@@ -10,9 +10,12 @@ use nom::{
     error::{ErrorKind, FromExternalError, ParseError, VerboseError, VerboseErrorKind},
     multi::many0,
 };
+use num_bigint::BigInt;
+use num_rational::BigRational;
 use ordered_float::NotNan;
-use std::fmt::Write;
-use webschembly_compiler_error::CompilerError;
+use webschembly_compiler_error::{CompilerError, Diagnostic, ErrorCode};
+use webschembly_compiler_locate::{Pos, Span};
+use webschembly_compiler_sexpr::SUVectorKind;
 mod error;
 mod located;
 pub use located::LocatedStr;
@@ -38,12 +41,56 @@ fn dot<'a, E: ErrorBound<'a>>(input: LocatedStr<'a>) -> IResult<LocatedStr<'a>,
     }
 }
 
+// `...` はsyntax-rulesで使うので識別子として扱う
+fn is_number_like(ident: &str) -> bool {
+    ident != "..." && ident.starts_with(|c: char| c.is_ascii_digit() || c == '.')
+}
+
+fn is_digits(s: &str) -> bool {
+    !s.is_empty() && s.chars().all(|c| c.is_ascii_digit())
+}
+
+fn integer_token(digits: String) -> TokenKind {
+    if let Ok(int) = digits.parse::<i64>() {
+        TokenKind::Int(int)
+    } else {
+        TokenKind::BigInt(digits)
+    }
+}
+
 fn number<'a, E: ErrorBound<'a>>(input: LocatedStr<'a>) -> IResult<LocatedStr<'a>, TokenKind, E> {
     let (input, sign) = alt((value(1, tag("+")), value(-1, tag("-")), success(1)))(input)?;
     let (input, ident) = identifier_like(input)?;
-    if ident.starts_with(|c: char| c.is_ascii_digit() || c == '.') {
-        if let Ok(int) = ident.parse::<i64>() {
-            Ok((input, TokenKind::Int(sign * int)))
+    if is_number_like(ident) {
+        if ident.chars().all(|c| c.is_ascii_digit()) {
+            let digits = if sign < 0 {
+                format!("-{}", ident)
+            } else {
+                ident.to_string()
+            };
+            Ok((input, integer_token(digits)))
+        } else if let Some((num, den)) = ident.split_once('/')
+            && is_digits(num)
+            && is_digits(den)
+        {
+            let num = BigInt::parse_bytes(num.as_bytes(), 10).unwrap() * sign;
+            let den = BigInt::parse_bytes(den.as_bytes(), 10).unwrap();
+            if den == BigInt::ZERO {
+                return Err(nom::Err::Failure(E::from_error_kind(
+                    input,
+                    ErrorKind::Digit,
+                )));
+            }
+            // 既約分数に正規化し、分母が1なら整数として扱う
+            let r = BigRational::new(num, den);
+            if r.is_integer() {
+                Ok((input, integer_token(r.numer().to_string())))
+            } else {
+                Ok((
+                    input,
+                    TokenKind::Rational(r.numer().to_string(), r.denom().to_string()),
+                ))
+            }
         } else if let Ok(float) = ident.parse::<f64>() {
             if let Ok(not_nan) = NotNan::new(sign as f64 * float) {
                 Ok((input, TokenKind::Float(not_nan)))
@@ -65,7 +112,7 @@ fn identifier<'a, E: ErrorBound<'a>>(
     input: LocatedStr<'a>,
 ) -> IResult<LocatedStr<'a>, TokenKind, E> {
     let (input, ident) = identifier_like(input)?;
-    if !ident.starts_with(|c: char| c.is_ascii_digit() || c == '.') {
+    if !is_number_like(ident) {
         Ok((input, TokenKind::Identifier(ident.to_string())))
     } else {
         Err(nom::Err::Error(E::from_error_kind(input, ErrorKind::Digit)))
@@ -109,6 +156,26 @@ fn directive<'a, E: ErrorBound<'a>>(
     Ok((input, TokenKind::Directive(ident.to_string())))
 }
 
+fn uvector_open_paren<'a, E: ErrorBound<'a>>(
+    input: LocatedStr<'a>,
+) -> IResult<LocatedStr<'a>, TokenKind, E> {
+    let (input, _) = tag("#")(input)?;
+    let (input, kind) = alt((
+        tag("s64").map(|_| SUVectorKind::S64),
+        tag("f64").map(|_| SUVectorKind::F64),
+        tag("u8").map(|_| SUVectorKind::U8),
+        tag("s8").map(|_| SUVectorKind::S8),
+        tag("u16").map(|_| SUVectorKind::U16),
+        tag("s16").map(|_| SUVectorKind::S16),
+        tag("u32").map(|_| SUVectorKind::U32),
+        tag("s32").map(|_| SUVectorKind::S32),
+        tag("f32").map(|_| SUVectorKind::F32),
+    ))
+    .parse(input)?;
+    let (input, _) = tag("(")(input)?;
+    Ok((input, TokenKind::UVectorOpenParen(kind)))
+}
+
 fn token_kind<'a, E: ErrorBound<'a>>(
     input: LocatedStr<'a>,
 ) -> IResult<LocatedStr<'a>, TokenKind, E> {
@@ -116,8 +183,7 @@ fn token_kind<'a, E: ErrorBound<'a>>(
         tag("(").map(|_| TokenKind::OpenParen),
         tag(")").map(|_| TokenKind::CloseParen),
         tag("#(").map(|_| TokenKind::VectorOpenParen),
-        tag("#s64(").map(|_| TokenKind::UVectorS64OpenParen),
-        tag("#f64(").map(|_| TokenKind::UVectorF64OpenParen),
+        uvector_open_paren,
         tag("#t").map(|_| TokenKind::Bool(true)),
         tag("#f").map(|_| TokenKind::Bool(false)),
         tag("'").map(|_| TokenKind::Quote),
@@ -182,44 +248,37 @@ fn tokens<'a, E: ErrorBound<'a>>(input: LocatedStr<'a>) -> IResult<LocatedStr<'a
 }
 
 fn convert_error(e: VerboseError<LocatedStr>) -> CompilerError {
-    let mut result = String::new();
+    // 最初のエラーを主、残り(context等)を注記として扱う
+    let mut diagnostic: Option<Diagnostic> = None;
 
     for (substring, kind) in e.errors.iter() {
         let pos = to_pos(substring);
 
-        match kind {
+        let message = match kind {
             VerboseErrorKind::Char(c) => {
                 if let Some(actual) = substring.chars().next() {
-                    writeln!(
-                        &mut result,
-                        "{pos}: expected '{expected}', found {actual}",
-                        pos = pos,
-                        expected = c,
-                        actual = actual,
-                    )
+                    format!("expected '{}', found {}", c, actual)
                 } else {
-                    writeln!(
-                        &mut result,
-                        "{pos}: expected '{expected}', got end of input",
-                        pos = pos,
-                        expected = c,
-                    )
+                    format!("expected '{}', got end of input", c)
                 }
             }
-            VerboseErrorKind::Context(s) => {
-                writeln!(&mut result, "{pos}, in {context}:", pos = pos, context = s,)
-            }
-            VerboseErrorKind::Nom(e) => writeln!(
-                &mut result,
-                "{pos}, in {nom_err:?}:",
-                pos = pos,
-                nom_err = e,
-            ),
-        }
-        .unwrap();
+            VerboseErrorKind::Context(s) => format!("in {}", s),
+            VerboseErrorKind::Nom(_) => match substring.chars().next() {
+                Some(actual) => format!("unexpected character '{}'", actual),
+                None => "unexpected end of input".to_string(),
+            },
+        };
+
+        diagnostic = Some(match diagnostic {
+            None => Diagnostic::error(ErrorCode::Lex, message)
+                .with_primary(Span::new(pos, Pos::new(pos.line, pos.column + 1)), ""),
+            Some(diagnostic) => diagnostic.with_note(format!("{} at {}", message, pos)),
+        });
     }
 
-    CompilerError(result)
+    CompilerError::from(
+        diagnostic.unwrap_or_else(|| Diagnostic::error(ErrorCode::Lex, "invalid token")),
+    )
 }
 
 pub fn lex(input: &str) -> Result<Vec<Token>, CompilerError> {
@@ -231,6 +290,56 @@ pub fn lex(input: &str) -> Result<Vec<Token>, CompilerError> {
     Ok(tokens)
 }
 
+// 先頭のデータ1つ分だけ字句解析する。末尾にはEofトークンを付ける
+// 後ろの入力は読まないので、そこに字句エラーがあっても先頭のデータは読める
+pub fn lex_datum(input: &str) -> Result<Vec<Token>, CompilerError> {
+    let mut input = LocatedStr::new(input);
+    let mut tokens = Vec::new();
+    let mut depth = 0usize;
+    loop {
+        let (rest, token) = match token::<VerboseError<_>>(input).finish() {
+            Ok(result) => result,
+            Err(err) => match eof::<VerboseError<_>>(input).finish() {
+                Ok((_, eof_token)) => {
+                    tokens.push(eof_token);
+                    return Ok(tokens);
+                }
+                Err(_) => return Err(convert_error(err)),
+            },
+        };
+        input = rest;
+        let done = match token.kind {
+            TokenKind::OpenParen | TokenKind::VectorOpenParen | TokenKind::UVectorOpenParen(_) => {
+                depth += 1;
+                false
+            }
+            // 対応しない閉じ括弧はパーサーがエラーにする
+            TokenKind::CloseParen => match depth.checked_sub(1) {
+                Some(d) => {
+                    depth = d;
+                    depth == 0
+                }
+                None => true,
+            },
+            // クォートは後続のデータと合わせて1つのデータになる
+            TokenKind::Quote => false,
+            _ => depth == 0,
+        };
+        tokens.push(token);
+        if done {
+            let (_, (pos, _)) = consumed(success::<_, _, VerboseError<_>>(()))
+                .parse(input)
+                .finish()
+                .unwrap();
+            tokens.push(Token {
+                kind: TokenKind::Eof,
+                span: to_span(&pos),
+            });
+            return Ok(tokens);
+        }
+    }
+}
+
 #[test]
 fn test_snapshot_lex() {
     use insta::assert_debug_snapshot;
@@ -243,6 +352,11 @@ fn test_snapshot_lex() {
     assert_debug_snapshot!(lex("#\\\n"));
     assert_debug_snapshot!(lex("#\\newline"));
     assert_debug_snapshot!(lex("#\\nEwLine"));
+    assert_debug_snapshot!(lex("(a ...)"));
+    assert_debug_snapshot!(lex(
+        "9223372036854775807 9223372036854775808 -9223372036854775809"
+    ));
+    assert_debug_snapshot!(lex("1/3 -2/4 6/3 0/5 1/18446744073709551616"));
 }
 
 #[test]
@@ -253,4 +367,37 @@ fn test_lex() {
     assert!(lex("#\\").is_err());
     assert!(lex("#\\abc").is_err());
     assert!(lex("#\\.123").is_err());
+    assert!(lex("1/0").is_err());
+    assert!(lex("1/").is_err());
+    assert!(lex("1/2/3").is_err());
+}
+
+#[test]
+fn test_lex_datum() {
+    let kinds = |input| {
+        lex_datum(input)
+            .unwrap()
+            .into_iter()
+            .map(|token| token.kind)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        kinds("(a) b"),
+        vec![
+            TokenKind::OpenParen,
+            TokenKind::Identifier("a".to_string()),
+            TokenKind::CloseParen,
+            TokenKind::Eof
+        ]
+    );
+    assert_eq!(
+        kinds("'a #\\abc"),
+        vec![
+            TokenKind::Quote,
+            TokenKind::Identifier("a".to_string()),
+            TokenKind::Eof
+        ]
+    );
+    assert_eq!(kinds("  ; comment\n"), vec![TokenKind::Eof]);
+    assert!(lex_datum("(a #\\abc)").is_err());
 }
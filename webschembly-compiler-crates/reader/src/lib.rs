@@ -0,0 +1,7 @@
+#![feature(trait_alias)]
+
+pub mod lexer;
+pub mod parser_combinator;
+pub mod sexpr_parser;
+pub mod token;
+pub mod tokens;
@@ -1,13 +1,14 @@
 use crate::token::TokenKind;
 
 use super::token::Token;
-use webschembly_compiler_sexpr::{Cons, LSExpr, SExpr, SUVectorKind, list};
+use webschembly_compiler_sexpr::{Cons, LSExpr, SExpr, list};
 
 use super::parser_combinator::{satisfy, satisfy_map_opt};
 use crate::tokens::Tokens;
 use nom::combinator::opt;
 use nom::sequence::preceded;
 use nom::{IResult, Parser, branch::alt, multi::many0};
+use webschembly_compiler_error::{CompilerError, Diagnostic, ErrorCode, compiler_error};
 
 fn bool(input: Tokens) -> IResult<Tokens, LSExpr> {
     satisfy_map_opt(|t: &Token| match &t.kind {
@@ -31,6 +32,28 @@ fn int(input: Tokens) -> IResult<Tokens, LSExpr> {
     .parse(input)
 }
 
+fn bigint(input: Tokens) -> IResult<Tokens, LSExpr> {
+    satisfy_map_opt(|t: &Token| match &t.kind {
+        TokenKind::BigInt(i) => Some(LSExpr {
+            value: SExpr::BigInt(i.clone()),
+            span: t.span,
+        }),
+        _ => None,
+    })
+    .parse(input)
+}
+
+fn rational(input: Tokens) -> IResult<Tokens, LSExpr> {
+    satisfy_map_opt(|t: &Token| match &t.kind {
+        TokenKind::Rational(n, d) => Some(LSExpr {
+            value: SExpr::Rational(n.clone(), d.clone()),
+            span: t.span,
+        }),
+        _ => None,
+    })
+    .parse(input)
+}
+
 fn float(input: Tokens) -> IResult<Tokens, LSExpr> {
     satisfy_map_opt(|t: &Token| match &t.kind {
         TokenKind::Float(f) => Some(LSExpr {
@@ -101,12 +124,10 @@ fn vector(input: Tokens) -> IResult<Tokens, LSExpr> {
 }
 
 fn uvector(input: Tokens) -> IResult<Tokens, LSExpr> {
-    let (input, (open_token, kind)) = alt((
-        satisfy(|t: &Token| t.kind == TokenKind::UVectorS64OpenParen)
-            .map(|open_token| (open_token, SUVectorKind::S64)),
-        satisfy(|t: &Token| t.kind == TokenKind::UVectorF64OpenParen)
-            .map(|open_token| (open_token, SUVectorKind::F64)),
-    ))
+    let (input, (open_token, kind)) = satisfy_map_opt(|t: &Token| match t.kind {
+        TokenKind::UVectorOpenParen(kind) => Some((t.clone(), kind)),
+        _ => None,
+    })
     .parse(input)?;
     let (input, elements) = many0(sexpr)(input)?;
     let (input, close_token) = satisfy(|t: &Token| t.kind == TokenKind::CloseParen).parse(input)?;
@@ -182,7 +203,7 @@ fn quote(input: Tokens) -> IResult<Tokens, LSExpr> {
 
 fn sexpr(input: Tokens) -> IResult<Tokens, LSExpr> {
     alt((
-        bool, int, float, nan, string, symbol, char, list, vector, uvector, quote,
+        bool, int, bigint, rational, float, nan, string, symbol, char, list, vector, uvector, quote,
     ))
     .parse(input)
 }
@@ -192,11 +213,51 @@ fn sexprs(input: Tokens) -> IResult<Tokens, Vec<LSExpr>> {
     Ok((input, sexprs))
 }
 
-pub fn parse<'a>(
-    input: &'a [Token],
-) -> Result<Vec<LSExpr>, nom::Err<nom::error::Error<Tokens<'a>>>> {
+pub fn parse(input: &[Token]) -> Result<Vec<LSExpr>, CompilerError> {
     let input = Tokens::new(input);
-    let (input, sexprs) = sexprs(input)?;
-    let (_, _) = satisfy(|t: &Token| t.kind == TokenKind::Eof).parse(input)?;
+    let (input, sexprs) = sexprs(input).map_err(convert_error)?;
+    let (_, _) = satisfy(|t: &Token| t.kind == TokenKind::Eof)
+        .parse(input)
+        .map_err(convert_error)?;
     Ok(sexprs)
 }
+
+// 先頭のS式を1つだけパースする。readで使う
+// 残りのトークンは読まないので、後ろに不正な括弧があってもエラーにならない
+// 入力が空ならNoneを返す
+pub fn parse_datum(input: &[Token]) -> Result<Option<LSExpr>, CompilerError> {
+    if let Some(Token {
+        kind: TokenKind::Eof,
+        ..
+    }) = input.first()
+    {
+        return Ok(None);
+    }
+    let input = Tokens::new(input);
+    let (_, sexpr) = sexpr(input).map_err(convert_error)?;
+    Ok(Some(sexpr))
+}
+
+// 失敗した位置のトークンを指すエラーにする
+fn convert_error(e: nom::Err<nom::error::Error<Tokens>>) -> CompilerError {
+    let token = match &e {
+        nom::Err::Error(e) | nom::Err::Failure(e) => e.input.tok.first(),
+        nom::Err::Incomplete(_) => None,
+    };
+    match token {
+        Some(Token {
+            kind: TokenKind::Eof,
+            span,
+        }) => compiler_error!(Parse, *span, "unexpected end of input"),
+        Some(Token {
+            kind: TokenKind::CloseParen,
+            span,
+        }) => compiler_error!(Parse, *span, "unexpected ')'"),
+        Some(Token { span, .. }) => CompilerError::from(
+            Diagnostic::error(ErrorCode::Parse, "failed to parse expression")
+                .with_primary(*span, "")
+                .with_note("parentheses may be unbalanced"),
+        ),
+        None => compiler_error!(Parse, "unexpected end of input"),
+    }
+}
@@ -1,6 +1,7 @@
 use ordered_float::NotNan;
 
 use webschembly_compiler_locate::Span;
+use webschembly_compiler_sexpr::SUVectorKind;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum TokenKind {
@@ -8,14 +9,15 @@ pub enum TokenKind {
     OpenParen,
     CloseParen,
     Int(i64),
+    BigInt(String),
+    Rational(String, String),
     Float(NotNan<f64>),
     NaN,
     String(String),
     Bool(bool),
     Quote,
     VectorOpenParen,
-    UVectorS64OpenParen,
-    UVectorF64OpenParen,
+    UVectorOpenParen(SUVectorKind),
     Dot,
     Eof,
     Char(char),
@@ -6,12 +6,21 @@ use webschembly_compiler_locate::L;
 pub enum SUVectorKind {
     S64,
     F64,
+    U8,
+    S8,
+    U16,
+    S16,
+    U32,
+    S32,
+    F32,
 }
 
 #[derive(Debug, Clone)]
 pub enum SExpr {
     Bool(bool),
     Int(i64),
+    BigInt(String),           // i64に収まらない整数の10進数表記
+    Rational(String, String), // 既約分数の分子と分母(分母は2以上)の10進数表記
     Float(NotNan<f64>),
     NaN,
     String(String),
@@ -1,7 +1,8 @@
-use rustc_hash::FxHashSet;
+use rustc_hash::{FxHashMap, FxHashSet, FxHasher};
+use std::hash::{Hash, Hasher};
 
 use crate::ir_generator;
-use crate::ir_generator::GlobalManager;
+use crate::ir_generator::{GlobalManager, SourceLocManager};
 use crate::ir_processor::desugar::desugar;
 use crate::ir_processor::optimizer::remove_unreachable_bb;
 use crate::ir_processor::optimizer::remove_unused_local;
@@ -13,21 +14,28 @@ use crate::ir_processor::ssa_optimizer::SsaOptimizerConfig;
 use crate::ir_processor::ssa_optimizer::inlining;
 use crate::ir_processor::ssa_optimizer::ssa_optimize;
 use crate::jit::BlockFusionConfig;
+use crate::jit::profile::JitProfile;
 use crate::jit::{Jit, JitConfig};
-use crate::lexer;
-use crate::sexpr_parser;
 use crate::stdlib::generate_stdlib;
-use crate::token::TokenKind;
+use crate::wasm_generator;
 use webschembly_compiler_ast_generator::ASTGenerator;
-use webschembly_compiler_error::compiler_error;
 use webschembly_compiler_ir as ir;
+use webschembly_compiler_reader::token::TokenKind;
+use webschembly_compiler_reader::{lexer, sexpr_parser};
+use webschembly_compiler_sexpr::LSExpr;
+
+// evalで生成したモジュールのスタックトレースに表示するファイル名
+pub const EVAL_FILE_NAME: &str = "<eval>";
 
 #[derive(Debug)]
 pub struct Compiler {
     module_count: usize,
     ast_generator: ASTGenerator,
     global_manager: ir_generator::GlobalManager,
+    source_loc_manager: ir_generator::SourceLocManager,
     jit: Option<Jit>,
+    enable_continuation: bool,
+    enable_debug_info: bool,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -37,6 +45,9 @@ pub struct FlatConfig {
     pub enable_jit_small_block_fusion: bool,
     pub enable_jit_large_block_fusion: bool,
     pub enable_jit_log: bool,
+    pub jit_tier_up_threshold: usize,
+    pub enable_continuation: bool,
+    pub enable_debug_info: bool,
 }
 
 impl From<FlatConfig> for Config {
@@ -53,10 +64,14 @@ impl From<FlatConfig> for Config {
                         BlockFusionConfig::Disabled
                     },
                     enable_log: config.enable_jit_log,
+                    tier_up_threshold: config.jit_tier_up_threshold,
+                    aot: false,
                 })
             } else {
                 None
             },
+            enable_continuation: config.enable_continuation,
+            enable_debug_info: config.enable_debug_info,
         }
     }
 }
@@ -64,51 +79,121 @@ impl From<FlatConfig> for Config {
 #[derive(Debug, Clone, Copy)]
 pub struct Config {
     pub jit: Option<JitConfig>,
+    // call/ccを有効にするか。有効な場合はCPS変換し、無効の場合call/ccを呼び出すと実行時エラーになる
+    pub enable_continuation: bool,
+    // 生成するwasmにnameセクションとSource Mapを含めるか
+    pub enable_debug_info: bool,
 }
 
 impl Compiler {
     pub fn new(config: Config) -> Self {
+        Self::with_jit_profile(config, JitProfile::default())
+    }
+
+    // 前回の実行で書き出したプロファイルを使ってJITを初期化する
+    pub fn with_jit_profile(config: Config, profile: JitProfile) -> Self {
         Self {
             module_count: 0,
             ast_generator: ASTGenerator::new(),
             global_manager: ir_generator::GlobalManager::new(),
-            jit: config.jit.map(Jit::new),
+            source_loc_manager: ir_generator::SourceLocManager::new(),
+            jit: config.jit.map(|jit_config| Jit::new(jit_config, profile)),
+            enable_continuation: config.enable_continuation,
+            enable_debug_info: config.enable_debug_info,
+        }
+    }
+
+    pub fn wasm_generator_config(&self) -> wasm_generator::Config {
+        wasm_generator::Config {
+            name_section: self.enable_debug_info,
+            source_map: self
+                .enable_debug_info
+                .then_some(wasm_generator::SourceMapUrl::Inline),
+            embed_source_locs: false,
         }
     }
 
+    // JITが無効の場合はNone
+    pub fn jit_profile(&mut self) -> Option<JitProfile> {
+        self.jit.as_mut().map(|jit| jit.profile())
+    }
+
     pub fn get_global_id(&self, name: &str) -> Option<i32> {
         let global_var_id = self.ast_generator.get_global_id(name)?;
         let global_id = self.global_manager.get_global_id(global_var_id)?;
         Some(usize::from(global_id) as i32)
     }
 
+    pub fn get_source_loc(&self, id: usize) -> Option<&ir::SourceLoc> {
+        self.source_loc_manager.get(ir::SourceLocId::from(id))
+    }
+
     pub fn compile_module(
         &mut self,
         input: &str,
+        file_name: Option<&str>,
         is_stdlib: bool,
     ) -> webschembly_compiler_error::Result<ir::Module> {
         let raw_tokens = lexer::lex(input)?;
         let mut tokens = Vec::new();
+        let mut hasher = FxHasher::default();
+        input.hash(&mut hasher);
         for token in raw_tokens {
             if let TokenKind::Directive("include-stdlib") = token.kind {
-                tokens.extend_from_slice(&lexer::lex(&generate_stdlib())?);
+                let stdlib = generate_stdlib(self.enable_continuation);
+                stdlib.hash(&mut hasher);
+                tokens.extend_from_slice(&lexer::lex(&stdlib)?);
             } else {
                 tokens.push(token);
             }
         }
 
-        let sexprs =
-            sexpr_parser::parse(tokens.as_slice()).map_err(|e| compiler_error!("{}", e))?;
-        let ast = self.ast_generator.gen_ast(sexprs)?;
+        let sexprs = sexpr_parser::parse(tokens.as_slice())?;
+        self.compile_sexprs(sexprs, file_name, is_stdlib, false, hasher.finish())
+    }
+
+    // evalで使う。トップレベルの値はevalの結果として保存される
+    pub fn compile_datum(
+        &mut self,
+        datum: LSExpr,
+    ) -> webschembly_compiler_error::Result<ir::Module> {
+        let mut hasher = FxHasher::default();
+        format!("{:?}", datum).hash(&mut hasher);
+        self.compile_sexprs(
+            vec![datum],
+            Some(EVAL_FILE_NAME),
+            false,
+            true,
+            hasher.finish(),
+        )
+    }
+
+    fn compile_sexprs(
+        &mut self,
+        sexprs: Vec<LSExpr>,
+        file_name: Option<&str>,
+        is_stdlib: bool,
+        is_eval: bool,
+        source_hash: u64,
+    ) -> webschembly_compiler_error::Result<ir::Module> {
+        let ast = self
+            .ast_generator
+            .gen_ast(sexprs, self.enable_continuation)?;
         // TODO: ここで生成するべきではない
         let module_id = ir::JitModuleId::from(self.module_count);
         self.module_count += 1;
         let mut module = ir_generator::generate_module(
             module_id,
             &mut self.global_manager,
+            &mut self.source_loc_manager,
             &ast,
             ir_generator::Config {
                 allow_set_builtin: is_stdlib,
+                enable_continuation: self.enable_continuation,
+                // 標準ライブラリのフレームはスタックトレースに表示しない
+                enable_source_loc: !is_stdlib,
+                file_name: file_name.map(|s| s.to_string()),
+                is_eval,
             },
         );
 
@@ -121,7 +206,8 @@ impl Compiler {
                     ..Default::default()
                 },
             );
-            let mut stub_module = jit.register_module(&mut self.global_manager, module);
+            let mut stub_module =
+                jit.register_module(&mut self.global_manager, module, source_hash);
             preprocess_module(&mut stub_module);
             if jit.config().enable_optimization {
                 optimize_module(
@@ -132,11 +218,19 @@ impl Compiler {
                     },
                 );
             }
-            postprocess(&mut stub_module, &mut self.global_manager);
+            postprocess(
+                &mut stub_module,
+                &mut self.global_manager,
+                &self.source_loc_manager,
+            );
             Ok(stub_module)
         } else {
             optimize_module(&mut module, Default::default());
-            postprocess(&mut module, &mut self.global_manager);
+            postprocess(
+                &mut module,
+                &mut self.global_manager,
+                &self.source_loc_manager,
+            );
 
             Ok(module)
         }
@@ -170,7 +264,11 @@ impl Compiler {
                 },
             );
         }
-        postprocess(&mut module, &mut self.global_manager);
+        postprocess(
+            &mut module,
+            &mut self.global_manager,
+            &self.source_loc_manager,
+        );
         (module, jit_events)
     }
 
@@ -206,10 +304,41 @@ impl Compiler {
                 },
             );
         }
-        postprocess(&mut module, &mut self.global_manager);
+        postprocess(
+            &mut module,
+            &mut self.global_manager,
+            &self.source_loc_manager,
+        );
         (module, jit_events)
     }
 
+    pub fn increment_bb_layout_counter(
+        &mut self,
+        module_id: usize,
+        func_id: usize,
+        env_index: usize,
+        func_index: usize,
+        bb_id: usize,
+        index: usize,
+        kind: usize, // 0: Hit, 1: GuardFailure
+    ) {
+        let jit = self.jit.as_mut().expect("JIT is not enabled");
+        let kind = match kind {
+            0 => ir::BBLayoutCounterKind::Hit,
+            1 => ir::BBLayoutCounterKind::GuardFailure,
+            _ => panic!("Invalid BB layout counter kind"),
+        };
+        jit.increment_bb_layout_counter(
+            ir::JitModuleId::from(module_id),
+            ir::FuncId::from(func_id),
+            crate::jit::env_index_manager::EnvIndex(env_index),
+            crate::jit::closure_global_layout::ClosureIndex(func_index),
+            ir::BasicBlockId::from(bb_id),
+            crate::jit::bb_index_manager::BBIndex(index),
+            kind,
+        );
+    }
+
     pub fn increment_branch_counter(
         &mut self,
         module_id: usize,
@@ -252,12 +381,43 @@ impl Compiler {
                     },
                 );
             }
-            postprocess(&mut module, &mut self.global_manager);
+            postprocess(
+                &mut module,
+                &mut self.global_manager,
+                &self.source_loc_manager,
+            );
             (module, jit_events)
         })
     }
 }
 
+// AOTコンパイルで使う。モジュールを1つにまとめ、エントリーで順に初期化する
+pub fn link_modules(modules: impl IntoIterator<Item = ir::Module>) -> ir::Module {
+    let mut linked = ir::Module::new();
+    let entries = modules
+        .into_iter()
+        .map(|module| linked.merge(module))
+        .collect::<Vec<_>>();
+    linked.extend_entry_func(|entry_func, next| {
+        let mut instrs = entries
+            .into_iter()
+            .map(|func_id| ir::Instr {
+                local: None,
+                kind: ir::InstrKind::Call(ir::InstrCall {
+                    func_id,
+                    args: vec![],
+                }),
+            })
+            .collect::<Vec<_>>();
+        instrs.push(ir::Instr {
+            local: None,
+            kind: ir::InstrKind::Terminator(next),
+        });
+        entry_func.bbs.push_with(|id| ir::BasicBlock { id, instrs })
+    });
+    linked
+}
+
 fn preprocess_module(module: &mut ir::Module) {
     for func in module.funcs.values_mut() {
         debug_assert_ssa(func);
@@ -285,7 +445,12 @@ fn optimize_module(module: &mut ir::Module, config: SsaOptimizerConfig) {
     }
 }
 
-fn postprocess(module: &mut ir::Module, global_manager: &mut GlobalManager) {
+fn postprocess(
+    module: &mut ir::Module,
+    global_manager: &mut GlobalManager,
+    source_loc_manager: &SourceLocManager,
+) {
+    let mut local_maps = FxHashMap::default();
     for func in module.funcs.values_mut() {
         debug_assert_ssa(func);
 
@@ -293,10 +458,12 @@ fn postprocess(module: &mut ir::Module, global_manager: &mut GlobalManager) {
 
         split_critical_edges(func);
         remove_phi(func);
-        register_allocation(func);
+        local_maps.insert(func.id, register_allocation(func));
 
         remove_unused_local(func);
     }
+    remap_local_metas(&mut module.meta, &local_maps);
+    complete_source_loc_metas(module, source_loc_manager);
 
     // モジュールごとにグローバルを真面目に管理するのは大変なのでここで計算
     let mut global_ids = FxHashSet::default();
@@ -312,3 +479,151 @@ fn postprocess(module: &mut ir::Module, global_manager: &mut GlobalManager) {
     module.globals =
         global_manager.calc_module_globals(&global_ids.iter().copied().collect::<Vec<_>>());
 }
+
+// レジスタ割り当て後のLocalIdに合わせてローカル変数名を付け替える
+// 複数の変数が同じLocalIdに割り当てられた場合は名前を/で連結する
+fn remap_local_metas(
+    meta: &mut ir::Meta,
+    local_maps: &FxHashMap<ir::FuncId, FxHashMap<ir::LocalId, ir::LocalId>>,
+) {
+    let mut old_local_metas = std::mem::take(&mut meta.local_metas)
+        .into_iter()
+        .collect::<Vec<_>>();
+    old_local_metas.sort_by_key(|((func_id, local_id), _)| (usize::from(*func_id), *local_id));
+    for ((func_id, local_id), var_meta) in old_local_metas {
+        let Some(&new_local_id) = local_maps
+            .get(&func_id)
+            .and_then(|local_map| local_map.get(&local_id))
+        else {
+            continue;
+        };
+        meta.local_metas
+            .entry((func_id, new_local_id))
+            .and_modify(|new_meta| {
+                if !new_meta.name.split('/').any(|name| name == var_meta.name) {
+                    new_meta.name.push('/');
+                    new_meta.name.push_str(&var_meta.name);
+                }
+            })
+            .or_insert(var_meta);
+    }
+}
+
+// JITで生成されたモジュールはSourceLocのメタ情報を持たないので補完する
+fn complete_source_loc_metas(module: &mut ir::Module, source_loc_manager: &SourceLocManager) {
+    let source_loc_ids = module
+        .funcs
+        .values()
+        .flat_map(|func| func.bbs.values())
+        .flat_map(|bb| &bb.instrs)
+        .filter_map(|instr| match instr.kind {
+            ir::InstrKind::SourceLoc(id) => Some(id),
+            _ => None,
+        });
+    for id in source_loc_ids {
+        if let Some(source_loc) = source_loc_manager.get(id) {
+            module
+                .meta
+                .source_locs
+                .entry(id)
+                .or_insert_with(|| source_loc.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jit::profile::{FuncProfile, JIT_PROFILE_VERSION};
+
+    fn compile_aot(profile: JitProfile) -> (ir::Module, JitProfile) {
+        let mut compiler = Compiler::with_jit_profile(
+            Config {
+                jit: Some(JitConfig {
+                    aot: true,
+                    ..JitConfig::new()
+                }),
+                enable_continuation: false,
+                enable_debug_info: false,
+            },
+            profile,
+        );
+        let src = "
+            (define (add1 x) (+ x 1))
+            (define (apply-to-2 f) (f 2))
+            (apply-to-2 (lambda (y) (add1 y)))
+        ";
+        let module = link_modules([compiler.compile_module(src, None, false).unwrap()]);
+        (module, compiler.jit_profile().unwrap())
+    }
+
+    fn hot_funcs_profile(module_hashes: Vec<u64>) -> JitProfile {
+        JitProfile {
+            version: JIT_PROFILE_VERSION,
+            module_hashes,
+            funcs: (0..16)
+                .map(|func_id| FuncProfile {
+                    module_id: 0,
+                    func_id,
+                })
+                .collect(),
+            closure_layouts: vec![vec![ir::Type::Val(ir::ValType::Int)]],
+            bbs: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_aot_does_not_call_jit() {
+        let (empty_profile_module, empty_profile) = compile_aot(JitProfile::default());
+        let (module, _) = compile_aot(hot_funcs_profile(empty_profile.module_hashes));
+        // プロファイルにある関数は特殊化したコードが含まれる
+        assert!(module.funcs.values().count() > empty_profile_module.funcs.values().count());
+
+        for module in [&module, &empty_profile_module] {
+            for func in module.funcs.values() {
+                for bb in func.bbs.values() {
+                    for instr in &bb.instrs {
+                        assert!(
+                            !matches!(
+                                instr.kind,
+                                ir::InstrKind::InstantiateFunc(..)
+                                    | ir::InstrKind::InstantiateClosureFunc(..)
+                                    | ir::InstrKind::InstantiateBB(..)
+                                    | ir::InstrKind::IncrementBranchCounter(..)
+                            ),
+                            "{:?}",
+                            instr.kind
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_stale_profile_is_ignored() {
+        let (empty_profile_module, empty_profile) = compile_aot(JitProfile::default());
+        let func_count = |module: &ir::Module| module.funcs.values().count();
+
+        // ソースが異なるモジュールのプロファイルは使わない
+        // クロージャのレイアウトは型の列でモジュールに依存しないので、そのまま使われる
+        let stale_hashes = empty_profile
+            .module_hashes
+            .iter()
+            .map(|hash| hash.wrapping_add(1))
+            .collect();
+        let (module, profile) = compile_aot(JitProfile {
+            closure_layouts: Vec::new(),
+            ..hot_funcs_profile(stale_hashes)
+        });
+        assert_eq!(func_count(&module), func_count(&empty_profile_module));
+        assert_eq!(profile.module_hashes, empty_profile.module_hashes);
+
+        // バージョンが異なるプロファイルは使わない
+        let (module, _) = compile_aot(JitProfile {
+            version: 0,
+            ..hot_funcs_profile(empty_profile.module_hashes)
+        });
+        assert_eq!(func_count(&module), func_count(&empty_profile_module));
+    }
+}
@@ -2,3 +2,5 @@ mod module_generator;
 pub use module_generator::*;
 pub mod global_manager;
 pub use global_manager::*;
+pub mod source_loc_manager;
+pub use source_loc_manager::*;
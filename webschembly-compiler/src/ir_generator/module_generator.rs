@@ -2,7 +2,8 @@ use rustc_hash::FxHashMap;
 use webschembly_compiler_ast_generator::{Final, GlobalVarId, LocalVarId, UsedExtR, VarId};
 use webschembly_compiler_locate::{Located, Span};
 
-use crate::ir_generator::GlobalManager;
+use crate::ir_generator::{GlobalManager, SourceLocManager};
+use ordered_float::NotNan;
 use vec_map::VecMap;
 use webschembly_compiler_ast as ast;
 use webschembly_compiler_ast::AstPhase;
@@ -11,15 +12,23 @@ use webschembly_compiler_ir::*;
 #[derive(Debug, Clone)]
 pub struct Config {
     pub allow_set_builtin: bool,
+    // call/ccのための組み込み関数を有効にするか
+    pub enable_continuation: bool,
+    // スタックトレース用の位置情報を埋め込むか
+    pub enable_source_loc: bool,
+    pub file_name: Option<String>,
+    // トップレベルの値をevalの結果として保存するか
+    pub is_eval: bool,
 }
 
 pub fn generate_module(
     id: JitModuleId,
     global_manager: &mut GlobalManager,
+    source_loc_manager: &mut SourceLocManager,
     ast: &ast::Ast<Final>,
     config: Config,
 ) -> Module {
-    let module_gen = ModuleGenerator::new(id, config, global_manager, ast);
+    let module_gen = ModuleGenerator::new(id, config, global_manager, source_loc_manager, ast);
 
     module_gen.generate()
 }
@@ -28,6 +37,7 @@ pub fn generate_module(
 struct ModuleGenerator<'a> {
     id: JitModuleId,
     global_manager: &'a mut GlobalManager,
+    source_loc_manager: &'a mut SourceLocManager,
     ast: &'a ast::Ast<Final>,
     funcs: VecMap<FuncId, Func>,
     config: Config,
@@ -36,6 +46,8 @@ struct ModuleGenerator<'a> {
     // メタ情報
     local_metas: FxHashMap<(FuncId, LocalId), VarMeta>,
     global_metas: FxHashMap<GlobalId, VarMeta>,
+    func_metas: FxHashMap<FuncId, FuncMeta>,
+    source_locs: FxHashMap<SourceLocId, SourceLoc>,
 }
 
 impl<'a> ModuleGenerator<'a> {
@@ -43,16 +55,20 @@ impl<'a> ModuleGenerator<'a> {
         id: JitModuleId,
         config: Config,
         ir_generator: &'a mut GlobalManager,
+        source_loc_manager: &'a mut SourceLocManager,
         ast: &'a ast::Ast<Final>,
     ) -> Self {
         Self {
             id,
             ast,
             global_manager: ir_generator,
+            source_loc_manager,
             funcs: VecMap::new(),
             config,
             local_metas: FxHashMap::default(),
             global_metas: FxHashMap::default(),
+            func_metas: FxHashMap::default(),
+            source_locs: FxHashMap::default(),
             func_to_entrypoint_table: FxHashMap::default(),
             globals: FxHashMap::default(),
         }
@@ -73,7 +89,8 @@ impl<'a> ModuleGenerator<'a> {
         self.globals.extend(ast_globals);
 
         let entry_func_id = self.funcs.allocate_key();
-        let mut entry_func = FuncGenerator::new(&mut self, entry_func_id).entry_gen();
+        let mut entry_func =
+            FuncGenerator::new(&mut self, entry_func_id, "<toplevel>".to_string()).entry_gen();
 
         // エントリーポイントにモジュール初期化ロジックを追加
         let prev_bb_entry = entry_func.bb_entry;
@@ -124,6 +141,8 @@ impl<'a> ModuleGenerator<'a> {
         let meta = Meta {
             local_metas: self.local_metas,
             global_metas: self.global_metas,
+            func_metas: self.func_metas,
+            source_locs: self.source_locs,
         };
 
         Module {
@@ -152,9 +171,24 @@ impl<'a> ModuleGenerator<'a> {
         x: &<Final as AstPhase>::XLambda,
         span: Span,
         lambda: &ast::Lambda<Final>,
+        name: String,
     ) -> FuncId {
         let id = self.funcs.allocate_key();
-        let func = FuncGenerator::new(self, id).lambda_gen(x, span, lambda);
+        let func = FuncGenerator::new(self, id, name).lambda_gen(x, span, lambda);
+        self.funcs.insert_node(func);
+
+        id
+    }
+
+    fn gen_case_lambda_func(
+        &mut self,
+        clauses: &[(&<Final as AstPhase>::XLambda, &ast::Lambda<Final>)],
+        captures: &[LocalVarId],
+        span: Span,
+        name: String,
+    ) -> FuncId {
+        let id = self.funcs.allocate_key();
+        let func = FuncGenerator::new(self, id, name).case_lambda_gen(clauses, captures, span);
         self.funcs.insert_node(func);
 
         id
@@ -214,6 +248,10 @@ struct FuncGenerator<'a, 'b> {
     module_generator: &'a mut ModuleGenerator<'b>,
     uninitialized_vars: FxHashMap<LocalVarId, Vec<UninitializedVarCaptureClosure>>,
     builder: IrFuncBuilder,
+    // スタックトレースに表示する関数名
+    name: String,
+    // 直後に生成するラムダ式の名前(define/set!の右辺の場合)
+    lambda_name: Option<String>,
 }
 
 // クロージャにキャプチャされているが、まだ初期化されていない変数
@@ -225,14 +263,39 @@ struct UninitializedVarCaptureClosure {
 }
 
 impl<'a, 'b> FuncGenerator<'a, 'b> {
-    fn new(module_generator: &'a mut ModuleGenerator<'b>, id: FuncId) -> Self {
+    fn new(module_generator: &'a mut ModuleGenerator<'b>, id: FuncId, name: String) -> Self {
+        module_generator
+            .func_metas
+            .insert(id, FuncMeta { name: name.clone() });
         Self {
             id,
             local_ids: FxHashMap::default(),
             module_generator,
             uninitialized_vars: FxHashMap::default(),
             builder: IrFuncBuilder::new(),
+            name,
+            lambda_name: None,
+        }
+    }
+
+    fn gen_source_loc(&mut self, span: Span) {
+        if !self.module_generator.config.enable_source_loc {
+            return;
         }
+        let source_loc = SourceLoc {
+            func_name: self.name.clone(),
+            file_name: self.module_generator.config.file_name.clone(),
+            span,
+        };
+        let id = self
+            .module_generator
+            .source_loc_manager
+            .gen_source_loc(source_loc.clone());
+        self.module_generator.source_locs.insert(id, source_loc);
+        self.builder.exprs.push(Instr {
+            local: None,
+            kind: InstrKind::SourceLoc(id),
+        });
     }
 
     fn entry_gen(mut self) -> Func {
@@ -243,6 +306,12 @@ impl<'a, 'b> FuncGenerator<'a, 'b> {
         let bb_entry = self.builder.bbs.allocate_key();
         self.builder.current_bb_id = Some(bb_entry);
         self.gen_exprs(Some(obj_local), &self.module_generator.ast.exprs);
+        if self.module_generator.config.is_eval {
+            self.builder.exprs.push(Instr {
+                local: None,
+                kind: InstrKind::SetEvalResult(obj_local),
+            });
+        }
         self.builder
             .close_bb(TerminatorInstr::Exit(ExitInstr::Return(obj_local)));
 
@@ -300,6 +369,7 @@ impl<'a, 'b> FuncGenerator<'a, 'b> {
             error_bb_id,
         ));
         self.builder.current_bb_id = Some(error_bb_id);
+        self.gen_source_loc(span);
         let msg = self.builder.local(Type::Val(ValType::String));
         self.builder.exprs.push(Instr {
             local: Some(msg),
@@ -309,6 +379,117 @@ impl<'a, 'b> FuncGenerator<'a, 'b> {
             .close_bb(TerminatorInstr::Exit(ExitInstr::Error(msg)));
         self.builder.current_bb_id = Some(merge_bb_id);
 
+        self.gen_lambda_args(x, lambda, args);
+        let env_types = self.gen_closure_env_restore(&x.captures, self_closure);
+
+        self.define_all_ast_local_and_create_ref(&x.defines);
+
+        let ret = self.builder.local(Type::Obj);
+        self.gen_exprs(Some(ret), &lambda.body);
+        self.builder
+            .close_bb(TerminatorInstr::Exit(ExitInstr::Return(ret)));
+        Func {
+            id: self.id,
+            args: vec![self_closure, args],
+            ret_type: LocalType::Type(Type::Obj),
+            locals: self.builder.locals,
+            bb_entry,
+            bbs: self.builder.bbs,
+            closure_meta: Some(ClosureFuncMeta {
+                env_types,
+                module_id: self.module_generator.id,
+                func_id: self.id.into(),
+            }),
+        }
+    }
+
+    // case-lambdaの全ての節を1つの関数にまとめ、引数の数で節を選ぶ
+    // 環境は各節のキャプチャを合わせたもの
+    // JITの引数の型ごとのエントリポイントでは引数の数が定数になるので、節の選択は畳み込まれ、各エントリポイントは対応する節の本体を直接実行する
+    fn case_lambda_gen(
+        mut self,
+        clauses: &[(&<Final as AstPhase>::XLambda, &ast::Lambda<Final>)],
+        captures: &[LocalVarId],
+        span: Span,
+    ) -> Func {
+        let bb_entry = self.builder.bbs.allocate_key();
+        self.builder.current_bb_id = Some(bb_entry);
+
+        let self_closure = self.builder.local(Type::Val(ValType::Closure));
+        let args = self.builder.local(LocalType::VariadicArgs);
+        let args_len_local = self.builder.local(Type::Val(ValType::Int));
+        self.builder.exprs.push(Instr {
+            local: Some(args_len_local),
+            kind: InstrKind::VariadicArgsLength(args),
+        });
+        let env_types = self.gen_closure_env_restore(captures, self_closure);
+
+        for &(x, lambda) in clauses {
+            let expected_args_len_local = self.builder.local(Type::Val(ValType::Int));
+            let args_len_check_success_local = self.builder.local(Type::Val(ValType::Bool));
+            self.builder.exprs.push(Instr {
+                local: Some(expected_args_len_local),
+                kind: InstrKind::Int(lambda.args.len() as i64),
+            });
+            self.builder.exprs.push(Instr {
+                local: Some(args_len_check_success_local),
+                kind: if x.variadic_arg.is_some() {
+                    InstrKind::GeInt(args_len_local, expected_args_len_local)
+                } else {
+                    InstrKind::EqInt(args_len_local, expected_args_len_local)
+                },
+            });
+
+            let clause_bb_id = self.builder.bbs.allocate_key();
+            let next_bb_id = self.builder.bbs.allocate_key();
+            self.builder.close_bb(TerminatorInstr::If(
+                args_len_check_success_local,
+                clause_bb_id,
+                next_bb_id,
+            ));
+
+            self.builder.current_bb_id = Some(clause_bb_id);
+            self.gen_lambda_args(x, lambda, args);
+            self.define_all_ast_local_and_create_ref(&x.defines);
+            let ret = self.builder.local(Type::Obj);
+            self.gen_exprs(Some(ret), &lambda.body);
+            self.builder
+                .close_bb(TerminatorInstr::Exit(ExitInstr::Return(ret)));
+
+            self.builder.current_bb_id = Some(next_bb_id);
+        }
+
+        self.gen_source_loc(span);
+        let msg = self.builder.local(Type::Val(ValType::String));
+        self.builder.exprs.push(Instr {
+            local: Some(msg),
+            kind: InstrKind::String(format!("args count mismatch. at {}\n", span)),
+        });
+        self.builder
+            .close_bb(TerminatorInstr::Exit(ExitInstr::Error(msg)));
+
+        Func {
+            id: self.id,
+            args: vec![self_closure, args],
+            ret_type: LocalType::Type(Type::Obj),
+            locals: self.builder.locals,
+            bb_entry,
+            bbs: self.builder.bbs,
+            closure_meta: Some(ClosureFuncMeta {
+                env_types,
+                module_id: self.module_generator.id,
+                func_id: self.id.into(),
+            }),
+        }
+    }
+
+    // 可変長引数から仮引数を束縛する。引数の数は検査済みである必要がある
+    fn gen_lambda_args(
+        &mut self,
+        x: &<Final as AstPhase>::XLambda,
+        lambda: &ast::Lambda<Final>,
+        args: LocalId,
+    ) {
         for (arg_idx, arg) in x.args.iter().enumerate() {
             if self.module_generator.ast.x.box_vars.contains(arg) {
                 let local = self.builder.local(LocalType::Type(Type::Obj));
@@ -360,19 +541,25 @@ impl<'a, 'b> FuncGenerator<'a, 'b> {
                 });
             }
         }
+    }
 
+    // クロージャの環境からキャプチャした変数を復元し、環境の型を返す
+    fn gen_closure_env_restore(
+        &mut self,
+        captures: &[LocalVarId],
+        self_closure: LocalId,
+    ) -> Vec<LocalType> {
         // 環境を復元するためのローカル変数を定義
-        for var_id in x.captures.iter() {
+        for var_id in captures.iter() {
             self.define_ast_local(*var_id);
         }
         // 環境の型を収集
-        let env_types = x
-            .captures
+        let env_types = captures
             .iter()
             .map(|id| self.builder.locals[*self.local_ids.get(id).unwrap()].typ)
             .collect::<Vec<_>>();
         // 環境を復元する処理を追加
-        for (i, var_id) in x.captures.iter().enumerate() {
+        for (i, var_id) in captures.iter().enumerate() {
             let env_local = *self.local_ids.get(var_id).unwrap();
             self.builder.exprs.push(Instr {
                 local: Some(env_local),
@@ -380,26 +567,86 @@ impl<'a, 'b> FuncGenerator<'a, 'b> {
                 kind: InstrKind::ClosureEnv(env_types.clone(), self_closure, i),
             });
         }
+        env_types
+    }
 
-        self.define_all_ast_local_and_create_ref(&x.defines);
+    // キャプチャした変数を環境に持つクロージャを生成する
+    fn gen_capturing_closure(
+        &mut self,
+        result: Option<LocalId>,
+        func_id: FuncId,
+        captures: &[LocalVarId],
+    ) {
+        let val_type_local = self.builder.local(Type::Val(ValType::Closure));
 
-        let ret = self.builder.local(Type::Obj);
-        self.gen_exprs(Some(ret), &lambda.body);
-        self.builder
-            .close_bb(TerminatorInstr::Exit(ExitInstr::Return(ret)));
-        Func {
-            id: self.id,
-            args: vec![self_closure, args],
-            ret_type: LocalType::Type(Type::Obj),
-            locals: self.builder.locals,
-            bb_entry,
-            bbs: self.builder.bbs,
-            closure_meta: Some(ClosureFuncMeta {
+        let env_types = captures
+            .iter()
+            .map(|id| self.builder.locals[*self.local_ids.get(id).unwrap()].typ)
+            .collect::<Vec<_>>();
+        let mut envs = Vec::new();
+        for (i, capture) in captures.iter().enumerate() {
+            if let Some(uninitialized_var_captures) = self.uninitialized_vars.get_mut(capture) {
+                uninitialized_var_captures.push(UninitializedVarCaptureClosure {
+                    closure: val_type_local,
+                    env_index: i,
+                    env_types: env_types.clone(),
+                });
+                envs.push(None);
+            } else {
+                envs.push(Some(*self.local_ids.get(capture).unwrap()));
+            }
+        }
+
+        self.gen_closure(result, val_type_local, func_id, envs, env_types);
+    }
+
+    fn gen_closure(
+        &mut self,
+        result: Option<LocalId>,
+        val_type_local: LocalId,
+        func_id: FuncId,
+        envs: Vec<Option<LocalId>>,
+        env_types: Vec<LocalType>,
+    ) {
+        let func_local = self.builder.local(LocalType::FuncRef);
+        self.builder.exprs.push(Instr {
+            local: Some(func_local),
+            kind: InstrKind::FuncRef(func_id),
+        });
+
+        let entrypoint_table_global = self
+            .module_generator
+            .global_manager
+            .gen_global(LocalType::EntrypointTable);
+        self.module_generator
+            .globals
+            .insert(entrypoint_table_global.id, entrypoint_table_global);
+        self.module_generator
+            .func_to_entrypoint_table
+            .insert(func_id, entrypoint_table_global.id);
+
+        let entrypoint_table_local = self.builder.local(LocalType::EntrypointTable);
+        self.builder.exprs.push(Instr {
+            local: Some(entrypoint_table_local),
+            kind: InstrKind::GlobalGet(entrypoint_table_global.id),
+        });
+
+        self.builder.exprs.push(Instr {
+            local: Some(val_type_local),
+            kind: InstrKind::Closure {
+                envs,
                 env_types,
+                func_id: JitFuncId::from(func_id),
+                env_index: 0,
                 module_id: self.module_generator.id,
-                func_id: self.id.into(),
-            }),
-        }
+                entrypoint_table: entrypoint_table_local,
+                original_entrypoint_table: entrypoint_table_local,
+            },
+        });
+        self.builder.exprs.push(Instr {
+            local: result,
+            kind: InstrKind::ToObj(ValType::Closure, val_type_local),
+        });
     }
 
     fn define_all_ast_local_and_create_ref(&mut self, locals: &[LocalVarId]) {
@@ -458,6 +705,25 @@ impl<'a, 'b> FuncGenerator<'a, 'b> {
         });
     }
 
+    // 10進数表記の整数定数をobjとして生成する
+    fn gen_integer_const(&mut self, digits: &str) -> LocalId {
+        let (val_type, kind) = match digits.parse::<i64>() {
+            Ok(i) => (ValType::Int, InstrKind::Int(i)),
+            Err(_) => (ValType::BigInt, InstrKind::BigInt(digits.to_string())),
+        };
+        let val_type_local = self.builder.local(Type::Val(val_type));
+        self.builder.exprs.push(Instr {
+            local: Some(val_type_local),
+            kind,
+        });
+        let obj = self.builder.local(Type::Obj);
+        self.builder.exprs.push(Instr {
+            local: Some(obj),
+            kind: InstrKind::ToObj(val_type, val_type_local),
+        });
+        obj
+    }
+
     fn gen_expr_impl(&mut self, result: Option<LocalId>, ast: &ast::LExpr<Final>) {
         match &ast.value {
             ast::Expr::Const(_, lit) => match lit {
@@ -483,6 +749,30 @@ impl<'a, 'b> FuncGenerator<'a, 'b> {
                         kind: InstrKind::ToObj(ValType::Int, val_type_local),
                     });
                 }
+                ast::Const::BigInt(i) => {
+                    let val_type_local = self.builder.local(Type::Val(ValType::BigInt));
+                    self.builder.exprs.push(Instr {
+                        local: Some(val_type_local),
+                        kind: InstrKind::BigInt(i.clone()),
+                    });
+                    self.builder.exprs.push(Instr {
+                        local: result,
+                        kind: InstrKind::ToObj(ValType::BigInt, val_type_local),
+                    });
+                }
+                ast::Const::Rational(n, d) => {
+                    let num = self.gen_integer_const(n);
+                    let den = self.gen_integer_const(d);
+                    let val_type_local = self.builder.local(Type::Val(ValType::Rational));
+                    self.builder.exprs.push(Instr {
+                        local: Some(val_type_local),
+                        kind: InstrKind::Rational(num, den),
+                    });
+                    self.builder.exprs.push(Instr {
+                        local: result,
+                        kind: InstrKind::ToObj(ValType::Rational, val_type_local),
+                    });
+                }
                 ast::Const::Float(f) => {
                     let val_type_local = self.builder.local(Type::Val(ValType::Float));
                     self.builder.exprs.push(Instr {
@@ -557,68 +847,46 @@ impl<'a, 'b> FuncGenerator<'a, 'b> {
             },
             ast::Expr::Define(x, _) => *x,
             ast::Expr::Lambda(x, lambda) => {
-                let func_id = self.module_generator.gen_func(x, ast.span, lambda);
-                let func_local = self.builder.local(LocalType::FuncRef);
-                let val_type_local = self.builder.local(Type::Val(ValType::Closure));
-                self.builder.exprs.push(Instr {
-                    local: Some(func_local),
-                    kind: InstrKind::FuncRef(func_id),
-                });
-
-                let entrypoint_table_global = self
-                    .module_generator
-                    .global_manager
-                    .gen_global(LocalType::EntrypointTable);
-                self.module_generator
-                    .globals
-                    .insert(entrypoint_table_global.id, entrypoint_table_global);
-                self.module_generator
-                    .func_to_entrypoint_table
-                    .insert(func_id, entrypoint_table_global.id);
-
-                let entrypoint_table_local = self.builder.local(LocalType::EntrypointTable);
-                self.builder.exprs.push(Instr {
-                    local: Some(entrypoint_table_local),
-                    kind: InstrKind::GlobalGet(entrypoint_table_global.id),
-                });
-
-                let env_types = x
-                    .captures
+                let name = self
+                    .lambda_name
+                    .take()
+                    .unwrap_or_else(|| "<lambda>".to_string());
+                let func_id = self.module_generator.gen_func(x, ast.span, lambda, name);
+                self.gen_capturing_closure(result, func_id, &x.captures);
+            }
+            ast::Expr::CaseLambda(_, case_lambda) => {
+                let name = self
+                    .lambda_name
+                    .take()
+                    .unwrap_or_else(|| "<case-lambda>".to_string());
+                let clauses = case_lambda
+                    .clauses
                     .iter()
-                    .map(|id| self.builder.locals[*self.local_ids.get(id).unwrap()].typ)
+                    .map(|clause| {
+                        let [
+                            Located {
+                                value: ast::Expr::Lambda(x, lambda),
+                                ..
+                            },
+                        ] = clause.as_slice()
+                        else {
+                            unreachable!("case-lambda clause must be a lambda")
+                        };
+                        (x, lambda)
+                    })
                     .collect::<Vec<_>>();
                 let mut captures = Vec::new();
-                for (i, capture) in x.captures.iter().enumerate() {
-                    if let Some(uninitialized_var_captures) =
-                        self.uninitialized_vars.get_mut(capture)
-                    {
-                        uninitialized_var_captures.push(UninitializedVarCaptureClosure {
-                            closure: val_type_local,
-                            env_index: i,
-                            env_types: env_types.clone(),
-                        });
-                        captures.push(None);
-                    } else {
-                        captures.push(Some(*self.local_ids.get(capture).unwrap()));
+                for (x, _) in &clauses {
+                    for capture in &x.captures {
+                        if !captures.contains(capture) {
+                            captures.push(*capture);
+                        }
                     }
                 }
-
-                self.builder.exprs.push(Instr {
-                    local: Some(val_type_local),
-                    kind: InstrKind::Closure {
-                        envs: captures,
-                        env_types,
-                        func_id: JitFuncId::from(func_id),
-                        env_index: 0,
-                        module_id: self.module_generator.id,
-                        entrypoint_table: entrypoint_table_local,
-                        original_entrypoint_table: entrypoint_table_local,
-                    },
-                });
-                self.builder.exprs.push(Instr {
-                    local: result,
-                    kind: InstrKind::ToObj(ValType::Closure, val_type_local),
-                });
+                let func_id = self
+                    .module_generator
+                    .gen_case_lambda_func(&clauses, &captures, ast.span, name);
+                self.gen_capturing_closure(result, func_id, &captures);
             }
             ast::Expr::If(_, ast::If { cond, then, els }) => {
                 let obj_cond_local = self.builder.local(Type::Obj);
@@ -729,6 +997,7 @@ impl<'a, 'b> FuncGenerator<'a, 'b> {
             }
             ast::Expr::Cond(x, _) => *x,
             ast::Expr::Call(x, ast::Call { func, args }) => {
+                let is_tail = x.is_tail;
                 if let [
                     Located {
                         value: ast::Expr::Var(x, name),
@@ -738,147 +1007,66 @@ impl<'a, 'b> FuncGenerator<'a, 'b> {
                     && let VarId::Global(_) = x.var_id
                     && let Some(builtin) = ast::Builtin::from_name(name)
                 {
-                    let rules = BuiltinConversionRule::from_builtin(builtin)
-                        .into_iter()
-                        .filter(|rule| rule.arg_count() == args.len())
-                        .collect::<Vec<_>>();
+                    let has_rule = BuiltinConversionRule::from_builtin(builtin)
+                        .iter()
+                        .any(|rule| rule.arg_count() == args.len());
+                    let variadic = BuiltinVariadicRule::from_builtin(builtin)
+                        .filter(|variadic| !has_rule && args.len() >= variadic.min_arg_count());
 
-                    if rules.is_empty() {
+                    if BuiltinConversionRule::takes_record_literals(builtin) {
+                        self.gen_record_builtin_call(result, builtin, args, ast.span);
+                    } else if BuiltinConversionRule::requires_continuation(builtin)
+                        && !self.module_generator.config.enable_continuation
+                    {
+                        self.gen_source_loc(ast.span);
                         let msg = self.builder.local(Type::Val(ValType::String));
                         self.builder.exprs.push(Instr {
                             local: Some(msg),
                             kind: InstrKind::String(format!(
-                                "builtin args count mismatch. at {}\n",
+                                "continuations are not enabled. at {}\n",
                                 ast.span
                             )),
                         });
                         self.builder
                             .close_bb(TerminatorInstr::Exit(ExitInstr::Error(msg)));
-                    } else {
-                        let merge_bb_id = self.builder.bbs.allocate_key();
-                        let mut phi_incoming_values = Vec::new();
-                        for rule in rules {
-                            let mut obj_arg_locals = Vec::new();
-                            let mut type_check_success_locals = Vec::new();
-                            for (typ, arg) in rule.arg_types().iter().zip(args) {
-                                let obj_arg_local = self.builder.local(Type::Obj);
-                                self.gen_exprs(Some(obj_arg_local), arg);
-                                obj_arg_locals.push(obj_arg_local);
-                                if let Type::Val(val_type) = typ {
-                                    let type_check_success_local =
-                                        self.builder.local(Type::Val(ValType::Bool));
-                                    self.builder.exprs.push(Instr {
-                                        local: Some(type_check_success_local),
-                                        kind: InstrKind::Is(*val_type, obj_arg_local),
-                                    });
-                                    type_check_success_locals.push(type_check_success_local);
-                                }
-                            }
-
-                            let mut all_type_check_success_local =
-                                self.builder.local(Type::Val(ValType::Bool));
-                            self.builder.exprs.push(Instr {
-                                local: Some(all_type_check_success_local),
-                                kind: InstrKind::Bool(true),
-                            });
-                            for type_check_success_local in type_check_success_locals {
-                                let new_all_type_check_success_local =
-                                    self.builder.local(Type::Val(ValType::Bool));
-                                self.builder.exprs.push(Instr {
-                                    local: Some(new_all_type_check_success_local),
-                                    kind: InstrKind::And(
-                                        all_type_check_success_local,
-                                        type_check_success_local,
-                                    ),
-                                });
-                                all_type_check_success_local = new_all_type_check_success_local;
-                            }
-
-                            let then_bb_id = self.builder.bbs.allocate_key();
-                            let else_bb_id = self.builder.bbs.allocate_key();
-
-                            self.builder.close_bb(TerminatorInstr::If(
-                                all_type_check_success_local,
-                                then_bb_id,
-                                else_bb_id,
-                            ));
-
-                            self.builder.current_bb_id = Some(then_bb_id);
-
-                            let mut arg_locals = Vec::new();
-                            for (typ, obj_arg_local) in rule.arg_types().iter().zip(obj_arg_locals)
-                            {
-                                let arg_local = match typ {
-                                    Type::Obj => obj_arg_local,
-                                    Type::Val(val_type) => {
-                                        let val_type_local =
-                                            self.builder.local(Type::Val(*val_type));
-                                        self.builder.exprs.push(Instr {
-                                            local: Some(val_type_local),
-                                            kind: InstrKind::FromObj(*val_type, obj_arg_local),
-                                        });
-                                        val_type_local
-                                    }
-                                };
-                                arg_locals.push(arg_local);
-                            }
-
-                            let ret_local = match rule.ret_type() {
-                                Type::Obj => self.builder.local(Type::Obj),
-                                Type::Val(val_type) => self.builder.local(Type::Val(val_type)),
-                            };
-                            let builtin_ctx = BuiltinIrGenCtx {
-                                builder: &mut self.builder,
-                                dest: ret_local,
-                            };
-                            match rule {
-                                BuiltinConversionRule::Unary { ir_gen, .. } => {
-                                    ir_gen(builtin_ctx, arg_locals[0])
-                                }
-                                BuiltinConversionRule::Binary { ir_gen, .. } => {
-                                    ir_gen(builtin_ctx, arg_locals[0], arg_locals[1])
-                                }
-                                BuiltinConversionRule::Ternary { ir_gen, .. } => {
-                                    ir_gen(builtin_ctx, arg_locals[0], arg_locals[1], arg_locals[2])
-                                }
-                            };
-
-                            let ret_obj_local = self.builder.local(Type::Obj);
-                            self.builder.exprs.push(Instr {
-                                local: Some(ret_obj_local),
-                                kind: match rule.ret_type() {
-                                    Type::Obj => InstrKind::Move(ret_local),
-                                    Type::Val(val_type) => InstrKind::ToObj(val_type, ret_local),
-                                },
-                            });
-                            phi_incoming_values.push(PhiIncomingValue {
-                                bb: then_bb_id,
-                                local: ret_obj_local,
-                            });
-
-                            self.builder.close_bb(TerminatorInstr::Jump(merge_bb_id));
-                            self.builder.current_bb_id = Some(else_bb_id);
-                        }
-
+                    } else if !has_rule && variadic.is_none() {
+                        self.gen_source_loc(ast.span);
                         let msg = self.builder.local(Type::Val(ValType::String));
                         self.builder.exprs.push(Instr {
                             local: Some(msg),
                             kind: InstrKind::String(format!(
-                                "{}: arg type mismatch. at {}\n",
-                                builtin.name(),
+                                "builtin args count mismatch. at {}\n",
                                 ast.span
                             )),
                         });
                         self.builder
                             .close_bb(TerminatorInstr::Exit(ExitInstr::Error(msg)));
-                        self.builder.current_bb_id = Some(merge_bb_id);
-                        self.builder.exprs.push(Instr {
-                            local: result,
-                            kind: InstrKind::Phi {
-                                incomings: phi_incoming_values,
-                                non_exhaustive: false,
-                            },
-                        });
+                    } else {
+                        // 引数は規則によらず一度だけ評価する
+                        let mut obj_arg_locals = Vec::new();
+                        for arg in args {
+                            let obj_arg_local = self.builder.local(Type::Obj);
+                            self.gen_exprs(Some(obj_arg_local), arg);
+                            obj_arg_locals.push(obj_arg_local);
+                        }
+
+                        if let Some(variadic) = variadic {
+                            self.gen_variadic_builtin_call(
+                                result,
+                                builtin,
+                                variadic,
+                                &obj_arg_locals,
+                                ast.span,
+                            );
+                        } else {
+                            self.gen_builtin_call(
+                                result,
+                                builtin,
+                                &obj_arg_locals,
+                                is_tail,
+                                ast.span,
+                            );
+                        }
                     }
                 } else {
                     let obj_func_local = self.builder.local(Type::Obj);
@@ -899,6 +1087,7 @@ impl<'a, 'b> FuncGenerator<'a, 'b> {
                     ));
 
                     self.builder.current_bb_id = Some(error_bb_id);
+                    self.gen_source_loc(ast.span);
                     let msg = self.builder.local(Type::Val(ValType::String));
                     self.builder.exprs.push(Instr {
                         local: Some(msg),
@@ -930,7 +1119,6 @@ impl<'a, 'b> FuncGenerator<'a, 'b> {
                         kind: InstrKind::VariadicArgs(args_locals),
                     });
 
-                    let is_tail = x.is_tail;
                     let call_closure = InstrCallClosure {
                         closure: closure_local,
                         args: vec![args_local],
@@ -943,6 +1131,7 @@ impl<'a, 'b> FuncGenerator<'a, 'b> {
                                 call_closure,
                             )));
                     } else {
+                        self.gen_source_loc(ast.span);
                         self.builder.exprs.push(Instr {
                             local: result,
                             kind: InstrKind::CallClosure(call_closure),
@@ -976,6 +1165,15 @@ impl<'a, 'b> FuncGenerator<'a, 'b> {
                 self.gen_exprs(result, exprs);
             }
             ast::Expr::Set(x, ast::Set { name, expr, .. }) => {
+                if let [
+                    Located {
+                        value: ast::Expr::Lambda(..) | ast::Expr::CaseLambda(..),
+                        ..
+                    },
+                ] = expr.as_slice()
+                {
+                    self.lambda_name = Some(name.value.clone());
+                }
                 match &x.var_id {
                     VarId::Local(id) => {
                         if self.module_generator.ast.x.box_vars.contains(id) {
@@ -1010,6 +1208,7 @@ impl<'a, 'b> FuncGenerator<'a, 'b> {
                         if let Some(_) = ast::Builtin::from_name(&name.value)
                             && !self.module_generator.config.allow_set_builtin
                         {
+                            self.gen_source_loc(ast.span);
                             let msg = self.builder.local(Type::Val(ValType::String));
                             self.builder.exprs.push(Instr {
                                 local: Some(msg),
@@ -1035,12 +1234,16 @@ impl<'a, 'b> FuncGenerator<'a, 'b> {
                         }
                     }
                 }
+                // 右辺を生成しなかった場合のため
+                self.lambda_name = None;
             }
             ast::Expr::Let(x, _) => *x,
             ast::Expr::LetStar(x, _) => *x,
             ast::Expr::LetRec(x, _) => *x,
+            ast::Expr::LetRecStar(x, _) => *x,
             ast::Expr::NamedLet(x, _, _) => *x,
             ast::Expr::Do(x, _) => *x,
+            ast::Expr::Delay(x, _) => *x,
             ast::Expr::And(x, _) => *x,
             ast::Expr::Or(x, _) => *x,
             ast::Expr::Vector(_, vec) => {
@@ -1064,6 +1267,13 @@ impl<'a, 'b> FuncGenerator<'a, 'b> {
                 let kind = match uvec.kind {
                     ast::UVectorKind::S64 => UVectorKind::S64,
                     ast::UVectorKind::F64 => UVectorKind::F64,
+                    ast::UVectorKind::U8 => UVectorKind::U8,
+                    ast::UVectorKind::S8 => UVectorKind::S8,
+                    ast::UVectorKind::U16 => UVectorKind::U16,
+                    ast::UVectorKind::S16 => UVectorKind::S16,
+                    ast::UVectorKind::U32 => UVectorKind::U32,
+                    ast::UVectorKind::S32 => UVectorKind::S32,
+                    ast::UVectorKind::F32 => UVectorKind::F32,
                 };
 
                 let mut element_obj_locals = Vec::new();
@@ -1104,6 +1314,7 @@ impl<'a, 'b> FuncGenerator<'a, 'b> {
                 ));
 
                 self.builder.current_bb_id = Some(else_bb_id);
+                self.gen_source_loc(ast.span);
                 let msg = self.builder.local(Type::Val(ValType::String));
                 self.builder.exprs.push(Instr {
                     local: Some(msg),
@@ -1158,8 +1369,20 @@ impl<'a, 'b> FuncGenerator<'a, 'b> {
                         let prev = self.uninitialized_vars.insert(*var_id, Vec::new());
                         debug_assert!(prev.is_none());
                     }
-                    self.gen_exprs(result, &set_group.exprs);
-                    for var_id in &set_group.var_ids {
+                    debug_assert_eq!(set_group.var_ids.len(), set_group.exprs.len());
+                    if set_group.exprs.is_empty() {
+                        self.gen_exprs(result, &set_group.exprs);
+                    }
+                    // 代入した時点でその変数をキャプチャしたクロージャの環境を設定する
+                    // letrec*や内部定義で、後の定義式からクロージャが呼び出される場合に必要
+                    for (i, (var_id, expr)) in set_group
+                        .var_ids
+                        .iter()
+                        .zip(set_group.exprs.iter())
+                        .enumerate()
+                    {
+                        let is_last = i + 1 == set_group.exprs.len();
+                        self.gen_expr(if is_last { result } else { None }, expr);
                         let captures = self.uninitialized_vars.remove(var_id).unwrap();
                         for capture in captures {
                             self.builder.exprs.push(Instr {
@@ -1178,34 +1401,462 @@ impl<'a, 'b> FuncGenerator<'a, 'b> {
         }
     }
 
-    fn gen_exprs(&mut self, result: Option<LocalId>, exprs: &[ast::LExpr<Final>]) {
-        if let Some((last, rest)) = exprs.split_last() {
-            for expr in rest {
-                self.gen_expr(None, expr);
+    // 評価済みの引数で組み込み関数を呼び出す。引数の数に合う規則が存在する必要がある
+    fn gen_builtin_call(
+        &mut self,
+        result: Option<LocalId>,
+        builtin: ast::Builtin,
+        obj_arg_locals: &[LocalId],
+        is_tail: bool,
+        span: Span,
+    ) {
+        let merge_bb_id = self.builder.bbs.allocate_key();
+        let mut phi_incoming_values = Vec::new();
+        for rule in BuiltinConversionRule::from_builtin(builtin)
+            .into_iter()
+            .filter(|rule| rule.arg_count() == obj_arg_locals.len())
+        {
+            let mut type_check_success_locals = Vec::new();
+            for (typ, &obj_arg_local) in rule.arg_types().iter().zip(obj_arg_locals) {
+                if let Type::Val(val_type) = typ {
+                    let type_check_success_local = self.builder.local(Type::Val(ValType::Bool));
+                    self.builder.exprs.push(Instr {
+                        local: Some(type_check_success_local),
+                        kind: InstrKind::Is(*val_type, obj_arg_local),
+                    });
+                    type_check_success_locals.push(type_check_success_local);
+                }
             }
-            self.gen_expr(result, last);
-        } else {
-            let val_type_local = self.builder.local(Type::Val(ValType::Nil));
-            self.builder.exprs.push(Instr {
-                local: Some(val_type_local),
-                kind: InstrKind::Nil,
-            });
+
+            let mut all_type_check_success_local = self.builder.local(Type::Val(ValType::Bool));
             self.builder.exprs.push(Instr {
-                local: result,
-                kind: InstrKind::ToObj(ValType::Nil, val_type_local),
+                local: Some(all_type_check_success_local),
+                kind: InstrKind::Bool(true),
             });
-        }
-    }
-}
+            for type_check_success_local in type_check_success_locals {
+                let new_all_type_check_success_local = self.builder.local(Type::Val(ValType::Bool));
+                self.builder.exprs.push(Instr {
+                    local: Some(new_all_type_check_success_local),
+                    kind: InstrKind::And(all_type_check_success_local, type_check_success_local),
+                });
+                all_type_check_success_local = new_all_type_check_success_local;
+            }
 
-#[derive(Debug)]
-pub struct BuiltinIrGenCtx<'a> {
-    builder: &'a mut IrFuncBuilder,
-    dest: LocalId,
-}
+            let then_bb_id = self.builder.bbs.allocate_key();
+            let else_bb_id = self.builder.bbs.allocate_key();
+
+            self.builder.close_bb(TerminatorInstr::If(
+                all_type_check_success_local,
+                then_bb_id,
+                else_bb_id,
+            ));
+
+            self.builder.current_bb_id = Some(then_bb_id);
+            // 0除算などランタイム関数内でエラーになることがある
+            self.gen_source_loc(span);
+
+            let mut arg_locals = Vec::new();
+            for (typ, &obj_arg_local) in rule.arg_types().iter().zip(obj_arg_locals) {
+                let arg_local = match typ {
+                    Type::Obj => obj_arg_local,
+                    Type::Val(val_type) => {
+                        let val_type_local = self.builder.local(Type::Val(*val_type));
+                        self.builder.exprs.push(Instr {
+                            local: Some(val_type_local),
+                            kind: InstrKind::FromObj(*val_type, obj_arg_local),
+                        });
+                        val_type_local
+                    }
+                };
+                arg_locals.push(arg_local);
+            }
+
+            let ret_local = match rule.ret_type() {
+                Type::Obj => self.builder.local(Type::Obj),
+                Type::Val(val_type) => self.builder.local(Type::Val(val_type)),
+            };
+            let builtin_ctx = BuiltinIrGenCtx {
+                builder: &mut self.builder,
+                dest: ret_local,
+                obj_args: obj_arg_locals,
+                is_tail,
+                merge: Some((merge_bb_id, &mut phi_incoming_values)),
+            };
+            match rule {
+                BuiltinConversionRule::Nullary { ir_gen, .. } => ir_gen(builtin_ctx),
+                BuiltinConversionRule::Unary { ir_gen, .. } => ir_gen(builtin_ctx, arg_locals[0]),
+                BuiltinConversionRule::Binary { ir_gen, .. } => {
+                    ir_gen(builtin_ctx, arg_locals[0], arg_locals[1])
+                }
+                BuiltinConversionRule::Ternary { ir_gen, .. } => {
+                    ir_gen(builtin_ctx, arg_locals[0], arg_locals[1], arg_locals[2])
+                }
+            };
+
+            // ir_genが末尾呼び出しで基本ブロックを閉じた場合は合流しない
+            if self.builder.current_bb_id.is_none() {
+                self.builder.current_bb_id = Some(else_bb_id);
+                continue;
+            }
+
+            let ret_obj_local = self.builder.local(Type::Obj);
+            self.builder.exprs.push(Instr {
+                local: Some(ret_obj_local),
+                kind: match rule.ret_type() {
+                    Type::Obj => InstrKind::Move(ret_local),
+                    Type::Val(val_type) => InstrKind::ToObj(val_type, ret_local),
+                },
+            });
+            phi_incoming_values.push(PhiIncomingValue {
+                // ir_genが基本ブロックを分割している可能性がある
+                bb: self.builder.current_bb_id.unwrap(),
+                local: ret_obj_local,
+            });
+
+            self.builder.close_bb(TerminatorInstr::Jump(merge_bb_id));
+            self.builder.current_bb_id = Some(else_bb_id);
+        }
+
+        // JITでは基本ブロックごとに別の関数になり得るため、エラーを起こす基本ブロック内で設定する
+        self.gen_source_loc(span);
+        let msg = self.builder.local(Type::Val(ValType::String));
+        self.builder.exprs.push(Instr {
+            local: Some(msg),
+            kind: InstrKind::String(format!(
+                "{}: arg type mismatch. at {}\n",
+                builtin.name(),
+                span
+            )),
+        });
+        self.builder
+            .close_bb(TerminatorInstr::Exit(ExitInstr::Error(msg)));
+        self.builder.current_bb_id = Some(merge_bb_id);
+        self.builder.exprs.push(Instr {
+            local: result,
+            kind: InstrKind::Phi {
+                incomings: phi_incoming_values,
+                non_exhaustive: false,
+            },
+        });
+    }
+
+    /*
+    define-record-typeの展開で使うレコードの組み込み関数
+    レコード型のIDとフィールドのインデックスはリテラルで受け取り、IDごとに別の型として扱う
+    (%make-record id type size), (%record-of? id obj), (%record-ref id obj index), (%record-set! id obj index value)
+    */
+    fn gen_record_builtin_call(
+        &mut self,
+        result: Option<LocalId>,
+        builtin: ast::Builtin,
+        args: &[Vec<ast::LExpr<Final>>],
+        span: Span,
+    ) {
+        let literal = |arg: &[ast::LExpr<Final>]| match arg {
+            [
+                Located {
+                    value: ast::Expr::Const(_, ast::Const::Int(i)),
+                    ..
+                },
+            ] => usize::try_from(*i).ok(),
+            _ => None,
+        };
+        let (literal_positions, arg_count): (&[usize], usize) = match builtin {
+            ast::Builtin::MakeRecord => (&[0, 2], 3),
+            ast::Builtin::IsRecordOf => (&[0], 2),
+            ast::Builtin::RecordRef => (&[0, 2], 3),
+            ast::Builtin::RecordSet => (&[0, 2], 4),
+            _ => unreachable!(),
+        };
+        let literals = literal_positions
+            .iter()
+            .map(|&i| args.get(i).and_then(|arg| literal(arg)))
+            .collect::<Option<Vec<_>>>();
+        let Some(literals) = literals.filter(|_| args.len() == arg_count) else {
+            self.gen_source_loc(span);
+            let msg = self.builder.local(Type::Val(ValType::String));
+            self.builder.exprs.push(Instr {
+                local: Some(msg),
+                kind: InstrKind::String(format!(
+                    "{}: record type id and field index must be literals. at {}\n",
+                    builtin.name(),
+                    span
+                )),
+            });
+            self.builder
+                .close_bb(TerminatorInstr::Exit(ExitInstr::Error(msg)));
+            return;
+        };
+        let record_type = ValType::Record(RecordTypeId::from(literals[0]));
+
+        let mut obj_arg_locals = Vec::new();
+        for (i, arg) in args.iter().enumerate() {
+            if !literal_positions.contains(&i) {
+                let obj_arg_local = self.builder.local(Type::Obj);
+                self.gen_exprs(Some(obj_arg_local), arg);
+                obj_arg_locals.push(obj_arg_local);
+            }
+        }
+
+        match builtin {
+            ast::Builtin::MakeRecord => {
+                let record_local = self.builder.local(record_type);
+                self.builder.exprs.push(Instr {
+                    local: Some(record_local),
+                    kind: InstrKind::MakeRecord(
+                        RecordTypeId::from(literals[0]),
+                        obj_arg_locals[0],
+                        literals[1],
+                    ),
+                });
+                self.builder.exprs.push(Instr {
+                    local: result,
+                    kind: InstrKind::ToObj(record_type, record_local),
+                });
+            }
+            ast::Builtin::IsRecordOf => {
+                let is_local = self.builder.local(Type::Val(ValType::Bool));
+                self.builder.exprs.push(Instr {
+                    local: Some(is_local),
+                    kind: InstrKind::Is(record_type, obj_arg_locals[0]),
+                });
+                self.builder.exprs.push(Instr {
+                    local: result,
+                    kind: InstrKind::ToObj(ValType::Bool, is_local),
+                });
+            }
+            ast::Builtin::RecordRef | ast::Builtin::RecordSet => {
+                // 型検査はIsで行うので、JITでレコード型が分かっていれば取り除かれる
+                let is_local = self.builder.local(Type::Val(ValType::Bool));
+                self.builder.exprs.push(Instr {
+                    local: Some(is_local),
+                    kind: InstrKind::Is(record_type, obj_arg_locals[0]),
+                });
+                let then_bb_id = self.builder.bbs.allocate_key();
+                let else_bb_id = self.builder.bbs.allocate_key();
+                self.builder
+                    .close_bb(TerminatorInstr::If(is_local, then_bb_id, else_bb_id));
+
+                self.builder.current_bb_id = Some(else_bb_id);
+                self.gen_source_loc(span);
+                let msg = self.builder.local(Type::Val(ValType::String));
+                self.builder.exprs.push(Instr {
+                    local: Some(msg),
+                    kind: InstrKind::String(format!(
+                        "{}: arg type mismatch. at {}\n",
+                        builtin.name(),
+                        span
+                    )),
+                });
+                self.builder
+                    .close_bb(TerminatorInstr::Exit(ExitInstr::Error(msg)));
+
+                self.builder.current_bb_id = Some(then_bb_id);
+                let record_local = self.builder.local(record_type);
+                self.builder.exprs.push(Instr {
+                    local: Some(record_local),
+                    kind: InstrKind::FromObj(record_type, obj_arg_locals[0]),
+                });
+                if builtin == ast::Builtin::RecordRef {
+                    self.builder.exprs.push(Instr {
+                        local: result,
+                        kind: InstrKind::RecordRef(record_local, literals[1]),
+                    });
+                } else {
+                    let nil_local = self.builder.local(Type::Val(ValType::Nil));
+                    self.builder.exprs.push(Instr {
+                        local: Some(nil_local),
+                        kind: InstrKind::RecordSet(record_local, literals[1], obj_arg_locals[1]),
+                    });
+                    self.builder.exprs.push(Instr {
+                        local: result,
+                        kind: InstrKind::ToObj(ValType::Nil, nil_local),
+                    });
+                }
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    // 可変長引数の呼び出しを二項演算の規則の組み合わせに展開する
+    fn gen_variadic_builtin_call(
+        &mut self,
+        result: Option<LocalId>,
+        builtin: ast::Builtin,
+        variadic: BuiltinVariadicRule,
+        obj_arg_locals: &[LocalId],
+        span: Span,
+    ) {
+        match (variadic, obj_arg_locals) {
+            (BuiltinVariadicRule::Fold { identity }, []) => {
+                let identity_local = self.builder.local(Type::Val(ValType::Int));
+                self.builder.exprs.push(Instr {
+                    local: Some(identity_local),
+                    kind: InstrKind::Int(identity),
+                });
+                self.builder.exprs.push(Instr {
+                    local: result,
+                    kind: InstrKind::ToObj(ValType::Int, identity_local),
+                });
+            }
+            (
+                BuiltinVariadicRule::Fold { identity }
+                | BuiltinVariadicRule::FoldInverse { identity },
+                &[arg],
+            ) => {
+                // 正確数と非正確数は混ぜられないので、単位元の正確性を引数に合わせる
+                let is_float_local = self.builder.local(Type::Val(ValType::Bool));
+                self.builder.exprs.push(Instr {
+                    local: Some(is_float_local),
+                    kind: InstrKind::Is(ValType::Float, arg),
+                });
+                let identity_obj_local = self.builder.local(Type::Obj);
+                gen_if_obj(
+                    BuiltinIrGenCtx {
+                        builder: &mut self.builder,
+                        dest: identity_obj_local,
+                        obj_args: &[],
+                        is_tail: false,
+                        merge: None,
+                    },
+                    is_float_local,
+                    |builder| {
+                        let identity_local = builder.local(Type::Val(ValType::Float));
+                        builder.exprs.push(Instr {
+                            local: Some(identity_local),
+                            kind: InstrKind::Float(NotNan::new(identity as f64).unwrap()),
+                        });
+                        InstrKind::ToObj(ValType::Float, identity_local)
+                    },
+                    |builder| {
+                        let identity_local = builder.local(Type::Val(ValType::Int));
+                        builder.exprs.push(Instr {
+                            local: Some(identity_local),
+                            kind: InstrKind::Int(identity),
+                        });
+                        InstrKind::ToObj(ValType::Int, identity_local)
+                    },
+                );
+                self.gen_builtin_call(result, builtin, &[identity_obj_local, arg], false, span);
+            }
+            (BuiltinVariadicRule::FoldSelf, &[arg]) => {
+                self.gen_builtin_call(result, builtin, &[arg, arg], false, span);
+            }
+            (
+                BuiltinVariadicRule::Fold { .. }
+                | BuiltinVariadicRule::FoldInverse { .. }
+                | BuiltinVariadicRule::FoldSelf,
+                [first, rest @ ..],
+            ) => {
+                let mut acc_local = *first;
+                for (i, &arg) in rest.iter().enumerate() {
+                    let dest = if i == rest.len() - 1 {
+                        result
+                    } else {
+                        Some(self.builder.local(Type::Obj))
+                    };
+                    self.gen_builtin_call(dest, builtin, &[acc_local, arg], false, span);
+                    if let Some(dest) = dest {
+                        acc_local = dest;
+                    }
+                }
+            }
+            (BuiltinVariadicRule::Chain, _) => {
+                let false_local = self.builder.local(Type::Val(ValType::Bool));
+                self.builder.exprs.push(Instr {
+                    local: Some(false_local),
+                    kind: InstrKind::Bool(false),
+                });
+                let false_obj_local = self.builder.local(Type::Obj);
+                self.builder.exprs.push(Instr {
+                    local: Some(false_obj_local),
+                    kind: InstrKind::ToObj(ValType::Bool, false_local),
+                });
+
+                let merge_bb_id = self.builder.bbs.allocate_key();
+                let mut phi_incoming_values = Vec::new();
+                for (i, pair) in obj_arg_locals.windows(2).enumerate() {
+                    let cmp_local = self.builder.local(Type::Obj);
+                    self.gen_builtin_call(Some(cmp_local), builtin, pair, false, span);
+                    if i == obj_arg_locals.len() - 2 {
+                        phi_incoming_values.push(PhiIncomingValue {
+                            bb: self.builder.current_bb_id.unwrap(),
+                            local: cmp_local,
+                        });
+                        self.builder.close_bb(TerminatorInstr::Jump(merge_bb_id));
+                    } else {
+                        // 偽になった時点で残りの比較は行わない
+                        let is_false_local = self.builder.local(Type::Val(ValType::Bool));
+                        self.builder.exprs.push(Instr {
+                            local: Some(is_false_local),
+                            kind: InstrKind::EqObj(cmp_local, false_obj_local),
+                        });
+                        let false_bb_id = self.builder.bbs.allocate_key();
+                        let next_bb_id = self.builder.bbs.allocate_key();
+                        self.builder.close_bb(TerminatorInstr::If(
+                            is_false_local,
+                            false_bb_id,
+                            next_bb_id,
+                        ));
+                        self.builder.current_bb_id = Some(false_bb_id);
+                        phi_incoming_values.push(PhiIncomingValue {
+                            bb: false_bb_id,
+                            local: false_obj_local,
+                        });
+                        self.builder.close_bb(TerminatorInstr::Jump(merge_bb_id));
+                        self.builder.current_bb_id = Some(next_bb_id);
+                    }
+                }
+                self.builder.current_bb_id = Some(merge_bb_id);
+                self.builder.exprs.push(Instr {
+                    local: result,
+                    kind: InstrKind::Phi {
+                        incomings: phi_incoming_values,
+                        non_exhaustive: false,
+                    },
+                });
+            }
+            _ => unreachable!("too few arguments for variadic builtin"),
+        }
+    }
+
+    fn gen_exprs(&mut self, result: Option<LocalId>, exprs: &[ast::LExpr<Final>]) {
+        if let Some((last, rest)) = exprs.split_last() {
+            for expr in rest {
+                self.gen_expr(None, expr);
+            }
+            self.gen_expr(result, last);
+        } else {
+            let val_type_local = self.builder.local(Type::Val(ValType::Nil));
+            self.builder.exprs.push(Instr {
+                local: Some(val_type_local),
+                kind: InstrKind::Nil,
+            });
+            self.builder.exprs.push(Instr {
+                local: result,
+                kind: InstrKind::ToObj(ValType::Nil, val_type_local),
+            });
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct BuiltinIrGenCtx<'a> {
+    builder: &'a mut IrFuncBuilder,
+    dest: LocalId,
+    obj_args: &'a [LocalId], // FromObjする前の引数
+    is_tail: bool,
+    // 組み込み関数呼び出しの合流点とそのPhiの入力。destを経由せずにobjの結果を渡すときに使う
+    merge: Option<(BasicBlockId, &'a mut Vec<PhiIncomingValue>)>,
+}
 
 #[derive(Debug, Clone, Copy)]
 pub enum BuiltinConversionRule {
+    Nullary {
+        args: [Type; 0],
+        ret: Type,
+        ir_gen: fn(BuiltinIrGenCtx),
+    },
     Unary {
         args: [Type; 1],
         ret: Type,
@@ -1227,6 +1878,7 @@ pub enum BuiltinConversionRule {
 impl BuiltinConversionRule {
     pub fn ret_type(self) -> Type {
         match self {
+            BuiltinConversionRule::Nullary { ret, .. } => ret,
             BuiltinConversionRule::Unary { ret, .. } => ret,
             BuiltinConversionRule::Binary { ret, .. } => ret,
             BuiltinConversionRule::Ternary { ret, .. } => ret,
@@ -1240,6 +1892,7 @@ impl BuiltinConversionRule {
 
     pub fn arg_types(&self) -> &[Type] {
         match self {
+            BuiltinConversionRule::Nullary { args, .. } => args,
             BuiltinConversionRule::Unary { args, .. } => args,
             BuiltinConversionRule::Binary { args, .. } => args,
             BuiltinConversionRule::Ternary { args, .. } => args,
@@ -1250,28 +1903,44 @@ impl BuiltinConversionRule {
         use ast::Builtin;
 
         match builtin {
-            Builtin::Display => vec![BuiltinConversionRule::Unary {
-                // TODO: 一旦Stringのみ
-                args: [Type::Val(ValType::String)],
-                ret: Type::Val(ValType::Nil),
-                ir_gen: |ctx, arg1| {
-                    ctx.builder.exprs.push(Instr {
-                        local: Some(ctx.dest),
-                        kind: InstrKind::Display(arg1),
-                    });
+            Builtin::Display => vec![
+                BuiltinConversionRule::Unary {
+                    // TODO: 一旦Stringのみ
+                    args: [Type::Val(ValType::String)],
+                    ret: Type::Val(ValType::Nil),
+                    ir_gen: |ctx, arg1| {
+                        ctx.builder.exprs.push(Instr {
+                            local: Some(ctx.dest),
+                            kind: InstrKind::Display(arg1),
+                        });
+                    },
                 },
-            }],
-            Builtin::Add => vec![
                 BuiltinConversionRule::Binary {
-                    args: [Type::Val(ValType::Int), Type::Val(ValType::Int)],
-                    ret: Type::Val(ValType::Int),
+                    args: [Type::Val(ValType::String), Type::Val(ValType::Port)],
+                    ret: Type::Val(ValType::Nil),
                     ir_gen: |ctx, arg1, arg2| {
                         ctx.builder.exprs.push(Instr {
                             local: Some(ctx.dest),
-                            kind: InstrKind::AddInt(arg1, arg2),
+                            kind: InstrKind::DisplayPort(arg1, arg2),
                         });
                     },
                 },
+            ],
+            Builtin::Add => [
+                BuiltinConversionRule::Binary {
+                    args: [Type::Val(ValType::Int), Type::Val(ValType::Int)],
+                    ret: Type::Val(ValType::Int),
+                    ir_gen: |ctx, arg1, arg2| {
+                        gen_int_op_with_overflow_check(
+                            ctx,
+                            arg1,
+                            arg2,
+                            InstrKind::AddInt,
+                            InstrKind::AddIntOverflow,
+                            InstrKind::AddInteger,
+                        );
+                    },
+                },
                 BuiltinConversionRule::Binary {
                     args: [Type::Val(ValType::Float), Type::Val(ValType::Float)],
                     ret: Type::Val(ValType::Float),
@@ -1282,16 +1951,34 @@ impl BuiltinConversionRule {
                         });
                     },
                 },
-            ],
-            Builtin::Sub => vec![
+            ]
+            .into_iter()
+            .chain(integer_rules(Type::Obj, |ctx, _, _| {
+                ctx.builder.exprs.push(Instr {
+                    local: Some(ctx.dest),
+                    kind: InstrKind::AddInteger(ctx.obj_args[0], ctx.obj_args[1]),
+                });
+            }))
+            .chain(rational_rules(Type::Obj, |ctx, _, _| {
+                ctx.builder.exprs.push(Instr {
+                    local: Some(ctx.dest),
+                    kind: InstrKind::AddRational(ctx.obj_args[0], ctx.obj_args[1]),
+                });
+            }))
+            .collect(),
+            Builtin::Sub => [
                 BuiltinConversionRule::Binary {
                     args: [Type::Val(ValType::Int), Type::Val(ValType::Int)],
                     ret: Type::Val(ValType::Int),
                     ir_gen: |ctx, arg1, arg2| {
-                        ctx.builder.exprs.push(Instr {
-                            local: Some(ctx.dest),
-                            kind: InstrKind::SubInt(arg1, arg2),
-                        });
+                        gen_int_op_with_overflow_check(
+                            ctx,
+                            arg1,
+                            arg2,
+                            InstrKind::SubInt,
+                            InstrKind::SubIntOverflow,
+                            InstrKind::SubInteger,
+                        );
                     },
                 },
                 BuiltinConversionRule::Binary {
@@ -1304,50 +1991,121 @@ impl BuiltinConversionRule {
                         });
                     },
                 },
-            ],
-            Builtin::Mul => vec![
+            ]
+            .into_iter()
+            .chain(integer_rules(Type::Obj, |ctx, _, _| {
+                ctx.builder.exprs.push(Instr {
+                    local: Some(ctx.dest),
+                    kind: InstrKind::SubInteger(ctx.obj_args[0], ctx.obj_args[1]),
+                });
+            }))
+            .chain(rational_rules(Type::Obj, |ctx, _, _| {
+                ctx.builder.exprs.push(Instr {
+                    local: Some(ctx.dest),
+                    kind: InstrKind::SubRational(ctx.obj_args[0], ctx.obj_args[1]),
+                });
+            }))
+            .collect(),
+            Builtin::Mul => [
                 BuiltinConversionRule::Binary {
                     args: [Type::Val(ValType::Int), Type::Val(ValType::Int)],
                     ret: Type::Val(ValType::Int),
+                    ir_gen: |ctx, arg1, arg2| {
+                        gen_int_op_with_overflow_check(
+                            ctx,
+                            arg1,
+                            arg2,
+                            InstrKind::MulInt,
+                            InstrKind::MulIntOverflow,
+                            InstrKind::MulInteger,
+                        );
+                    },
+                },
+                BuiltinConversionRule::Binary {
+                    args: [Type::Val(ValType::Float), Type::Val(ValType::Float)],
+                    ret: Type::Val(ValType::Float),
                     ir_gen: |ctx, arg1, arg2| {
                         ctx.builder.exprs.push(Instr {
                             local: Some(ctx.dest),
-                            kind: InstrKind::MulInt(arg1, arg2),
+                            kind: InstrKind::MulFloat(arg1, arg2),
                         });
                     },
                 },
+            ]
+            .into_iter()
+            .chain(integer_rules(Type::Obj, |ctx, _, _| {
+                ctx.builder.exprs.push(Instr {
+                    local: Some(ctx.dest),
+                    kind: InstrKind::MulInteger(ctx.obj_args[0], ctx.obj_args[1]),
+                });
+            }))
+            .chain(rational_rules(Type::Obj, |ctx, _, _| {
+                ctx.builder.exprs.push(Instr {
+                    local: Some(ctx.dest),
+                    kind: InstrKind::MulRational(ctx.obj_args[0], ctx.obj_args[1]),
+                });
+            }))
+            .collect(),
+            Builtin::Div => [
                 BuiltinConversionRule::Binary {
                     args: [Type::Val(ValType::Float), Type::Val(ValType::Float)],
                     ret: Type::Val(ValType::Float),
                     ir_gen: |ctx, arg1, arg2| {
                         ctx.builder.exprs.push(Instr {
                             local: Some(ctx.dest),
-                            kind: InstrKind::MulFloat(arg1, arg2),
+                            kind: InstrKind::DivFloat(arg1, arg2),
                         });
                     },
                 },
-            ],
-            Builtin::Div => vec![BuiltinConversionRule::Binary {
-                args: [Type::Val(ValType::Float), Type::Val(ValType::Float)],
-                ret: Type::Val(ValType::Float),
-                ir_gen: |ctx, arg1, arg2| {
-                    ctx.builder.exprs.push(Instr {
-                        local: Some(ctx.dest),
-                        kind: InstrKind::DivFloat(arg1, arg2),
-                    });
+                // 正確数同士の除算は割り切れなければrationalになる
+                BuiltinConversionRule::Binary {
+                    args: [Type::Val(ValType::Int), Type::Val(ValType::Int)],
+                    ret: Type::Obj,
+                    ir_gen: |ctx, _, _| {
+                        ctx.builder.exprs.push(Instr {
+                            local: Some(ctx.dest),
+                            kind: InstrKind::DivRational(ctx.obj_args[0], ctx.obj_args[1]),
+                        });
+                    },
                 },
-            }],
-            Builtin::Quotient => vec![BuiltinConversionRule::Binary {
+            ]
+            .into_iter()
+            .chain(integer_rules(Type::Obj, |ctx, _, _| {
+                ctx.builder.exprs.push(Instr {
+                    local: Some(ctx.dest),
+                    kind: InstrKind::DivRational(ctx.obj_args[0], ctx.obj_args[1]),
+                });
+            }))
+            .chain(rational_rules(Type::Obj, |ctx, _, _| {
+                ctx.builder.exprs.push(Instr {
+                    local: Some(ctx.dest),
+                    kind: InstrKind::DivRational(ctx.obj_args[0], ctx.obj_args[1]),
+                });
+            }))
+            .collect(),
+            Builtin::Quotient => [BuiltinConversionRule::Binary {
                 args: [Type::Val(ValType::Int), Type::Val(ValType::Int)],
                 ret: Type::Val(ValType::Int),
                 ir_gen: |ctx, arg1, arg2| {
-                    ctx.builder.exprs.push(Instr {
-                        local: Some(ctx.dest),
-                        kind: InstrKind::QuotientInt(arg1, arg2),
-                    });
+                    gen_int_op_with_overflow_check(
+                        ctx,
+                        arg1,
+                        arg2,
+                        InstrKind::QuotientInt,
+                        InstrKind::QuotientIntOverflow,
+                        InstrKind::QuotientInteger,
+                    );
                 },
-            }],
-            Builtin::Remainder => vec![BuiltinConversionRule::Binary {
+            }]
+            .into_iter()
+            .chain(integer_rules(Type::Obj, |ctx, _, _| {
+                ctx.builder.exprs.push(Instr {
+                    local: Some(ctx.dest),
+                    kind: InstrKind::QuotientInteger(ctx.obj_args[0], ctx.obj_args[1]),
+                });
+            }))
+            .collect(),
+            Builtin::Remainder => [BuiltinConversionRule::Binary {
                 args: [Type::Val(ValType::Int), Type::Val(ValType::Int)],
                 ret: Type::Val(ValType::Int),
                 ir_gen: |ctx, arg1, arg2| {
@@ -1356,8 +2114,16 @@ impl BuiltinConversionRule {
                         kind: InstrKind::RemainderInt(arg1, arg2),
                     });
                 },
-            }],
-            Builtin::Modulo => vec![BuiltinConversionRule::Binary {
+            }]
+            .into_iter()
+            .chain(integer_rules(Type::Obj, |ctx, _, _| {
+                ctx.builder.exprs.push(Instr {
+                    local: Some(ctx.dest),
+                    kind: InstrKind::RemainderInteger(ctx.obj_args[0], ctx.obj_args[1]),
+                });
+            }))
+            .collect(),
+            Builtin::Modulo => [BuiltinConversionRule::Binary {
                 args: [Type::Val(ValType::Int), Type::Val(ValType::Int)],
                 ret: Type::Val(ValType::Int),
                 ir_gen: |ctx, arg1, arg2| {
@@ -1366,773 +2132,2648 @@ impl BuiltinConversionRule {
                         kind: InstrKind::ModuloInt(arg1, arg2),
                     });
                 },
-            }],
-            Builtin::WriteChar => vec![BuiltinConversionRule::Unary {
-                args: [Type::Val(ValType::Char)],
-                ret: Type::Val(ValType::Nil),
-                ir_gen: |ctx, arg1| {
-                    ctx.builder.exprs.push(Instr {
-                        local: Some(ctx.dest),
-                        kind: InstrKind::WriteChar(arg1),
-                    });
-                },
-            }],
-            Builtin::IsPair => vec![BuiltinConversionRule::Unary {
-                args: [Type::Obj],
+            }]
+            .into_iter()
+            .chain(integer_rules(Type::Obj, |ctx, _, _| {
+                ctx.builder.exprs.push(Instr {
+                    local: Some(ctx.dest),
+                    kind: InstrKind::ModuloInteger(ctx.obj_args[0], ctx.obj_args[1]),
+                });
+            }))
+            .collect(),
+            Builtin::Numerator => vec![
+                BuiltinConversionRule::Unary {
+                    args: [Type::Val(ValType::Int)],
+                    ret: Type::Val(ValType::Int),
+                    ir_gen: |ctx, arg1| {
+                        ctx.builder.exprs.push(Instr {
+                            local: Some(ctx.dest),
+                            kind: InstrKind::Move(arg1),
+                        });
+                    },
+                },
+                BuiltinConversionRule::Unary {
+                    args: [Type::Val(ValType::BigInt)],
+                    ret: Type::Val(ValType::BigInt),
+                    ir_gen: |ctx, arg1| {
+                        ctx.builder.exprs.push(Instr {
+                            local: Some(ctx.dest),
+                            kind: InstrKind::Move(arg1),
+                        });
+                    },
+                },
+                BuiltinConversionRule::Unary {
+                    args: [Type::Val(ValType::Rational)],
+                    ret: Type::Obj,
+                    ir_gen: |ctx, arg1| {
+                        ctx.builder.exprs.push(Instr {
+                            local: Some(ctx.dest),
+                            kind: InstrKind::Numerator(arg1),
+                        });
+                    },
+                },
+            ],
+            Builtin::Denominator => vec![
+                BuiltinConversionRule::Unary {
+                    args: [Type::Val(ValType::Int)],
+                    ret: Type::Val(ValType::Int),
+                    ir_gen: |ctx, _| {
+                        ctx.builder.exprs.push(Instr {
+                            local: Some(ctx.dest),
+                            kind: InstrKind::Int(1),
+                        });
+                    },
+                },
+                BuiltinConversionRule::Unary {
+                    args: [Type::Val(ValType::BigInt)],
+                    ret: Type::Val(ValType::Int),
+                    ir_gen: |ctx, _| {
+                        ctx.builder.exprs.push(Instr {
+                            local: Some(ctx.dest),
+                            kind: InstrKind::Int(1),
+                        });
+                    },
+                },
+                BuiltinConversionRule::Unary {
+                    args: [Type::Val(ValType::Rational)],
+                    ret: Type::Obj,
+                    ir_gen: |ctx, arg1| {
+                        ctx.builder.exprs.push(Instr {
+                            local: Some(ctx.dest),
+                            kind: InstrKind::Denominator(arg1),
+                        });
+                    },
+                },
+            ],
+            Builtin::Floor => [
+                BuiltinConversionRule::Unary {
+                    args: [Type::Val(ValType::Float)],
+                    ret: Type::Val(ValType::Float),
+                    ir_gen: |ctx, arg1| {
+                        ctx.builder.exprs.push(Instr {
+                            local: Some(ctx.dest),
+                            kind: InstrKind::FloorFloat(arg1),
+                        });
+                    },
+                },
+                BuiltinConversionRule::Unary {
+                    args: [Type::Val(ValType::Rational)],
+                    ret: Type::Obj,
+                    ir_gen: |ctx, arg1| {
+                        ctx.builder.exprs.push(Instr {
+                            local: Some(ctx.dest),
+                            kind: InstrKind::FloorRational(arg1),
+                        });
+                    },
+                },
+            ]
+            .into_iter()
+            .chain(integer_identity_rules())
+            .collect(),
+            Builtin::Round => [
+                BuiltinConversionRule::Unary {
+                    args: [Type::Val(ValType::Float)],
+                    ret: Type::Val(ValType::Float),
+                    ir_gen: |ctx, arg1| {
+                        ctx.builder.exprs.push(Instr {
+                            local: Some(ctx.dest),
+                            kind: InstrKind::RoundFloat(arg1),
+                        });
+                    },
+                },
+                BuiltinConversionRule::Unary {
+                    args: [Type::Val(ValType::Rational)],
+                    ret: Type::Obj,
+                    ir_gen: |ctx, arg1| {
+                        ctx.builder.exprs.push(Instr {
+                            local: Some(ctx.dest),
+                            kind: InstrKind::RoundRational(arg1),
+                        });
+                    },
+                },
+            ]
+            .into_iter()
+            .chain(integer_identity_rules())
+            .collect(),
+            Builtin::Ceiling => [
+                BuiltinConversionRule::Unary {
+                    args: [Type::Val(ValType::Float)],
+                    ret: Type::Val(ValType::Float),
+                    ir_gen: |ctx, arg1| {
+                        ctx.builder.exprs.push(Instr {
+                            local: Some(ctx.dest),
+                            kind: InstrKind::CeilingFloat(arg1),
+                        });
+                    },
+                },
+                BuiltinConversionRule::Unary {
+                    args: [Type::Val(ValType::Rational)],
+                    ret: Type::Obj,
+                    ir_gen: |ctx, arg1| {
+                        ctx.builder.exprs.push(Instr {
+                            local: Some(ctx.dest),
+                            kind: InstrKind::CeilingRational(arg1),
+                        });
+                    },
+                },
+            ]
+            .into_iter()
+            .chain(integer_identity_rules())
+            .collect(),
+            Builtin::Truncate => [
+                BuiltinConversionRule::Unary {
+                    args: [Type::Val(ValType::Float)],
+                    ret: Type::Val(ValType::Float),
+                    ir_gen: |ctx, arg1| {
+                        ctx.builder.exprs.push(Instr {
+                            local: Some(ctx.dest),
+                            kind: InstrKind::TruncateFloat(arg1),
+                        });
+                    },
+                },
+                BuiltinConversionRule::Unary {
+                    args: [Type::Val(ValType::Rational)],
+                    ret: Type::Obj,
+                    ir_gen: |ctx, arg1| {
+                        ctx.builder.exprs.push(Instr {
+                            local: Some(ctx.dest),
+                            kind: InstrKind::TruncateRational(arg1),
+                        });
+                    },
+                },
+            ]
+            .into_iter()
+            .chain(integer_identity_rules())
+            .collect(),
+            Builtin::Abs => vec![
+                BuiltinConversionRule::Unary {
+                    args: [Type::Val(ValType::Int)],
+                    ret: Type::Obj,
+                    ir_gen: |ctx, arg1| {
+                        gen_abs_int(ctx, arg1);
+                    },
+                },
+                BuiltinConversionRule::Unary {
+                    args: [Type::Val(ValType::Float)],
+                    ret: Type::Val(ValType::Float),
+                    ir_gen: |ctx, arg1| {
+                        ctx.builder.exprs.push(Instr {
+                            local: Some(ctx.dest),
+                            kind: InstrKind::AbsFloat(arg1),
+                        });
+                    },
+                },
+                BuiltinConversionRule::Unary {
+                    args: [Type::Val(ValType::BigInt)],
+                    ret: Type::Obj,
+                    ir_gen: |ctx, _| {
+                        gen_abs_exact(ctx);
+                    },
+                },
+                BuiltinConversionRule::Unary {
+                    args: [Type::Val(ValType::Rational)],
+                    ret: Type::Obj,
+                    ir_gen: |ctx, _| {
+                        gen_abs_exact(ctx);
+                    },
+                },
+            ],
+            Builtin::Min => [
+                BuiltinConversionRule::Binary {
+                    args: [Type::Val(ValType::Int), Type::Val(ValType::Int)],
+                    ret: Type::Val(ValType::Int),
+                    ir_gen: |ctx, arg1, arg2| {
+                        ctx.builder.exprs.push(Instr {
+                            local: Some(ctx.dest),
+                            kind: InstrKind::MinInt(arg1, arg2),
+                        });
+                    },
+                },
+                BuiltinConversionRule::Binary {
+                    args: [Type::Val(ValType::Float), Type::Val(ValType::Float)],
+                    ret: Type::Val(ValType::Float),
+                    ir_gen: |ctx, arg1, arg2| {
+                        ctx.builder.exprs.push(Instr {
+                            local: Some(ctx.dest),
+                            kind: InstrKind::MinFloat(arg1, arg2),
+                        });
+                    },
+                },
+            ]
+            .into_iter()
+            .chain(integer_rules(Type::Obj, |ctx, _, _| {
+                gen_select_exact(ctx, InstrKind::LtInt);
+            }))
+            .chain(rational_rules(Type::Obj, |ctx, _, _| {
+                gen_select_exact(ctx, InstrKind::LtInt);
+            }))
+            .collect(),
+            Builtin::Max => [
+                BuiltinConversionRule::Binary {
+                    args: [Type::Val(ValType::Int), Type::Val(ValType::Int)],
+                    ret: Type::Val(ValType::Int),
+                    ir_gen: |ctx, arg1, arg2| {
+                        ctx.builder.exprs.push(Instr {
+                            local: Some(ctx.dest),
+                            kind: InstrKind::MaxInt(arg1, arg2),
+                        });
+                    },
+                },
+                BuiltinConversionRule::Binary {
+                    args: [Type::Val(ValType::Float), Type::Val(ValType::Float)],
+                    ret: Type::Val(ValType::Float),
+                    ir_gen: |ctx, arg1, arg2| {
+                        ctx.builder.exprs.push(Instr {
+                            local: Some(ctx.dest),
+                            kind: InstrKind::MaxFloat(arg1, arg2),
+                        });
+                    },
+                },
+            ]
+            .into_iter()
+            .chain(integer_rules(Type::Obj, |ctx, _, _| {
+                gen_select_exact(ctx, InstrKind::GtInt);
+            }))
+            .chain(rational_rules(Type::Obj, |ctx, _, _| {
+                gen_select_exact(ctx, InstrKind::GtInt);
+            }))
+            .collect(),
+            Builtin::Gcd => [BuiltinConversionRule::Binary {
+                args: [Type::Val(ValType::Int), Type::Val(ValType::Int)],
+                ret: Type::Val(ValType::Int),
+                ir_gen: |ctx, arg1, arg2| {
+                    gen_int_op_with_overflow_check(
+                        ctx,
+                        arg1,
+                        arg2,
+                        InstrKind::GcdInt,
+                        InstrKind::GcdIntOverflow,
+                        InstrKind::GcdInteger,
+                    );
+                },
+            }]
+            .into_iter()
+            .chain(integer_rules(Type::Obj, |ctx, _, _| {
+                ctx.builder.exprs.push(Instr {
+                    local: Some(ctx.dest),
+                    kind: InstrKind::GcdInteger(ctx.obj_args[0], ctx.obj_args[1]),
+                });
+            }))
+            .collect(),
+            Builtin::Lcm => [BuiltinConversionRule::Binary {
+                args: [Type::Val(ValType::Int), Type::Val(ValType::Int)],
+                ret: Type::Val(ValType::Int),
+                ir_gen: |ctx, arg1, arg2| {
+                    gen_int_op_with_overflow_check(
+                        ctx,
+                        arg1,
+                        arg2,
+                        InstrKind::LcmInt,
+                        InstrKind::LcmIntOverflow,
+                        InstrKind::LcmInteger,
+                    );
+                },
+            }]
+            .into_iter()
+            .chain(integer_rules(Type::Obj, |ctx, _, _| {
+                ctx.builder.exprs.push(Instr {
+                    local: Some(ctx.dest),
+                    kind: InstrKind::LcmInteger(ctx.obj_args[0], ctx.obj_args[1]),
+                });
+            }))
+            .collect(),
+            Builtin::Expt => [
+                // 指数が負の場合もオーバーフローとして扱い、有理数の演算にする
+                BuiltinConversionRule::Binary {
+                    args: [Type::Val(ValType::Int), Type::Val(ValType::Int)],
+                    ret: Type::Val(ValType::Int),
+                    ir_gen: |ctx, arg1, arg2| {
+                        gen_int_op_with_overflow_check(
+                            ctx,
+                            arg1,
+                            arg2,
+                            InstrKind::ExptInt,
+                            InstrKind::ExptIntOverflow,
+                            InstrKind::ExptRational,
+                        );
+                    },
+                },
+                BuiltinConversionRule::Binary {
+                    args: [Type::Val(ValType::Float), Type::Val(ValType::Float)],
+                    ret: Type::Val(ValType::Float),
+                    ir_gen: |ctx, arg1, arg2| {
+                        ctx.builder.exprs.push(Instr {
+                            local: Some(ctx.dest),
+                            kind: InstrKind::ExptFloat(arg1, arg2),
+                        });
+                    },
+                },
+            ]
+            .into_iter()
+            .chain(integer_rules(Type::Obj, |ctx, _, _| {
+                ctx.builder.exprs.push(Instr {
+                    local: Some(ctx.dest),
+                    kind: InstrKind::ExptRational(ctx.obj_args[0], ctx.obj_args[1]),
+                });
+            }))
+            .chain(rational_rules(Type::Obj, |ctx, _, _| {
+                ctx.builder.exprs.push(Instr {
+                    local: Some(ctx.dest),
+                    kind: InstrKind::ExptRational(ctx.obj_args[0], ctx.obj_args[1]),
+                });
+            }))
+            .collect(),
+            Builtin::ExactIntegerSqrt => vec![
+                BuiltinConversionRule::Unary {
+                    args: [Type::Val(ValType::Int)],
+                    ret: Type::Val(ValType::Int),
+                    ir_gen: |ctx, arg1| {
+                        ctx.builder.exprs.push(Instr {
+                            local: Some(ctx.dest),
+                            kind: InstrKind::ExactIntegerSqrtInt(arg1),
+                        });
+                    },
+                },
+                BuiltinConversionRule::Unary {
+                    args: [Type::Val(ValType::BigInt)],
+                    ret: Type::Obj,
+                    ir_gen: |ctx, _| {
+                        ctx.builder.exprs.push(Instr {
+                            local: Some(ctx.dest),
+                            kind: InstrKind::ExactIntegerSqrtInteger(ctx.obj_args[0]),
+                        });
+                    },
+                },
+            ],
+            Builtin::Sqrt => float_math_rules(|ctx, arg1| {
+                gen_float_math(ctx, arg1, InstrKind::SqrtFloat);
+            }),
+            Builtin::Exp => float_math_rules(|ctx, arg1| {
+                gen_float_math(ctx, arg1, InstrKind::ExpFloat);
+            }),
+            Builtin::Sin => float_math_rules(|ctx, arg1| {
+                gen_float_math(ctx, arg1, InstrKind::SinFloat);
+            }),
+            Builtin::Cos => float_math_rules(|ctx, arg1| {
+                gen_float_math(ctx, arg1, InstrKind::CosFloat);
+            }),
+            Builtin::Tan => float_math_rules(|ctx, arg1| {
+                gen_float_math(ctx, arg1, InstrKind::TanFloat);
+            }),
+            Builtin::Asin => float_math_rules(|ctx, arg1| {
+                gen_float_math(ctx, arg1, InstrKind::AsinFloat);
+            }),
+            Builtin::Acos => float_math_rules(|ctx, arg1| {
+                gen_float_math(ctx, arg1, InstrKind::AcosFloat);
+            }),
+            // (log z1 z2)はz2を底とする対数
+            Builtin::Log => float_math_rules(|ctx, arg1| {
+                gen_float_math(ctx, arg1, InstrKind::LogFloat);
+            })
+            .into_iter()
+            .chain(float_math_binary_rules(|mut ctx, arg1, arg2| {
+                let [x, y] = gen_to_floats(&mut ctx, [arg1, arg2]);
+                let log_x = ctx.builder.local(Type::Val(ValType::Float));
+                let log_y = ctx.builder.local(Type::Val(ValType::Float));
+                ctx.builder.exprs.push(Instr {
+                    local: Some(log_x),
+                    kind: InstrKind::LogFloat(x),
+                });
+                ctx.builder.exprs.push(Instr {
+                    local: Some(log_y),
+                    kind: InstrKind::LogFloat(y),
+                });
+                ctx.builder.exprs.push(Instr {
+                    local: Some(ctx.dest),
+                    kind: InstrKind::DivFloat(log_x, log_y),
+                });
+            }))
+            .collect(),
+            Builtin::Atan => float_math_rules(|ctx, arg1| {
+                gen_float_math(ctx, arg1, InstrKind::AtanFloat);
+            })
+            .into_iter()
+            .chain(float_math_binary_rules(|mut ctx, arg1, arg2| {
+                let [y, x] = gen_to_floats(&mut ctx, [arg1, arg2]);
+                ctx.builder.exprs.push(Instr {
+                    local: Some(ctx.dest),
+                    kind: InstrKind::Atan2Float(y, x),
+                });
+            }))
+            .collect(),
+            Builtin::IsExact => exactness_rules(
+                |ctx, _| {
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(ctx.dest),
+                        kind: InstrKind::Bool(true),
+                    });
+                },
+                |ctx, _| {
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(ctx.dest),
+                        kind: InstrKind::Bool(false),
+                    });
+                },
+            ),
+            Builtin::IsInexact => exactness_rules(
+                |ctx, _| {
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(ctx.dest),
+                        kind: InstrKind::Bool(false),
+                    });
+                },
+                |ctx, _| {
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(ctx.dest),
+                        kind: InstrKind::Bool(true),
+                    });
+                },
+            ),
+            Builtin::ExactToInexact => vec![
+                BuiltinConversionRule::Unary {
+                    args: [Type::Val(ValType::Int)],
+                    ret: Type::Val(ValType::Float),
+                    ir_gen: |ctx, arg1| {
+                        ctx.builder.exprs.push(Instr {
+                            local: Some(ctx.dest),
+                            kind: InstrKind::IntToFloat(arg1),
+                        });
+                    },
+                },
+                BuiltinConversionRule::Unary {
+                    args: [Type::Val(ValType::Float)],
+                    ret: Type::Val(ValType::Float),
+                    ir_gen: |ctx, arg1| {
+                        ctx.builder.exprs.push(Instr {
+                            local: Some(ctx.dest),
+                            kind: InstrKind::Move(arg1),
+                        });
+                    },
+                },
+                BuiltinConversionRule::Unary {
+                    args: [Type::Val(ValType::BigInt)],
+                    ret: Type::Val(ValType::Float),
+                    ir_gen: |ctx, _| {
+                        ctx.builder.exprs.push(Instr {
+                            local: Some(ctx.dest),
+                            kind: InstrKind::RationalToFloat(ctx.obj_args[0]),
+                        });
+                    },
+                },
+                BuiltinConversionRule::Unary {
+                    args: [Type::Val(ValType::Rational)],
+                    ret: Type::Val(ValType::Float),
+                    ir_gen: |ctx, _| {
+                        ctx.builder.exprs.push(Instr {
+                            local: Some(ctx.dest),
+                            kind: InstrKind::RationalToFloat(ctx.obj_args[0]),
+                        });
+                    },
+                },
+            ],
+            Builtin::InexactToExact => [BuiltinConversionRule::Unary {
+                args: [Type::Val(ValType::Float)],
+                ret: Type::Obj,
+                ir_gen: |ctx, arg1| {
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(ctx.dest),
+                        kind: InstrKind::FloatToExact(arg1),
+                    });
+                },
+            }]
+            .into_iter()
+            .chain(integer_identity_rules())
+            .chain([BuiltinConversionRule::Unary {
+                args: [Type::Val(ValType::Rational)],
+                ret: Type::Val(ValType::Rational),
+                ir_gen: |ctx, arg1| {
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(ctx.dest),
+                        kind: InstrKind::Move(arg1),
+                    });
+                },
+            }])
+            .collect(),
+            Builtin::WriteChar => vec![
+                BuiltinConversionRule::Unary {
+                    args: [Type::Val(ValType::Char)],
+                    ret: Type::Val(ValType::Nil),
+                    ir_gen: |ctx, arg1| {
+                        ctx.builder.exprs.push(Instr {
+                            local: Some(ctx.dest),
+                            kind: InstrKind::WriteChar(arg1),
+                        });
+                    },
+                },
+                BuiltinConversionRule::Binary {
+                    args: [Type::Val(ValType::Char), Type::Val(ValType::Port)],
+                    ret: Type::Val(ValType::Nil),
+                    ir_gen: |ctx, arg1, arg2| {
+                        ctx.builder.exprs.push(Instr {
+                            local: Some(ctx.dest),
+                            kind: InstrKind::WriteCharPort(arg1, arg2),
+                        });
+                    },
+                },
+            ],
+            Builtin::IsPair => vec![BuiltinConversionRule::Unary {
+                args: [Type::Obj],
+                ret: Type::Val(ValType::Bool),
+                ir_gen: |ctx, arg1| {
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(ctx.dest),
+                        kind: InstrKind::Is(ValType::Cons, arg1),
+                    });
+                },
+            }],
+            Builtin::IsSymbol => vec![BuiltinConversionRule::Unary {
+                args: [Type::Obj],
+                ret: Type::Val(ValType::Bool),
+                ir_gen: |ctx, arg1| {
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(ctx.dest),
+                        kind: InstrKind::Is(ValType::Symbol, arg1),
+                    });
+                },
+            }],
+            Builtin::IsString => vec![BuiltinConversionRule::Unary {
+                args: [Type::Obj],
+                ret: Type::Val(ValType::Bool),
+                ir_gen: |ctx, arg1| {
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(ctx.dest),
+                        kind: InstrKind::Is(ValType::String, arg1),
+                    });
+                },
+            }],
+            Builtin::IsNumber => vec![BuiltinConversionRule::Unary {
+                args: [Type::Obj],
+                ret: Type::Val(ValType::Bool),
+                ir_gen: |ctx, arg1| {
+                    let is_int_local = ctx.builder.locals.push_with(|id| Local {
+                        id,
+                        typ: ValType::Bool.into(),
+                    });
+                    let is_float_local = ctx.builder.locals.push_with(|id| Local {
+                        id,
+                        typ: ValType::Bool.into(),
+                    });
+                    let is_bigint_local = ctx.builder.locals.push_with(|id| Local {
+                        id,
+                        typ: ValType::Bool.into(),
+                    });
+                    let is_rational_local = ctx.builder.locals.push_with(|id| Local {
+                        id,
+                        typ: ValType::Bool.into(),
+                    });
+                    let is_int_or_float_local = ctx.builder.locals.push_with(|id| Local {
+                        id,
+                        typ: ValType::Bool.into(),
+                    });
+                    let is_exact_or_float_local = ctx.builder.locals.push_with(|id| Local {
+                        id,
+                        typ: ValType::Bool.into(),
+                    });
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(is_int_local),
+                        kind: InstrKind::Is(ValType::Int, arg1),
+                    });
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(is_float_local),
+                        kind: InstrKind::Is(ValType::Float, arg1),
+                    });
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(is_bigint_local),
+                        kind: InstrKind::Is(ValType::BigInt, arg1),
+                    });
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(is_rational_local),
+                        kind: InstrKind::Is(ValType::Rational, arg1),
+                    });
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(is_int_or_float_local),
+                        kind: InstrKind::Or(is_int_local, is_float_local),
+                    });
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(is_exact_or_float_local),
+                        kind: InstrKind::Or(is_int_or_float_local, is_bigint_local),
+                    });
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(ctx.dest),
+                        kind: InstrKind::Or(is_exact_or_float_local, is_rational_local),
+                    });
+                },
+            }],
+            Builtin::IsBoolean => vec![BuiltinConversionRule::Unary {
+                args: [Type::Obj],
+                ret: Type::Val(ValType::Bool),
+                ir_gen: |ctx, arg1| {
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(ctx.dest),
+                        kind: InstrKind::Is(ValType::Bool, arg1),
+                    });
+                },
+            }],
+            Builtin::IsProcedure => vec![BuiltinConversionRule::Unary {
+                args: [Type::Obj],
+                ret: Type::Val(ValType::Bool),
+                ir_gen: |ctx, arg1| {
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(ctx.dest),
+                        kind: InstrKind::Is(ValType::Closure, arg1),
+                    });
+                },
+            }],
+            Builtin::IsChar => vec![BuiltinConversionRule::Unary {
+                args: [Type::Obj],
+                ret: Type::Val(ValType::Bool),
+                ir_gen: |ctx, arg1| {
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(ctx.dest),
+                        kind: InstrKind::Is(ValType::Char, arg1),
+                    });
+                },
+            }],
+            Builtin::IsVector => vec![BuiltinConversionRule::Unary {
+                args: [Type::Obj],
+                ret: Type::Val(ValType::Bool),
+                ir_gen: |ctx, arg1| {
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(ctx.dest),
+                        kind: InstrKind::Is(ValType::Vector, arg1),
+                    });
+                },
+            }],
+            Builtin::VectorLength => vec![BuiltinConversionRule::Unary {
+                args: [Type::Val(ValType::Vector)],
+                ret: Type::Val(ValType::Int),
+                ir_gen: |ctx, arg1| {
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(ctx.dest),
+                        kind: InstrKind::VectorLength(arg1),
+                    });
+                },
+            }],
+            Builtin::VectorRef => vec![BuiltinConversionRule::Binary {
+                args: [Type::Val(ValType::Vector), Type::Val(ValType::Int)],
+                ret: Type::Obj,
+                ir_gen: |ctx, arg1, arg2| {
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(ctx.dest),
+                        kind: InstrKind::VectorRef(arg1, arg2),
+                    });
+                },
+            }],
+            Builtin::VectorSet => vec![BuiltinConversionRule::Ternary {
+                args: [
+                    Type::Val(ValType::Vector),
+                    Type::Val(ValType::Int),
+                    Type::Obj,
+                ],
+                ret: Type::Val(ValType::Nil),
+                ir_gen: |ctx, arg1, arg2, arg3| {
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(ctx.dest),
+                        kind: InstrKind::VectorSet(arg1, arg2, arg3),
+                    });
+                },
+            }],
+            Builtin::MakeVector => vec![BuiltinConversionRule::Unary {
+                args: [Type::Val(ValType::Int)],
+                ret: Type::Val(ValType::Vector),
+                ir_gen: |ctx, arg1| {
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(ctx.dest),
+                        kind: InstrKind::MakeVector(arg1),
+                    });
+                },
+            }],
+            Builtin::IsS64Vector => vec![BuiltinConversionRule::Unary {
+                args: [Type::Obj],
+                ret: Type::Val(ValType::Bool),
+                ir_gen: |ctx, arg1| {
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(ctx.dest),
+                        kind: InstrKind::Is(ValType::UVector(UVectorKind::S64), arg1),
+                    });
+                },
+            }],
+            Builtin::IsF64Vector => vec![BuiltinConversionRule::Unary {
+                args: [Type::Obj],
+                ret: Type::Val(ValType::Bool),
+                ir_gen: |ctx, arg1| {
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(ctx.dest),
+                        kind: InstrKind::Is(ValType::UVector(UVectorKind::F64), arg1),
+                    });
+                },
+            }],
+            Builtin::IsU8Vector => vec![BuiltinConversionRule::Unary {
+                args: [Type::Obj],
+                ret: Type::Val(ValType::Bool),
+                ir_gen: |ctx, arg1| {
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(ctx.dest),
+                        kind: InstrKind::Is(ValType::UVector(UVectorKind::U8), arg1),
+                    });
+                },
+            }],
+            Builtin::IsS8Vector => vec![BuiltinConversionRule::Unary {
+                args: [Type::Obj],
+                ret: Type::Val(ValType::Bool),
+                ir_gen: |ctx, arg1| {
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(ctx.dest),
+                        kind: InstrKind::Is(ValType::UVector(UVectorKind::S8), arg1),
+                    });
+                },
+            }],
+            Builtin::IsU16Vector => vec![BuiltinConversionRule::Unary {
+                args: [Type::Obj],
+                ret: Type::Val(ValType::Bool),
+                ir_gen: |ctx, arg1| {
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(ctx.dest),
+                        kind: InstrKind::Is(ValType::UVector(UVectorKind::U16), arg1),
+                    });
+                },
+            }],
+            Builtin::IsS16Vector => vec![BuiltinConversionRule::Unary {
+                args: [Type::Obj],
+                ret: Type::Val(ValType::Bool),
+                ir_gen: |ctx, arg1| {
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(ctx.dest),
+                        kind: InstrKind::Is(ValType::UVector(UVectorKind::S16), arg1),
+                    });
+                },
+            }],
+            Builtin::IsU32Vector => vec![BuiltinConversionRule::Unary {
+                args: [Type::Obj],
+                ret: Type::Val(ValType::Bool),
+                ir_gen: |ctx, arg1| {
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(ctx.dest),
+                        kind: InstrKind::Is(ValType::UVector(UVectorKind::U32), arg1),
+                    });
+                },
+            }],
+            Builtin::IsS32Vector => vec![BuiltinConversionRule::Unary {
+                args: [Type::Obj],
+                ret: Type::Val(ValType::Bool),
+                ir_gen: |ctx, arg1| {
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(ctx.dest),
+                        kind: InstrKind::Is(ValType::UVector(UVectorKind::S32), arg1),
+                    });
+                },
+            }],
+            Builtin::IsF32Vector => vec![BuiltinConversionRule::Unary {
+                args: [Type::Obj],
+                ret: Type::Val(ValType::Bool),
+                ir_gen: |ctx, arg1| {
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(ctx.dest),
+                        kind: InstrKind::Is(ValType::UVector(UVectorKind::F32), arg1),
+                    });
+                },
+            }],
+            Builtin::IsUVector => vec![BuiltinConversionRule::Unary {
+                args: [Type::Obj],
+                ret: Type::Val(ValType::Bool),
+                ir_gen: |ctx, arg1| {
+                    // 全ての種類のuvectorについての判定のorを取る
+                    let mut result = None;
+                    for kind in UVectorKind::ALL {
+                        let is_local = ctx.builder.local(Type::Val(ValType::Bool));
+                        ctx.builder.exprs.push(Instr {
+                            local: Some(is_local),
+                            kind: InstrKind::Is(ValType::UVector(kind), arg1),
+                        });
+                        result = Some(match result {
+                            None => is_local,
+                            Some(prev) => {
+                                let or_local = ctx.builder.local(Type::Val(ValType::Bool));
+                                ctx.builder.exprs.push(Instr {
+                                    local: Some(or_local),
+                                    kind: InstrKind::Or(prev, is_local),
+                                });
+                                or_local
+                            }
+                        });
+                    }
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(ctx.dest),
+                        kind: InstrKind::Move(result.unwrap()),
+                    });
+                },
+            }],
+            Builtin::MakeS64Vector => vec![BuiltinConversionRule::Unary {
+                args: [Type::Val(ValType::Int)],
+                ret: Type::Val(ValType::UVector(UVectorKind::S64)),
+                ir_gen: |ctx, arg1| {
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(ctx.dest),
+                        kind: InstrKind::MakeUVector(UVectorKind::S64, arg1),
+                    });
+                },
+            }],
+            Builtin::MakeF64Vector => vec![BuiltinConversionRule::Unary {
+                args: [Type::Val(ValType::Int)],
+                ret: Type::Val(ValType::UVector(UVectorKind::F64)),
+                ir_gen: |ctx, arg1| {
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(ctx.dest),
+                        kind: InstrKind::MakeUVector(UVectorKind::F64, arg1),
+                    });
+                },
+            }],
+            Builtin::MakeU8Vector => vec![BuiltinConversionRule::Unary {
+                args: [Type::Val(ValType::Int)],
+                ret: Type::Val(ValType::UVector(UVectorKind::U8)),
+                ir_gen: |ctx, arg1| {
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(ctx.dest),
+                        kind: InstrKind::MakeUVector(UVectorKind::U8, arg1),
+                    });
+                },
+            }],
+            Builtin::MakeS8Vector => vec![BuiltinConversionRule::Unary {
+                args: [Type::Val(ValType::Int)],
+                ret: Type::Val(ValType::UVector(UVectorKind::S8)),
+                ir_gen: |ctx, arg1| {
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(ctx.dest),
+                        kind: InstrKind::MakeUVector(UVectorKind::S8, arg1),
+                    });
+                },
+            }],
+            Builtin::MakeU16Vector => vec![BuiltinConversionRule::Unary {
+                args: [Type::Val(ValType::Int)],
+                ret: Type::Val(ValType::UVector(UVectorKind::U16)),
+                ir_gen: |ctx, arg1| {
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(ctx.dest),
+                        kind: InstrKind::MakeUVector(UVectorKind::U16, arg1),
+                    });
+                },
+            }],
+            Builtin::MakeS16Vector => vec![BuiltinConversionRule::Unary {
+                args: [Type::Val(ValType::Int)],
+                ret: Type::Val(ValType::UVector(UVectorKind::S16)),
+                ir_gen: |ctx, arg1| {
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(ctx.dest),
+                        kind: InstrKind::MakeUVector(UVectorKind::S16, arg1),
+                    });
+                },
+            }],
+            Builtin::MakeU32Vector => vec![BuiltinConversionRule::Unary {
+                args: [Type::Val(ValType::Int)],
+                ret: Type::Val(ValType::UVector(UVectorKind::U32)),
+                ir_gen: |ctx, arg1| {
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(ctx.dest),
+                        kind: InstrKind::MakeUVector(UVectorKind::U32, arg1),
+                    });
+                },
+            }],
+            Builtin::MakeS32Vector => vec![BuiltinConversionRule::Unary {
+                args: [Type::Val(ValType::Int)],
+                ret: Type::Val(ValType::UVector(UVectorKind::S32)),
+                ir_gen: |ctx, arg1| {
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(ctx.dest),
+                        kind: InstrKind::MakeUVector(UVectorKind::S32, arg1),
+                    });
+                },
+            }],
+            Builtin::MakeF32Vector => vec![BuiltinConversionRule::Unary {
+                args: [Type::Val(ValType::Int)],
+                ret: Type::Val(ValType::UVector(UVectorKind::F32)),
+                ir_gen: |ctx, arg1| {
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(ctx.dest),
+                        kind: InstrKind::MakeUVector(UVectorKind::F32, arg1),
+                    });
+                },
+            }],
+            Builtin::UVectorLength => uvector_length_rules(&UVectorKind::ALL),
+            Builtin::UVectorRef => uvector_ref_rules(&UVectorKind::ALL),
+            Builtin::UVectorSet => uvector_set_rules(&UVectorKind::ALL),
+            Builtin::IsBytevector => vec![BuiltinConversionRule::Unary {
+                args: [Type::Obj],
+                ret: Type::Val(ValType::Bool),
+                ir_gen: |ctx, arg1| {
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(ctx.dest),
+                        kind: InstrKind::Is(ValType::UVector(UVectorKind::U8), arg1),
+                    });
+                },
+            }],
+            Builtin::BytevectorLength => uvector_length_rules(&[UVectorKind::U8]),
+            Builtin::BytevectorU8Ref => uvector_ref_rules(&[UVectorKind::U8]),
+            Builtin::BytevectorU8Set => uvector_set_rules(&[UVectorKind::U8]),
+            Builtin::Utf8ToString => vec![BuiltinConversionRule::Unary {
+                args: [Type::Val(ValType::UVector(UVectorKind::U8))],
+                ret: Type::Val(ValType::String),
+                ir_gen: |ctx, arg1| {
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(ctx.dest),
+                        kind: InstrKind::Utf8ToString(arg1),
+                    });
+                },
+            }],
+            Builtin::StringToUtf8 => vec![BuiltinConversionRule::Unary {
+                args: [Type::Val(ValType::String)],
+                ret: Type::Val(ValType::UVector(UVectorKind::U8)),
+                ir_gen: |ctx, arg1| {
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(ctx.dest),
+                        kind: InstrKind::StringToUtf8(arg1),
+                    });
+                },
+            }],
+            Builtin::Eq => vec![BuiltinConversionRule::Binary {
+                args: [Type::Obj, Type::Obj],
+                ret: Type::Val(ValType::Bool),
+                ir_gen: |ctx, arg1, arg2| {
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(ctx.dest),
+                        kind: InstrKind::EqObj(arg1, arg2),
+                    });
+                },
+            }],
+            Builtin::Eqv => vec![BuiltinConversionRule::Binary {
+                args: [Type::Obj, Type::Obj],
                 ret: Type::Val(ValType::Bool),
+                ir_gen: |ctx, arg1, arg2| {
+                    /*
+                    if eq_obj(a, b) {
+                        dest1 = true
+                    } else if is_int(a) && is_int(b) {
+                        dest2 = eq_int(from_obj_int(a), from_obj_int(b))
+                    } else if is_float(a) && is_float(b) {
+                        dest3 = eq_float(from_obj_float(a), from_obj_float(b))
+                    } else if is_char(a) && is_char(b) {
+                        dest4 = eq_char(from_obj_char(a), from_obj_char(b))
+                    } else if is_bigint(a) && is_bigint(b) {
+                        dest5 = cmp_integer(a, b) == 0
+                    } else if is_rational(a) && is_rational(b) {
+                        dest6 = cmp_rational(a, b) == 0
+                    } else {
+                        dest7 = false
+                    }
+                    dest = phi(dest1, dest2, dest3, dest4, dest5, dest6, dest7)
+                    */
+                    let merge_bb_id = ctx.builder.bbs.allocate_key();
+
+                    let eq_obj_local = ctx.builder.local(Type::Val(ValType::Bool));
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(eq_obj_local),
+                        kind: InstrKind::EqObj(arg1, arg2),
+                    });
+
+                    let eq_obj_then_bb_id = ctx.builder.bbs.allocate_key();
+                    let eq_obj_else_bb_id = ctx.builder.bbs.allocate_key();
+                    ctx.builder.close_bb(TerminatorInstr::If(
+                        eq_obj_local,
+                        eq_obj_then_bb_id,
+                        eq_obj_else_bb_id,
+                    ));
+
+                    let eq_obj_dest_local = ctx.builder.local(Type::Val(ValType::Bool));
+                    ctx.builder.current_bb_id = Some(eq_obj_then_bb_id);
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(eq_obj_dest_local),
+                        kind: InstrKind::Bool(true),
+                    });
+                    ctx.builder.close_bb(TerminatorInstr::Jump(merge_bb_id));
+
+                    ctx.builder.current_bb_id = Some(eq_obj_else_bb_id);
+                    let is_int1_local = ctx.builder.local(Type::Val(ValType::Bool));
+                    let is_int2_local = ctx.builder.local(Type::Val(ValType::Bool));
+                    let is_int_local = ctx.builder.local(Type::Val(ValType::Bool));
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(is_int1_local),
+                        kind: InstrKind::Is(ValType::Int, arg1),
+                    });
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(is_int2_local),
+                        kind: InstrKind::Is(ValType::Int, arg2),
+                    });
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(is_int_local),
+                        kind: InstrKind::And(is_int1_local, is_int2_local),
+                    });
+                    let is_int_then_bb_id = ctx.builder.bbs.allocate_key();
+                    let is_int_else_bb_id = ctx.builder.bbs.allocate_key();
+                    ctx.builder.close_bb(TerminatorInstr::If(
+                        is_int_local,
+                        is_int_then_bb_id,
+                        is_int_else_bb_id,
+                    ));
+
+                    let is_int_dest_local = ctx.builder.local(Type::Val(ValType::Bool));
+                    ctx.builder.current_bb_id = Some(is_int_then_bb_id);
+                    let from_obj_int1_local = ctx.builder.local(Type::Val(ValType::Int));
+                    let from_obj_int2_local = ctx.builder.local(Type::Val(ValType::Int));
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(from_obj_int1_local),
+                        kind: InstrKind::FromObj(ValType::Int, arg1),
+                    });
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(from_obj_int2_local),
+                        kind: InstrKind::FromObj(ValType::Int, arg2),
+                    });
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(is_int_dest_local),
+                        kind: InstrKind::EqInt(from_obj_int1_local, from_obj_int2_local),
+                    });
+                    ctx.builder.close_bb(TerminatorInstr::Jump(merge_bb_id));
+
+                    ctx.builder.current_bb_id = Some(is_int_else_bb_id);
+                    let is_float1_local = ctx.builder.local(Type::Val(ValType::Bool));
+                    let is_float2_local = ctx.builder.local(Type::Val(ValType::Bool));
+                    let is_float_local = ctx.builder.local(Type::Val(ValType::Bool));
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(is_float1_local),
+                        kind: InstrKind::Is(ValType::Float, arg1),
+                    });
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(is_float2_local),
+                        kind: InstrKind::Is(ValType::Float, arg2),
+                    });
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(is_float_local),
+                        kind: InstrKind::And(is_float1_local, is_float2_local),
+                    });
+                    let is_float_then_bb_id = ctx.builder.bbs.allocate_key();
+                    let is_float_else_bb_id = ctx.builder.bbs.allocate_key();
+                    ctx.builder.close_bb(TerminatorInstr::If(
+                        is_float_local,
+                        is_float_then_bb_id,
+                        is_float_else_bb_id,
+                    ));
+                    let is_float_dest_local = ctx.builder.local(Type::Val(ValType::Bool));
+                    ctx.builder.current_bb_id = Some(is_float_then_bb_id);
+                    let from_obj_float1_local = ctx.builder.local(Type::Val(ValType::Float));
+                    let from_obj_float2_local = ctx.builder.local(Type::Val(ValType::Float));
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(from_obj_float1_local),
+                        kind: InstrKind::FromObj(ValType::Float, arg1),
+                    });
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(from_obj_float2_local),
+                        kind: InstrKind::FromObj(ValType::Float, arg2),
+                    });
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(is_float_dest_local),
+                        kind: InstrKind::EqFloat(from_obj_float1_local, from_obj_float2_local),
+                    });
+                    ctx.builder.close_bb(TerminatorInstr::Jump(merge_bb_id));
+
+                    ctx.builder.current_bb_id = Some(is_float_else_bb_id);
+                    let is_char1_local = ctx.builder.local(Type::Val(ValType::Bool));
+                    let is_char2_local = ctx.builder.local(Type::Val(ValType::Bool));
+                    let is_char_local = ctx.builder.local(Type::Val(ValType::Bool));
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(is_char1_local),
+                        kind: InstrKind::Is(ValType::Char, arg1),
+                    });
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(is_char2_local),
+                        kind: InstrKind::Is(ValType::Char, arg2),
+                    });
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(is_char_local),
+                        kind: InstrKind::And(is_char1_local, is_char2_local),
+                    });
+                    let is_char_then_bb_id = ctx.builder.bbs.allocate_key();
+                    let is_char_else_bb_id = ctx.builder.bbs.allocate_key();
+                    ctx.builder.close_bb(TerminatorInstr::If(
+                        is_char_local,
+                        is_char_then_bb_id,
+                        is_char_else_bb_id,
+                    ));
+                    let is_char_dest_local = ctx.builder.local(Type::Val(ValType::Bool));
+                    ctx.builder.current_bb_id = Some(is_char_then_bb_id);
+                    let from_obj_char1_local = ctx.builder.local(Type::Val(ValType::Char));
+                    let from_obj_char2_local = ctx.builder.local(Type::Val(ValType::Char));
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(from_obj_char1_local),
+                        kind: InstrKind::FromObj(ValType::Char, arg1),
+                    });
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(from_obj_char2_local),
+                        kind: InstrKind::FromObj(ValType::Char, arg2),
+                    });
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(is_char_dest_local),
+                        kind: InstrKind::EqChar(from_obj_char1_local, from_obj_char2_local),
+                    });
+                    ctx.builder.close_bb(TerminatorInstr::Jump(merge_bb_id));
+
+                    ctx.builder.current_bb_id = Some(is_char_else_bb_id);
+                    let is_bigint1_local = ctx.builder.local(Type::Val(ValType::Bool));
+                    let is_bigint2_local = ctx.builder.local(Type::Val(ValType::Bool));
+                    let is_bigint_local = ctx.builder.local(Type::Val(ValType::Bool));
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(is_bigint1_local),
+                        kind: InstrKind::Is(ValType::BigInt, arg1),
+                    });
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(is_bigint2_local),
+                        kind: InstrKind::Is(ValType::BigInt, arg2),
+                    });
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(is_bigint_local),
+                        kind: InstrKind::And(is_bigint1_local, is_bigint2_local),
+                    });
+                    let is_bigint_then_bb_id = ctx.builder.bbs.allocate_key();
+                    let is_bigint_else_bb_id = ctx.builder.bbs.allocate_key();
+                    ctx.builder.close_bb(TerminatorInstr::If(
+                        is_bigint_local,
+                        is_bigint_then_bb_id,
+                        is_bigint_else_bb_id,
+                    ));
+                    let is_bigint_dest_local = ctx.builder.local(Type::Val(ValType::Bool));
+                    ctx.builder.current_bb_id = Some(is_bigint_then_bb_id);
+                    let cmp_local = ctx.builder.local(Type::Val(ValType::Int));
+                    let zero_local = ctx.builder.local(Type::Val(ValType::Int));
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(cmp_local),
+                        kind: InstrKind::CmpInteger(arg1, arg2),
+                    });
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(zero_local),
+                        kind: InstrKind::Int(0),
+                    });
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(is_bigint_dest_local),
+                        kind: InstrKind::EqInt(cmp_local, zero_local),
+                    });
+                    ctx.builder.close_bb(TerminatorInstr::Jump(merge_bb_id));
+
+                    ctx.builder.current_bb_id = Some(is_bigint_else_bb_id);
+                    let is_rational1_local = ctx.builder.local(Type::Val(ValType::Bool));
+                    let is_rational2_local = ctx.builder.local(Type::Val(ValType::Bool));
+                    let is_rational_local = ctx.builder.local(Type::Val(ValType::Bool));
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(is_rational1_local),
+                        kind: InstrKind::Is(ValType::Rational, arg1),
+                    });
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(is_rational2_local),
+                        kind: InstrKind::Is(ValType::Rational, arg2),
+                    });
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(is_rational_local),
+                        kind: InstrKind::And(is_rational1_local, is_rational2_local),
+                    });
+                    let is_rational_then_bb_id = ctx.builder.bbs.allocate_key();
+                    let is_rational_else_bb_id = ctx.builder.bbs.allocate_key();
+                    ctx.builder.close_bb(TerminatorInstr::If(
+                        is_rational_local,
+                        is_rational_then_bb_id,
+                        is_rational_else_bb_id,
+                    ));
+                    let is_rational_dest_local = ctx.builder.local(Type::Val(ValType::Bool));
+                    ctx.builder.current_bb_id = Some(is_rational_then_bb_id);
+                    let rational_cmp_local = ctx.builder.local(Type::Val(ValType::Int));
+                    let rational_zero_local = ctx.builder.local(Type::Val(ValType::Int));
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(rational_cmp_local),
+                        kind: InstrKind::CmpRational(arg1, arg2),
+                    });
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(rational_zero_local),
+                        kind: InstrKind::Int(0),
+                    });
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(is_rational_dest_local),
+                        kind: InstrKind::EqInt(rational_cmp_local, rational_zero_local),
+                    });
+                    ctx.builder.close_bb(TerminatorInstr::Jump(merge_bb_id));
+
+                    ctx.builder.current_bb_id = Some(is_rational_else_bb_id);
+                    let false_local = ctx.builder.local(Type::Val(ValType::Bool));
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(false_local),
+                        kind: InstrKind::Bool(false),
+                    });
+                    ctx.builder.close_bb(TerminatorInstr::Jump(merge_bb_id));
+                    ctx.builder.current_bb_id = Some(merge_bb_id);
+
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(ctx.dest),
+                        kind: InstrKind::Phi {
+                            incomings: vec![
+                                PhiIncomingValue {
+                                    local: eq_obj_dest_local,
+                                    bb: eq_obj_then_bb_id,
+                                },
+                                PhiIncomingValue {
+                                    local: is_int_dest_local,
+                                    bb: is_int_then_bb_id,
+                                },
+                                PhiIncomingValue {
+                                    local: is_float_dest_local,
+                                    bb: is_float_then_bb_id,
+                                },
+                                PhiIncomingValue {
+                                    local: is_char_dest_local,
+                                    bb: is_char_then_bb_id,
+                                },
+                                PhiIncomingValue {
+                                    local: is_bigint_dest_local,
+                                    bb: is_bigint_then_bb_id,
+                                },
+                                PhiIncomingValue {
+                                    local: is_rational_dest_local,
+                                    bb: is_rational_then_bb_id,
+                                },
+                                PhiIncomingValue {
+                                    local: false_local,
+                                    bb: is_rational_else_bb_id,
+                                },
+                            ],
+                            non_exhaustive: false,
+                        },
+                    });
+                },
+            }],
+            Builtin::Cons => vec![BuiltinConversionRule::Binary {
+                args: [Type::Obj, Type::Obj],
+                ret: Type::Val(ValType::Cons),
+                ir_gen: |ctx, arg1, arg2| {
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(ctx.dest),
+                        kind: InstrKind::Cons(arg1, arg2),
+                    });
+                },
+            }],
+            Builtin::Car => vec![BuiltinConversionRule::Unary {
+                args: [Type::Val(ValType::Cons)],
+                ret: Type::Obj,
                 ir_gen: |ctx, arg1| {
                     ctx.builder.exprs.push(Instr {
                         local: Some(ctx.dest),
-                        kind: InstrKind::Is(ValType::Cons, arg1),
+                        kind: InstrKind::Car(arg1),
                     });
                 },
             }],
-            Builtin::IsSymbol => vec![BuiltinConversionRule::Unary {
-                args: [Type::Obj],
-                ret: Type::Val(ValType::Bool),
+            Builtin::Cdr => vec![BuiltinConversionRule::Unary {
+                args: [Type::Val(ValType::Cons)],
+                ret: Type::Obj,
                 ir_gen: |ctx, arg1| {
                     ctx.builder.exprs.push(Instr {
                         local: Some(ctx.dest),
-                        kind: InstrKind::Is(ValType::Symbol, arg1),
+                        kind: InstrKind::Cdr(arg1),
                     });
                 },
             }],
-            Builtin::IsString => vec![BuiltinConversionRule::Unary {
-                args: [Type::Obj],
-                ret: Type::Val(ValType::Bool),
+            Builtin::SetCar => vec![BuiltinConversionRule::Binary {
+                args: [Type::Val(ValType::Cons), Type::Obj],
+                ret: Type::Val(ValType::Nil),
+                ir_gen: |ctx, arg1, arg2| {
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(ctx.dest),
+                        kind: InstrKind::SetCar(arg1, arg2),
+                    });
+                },
+            }],
+            Builtin::SetCdr => vec![BuiltinConversionRule::Binary {
+                args: [Type::Val(ValType::Cons), Type::Obj],
+                ret: Type::Val(ValType::Nil),
+                ir_gen: |ctx, arg1, arg2| {
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(ctx.dest),
+                        kind: InstrKind::SetCdr(arg1, arg2),
+                    });
+                },
+            }],
+            Builtin::SymbolToString => vec![BuiltinConversionRule::Unary {
+                args: [Type::Val(ValType::Symbol)],
+                ret: Type::Val(ValType::String),
                 ir_gen: |ctx, arg1| {
                     ctx.builder.exprs.push(Instr {
                         local: Some(ctx.dest),
-                        kind: InstrKind::Is(ValType::String, arg1),
+                        kind: InstrKind::SymbolToString(arg1),
                     });
                 },
             }],
-            Builtin::IsNumber => vec![BuiltinConversionRule::Unary {
-                args: [Type::Obj],
-                ret: Type::Val(ValType::Bool),
+            Builtin::StringToSymbol => vec![BuiltinConversionRule::Unary {
+                args: [Type::Val(ValType::String)],
+                ret: Type::Val(ValType::Symbol),
                 ir_gen: |ctx, arg1| {
-                    let is_int_local = ctx.builder.locals.push_with(|id| Local {
-                        id,
-                        typ: ValType::Bool.into(),
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(ctx.dest),
+                        kind: InstrKind::StringToSymbol(arg1),
                     });
-                    let is_float_local = ctx.builder.locals.push_with(|id| Local {
-                        id,
-                        typ: ValType::Bool.into(),
+                },
+            }],
+            Builtin::NumberToString => vec![
+                BuiltinConversionRule::Unary {
+                    args: [Type::Val(ValType::Int)],
+                    ret: Type::Val(ValType::String),
+                    ir_gen: |ctx, arg1| {
+                        ctx.builder.exprs.push(Instr {
+                            local: Some(ctx.dest),
+                            kind: InstrKind::IntToString(arg1),
+                        });
+                    },
+                },
+                BuiltinConversionRule::Unary {
+                    args: [Type::Val(ValType::Float)],
+                    ret: Type::Val(ValType::String),
+                    ir_gen: |ctx, arg1| {
+                        ctx.builder.exprs.push(Instr {
+                            local: Some(ctx.dest),
+                            kind: InstrKind::FloatToString(arg1),
+                        });
+                    },
+                },
+                BuiltinConversionRule::Unary {
+                    args: [Type::Val(ValType::BigInt)],
+                    ret: Type::Val(ValType::String),
+                    ir_gen: |ctx, arg1| {
+                        ctx.builder.exprs.push(Instr {
+                            local: Some(ctx.dest),
+                            kind: InstrKind::BigIntToString(arg1),
+                        });
+                    },
+                },
+                BuiltinConversionRule::Unary {
+                    args: [Type::Val(ValType::Rational)],
+                    ret: Type::Val(ValType::String),
+                    ir_gen: |ctx, arg1| {
+                        ctx.builder.exprs.push(Instr {
+                            local: Some(ctx.dest),
+                            kind: InstrKind::RationalToString(arg1),
+                        });
+                    },
+                },
+            ],
+            Builtin::StringRef => vec![BuiltinConversionRule::Binary {
+                args: [Type::Val(ValType::String), Type::Val(ValType::Int)],
+                ret: Type::Val(ValType::Char),
+                ir_gen: |ctx, arg1, arg2| {
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(ctx.dest),
+                        kind: InstrKind::StringRef(arg1, arg2),
                     });
+                },
+            }],
+            Builtin::StringCopy => vec![BuiltinConversionRule::Unary {
+                args: [Type::Val(ValType::String)],
+                ret: Type::Val(ValType::String),
+                ir_gen: |ctx, arg| {
                     ctx.builder.exprs.push(Instr {
-                        local: Some(is_int_local),
-                        kind: InstrKind::Is(ValType::Int, arg1),
+                        local: Some(ctx.dest),
+                        kind: InstrKind::StringCopy(arg),
                     });
+                },
+            }],
+            Builtin::StringSet => vec![BuiltinConversionRule::Ternary {
+                args: [
+                    Type::Val(ValType::String),
+                    Type::Val(ValType::Int),
+                    Type::Val(ValType::Char),
+                ],
+                ret: Type::Val(ValType::Nil),
+                ir_gen: |ctx, arg1, arg2, arg3| {
                     ctx.builder.exprs.push(Instr {
-                        local: Some(is_float_local),
-                        kind: InstrKind::Is(ValType::Float, arg1),
+                        local: Some(ctx.dest),
+                        kind: InstrKind::StringSet(arg1, arg2, arg3),
                     });
+                },
+            }],
+            Builtin::StringLength => vec![BuiltinConversionRule::Unary {
+                args: [Type::Val(ValType::String)],
+                ret: Type::Val(ValType::Int),
+                ir_gen: |ctx, arg1| {
                     ctx.builder.exprs.push(Instr {
                         local: Some(ctx.dest),
-                        kind: InstrKind::Or(is_int_local, is_float_local),
+                        kind: InstrKind::StringLength(arg1),
+                    });
+                },
+            }],
+            Builtin::StringAppend => vec![BuiltinConversionRule::Binary {
+                args: [Type::Val(ValType::String), Type::Val(ValType::String)],
+                ret: Type::Val(ValType::String),
+                ir_gen: |ctx, arg1, arg2| {
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(ctx.dest),
+                        kind: InstrKind::StringAppend(arg1, arg2),
+                    });
+                },
+            }],
+            Builtin::Substring => vec![BuiltinConversionRule::Ternary {
+                args: [
+                    Type::Val(ValType::String),
+                    Type::Val(ValType::Int),
+                    Type::Val(ValType::Int),
+                ],
+                ret: Type::Val(ValType::String),
+                ir_gen: |ctx, arg1, arg2, arg3| {
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(ctx.dest),
+                        kind: InstrKind::Substring(arg1, arg2, arg3),
+                    });
+                },
+            }],
+            Builtin::MakeString => vec![BuiltinConversionRule::Binary {
+                args: [Type::Val(ValType::Int), Type::Val(ValType::Char)],
+                ret: Type::Val(ValType::String),
+                ir_gen: |ctx, arg1, arg2| {
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(ctx.dest),
+                        kind: InstrKind::MakeString(arg1, arg2),
+                    });
+                },
+            }],
+            Builtin::ListToString => vec![BuiltinConversionRule::Unary {
+                args: [Type::Obj],
+                ret: Type::Val(ValType::String),
+                ir_gen: |ctx, arg1| {
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(ctx.dest),
+                        kind: InstrKind::ListToString(arg1),
+                    });
+                },
+            }],
+            Builtin::StringToList => vec![BuiltinConversionRule::Unary {
+                args: [Type::Val(ValType::String)],
+                ret: Type::Obj,
+                ir_gen: |ctx, arg1| {
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(ctx.dest),
+                        kind: InstrKind::StringToList(arg1),
                     });
                 },
             }],
-            Builtin::IsBoolean => vec![BuiltinConversionRule::Unary {
-                args: [Type::Obj],
+            Builtin::StringLt => vec![BuiltinConversionRule::Binary {
+                args: [Type::Val(ValType::String), Type::Val(ValType::String)],
+                ret: Type::Val(ValType::Bool),
+                ir_gen: |ctx, arg1, arg2| {
+                    gen_cmp_string(ctx, InstrKind::CmpString(arg1, arg2), InstrKind::LtInt);
+                },
+            }],
+            Builtin::StringGt => vec![BuiltinConversionRule::Binary {
+                args: [Type::Val(ValType::String), Type::Val(ValType::String)],
+                ret: Type::Val(ValType::Bool),
+                ir_gen: |ctx, arg1, arg2| {
+                    gen_cmp_string(ctx, InstrKind::CmpString(arg1, arg2), InstrKind::GtInt);
+                },
+            }],
+            Builtin::StringLe => vec![BuiltinConversionRule::Binary {
+                args: [Type::Val(ValType::String), Type::Val(ValType::String)],
+                ret: Type::Val(ValType::Bool),
+                ir_gen: |ctx, arg1, arg2| {
+                    gen_cmp_string(ctx, InstrKind::CmpString(arg1, arg2), InstrKind::LeInt);
+                },
+            }],
+            Builtin::StringGe => vec![BuiltinConversionRule::Binary {
+                args: [Type::Val(ValType::String), Type::Val(ValType::String)],
+                ret: Type::Val(ValType::Bool),
+                ir_gen: |ctx, arg1, arg2| {
+                    gen_cmp_string(ctx, InstrKind::CmpString(arg1, arg2), InstrKind::GeInt);
+                },
+            }],
+            Builtin::StringCiEq => vec![BuiltinConversionRule::Binary {
+                args: [Type::Val(ValType::String), Type::Val(ValType::String)],
+                ret: Type::Val(ValType::Bool),
+                ir_gen: |ctx, arg1, arg2| {
+                    gen_cmp_string(ctx, InstrKind::CmpStringCi(arg1, arg2), InstrKind::EqInt);
+                },
+            }],
+            Builtin::StringCiLt => vec![BuiltinConversionRule::Binary {
+                args: [Type::Val(ValType::String), Type::Val(ValType::String)],
+                ret: Type::Val(ValType::Bool),
+                ir_gen: |ctx, arg1, arg2| {
+                    gen_cmp_string(ctx, InstrKind::CmpStringCi(arg1, arg2), InstrKind::LtInt);
+                },
+            }],
+            Builtin::StringCiGt => vec![BuiltinConversionRule::Binary {
+                args: [Type::Val(ValType::String), Type::Val(ValType::String)],
                 ret: Type::Val(ValType::Bool),
-                ir_gen: |ctx, arg1| {
-                    ctx.builder.exprs.push(Instr {
-                        local: Some(ctx.dest),
-                        kind: InstrKind::Is(ValType::Bool, arg1),
-                    });
+                ir_gen: |ctx, arg1, arg2| {
+                    gen_cmp_string(ctx, InstrKind::CmpStringCi(arg1, arg2), InstrKind::GtInt);
                 },
             }],
-            Builtin::IsProcedure => vec![BuiltinConversionRule::Unary {
-                args: [Type::Obj],
+            Builtin::StringCiLe => vec![BuiltinConversionRule::Binary {
+                args: [Type::Val(ValType::String), Type::Val(ValType::String)],
                 ret: Type::Val(ValType::Bool),
-                ir_gen: |ctx, arg1| {
-                    ctx.builder.exprs.push(Instr {
-                        local: Some(ctx.dest),
-                        kind: InstrKind::Is(ValType::Closure, arg1),
-                    });
+                ir_gen: |ctx, arg1, arg2| {
+                    gen_cmp_string(ctx, InstrKind::CmpStringCi(arg1, arg2), InstrKind::LeInt);
                 },
             }],
-            Builtin::IsChar => vec![BuiltinConversionRule::Unary {
-                args: [Type::Obj],
+            Builtin::StringCiGe => vec![BuiltinConversionRule::Binary {
+                args: [Type::Val(ValType::String), Type::Val(ValType::String)],
                 ret: Type::Val(ValType::Bool),
-                ir_gen: |ctx, arg1| {
-                    ctx.builder.exprs.push(Instr {
-                        local: Some(ctx.dest),
-                        kind: InstrKind::Is(ValType::Char, arg1),
-                    });
+                ir_gen: |ctx, arg1, arg2| {
+                    gen_cmp_string(ctx, InstrKind::CmpStringCi(arg1, arg2), InstrKind::GeInt);
                 },
             }],
-            Builtin::IsVector => vec![BuiltinConversionRule::Unary {
-                args: [Type::Obj],
-                ret: Type::Val(ValType::Bool),
-                ir_gen: |ctx, arg1| {
+            Builtin::StringToNumber => vec![BuiltinConversionRule::Binary {
+                args: [Type::Val(ValType::String), Type::Val(ValType::Int)],
+                ret: Type::Obj,
+                ir_gen: |ctx, arg1, arg2| {
                     ctx.builder.exprs.push(Instr {
                         local: Some(ctx.dest),
-                        kind: InstrKind::Is(ValType::Vector, arg1),
+                        kind: InstrKind::StringToNumber(arg1, arg2),
                     });
                 },
             }],
-            Builtin::VectorLength => vec![BuiltinConversionRule::Unary {
-                args: [Type::Val(ValType::Vector)],
+            Builtin::CharToInteger => vec![BuiltinConversionRule::Unary {
+                args: [Type::Val(ValType::Char)],
                 ret: Type::Val(ValType::Int),
                 ir_gen: |ctx, arg1| {
                     ctx.builder.exprs.push(Instr {
                         local: Some(ctx.dest),
-                        kind: InstrKind::VectorLength(arg1),
+                        kind: InstrKind::CharToInt(arg1),
                     });
                 },
             }],
-            Builtin::VectorRef => vec![BuiltinConversionRule::Binary {
-                args: [Type::Val(ValType::Vector), Type::Val(ValType::Int)],
-                ret: Type::Obj,
-                ir_gen: |ctx, arg1, arg2| {
+            Builtin::IntegerToChar => vec![BuiltinConversionRule::Unary {
+                args: [Type::Val(ValType::Int)],
+                ret: Type::Val(ValType::Char),
+                ir_gen: |ctx, arg1| {
                     ctx.builder.exprs.push(Instr {
                         local: Some(ctx.dest),
-                        kind: InstrKind::VectorRef(arg1, arg2),
+                        kind: InstrKind::IntToChar(arg1),
                     });
                 },
             }],
-            Builtin::VectorSet => vec![BuiltinConversionRule::Ternary {
-                args: [
-                    Type::Val(ValType::Vector),
-                    Type::Val(ValType::Int),
-                    Type::Obj,
-                ],
-                ret: Type::Val(ValType::Nil),
-                ir_gen: |ctx, arg1, arg2, arg3| {
+            Builtin::CharUpcase => vec![BuiltinConversionRule::Unary {
+                args: [Type::Val(ValType::Char)],
+                ret: Type::Val(ValType::Char),
+                ir_gen: |ctx, arg1| {
                     ctx.builder.exprs.push(Instr {
                         local: Some(ctx.dest),
-                        kind: InstrKind::VectorSet(arg1, arg2, arg3),
+                        kind: InstrKind::CharUpcase(arg1),
                     });
                 },
             }],
-            Builtin::MakeVector => vec![BuiltinConversionRule::Unary {
-                args: [Type::Val(ValType::Int)],
-                ret: Type::Val(ValType::Vector),
+            Builtin::CharDowncase => vec![BuiltinConversionRule::Unary {
+                args: [Type::Val(ValType::Char)],
+                ret: Type::Val(ValType::Char),
                 ir_gen: |ctx, arg1| {
                     ctx.builder.exprs.push(Instr {
                         local: Some(ctx.dest),
-                        kind: InstrKind::MakeVector(arg1),
+                        kind: InstrKind::CharDowncase(arg1),
                     });
                 },
             }],
-            Builtin::IsS64Vector => vec![BuiltinConversionRule::Unary {
-                args: [Type::Obj],
+            Builtin::CharAlphabetic => vec![BuiltinConversionRule::Unary {
+                args: [Type::Val(ValType::Char)],
                 ret: Type::Val(ValType::Bool),
                 ir_gen: |ctx, arg1| {
                     ctx.builder.exprs.push(Instr {
                         local: Some(ctx.dest),
-                        kind: InstrKind::Is(ValType::UVector(UVectorKind::S64), arg1),
+                        kind: InstrKind::CharAlphabetic(arg1),
                     });
                 },
             }],
-            Builtin::IsF64Vector => vec![BuiltinConversionRule::Unary {
-                args: [Type::Obj],
+            Builtin::CharNumeric => vec![BuiltinConversionRule::Unary {
+                args: [Type::Val(ValType::Char)],
                 ret: Type::Val(ValType::Bool),
                 ir_gen: |ctx, arg1| {
                     ctx.builder.exprs.push(Instr {
                         local: Some(ctx.dest),
-                        kind: InstrKind::Is(ValType::UVector(UVectorKind::F64), arg1),
+                        kind: InstrKind::CharNumeric(arg1),
                     });
                 },
             }],
-            Builtin::IsUVector => vec![BuiltinConversionRule::Unary {
-                args: [Type::Obj],
+            Builtin::CharWhitespace => vec![BuiltinConversionRule::Unary {
+                args: [Type::Val(ValType::Char)],
                 ret: Type::Val(ValType::Bool),
                 ir_gen: |ctx, arg1| {
-                    let is_s64_local = ctx.builder.locals.push_with(|id| Local {
-                        id,
-                        typ: ValType::Bool.into(),
-                    });
-                    let is_f64_local = ctx.builder.locals.push_with(|id| Local {
-                        id,
-                        typ: ValType::Bool.into(),
-                    });
-                    ctx.builder.exprs.push(Instr {
-                        local: Some(is_s64_local),
-                        kind: InstrKind::Is(ValType::UVector(UVectorKind::S64), arg1),
-                    });
-                    ctx.builder.exprs.push(Instr {
-                        local: Some(is_f64_local),
-                        kind: InstrKind::Is(ValType::UVector(UVectorKind::F64), arg1),
-                    });
                     ctx.builder.exprs.push(Instr {
                         local: Some(ctx.dest),
-                        kind: InstrKind::Or(is_s64_local, is_f64_local),
+                        kind: InstrKind::CharWhitespace(arg1),
                     });
                 },
             }],
-            Builtin::MakeS64Vector => vec![BuiltinConversionRule::Unary {
-                args: [Type::Val(ValType::Int)],
-                ret: Type::Val(ValType::UVector(UVectorKind::S64)),
+            Builtin::CharUpperCase => vec![BuiltinConversionRule::Unary {
+                args: [Type::Val(ValType::Char)],
+                ret: Type::Val(ValType::Bool),
                 ir_gen: |ctx, arg1| {
                     ctx.builder.exprs.push(Instr {
                         local: Some(ctx.dest),
-                        kind: InstrKind::MakeUVector(UVectorKind::S64, arg1),
+                        kind: InstrKind::CharUpperCase(arg1),
                     });
                 },
             }],
-            Builtin::MakeF64Vector => vec![BuiltinConversionRule::Unary {
-                args: [Type::Val(ValType::Int)],
-                ret: Type::Val(ValType::UVector(UVectorKind::F64)),
+            Builtin::CharLowerCase => vec![BuiltinConversionRule::Unary {
+                args: [Type::Val(ValType::Char)],
+                ret: Type::Val(ValType::Bool),
                 ir_gen: |ctx, arg1| {
                     ctx.builder.exprs.push(Instr {
                         local: Some(ctx.dest),
-                        kind: InstrKind::MakeUVector(UVectorKind::F64, arg1),
+                        kind: InstrKind::CharLowerCase(arg1),
                     });
                 },
             }],
-            Builtin::UVectorLength => vec![
-                BuiltinConversionRule::Unary {
-                    args: [Type::Val(ValType::UVector(UVectorKind::S64))],
-                    ret: Type::Val(ValType::Int),
-                    ir_gen: |ctx, arg1| {
+            Builtin::EqNum => [
+                BuiltinConversionRule::Binary {
+                    args: [Type::Val(ValType::Int), Type::Val(ValType::Int)],
+                    ret: Type::Val(ValType::Bool),
+                    ir_gen: |ctx, arg1, arg2| {
                         ctx.builder.exprs.push(Instr {
                             local: Some(ctx.dest),
-                            kind: InstrKind::UVectorLength(UVectorKind::S64, arg1),
+                            kind: InstrKind::EqInt(arg1, arg2),
                         });
                     },
                 },
-                BuiltinConversionRule::Unary {
-                    args: [Type::Val(ValType::UVector(UVectorKind::F64))],
-                    ret: Type::Val(ValType::Int),
-                    ir_gen: |ctx, arg1| {
+                BuiltinConversionRule::Binary {
+                    args: [Type::Val(ValType::Float), Type::Val(ValType::Float)],
+                    ret: Type::Val(ValType::Bool),
+                    ir_gen: |ctx, arg1, arg2| {
                         ctx.builder.exprs.push(Instr {
                             local: Some(ctx.dest),
-                            kind: InstrKind::UVectorLength(UVectorKind::F64, arg1),
+                            kind: InstrKind::EqFloat(arg1, arg2),
                         });
                     },
                 },
-            ],
-            Builtin::UVectorRef => vec![
+            ]
+            .into_iter()
+            .chain(integer_rules(Type::Val(ValType::Bool), |ctx, _, _| {
+                gen_cmp_integer(ctx, InstrKind::EqInt);
+            }))
+            .chain(rational_rules(Type::Val(ValType::Bool), |ctx, _, _| {
+                gen_cmp_rational(ctx, InstrKind::EqInt);
+            }))
+            .collect(),
+            Builtin::StringEq => vec![BuiltinConversionRule::Binary {
+                args: [Type::Val(ValType::String), Type::Val(ValType::String)],
+                ret: Type::Val(ValType::Bool),
+                ir_gen: |ctx, arg1, arg2| {
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(ctx.dest),
+                        kind: InstrKind::EqString(arg1, arg2),
+                    });
+                },
+            }],
+            Builtin::Lt => [
                 BuiltinConversionRule::Binary {
-                    args: [
-                        Type::Val(ValType::UVector(UVectorKind::S64)),
-                        Type::Val(ValType::Int),
-                    ],
-                    ret: Type::Val(ValType::Int),
+                    args: [Type::Val(ValType::Int), Type::Val(ValType::Int)],
+                    ret: Type::Val(ValType::Bool),
                     ir_gen: |ctx, arg1, arg2| {
                         ctx.builder.exprs.push(Instr {
                             local: Some(ctx.dest),
-                            kind: InstrKind::UVectorRef(UVectorKind::S64, arg1, arg2),
+                            kind: InstrKind::LtInt(arg1, arg2),
                         });
                     },
                 },
                 BuiltinConversionRule::Binary {
-                    args: [
-                        Type::Val(ValType::UVector(UVectorKind::F64)),
-                        Type::Val(ValType::Int),
-                    ],
-                    ret: Type::Val(ValType::Float),
+                    args: [Type::Val(ValType::Float), Type::Val(ValType::Float)],
+                    ret: Type::Val(ValType::Bool),
                     ir_gen: |ctx, arg1, arg2| {
                         ctx.builder.exprs.push(Instr {
                             local: Some(ctx.dest),
-                            kind: InstrKind::UVectorRef(UVectorKind::F64, arg1, arg2),
+                            kind: InstrKind::LtFloat(arg1, arg2),
                         });
                     },
                 },
-            ],
-            Builtin::UVectorSet => vec![
-                BuiltinConversionRule::Ternary {
-                    args: [
-                        Type::Val(ValType::UVector(UVectorKind::S64)),
-                        Type::Val(ValType::Int),
-                        Type::Val(ValType::Int),
-                    ],
-                    ret: Type::Val(ValType::Nil),
-                    ir_gen: |ctx, arg1, arg2, arg3| {
+            ]
+            .into_iter()
+            .chain(integer_rules(Type::Val(ValType::Bool), |ctx, _, _| {
+                gen_cmp_integer(ctx, InstrKind::LtInt);
+            }))
+            .chain(rational_rules(Type::Val(ValType::Bool), |ctx, _, _| {
+                gen_cmp_rational(ctx, InstrKind::LtInt);
+            }))
+            .collect(),
+            Builtin::Gt => [
+                BuiltinConversionRule::Binary {
+                    args: [Type::Val(ValType::Int), Type::Val(ValType::Int)],
+                    ret: Type::Val(ValType::Bool),
+                    ir_gen: |ctx, arg1, arg2| {
                         ctx.builder.exprs.push(Instr {
                             local: Some(ctx.dest),
-                            kind: InstrKind::UVectorSet(UVectorKind::S64, arg1, arg2, arg3),
+                            kind: InstrKind::GtInt(arg1, arg2),
                         });
                     },
                 },
-                BuiltinConversionRule::Ternary {
-                    args: [
-                        Type::Val(ValType::UVector(UVectorKind::F64)),
-                        Type::Val(ValType::Int),
-                        Type::Val(ValType::Float),
-                    ],
-                    ret: Type::Val(ValType::Nil),
-                    ir_gen: |ctx, arg1, arg2, arg3| {
+                BuiltinConversionRule::Binary {
+                    args: [Type::Val(ValType::Float), Type::Val(ValType::Float)],
+                    ret: Type::Val(ValType::Bool),
+                    ir_gen: |ctx, arg1, arg2| {
                         ctx.builder.exprs.push(Instr {
                             local: Some(ctx.dest),
-                            kind: InstrKind::UVectorSet(UVectorKind::F64, arg1, arg2, arg3),
+                            kind: InstrKind::GtFloat(arg1, arg2),
                         });
                     },
                 },
-            ],
-            Builtin::Eq => vec![BuiltinConversionRule::Binary {
-                args: [Type::Obj, Type::Obj],
-                ret: Type::Val(ValType::Bool),
-                ir_gen: |ctx, arg1, arg2| {
-                    ctx.builder.exprs.push(Instr {
-                        local: Some(ctx.dest),
-                        kind: InstrKind::EqObj(arg1, arg2),
-                    });
-                },
-            }],
-            Builtin::Eqv => vec![BuiltinConversionRule::Binary {
-                args: [Type::Obj, Type::Obj],
-                ret: Type::Val(ValType::Bool),
-                ir_gen: |ctx, arg1, arg2| {
-                    /*
-                    if eq_obj(a, b) {
-                        dest1 = true
-                    } else if is_int(a) && is_int(b) {
-                        dest2 = eq_int(from_obj_int(a), from_obj_int(b))
-                    } else if is_float(a) && is_float(b) {
-                        dest3 = eq_float(from_obj_float(a), from_obj_float(b))
-                    } else if is_char(a) && is_char(b) {
-                        dest4 = eq_char(from_obj_char(a), from_obj_char(b))
-                    } else {
-                        dest5 = false
-                    }
-                    dest = phi(dest1, dest2, dest3, dest4, dest5)
-                    */
-                    let merge_bb_id = ctx.builder.bbs.allocate_key();
-
-                    let eq_obj_local = ctx.builder.local(Type::Val(ValType::Bool));
-                    ctx.builder.exprs.push(Instr {
-                        local: Some(eq_obj_local),
-                        kind: InstrKind::EqObj(arg1, arg2),
-                    });
-
-                    let eq_obj_then_bb_id = ctx.builder.bbs.allocate_key();
-                    let eq_obj_else_bb_id = ctx.builder.bbs.allocate_key();
-                    ctx.builder.close_bb(TerminatorInstr::If(
-                        eq_obj_local,
-                        eq_obj_then_bb_id,
-                        eq_obj_else_bb_id,
-                    ));
-
-                    let eq_obj_dest_local = ctx.builder.local(Type::Val(ValType::Bool));
-                    ctx.builder.current_bb_id = Some(eq_obj_then_bb_id);
-                    ctx.builder.exprs.push(Instr {
-                        local: Some(eq_obj_dest_local),
-                        kind: InstrKind::Bool(true),
-                    });
-                    ctx.builder.close_bb(TerminatorInstr::Jump(merge_bb_id));
-
-                    ctx.builder.current_bb_id = Some(eq_obj_else_bb_id);
-                    let is_int1_local = ctx.builder.local(Type::Val(ValType::Bool));
-                    let is_int2_local = ctx.builder.local(Type::Val(ValType::Bool));
-                    let is_int_local = ctx.builder.local(Type::Val(ValType::Bool));
-                    ctx.builder.exprs.push(Instr {
-                        local: Some(is_int1_local),
-                        kind: InstrKind::Is(ValType::Int, arg1),
-                    });
-                    ctx.builder.exprs.push(Instr {
-                        local: Some(is_int2_local),
-                        kind: InstrKind::Is(ValType::Int, arg2),
-                    });
-                    ctx.builder.exprs.push(Instr {
-                        local: Some(is_int_local),
-                        kind: InstrKind::And(is_int1_local, is_int2_local),
-                    });
-                    let is_int_then_bb_id = ctx.builder.bbs.allocate_key();
-                    let is_int_else_bb_id = ctx.builder.bbs.allocate_key();
-                    ctx.builder.close_bb(TerminatorInstr::If(
-                        is_int_local,
-                        is_int_then_bb_id,
-                        is_int_else_bb_id,
-                    ));
-
-                    let is_int_dest_local = ctx.builder.local(Type::Val(ValType::Bool));
-                    ctx.builder.current_bb_id = Some(is_int_then_bb_id);
-                    let from_obj_int1_local = ctx.builder.local(Type::Val(ValType::Int));
-                    let from_obj_int2_local = ctx.builder.local(Type::Val(ValType::Int));
-                    ctx.builder.exprs.push(Instr {
-                        local: Some(from_obj_int1_local),
-                        kind: InstrKind::FromObj(ValType::Int, arg1),
-                    });
-                    ctx.builder.exprs.push(Instr {
-                        local: Some(from_obj_int2_local),
-                        kind: InstrKind::FromObj(ValType::Int, arg2),
-                    });
-                    ctx.builder.exprs.push(Instr {
-                        local: Some(is_int_dest_local),
-                        kind: InstrKind::EqInt(from_obj_int1_local, from_obj_int2_local),
-                    });
-                    ctx.builder.close_bb(TerminatorInstr::Jump(merge_bb_id));
-
-                    ctx.builder.current_bb_id = Some(is_int_else_bb_id);
-                    let is_float1_local = ctx.builder.local(Type::Val(ValType::Bool));
-                    let is_float2_local = ctx.builder.local(Type::Val(ValType::Bool));
-                    let is_float_local = ctx.builder.local(Type::Val(ValType::Bool));
+            ]
+            .into_iter()
+            .chain(integer_rules(Type::Val(ValType::Bool), |ctx, _, _| {
+                gen_cmp_integer(ctx, InstrKind::GtInt);
+            }))
+            .chain(rational_rules(Type::Val(ValType::Bool), |ctx, _, _| {
+                gen_cmp_rational(ctx, InstrKind::GtInt);
+            }))
+            .collect(),
+            Builtin::Le => [
+                BuiltinConversionRule::Binary {
+                    args: [Type::Val(ValType::Int), Type::Val(ValType::Int)],
+                    ret: Type::Val(ValType::Bool),
+                    ir_gen: |ctx, arg1, arg2| {
+                        ctx.builder.exprs.push(Instr {
+                            local: Some(ctx.dest),
+                            kind: InstrKind::LeInt(arg1, arg2),
+                        });
+                    },
+                },
+                BuiltinConversionRule::Binary {
+                    args: [Type::Val(ValType::Float), Type::Val(ValType::Float)],
+                    ret: Type::Val(ValType::Bool),
+                    ir_gen: |ctx, arg1, arg2| {
+                        ctx.builder.exprs.push(Instr {
+                            local: Some(ctx.dest),
+                            kind: InstrKind::LeFloat(arg1, arg2),
+                        });
+                    },
+                },
+            ]
+            .into_iter()
+            .chain(integer_rules(Type::Val(ValType::Bool), |ctx, _, _| {
+                gen_cmp_integer(ctx, InstrKind::LeInt);
+            }))
+            .chain(rational_rules(Type::Val(ValType::Bool), |ctx, _, _| {
+                gen_cmp_rational(ctx, InstrKind::LeInt);
+            }))
+            .collect(),
+            Builtin::Ge => [
+                BuiltinConversionRule::Binary {
+                    args: [Type::Val(ValType::Int), Type::Val(ValType::Int)],
+                    ret: Type::Val(ValType::Bool),
+                    ir_gen: |ctx, arg1, arg2| {
+                        ctx.builder.exprs.push(Instr {
+                            local: Some(ctx.dest),
+                            kind: InstrKind::GeInt(arg1, arg2),
+                        });
+                    },
+                },
+                BuiltinConversionRule::Binary {
+                    args: [Type::Val(ValType::Float), Type::Val(ValType::Float)],
+                    ret: Type::Val(ValType::Bool),
+                    ir_gen: |ctx, arg1, arg2| {
+                        ctx.builder.exprs.push(Instr {
+                            local: Some(ctx.dest),
+                            kind: InstrKind::GeFloat(arg1, arg2),
+                        });
+                    },
+                },
+            ]
+            .into_iter()
+            .chain(integer_rules(Type::Val(ValType::Bool), |ctx, _, _| {
+                gen_cmp_integer(ctx, InstrKind::GeInt);
+            }))
+            .chain(rational_rules(Type::Val(ValType::Bool), |ctx, _, _| {
+                gen_cmp_rational(ctx, InstrKind::GeInt);
+            }))
+            .collect(),
+            // CPS変換で展開されるため、ここに到達するのは継続が無効な場合のみ(その場合はエラーになる)
+            Builtin::CallCc => vec![BuiltinConversionRule::Unary {
+                args: [Type::Val(ValType::Closure)],
+                ret: Type::Obj,
+                ir_gen: |_, _| unreachable!("%call/cc must be eliminated by CPS conversion"),
+            }],
+            Builtin::Resume => vec![BuiltinConversionRule::Binary {
+                args: [Type::Val(ValType::Closure), Type::Obj],
+                ret: Type::Obj,
+                ir_gen: |_, _, _| unreachable!("%resume must be eliminated by CPS conversion"),
+            }],
+            Builtin::IsErrorObject => vec![BuiltinConversionRule::Unary {
+                args: [Type::Obj],
+                ret: Type::Val(ValType::Bool),
+                ir_gen: |ctx, arg1| {
                     ctx.builder.exprs.push(Instr {
-                        local: Some(is_float1_local),
-                        kind: InstrKind::Is(ValType::Float, arg1),
+                        local: Some(ctx.dest),
+                        kind: InstrKind::Is(ValType::ErrorObject, arg1),
                     });
+                },
+            }],
+            Builtin::ErrorObjectMessage => vec![BuiltinConversionRule::Unary {
+                args: [Type::Val(ValType::ErrorObject)],
+                ret: Type::Val(ValType::String),
+                ir_gen: |ctx, arg1| {
                     ctx.builder.exprs.push(Instr {
-                        local: Some(is_float2_local),
-                        kind: InstrKind::Is(ValType::Float, arg2),
+                        local: Some(ctx.dest),
+                        kind: InstrKind::ErrorObjectMessage(arg1),
                     });
+                },
+            }],
+            Builtin::ErrorObjectIrritants => vec![BuiltinConversionRule::Unary {
+                args: [Type::Val(ValType::ErrorObject)],
+                ret: Type::Obj,
+                ir_gen: |ctx, arg1| {
                     ctx.builder.exprs.push(Instr {
-                        local: Some(is_float_local),
-                        kind: InstrKind::And(is_float1_local, is_float2_local),
+                        local: Some(ctx.dest),
+                        kind: InstrKind::ErrorObjectIrritants(arg1),
                     });
-                    let is_float_then_bb_id = ctx.builder.bbs.allocate_key();
-                    let is_float_else_bb_id = ctx.builder.bbs.allocate_key();
-                    ctx.builder.close_bb(TerminatorInstr::If(
-                        is_float_local,
-                        is_float_then_bb_id,
-                        is_float_else_bb_id,
-                    ));
-                    let is_float_dest_local = ctx.builder.local(Type::Val(ValType::Bool));
-                    ctx.builder.current_bb_id = Some(is_float_then_bb_id);
-                    let from_obj_float1_local = ctx.builder.local(Type::Val(ValType::Float));
-                    let from_obj_float2_local = ctx.builder.local(Type::Val(ValType::Float));
+                },
+            }],
+            Builtin::MakeErrorObject => vec![BuiltinConversionRule::Binary {
+                args: [Type::Val(ValType::String), Type::Obj],
+                ret: Type::Val(ValType::ErrorObject),
+                ir_gen: |ctx, arg1, arg2| {
                     ctx.builder.exprs.push(Instr {
-                        local: Some(from_obj_float1_local),
-                        kind: InstrKind::FromObj(ValType::Float, arg1),
+                        local: Some(ctx.dest),
+                        kind: InstrKind::MakeErrorObject(arg1, arg2),
                     });
+                },
+            }],
+            Builtin::Raise => vec![BuiltinConversionRule::Unary {
+                args: [Type::Obj],
+                ret: Type::Obj,
+                ir_gen: |ctx, arg1| {
                     ctx.builder.exprs.push(Instr {
-                        local: Some(from_obj_float2_local),
-                        kind: InstrKind::FromObj(ValType::Float, arg2),
+                        local: Some(ctx.dest),
+                        kind: InstrKind::Raise(arg1),
                     });
+                },
+            }],
+            Builtin::WithRaiseHandler => vec![BuiltinConversionRule::Binary {
+                args: [Type::Val(ValType::Closure), Type::Val(ValType::Closure)],
+                ret: Type::Obj,
+                ir_gen: |ctx, arg1, arg2| {
                     ctx.builder.exprs.push(Instr {
-                        local: Some(is_float_dest_local),
-                        kind: InstrKind::EqFloat(from_obj_float1_local, from_obj_float2_local),
+                        local: Some(ctx.dest),
+                        kind: InstrKind::WithRaiseHandler(arg1, arg2),
                     });
-                    ctx.builder.close_bb(TerminatorInstr::Jump(merge_bb_id));
-
-                    ctx.builder.current_bb_id = Some(is_float_else_bb_id);
-                    let is_char1_local = ctx.builder.local(Type::Val(ValType::Bool));
-                    let is_char2_local = ctx.builder.local(Type::Val(ValType::Bool));
-                    let is_char_local = ctx.builder.local(Type::Val(ValType::Bool));
+                },
+            }],
+            Builtin::ErrorObjectKind => vec![BuiltinConversionRule::Unary {
+                args: [Type::Val(ValType::ErrorObject)],
+                ret: Type::Val(ValType::Int),
+                ir_gen: |ctx, arg1| {
                     ctx.builder.exprs.push(Instr {
-                        local: Some(is_char1_local),
-                        kind: InstrKind::Is(ValType::Char, arg1),
+                        local: Some(ctx.dest),
+                        kind: InstrKind::ErrorObjectKind(arg1),
                     });
+                },
+            }],
+            Builtin::IsPort => vec![BuiltinConversionRule::Unary {
+                args: [Type::Obj],
+                ret: Type::Val(ValType::Bool),
+                ir_gen: |ctx, arg1| {
                     ctx.builder.exprs.push(Instr {
-                        local: Some(is_char2_local),
-                        kind: InstrKind::Is(ValType::Char, arg2),
+                        local: Some(ctx.dest),
+                        kind: InstrKind::Is(ValType::Port, arg1),
                     });
+                },
+            }],
+            Builtin::IsInputPort => vec![BuiltinConversionRule::Unary {
+                args: [Type::Val(ValType::Port)],
+                ret: Type::Val(ValType::Bool),
+                ir_gen: |ctx, arg1| {
                     ctx.builder.exprs.push(Instr {
-                        local: Some(is_char_local),
-                        kind: InstrKind::And(is_char1_local, is_char2_local),
+                        local: Some(ctx.dest),
+                        kind: InstrKind::IsInputPort(arg1),
                     });
-                    let is_char_then_bb_id = ctx.builder.bbs.allocate_key();
-                    let is_char_else_bb_id = ctx.builder.bbs.allocate_key();
-                    ctx.builder.close_bb(TerminatorInstr::If(
-                        is_char_local,
-                        is_char_then_bb_id,
-                        is_char_else_bb_id,
-                    ));
-                    let is_char_dest_local = ctx.builder.local(Type::Val(ValType::Bool));
-                    ctx.builder.current_bb_id = Some(is_char_then_bb_id);
-                    let from_obj_char1_local = ctx.builder.local(Type::Val(ValType::Char));
-                    let from_obj_char2_local = ctx.builder.local(Type::Val(ValType::Char));
+                },
+            }],
+            Builtin::IsOutputPort => vec![BuiltinConversionRule::Unary {
+                args: [Type::Val(ValType::Port)],
+                ret: Type::Val(ValType::Bool),
+                ir_gen: |ctx, arg1| {
                     ctx.builder.exprs.push(Instr {
-                        local: Some(from_obj_char1_local),
-                        kind: InstrKind::FromObj(ValType::Char, arg1),
+                        local: Some(ctx.dest),
+                        kind: InstrKind::IsOutputPort(arg1),
                     });
+                },
+            }],
+            Builtin::OpenInputString => vec![BuiltinConversionRule::Unary {
+                args: [Type::Val(ValType::String)],
+                ret: Type::Val(ValType::Port),
+                ir_gen: |ctx, arg1| {
                     ctx.builder.exprs.push(Instr {
-                        local: Some(from_obj_char2_local),
-                        kind: InstrKind::FromObj(ValType::Char, arg2),
+                        local: Some(ctx.dest),
+                        kind: InstrKind::OpenInputString(arg1),
                     });
+                },
+            }],
+            Builtin::OpenOutputString => vec![BuiltinConversionRule::Nullary {
+                args: [],
+                ret: Type::Val(ValType::Port),
+                ir_gen: |ctx| {
                     ctx.builder.exprs.push(Instr {
-                        local: Some(is_char_dest_local),
-                        kind: InstrKind::EqChar(from_obj_char1_local, from_obj_char2_local),
+                        local: Some(ctx.dest),
+                        kind: InstrKind::OpenOutputString,
                     });
-                    ctx.builder.close_bb(TerminatorInstr::Jump(merge_bb_id));
-                    ctx.builder.current_bb_id = Some(is_char_else_bb_id);
-                    let false_local = ctx.builder.local(Type::Val(ValType::Bool));
+                },
+            }],
+            Builtin::GetOutputString => vec![BuiltinConversionRule::Unary {
+                args: [Type::Val(ValType::Port)],
+                ret: Type::Val(ValType::String),
+                ir_gen: |ctx, arg1| {
                     ctx.builder.exprs.push(Instr {
-                        local: Some(false_local),
-                        kind: InstrKind::Bool(false),
+                        local: Some(ctx.dest),
+                        kind: InstrKind::GetOutputString(arg1),
                     });
-                    ctx.builder.close_bb(TerminatorInstr::Jump(merge_bb_id));
-                    ctx.builder.current_bb_id = Some(merge_bb_id);
-
+                },
+            }],
+            Builtin::ClosePort => vec![BuiltinConversionRule::Unary {
+                args: [Type::Val(ValType::Port)],
+                ret: Type::Val(ValType::Nil),
+                ir_gen: |ctx, arg1| {
                     ctx.builder.exprs.push(Instr {
                         local: Some(ctx.dest),
-                        kind: InstrKind::Phi {
-                            incomings: vec![
-                                PhiIncomingValue {
-                                    local: eq_obj_dest_local,
-                                    bb: eq_obj_then_bb_id,
-                                },
-                                PhiIncomingValue {
-                                    local: is_int_dest_local,
-                                    bb: is_int_then_bb_id,
-                                },
-                                PhiIncomingValue {
-                                    local: is_float_dest_local,
-                                    bb: is_float_then_bb_id,
-                                },
-                                PhiIncomingValue {
-                                    local: is_char_dest_local,
-                                    bb: is_char_then_bb_id,
-                                },
-                                PhiIncomingValue {
-                                    local: false_local,
-                                    bb: is_char_else_bb_id,
-                                },
-                            ],
-                            non_exhaustive: false,
-                        },
+                        kind: InstrKind::ClosePort(arg1),
                     });
                 },
             }],
-            Builtin::Cons => vec![BuiltinConversionRule::Binary {
-                args: [Type::Obj, Type::Obj],
-                ret: Type::Val(ValType::Cons),
-                ir_gen: |ctx, arg1, arg2| {
+            Builtin::StandardPort => vec![BuiltinConversionRule::Unary {
+                args: [Type::Val(ValType::Int)],
+                ret: Type::Val(ValType::Port),
+                ir_gen: |ctx, arg1| {
                     ctx.builder.exprs.push(Instr {
                         local: Some(ctx.dest),
-                        kind: InstrKind::Cons(arg1, arg2),
+                        kind: InstrKind::StandardPort(arg1),
                     });
                 },
             }],
-            Builtin::Car => vec![BuiltinConversionRule::Unary {
-                args: [Type::Val(ValType::Cons)],
+            Builtin::ReadChar => vec![BuiltinConversionRule::Unary {
+                args: [Type::Val(ValType::Port)],
                 ret: Type::Obj,
                 ir_gen: |ctx, arg1| {
                     ctx.builder.exprs.push(Instr {
                         local: Some(ctx.dest),
-                        kind: InstrKind::Car(arg1),
+                        kind: InstrKind::ReadChar(arg1),
                     });
                 },
             }],
-            Builtin::Cdr => vec![BuiltinConversionRule::Unary {
-                args: [Type::Val(ValType::Cons)],
+            Builtin::PeekChar => vec![BuiltinConversionRule::Unary {
+                args: [Type::Val(ValType::Port)],
                 ret: Type::Obj,
                 ir_gen: |ctx, arg1| {
                     ctx.builder.exprs.push(Instr {
                         local: Some(ctx.dest),
-                        kind: InstrKind::Cdr(arg1),
+                        kind: InstrKind::PeekChar(arg1),
                     });
                 },
             }],
-            Builtin::SetCar => vec![BuiltinConversionRule::Binary {
-                args: [Type::Val(ValType::Cons), Type::Obj],
-                ret: Type::Val(ValType::Nil),
-                ir_gen: |ctx, arg1, arg2| {
+            Builtin::Read => vec![BuiltinConversionRule::Unary {
+                args: [Type::Val(ValType::Port)],
+                ret: Type::Obj,
+                ir_gen: |ctx, arg1| {
                     ctx.builder.exprs.push(Instr {
                         local: Some(ctx.dest),
-                        kind: InstrKind::SetCar(arg1, arg2),
+                        kind: InstrKind::Read(arg1),
                     });
                 },
             }],
-            Builtin::SetCdr => vec![BuiltinConversionRule::Binary {
-                args: [Type::Val(ValType::Cons), Type::Obj],
-                ret: Type::Val(ValType::Nil),
-                ir_gen: |ctx, arg1, arg2| {
+            Builtin::EofObject => vec![BuiltinConversionRule::Nullary {
+                args: [],
+                ret: Type::Obj,
+                ir_gen: |ctx| {
                     ctx.builder.exprs.push(Instr {
                         local: Some(ctx.dest),
-                        kind: InstrKind::SetCdr(arg1, arg2),
+                        kind: InstrKind::EofObject,
                     });
                 },
             }],
-            Builtin::SymbolToString => vec![BuiltinConversionRule::Unary {
-                args: [Type::Val(ValType::Symbol)],
-                ret: Type::Val(ValType::String),
+            Builtin::SetCurrentOutputPort => vec![BuiltinConversionRule::Unary {
+                args: [Type::Val(ValType::Port)],
+                ret: Type::Val(ValType::Nil),
                 ir_gen: |ctx, arg1| {
                     ctx.builder.exprs.push(Instr {
                         local: Some(ctx.dest),
-                        kind: InstrKind::SymbolToString(arg1),
+                        kind: InstrKind::SetCurrentOutputPort(arg1),
                     });
                 },
             }],
-            Builtin::StringToSymbol => vec![BuiltinConversionRule::Unary {
-                args: [Type::Val(ValType::String)],
-                ret: Type::Val(ValType::Symbol),
+            Builtin::Eval => vec![BuiltinConversionRule::Unary {
+                args: [Type::Obj],
+                ret: Type::Obj,
                 ir_gen: |ctx, arg1| {
                     ctx.builder.exprs.push(Instr {
                         local: Some(ctx.dest),
-                        kind: InstrKind::StringToSymbol(arg1),
+                        kind: InstrKind::Eval(arg1),
                     });
                 },
             }],
-            Builtin::NumberToString => vec![
-                BuiltinConversionRule::Unary {
-                    args: [Type::Val(ValType::Int)],
-                    ret: Type::Val(ValType::String),
-                    ir_gen: |ctx, arg1| {
-                        ctx.builder.exprs.push(Instr {
-                            local: Some(ctx.dest),
-                            kind: InstrKind::IntToString(arg1),
-                        });
-                    },
-                },
-                BuiltinConversionRule::Unary {
-                    args: [Type::Val(ValType::Float)],
-                    ret: Type::Val(ValType::String),
-                    ir_gen: |ctx, arg1| {
+            Builtin::Apply => vec![BuiltinConversionRule::Binary {
+                args: [Type::Val(ValType::Closure), Type::Obj],
+                ret: Type::Obj,
+                ir_gen: |ctx, arg1, arg2| {
+                    let args_local = ctx.builder.local(LocalType::VariadicArgs);
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(args_local),
+                        kind: InstrKind::ListToVariadicArgs(arg2),
+                    });
+                    let call_closure = InstrCallClosure {
+                        closure: arg1,
+                        args: vec![args_local],
+                        arg_types: vec![LocalType::VariadicArgs],
+                        func_index: 0,
+                    };
+                    if ctx.is_tail {
+                        ctx.builder
+                            .close_bb(TerminatorInstr::Exit(ExitInstr::TailCallClosure(
+                                call_closure,
+                            )));
+                    } else {
                         ctx.builder.exprs.push(Instr {
                             local: Some(ctx.dest),
-                            kind: InstrKind::FloatToString(arg1),
+                            kind: InstrKind::CallClosure(call_closure),
                         });
-                    },
+                    }
                 },
-            ],
-            Builtin::StringRef => vec![BuiltinConversionRule::Binary {
-                args: [Type::Val(ValType::String), Type::Val(ValType::Int)],
-                ret: Type::Val(ValType::Char),
-                ir_gen: |ctx, arg1, arg2| {
+            }],
+            Builtin::MakeHashTable => vec![BuiltinConversionRule::Unary {
+                args: [Type::Val(ValType::Int)],
+                ret: Type::Val(ValType::HashTable),
+                ir_gen: |ctx, arg1| {
                     ctx.builder.exprs.push(Instr {
                         local: Some(ctx.dest),
-                        kind: InstrKind::StringRef(arg1, arg2),
+                        kind: InstrKind::MakeHashTable(arg1),
                     });
                 },
             }],
-            Builtin::StringCopy => vec![BuiltinConversionRule::Unary {
-                args: [Type::Val(ValType::String)],
-                ret: Type::Val(ValType::String),
-                ir_gen: |ctx, arg| {
+            Builtin::IsHashTable => vec![BuiltinConversionRule::Unary {
+                args: [Type::Obj],
+                ret: Type::Val(ValType::Bool),
+                ir_gen: |ctx, arg1| {
                     ctx.builder.exprs.push(Instr {
                         local: Some(ctx.dest),
-                        kind: InstrKind::StringCopy(arg),
+                        kind: InstrKind::Is(ValType::HashTable, arg1),
                     });
                 },
             }],
-            Builtin::StringSet => vec![BuiltinConversionRule::Ternary {
-                args: [
-                    Type::Val(ValType::String),
-                    Type::Val(ValType::Int),
-                    Type::Val(ValType::Char),
-                ],
+            Builtin::HashTableRef => vec![BuiltinConversionRule::Ternary {
+                args: [Type::Val(ValType::HashTable), Type::Obj, Type::Obj],
+                ret: Type::Obj,
+                ir_gen: |ctx, arg1, arg2, arg3| {
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(ctx.dest),
+                        kind: InstrKind::HashTableRef(arg1, arg2, arg3),
+                    });
+                },
+            }],
+            Builtin::HashTableSet => vec![BuiltinConversionRule::Ternary {
+                args: [Type::Val(ValType::HashTable), Type::Obj, Type::Obj],
                 ret: Type::Val(ValType::Nil),
                 ir_gen: |ctx, arg1, arg2, arg3| {
                     ctx.builder.exprs.push(Instr {
                         local: Some(ctx.dest),
-                        kind: InstrKind::StringSet(arg1, arg2, arg3),
+                        kind: InstrKind::HashTableSet(arg1, arg2, arg3),
                     });
                 },
             }],
-            Builtin::StringLength => vec![BuiltinConversionRule::Unary {
-                args: [Type::Val(ValType::String)],
+            Builtin::HashTableDelete => vec![BuiltinConversionRule::Binary {
+                args: [Type::Val(ValType::HashTable), Type::Obj],
+                ret: Type::Val(ValType::Nil),
+                ir_gen: |ctx, arg1, arg2| {
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(ctx.dest),
+                        kind: InstrKind::HashTableDelete(arg1, arg2),
+                    });
+                },
+            }],
+            Builtin::HashTableCount => vec![BuiltinConversionRule::Unary {
+                args: [Type::Val(ValType::HashTable)],
                 ret: Type::Val(ValType::Int),
                 ir_gen: |ctx, arg1| {
                     ctx.builder.exprs.push(Instr {
                         local: Some(ctx.dest),
-                        kind: InstrKind::StringLength(arg1),
+                        kind: InstrKind::HashTableCount(arg1),
                     });
                 },
             }],
-            Builtin::EqNum => vec![
-                BuiltinConversionRule::Binary {
-                    args: [Type::Val(ValType::Int), Type::Val(ValType::Int)],
-                    ret: Type::Val(ValType::Bool),
-                    ir_gen: |ctx, arg1, arg2| {
-                        ctx.builder.exprs.push(Instr {
-                            local: Some(ctx.dest),
-                            kind: InstrKind::EqInt(arg1, arg2),
-                        });
-                    },
-                },
-                BuiltinConversionRule::Binary {
-                    args: [Type::Val(ValType::Float), Type::Val(ValType::Float)],
-                    ret: Type::Val(ValType::Bool),
-                    ir_gen: |ctx, arg1, arg2| {
-                        ctx.builder.exprs.push(Instr {
-                            local: Some(ctx.dest),
-                            kind: InstrKind::EqFloat(arg1, arg2),
-                        });
-                    },
+            Builtin::HashTableToAlist => vec![BuiltinConversionRule::Unary {
+                args: [Type::Val(ValType::HashTable)],
+                ret: Type::Obj,
+                ir_gen: |ctx, arg1| {
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(ctx.dest),
+                        kind: InstrKind::HashTableToAlist(arg1),
+                    });
                 },
-            ],
-            Builtin::StringEq => vec![BuiltinConversionRule::Binary {
-                args: [Type::Val(ValType::String), Type::Val(ValType::String)],
+            }],
+            // レコード型のIDがリテラルで渡されるので、gen_record_builtin_callで生成する
+            Builtin::MakeRecord | Builtin::IsRecordOf | Builtin::RecordRef | Builtin::RecordSet => {
+                vec![]
+            }
+            Builtin::IsRecord => vec![BuiltinConversionRule::Unary {
+                args: [Type::Obj],
                 ret: Type::Val(ValType::Bool),
-                ir_gen: |ctx, arg1, arg2| {
+                ir_gen: |ctx, arg1| {
                     ctx.builder.exprs.push(Instr {
                         local: Some(ctx.dest),
-                        kind: InstrKind::EqString(arg1, arg2),
+                        kind: InstrKind::IsRecord(arg1),
                     });
                 },
             }],
-            Builtin::Lt => vec![
-                BuiltinConversionRule::Binary {
-                    args: [Type::Val(ValType::Int), Type::Val(ValType::Int)],
-                    ret: Type::Val(ValType::Bool),
-                    ir_gen: |ctx, arg1, arg2| {
-                        ctx.builder.exprs.push(Instr {
-                            local: Some(ctx.dest),
-                            kind: InstrKind::LtInt(arg1, arg2),
-                        });
-                    },
-                },
-                BuiltinConversionRule::Binary {
-                    args: [Type::Val(ValType::Float), Type::Val(ValType::Float)],
-                    ret: Type::Val(ValType::Bool),
-                    ir_gen: |ctx, arg1, arg2| {
-                        ctx.builder.exprs.push(Instr {
-                            local: Some(ctx.dest),
-                            kind: InstrKind::LtFloat(arg1, arg2),
-                        });
-                    },
-                },
-            ],
-            Builtin::Gt => vec![
-                BuiltinConversionRule::Binary {
-                    args: [Type::Val(ValType::Int), Type::Val(ValType::Int)],
-                    ret: Type::Val(ValType::Bool),
-                    ir_gen: |ctx, arg1, arg2| {
-                        ctx.builder.exprs.push(Instr {
-                            local: Some(ctx.dest),
-                            kind: InstrKind::GtInt(arg1, arg2),
-                        });
-                    },
-                },
-                BuiltinConversionRule::Binary {
-                    args: [Type::Val(ValType::Float), Type::Val(ValType::Float)],
-                    ret: Type::Val(ValType::Bool),
-                    ir_gen: |ctx, arg1, arg2| {
-                        ctx.builder.exprs.push(Instr {
-                            local: Some(ctx.dest),
-                            kind: InstrKind::GtFloat(arg1, arg2),
-                        });
-                    },
-                },
-            ],
-            Builtin::Le => vec![
-                BuiltinConversionRule::Binary {
-                    args: [Type::Val(ValType::Int), Type::Val(ValType::Int)],
-                    ret: Type::Val(ValType::Bool),
-                    ir_gen: |ctx, arg1, arg2| {
-                        ctx.builder.exprs.push(Instr {
-                            local: Some(ctx.dest),
-                            kind: InstrKind::LeInt(arg1, arg2),
-                        });
-                    },
-                },
-                BuiltinConversionRule::Binary {
-                    args: [Type::Val(ValType::Float), Type::Val(ValType::Float)],
-                    ret: Type::Val(ValType::Bool),
-                    ir_gen: |ctx, arg1, arg2| {
-                        ctx.builder.exprs.push(Instr {
-                            local: Some(ctx.dest),
-                            kind: InstrKind::LeFloat(arg1, arg2),
-                        });
-                    },
+            // %record?で検査した値にのみ使う
+            Builtin::RecordType => vec![BuiltinConversionRule::Unary {
+                args: [Type::Obj],
+                ret: Type::Obj,
+                ir_gen: |ctx, arg1| {
+                    ctx.builder.exprs.push(Instr {
+                        local: Some(ctx.dest),
+                        kind: InstrKind::RecordType(arg1),
+                    });
                 },
+            }],
+        }
+    }
+
+    // レコード型のIDなどをリテラルで受け取る組み込み関数か
+    pub fn takes_record_literals(builtin: ast::Builtin) -> bool {
+        matches!(
+            builtin,
+            ast::Builtin::MakeRecord
+                | ast::Builtin::IsRecordOf
+                | ast::Builtin::RecordRef
+                | ast::Builtin::RecordSet
+        )
+    }
+
+    // 継続が無効な場合は使用できない組み込み関数か
+    pub fn requires_continuation(builtin: ast::Builtin) -> bool {
+        matches!(builtin, ast::Builtin::CallCc | ast::Builtin::Resume)
+    }
+}
+
+// 引数の数に合う規則がない場合に、二項演算の規則を組み合わせて展開する方法
+// 第一級の値として使う場合はstdlibで同じ展開を行う
+#[derive(Debug, Clone, Copy)]
+pub enum BuiltinVariadicRule {
+    // (+ a b c) => (+ (+ a b) c)
+    // 引数が1つなら(+ 単位元 a)、引数がなければ単位元を返す
+    Fold { identity: i64 },
+    // (- a b c) => (- (- a b) c)
+    // 引数が1つなら(- 単位元 a)
+    FoldInverse { identity: i64 },
+    // (max a b c) => (max (max a b) c)
+    // 引数が1つなら(max a a)
+    FoldSelf,
+    // (< a b c) => (and (< a b) (< b c))
+    Chain,
+}
+
+impl BuiltinVariadicRule {
+    pub fn from_builtin(builtin: ast::Builtin) -> Option<BuiltinVariadicRule> {
+        use ast::Builtin;
+
+        match builtin {
+            Builtin::Add => Some(BuiltinVariadicRule::Fold { identity: 0 }),
+            Builtin::Mul => Some(BuiltinVariadicRule::Fold { identity: 1 }),
+            Builtin::Sub => Some(BuiltinVariadicRule::FoldInverse { identity: 0 }),
+            Builtin::Div => Some(BuiltinVariadicRule::FoldInverse { identity: 1 }),
+            Builtin::Max | Builtin::Min => Some(BuiltinVariadicRule::FoldSelf),
+            Builtin::EqNum | Builtin::Lt | Builtin::Gt | Builtin::Le | Builtin::Ge => {
+                Some(BuiltinVariadicRule::Chain)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn min_arg_count(self) -> usize {
+        match self {
+            BuiltinVariadicRule::Fold { .. } => 0,
+            BuiltinVariadicRule::FoldInverse { .. } | BuiltinVariadicRule::FoldSelf => 1,
+            BuiltinVariadicRule::Chain => 2,
+        }
+    }
+}
+
+// 引数の少なくとも一方がbigintである場合の規則
+// ir_genは引数をobj_argsから取得する
+fn integer_rules(
+    ret: Type,
+    ir_gen: fn(BuiltinIrGenCtx, LocalId, LocalId),
+) -> [BuiltinConversionRule; 3] {
+    [
+        [Type::Val(ValType::Int), Type::Val(ValType::BigInt)],
+        [Type::Val(ValType::BigInt), Type::Val(ValType::Int)],
+        [Type::Val(ValType::BigInt), Type::Val(ValType::BigInt)],
+    ]
+    .map(|args| BuiltinConversionRule::Binary { args, ret, ir_gen })
+}
+
+// 引数の少なくとも一方がrationalで、もう一方が正確数である場合の規則
+// ir_genは引数をobj_argsから取得する
+fn rational_rules(
+    ret: Type,
+    ir_gen: fn(BuiltinIrGenCtx, LocalId, LocalId),
+) -> [BuiltinConversionRule; 5] {
+    [
+        [Type::Val(ValType::Int), Type::Val(ValType::Rational)],
+        [Type::Val(ValType::BigInt), Type::Val(ValType::Rational)],
+        [Type::Val(ValType::Rational), Type::Val(ValType::Int)],
+        [Type::Val(ValType::Rational), Type::Val(ValType::BigInt)],
+        [Type::Val(ValType::Rational), Type::Val(ValType::Rational)],
+    ]
+    .map(|args| BuiltinConversionRule::Binary { args, ret, ir_gen })
+}
+
+// 整数をそのまま返す規則
+fn integer_identity_rules() -> [BuiltinConversionRule; 2] {
+    [ValType::Int, ValType::BigInt].map(|typ| BuiltinConversionRule::Unary {
+        args: [Type::Val(typ)],
+        ret: Type::Val(typ),
+        ir_gen: |ctx, arg1| {
+            ctx.builder.exprs.push(Instr {
+                local: Some(ctx.dest),
+                kind: InstrKind::Move(arg1),
+            });
+        },
+    })
+}
+
+// 不正確数に変換してから計算する規則
+fn float_math_rules(ir_gen: fn(BuiltinIrGenCtx, LocalId)) -> Vec<BuiltinConversionRule> {
+    [
+        ValType::Float,
+        ValType::Int,
+        ValType::BigInt,
+        ValType::Rational,
+    ]
+    .map(|typ| BuiltinConversionRule::Unary {
+        args: [Type::Val(typ)],
+        ret: Type::Val(ValType::Float),
+        ir_gen,
+    })
+    .into()
+}
+
+fn float_math_binary_rules(
+    ir_gen: fn(BuiltinIrGenCtx, LocalId, LocalId),
+) -> [BuiltinConversionRule; 2] {
+    [ValType::Float, ValType::Int].map(|typ| BuiltinConversionRule::Binary {
+        args: [Type::Val(typ), Type::Val(typ)],
+        ret: Type::Val(ValType::Float),
+        ir_gen,
+    })
+}
+
+// 指定した種類のuvectorに対する規則
+// ir_genは第1引数の型から種類を取得する
+fn uvector_length_rules(kinds: &[UVectorKind]) -> Vec<BuiltinConversionRule> {
+    kinds
+        .iter()
+        .map(|&kind| BuiltinConversionRule::Unary {
+            args: [Type::Val(ValType::UVector(kind))],
+            ret: Type::Val(ValType::Int),
+            ir_gen: |ctx, arg1| {
+                let kind = uvector_kind_of(&ctx, arg1);
+                ctx.builder.exprs.push(Instr {
+                    local: Some(ctx.dest),
+                    kind: InstrKind::UVectorLength(kind, arg1),
+                });
+            },
+        })
+        .collect()
+}
+
+fn uvector_ref_rules(kinds: &[UVectorKind]) -> Vec<BuiltinConversionRule> {
+    kinds
+        .iter()
+        .map(|&kind| BuiltinConversionRule::Binary {
+            args: [Type::Val(ValType::UVector(kind)), Type::Val(ValType::Int)],
+            ret: Type::Val(kind.element_type()),
+            ir_gen: |ctx, arg1, arg2| {
+                let kind = uvector_kind_of(&ctx, arg1);
+                ctx.builder.exprs.push(Instr {
+                    local: Some(ctx.dest),
+                    kind: InstrKind::UVectorRef(kind, arg1, arg2),
+                });
+            },
+        })
+        .collect()
+}
+
+fn uvector_set_rules(kinds: &[UVectorKind]) -> Vec<BuiltinConversionRule> {
+    kinds
+        .iter()
+        .map(|&kind| BuiltinConversionRule::Ternary {
+            args: [
+                Type::Val(ValType::UVector(kind)),
+                Type::Val(ValType::Int),
+                Type::Val(kind.element_type()),
             ],
-            Builtin::Ge => vec![
-                BuiltinConversionRule::Binary {
-                    args: [Type::Val(ValType::Int), Type::Val(ValType::Int)],
-                    ret: Type::Val(ValType::Bool),
-                    ir_gen: |ctx, arg1, arg2| {
-                        ctx.builder.exprs.push(Instr {
-                            local: Some(ctx.dest),
-                            kind: InstrKind::GeInt(arg1, arg2),
-                        });
-                    },
+            ret: Type::Val(ValType::Nil),
+            ir_gen: |ctx, arg1, arg2, arg3| {
+                let kind = uvector_kind_of(&ctx, arg1);
+                ctx.builder.exprs.push(Instr {
+                    local: Some(ctx.dest),
+                    kind: InstrKind::UVectorSet(kind, arg1, arg2, arg3),
+                });
+            },
+        })
+        .collect()
+}
+
+fn uvector_kind_of(ctx: &BuiltinIrGenCtx, arg: LocalId) -> UVectorKind {
+    match ctx.builder.locals[arg].typ {
+        LocalType::Type(Type::Val(ValType::UVector(kind))) => kind,
+        _ => unreachable!("uvector rule applied to a non-uvector argument"),
+    }
+}
+
+// 引数の型に応じてfloatに変換する。int以外の正確数はobj_argsから変換する
+fn gen_to_floats<const N: usize>(ctx: &mut BuiltinIrGenCtx, args: [LocalId; N]) -> [LocalId; N] {
+    let mut i = 0;
+    args.map(|arg| {
+        let obj_arg = ctx.obj_args[i];
+        i += 1;
+        let kind = match ctx.builder.locals[arg].typ {
+            LocalType::Type(Type::Val(ValType::Float)) => return arg,
+            LocalType::Type(Type::Val(ValType::Int)) => InstrKind::IntToFloat(arg),
+            _ => InstrKind::RationalToFloat(obj_arg),
+        };
+        let float_local = ctx.builder.local(Type::Val(ValType::Float));
+        ctx.builder.exprs.push(Instr {
+            local: Some(float_local),
+            kind,
+        });
+        float_local
+    })
+}
+
+fn gen_float_math(mut ctx: BuiltinIrGenCtx, arg: LocalId, op: fn(LocalId) -> InstrKind) {
+    let [x] = gen_to_floats(&mut ctx, [arg]);
+    ctx.builder.exprs.push(Instr {
+        local: Some(ctx.dest),
+        kind: op(x),
+    });
+}
+
+// cond ? then_gen() : else_gen() の結果(obj)をdestに合流させる
+fn gen_if_obj(
+    ctx: BuiltinIrGenCtx,
+    cond: LocalId,
+    then_gen: impl FnOnce(&mut IrFuncBuilder) -> InstrKind,
+    else_gen: impl FnOnce(&mut IrFuncBuilder) -> InstrKind,
+) {
+    let merge_bb_id = ctx.builder.bbs.allocate_key();
+    let then_bb_id = ctx.builder.bbs.allocate_key();
+    let else_bb_id = ctx.builder.bbs.allocate_key();
+    ctx.builder
+        .close_bb(TerminatorInstr::If(cond, then_bb_id, else_bb_id));
+
+    ctx.builder.current_bb_id = Some(then_bb_id);
+    let then_local = ctx.builder.local(Type::Obj);
+    let kind = then_gen(ctx.builder);
+    ctx.builder.exprs.push(Instr {
+        local: Some(then_local),
+        kind,
+    });
+    ctx.builder.close_bb(TerminatorInstr::Jump(merge_bb_id));
+
+    ctx.builder.current_bb_id = Some(else_bb_id);
+    let else_local = ctx.builder.local(Type::Obj);
+    let kind = else_gen(ctx.builder);
+    ctx.builder.exprs.push(Instr {
+        local: Some(else_local),
+        kind,
+    });
+    ctx.builder.close_bb(TerminatorInstr::Jump(merge_bb_id));
+
+    ctx.builder.current_bb_id = Some(merge_bb_id);
+    ctx.builder.exprs.push(Instr {
+        local: Some(ctx.dest),
+        kind: InstrKind::Phi {
+            incomings: vec![
+                PhiIncomingValue {
+                    local: then_local,
+                    bb: then_bb_id,
                 },
-                BuiltinConversionRule::Binary {
-                    args: [Type::Val(ValType::Float), Type::Val(ValType::Float)],
-                    ret: Type::Val(ValType::Bool),
-                    ir_gen: |ctx, arg1, arg2| {
-                        ctx.builder.exprs.push(Instr {
-                            local: Some(ctx.dest),
-                            kind: InstrKind::GeFloat(arg1, arg2),
-                        });
-                    },
+                PhiIncomingValue {
+                    local: else_local,
+                    bb: else_bb_id,
                 },
             ],
-        }
-    }
+            non_exhaustive: false,
+        },
+    });
+}
+
+fn gen_zero_obj(builder: &mut IrFuncBuilder) -> LocalId {
+    let zero = builder.local(Type::Val(ValType::Int));
+    let zero_obj = builder.local(Type::Obj);
+    builder.exprs.push(Instr {
+        local: Some(zero),
+        kind: InstrKind::Int(0),
+    });
+    builder.exprs.push(Instr {
+        local: Some(zero_obj),
+        kind: InstrKind::ToObj(ValType::Int, zero),
+    });
+    zero_obj
+}
+
+// i64::MINの絶対値のみi64に収まらない
+fn gen_abs_int(ctx: BuiltinIrGenCtx, arg: LocalId) {
+    let min_local = ctx.builder.local(Type::Val(ValType::Int));
+    let overflow_local = ctx.builder.local(Type::Val(ValType::Bool));
+    ctx.builder.exprs.push(Instr {
+        local: Some(min_local),
+        kind: InstrKind::Int(i64::MIN),
+    });
+    ctx.builder.exprs.push(Instr {
+        local: Some(overflow_local),
+        kind: InstrKind::EqInt(arg, min_local),
+    });
+    let obj_arg = ctx.obj_args[0];
+    gen_if_obj(
+        ctx,
+        overflow_local,
+        |builder| InstrKind::SubInteger(gen_zero_obj(builder), obj_arg),
+        |builder| {
+            let abs_local = builder.local(Type::Val(ValType::Int));
+            builder.exprs.push(Instr {
+                local: Some(abs_local),
+                kind: InstrKind::AbsInt(arg),
+            });
+            InstrKind::ToObj(ValType::Int, abs_local)
+        },
+    );
+}
+
+// bigint/rational: 負なら0から引く
+fn gen_abs_exact(ctx: BuiltinIrGenCtx) {
+    let obj_arg = ctx.obj_args[0];
+    let zero_obj = gen_zero_obj(ctx.builder);
+    let cmp_local = ctx.builder.local(Type::Val(ValType::Int));
+    let zero_local = ctx.builder.local(Type::Val(ValType::Int));
+    let neg_local = ctx.builder.local(Type::Val(ValType::Bool));
+    ctx.builder.exprs.push(Instr {
+        local: Some(cmp_local),
+        kind: InstrKind::CmpRational(obj_arg, zero_obj),
+    });
+    ctx.builder.exprs.push(Instr {
+        local: Some(zero_local),
+        kind: InstrKind::Int(0),
+    });
+    ctx.builder.exprs.push(Instr {
+        local: Some(neg_local),
+        kind: InstrKind::LtInt(cmp_local, zero_local),
+    });
+    gen_if_obj(
+        ctx,
+        neg_local,
+        |_| InstrKind::SubRational(zero_obj, obj_arg),
+        |_| InstrKind::Move(obj_arg),
+    );
+}
+
+// min/max用。cmp(cmp_rational(a, b), 0) ? a : b
+fn gen_select_exact(ctx: BuiltinIrGenCtx, cmp: fn(LocalId, LocalId) -> InstrKind) {
+    let [a, b] = [ctx.obj_args[0], ctx.obj_args[1]];
+    let cmp_local = ctx.builder.local(Type::Val(ValType::Int));
+    let zero_local = ctx.builder.local(Type::Val(ValType::Int));
+    let cond_local = ctx.builder.local(Type::Val(ValType::Bool));
+    ctx.builder.exprs.push(Instr {
+        local: Some(cmp_local),
+        kind: InstrKind::CmpRational(a, b),
+    });
+    ctx.builder.exprs.push(Instr {
+        local: Some(zero_local),
+        kind: InstrKind::Int(0),
+    });
+    ctx.builder.exprs.push(Instr {
+        local: Some(cond_local),
+        kind: cmp(cmp_local, zero_local),
+    });
+    gen_if_obj(
+        ctx,
+        cond_local,
+        |_| InstrKind::Move(a),
+        |_| InstrKind::Move(b),
+    );
+}
+
+// exact?/inexact?用。数値以外は型エラーになる
+fn exactness_rules(
+    exact: fn(BuiltinIrGenCtx, LocalId),
+    inexact: fn(BuiltinIrGenCtx, LocalId),
+) -> Vec<BuiltinConversionRule> {
+    [ValType::Int, ValType::BigInt, ValType::Rational]
+        .map(|typ| BuiltinConversionRule::Unary {
+            args: [Type::Val(typ)],
+            ret: Type::Val(ValType::Bool),
+            ir_gen: exact,
+        })
+        .into_iter()
+        .chain([BuiltinConversionRule::Unary {
+            args: [Type::Val(ValType::Float)],
+            ret: Type::Val(ValType::Bool),
+            ir_gen: inexact,
+        }])
+        .collect()
+}
+
+/*
+if overflow(a, b) {
+    result = generic(obj_a, obj_b) // 組み込み関数呼び出しの合流点に直接渡す
+} else {
+    dest = op(a, b)
+}
+
+オーバーフローした場合のみobjになるので、高速なパスの結果はintのまま扱える
+JITではオーバーフローしない限りthen節はスタブになり、intの高速なパスのみが残る
+*/
+fn gen_int_op_with_overflow_check(
+    ctx: BuiltinIrGenCtx,
+    arg1: LocalId,
+    arg2: LocalId,
+    op: fn(LocalId, LocalId) -> InstrKind,
+    overflow: fn(LocalId, LocalId) -> InstrKind,
+    generic: fn(LocalId, LocalId) -> InstrKind,
+) {
+    let (merge_bb_id, merge_incomings) = ctx
+        .merge
+        .expect("overflow check requires a builtin call merge block");
+
+    let overflow_local = ctx.builder.local(Type::Val(ValType::Bool));
+    ctx.builder.exprs.push(Instr {
+        local: Some(overflow_local),
+        kind: overflow(arg1, arg2),
+    });
+
+    let overflow_then_bb_id = ctx.builder.bbs.allocate_key();
+    let overflow_else_bb_id = ctx.builder.bbs.allocate_key();
+    ctx.builder.close_bb(TerminatorInstr::If(
+        overflow_local,
+        overflow_then_bb_id,
+        overflow_else_bb_id,
+    ));
+
+    ctx.builder.current_bb_id = Some(overflow_then_bb_id);
+    let generic_dest_local = ctx.builder.local(Type::Obj);
+    ctx.builder.exprs.push(Instr {
+        local: Some(generic_dest_local),
+        kind: generic(ctx.obj_args[0], ctx.obj_args[1]),
+    });
+    merge_incomings.push(PhiIncomingValue {
+        local: generic_dest_local,
+        bb: overflow_then_bb_id,
+    });
+    ctx.builder.close_bb(TerminatorInstr::Jump(merge_bb_id));
+
+    ctx.builder.current_bb_id = Some(overflow_else_bb_id);
+    ctx.builder.exprs.push(Instr {
+        local: Some(ctx.dest),
+        kind: op(arg1, arg2),
+    });
+}
+
+// cmp(cmp_integer(a, b), 0)
+fn gen_cmp_integer(ctx: BuiltinIrGenCtx, cmp: fn(LocalId, LocalId) -> InstrKind) {
+    gen_cmp_generic(ctx, InstrKind::CmpInteger, cmp);
+}
+
+// cmp(cmp_rational(a, b), 0)
+fn gen_cmp_rational(ctx: BuiltinIrGenCtx, cmp: fn(LocalId, LocalId) -> InstrKind) {
+    gen_cmp_generic(ctx, InstrKind::CmpRational, cmp);
+}
+
+// 文字列の比較結果(-1, 0, 1)を0と比較する
+fn gen_cmp_string(
+    ctx: BuiltinIrGenCtx,
+    cmp_kind: InstrKind,
+    cmp: fn(LocalId, LocalId) -> InstrKind,
+) {
+    let cmp_local = ctx.builder.local(Type::Val(ValType::Int));
+    let zero_local = ctx.builder.local(Type::Val(ValType::Int));
+    ctx.builder.exprs.push(Instr {
+        local: Some(cmp_local),
+        kind: cmp_kind,
+    });
+    ctx.builder.exprs.push(Instr {
+        local: Some(zero_local),
+        kind: InstrKind::Int(0),
+    });
+    ctx.builder.exprs.push(Instr {
+        local: Some(ctx.dest),
+        kind: cmp(cmp_local, zero_local),
+    });
+}
+
+fn gen_cmp_generic(
+    ctx: BuiltinIrGenCtx,
+    generic: fn(LocalId, LocalId) -> InstrKind,
+    cmp: fn(LocalId, LocalId) -> InstrKind,
+) {
+    let cmp_local = ctx.builder.local(Type::Val(ValType::Int));
+    let zero_local = ctx.builder.local(Type::Val(ValType::Int));
+    ctx.builder.exprs.push(Instr {
+        local: Some(cmp_local),
+        kind: generic(ctx.obj_args[0], ctx.obj_args[1]),
+    });
+    ctx.builder.exprs.push(Instr {
+        local: Some(zero_local),
+        kind: InstrKind::Int(0),
+    });
+    ctx.builder.exprs.push(Instr {
+        local: Some(ctx.dest),
+        kind: cmp(cmp_local, zero_local),
+    });
 }
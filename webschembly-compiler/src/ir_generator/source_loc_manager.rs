@@ -0,0 +1,24 @@
+use vec_map::VecMap;
+use webschembly_compiler_ir::*;
+
+// JITで生成されるモジュールからも参照されるため、SourceLocIdはモジュールを跨いで一意にする
+#[derive(Debug, Default)]
+pub struct SourceLocManager {
+    source_locs: VecMap<SourceLocId, SourceLoc>,
+}
+
+impl SourceLocManager {
+    pub fn new() -> Self {
+        Self {
+            source_locs: VecMap::new(),
+        }
+    }
+
+    pub fn gen_source_loc(&mut self, source_loc: SourceLoc) -> SourceLocId {
+        self.source_locs.push(source_loc)
+    }
+
+    pub fn get(&self, id: SourceLocId) -> Option<&SourceLoc> {
+        self.source_locs.get(id)
+    }
+}
@@ -191,6 +191,25 @@ pub fn find_loop_headers(
     headers
 }
 
+// 後方エッジ (ジャンプ先が自身を支配しているエッジ) の一覧を (ジャンプ元, ループヘッダ) で返す
+pub fn find_back_edges(
+    cfg: &VecMap<BasicBlockId, BasicBlock>,
+    doms: &FxHashMap<BasicBlockId, FxHashSet<BasicBlockId>>,
+) -> Vec<(BasicBlockId, BasicBlockId)> {
+    let mut back_edges = Vec::new();
+    for (source_id, block) in cfg.iter() {
+        for target_id in block.terminator().successors() {
+            if doms
+                .get(&source_id)
+                .is_some_and(|source_doms| source_doms.contains(&target_id))
+            {
+                back_edges.push((source_id, target_id));
+            }
+        }
+    }
+    back_edges
+}
+
 pub fn find_merge_nodes(
     rpo: &FxHashMap<BasicBlockId, usize>,
     predecessors: &FxHashMap<BasicBlockId, Vec<BasicBlockId>>,
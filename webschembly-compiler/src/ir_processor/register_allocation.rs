@@ -7,7 +7,8 @@ use crate::ir_processor::{
     dataflow::{analyze_liveness, calc_def_use},
 };
 
-pub fn register_allocation(func: &mut Func) {
+// 元のLocalIdから新しいLocalIdへの対応を返す
+pub fn register_allocation(func: &mut Func) -> FxHashMap<LocalId, LocalId> {
     // リニアスキャンレジスタアロケーションの実装
     // 1. 各変数の生存区間(Live Interval)を計算
     // 2. 生存区間を元に、同時の生存しない変数を同じLocalIdに割り当てることで変数を削減する
@@ -124,6 +125,8 @@ pub fn register_allocation(func: &mut Func) {
             }
         }
     }
+
+    new_local_map
 }
 
 #[derive(Debug, Clone)]
@@ -1,3 +1,4 @@
+use ordered_float::NotNan;
 use rustc_hash::{FxHashMap, FxHashSet};
 
 use crate::ir_processor::{
@@ -197,7 +198,8 @@ pub fn constant_folding(
         }
     }
 
-    // TODO: オーバーフローを考慮
+    // XxxIntはオーバーフローしないことが確認されている(XxxIntOverflow)場合のみ生成されるが、
+    // 到達不能コードでパニックしないようにラップアラウンドで計算する
     let mut rpo_nodes = func.bbs.keys().collect::<Vec<_>>();
     rpo_nodes.sort_by_key(|id| rpo.get(id).unwrap());
 
@@ -211,7 +213,7 @@ pub fn constant_folding(
                         && let Some(&InstrKind::Int(b)) =
                             def_use.get_def_non_move_expr(&func.bbs, local2) =>
                 {
-                    func.bbs[*bb_id].instrs[expr_idx].kind = InstrKind::Int(a + b);
+                    func.bbs[*bb_id].instrs[expr_idx].kind = InstrKind::Int(a.wrapping_add(b));
                 }
                 InstrKind::SubInt(local1, local2)
                     if let Some(&InstrKind::Int(a)) =
@@ -219,7 +221,7 @@ pub fn constant_folding(
                         && let Some(&InstrKind::Int(b)) =
                             def_use.get_def_non_move_expr(&func.bbs, local2) =>
                 {
-                    func.bbs[*bb_id].instrs[expr_idx].kind = InstrKind::Int(a - b);
+                    func.bbs[*bb_id].instrs[expr_idx].kind = InstrKind::Int(a.wrapping_sub(b));
                 }
                 InstrKind::MulInt(local1, local2)
                     if let Some(&InstrKind::Int(a)) =
@@ -227,16 +229,52 @@ pub fn constant_folding(
                         && let Some(&InstrKind::Int(b)) =
                             def_use.get_def_non_move_expr(&func.bbs, local2) =>
                 {
-                    func.bbs[*bb_id].instrs[expr_idx].kind = InstrKind::Int(a * b);
+                    func.bbs[*bb_id].instrs[expr_idx].kind = InstrKind::Int(a.wrapping_mul(b));
+                }
+                InstrKind::AddIntOverflow(local1, local2)
+                    if let Some(&InstrKind::Int(a)) =
+                        def_use.get_def_non_move_expr(&func.bbs, local1)
+                        && let Some(&InstrKind::Int(b)) =
+                            def_use.get_def_non_move_expr(&func.bbs, local2) =>
+                {
+                    func.bbs[*bb_id].instrs[expr_idx].kind =
+                        InstrKind::Bool(a.checked_add(b).is_none());
+                }
+                InstrKind::SubIntOverflow(local1, local2)
+                    if let Some(&InstrKind::Int(a)) =
+                        def_use.get_def_non_move_expr(&func.bbs, local1)
+                        && let Some(&InstrKind::Int(b)) =
+                            def_use.get_def_non_move_expr(&func.bbs, local2) =>
+                {
+                    func.bbs[*bb_id].instrs[expr_idx].kind =
+                        InstrKind::Bool(a.checked_sub(b).is_none());
+                }
+                InstrKind::MulIntOverflow(local1, local2)
+                    if let Some(&InstrKind::Int(a)) =
+                        def_use.get_def_non_move_expr(&func.bbs, local1)
+                        && let Some(&InstrKind::Int(b)) =
+                            def_use.get_def_non_move_expr(&func.bbs, local2) =>
+                {
+                    func.bbs[*bb_id].instrs[expr_idx].kind =
+                        InstrKind::Bool(a.checked_mul(b).is_none());
                 }
                 InstrKind::QuotientInt(local1, local2)
                     if let Some(&InstrKind::Int(a)) =
                         def_use.get_def_non_move_expr(&func.bbs, local1)
                         && let Some(&InstrKind::Int(b)) =
                             def_use.get_def_non_move_expr(&func.bbs, local2)
-                        && b != 0 =>
+                        && let Some(c) = a.checked_div(b) =>
                 {
-                    func.bbs[*bb_id].instrs[expr_idx].kind = InstrKind::Int(a / b);
+                    func.bbs[*bb_id].instrs[expr_idx].kind = InstrKind::Int(c);
+                }
+                InstrKind::QuotientIntOverflow(local1, local2)
+                    if let Some(&InstrKind::Int(a)) =
+                        def_use.get_def_non_move_expr(&func.bbs, local1)
+                        && let Some(&InstrKind::Int(b)) =
+                            def_use.get_def_non_move_expr(&func.bbs, local2) =>
+                {
+                    func.bbs[*bb_id].instrs[expr_idx].kind =
+                        InstrKind::Bool(a.checked_div(b).is_none());
                 }
                 InstrKind::RemainderInt(local1, local2)
                     if let Some(&InstrKind::Int(a)) =
@@ -245,9 +283,57 @@ pub fn constant_folding(
                             def_use.get_def_non_move_expr(&func.bbs, local2)
                         && b != 0 =>
                 {
-                    func.bbs[*bb_id].instrs[expr_idx].kind = InstrKind::Int(a % b);
+                    func.bbs[*bb_id].instrs[expr_idx].kind = InstrKind::Int(a.wrapping_rem(b));
                 }
                 // TODO: ModuloInt
+                InstrKind::IntToFloat(local)
+                    if let Some(&InstrKind::Int(a)) =
+                        def_use.get_def_non_move_expr(&func.bbs, local) =>
+                {
+                    func.bbs[*bb_id].instrs[expr_idx].kind =
+                        InstrKind::Float(NotNan::new(a as f64).unwrap());
+                }
+                InstrKind::FloorFloat(local)
+                    if let Some(&InstrKind::Float(a)) =
+                        def_use.get_def_non_move_expr(&func.bbs, local) =>
+                {
+                    func.bbs[*bb_id].instrs[expr_idx].kind =
+                        InstrKind::Float(NotNan::new(a.floor()).unwrap());
+                }
+                InstrKind::RoundFloat(local)
+                    if let Some(&InstrKind::Float(a)) =
+                        def_use.get_def_non_move_expr(&func.bbs, local) =>
+                {
+                    func.bbs[*bb_id].instrs[expr_idx].kind =
+                        InstrKind::Float(NotNan::new(a.round_ties_even()).unwrap());
+                }
+                InstrKind::CeilingFloat(local)
+                    if let Some(&InstrKind::Float(a)) =
+                        def_use.get_def_non_move_expr(&func.bbs, local) =>
+                {
+                    func.bbs[*bb_id].instrs[expr_idx].kind =
+                        InstrKind::Float(NotNan::new(a.ceil()).unwrap());
+                }
+                InstrKind::TruncateFloat(local)
+                    if let Some(&InstrKind::Float(a)) =
+                        def_use.get_def_non_move_expr(&func.bbs, local) =>
+                {
+                    func.bbs[*bb_id].instrs[expr_idx].kind =
+                        InstrKind::Float(NotNan::new(a.trunc()).unwrap());
+                }
+                // rationalは不変なので構築時の分子と分母をそのまま使える
+                InstrKind::Numerator(local)
+                    if let Some(&InstrKind::Rational(num, _)) =
+                        def_use.get_def_non_move_expr(&func.bbs, local) =>
+                {
+                    func.bbs[*bb_id].instrs[expr_idx].kind = InstrKind::Move(num);
+                }
+                InstrKind::Denominator(local)
+                    if let Some(&InstrKind::Rational(_, den)) =
+                        def_use.get_def_non_move_expr(&func.bbs, local) =>
+                {
+                    func.bbs[*bb_id].instrs[expr_idx].kind = InstrKind::Move(den);
+                }
                 InstrKind::EqInt(local1, local2)
                     if let Some(&InstrKind::Int(a)) =
                         def_use.get_def_non_move_expr(&func.bbs, local1)
@@ -416,6 +502,20 @@ pub fn constant_folding(
                         }
                     }
                 }
+                // (if (pair? x) ...)のような分岐はIs命令の結果で直接分岐させ、JITが分岐先で型を確定できるようにする
+                InstrKind::Terminator(TerminatorInstr::If(cond, then_bb, else_bb))
+                    if let Some(&InstrKind::EqObj(local1, local2)) =
+                        def_use.get_def_non_move_expr(&func.bbs, cond)
+                        && let Some(&InstrKind::ToObj(ValType::Bool, src1)) =
+                            def_use.get_def_non_move_expr(&func.bbs, local1)
+                        && let Some(&InstrKind::ToObj(ValType::Bool, src2)) =
+                            def_use.get_def_non_move_expr(&func.bbs, local2)
+                        && let Some(&InstrKind::Bool(false)) =
+                            def_use.get_def_non_move_expr(&func.bbs, src2) =>
+                {
+                    func.bbs[*bb_id].instrs[expr_idx].kind =
+                        InstrKind::Terminator(TerminatorInstr::If(src1, else_bb, then_bb));
+                }
                 InstrKind::Terminator(TerminatorInstr::If(cond, then_bb, else_bb))
                     if let Some(&InstrKind::Bool(value)) =
                         def_use.get_def_non_move_expr(&func.bbs, cond) =>
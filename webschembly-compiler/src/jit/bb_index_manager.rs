@@ -9,14 +9,61 @@ use super::index_flag::IndexFlag;
 
 pub const BB_LAYOUT_MAX_SIZE: usize = 32;
 pub const BB_LAYOUT_DEFAULT_INDEX: BBIndex = BBIndex(0);
+// 実行中の特殊化の追い出しがこの回数を超えたBBはメガモーフィックとみなし、以降は汎用レイアウトのみを使う
+pub const BB_LAYOUT_MAX_EVICTIONS: usize = 32;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct BBIndex(pub usize);
 
+// 特殊化ごとの実行時のカウンタ
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BBLayoutCounts {
+    // 特殊化されたBBに入った回数
+    pub hits: usize,
+    // この特殊化に入るための型検査(Is命令による分岐)が失敗した回数
+    pub guard_failures: usize,
+}
+
+impl BBLayoutCounts {
+    // 型検査が成功するより失敗するほうが多い特殊化は、実行回数が多くても役に立っていない
+    fn benefit(&self) -> usize {
+        self.hits.saturating_sub(self.guard_failures)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BBIndexDecision {
+    // 使用頻度が低いか型検査に失敗してばかりの特殊化を追い出した
+    Evicted {
+        index: BBIndex,
+        counts: BBLayoutCounts,
+    },
+    // 特殊化をやめて汎用レイアウトに切り替えた
+    Megamorphic {
+        evictions: usize,
+    },
+}
+
 #[derive(Debug)]
 pub struct BBIndexManager {
+    // 新しい呼び出し元に割り当てられる特殊化
     type_params_to_index: FxBiHashMap<VecMapEq<TypeParamId, ValType>, BBIndex>,
+    // 追い出された特殊化
+    // 生成済みのコードからは引き続き呼び出されるので型情報とglobalは残しておく
+    evicted: FxBiHashMap<VecMapEq<TypeParamId, ValType>, BBIndex>,
     index_to_global: FxHashMap<BBIndex, Global>,
+    // 特殊化ごとの実行回数や型検査の失敗回数を数えるglobal
+    index_to_counter: FxHashMap<(BBIndex, BBLayoutCounterKind), Global>,
+    // 特殊化ごとのカウンタ。実行時のカウンタから通知され、追い出しのたびに半減させて古いプロファイルを忘れる
+    counts: FxHashMap<BBIndex, BBLayoutCounts>,
+    // 前回の実行のプロファイルでのカウンタ。特殊化を生成したときの初期値になる
+    profiled_counts: FxHashMap<VecMapEq<TypeParamId, ValType>, BBLayoutCounts>,
+    // プロファイルにない特殊化は生成せず汎用レイアウトを使う
+    profile_only: bool,
+    // 役に立っていた特殊化を追い出した回数
+    evictions: usize,
+    megamorphic: bool,
+    next_index: usize,
 }
 
 impl BBIndexManager {
@@ -27,7 +74,15 @@ impl BBIndexManager {
         index_to_global.insert(BB_LAYOUT_DEFAULT_INDEX, global);
         Self {
             type_params_to_index,
+            evicted: FxBiHashMap::default(),
             index_to_global,
+            index_to_counter: FxHashMap::default(),
+            counts: FxHashMap::default(),
+            profiled_counts: FxHashMap::default(),
+            profile_only: false,
+            evictions: 0,
+            megamorphic: false,
+            next_index: BB_LAYOUT_DEFAULT_INDEX.0 + 1,
         }
     }
 
@@ -35,32 +90,280 @@ impl BBIndexManager {
         &mut self,
         type_params: &VecMap<TypeParamId, ValType>,
         global_manager: &mut GlobalManager,
+        decisions: &mut Vec<BBIndexDecision>,
     ) -> Option<(Global, BBIndex, IndexFlag)> {
         if let Some(&index) = self
             .type_params_to_index
             .get_by_left(VecMapEq::from_ref(type_params))
         {
             let global = *self.index_to_global.get(&index).unwrap();
-            Some((global.to_import(), index, IndexFlag::ExistingInstance))
-        } else if self.type_params_to_index.len() < BB_LAYOUT_MAX_SIZE {
-            let index = BBIndex(self.type_params_to_index.len());
-            self.type_params_to_index
-                .insert(VecMapEq::from(type_params.clone()), index);
-            let global = global_manager.gen_global(LocalType::FuncRef);
-            self.index_to_global.insert(index, global);
-            Some((global, index, IndexFlag::NewInstance))
-        } else {
-            None
+            return Some((global.to_import(), index, IndexFlag::ExistingInstance));
         }
+
+        if self.profile_only
+            && !self
+                .profiled_counts
+                .contains_key(VecMapEq::from_ref(type_params))
+        {
+            return None;
+        }
+
+        if self.megamorphic {
+            return None;
+        }
+
+        if self.type_params_to_index.len() >= BB_LAYOUT_MAX_SIZE {
+            if self.evictions >= BB_LAYOUT_MAX_EVICTIONS {
+                self.megamorphic = true;
+                decisions.push(BBIndexDecision::Megamorphic {
+                    evictions: self.evictions,
+                });
+                return None;
+            }
+            let (index, counts) = self.evict_least_beneficial();
+            decisions.push(BBIndexDecision::Evicted { index, counts });
+        }
+
+        if let Some((revived, index)) = self.evicted.remove_by_left(VecMapEq::from_ref(type_params))
+        {
+            // 一度追い出した特殊化はコードが残っているのでそのまま復帰させる
+            self.type_params_to_index.insert(revived, index);
+            self.counts.insert(index, self.initial_counts(type_params));
+            let global = *self.index_to_global.get(&index).unwrap();
+            return Some((global.to_import(), index, IndexFlag::ExistingInstance));
+        }
+
+        let index = BBIndex(self.next_index);
+        self.next_index += 1;
+        self.type_params_to_index
+            .insert(VecMapEq::from(type_params.clone()), index);
+        self.counts.insert(index, self.initial_counts(type_params));
+        let global = global_manager.gen_global(LocalType::FuncRef);
+        self.index_to_global.insert(index, global);
+        Some((global, index, IndexFlag::NewInstance))
+    }
+
+    fn initial_counts(&self, type_params: &VecMap<TypeParamId, ValType>) -> BBLayoutCounts {
+        self.profiled_counts
+            .get(VecMapEq::from_ref(type_params))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    pub fn counter_global(
+        &mut self,
+        index: BBIndex,
+        kind: BBLayoutCounterKind,
+        global_manager: &mut GlobalManager,
+    ) -> Global {
+        *self
+            .index_to_counter
+            .entry((index, kind))
+            .or_insert_with(|| global_manager.gen_global(Type::Val(ValType::Int).into()))
+    }
+
+    pub fn record(&mut self, index: BBIndex, kind: BBLayoutCounterKind, count: usize) {
+        let counts = self.counts.entry(index).or_default();
+        match kind {
+            BBLayoutCounterKind::Hit => counts.hits += count,
+            BBLayoutCounterKind::GuardFailure => counts.guard_failures += count,
+        }
+    }
+
+    pub fn load_profile(
+        &mut self,
+        layouts: impl IntoIterator<Item = (VecMap<TypeParamId, ValType>, BBLayoutCounts)>,
+        megamorphic: bool,
+    ) {
+        self.profiled_counts = layouts
+            .into_iter()
+            .map(|(type_params, counts)| (VecMapEq::from(type_params), counts))
+            .collect();
+        self.megamorphic = megamorphic;
+    }
+
+    // 前回の実行で役に立った特殊化から順に割り当て、新しく割り当てたindexを返す
+    pub fn allocate_profiled(&mut self, global_manager: &mut GlobalManager) -> Vec<BBIndex> {
+        if self.megamorphic {
+            return Vec::new();
+        }
+        let mut layouts = self
+            .profiled_counts
+            .iter()
+            .map(|(type_params, counts)| (type_params.as_inner().clone(), counts.benefit()))
+            .collect::<Vec<_>>();
+        layouts.sort_by_key(|&(_, benefit)| std::cmp::Reverse(benefit));
+        layouts
+            .into_iter()
+            .take(BB_LAYOUT_MAX_SIZE - 1)
+            .filter_map(|(type_params, _)| {
+                match self.idx(&type_params, global_manager, &mut Vec::new()) {
+                    Some((_, index, IndexFlag::NewInstance)) => Some(index),
+                    _ => None,
+                }
+            })
+            .collect()
+    }
+
+    // AOTコンパイルで使う。実行時に特殊化を追加できないので、プロファイルで使われたものだけを生成する
+    pub fn restrict_to_profile(&mut self) {
+        self.profile_only = true;
+    }
+
+    // 生成した特殊化(追い出したものを含む)とそのカウンタ
+    pub fn layouts(&self) -> impl Iterator<Item = (&VecMap<TypeParamId, ValType>, BBLayoutCounts)> {
+        self.type_params_to_index
+            .iter()
+            .chain(self.evicted.iter())
+            .filter(|&(_, &index)| index != BB_LAYOUT_DEFAULT_INDEX)
+            .map(|(type_params, index)| {
+                (
+                    type_params.as_inner(),
+                    self.counts.get(index).copied().unwrap_or_default(),
+                )
+            })
+    }
+
+    pub fn is_megamorphic(&self) -> bool {
+        self.megamorphic
+    }
+
+    fn evict_least_beneficial(&mut self) -> (BBIndex, BBLayoutCounts) {
+        let (index, counts) = self
+            .type_params_to_index
+            .right_values()
+            .filter(|&&index| index != BB_LAYOUT_DEFAULT_INDEX)
+            .map(|&index| (index, self.counts.get(&index).copied().unwrap_or_default()))
+            .min_by_key(|&(index, counts)| (counts.benefit(), index.0))
+            .unwrap();
+        let (type_params, _) = self.type_params_to_index.remove_by_right(&index).unwrap();
+        self.evicted.insert(type_params, index);
+        // 役に立っていない特殊化を追い出しても、特殊化が多すぎることにはならない
+        if counts.benefit() > 0 {
+            self.evictions += 1;
+        }
+        for counts in self.counts.values_mut() {
+            counts.hits /= 2;
+            counts.guard_failures /= 2;
+        }
+        (index, counts)
     }
 
     pub fn type_args(&self, index: BBIndex) -> (&VecMap<TypeParamId, ValType>, Global) {
         (
             self.type_params_to_index
                 .get_by_right(&index)
+                .or_else(|| self.evicted.get_by_right(&index))
                 .unwrap()
                 .as_inner(),
             self.index_to_global.get(&index).unwrap().to_import(),
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layout(i: usize) -> VecMap<TypeParamId, ValType> {
+        let mut type_params = VecMap::default();
+        for bit in 0..8 {
+            let typ = if i & (1 << bit) != 0 {
+                ValType::Int
+            } else {
+                ValType::Float
+            };
+            type_params.insert(TypeParamId::from(bit), typ);
+        }
+        type_params
+    }
+
+    #[test]
+    fn test_evict_by_runtime_hits() {
+        let mut global_manager = GlobalManager::new();
+        let mut manager = BBIndexManager::new(global_manager.gen_global(LocalType::FuncRef));
+        let mut decisions = Vec::new();
+        let indices = (0..BB_LAYOUT_MAX_SIZE - 1)
+            .map(|i| {
+                manager
+                    .idx(&layout(i), &mut global_manager, &mut decisions)
+                    .unwrap()
+                    .1
+            })
+            .collect::<Vec<_>>();
+        // コンパイル時に何度要求されても、実行されていなければ追い出される
+        for _ in 0..10 {
+            manager.idx(&layout(0), &mut global_manager, &mut decisions);
+        }
+        for &index in &indices[1..] {
+            manager.record(index, BBLayoutCounterKind::Hit, 64);
+        }
+        manager.idx(&layout(100), &mut global_manager, &mut decisions);
+        assert_eq!(
+            decisions,
+            vec![BBIndexDecision::Evicted {
+                index: indices[0],
+                counts: BBLayoutCounts::default(),
+            }]
+        );
+        assert!(!manager.is_megamorphic());
+    }
+
+    #[test]
+    fn test_evict_by_guard_failures() {
+        let mut global_manager = GlobalManager::new();
+        let mut manager = BBIndexManager::new(global_manager.gen_global(LocalType::FuncRef));
+        let mut decisions = Vec::new();
+        let indices = (0..BB_LAYOUT_MAX_SIZE - 1)
+            .map(|i| {
+                manager
+                    .idx(&layout(i), &mut global_manager, &mut decisions)
+                    .unwrap()
+                    .1
+            })
+            .collect::<Vec<_>>();
+        for &index in &indices {
+            manager.record(index, BBLayoutCounterKind::Hit, 64);
+        }
+        // 最も実行回数が多くても、型検査の失敗がそれを上回れば最初に追い出される
+        manager.record(indices[5], BBLayoutCounterKind::Hit, 1024);
+        manager.record(indices[5], BBLayoutCounterKind::GuardFailure, 4096);
+        manager.idx(&layout(100), &mut global_manager, &mut decisions);
+        assert_eq!(
+            decisions,
+            vec![BBIndexDecision::Evicted {
+                index: indices[5],
+                counts: BBLayoutCounts {
+                    hits: 1088,
+                    guard_failures: 4096,
+                },
+            }]
+        );
+    }
+
+    #[test]
+    fn test_megamorphic_when_evicting_hot_layouts() {
+        let mut global_manager = GlobalManager::new();
+        let mut manager = BBIndexManager::new(global_manager.gen_global(LocalType::FuncRef));
+        let mut decisions = Vec::new();
+        let mut live = Vec::new();
+        // 全ての特殊化が実行され続けている
+        for i in 0..256 {
+            let Some((_, index, _)) = manager.idx(&layout(i), &mut global_manager, &mut decisions)
+            else {
+                break;
+            };
+            live.push(index);
+            for &index in &live {
+                manager.record(index, BBLayoutCounterKind::Hit, 64);
+            }
+        }
+        assert!(manager.is_megamorphic());
+        assert_eq!(
+            decisions.last(),
+            Some(&BBIndexDecision::Megamorphic {
+                evictions: BB_LAYOUT_MAX_EVICTIONS
+            })
+        );
+    }
+}
@@ -1,4 +1,4 @@
-use rustc_hash::FxHashSet;
+use rustc_hash::{FxHashMap, FxHashSet};
 use webschembly_compiler_ir::*;
 
 use crate::fxbihashmap::FxBiHashMap;
@@ -21,6 +21,11 @@ pub enum ClosureArgs {
 pub struct ClosureGlobalLayout {
     args_to_index: FxBiHashMap<ClosureArgs, ClosureIndex>,
     instantiated_idx: FxHashSet<ClosureIndex>,
+    // 満杯のため汎用レイアウトにフォールバックした回数
+    // indexは生成済みの呼び出し元に埋め込まれているので追い出しは行えない
+    fallbacks: FxHashMap<ClosureArgs, usize>,
+    // reserveしたレイアウト以外は割り当てない
+    reserved_only: bool,
 }
 
 impl Default for ClosureGlobalLayout {
@@ -36,6 +41,8 @@ impl ClosureGlobalLayout {
         Self {
             args_to_index,
             instantiated_idx: FxHashSet::default(),
+            fallbacks: FxHashMap::default(),
+            reserved_only: false,
         }
     }
 
@@ -43,7 +50,7 @@ impl ClosureGlobalLayout {
         // TODO: argsの長さに上限を設定
         let index = if let Some(&index) = self.args_to_index.get_by_left(args) {
             index
-        } else if self.args_to_index.len() < CLOSURE_LAYOUT_MAX_SIZE {
+        } else if self.args_to_index.len() < CLOSURE_LAYOUT_MAX_SIZE && !self.reserved_only {
             let index = ClosureIndex(self.args_to_index.len());
             self.args_to_index.insert(args.clone(), index);
             index
@@ -58,6 +65,42 @@ impl ClosureGlobalLayout {
         Some((index, flag))
     }
 
+    // indexを割り当てるだけでインスタンス化はしない
+    pub fn reserve(&mut self, args: &ClosureArgs) {
+        if self.args_to_index.get_by_left(args).is_none()
+            && self.args_to_index.len() < CLOSURE_LAYOUT_MAX_SIZE
+        {
+            let index = ClosureIndex(self.args_to_index.len());
+            self.args_to_index.insert(args.clone(), index);
+        }
+    }
+
+    // AOTコンパイルで使う。プロファイルから読み込んだレイアウトのみを使う
+    pub fn restrict_to_reserved(&mut self) {
+        self.reserved_only = true;
+    }
+
+    pub fn indices(&self) -> impl Iterator<Item = ClosureIndex> {
+        (0..self.args_to_index.len()).map(ClosureIndex)
+    }
+
+    // index順に並べた引数の型。index=0の可変長引数は含まない
+    pub fn specified_layouts(&self) -> Vec<Vec<Type>> {
+        (1..self.args_to_index.len())
+            .filter_map(|index| match self.arg_types(ClosureIndex(index)) {
+                ClosureArgs::Specified(args) => Some(args.clone()),
+                ClosureArgs::Variadic => None,
+            })
+            .collect()
+    }
+
+    // 初めてのフォールバックならtrueを返す
+    pub fn fallback(&mut self, args: &ClosureArgs) -> bool {
+        let count = self.fallbacks.entry(args.clone()).or_default();
+        *count += 1;
+        *count == 1
+    }
+
     pub fn arg_types(&self, index: ClosureIndex) -> &ClosureArgs {
         self.args_to_index.get_by_right(&index).unwrap()
     }
@@ -17,6 +17,8 @@ pub struct EnvIndex(pub usize);
 pub struct EnvIndexManager {
     env_types_to_index: FxBiHashMap<VecMapEq<usize, ValType>, EnvIndex>,
     index_to_table_global: FxHashMap<EnvIndex, Global>,
+    // 満杯のため汎用レイアウトにフォールバックした回数
+    fallbacks: FxHashMap<VecMapEq<usize, ValType>, usize>,
 }
 
 impl Default for EnvIndexManager {
@@ -33,6 +35,7 @@ impl EnvIndexManager {
         Self {
             env_types_to_index,
             index_to_table_global: FxHashMap::default(),
+            fallbacks: FxHashMap::default(),
         }
     }
 
@@ -60,6 +63,20 @@ impl EnvIndexManager {
         }
     }
 
+    // 初めてのフォールバックならtrueを返す
+    pub fn fallback(&mut self, env_types: &VecMap<usize, ValType>) -> bool {
+        let count = self
+            .fallbacks
+            .entry(VecMapEq::from(env_types.clone()))
+            .or_default();
+        *count += 1;
+        *count == 1
+    }
+
+    pub fn indices(&self) -> impl Iterator<Item = EnvIndex> + '_ {
+        self.env_types_to_index.right_values().copied()
+    }
+
     pub fn env_types(&self, index: EnvIndex) -> (&VecMap<usize, ValType>, Option<Global>) {
         debug_assert!(
             index == ENV_LAYOUT_DEFAULT_INDEX || self.index_to_table_global.contains_key(&index)
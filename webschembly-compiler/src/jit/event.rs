@@ -14,4 +14,46 @@ pub enum JitLogEvent {
         successors: Vec<(usize, usize)>,
         display: String,
     },
+    // 使用頻度が低いか型検査に失敗してばかりのBBの特殊化を追い出した
+    #[serde(rename = "bb_evict")]
+    BasicBlockEvict {
+        module_id: usize,
+        func_id: usize,
+        env_index: usize,
+        func_index: usize,
+        bb_id: usize,
+        index: usize,
+        hits: usize,
+        guard_failures: usize,
+    },
+    // BBの特殊化を諦めて汎用レイアウトに切り替えた
+    #[serde(rename = "bb_megamorphic")]
+    BasicBlockMegamorphic {
+        module_id: usize,
+        func_id: usize,
+        env_index: usize,
+        func_index: usize,
+        bb_id: usize,
+        evictions: usize,
+    },
+    // ループの後方エッジから、bb_idをindexの型で特殊化したループの本体に移るOSRの出口を生成した
+    #[serde(rename = "osr")]
+    Osr {
+        module_id: usize,
+        func_id: usize,
+        env_index: usize,
+        func_index: usize,
+        bb_id: usize,
+        index: usize,
+    },
+    // クロージャの引数レイアウトが満杯のため可変長引数で呼び出す
+    #[serde(rename = "closure_megamorphic")]
+    ClosureMegamorphic { arg_types: String },
+    // 環境のレイアウトが満杯のため特殊化せずにクロージャを作成する
+    #[serde(rename = "env_megamorphic")]
+    EnvMegamorphic {
+        module_id: usize,
+        func_id: usize,
+        env_types: String,
+    },
 }
@@ -3,6 +3,10 @@ pub struct JitConfig {
     pub enable_optimization: bool,
     pub block_fusion: BlockFusionConfig,
     pub enable_log: bool,
+    // 関数がこの回数呼び出されるまでは特殊化せずに実行する。0なら最初の呼び出しでJITコンパイルする
+    pub tier_up_threshold: usize,
+    // プロファイルを元に全ての特殊化を事前に生成する。生成したコードは実行時にJITを呼び出さない
+    pub aot: bool,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -24,6 +28,8 @@ impl JitConfig {
             enable_optimization: true,
             block_fusion: BlockFusionConfig::LargeFusion,
             enable_log: false,
+            tier_up_threshold: 100,
+            aot: false,
         }
     }
 }
@@ -1,7 +1,9 @@
-use rustc_hash::FxHashMap;
+use rustc_hash::{FxHashMap, FxHashSet};
 
-use super::closure_global_layout::{ClosureGlobalLayout, ClosureIndex};
+use super::closure_global_layout::{ClosureArgs, ClosureGlobalLayout, ClosureIndex};
+use super::env_index_manager::ENV_LAYOUT_DEFAULT_INDEX;
 use super::jit_config::JitConfig;
+use super::profile::{BBProfile, BBProfileKey, FuncProfile, JitProfile};
 use webschembly_compiler_ir::*;
 
 #[derive(Debug)]
@@ -15,19 +17,62 @@ pub struct JitCtx {
     stub_globals: FxHashMap<ClosureIndex, Global>,
     // instantiate_funcの結果を保存するグローバル
     instantiate_func_global: Option<Global>,
+    // 前回の実行で記録されたプロファイル
+    profile_funcs: FxHashSet<FuncProfile>,
+    profile_bbs: FxHashMap<BBProfileKey, BBProfile>,
+    profile_module_hashes: Vec<u64>,
+    // 登録済みのモジュールのソースのハッシュ
+    module_hashes: Vec<u64>,
 }
 
 impl JitCtx {
-    pub fn new(config: JitConfig) -> Self {
+    pub fn new(config: JitConfig, profile: JitProfile) -> Self {
+        let profile = if profile.is_compatible() {
+            profile
+        } else {
+            JitProfile::default()
+        };
+        let mut closure_global_layout = ClosureGlobalLayout::new();
+        // 同じindexが割り当てられるように前回と同じ順番で登録しておく
+        for args in profile.closure_layouts {
+            closure_global_layout.reserve(&ClosureArgs::Specified(args));
+        }
+        if config.aot {
+            closure_global_layout.restrict_to_reserved();
+        }
         Self {
             config,
-            closure_global_layout: ClosureGlobalLayout::new(),
+            closure_global_layout,
             is_instantiated: false,
             stub_globals: FxHashMap::default(),
             instantiate_func_global: None,
+            profile_funcs: profile.funcs.into_iter().collect(),
+            profile_bbs: profile
+                .bbs
+                .into_iter()
+                .map(|bb_profile| (bb_profile.key, bb_profile))
+                .collect(),
+            profile_module_hashes: profile.module_hashes,
+            module_hashes: Vec::new(),
         }
     }
 
+    // ソースが前回と異なるモジュールがあれば、それ以降のモジュールのプロファイルを捨てる
+    // 後続のモジュールのIDもずれている可能性があるため
+    pub fn register_module_hash(&mut self, source_hash: u64) {
+        let module_id = self.module_hashes.len();
+        self.module_hashes.push(source_hash);
+        if self.profile_module_hashes.get(module_id) != Some(&source_hash) {
+            self.profile_module_hashes.truncate(module_id);
+            self.profile_funcs.retain(|func| func.module_id < module_id);
+            self.profile_bbs.retain(|key, _| key.module_id < module_id);
+        }
+    }
+
+    pub fn module_hashes(&self) -> &[u64] {
+        &self.module_hashes
+    }
+
     pub fn config(&self) -> JitConfig {
         self.config
     }
@@ -63,4 +108,42 @@ impl JitCtx {
     pub fn closure_global_layout(&mut self) -> &mut ClosureGlobalLayout {
         &mut self.closure_global_layout
     }
+
+    pub fn is_profiled_hot_func(&self, module_id: JitModuleId, func_id: FuncId) -> bool {
+        self.profile_funcs.contains(&FuncProfile {
+            module_id: usize::from(module_id),
+            func_id: usize::from(func_id),
+        })
+    }
+
+    // 前回の実行でJITコンパイルされた関数とクロージャのレイアウトの組
+    // 環境のindexは特殊化を見つけた順に割り当てられ実行ごとに変わるので、デフォルトの環境のもののみ返す
+    pub fn profiled_func_layouts(&self, module_id: JitModuleId) -> Vec<(FuncId, ClosureIndex)> {
+        let module_id = usize::from(module_id);
+        let mut layouts = self
+            .profile_funcs
+            .iter()
+            .filter(|func| func.module_id == module_id)
+            .map(|func| (func.func_id, 0))
+            .chain(
+                self.profile_bbs
+                    .keys()
+                    .filter(|key| {
+                        key.module_id == module_id && key.env_index == ENV_LAYOUT_DEFAULT_INDEX.0
+                    })
+                    .map(|key| (key.func_id, key.func_index)),
+            )
+            .collect::<FxHashSet<_>>()
+            .into_iter()
+            .collect::<Vec<_>>();
+        layouts.sort();
+        layouts
+            .into_iter()
+            .map(|(func_id, func_index)| (FuncId::from(func_id), ClosureIndex(func_index)))
+            .collect()
+    }
+
+    pub fn bb_profile(&self, key: &BBProfileKey) -> Option<&BBProfile> {
+        self.profile_bbs.get(key)
+    }
 }
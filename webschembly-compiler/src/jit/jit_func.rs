@@ -1,6 +1,8 @@
 use rustc_hash::{FxHashMap, FxHashSet};
 
-use super::bb_index_manager::{BB_LAYOUT_DEFAULT_INDEX, BBIndex, BBIndexManager};
+use super::bb_index_manager::{
+    BB_LAYOUT_DEFAULT_INDEX, BBIndex, BBIndexDecision, BBIndexManager, BBLayoutCounts,
+};
 use super::closure_global_layout::{
     CLOSURE_LAYOUT_DEFAULT_INDEX, CLOSURE_LAYOUT_MAX_SIZE, ClosureArgs, ClosureGlobalLayout,
     ClosureIndex,
@@ -10,7 +12,9 @@ use super::index_flag::IndexFlag;
 use super::jit_ctx::JitCtx;
 use crate::fxbihashmap::FxBiHashMap;
 use crate::ir_generator::GlobalManager;
-use crate::ir_processor::cfg_analyzer::calculate_rpo;
+use crate::ir_processor::cfg_analyzer::{
+    calc_doms, calc_predecessors, calculate_rpo, find_back_edges,
+};
 use crate::ir_processor::dataflow::{analyze_liveness, calc_def_use};
 use crate::ir_processor::optimizer::remove_unreachable_bb;
 use crate::ir_processor::ssa::{DefUseChain, build_ssa};
@@ -25,6 +29,7 @@ pub struct JitFunc {
 }
 
 use super::event::JitLogEvent;
+use super::profile::{BBLayoutProfile, BBProfile, BBProfileKey};
 
 impl JitFunc {
     pub fn new(
@@ -91,6 +96,10 @@ pub struct JitSpecializedArgFunc {
     func_index: ClosureIndex,
     func: Func,
     jit_bbs: VecMap<BasicBlockId, JitBB>,
+    // AOTコンパイルでまだ生成していないBBの特殊化
+    aot_pending_bbs: Vec<(BasicBlockId, BBIndex)>,
+    // OSRを行う後方エッジ。(ジャンプ元, ループヘッダ) -> ループの本体の先頭のBB
+    osr_entries: FxHashMap<(BasicBlockId, BasicBlockId), BasicBlockId>,
 }
 
 impl JitSpecializedArgFunc {
@@ -104,6 +113,7 @@ impl JitSpecializedArgFunc {
     ) -> Self {
         let mut func = func.clone();
         closure_func_assign_types(&mut func, func_index, jit_ctx.closure_global_layout());
+        loopify_self_tail_calls(&mut func, func_index, jit_ctx.closure_global_layout());
         // 共通部分式除去を行うと変数の生存期間が伸びてしまい、JITでのパフォーマンスが落ちるのでここでは行わない
         ssa_optimize(
             &mut func,
@@ -112,6 +122,7 @@ impl JitSpecializedArgFunc {
                 ..Default::default()
             },
         );
+        let osr_entries = find_osr_entries(&func);
         let bb_to_globals = func
             .bbs
             .keys()
@@ -122,11 +133,29 @@ impl JitSpecializedArgFunc {
         let jit_bbs = func
             .bbs
             .values()
-            .map(|bb| JitBB {
-                bb_id: bb.id,
-                info: bb_infos[bb.id].clone(),
-                bb_index_manager: BBIndexManager::new(bb_to_globals[bb.id]),
-                branch_counter: BranchCounter::default(),
+            .map(|bb| {
+                let mut jit_bb = JitBB {
+                    bb_id: bb.id,
+                    info: bb_infos[bb.id].clone(),
+                    bb_index_manager: BBIndexManager::new(bb_to_globals[bb.id]),
+                    branch_counter: BranchCounter::default(),
+                    fused: false,
+                    backedge_counter: None,
+                };
+                let key = BBProfileKey {
+                    module_id: usize::from(module_id),
+                    func_id: usize::from(func.id),
+                    env_index: env_index.0,
+                    func_index: func_index.0,
+                    bb_id: usize::from(bb.id),
+                };
+                if let Some(bb_profile) = jit_ctx.bb_profile(&key) {
+                    jit_bb.load_profile(bb_profile);
+                }
+                if jit_ctx.config().aot {
+                    jit_bb.bb_index_manager.restrict_to_profile();
+                }
+                jit_bb
             })
             .collect::<VecMap<BasicBlockId, _>>();
 
@@ -136,9 +165,15 @@ impl JitSpecializedArgFunc {
             func_index,
             func,
             jit_bbs,
+            aot_pending_bbs: Vec::new(),
+            osr_entries,
         }
     }
 
+    pub fn pop_aot_pending_bb(&mut self) -> Option<(BasicBlockId, BBIndex)> {
+        self.aot_pending_bbs.pop()
+    }
+
     pub fn generate_func_module(
         &mut self,
         func_to_globals: &VecMap<FuncId, GlobalId>,
@@ -147,8 +182,26 @@ impl JitSpecializedArgFunc {
         env_index_managers: &mut FxHashMap<FuncId, EnvIndexManager>,
         jit_ctx: &mut JitCtx,
     ) -> (Module, Vec<JitLogEvent>) {
+        // 前回の実行で使われたBBの特殊化は、遅延コンパイルを待たずに関数と一緒に生成する
+        // 先に全てのindexを割り当てておくと、生成するコードはスタブを経由せずに互いを呼び出す
+        let profiled_bbs = if jit_ctx.config().aot {
+            Vec::new()
+        } else {
+            self.jit_bbs
+                .values_mut()
+                .flat_map(|jit_bb| {
+                    let bb_id = jit_bb.bb_id;
+                    jit_bb
+                        .bb_index_manager
+                        .allocate_profiled(global_manager)
+                        .into_iter()
+                        .map(move |index| (bb_id, index))
+                })
+                .collect::<Vec<_>>()
+        };
+
         // entry_bbのモジュールをベースに拡張する
-        let (mut module, jit_events) = self.generate_bb_module(
+        let (mut module, mut jit_events) = self.generate_bb_module(
             func_to_globals,
             func_types,
             self.func.bb_entry,
@@ -275,8 +328,50 @@ impl JitSpecializedArgFunc {
             })
         });
 
-        for bb_id in self.jit_bbs.keys() {
-            self.add_bb_stub_func(self.module_id, bb_id, BB_LAYOUT_DEFAULT_INDEX, &mut module);
+        if jit_ctx.config().aot {
+            // 実行時にインスタンス化できないので、全てのBBをJitModuleが続けて生成する
+            for bb_id in self.jit_bbs.keys() {
+                if bb_id != self.func.bb_entry {
+                    self.aot_pending_bbs.push((bb_id, BB_LAYOUT_DEFAULT_INDEX));
+                }
+            }
+        } else {
+            for bb_id in self.jit_bbs.keys() {
+                self.add_bb_stub_func(self.module_id, bb_id, BB_LAYOUT_DEFAULT_INDEX, &mut module);
+            }
+        }
+
+        for (bb_id, index) in profiled_bbs {
+            let (bb_module, bb_jit_events) = self.generate_bb_module(
+                func_to_globals,
+                func_types,
+                bb_id,
+                index,
+                global_manager,
+                env_index_managers,
+                jit_ctx,
+                false,
+            );
+            jit_events.extend(bb_jit_events);
+            let bb_entry = module.merge(bb_module);
+            module.extend_entry_func(|entry_func, next| {
+                entry_func.bbs.push_with(|id| BasicBlock {
+                    id,
+                    instrs: vec![
+                        Instr {
+                            local: None,
+                            kind: InstrKind::Call(InstrCall {
+                                func_id: bb_entry,
+                                args: vec![],
+                            }),
+                        },
+                        Instr {
+                            local: None,
+                            kind: InstrKind::Terminator(next),
+                        },
+                    ],
+                })
+            });
         }
 
         (module, jit_events)
@@ -393,6 +488,11 @@ impl JitSpecializedArgFunc {
         jit_ctx: &mut JitCtx,
         branch_specialization: bool,
     ) -> (Module, Vec<JitLogEvent>) {
+        // 前回の実行で分岐の特殊化を行ったBBは最初から特殊化する
+        let branch_specialization = branch_specialization
+            || (self.jit_bbs[orig_entry_bb_id].fused
+                && jit_ctx.config().block_fusion != BlockFusionConfig::Disabled);
+
         let mut required_closure_idx = Vec::new();
 
         {
@@ -409,6 +509,11 @@ impl JitSpecializedArgFunc {
 
         let mut required_stubs = Vec::new();
 
+        // 特殊化のレイアウトに関する判断。ログに出力する
+        let mut bb_index_decisions = Vec::new();
+        let mut closure_fallbacks = Vec::new();
+        let mut env_fallbacks: Vec<(JitModuleId, JitFuncId, VecMap<usize, ValType>)> = Vec::new();
+
         let (type_args, index_global) = self.jit_bbs[orig_entry_bb_id]
             .bb_index_manager
             .type_args(index);
@@ -430,6 +535,29 @@ impl JitSpecializedArgFunc {
 
         let new_ids = build_ssa(body_func);
 
+        // OSRの出口で渡す値。後方エッジのジャンプ元での、ループの本体の先頭のBBの生存変数の値
+        let osr_ids = self
+            .osr_entries
+            .iter()
+            .filter(|&(&(latch_bb_id, header_bb_id), _)| {
+                body_func.bbs.contains_key(latch_bb_id) && body_func.bbs.contains_key(header_bb_id)
+            })
+            .map(|(&(latch_bb_id, header_bb_id), &body_bb_id)| {
+                let ids = self.jit_bbs[body_bb_id]
+                    .info
+                    .args
+                    .iter()
+                    .map(|&arg| {
+                        let local = new_ids.get(&(body_bb_id, arg)).copied().unwrap_or(arg);
+                        let local = phi_incoming(&body_func.bbs[body_bb_id], local, header_bb_id);
+                        let local = phi_incoming(&body_func.bbs[header_bb_id], local, latch_bb_id);
+                        ((body_bb_id, arg), local)
+                    })
+                    .collect::<FxHashMap<_, _>>();
+                ((latch_bb_id, header_bb_id), ids)
+            })
+            .collect::<FxHashMap<_, _>>();
+
         let assigned_local_to_obj = assign_type_args(
             body_func,
             &self.jit_bbs[orig_entry_bb_id].info.type_params,
@@ -446,6 +574,58 @@ impl JitSpecializedArgFunc {
                 },
             );
         }
+
+        // 特殊化したBBの実行回数を数え、追い出しの判断に使う
+        if index != BB_LAYOUT_DEFAULT_INDEX && !jit_ctx.config().aot {
+            let counter_global = self.jit_bbs[orig_entry_bb_id]
+                .bb_index_manager
+                .counter_global(index, BBLayoutCounterKind::Hit, global_manager);
+            let [counter_local, one_local, new_counter_local] = [(); 3].map(|_| {
+                body_func.locals.push_with(|id| Local {
+                    id,
+                    typ: Type::Val(ValType::Int).into(),
+                })
+            });
+            let entry_instrs = &mut body_func.bbs[orig_entry_bb_id].instrs;
+            let phi_count = entry_instrs
+                .iter()
+                .take_while(|instr| matches!(instr.kind, InstrKind::Phi { .. } | InstrKind::Nop))
+                .count();
+            entry_instrs.splice(
+                phi_count..phi_count,
+                [
+                    Instr {
+                        local: Some(counter_local),
+                        kind: InstrKind::GlobalGet(counter_global.id),
+                    },
+                    Instr {
+                        local: Some(one_local),
+                        kind: InstrKind::Int(1),
+                    },
+                    Instr {
+                        local: Some(new_counter_local),
+                        kind: InstrKind::AddInt(counter_local, one_local),
+                    },
+                    Instr {
+                        local: None,
+                        kind: InstrKind::GlobalSet(counter_global.id, new_counter_local),
+                    },
+                    Instr {
+                        local: None,
+                        kind: InstrKind::IncrementBBLayoutCounter(
+                            new_counter_local,
+                            self.module_id,
+                            JitFuncId::from(self.func.id),
+                            self.env_index.0,
+                            self.func_index.0,
+                            JitBasicBlockId::from(orig_entry_bb_id),
+                            index.0,
+                            BBLayoutCounterKind::Hit,
+                        ),
+                    },
+                ],
+            );
+        }
         let def_use_chain = DefUseChain::from_bbs(&body_func.bbs);
 
         // マージ済みのBB ID、マージ予定のBB ID
@@ -458,6 +638,9 @@ impl JitSpecializedArgFunc {
 
         let mut new_entrypoint_table_globals = Vec::new();
 
+        // このモジュール内でループヘッダに戻る後方エッジ
+        let mut osr_edges = Vec::new();
+
         while let Some(orig_bb_id) = todo_bb_ids.pop() {
             if processed_bb_ids.contains(&orig_bb_id) {
                 continue;
@@ -500,6 +683,9 @@ impl JitSpecializedArgFunc {
                             }
                         }
 
+                        // 型検査で分岐する場合、else側は型検査に失敗したthen側の特殊化を記録する
+                        let guarded_bb_id = (!then_types.is_empty()).then_some(orig_then_bb_id);
+
                         if branch_specialization {
                             match self.jit_bbs[orig_bb_id].branch_counter.dominant_branch() {
                                 DominantBranchKind::Then => {
@@ -508,6 +694,7 @@ impl JitSpecializedArgFunc {
                                         orig_else_bb_id,
                                         Vec::new(),
                                         BranchKind::Else,
+                                        None,
                                     ));
                                 }
                                 DominantBranchKind::Else => {
@@ -516,6 +703,7 @@ impl JitSpecializedArgFunc {
                                         orig_then_bb_id,
                                         then_types,
                                         BranchKind::Then,
+                                        None,
                                     ));
                                 }
                                 DominantBranchKind::Both => match jit_ctx.config().block_fusion {
@@ -527,11 +715,13 @@ impl JitSpecializedArgFunc {
                                             orig_then_bb_id,
                                             then_types,
                                             BranchKind::Then,
+                                            None,
                                         ));
                                         required_bbs.push((
                                             orig_else_bb_id,
                                             Vec::new(),
                                             BranchKind::Else,
+                                            guarded_bb_id,
                                         ));
                                     }
                                     BlockFusionConfig::LargeFusion => {
@@ -541,14 +731,27 @@ impl JitSpecializedArgFunc {
                                 },
                             }
                         } else {
-                            required_bbs.push((orig_then_bb_id, then_types, BranchKind::Then));
-                            required_bbs.push((orig_else_bb_id, Vec::new(), BranchKind::Else));
+                            required_bbs.push((
+                                orig_then_bb_id,
+                                then_types,
+                                BranchKind::Then,
+                                None,
+                            ));
+                            required_bbs.push((
+                                orig_else_bb_id,
+                                Vec::new(),
+                                BranchKind::Else,
+                                guarded_bb_id,
+                            ));
                         }
 
                         TerminatorInstr::If(cond, orig_then_bb_id, orig_else_bb_id)
                     }
                 }
                 TerminatorInstr::Jump(orig_next_bb_id) => {
+                    if osr_ids.contains_key(&(orig_bb_id, orig_next_bb_id)) {
+                        osr_edges.push((orig_bb_id, orig_next_bb_id));
+                    }
                     todo_bb_ids.push(orig_next_bb_id);
                     TerminatorInstr::Jump(orig_next_bb_id)
                 }
@@ -578,15 +781,21 @@ impl JitSpecializedArgFunc {
             *body_func.bbs[orig_bb_id].terminator_mut() = new_next;
         }
 
+        // ループを通ってこのモジュールにマージしたBBへの分岐は、モジュール内のジャンプのままにする
+        required_bbs.retain(|(bb_id, _, _, _)| !processed_bb_ids.contains(bb_id));
+
         let required_bb_set = required_bbs
             .iter()
-            .map(|(bb_id, _, _)| *bb_id)
+            .map(|(bb_id, _, _, _)| *bb_id)
             .collect::<FxHashSet<BasicBlockId>>();
 
         // log用のエッジ一覧
         let mut edges = Vec::new();
 
-        for &(bb_id, ref types, branch_kind) in required_bbs.iter() {
+        // then側のBBに渡す特殊化。then側はelse側より先に処理される
+        let mut then_indices = FxHashMap::default();
+
+        for &(bb_id, ref types, branch_kind, guarded_bb_id) in required_bbs.iter() {
             let mut instrs = Vec::new();
             for instr in &body_func.bbs[bb_id].instrs {
                 // ジャンプ先のBBのPhiはここに移動
@@ -597,6 +806,7 @@ impl JitSpecializedArgFunc {
             }
 
             if !branch_specialization
+                && !jit_ctx.config().aot
                 && jit_ctx.config().block_fusion != BlockFusionConfig::Disabled
             {
                 instrs.push(Instr {
@@ -614,6 +824,57 @@ impl JitSpecializedArgFunc {
                 });
             }
 
+            // 型検査に失敗してelse側に来た回数を、then側の特殊化の型検査の失敗回数として数える
+            // 分岐を融合してelse側をこのモジュールにまとめた場合は数えない
+            if let Some(guarded_bb_id) = guarded_bb_id
+                && let Some(&guarded_index) = then_indices.get(&guarded_bb_id)
+                && guarded_index != BB_LAYOUT_DEFAULT_INDEX
+                && !jit_ctx.config().aot
+            {
+                let counter_global = self.jit_bbs[guarded_bb_id].bb_index_manager.counter_global(
+                    guarded_index,
+                    BBLayoutCounterKind::GuardFailure,
+                    global_manager,
+                );
+                let [counter_local, one_local, new_counter_local] = [(); 3].map(|_| {
+                    body_func.locals.push_with(|id| Local {
+                        id,
+                        typ: Type::Val(ValType::Int).into(),
+                    })
+                });
+                instrs.extend([
+                    Instr {
+                        local: Some(counter_local),
+                        kind: InstrKind::GlobalGet(counter_global.id),
+                    },
+                    Instr {
+                        local: Some(one_local),
+                        kind: InstrKind::Int(1),
+                    },
+                    Instr {
+                        local: Some(new_counter_local),
+                        kind: InstrKind::AddInt(counter_local, one_local),
+                    },
+                    Instr {
+                        local: None,
+                        kind: InstrKind::GlobalSet(counter_global.id, new_counter_local),
+                    },
+                    Instr {
+                        local: None,
+                        kind: InstrKind::IncrementBBLayoutCounter(
+                            new_counter_local,
+                            self.module_id,
+                            JitFuncId::from(self.func.id),
+                            self.env_index.0,
+                            self.func_index.0,
+                            JitBasicBlockId::from(guarded_bb_id),
+                            guarded_index.0,
+                            BBLayoutCounterKind::GuardFailure,
+                        ),
+                    },
+                ]);
+            }
+
             /*
             Is命令によって分岐している場合、この分岐で型が確定する
             しかし、このbb moduleにはその後に存在するはずのfrom_obj命令が存在しないためこの情報を使った最適化が行えない
@@ -633,6 +894,7 @@ impl JitSpecializedArgFunc {
             }
 
             let callee_jit_bb = &mut self.jit_bbs[bb_id];
+            let mut decisions = Vec::new();
             let (locals_to_pass, type_args, index_global, next_bb_index) = calculate_args_to_pass(
                 &callee_jit_bb.info,
                 |obj_local| {
@@ -648,8 +910,13 @@ impl JitSpecializedArgFunc {
                 &new_ids,
                 &mut callee_jit_bb.bb_index_manager,
                 &mut required_stubs,
+                &mut decisions,
                 global_manager,
             );
+            bb_index_decisions.extend(decisions.into_iter().map(|decision| (bb_id, decision)));
+            if branch_kind == BranchKind::Then {
+                then_indices.insert(bb_id, next_bb_index);
+            }
 
             if jit_ctx.config().enable_log {
                 edges.push((bb_id, next_bb_index));
@@ -685,6 +952,176 @@ impl JitSpecializedArgFunc {
 
         let mut jit_events = Vec::new();
 
+        /*
+        モジュール内で回り続けるループは、後方エッジの実行回数がtier_up_thresholdに達したら、その時点の型で特殊化したループの本体に移る(OSR)
+        latch:
+          backedge_counter <- backedge_counter + 1
+          if backedge_counter >= threshold then osr else loop
+        loop:
+          jump header
+        osr:
+          body <- get_global body_ref
+          body(...)
+        */
+        for (latch_bb_id, header_bb_id) in osr_edges {
+            let body_bb_id = self.osr_entries[&(latch_bb_id, header_bb_id)];
+            let body_jit_bb = &mut self.jit_bbs[body_bb_id];
+            let mut decisions = Vec::new();
+            let (locals_to_pass, type_args, index_global, next_bb_index) = calculate_args_to_pass(
+                &body_jit_bb.info,
+                |mut obj_local| {
+                    loop {
+                        match def_use_chain.get_def_non_move_expr(&body_func.bbs, obj_local)? {
+                            &InstrKind::ToObj(typ, val_local) => {
+                                return Some(TypedObj { typ, val_local });
+                            }
+                            // 他の入力元がモジュールの外に出たPhiは、残った入力元の値になる
+                            InstrKind::Phi { incomings, .. } => {
+                                let mut incomings = incomings
+                                    .iter()
+                                    .filter(|incoming| !required_bb_set.contains(&incoming.bb));
+                                let incoming = incomings.next()?;
+                                if incomings.next().is_some() {
+                                    return None;
+                                }
+                                obj_local = incoming.local;
+                            }
+                            _ => return None,
+                        }
+                    }
+                },
+                &assigned_local_to_obj,
+                &osr_ids[&(latch_bb_id, header_bb_id)],
+                &mut body_jit_bb.bb_index_manager,
+                &mut required_stubs,
+                &mut decisions,
+                global_manager,
+            );
+            bb_index_decisions.extend(decisions.into_iter().map(|decision| (body_bb_id, decision)));
+            // 型が分からなければ移っても速くならない
+            if next_bb_index == BB_LAYOUT_DEFAULT_INDEX {
+                continue;
+            }
+
+            if jit_ctx.config().enable_log {
+                edges.push((body_bb_id, next_bb_index));
+                jit_events.push(JitLogEvent::Osr {
+                    module_id: usize::from(self.module_id),
+                    func_id: usize::from(self.func.id),
+                    env_index: self.env_index.0,
+                    func_index: self.func_index.0,
+                    bb_id: usize::from(body_bb_id),
+                    index: next_bb_index.0,
+                });
+            }
+
+            let func_ref_local = body_func.locals.push_with(|id| Local {
+                id,
+                typ: LocalType::FuncRef,
+            });
+            let osr_bb_id = body_func.bbs.push_with(|id| BasicBlock {
+                id,
+                instrs: vec![
+                    Instr {
+                        local: Some(func_ref_local),
+                        kind: InstrKind::GlobalGet(index_global.id),
+                    },
+                    Instr {
+                        local: None,
+                        kind: InstrKind::Terminator(TerminatorInstr::Exit(ExitInstr::TailCallRef(
+                            InstrCallRef {
+                                func: func_ref_local,
+                                args: locals_to_pass,
+                                func_type: FuncType {
+                                    args: self.jit_bbs[body_bb_id]
+                                        .info
+                                        .arg_types(&self.func, &type_args),
+                                    ret: body_func.ret_type,
+                                },
+                            },
+                        ))),
+                    },
+                ],
+            });
+
+            let new_latch_bb_id = if jit_ctx.config().aot {
+                // 実行時にコンパイルしないので、数えずに常に特殊化されたループの本体に移る
+                *body_func.bbs[latch_bb_id].terminator_mut() = TerminatorInstr::Jump(osr_bb_id);
+                None
+            } else {
+                let counter_global = *self.jit_bbs[body_bb_id]
+                    .backedge_counter
+                    .get_or_insert_with(|| {
+                        global_manager.gen_global(Type::Val(ValType::Int).into())
+                    });
+                let [counter_local, one_local, new_counter_local, threshold_local] =
+                    [(); 4].map(|_| {
+                        body_func.locals.push_with(|id| Local {
+                            id,
+                            typ: Type::Val(ValType::Int).into(),
+                        })
+                    });
+                let cond_local = body_func.locals.push_with(|id| Local {
+                    id,
+                    typ: Type::Val(ValType::Bool).into(),
+                });
+                let loop_bb_id = body_func.bbs.push_with(|id| BasicBlock {
+                    id,
+                    instrs: vec![Instr {
+                        local: None,
+                        kind: InstrKind::Terminator(TerminatorInstr::Jump(header_bb_id)),
+                    }],
+                });
+                let latch_bb = &mut body_func.bbs[latch_bb_id];
+                *latch_bb.terminator_mut() = TerminatorInstr::If(cond_local, osr_bb_id, loop_bb_id);
+                latch_bb.insert_instrs_before_terminator(
+                    [
+                        Instr {
+                            local: Some(counter_local),
+                            kind: InstrKind::GlobalGet(counter_global.id),
+                        },
+                        Instr {
+                            local: Some(one_local),
+                            kind: InstrKind::Int(1),
+                        },
+                        Instr {
+                            local: Some(new_counter_local),
+                            kind: InstrKind::AddInt(counter_local, one_local),
+                        },
+                        Instr {
+                            local: None,
+                            kind: InstrKind::GlobalSet(counter_global.id, new_counter_local),
+                        },
+                        Instr {
+                            local: Some(threshold_local),
+                            kind: InstrKind::Int(jit_ctx.config().tier_up_threshold as i64),
+                        },
+                        Instr {
+                            local: Some(cond_local),
+                            kind: InstrKind::GeInt(new_counter_local, threshold_local),
+                        },
+                    ]
+                    .into_iter(),
+                );
+                Some(loop_bb_id)
+            };
+
+            // ループヘッダのPhi命令の入力元を付け替える
+            for instr in body_func.bbs[header_bb_id].instrs.iter_mut() {
+                if let InstrKind::Phi { incomings, .. } = &mut instr.kind {
+                    if let Some(new_latch_bb_id) = new_latch_bb_id {
+                        for incoming in incomings.iter_mut() {
+                            if incoming.bb == latch_bb_id {
+                                incoming.bb = new_latch_bb_id;
+                            }
+                        }
+                    } else {
+                        incomings.retain(|incoming| incoming.bb != latch_bb_id);
+                    }
+                }
+            }
+        }
+
         if jit_ctx.config().enable_log && !branch_specialization
         // workaround: BB融合はまだ未対応
         {
@@ -847,6 +1284,7 @@ impl JitSpecializedArgFunc {
                         &body_func.bbs,
                         jit_ctx.closure_global_layout(),
                         &mut required_closure_idx,
+                        &mut closure_fallbacks,
                     ) =>
                     {
                         body_func.bbs[bb_id].instrs[instr_idx].kind =
@@ -864,6 +1302,7 @@ impl JitSpecializedArgFunc {
                         &body_func.bbs,
                         jit_ctx.closure_global_layout(),
                         &mut required_closure_idx,
+                        &mut closure_fallbacks,
                     ) =>
                     {
                         body_func.bbs[bb_id].instrs[instr_idx].kind = InstrKind::Terminator(
@@ -927,6 +1366,19 @@ impl JitSpecializedArgFunc {
                                     new_envs.push(None);
                                 }
                             } else {
+                                // 特殊化しないenvのSetEnv命令もenvの型が変わるので書き換える
+                                if envs[i].is_none()
+                                    && let Some(local) = local
+                                    && let Some(&(value_local, set_env_instr_idx)) =
+                                        closure_set_envs.get(&(*local, i))
+                                {
+                                    rewrite_closure_set_envs.push((
+                                        set_env_instr_idx,
+                                        *local,
+                                        i,
+                                        value_local,
+                                    ));
+                                }
                                 new_env_types.push(*env_type);
                                 new_envs.push(envs[i]);
                             }
@@ -937,7 +1389,18 @@ impl JitSpecializedArgFunc {
                             .expect("EnvIndexManager not found");
                         let (entrypoint_table_global, env_index, index_flag) = env_index_manager
                             .idx(&env_types_for_manager, global_manager)
-                            .unwrap(); // TODO: 上限に到達したときの処理
+                            .unwrap_or_else(|| {
+                                if env_index_manager.fallback(&env_types_for_manager) {
+                                    env_fallbacks.push((
+                                        *module_id,
+                                        *func_id,
+                                        env_types_for_manager.clone(),
+                                    ));
+                                }
+                                env_index_manager
+                                    .idx(&VecMap::default(), global_manager)
+                                    .unwrap()
+                            });
                         if env_index != ENV_LAYOUT_DEFAULT_INDEX {
                             let entrypoint_table_global = entrypoint_table_global.unwrap();
                             if index_flag == IndexFlag::NewInstance {
@@ -983,14 +1446,23 @@ impl JitSpecializedArgFunc {
         }
 
         // DefUseChainを壊さないために最後に追加する
+        // Phi命令より前には追加できない
         for (bb_id, mut preamble_instrs) in all_preamble_instrs {
-            body_func.bbs[bb_id]
-                .instrs
-                .splice(0..0, preamble_instrs.drain(..));
+            let instrs = &mut body_func.bbs[bb_id].instrs;
+            let phi_end = instrs
+                .iter()
+                .position(|instr| !matches!(instr.kind, InstrKind::Phi { .. } | InstrKind::Nop))
+                .unwrap_or(instrs.len());
+            instrs.splice(phi_end..phi_end, preamble_instrs.drain(..));
         }
 
         remove_unreachable_bb(body_func);
 
+        // AOTではスタブの代わりにJitModuleが生成済みの関数を呼び分ける関数を設定する
+        if jit_ctx.config().aot {
+            required_closure_idx.clear();
+        }
+
         let required_closure_idx = required_closure_idx
             .iter()
             .map(|&closure_idx| {
@@ -1235,16 +1707,129 @@ impl JitSpecializedArgFunc {
                 // TODO:
                 local_metas: FxHashMap::default(),
                 global_metas: FxHashMap::default(),
+                func_metas: FxHashMap::default(),
+                source_locs: FxHashMap::default(),
             },
         };
 
-        for (bb_id, index) in &required_stubs {
-            self.add_bb_stub_func(self.module_id, *bb_id, BBIndex(*index), &mut module);
+        for &(bb_id, index) in &required_stubs {
+            if jit_ctx.config().aot {
+                self.aot_pending_bbs.push((bb_id, BBIndex(index)));
+            } else {
+                self.add_bb_stub_func(self.module_id, bb_id, BBIndex(index), &mut module);
+            }
+        }
+
+        if jit_ctx.config().enable_log {
+            for (bb_id, decision) in bb_index_decisions {
+                jit_events.push(match decision {
+                    BBIndexDecision::Evicted { index, counts } => JitLogEvent::BasicBlockEvict {
+                        module_id: usize::from(self.module_id),
+                        func_id: usize::from(self.func.id),
+                        env_index: self.env_index.0,
+                        func_index: self.func_index.0,
+                        bb_id: usize::from(bb_id),
+                        index: index.0,
+                        hits: counts.hits,
+                        guard_failures: counts.guard_failures,
+                    },
+                    BBIndexDecision::Megamorphic { evictions } => {
+                        JitLogEvent::BasicBlockMegamorphic {
+                            module_id: usize::from(self.module_id),
+                            func_id: usize::from(self.func.id),
+                            env_index: self.env_index.0,
+                            func_index: self.func_index.0,
+                            bb_id: usize::from(bb_id),
+                            evictions,
+                        }
+                    }
+                });
+            }
+            for args in closure_fallbacks {
+                let ClosureArgs::Specified(args) = args else {
+                    continue;
+                };
+                jit_events.push(JitLogEvent::ClosureMegamorphic {
+                    arg_types: args
+                        .iter()
+                        .map(|v| format!("{v}"))
+                        .collect::<Vec<_>>()
+                        .join(","),
+                });
+            }
+            for (module_id, func_id, env_types) in env_fallbacks {
+                jit_events.push(JitLogEvent::EnvMegamorphic {
+                    module_id: usize::from(module_id),
+                    func_id: usize::from(func_id),
+                    env_types: env_types
+                        .iter()
+                        .map(|(k, v)| format!("{k}={v}"))
+                        .collect::<Vec<_>>()
+                        .join(","),
+                });
+            }
         }
 
         (module, jit_events)
     }
 
+    pub fn bb_profiles(&self) -> impl Iterator<Item = BBProfile> + '_ {
+        self.jit_bbs.values().filter_map(|jit_bb| {
+            let layouts = jit_bb
+                .bb_index_manager
+                .layouts()
+                .map(|(type_args, counts)| BBLayoutProfile {
+                    type_args: type_args
+                        .iter()
+                        .map(|(type_param_id, &typ)| (usize::from(type_param_id), typ))
+                        .collect(),
+                    hits: counts.hits,
+                    guard_failures: counts.guard_failures,
+                })
+                .collect::<Vec<_>>();
+            let megamorphic = jit_bb.bb_index_manager.is_megamorphic();
+            let BranchCounter {
+                then_count,
+                else_count,
+            } = jit_bb.branch_counter;
+            if layouts.is_empty()
+                && !megamorphic
+                && !jit_bb.fused
+                && then_count == 0
+                && else_count == 0
+            {
+                return None;
+            }
+            Some(BBProfile {
+                key: BBProfileKey {
+                    module_id: usize::from(self.module_id),
+                    func_id: usize::from(self.func.id),
+                    env_index: self.env_index.0,
+                    func_index: self.func_index.0,
+                    bb_id: usize::from(jit_bb.bb_id),
+                },
+                then_count,
+                else_count,
+                fused: jit_bb.fused,
+                megamorphic,
+                layouts,
+            })
+        })
+    }
+
+    pub fn increment_bb_layout_counter(
+        &mut self,
+        bb_id: BasicBlockId,
+        index: BBIndex,
+        kind: BBLayoutCounterKind,
+    ) {
+        self.jit_bbs[bb_id].bb_index_manager.record(
+            index,
+            kind,
+            BB_LAYOUT_COUNTER_INTERVAL as usize,
+        );
+    }
+
     pub fn increment_branch_counter(
         &mut self,
         func_to_globals: &VecMap<FuncId, GlobalId>,
@@ -1259,6 +1844,7 @@ impl JitSpecializedArgFunc {
     ) -> Option<(Module, Vec<JitLogEvent>)> {
         self.jit_bbs[bb_id].branch_counter.increment(kind);
         if self.jit_bbs[bb_id].branch_counter.should_specialize() {
+            self.jit_bbs[source_bb_id].fused = true;
             let (module, jit_events) = self.generate_bb_module(
                 func_to_globals,
                 func_types,
@@ -1282,16 +1868,18 @@ fn specialize_call_closure(
     bbs: &VecMap<BasicBlockId, BasicBlock>,
     closure_global_layout: &mut ClosureGlobalLayout,
     required_closure_idx: &mut Vec<ClosureIndex>,
+    closure_fallbacks: &mut Vec<ClosureArgs>,
 ) -> Option<InstrCallClosure> {
     if call_closure.func_index != CLOSURE_LAYOUT_DEFAULT_INDEX.0 {
         return None;
     }
 
     // func_index == CLOSURE_LAYOUT_DEFAULT_INDEX なら引数は[Args]を仮定してよい
+    // applyの場合は引数の数が静的にわからないので特殊化しない
     let InstrKind::VariadicArgs(args) =
         def_use_chain.get_def_non_move_expr(bbs, call_closure.args[0])?
     else {
-        unreachable!("unexpected expr other than VariadicArgs");
+        return None;
     };
 
     let mut fixed_args = Vec::new();
@@ -1313,9 +1901,15 @@ fn specialize_call_closure(
         .copied()
         .map(LocalType::Type)
         .collect();
+    let specified_args = ClosureArgs::Specified(fixed_arg_types);
     let (closure_index, flag) = closure_global_layout
-        .idx(&ClosureArgs::Specified(fixed_arg_types))
-        .unwrap_or_else(|| closure_global_layout.idx(&ClosureArgs::Variadic).unwrap());
+        .idx(&specified_args)
+        .unwrap_or_else(|| {
+            if closure_global_layout.fallback(&specified_args) {
+                closure_fallbacks.push(specified_args.clone());
+            }
+            closure_global_layout.idx(&ClosureArgs::Variadic).unwrap()
+        });
 
     if flag == IndexFlag::NewInstance {
         required_closure_idx.push(closure_index);
@@ -1339,6 +1933,36 @@ struct JitBB {
     bb_index_manager: BBIndexManager,
     // BB Indexごとにカウンターを持つと、まとめて複数の分岐をマージできないためBBごとに持つ
     branch_counter: BranchCounter,
+    // 分岐の特殊化を行ったか
+    fused: bool,
+    // このBBへOSRする後方エッジの実行回数
+    backedge_counter: Option<Global>,
+}
+
+impl JitBB {
+    fn load_profile(&mut self, bb_profile: &BBProfile) {
+        self.branch_counter = BranchCounter {
+            then_count: bb_profile.then_count,
+            else_count: bb_profile.else_count,
+        };
+        self.fused = bb_profile.fused;
+        self.bb_index_manager.load_profile(
+            bb_profile.layouts.iter().map(|layout| {
+                (
+                    layout
+                        .type_args
+                        .iter()
+                        .map(|&(type_param_id, typ)| (TypeParamId::from(type_param_id), typ))
+                        .collect(),
+                    BBLayoutCounts {
+                        hits: layout.hits,
+                        guard_failures: layout.guard_failures,
+                    },
+                )
+            }),
+            bb_profile.megamorphic,
+        );
+    }
 }
 
 impl HasId for JitBB {
@@ -1464,6 +2088,20 @@ pub struct TypedObj {
     pub typ: ValType,
 }
 
+// bbのPhi命令がlocalを定義していれば、pred_bb_idから来る値を返す
+fn phi_incoming(bb: &BasicBlock, local: LocalId, pred_bb_id: BasicBlockId) -> LocalId {
+    bb.instrs
+        .iter()
+        .find_map(|instr| match &instr.kind {
+            InstrKind::Phi { incomings, .. } if instr.local == Some(local) => incomings
+                .iter()
+                .find(|incoming| incoming.bb == pred_bb_id)
+                .map(|incoming| incoming.local),
+            _ => None,
+        })
+        .unwrap_or(local)
+}
+
 fn calculate_args_to_pass(
     callee: &BBInfo,
     get_typed_obj: impl Fn(LocalId) -> Option<TypedObj>,
@@ -1471,6 +2109,7 @@ fn calculate_args_to_pass(
     new_ids: &FxHashMap<(BasicBlockId, LocalId), LocalId>,
     bb_index_manager: &mut BBIndexManager,
     required_stubs: &mut Vec<(BasicBlockId, usize)>,
+    decisions: &mut Vec<BBIndexDecision>,
     global_manager: &mut GlobalManager,
 ) -> (Vec<LocalId>, VecMap<TypeParamId, ValType>, Global, BBIndex) {
     let mut type_args = VecMap::new();
@@ -1479,13 +2118,14 @@ fn calculate_args_to_pass(
     let mut args_to_pass_fallback = Vec::new();
 
     for &arg in &callee.args {
+        let type_param_id = callee.type_params.get_by_right(&arg).copied();
         let arg = new_ids.get(&(callee.bb_id, arg)).copied().unwrap_or(arg);
         let obj_arg = caller_assigned_local_to_obj
             .get(&arg)
             .copied()
             .unwrap_or(arg);
 
-        let caller_args = if let Some(&type_param_id) = callee.type_params.get_by_right(&arg)
+        let caller_args = if let Some(type_param_id) = type_param_id
             && let Some(typed_obj) = get_typed_obj(obj_arg)
         {
             type_args.insert(type_param_id, typed_obj.typ);
@@ -1499,11 +2139,13 @@ fn calculate_args_to_pass(
     }
 
     let (type_args, args_to_pass, (global, index, flag)) = bb_index_manager
-        .idx(&type_args, global_manager)
+        .idx(&type_args, global_manager, decisions)
         .map(|x| (type_args, args_to_pass, x))
         .unwrap_or_else(|| {
             let type_args = VecMap::default();
-            let x = bb_index_manager.idx(&type_args, global_manager).unwrap();
+            let x = bb_index_manager
+                .idx(&type_args, global_manager, decisions)
+                .unwrap();
             (type_args, args_to_pass_fallback, x)
         });
 
@@ -1682,6 +2324,213 @@ fn closure_func_assign_types(
     func.bb_entry = new_bb_entry;
 }
 
+/*
+自己末尾呼び出しをCFG上のループに変換する
+closure_func_assign_typesが生成したエントリーの後にループヘッダを追加し、呼び出し先が自身と同じクロージャなら引数をPhiで受け取ってループヘッダに戻る
+
+entry:
+  x1_obj = to_obj(x1)
+  jump header
+header:
+  x1_obj' = phi(entry: x1_obj, loop: y1_obj)
+  args = variadic_args(x1_obj')
+  jump prev_entry
+...
+bb:
+  is_self = eq_obj(to_obj(f), to_obj(self))
+  if is_self then loop else call
+loop:
+  jump header
+call:
+  tail_call_closure(f, variadic_args(y1_obj))
+*/
+fn loopify_self_tail_calls(
+    func: &mut Func,
+    func_index: ClosureIndex,
+    closure_global_layout: &ClosureGlobalLayout,
+) {
+    let ClosureArgs::Specified(args_type) = closure_global_layout.arg_types(func_index) else {
+        // 可変長引数のレイアウトでは、最初の自己呼び出しで特殊化されたレイアウトに移る
+        return;
+    };
+
+    let entry_bb_id = func.bb_entry;
+    let Some(variadic_args_idx) = func.bbs[entry_bb_id]
+        .instrs
+        .iter()
+        .position(|instr| matches!(instr.kind, InstrKind::VariadicArgs(_)))
+    else {
+        return;
+    };
+    let InstrKind::VariadicArgs(entry_obj_locals) =
+        func.bbs[entry_bb_id].instrs[variadic_args_idx].kind.clone()
+    else {
+        unreachable!();
+    };
+
+    let def_use_chain = DefUseChain::from_bbs(&func.bbs);
+    let call_sites = func
+        .bbs
+        .values()
+        .filter_map(|bb| {
+            let TerminatorInstr::Exit(ExitInstr::TailCallClosure(call_closure)) = bb.terminator()
+            else {
+                return None;
+            };
+            let &[args_local] = call_closure.args.as_slice() else {
+                return None;
+            };
+            let Some(InstrKind::VariadicArgs(obj_locals)) =
+                def_use_chain.get_def_non_move_expr(&func.bbs, args_local)
+            else {
+                return None;
+            };
+            (obj_locals.len() == args_type.len())
+                .then(|| (bb.id, call_closure.clone(), obj_locals.clone()))
+        })
+        .collect::<Vec<_>>();
+    if call_sites.is_empty() {
+        return;
+    }
+
+    let self_closure = func.args[0];
+    let header_bb_id = func.bbs.allocate_key();
+    let mut incomings = entry_obj_locals
+        .iter()
+        .map(|&local| {
+            vec![PhiIncomingValue {
+                bb: entry_bb_id,
+                local,
+            }]
+        })
+        .collect::<Vec<_>>();
+
+    for (bb_id, call_closure, obj_locals) in call_sites {
+        let self_obj_local = func.locals.push_with(|id| Local {
+            id,
+            typ: LocalType::Type(Type::Obj),
+        });
+        let callee_obj_local = func.locals.push_with(|id| Local {
+            id,
+            typ: LocalType::Type(Type::Obj),
+        });
+        let is_self_local = func.locals.push_with(|id| Local {
+            id,
+            typ: LocalType::Type(Type::Val(ValType::Bool)),
+        });
+
+        let loop_bb_id = func.bbs.push_with(|id| BasicBlock {
+            id,
+            instrs: vec![Instr {
+                local: None,
+                kind: InstrKind::Terminator(TerminatorInstr::Jump(header_bb_id)),
+            }],
+        });
+        let call_bb_id = func.bbs.push_with(|id| BasicBlock {
+            id,
+            instrs: vec![Instr {
+                local: None,
+                kind: InstrKind::Terminator(TerminatorInstr::Exit(ExitInstr::TailCallClosure(
+                    call_closure.clone(),
+                ))),
+            }],
+        });
+
+        let bb = &mut func.bbs[bb_id];
+        bb.instrs.pop();
+        bb.instrs.extend([
+            Instr {
+                local: Some(self_obj_local),
+                kind: InstrKind::ToObj(ValType::Closure, self_closure),
+            },
+            Instr {
+                local: Some(callee_obj_local),
+                kind: InstrKind::ToObj(ValType::Closure, call_closure.closure),
+            },
+            Instr {
+                local: Some(is_self_local),
+                kind: InstrKind::EqObj(callee_obj_local, self_obj_local),
+            },
+            Instr {
+                local: None,
+                kind: InstrKind::Terminator(TerminatorInstr::If(
+                    is_self_local,
+                    loop_bb_id,
+                    call_bb_id,
+                )),
+            },
+        ]);
+
+        for (incomings, &local) in incomings.iter_mut().zip(obj_locals.iter()) {
+            incomings.push(PhiIncomingValue {
+                bb: loop_bb_id,
+                local,
+            });
+        }
+    }
+
+    let mut header_instrs = Vec::new();
+    let mut phi_locals = Vec::new();
+    for incomings in incomings {
+        let phi_local = func.locals.push_with(|id| Local {
+            id,
+            typ: LocalType::Type(Type::Obj),
+        });
+        header_instrs.push(Instr {
+            local: Some(phi_local),
+            kind: InstrKind::Phi {
+                incomings,
+                non_exhaustive: false,
+            },
+        });
+        phi_locals.push(phi_local);
+    }
+
+    let entry_bb = &mut func.bbs[entry_bb_id];
+    let mut variadic_args_instr = entry_bb.instrs.remove(variadic_args_idx);
+    variadic_args_instr.kind = InstrKind::VariadicArgs(phi_locals);
+    header_instrs.push(variadic_args_instr);
+    header_instrs.push(Instr {
+        local: None,
+        kind: InstrKind::Terminator(std::mem::replace(
+            entry_bb.terminator_mut(),
+            TerminatorInstr::Jump(header_bb_id),
+        )),
+    });
+    func.bbs.insert_node(BasicBlock {
+        id: header_bb_id,
+        instrs: header_instrs,
+    });
+}
+
+// ループヘッダがPhi命令とジャンプのみからなり、ジャンプ先がループヘッダからしか到達しない場合、ジャンプ先をOSRの入口にする
+// ジャンプ先の生存変数は、後方エッジのジャンプ元でPhi命令への入力から求められる
+fn find_osr_entries(func: &Func) -> FxHashMap<(BasicBlockId, BasicBlockId), BasicBlockId> {
+    let predecessors = calc_predecessors(&func.bbs);
+    let rpo = calculate_rpo(&func.bbs, func.bb_entry);
+    let doms = calc_doms(&func.bbs, &rpo, func.bb_entry, &predecessors);
+
+    find_back_edges(&func.bbs, &doms)
+        .into_iter()
+        .filter_map(|(latch_bb_id, header_bb_id)| {
+            let header_bb = &func.bbs[header_bb_id];
+            let (terminator, phis) = header_bb.instrs.split_last()?;
+            let InstrKind::Terminator(TerminatorInstr::Jump(body_bb_id)) = terminator.kind else {
+                return None;
+            };
+            let only_phis = phis
+                .iter()
+                .all(|instr| matches!(instr.kind, InstrKind::Phi { .. } | InstrKind::Nop));
+            let latch_jumps =
+                matches!(func.bbs[latch_bb_id].terminator(), TerminatorInstr::Jump(_));
+            (only_phis
+                && latch_jumps
+                && predecessors.get(&body_bb_id).map(Vec::as_slice) == Some(&[header_bb_id]))
+            .then_some(((latch_bb_id, header_bb_id), body_bb_id))
+        })
+        .collect()
+}
+
 pub fn assign_type_args(
     func: &mut Func,
     type_params: &FxBiHashMap<TypeParamId, LocalId>,
@@ -1,10 +1,13 @@
-use rustc_hash::FxHashMap;
+use rustc_hash::{FxHashMap, FxHashSet};
 
 use super::bb_index_manager::BBIndex;
-use super::closure_global_layout::{CLOSURE_LAYOUT_MAX_SIZE, ClosureIndex};
-use super::env_index_manager::{EnvIndex, EnvIndexManager};
+use super::closure_global_layout::{
+    CLOSURE_LAYOUT_DEFAULT_INDEX, CLOSURE_LAYOUT_MAX_SIZE, ClosureArgs, ClosureIndex,
+};
+use super::env_index_manager::{ENV_LAYOUT_DEFAULT_INDEX, EnvIndex, EnvIndexManager};
 use super::jit_ctx::JitCtx;
 use super::jit_func::{JitFunc, JitSpecializedArgFunc};
+use super::profile::{BBProfile, FuncProfile};
 use crate::ir_generator::GlobalManager;
 use crate::jit::jit_func::JitSpecializedEnvFunc;
 use vec_map::{HasId, VecMap};
@@ -15,7 +18,11 @@ pub struct JitModule {
     module: Module,
     jit_funcs: FxHashMap<FuncId, JitFunc>,
     func_to_globals: VecMap<FuncId, GlobalId>,
+    // 関数の呼び出し回数。tier_up_thresholdに達したらJITでコンパイルする
+    tier_up_counter_globals: VecMap<FuncId, GlobalId>,
     func_types: VecMap<FuncId, FuncType>,
+    // JITコンパイルされた関数。プロファイルに記録する
+    instantiated_funcs: FxHashSet<FuncId>,
     pub env_index_managers: FxHashMap<FuncId, EnvIndexManager>,
 }
 
@@ -35,6 +42,7 @@ impl JitModule {
     ) -> Self {
         let mut jit_funcs = FxHashMap::default();
         let mut func_to_globals = VecMap::default();
+        let mut tier_up_counter_globals = VecMap::default();
 
         let env_index_managers = module
             .funcs
@@ -52,6 +60,8 @@ impl JitModule {
             );
             let global = global_manager.gen_global(LocalType::FuncRef);
             func_to_globals.insert(func.id, global.id);
+            let counter_global = global_manager.gen_global(Type::Val(ValType::Int).into());
+            tier_up_counter_globals.insert(func.id, counter_global.id);
             jit_funcs.insert(func.id, jit_func);
         }
 
@@ -66,13 +76,15 @@ impl JitModule {
             module,
             jit_funcs,
             func_to_globals,
+            tier_up_counter_globals,
             func_types,
+            instantiated_funcs: FxHashSet::default(),
             env_index_managers,
         }
     }
 
     pub fn generate_stub_module(
-        &self,
+        &mut self,
         global_manager: &mut GlobalManager,
         jit_ctx: &mut JitCtx,
     ) -> Module {
@@ -80,8 +92,42 @@ impl JitModule {
 
         // TODO: meta
 
+        // 最初にインスタンス化されるモジュールなら初期化処理
+        // ベースラインの関数がstub_globalsを参照するので先にグローバルを確保しておく
+        let new_stub_globals = if jit_ctx.is_instantiated() {
+            None
+        } else {
+            let mut stub_globals = FxHashMap::default();
+            for func_index in 0..CLOSURE_LAYOUT_MAX_SIZE {
+                let func_index = ClosureIndex(func_index);
+                let stub_global = global_manager.gen_global(LocalType::MutFuncRef);
+                stub_globals.insert(func_index, stub_global);
+            }
+
+            let instantiate_func_global = global_manager.gen_global(LocalType::FuncRef);
+            jit_ctx.init_instantiated(stub_globals.clone(), instantiate_func_global);
+            Some(stub_globals)
+        };
+
         // entry関数もあるので+1してる
         let mut stub_func_ids = FxHashMap::default();
+        for func in self.module.funcs.values() {
+            stub_func_ids.insert(func.id, module.funcs.allocate_key());
+        }
+
+        let tier_up_threshold = jit_ctx.config().tier_up_threshold;
+        let aot = jit_ctx.config().aot;
+        // tier_up_threshold回呼び出されるまではir_generatorが生成した関数を特殊化せずに実行する
+        // AOTではプロファイルにない関数は常にベースラインで実行する
+        let baseline_func_ids = if tier_up_threshold > 0 || aot {
+            let mut baseline_func_ids = FxHashMap::default();
+            for func in self.module.funcs.values() {
+                baseline_func_ids.insert(func.id, module.funcs.allocate_key());
+            }
+            Some(baseline_func_ids)
+        } else {
+            None
+        };
 
         /*
         以下のようなentryを生成
@@ -94,59 +140,55 @@ impl JitModule {
         */
 
         for func in self.module.funcs.values() {
-            /*
-            以下のようなスタブを生成
-            func f0_stub(x1, x2) {
-                instantiate_module(f0_module);
-                f0 <- get_global f0_ref
-                f0(x1, x2)
+            let stub_func_id = stub_func_ids[&func.id];
+            let stub_func = if aot {
+                let baseline_func_id = (!jit_ctx.is_profiled_hot_func(self.module_id, func.id))
+                    .then(|| baseline_func_ids.as_ref().unwrap()[&func.id]);
+                self.generate_aot_stub_func(stub_func_id, func, baseline_func_id)
+            } else if let Some(baseline_func_ids) = &baseline_func_ids {
+                // 前回の実行でJITコンパイルされた関数は最初の呼び出しでJITコンパイルする
+                // ベースラインからはスタブが直接呼ばれるので、カウンタでインスタンス化を1回に限る
+                let tier_up_threshold = if jit_ctx.is_profiled_hot_func(self.module_id, func.id) {
+                    1
+                } else {
+                    tier_up_threshold
+                };
+                self.generate_tiered_stub_func(
+                    stub_func_id,
+                    func,
+                    baseline_func_ids[&func.id],
+                    tier_up_threshold,
+                )
+            } else {
+                self.generate_stub_func(stub_func_id, func)
+            };
+            module.funcs.insert_node(stub_func);
+            if let Some(func_meta) = self.module.meta.func_metas.get(&func.id) {
+                module
+                    .meta
+                    .func_metas
+                    .insert(stub_func_id, func_meta.clone());
             }
-            */
-            let mut new_locals = func.locals.clone();
-            let f0_ref_local = new_locals.push_with(|id| Local {
-                id,
-                typ: LocalType::FuncRef,
-            });
+        }
 
-            let id = module.funcs.push_with(|id| Func {
-                id,
-                args: func.args.clone(),
-                ret_type: func.ret_type,
-                locals: new_locals,
-                bb_entry: BasicBlockId::from(0),
-                bbs: [BasicBlock {
-                    id: BasicBlockId::from(0),
-                    instrs: vec![
-                        Instr {
-                            local: None,
-                            kind: InstrKind::InstantiateFunc(
-                                self.module_id,
-                                JitFuncId::from(func.id),
-                            ),
-                        },
-                        Instr {
-                            local: Some(f0_ref_local),
-                            kind: InstrKind::GlobalGet(self.func_to_globals[func.id]),
-                        },
-                        Instr {
-                            local: None,
-                            kind: InstrKind::Terminator(TerminatorInstr::Exit(
-                                ExitInstr::TailCallRef(InstrCallRef {
-                                    func: f0_ref_local,
-                                    args: func.args.clone(),
-                                    func_type: func.func_type(),
-                                }),
-                            )),
-                        },
-                    ],
-                }]
-                .into_iter()
-                .collect(),
-                closure_meta: None,
-            });
-            stub_func_ids.insert(func.id, id);
+        if let Some(baseline_func_ids) = &baseline_func_ids {
+            for func in self.module.funcs.values() {
+                let baseline_func = self.generate_baseline_func(
+                    baseline_func_ids[&func.id],
+                    func,
+                    &stub_func_ids,
+                    jit_ctx,
+                );
+                module.funcs.insert_node(baseline_func);
+            }
+            self.add_baseline_metas(baseline_func_ids, &mut module);
         }
 
+        let adapter_installer_func_id = (aot && new_stub_globals.is_some())
+            .then(|| Self::generate_aot_adapters(&mut module, jit_ctx));
+        let aot_init_func_id =
+            aot.then(|| self.generate_aot_funcs(&mut module, global_manager, jit_ctx));
+
         module.extend_entry_func(|entry_func, next| {
             // entry
             let mut exprs = Vec::new();
@@ -166,13 +208,9 @@ impl JitModule {
                 });
             }
 
-            // 最初にインスタンス化されるモジュールなら初期化処理
-            if !jit_ctx.is_instantiated() {
-                let mut stub_globals = FxHashMap::default();
+            if let Some(stub_globals) = &new_stub_globals {
                 for func_index in 0..CLOSURE_LAYOUT_MAX_SIZE {
-                    let func_index = ClosureIndex(func_index);
-                    let stub_global = global_manager.gen_global(LocalType::MutFuncRef);
-                    stub_globals.insert(func_index, stub_global);
+                    let stub_global = stub_globals[&ClosureIndex(func_index)];
                     let stub_local = entry_func.locals.push_with(|id| Local {
                         id,
                         typ: LocalType::MutFuncRef,
@@ -186,10 +224,21 @@ impl JitModule {
                         kind: InstrKind::GlobalSet(stub_global.id, stub_local),
                     });
                 }
+            }
 
-                let instantiate_func_global = global_manager.gen_global(LocalType::FuncRef);
-                jit_ctx.init_instantiated(stub_globals, instantiate_func_global);
-            };
+            // ディスパッチ用の関数は前の内容をフォールバックに使うので、アダプタを先に設定する
+            for func_id in adapter_installer_func_id
+                .into_iter()
+                .chain(aot_init_func_id)
+            {
+                exprs.push(Instr {
+                    local: None,
+                    kind: InstrKind::Call(InstrCall {
+                        func_id,
+                        args: vec![],
+                    }),
+                });
+            }
 
             exprs.push(Instr {
                 local: None,
@@ -213,6 +262,892 @@ impl JitModule {
         module
     }
 
+    fn generate_stub_func(&self, id: FuncId, func: &Func) -> Func {
+        /*
+        以下のようなスタブを生成
+        func f0_stub(x1, x2) {
+            instantiate_module(f0_module);
+            f0 <- get_global f0_ref
+            f0(x1, x2)
+        }
+        */
+        let mut new_locals = func.locals.clone();
+        let f0_ref_local = new_locals.push_with(|id| Local {
+            id,
+            typ: LocalType::FuncRef,
+        });
+
+        Func {
+            id,
+            args: func.args.clone(),
+            ret_type: func.ret_type,
+            locals: new_locals,
+            bb_entry: BasicBlockId::from(0),
+            bbs: [BasicBlock {
+                id: BasicBlockId::from(0),
+                instrs: vec![
+                    Instr {
+                        local: None,
+                        kind: InstrKind::InstantiateFunc(self.module_id, JitFuncId::from(func.id)),
+                    },
+                    Instr {
+                        local: Some(f0_ref_local),
+                        kind: InstrKind::GlobalGet(self.func_to_globals[func.id]),
+                    },
+                    Instr {
+                        local: None,
+                        kind: InstrKind::Terminator(TerminatorInstr::Exit(ExitInstr::TailCallRef(
+                            InstrCallRef {
+                                func: f0_ref_local,
+                                args: func.args.clone(),
+                                func_type: func.func_type(),
+                            },
+                        ))),
+                    },
+                ],
+            }]
+            .into_iter()
+            .collect(),
+            closure_meta: None,
+        }
+    }
+
+    fn generate_tiered_stub_func(
+        &self,
+        id: FuncId,
+        func: &Func,
+        baseline_func_id: FuncId,
+        tier_up_threshold: usize,
+    ) -> Func {
+        /*
+        以下のようなスタブを生成
+        func f0_stub(x1, x2) {
+            f0_counter <- f0_counter + 1
+            if f0_counter < threshold {
+                f0_baseline(x1, x2)
+            } else {
+                if f0_counter == threshold {
+                    instantiate_module(f0_module);
+                }
+                f0 <- get_global f0_ref
+                // クロージャの場合は以降の呼び出しでスタブを経由しないようにする
+                set_entrypoint_table(closure_entrypoint_table(x1), 0, f0)
+                f0(x1, x2)
+            }
+        }
+        f0_refはf0_moduleのインスタンス化で書き換えられるので、スタブを経由するのはf0_refを介さない呼び出しのみ
+        */
+        let mut locals = func.locals.clone();
+        let mut new_local = |typ: LocalType| locals.push_with(|id| Local { id, typ });
+        let counter_local = new_local(LocalType::Type(Type::Val(ValType::Int)));
+        let one_local = new_local(LocalType::Type(Type::Val(ValType::Int)));
+        let new_counter_local = new_local(LocalType::Type(Type::Val(ValType::Int)));
+        let threshold_local = new_local(LocalType::Type(Type::Val(ValType::Int)));
+        let is_cold_local = new_local(LocalType::Type(Type::Val(ValType::Bool)));
+        let is_first_local = new_local(LocalType::Type(Type::Val(ValType::Bool)));
+        let jit_instrs = self.generate_jit_call_instrs(func, &mut locals);
+
+        let counter_global = self.tier_up_counter_globals[func.id];
+
+        let bb_entry = BasicBlockId::from(0);
+        let bb_baseline = BasicBlockId::from(1);
+        let bb_hot = BasicBlockId::from(2);
+        let bb_instantiate = BasicBlockId::from(3);
+        let bb_jit = BasicBlockId::from(4);
+
+        Func {
+            id,
+            args: func.args.clone(),
+            ret_type: func.ret_type,
+            locals,
+            bb_entry,
+            bbs: [
+                BasicBlock {
+                    id: bb_entry,
+                    instrs: vec![
+                        Instr {
+                            local: Some(counter_local),
+                            kind: InstrKind::GlobalGet(counter_global),
+                        },
+                        Instr {
+                            local: Some(one_local),
+                            kind: InstrKind::Int(1),
+                        },
+                        Instr {
+                            local: Some(new_counter_local),
+                            kind: InstrKind::AddInt(counter_local, one_local),
+                        },
+                        Instr {
+                            local: None,
+                            kind: InstrKind::GlobalSet(counter_global, new_counter_local),
+                        },
+                        Instr {
+                            local: Some(threshold_local),
+                            kind: InstrKind::Int(tier_up_threshold as i64),
+                        },
+                        Instr {
+                            local: Some(is_cold_local),
+                            kind: InstrKind::LtInt(new_counter_local, threshold_local),
+                        },
+                        Instr {
+                            local: None,
+                            kind: InstrKind::Terminator(TerminatorInstr::If(
+                                is_cold_local,
+                                bb_baseline,
+                                bb_hot,
+                            )),
+                        },
+                    ],
+                },
+                BasicBlock {
+                    id: bb_baseline,
+                    instrs: vec![Instr {
+                        local: None,
+                        kind: InstrKind::Terminator(TerminatorInstr::Exit(ExitInstr::TailCall(
+                            InstrCall {
+                                func_id: baseline_func_id,
+                                args: func.args.clone(),
+                            },
+                        ))),
+                    }],
+                },
+                BasicBlock {
+                    id: bb_hot,
+                    instrs: vec![
+                        Instr {
+                            local: Some(is_first_local),
+                            kind: InstrKind::EqInt(new_counter_local, threshold_local),
+                        },
+                        Instr {
+                            local: None,
+                            kind: InstrKind::Terminator(TerminatorInstr::If(
+                                is_first_local,
+                                bb_instantiate,
+                                bb_jit,
+                            )),
+                        },
+                    ],
+                },
+                BasicBlock {
+                    id: bb_instantiate,
+                    instrs: vec![
+                        Instr {
+                            local: None,
+                            kind: InstrKind::InstantiateFunc(
+                                self.module_id,
+                                JitFuncId::from(func.id),
+                            ),
+                        },
+                        Instr {
+                            local: None,
+                            kind: InstrKind::Terminator(TerminatorInstr::Jump(bb_jit)),
+                        },
+                    ],
+                },
+                BasicBlock {
+                    id: bb_jit,
+                    instrs: jit_instrs,
+                },
+            ]
+            .into_iter()
+            .collect(),
+            closure_meta: None,
+        }
+    }
+
+    // f0_refを呼び出す
+    // クロージャの場合は以降の呼び出しでスタブを経由しないようにする
+    fn generate_jit_call_instrs(
+        &self,
+        func: &Func,
+        locals: &mut VecMap<LocalId, Local>,
+    ) -> Vec<Instr> {
+        let f0_ref_local = locals.push_with(|id| Local {
+            id,
+            typ: LocalType::FuncRef,
+        });
+        let mut instrs = vec![Instr {
+            local: Some(f0_ref_local),
+            kind: InstrKind::GlobalGet(self.func_to_globals[func.id]),
+        }];
+        if func.closure_meta.is_some() {
+            let entrypoint_table_local = locals.push_with(|id| Local {
+                id,
+                typ: LocalType::EntrypointTable,
+            });
+            let mut_func_ref_local = locals.push_with(|id| Local {
+                id,
+                typ: LocalType::MutFuncRef,
+            });
+            instrs.extend([
+                Instr {
+                    local: Some(entrypoint_table_local),
+                    kind: InstrKind::ClosureEntrypointTable(func.args[0]),
+                },
+                Instr {
+                    local: Some(mut_func_ref_local),
+                    kind: InstrKind::CreateMutFuncRef(f0_ref_local),
+                },
+                Instr {
+                    local: None,
+                    kind: InstrKind::SetEntrypointTable(
+                        CLOSURE_LAYOUT_DEFAULT_INDEX.0,
+                        entrypoint_table_local,
+                        mut_func_ref_local,
+                    ),
+                },
+            ]);
+        }
+        instrs.push(Instr {
+            local: None,
+            kind: InstrKind::Terminator(TerminatorInstr::Exit(ExitInstr::TailCallRef(
+                InstrCallRef {
+                    func: f0_ref_local,
+                    args: func.args.clone(),
+                    func_type: func.func_type(),
+                },
+            ))),
+        });
+        instrs
+    }
+
+    fn generate_aot_stub_func(
+        &self,
+        id: FuncId,
+        func: &Func,
+        baseline_func_id: Option<FuncId>,
+    ) -> Func {
+        /*
+        以下のようなスタブを生成
+        // プロファイルにある関数
+        func f0_stub(x1, x2) {
+            f0 <- get_global f0_ref
+            f0(x1, x2)
+        }
+        // それ以外
+        func f0_stub(x1, x2) {
+            f0_baseline(x1, x2)
+        }
+        f0_refはエントリーでAOTコンパイルした関数に書き換えられる
+        */
+        let mut locals = func.locals.clone();
+        let instrs = if let Some(baseline_func_id) = baseline_func_id {
+            vec![Instr {
+                local: None,
+                kind: InstrKind::Terminator(TerminatorInstr::Exit(ExitInstr::TailCall(
+                    InstrCall {
+                        func_id: baseline_func_id,
+                        args: func.args.clone(),
+                    },
+                ))),
+            }]
+        } else {
+            self.generate_jit_call_instrs(func, &mut locals)
+        };
+
+        Func {
+            id,
+            args: func.args.clone(),
+            ret_type: func.ret_type,
+            locals,
+            bb_entry: BasicBlockId::from(0),
+            bbs: [BasicBlock {
+                id: BasicBlockId::from(0),
+                instrs,
+            }]
+            .into_iter()
+            .collect(),
+            closure_meta: None,
+        }
+    }
+
+    // ir_generatorが生成した関数をそのまま使うが、関数の参照はスタブに置き換える
+    // クロージャのエントリポイントテーブルは0番目をスタブ、それ以外をJITのスタブにする
+    fn generate_baseline_func(
+        &self,
+        id: FuncId,
+        func: &Func,
+        stub_func_ids: &FxHashMap<FuncId, FuncId>,
+        jit_ctx: &JitCtx,
+    ) -> Func {
+        let mut func = func.clone();
+        func.id = id;
+        for bb in func.bbs.values_mut() {
+            let mut instrs = Vec::new();
+            for mut instr in std::mem::take(&mut bb.instrs) {
+                for func_id in instr.kind.func_ids_mut() {
+                    *func_id = stub_func_ids[func_id];
+                }
+                match &mut instr.kind {
+                    InstrKind::Terminator(terminator) => {
+                        for func_id in terminator.func_ids_mut() {
+                            *func_id = stub_func_ids[func_id];
+                        }
+                    }
+                    InstrKind::EntrypointTable(entrypoints) => {
+                        for index in entrypoints.len()..CLOSURE_LAYOUT_MAX_SIZE {
+                            let stub = func.locals.push_with(|id| Local {
+                                id,
+                                typ: LocalType::MutFuncRef,
+                            });
+                            instrs.push(Instr {
+                                local: Some(stub),
+                                kind: InstrKind::GlobalGet(
+                                    jit_ctx.stub_global(ClosureIndex(index)).id,
+                                ),
+                            });
+                            entrypoints.push(stub);
+                        }
+                    }
+                    _ => {}
+                }
+                instrs.push(instr);
+            }
+            bb.instrs = instrs;
+        }
+        func
+    }
+
+    fn add_baseline_metas(
+        &self,
+        baseline_func_ids: &FxHashMap<FuncId, FuncId>,
+        module: &mut Module,
+    ) {
+        let meta = &self.module.meta;
+        for (&func_id, func_meta) in meta.func_metas.iter() {
+            module
+                .meta
+                .func_metas
+                .insert(baseline_func_ids[&func_id], func_meta.clone());
+        }
+        for (&(func_id, local_id), var_meta) in meta.local_metas.iter() {
+            module
+                .meta
+                .local_metas
+                .insert((baseline_func_ids[&func_id], local_id), var_meta.clone());
+        }
+        for (&global_id, var_meta) in meta.global_metas.iter() {
+            module.meta.global_metas.insert(global_id, var_meta.clone());
+        }
+        for (&source_loc_id, source_loc) in meta.source_locs.iter() {
+            module
+                .meta
+                .source_locs
+                .insert(source_loc_id, source_loc.clone());
+        }
+    }
+
+    // プロファイルにある特殊化と、そこから到達する特殊化を全て生成してmoduleに取り込む
+    // 戻り値は取り込んだモジュールの初期化とクロージャの呼び分けの設定を行う関数
+    fn generate_aot_funcs(
+        &mut self,
+        module: &mut Module,
+        global_manager: &mut GlobalManager,
+        jit_ctx: &mut JitCtx,
+    ) -> FuncId {
+        let mut todo = jit_ctx
+            .profiled_func_layouts(self.module_id)
+            .into_iter()
+            .filter(|&(func_id, func_index)| {
+                self.module.funcs.get(func_id).is_some_and(|func| {
+                    func_index == CLOSURE_LAYOUT_DEFAULT_INDEX
+                        || (func.closure_meta.is_some()
+                            && jit_ctx
+                                .closure_global_layout()
+                                .indices()
+                                .any(|index| index == func_index))
+                })
+            })
+            .map(|(func_id, func_index)| (func_id, ENV_LAYOUT_DEFAULT_INDEX, func_index))
+            .rev()
+            .collect::<Vec<_>>();
+        let mut queued = todo.iter().copied().collect::<FxHashSet<_>>();
+
+        // 取り込んだモジュールのエントリーと、instantiate_func_globalを移すグローバル
+        let mut entries = Vec::new();
+        // クロージャのfunc_indexごとの(関数, 環境のindex, 関数本体が入ったグローバル)
+        let mut closure_funcs = FxHashMap::<ClosureIndex, Vec<_>>::default();
+        while let Some((func_id, env_index, func_index)) = todo.pop() {
+            let (func_module, _) =
+                self.instantiate_func(global_manager, func_id, env_index, func_index, jit_ctx);
+            let entry = module.merge(func_module);
+            if env_index == ENV_LAYOUT_DEFAULT_INDEX && func_index == CLOSURE_LAYOUT_DEFAULT_INDEX {
+                entries.push((entry, None));
+            } else {
+                // instantiate_func_globalは次の関数のエントリーで上書きされるので移しておく
+                let body_global = global_manager.gen_global(LocalType::FuncRef);
+                entries.push((entry, Some(body_global.id)));
+                closure_funcs.entry(func_index).or_default().push((
+                    func_id,
+                    env_index,
+                    body_global.id,
+                ));
+            }
+
+            while let Some((bb_id, index)) = self
+                .jit_funcs
+                .get_mut(&func_id)
+                .unwrap()
+                .jit_specialized_env_funcs
+                .get_mut(&env_index)
+                .unwrap()
+                .jit_specialized_arg_funcs
+                .get_mut(&func_index)
+                .unwrap()
+                .pop_aot_pending_bb()
+            {
+                let (bb_module, _) = self.instantiate_bb(
+                    func_id,
+                    env_index,
+                    func_index,
+                    bb_id,
+                    index,
+                    global_manager,
+                    jit_ctx,
+                );
+                entries.push((module.merge(bb_module), None));
+            }
+
+            // 生成したコードが新しい環境の特殊化でクロージャを作るなら、その関数も生成する
+            let mut new_env_funcs = self
+                .env_index_managers
+                .iter()
+                .flat_map(|(&func_id, env_index_manager)| {
+                    env_index_manager
+                        .indices()
+                        .map(move |env_index| (func_id, env_index, CLOSURE_LAYOUT_DEFAULT_INDEX))
+                })
+                .filter(|key| key.1 != ENV_LAYOUT_DEFAULT_INDEX && !queued.contains(key))
+                .collect::<Vec<_>>();
+            new_env_funcs.sort_by_key(|&(func_id, env_index, _)| {
+                std::cmp::Reverse((usize::from(func_id), env_index.0))
+            });
+            queued.extend(new_env_funcs.iter().copied());
+            todo.extend(new_env_funcs);
+        }
+
+        // デフォルトの環境のクロージャはfunc_to_globalsの関数(スタブかAOTコンパイルした関数)を呼ぶ
+        for func in self.module.funcs.values() {
+            if func.closure_meta.is_some() {
+                closure_funcs
+                    .entry(CLOSURE_LAYOUT_DEFAULT_INDEX)
+                    .or_default()
+                    .push((
+                        func.id,
+                        ENV_LAYOUT_DEFAULT_INDEX,
+                        self.func_to_globals[func.id],
+                    ));
+            }
+        }
+
+        let mut dispatchers = Vec::new();
+        for func_index in jit_ctx
+            .closure_global_layout()
+            .indices()
+            .collect::<Vec<_>>()
+        {
+            let Some(funcs) = closure_funcs.get(&func_index) else {
+                continue;
+            };
+            let fallback_global = global_manager.gen_global(LocalType::FuncRef);
+            let dispatcher_func_id = self.generate_aot_dispatcher_func(
+                module,
+                func_index,
+                jit_ctx.closure_global_layout().arg_types(func_index),
+                funcs,
+                fallback_global.id,
+            );
+            dispatchers.push((func_index, dispatcher_func_id, fallback_global.id));
+        }
+
+        /*
+        以下のような初期化関数を生成
+        func aot_init() {
+            f0_entry()
+            set_global f1_body (get_global instantiate_func_global)
+            ...
+            set_global fallback1 (deref_mut_func_ref stub1)
+            set_mut_func_ref stub1 dispatcher1
+            ...
+        }
+        */
+        let mut locals = VecMap::new();
+        let mut instrs = Vec::new();
+        for (entry, body_global) in entries {
+            instrs.push(Instr {
+                local: None,
+                kind: InstrKind::Call(InstrCall {
+                    func_id: entry,
+                    args: vec![],
+                }),
+            });
+            if let Some(body_global) = body_global {
+                let func_ref_local = locals.push_with(|id| Local {
+                    id,
+                    typ: LocalType::FuncRef,
+                });
+                instrs.extend([
+                    Instr {
+                        local: Some(func_ref_local),
+                        kind: InstrKind::GlobalGet(jit_ctx.instantiate_func_global().id),
+                    },
+                    Instr {
+                        local: None,
+                        kind: InstrKind::GlobalSet(body_global, func_ref_local),
+                    },
+                ]);
+            }
+        }
+        for (func_index, dispatcher_func_id, fallback_global) in dispatchers {
+            let stub_local = locals.push_with(|id| Local {
+                id,
+                typ: LocalType::MutFuncRef,
+            });
+            let fallback_local = locals.push_with(|id| Local {
+                id,
+                typ: LocalType::FuncRef,
+            });
+            let dispatcher_local = locals.push_with(|id| Local {
+                id,
+                typ: LocalType::FuncRef,
+            });
+            instrs.extend([
+                Instr {
+                    local: Some(stub_local),
+                    kind: InstrKind::GlobalGet(jit_ctx.stub_global(func_index).id),
+                },
+                Instr {
+                    local: Some(fallback_local),
+                    kind: InstrKind::DerefMutFuncRef(stub_local),
+                },
+                Instr {
+                    local: None,
+                    kind: InstrKind::GlobalSet(fallback_global, fallback_local),
+                },
+                Instr {
+                    local: Some(dispatcher_local),
+                    kind: InstrKind::FuncRef(dispatcher_func_id),
+                },
+                Instr {
+                    local: None,
+                    kind: InstrKind::SetMutFuncRef(stub_local, dispatcher_local),
+                },
+            ]);
+        }
+
+        push_nil_func(module, locals, instrs)
+    }
+
+    fn generate_aot_dispatcher_func(
+        &self,
+        module: &mut Module,
+        func_index: ClosureIndex,
+        arg_types: &ClosureArgs,
+        funcs: &[(FuncId, EnvIndex, GlobalId)],
+        fallback_global: GlobalId,
+    ) -> FuncId {
+        /*
+        以下のような関数を生成
+        func dispatcher1(closure, x1, x2) {
+            if closure.module_id == m && closure.func_id == f0 && closure.env_index == e {
+                set_entrypoint_table(closure_entrypoint_table(closure), 1, f0_body)
+                closure_entrypoint_table(closure)[1](closure, x1, x2)
+            } else if ... {
+                ...
+            } else {
+                fallback1(closure, x1, x2)
+            }
+        }
+        fallback1は他のモジュールのディスパッチ用の関数かアダプタ
+        */
+        let mut locals = VecMap::new();
+        let (closure_local, arg_locals) = closure_func_args(&mut locals, arg_types);
+        let call_arg_types = arg_locals
+            .iter()
+            .map(|&local| locals[local].typ)
+            .collect::<Vec<_>>();
+        let mut new_local = |typ: LocalType| locals.push_with(|id| Local { id, typ });
+        let int_type = LocalType::Type(Type::Val(ValType::Int));
+        let bool_type = LocalType::Type(Type::Val(ValType::Bool));
+
+        let module_id_local = new_local(int_type);
+        let func_id_local = new_local(int_type);
+        let env_index_local = new_local(int_type);
+
+        let mut bbs = VecMap::new();
+        let bb_id = |i: usize| BasicBlockId::from(i);
+        let bb_fallback = bb_id(1 + funcs.len() * 2);
+        bbs.insert_node(BasicBlock {
+            id: bb_id(0),
+            instrs: vec![
+                Instr {
+                    local: Some(module_id_local),
+                    kind: InstrKind::ClosureModuleId(closure_local),
+                },
+                Instr {
+                    local: Some(func_id_local),
+                    kind: InstrKind::ClosureFuncId(closure_local),
+                },
+                Instr {
+                    local: Some(env_index_local),
+                    kind: InstrKind::ClosureEnvIndex(closure_local),
+                },
+                Instr {
+                    local: None,
+                    kind: InstrKind::Terminator(TerminatorInstr::Jump(bb_id(1))),
+                },
+            ],
+        });
+
+        for (i, &(func_id, env_index, body_global)) in funcs.iter().enumerate() {
+            let bb_check = bb_id(1 + i * 2);
+            let bb_hit = bb_id(2 + i * 2);
+            let bb_next = bb_id(3 + i * 2);
+
+            let mut instrs = Vec::new();
+            let mut conds = Vec::new();
+            for (actual_local, expected) in [
+                (module_id_local, usize::from(self.module_id)),
+                (func_id_local, usize::from(func_id)),
+                (env_index_local, env_index.0),
+            ] {
+                let expected_local = new_local(int_type);
+                let cond_local = new_local(bool_type);
+                instrs.extend([
+                    Instr {
+                        local: Some(expected_local),
+                        kind: InstrKind::Int(expected as i64),
+                    },
+                    Instr {
+                        local: Some(cond_local),
+                        kind: InstrKind::EqInt(actual_local, expected_local),
+                    },
+                ]);
+                conds.push(cond_local);
+            }
+            let cond_local = conds
+                .into_iter()
+                .reduce(|cond1, cond2| {
+                    let cond_local = new_local(bool_type);
+                    instrs.push(Instr {
+                        local: Some(cond_local),
+                        kind: InstrKind::And(cond1, cond2),
+                    });
+                    cond_local
+                })
+                .unwrap();
+            instrs.push(Instr {
+                local: None,
+                kind: InstrKind::Terminator(TerminatorInstr::If(cond_local, bb_hit, bb_next)),
+            });
+            bbs.insert_node(BasicBlock {
+                id: bb_check,
+                instrs,
+            });
+
+            let func_ref_local = new_local(LocalType::FuncRef);
+            let mut_func_ref_local = new_local(LocalType::MutFuncRef);
+            let entrypoint_table_local = new_local(LocalType::EntrypointTable);
+            bbs.insert_node(BasicBlock {
+                id: bb_hit,
+                instrs: vec![
+                    Instr {
+                        local: Some(func_ref_local),
+                        kind: InstrKind::GlobalGet(body_global),
+                    },
+                    Instr {
+                        local: Some(mut_func_ref_local),
+                        kind: InstrKind::CreateMutFuncRef(func_ref_local),
+                    },
+                    Instr {
+                        local: Some(entrypoint_table_local),
+                        kind: InstrKind::ClosureEntrypointTable(closure_local),
+                    },
+                    Instr {
+                        local: None,
+                        kind: InstrKind::SetEntrypointTable(
+                            func_index.0,
+                            entrypoint_table_local,
+                            mut_func_ref_local,
+                        ),
+                    },
+                    Instr {
+                        local: None,
+                        kind: InstrKind::Terminator(TerminatorInstr::Exit(
+                            ExitInstr::TailCallClosure(InstrCallClosure {
+                                closure: closure_local,
+                                args: arg_locals.clone(),
+                                arg_types: call_arg_types.clone(),
+                                func_index: func_index.0,
+                            }),
+                        )),
+                    },
+                ],
+            });
+        }
+
+        let fallback_local = new_local(LocalType::FuncRef);
+        bbs.insert_node(BasicBlock {
+            id: bb_fallback,
+            instrs: vec![
+                Instr {
+                    local: Some(fallback_local),
+                    kind: InstrKind::GlobalGet(fallback_global),
+                },
+                Instr {
+                    local: None,
+                    kind: InstrKind::Terminator(TerminatorInstr::Exit(ExitInstr::TailCallRef(
+                        InstrCallRef {
+                            func: fallback_local,
+                            args: std::iter::once(closure_local)
+                                .chain(arg_locals.iter().copied())
+                                .collect(),
+                            func_type: FuncType {
+                                args: std::iter::once(ValType::Closure.into())
+                                    .chain(call_arg_types.iter().copied())
+                                    .collect(),
+                                ret: LocalType::Type(Type::Obj),
+                            },
+                        },
+                    ))),
+                },
+            ],
+        });
+
+        let args = std::iter::once(closure_local).chain(arg_locals).collect();
+        module.funcs.push_with(|id| Func {
+            id,
+            args,
+            ret_type: LocalType::Type(Type::Obj),
+            locals,
+            bb_entry: bb_id(0),
+            bbs,
+            closure_meta: None,
+        })
+    }
+
+    // AOTコンパイルしていないクロージャをfunc_index=0で呼び出すアダプタを設定する関数を生成する
+    // 各モジュールのディスパッチ用の関数が一致しなかった場合に最後に呼ばれる
+    fn generate_aot_adapters(module: &mut Module, jit_ctx: &mut JitCtx) -> FuncId {
+        /*
+        以下のようなアダプタを生成
+        func adapter1(closure, x1, x2) {
+            closure_entrypoint_table(closure)[0](closure, variadic_args(to_obj(x1), x2))
+        }
+        */
+        let mut locals = VecMap::new();
+        let mut instrs = Vec::new();
+        for func_index in jit_ctx
+            .closure_global_layout()
+            .indices()
+            .collect::<Vec<_>>()
+        {
+            let arg_types = jit_ctx.closure_global_layout().arg_types(func_index);
+            if arg_types == &ClosureArgs::Variadic {
+                continue;
+            }
+
+            let mut adapter_locals = VecMap::new();
+            let (closure_local, arg_locals) = closure_func_args(&mut adapter_locals, arg_types);
+            let mut adapter_instrs = Vec::new();
+            let mut obj_locals = Vec::new();
+            for arg_local in arg_locals.iter().copied() {
+                match adapter_locals[arg_local].typ {
+                    LocalType::Type(Type::Val(val_type)) => {
+                        let obj_local = adapter_locals.push_with(|id| Local {
+                            id,
+                            typ: LocalType::Type(Type::Obj),
+                        });
+                        adapter_instrs.push(Instr {
+                            local: Some(obj_local),
+                            kind: InstrKind::ToObj(val_type, arg_local),
+                        });
+                        obj_locals.push(obj_local);
+                    }
+                    _ => obj_locals.push(arg_local),
+                }
+            }
+            let variadic_args_local = adapter_locals.push_with(|id| Local {
+                id,
+                typ: LocalType::VariadicArgs,
+            });
+            adapter_instrs.extend([
+                Instr {
+                    local: Some(variadic_args_local),
+                    kind: InstrKind::VariadicArgs(obj_locals),
+                },
+                Instr {
+                    local: None,
+                    kind: InstrKind::Terminator(TerminatorInstr::Exit(ExitInstr::TailCallClosure(
+                        InstrCallClosure {
+                            closure: closure_local,
+                            args: vec![variadic_args_local],
+                            arg_types: vec![LocalType::VariadicArgs],
+                            func_index: CLOSURE_LAYOUT_DEFAULT_INDEX.0,
+                        },
+                    ))),
+                },
+            ]);
+            let args = std::iter::once(closure_local).chain(arg_locals).collect();
+            let adapter_func_id = module.funcs.push_with(|id| Func {
+                id,
+                args,
+                ret_type: LocalType::Type(Type::Obj),
+                locals: adapter_locals,
+                bb_entry: BasicBlockId::from(0),
+                bbs: [BasicBlock {
+                    id: BasicBlockId::from(0),
+                    instrs: adapter_instrs,
+                }]
+                .into_iter()
+                .collect(),
+                closure_meta: None,
+            });
+
+            let stub_local = locals.push_with(|id| Local {
+                id,
+                typ: LocalType::MutFuncRef,
+            });
+            let adapter_local = locals.push_with(|id| Local {
+                id,
+                typ: LocalType::FuncRef,
+            });
+            instrs.extend([
+                Instr {
+                    local: Some(stub_local),
+                    kind: InstrKind::GlobalGet(jit_ctx.stub_global(func_index).id),
+                },
+                Instr {
+                    local: Some(adapter_local),
+                    kind: InstrKind::FuncRef(adapter_func_id),
+                },
+                Instr {
+                    local: None,
+                    kind: InstrKind::SetMutFuncRef(stub_local, adapter_local),
+                },
+            ]);
+        }
+
+        push_nil_func(module, locals, instrs)
+    }
+
+    pub fn func_profiles(&self) -> impl Iterator<Item = FuncProfile> + '_ {
+        self.instantiated_funcs.iter().map(|&func_id| FuncProfile {
+            module_id: usize::from(self.module_id),
+            func_id: usize::from(func_id),
+        })
+    }
+
+    pub fn bb_profiles(&self) -> impl Iterator<Item = BBProfile> + '_ {
+        self.jit_funcs.values().flat_map(|jit_func| {
+            jit_func
+                .jit_specialized_env_funcs
+                .values()
+                .flat_map(|jit_env_func| jit_env_func.jit_specialized_arg_funcs.values())
+                .flat_map(|jit_arg_func| jit_arg_func.bb_profiles())
+        })
+    }
+
     pub fn instantiate_func(
         &mut self,
         global_manager: &mut GlobalManager,
@@ -221,6 +1156,7 @@ impl JitModule {
         func_index: ClosureIndex,
         jit_ctx: &mut JitCtx,
     ) -> (Module, Vec<super::event::JitLogEvent>) {
+        self.instantiated_funcs.insert(func_id);
         let jit_func_entry = self.jit_funcs.get_mut(&func_id).unwrap();
         let jit_env_func = jit_func_entry
             .jit_specialized_env_funcs
@@ -248,7 +1184,7 @@ impl JitModule {
             .jit_specialized_arg_funcs
             .insert(func_index, jit_func);
 
-        jit_env_func
+        let (mut module, jit_events) = jit_env_func
             .jit_specialized_arg_funcs
             .get_mut(&func_index)
             .unwrap()
@@ -258,7 +1194,9 @@ impl JitModule {
                 global_manager,
                 &mut self.env_index_managers,
                 jit_ctx,
-            )
+            );
+        self.add_func_metas(func_id, &mut module);
+        (module, jit_events)
     }
 
     pub fn instantiate_bb(
@@ -280,7 +1218,7 @@ impl JitModule {
             .get_mut(&func_index)
             .unwrap();
 
-        jit_func.generate_bb_module(
+        let (mut module, jit_events) = jit_func.generate_bb_module(
             &self.func_to_globals,
             &self.func_types,
             bb_id,
@@ -289,7 +1227,30 @@ impl JitModule {
             &mut self.env_index_managers,
             jit_ctx,
             false,
-        )
+        );
+        self.add_func_metas(func_id, &mut module);
+        (module, jit_events)
+    }
+
+    pub fn increment_bb_layout_counter(
+        &mut self,
+        func_id: FuncId,
+        env_index: EnvIndex,
+        func_index: ClosureIndex,
+        bb_id: BasicBlockId,
+        index: BBIndex,
+        kind: BBLayoutCounterKind,
+    ) {
+        self.jit_funcs
+            .get_mut(&func_id)
+            .unwrap()
+            .jit_specialized_env_funcs
+            .get_mut(&env_index)
+            .unwrap()
+            .jit_specialized_arg_funcs
+            .get_mut(&func_index)
+            .unwrap()
+            .increment_bb_layout_counter(bb_id, index, kind);
     }
 
     pub fn increment_branch_counter(
@@ -312,16 +1273,98 @@ impl JitModule {
             .jit_specialized_arg_funcs
             .get_mut(&func_index)
             .unwrap();
-        jit_func.increment_branch_counter(
-            &self.func_to_globals,
-            &self.func_types,
-            global_manager,
-            &mut self.env_index_managers,
-            jit_ctx,
-            bb_id,
-            kind,
-            source_bb_id,
-            source_index.0,
-        )
+        jit_func
+            .increment_branch_counter(
+                &self.func_to_globals,
+                &self.func_types,
+                global_manager,
+                &mut self.env_index_managers,
+                jit_ctx,
+                bb_id,
+                kind,
+                source_bb_id,
+                source_index.0,
+            )
+            .map(|(mut module, jit_events)| {
+                self.add_func_metas(func_id, &mut module);
+                (module, jit_events)
+            })
     }
+
+    // JITで生成したモジュールの関数は全て元の関数の名前を引き継ぐ
+    fn add_func_metas(&self, func_id: FuncId, module: &mut Module) {
+        let Some(func_meta) = self.module.meta.func_metas.get(&func_id) else {
+            return;
+        };
+        for id in module.funcs.keys() {
+            module
+                .meta
+                .func_metas
+                .entry(id)
+                .or_insert_with(|| func_meta.clone());
+        }
+    }
+}
+
+// クロージャのfunc_indexのレイアウトに合わせた引数
+fn closure_func_args(
+    locals: &mut VecMap<LocalId, Local>,
+    arg_types: &ClosureArgs,
+) -> (LocalId, Vec<LocalId>) {
+    let closure_local = locals.push_with(|id| Local {
+        id,
+        typ: ValType::Closure.into(),
+    });
+    let arg_locals = match arg_types {
+        ClosureArgs::Specified(arg_types) => arg_types
+            .iter()
+            .map(|&typ| {
+                locals.push_with(|id| Local {
+                    id,
+                    typ: LocalType::Type(typ),
+                })
+            })
+            .collect(),
+        ClosureArgs::Variadic => vec![locals.push_with(|id| Local {
+            id,
+            typ: LocalType::VariadicArgs,
+        })],
+    };
+    (closure_local, arg_locals)
+}
+
+// instrsを実行してnilを返す関数を追加する
+fn push_nil_func(
+    module: &mut Module,
+    mut locals: VecMap<LocalId, Local>,
+    mut instrs: Vec<Instr>,
+) -> FuncId {
+    let nil_local = locals.push_with(|id| Local {
+        id,
+        typ: ValType::Nil.into(),
+    });
+    instrs.extend([
+        Instr {
+            local: Some(nil_local),
+            kind: InstrKind::Nil,
+        },
+        Instr {
+            local: None,
+            kind: InstrKind::Terminator(TerminatorInstr::Exit(ExitInstr::Return(nil_local))),
+        },
+    ]);
+    module.funcs.push_with(|id| Func {
+        id,
+        args: vec![],
+        ret_type: ValType::Nil.into(),
+        locals,
+        bb_entry: BasicBlockId::from(0),
+        bbs: [BasicBlock {
+            id: BasicBlockId::from(0),
+            instrs,
+        }]
+        .into_iter()
+        .collect(),
+        closure_meta: None,
+    })
 }
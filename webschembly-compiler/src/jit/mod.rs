@@ -17,6 +17,7 @@ pub mod env_index_manager;
 pub mod event;
 pub mod index_flag;
 mod jit_func;
+pub mod profile;
 
 #[derive(Debug)]
 pub struct Jit {
@@ -25,10 +26,10 @@ pub struct Jit {
 }
 
 impl Jit {
-    pub fn new(config: JitConfig) -> Self {
+    pub fn new(config: JitConfig, profile: profile::JitProfile) -> Self {
         Self {
             jit_module: VecMap::new(),
-            ctx: JitCtx::new(config),
+            ctx: JitCtx::new(config, profile),
         }
     }
 
@@ -36,11 +37,45 @@ impl Jit {
         self.ctx.config()
     }
 
+    // 実行中に学習した情報を次回の実行のために書き出す
+    pub fn profile(&mut self) -> profile::JitProfile {
+        let mut funcs = self
+            .jit_module
+            .values()
+            .flat_map(|jit_module| jit_module.func_profiles())
+            .collect::<Vec<_>>();
+        funcs.sort_by_key(|f| (f.module_id, f.func_id));
+        let mut bbs = self
+            .jit_module
+            .values()
+            .flat_map(|jit_module| jit_module.bb_profiles())
+            .collect::<Vec<_>>();
+        bbs.sort_by_key(|bb| {
+            (
+                bb.key.module_id,
+                bb.key.func_id,
+                bb.key.env_index,
+                bb.key.func_index,
+                bb.key.bb_id,
+            )
+        });
+        profile::JitProfile {
+            version: profile::JIT_PROFILE_VERSION,
+            module_hashes: self.ctx.module_hashes().to_vec(),
+            funcs,
+            closure_layouts: self.ctx.closure_global_layout().specified_layouts(),
+            bbs,
+        }
+    }
+
+    // source_hashはモジュールのソースのハッシュで、プロファイルが同じソースのものか確かめるのに使う
     pub fn register_module(
         &mut self,
         global_manager: &mut GlobalManager,
         module: Module,
+        source_hash: u64,
     ) -> Module {
+        self.ctx.register_module_hash(source_hash);
         let module_id = self
             .jit_module
             .push_with(|id| JitModule::new(global_manager, id, module, &mut self.ctx));
@@ -85,6 +120,20 @@ impl Jit {
         )
     }
 
+    pub fn increment_bb_layout_counter(
+        &mut self,
+        module_id: JitModuleId,
+        func_id: FuncId,
+        env_index: EnvIndex,
+        func_index: ClosureIndex,
+        bb_id: BasicBlockId,
+        index: BBIndex,
+        kind: BBLayoutCounterKind,
+    ) {
+        self.jit_module[module_id]
+            .increment_bb_layout_counter(func_id, env_index, func_index, bb_id, index, kind);
+    }
+
     pub fn increment_branch_counter(
         &mut self,
         global_manager: &mut GlobalManager,
@@ -0,0 +1,65 @@
+use serde::{Deserialize, Serialize};
+use webschembly_compiler_ir::*;
+
+// プロファイルの形式やIRの生成方法を変えたら上げる
+pub const JIT_PROFILE_VERSION: u32 = 2;
+
+// JITが実行中に学習した情報
+// 次回の実行時に読み込むと、ホットな関数やBBを最初から特殊化できる
+// モジュールや関数のIDで対応付けるので、同じプログラムに対してのみ意味を持つ
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JitProfile {
+    // バージョンが異なるプロファイルは使わない。古い形式のプロファイルは0になる
+    #[serde(default)]
+    pub version: u32,
+    // モジュールごとのソースのハッシュ。ソースが変わったモジュール以降のプロファイルは使わない
+    #[serde(default)]
+    pub module_hashes: Vec<u64>,
+    // JITコンパイルされた関数
+    pub funcs: Vec<FuncProfile>,
+    // ClosureGlobalLayoutの引数の型。index=1から順に並ぶ
+    pub closure_layouts: Vec<Vec<Type>>,
+    pub bbs: Vec<BBProfile>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct FuncProfile {
+    pub module_id: usize,
+    pub func_id: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct BBProfileKey {
+    pub module_id: usize,
+    pub func_id: usize,
+    pub env_index: usize,
+    pub func_index: usize,
+    pub bb_id: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BBProfile {
+    #[serde(flatten)]
+    pub key: BBProfileKey,
+    pub then_count: usize,
+    pub else_count: usize,
+    // 分岐の特殊化を行ったか
+    pub fused: bool,
+    pub megamorphic: bool,
+    pub layouts: Vec<BBLayoutProfile>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BBLayoutProfile {
+    // (TypeParamId, 型)の組
+    pub type_args: Vec<(usize, ValType)>,
+    pub hits: usize,
+    #[serde(default)]
+    pub guard_failures: usize,
+}
+
+impl JitProfile {
+    pub fn is_compatible(&self) -> bool {
+        self.version == JIT_PROFILE_VERSION
+    }
+}
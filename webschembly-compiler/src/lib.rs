@@ -16,10 +16,5 @@ pub mod fxbihashmap;
 pub mod ir_generator;
 pub mod ir_processor;
 pub mod jit;
-pub mod lexer;
-pub mod parser_combinator;
-pub mod sexpr_parser;
 pub mod stdlib;
-pub mod token;
-pub mod tokens;
 pub mod wasm_generator;
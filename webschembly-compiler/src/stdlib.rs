@@ -1,11 +1,17 @@
 use strum::IntoEnumIterator;
 
-use crate::ir_generator::BuiltinConversionRule;
+use crate::ir_generator::{BuiltinConversionRule, BuiltinVariadicRule};
 use webschembly_compiler_ast::Builtin;
 
-pub fn generate_stdlib() -> String {
+pub fn generate_stdlib(enable_continuation: bool) -> String {
     let mut result = String::new();
     result.push_str(include_str!("stdlib.scm"));
+    // guardの脱出方法は継続が有効かどうかで変わる
+    if enable_continuation {
+        result.push_str(include_str!("stdlib_continuation.scm"));
+    } else {
+        result.push_str(include_str!("stdlib_no_continuation.scm"));
+    }
     for builtin in Builtin::iter() {
         result.push_str(&generate_builtin(builtin));
     }
@@ -13,16 +19,65 @@ pub fn generate_stdlib() -> String {
 }
 
 fn generate_builtin(builtin: Builtin) -> String {
-    let rule = BuiltinConversionRule::from_builtin(builtin)[0]; // TODO: オーバーロード対応
-    let args = (0..rule.arg_count())
-        .map(|i| format!("x{}", i))
-        .collect::<Vec<_>>()
-        .join(" ");
-    format!(
-        "(define ({} {}) ({} {}))\n",
-        builtin.name(),
-        args,
-        builtin.name(),
-        args
-    )
+    let name = builtin.name();
+    // リテラルの引数を取る組み込み関数は、第一級の値としては使えない
+    if BuiltinConversionRule::takes_record_literals(builtin) {
+        return String::new();
+    }
+    if let Some(variadic) = BuiltinVariadicRule::from_builtin(builtin) {
+        return generate_variadic_builtin(name, variadic);
+    }
+
+    let mut arg_counts = BuiltinConversionRule::from_builtin(builtin)
+        .into_iter()
+        .map(|rule| rule.arg_count())
+        .collect::<Vec<_>>();
+    arg_counts.sort();
+    arg_counts.dedup();
+    let min_arg_count = arg_counts[0];
+    let max_arg_count = *arg_counts.last().unwrap();
+    let args = |n: usize| (0..n).map(|i| format!(" x{i}")).collect::<String>();
+    if min_arg_count == max_arg_count {
+        let args = args(min_arg_count);
+        return format!("(define ({name}{args}) ({name}{args}))\n");
+    }
+
+    // 残りの引数を1つずつ取り出し、引数の数に合う呼び出しに分岐する
+    let mismatch = format!("(error \"{name}: args count mismatch\")");
+    let max_args = args(max_arg_count);
+    let mut body = format!("(if (null? rest) ({name}{max_args}) {mismatch})");
+    for n in (min_arg_count..max_arg_count).rev() {
+        let call = if arg_counts.contains(&n) {
+            let args = args(n);
+            format!("({name}{args})")
+        } else {
+            mismatch.clone()
+        };
+        body =
+            format!("(if (null? rest) {call} (let ((x{n} (car rest)) (rest (cdr rest))) {body}))");
+    }
+    let min_args = args(min_arg_count);
+    format!("(define ({name}{min_args} . rest) {body})\n")
+}
+
+// 呼び出し位置での展開と同じく、二項演算の呼び出しに分解する
+fn generate_variadic_builtin(name: &str, variadic: BuiltinVariadicRule) -> String {
+    let fold = format!(
+        "(if (null? xs) ({name} x) \
+         (let loop ((acc x) (xs xs)) \
+         (if (null? xs) acc (loop ({name} acc (car xs)) (cdr xs)))))"
+    );
+    match variadic {
+        BuiltinVariadicRule::Fold { .. } => format!(
+            "(define ({name} . xs) (if (null? xs) ({name}) (let ((x (car xs)) (xs (cdr xs))) {fold})))\n"
+        ),
+        BuiltinVariadicRule::FoldInverse { .. } | BuiltinVariadicRule::FoldSelf => {
+            format!("(define ({name} x . xs) {fold})\n")
+        }
+        BuiltinVariadicRule::Chain => format!(
+            "(define ({name} x y . xs) \
+             (let loop ((x x) (y y) (xs xs)) \
+             (if ({name} x y) (if (null? xs) #t (loop y (car xs) (cdr xs))) #f)))\n"
+        ),
+    }
 }
@@ -1,15 +1,21 @@
-(define (newline)
-  (write-char #\newline))
-(define (write x)
+(define (newline . port)
+  (if (null? port)
+    (write-char #\newline)
+    (write-char #\newline (car port))))
+(define (write x . port)
+  (if (null? port)
+    (%write x)
+    (%with-output-port (car port) (lambda () (%write x)))))
+(define (%write x)
   (define (write-vector-inner v i)
-    (write (vector-ref v i))
+    (%write (vector-ref v i))
     (if (< (+ i 1) (vector-length v))
       (begin
         (write-char #\space)
         (write-vector-inner v (+ i 1)))
       #f))
   (define (write-uvector-inner v i)
-    (write (uvector-ref v i))
+    (%write (uvector-ref v i))
     (if (< (+ i 1) (uvector-length v))
       (begin
         (write-char #\space)
@@ -18,9 +24,9 @@
   (if (pair? x)
     (begin
       (write-char #\openparen)
-      (write (car x))
+      (%write (car x))
       (display " . ")
-      (write (cdr x))
+      (%write (cdr x))
       (write-char #\closeparen))
     (if (eq? x #t)
       (display "#t")
@@ -44,6 +50,21 @@
                     (write-char x))
                   (if (procedure? x)
                     (display "<procedure>")
+                    (if (error-object? x)
+                      (display "<error-object>")
+                    (if (port? x)
+                      (display "<port>")
+                    (if (hash-table? x)
+                      (display "<hash-table>")
+                    (if (promise? x)
+                      (display "<promise>")
+                    (if (%record? x)
+                      (begin
+                        (display "<record ")
+                        (%write (%record-type-name (%record-type x)))
+                        (display ">"))
+                    (if (eof-object? x)
+                      (display "<eof-object>")
                     (if (vector? x)
                       (begin
                         (write-char #\#)
@@ -55,18 +76,24 @@
                       (if (uvector? x)
                         (begin
                           (write-char #\#)
-                          (if (s64vector? x)
-                            (display "s64")
-                            (if (f64vector? x)
-                              (display "f64")
-                              (display "unknown_uvector")))
+                          (display
+                            (cond ((s64vector? x) "s64")
+                              ((f64vector? x) "f64")
+                              ((u8vector? x) "u8")
+                              ((s8vector? x) "s8")
+                              ((u16vector? x) "u16")
+                              ((s16vector? x) "s16")
+                              ((u32vector? x) "u32")
+                              ((s32vector? x) "s32")
+                              ((f32vector? x) "f32")
+                              (else "unknown_uvector")))
 
                           (write-char #\openparen)
                           (if (= (uvector-length x) 0)
                             #f
                             (write-uvector-inner x 0))
                           (write-char #\closeparen))
-                        (display "<unknown>")))))))))))))
+                        (display "<unknown>")))))))))))))))))))
 (define (not x) (if x #f #t))
 (define (null? x) (eq? x '()))
 
@@ -101,6 +128,19 @@
       ((eq? obj (caar l)) (car l))
       (else (loop (cdr l))))))
 
+(define (assv obj alist)
+  (let loop ((l alist))
+    (cond ((null? l) #f)
+      ((eqv? obj (caar l)) (car l))
+      (else (loop (cdr l))))))
+
+(define (assoc obj alist . compare)
+  (let ((compare (if (null? compare) equal? (car compare))))
+    (let loop ((l alist))
+      (cond ((null? l) #f)
+        ((compare obj (caar l)) (car l))
+        (else (loop (cdr l)))))))
+
 (define (equal? a b)
   (cond ((eqv? a b) #t)
     ((and (pair? a) (pair? b))
@@ -111,12 +151,146 @@
     ;; TODO: vectorなど
     (else #f)))
 
-(define (member obj lst)
+(define (member obj lst . compare)
+  (let ((compare (if (null? compare) equal? (car compare))))
+    (let loop ((l lst))
+      (cond ((null? l) #f)
+        ((compare obj (car l)) l)
+        (else (loop (cdr l)))))))
+
+(define (memq obj lst)
   (let loop ((l lst))
     (cond ((null? l) #f)
-      ((equal? obj (car l)) l)
+      ((eq? obj (car l)) l)
       (else (loop (cdr l))))))
 
+(define (memv obj lst)
+  (let loop ((l lst))
+    (cond ((null? l) #f)
+      ((eqv? obj (car l)) l)
+      (else (loop (cdr l))))))
+
+;; 循環リストは#fになる
+(define (list? x)
+  (let loop ((slow x) (fast x))
+    (cond ((null? fast) #t)
+      ((not (pair? fast)) #f)
+      ((null? (cdr fast)) #t)
+      ((not (pair? (cdr fast))) #f)
+      (else
+        (let ((slow (cdr slow)) (fast (cddr fast)))
+          (if (eq? slow fast)
+            #f
+            (loop slow fast)))))))
+
+(define (make-list k . fill)
+  (let ((fill (if (null? fill) #f (car fill))))
+    (let loop ((k k) (acc '()))
+      (if (= k 0)
+        acc
+        (loop (- k 1) (cons fill acc))))))
+
+(define (list-tail lst k)
+  (if (= k 0)
+    lst
+    (list-tail (cdr lst) (- k 1))))
+
+(define (list-ref lst k)
+  (car (list-tail lst k)))
+
+(define (list-set! lst k obj)
+  (set-car! (list-tail lst k) obj))
+
+(define (list-copy obj)
+  (if (pair? obj)
+    (let loop ((l obj) (acc '()))
+      (if (pair? l)
+        (loop (cdr l) (cons (car l) acc))
+        ;; 末尾がNilでない場合も保つ
+        (let recur ((acc acc) (result l))
+          (if (null? acc)
+            result
+            (recur (cdr acc) (cons (car acc) result))))))
+    obj))
+
+;; 最後の引数以外はリストの先頭に追加する
+(define (apply proc . args)
+  (%apply
+    proc
+    (let loop ((args args))
+      (if (null? (cdr args))
+        (car args)
+        (cons (car args) (loop (cdr args)))))))
+
+(define (%map1 proc lst)
+  (let loop ((l lst) (acc '()))
+    (if (null? l)
+      (reverse acc)
+      (loop (cdr l) (cons (proc (car l)) acc)))))
+
+(define (%any-null? lists)
+  (cond ((null? lists) #f)
+    ((null? (car lists)) #t)
+    (else (%any-null? (cdr lists)))))
+
+;; 複数のリストを渡した場合は最も短いリストに合わせる
+(define (map proc list1 . lists)
+  (if (null? lists)
+    (%map1 proc list1)
+    (let loop ((ls (cons list1 lists)) (acc '()))
+      (if (%any-null? ls)
+        (reverse acc)
+        (loop (%map1 cdr ls) (cons (apply proc (%map1 car ls)) acc))))))
+
+(define (for-each proc list1 . lists)
+  (if (null? lists)
+    (let loop ((l list1))
+      (if (null? l)
+        #f
+        (begin
+          (proc (car l))
+          (loop (cdr l)))))
+    (let loop ((ls (cons list1 lists)))
+      (if (%any-null? ls)
+        #f
+        (begin
+          (apply proc (%map1 car ls))
+          (loop (%map1 cdr ls)))))))
+
+;; 文字と文字列
+(define (char=? c1 c2) (= (char->integer c1) (char->integer c2)))
+(define (char<? c1 c2) (< (char->integer c1) (char->integer c2)))
+(define (char>? c1 c2) (> (char->integer c1) (char->integer c2)))
+(define (char<=? c1 c2) (<= (char->integer c1) (char->integer c2)))
+(define (char>=? c1 c2) (>= (char->integer c1) (char->integer c2)))
+
+(define (char-ci=? c1 c2) (char=? (char-downcase c1) (char-downcase c2)))
+(define (char-ci<? c1 c2) (char<? (char-downcase c1) (char-downcase c2)))
+(define (char-ci>? c1 c2) (char>? (char-downcase c1) (char-downcase c2)))
+(define (char-ci<=? c1 c2) (char<=? (char-downcase c1) (char-downcase c2)))
+(define (char-ci>=? c1 c2) (char>=? (char-downcase c1) (char-downcase c2)))
+
+(define (string-append . strings)
+  (let loop ((strings strings) (acc ""))
+    (if (null? strings)
+      acc
+      (loop (cdr strings) (%string-append acc (car strings))))))
+
+(define (make-string k . fill)
+  (%make-string k (if (null? fill) #\space (car fill))))
+
+(define (string . chars)
+  (list->string chars))
+
+(define (string->number s . radix)
+  (%string->number s (if (null? radix) 10 (car radix))))
+
+(define (string-upcase s)
+  (list->string (map char-upcase (string->list s))))
+
+(define (string-downcase s)
+  (list->string (map char-downcase (string->list s))))
+
 (define (zero? z)
   (= z 0))
 
@@ -126,6 +300,10 @@
 (define (negative? x)
   (< x 0))
 
+(define (exact-integer-sqrt k)
+  (let ((s (%exact-integer-sqrt k)))
+    (values s (- k (* s s)))))
+
 (define (odd? n)
   (= (remainder n 2) (if (>= n 0) 1 -1)))
 
@@ -162,3 +340,493 @@
 (define (cddadr x) (cdr (cdr (car (cdr x)))))
 (define (cdddar x) (cdr (cdr (cdr (car x)))))
 (define (cddddr x) (cdr (cdr (cdr (cdr x)))))
+
+;; dynamic-windで登録された(before . after)のリスト。先頭が最も内側
+(define %winders '())
+
+(define (dynamic-wind before thunk after)
+  (before)
+  (set! %winders (cons (cons before after) %winders))
+  (let ((result (thunk)))
+    (set! %winders (cdr %winders))
+    (after)
+    result))
+
+(define (%unwind-to winders)
+  (if (eq? %winders winders)
+    #f
+    (let ((after (cdar %winders)))
+      (set! %winders (cdr %winders))
+      (after)
+      (%unwind-to winders))))
+
+;; 共通の部分までafterを呼び、そこからbeforeを外側から順に呼ぶ
+(define (%rewind-to winders)
+  (let ((common (%common-winders %winders winders)))
+    (%unwind-to common)
+    (let loop ((ws winders))
+      (if (eq? ws common)
+        #f
+        (begin
+          (loop (cdr ws))
+          ((caar ws))
+          (set! %winders ws))))))
+
+(define (%common-winders a b)
+  (let ((la (length a))
+        (lb (length b)))
+    (let loop ((a (if (> la lb) (list-tail a (- la lb)) a))
+               (b (if (> lb la) (list-tail b (- lb la)) b)))
+      (if (eq? a b)
+        a
+        (loop (cdr a) (cdr b))))))
+
+;; 継続を呼び出すと、dynamic-windとハンドラを取り出した時点の状態に戻してから値を渡す
+;; %call/ccは継続が有効なときだけ使える
+(define (call-with-current-continuation proc)
+  (let ((winders %winders)
+        (handlers %handlers))
+    (%call/cc
+      (lambda (k)
+        (proc
+          (lambda vals
+            (%rewind-to winders)
+            (set! %handlers handlers)
+            (%resume k (apply values vals))))))))
+
+(define call/cc call-with-current-continuation)
+
+;; with-exception-handlerで登録されたハンドラのリスト。先頭が最も内側
+;; #fはguardを表し、そこに到達したraiseはWasmの例外としてguardまで脱出する
+(define %handlers '())
+
+(define (with-exception-handler handler thunk)
+  (let ((handlers %handlers))
+    (dynamic-wind
+      (lambda () (set! %handlers (cons handler handlers)))
+      thunk
+      (lambda () (set! %handlers handlers)))))
+
+;; ハンドラは登録時のハンドラのリストの下で呼ぶ
+(define (%raise-to-handler obj continuable)
+  (let ((handlers %handlers))
+    (cond
+      ((null? handlers) (%report-uncaught obj))
+      ((car handlers)
+        (set! %handlers (cdr handlers))
+        (let ((result ((car handlers) obj)))
+          (if continuable
+            (begin
+              (set! %handlers handlers)
+              result)
+            (raise (%make-error-object "handler returned from non-continuable raise" (list obj))))))
+      (else (%raise obj)))))
+
+(define (raise obj)
+  (%raise-to-handler obj #f))
+
+(define (raise-continuable obj)
+  (%raise-to-handler obj #t))
+
+(define (error message . irritants)
+  (raise (%make-error-object message irritants)))
+
+(define (file-error? obj) #f)
+;; readが返すエラーオブジェクトはkindが1になる
+(define (read-error? obj)
+  (and (error-object? obj) (= (%error-object-kind obj) 1)))
+
+(define (%report-uncaught obj)
+  (%with-output-port
+    %stderr-port
+    (lambda ()
+      (if (error-object? obj)
+        (begin
+          (display "error: ")
+          (display (error-object-message obj))
+          (let loop ((irritants (error-object-irritants obj)))
+            (if (null? irritants)
+              #f
+              (begin
+                (write-char #\space)
+                (write (car irritants))
+                (loop (cdr irritants))))))
+        (begin
+          (display "uncaught exception: ")
+          (write obj)))
+      (newline)))
+  ;; guardの外なのでプログラムが終了する
+  (%raise obj))
+
+;; どの節にも一致しなければ外側のハンドラにraise-continuableする
+(define-syntax guard
+  (syntax-rules ()
+    ((guard (var clause ...) body ...)
+      (%guard
+        (lambda () body ...)
+        (lambda (var) (%guard-clauses var clause ...))))))
+
+(define-syntax %guard-clauses
+  (syntax-rules (else)
+    ((_ var (else expr ...)) (begin expr ...))
+    ((_ var) (raise-continuable var))
+    ((_ var clause rest ...) (cond clause (else (%guard-clauses var rest ...))))))
+
+;; 標準入力は未対応なので常にEOFを返す
+(define %stdin-port (%standard-port 0))
+(define %stdout-port (%standard-port 1))
+(define %stderr-port (%standard-port 2))
+(define %current-output-port %stdout-port)
+
+(define (current-input-port) %stdin-port)
+(define (current-output-port) %current-output-port)
+(define (current-error-port) %stderr-port)
+
+(define (%set-output-port! port)
+  (set! %current-output-port port)
+  (%set-current-output-port! port))
+
+;; thunkの実行中はdisplayなどの出力先をportにする
+(define (%with-output-port port thunk)
+  (let ((prev %current-output-port))
+    (dynamic-wind
+      (lambda () (%set-output-port! port))
+      thunk
+      (lambda () (%set-output-port! prev)))))
+
+(define (input-port? obj)
+  (and (port? obj) (%input-port? obj)))
+
+(define (output-port? obj)
+  (and (port? obj) (%output-port? obj)))
+
+(define (textual-port? obj) (port? obj))
+
+(define (close-input-port port) (close-port port))
+(define (close-output-port port) (close-port port))
+
+(define (eof-object? obj) (eq? obj (eof-object)))
+
+(define (write-string s . port)
+  (if (null? port)
+    (display s)
+    (display s (car port))))
+
+(define (read-char . port)
+  (%read-char (if (null? port) (current-input-port) (car port))))
+
+(define (peek-char . port)
+  (%peek-char (if (null? port) (current-input-port) (car port))))
+
+(define (read . port)
+  (let ((datum (%read (if (null? port) (current-input-port) (car port)))))
+    (if (read-error? datum)
+      (raise datum)
+      datum)))
+
+;; 環境は区別せず、常にトップレベルの環境で評価する
+(define %environment (list 'environment))
+(define (interaction-environment) %environment)
+(define (scheme-report-environment version) %environment)
+(define (null-environment version) %environment)
+
+(define (eval expr env)
+  (%eval expr))
+
+;; ハッシュテーブル
+;; 同値性の手続きはeq? / eqv? / equal? / string=?のみ対応し、ハッシュ関数の引数は無視する
+(define (make-hash-table . args)
+  (let ((equiv (if (null? args) equal? (car args))))
+    (%make-hash-table
+      (cond ((eq? equiv eq?) 0)
+        ((eq? equiv eqv?) 1)
+        ;; string=?で等しい文字列はequal?でも等しい
+        ((or (eq? equiv equal?) (eq? equiv string=?)) 2)
+        (else (error "make-hash-table: unsupported equivalence procedure" equiv))))))
+
+;; 値として格納されることのない、見つからなかったことを表すオブジェクト
+(define %hash-table-missing (list 'missing))
+
+(define (hash-table-ref table key . rest)
+  (let ((value (%hash-table-ref table key %hash-table-missing)))
+    (cond ((not (eq? value %hash-table-missing))
+            (if (or (null? rest) (null? (cdr rest))) value ((cadr rest) value)))
+      ((null? rest) (error "hash-table-ref: key not found" key))
+      (else ((car rest))))))
+
+(define (hash-table-ref/default table key default)
+  (%hash-table-ref table key default))
+
+(define (hash-table-contains? table key)
+  (not (eq? (%hash-table-ref table key %hash-table-missing) %hash-table-missing)))
+(define (hash-table-exists? table key) (hash-table-contains? table key))
+
+(define (hash-table-update! table key proc . failure)
+  (hash-table-set! table key
+    (proc (if (null? failure)
+            (hash-table-ref table key)
+            (hash-table-ref table key (car failure))))))
+
+(define (hash-table-update!/default table key proc default)
+  (hash-table-set! table key (proc (%hash-table-ref table key default))))
+
+(define (hash-table-size table) (hash-table-count table))
+
+(define (hash-table-keys table) (map car (hash-table->alist table)))
+(define (hash-table-values table) (map cdr (hash-table->alist table)))
+
+;; 走査中にテーブルを変更してもよいように、先に連想リストにする
+(define (hash-table-walk table proc)
+  (for-each (lambda (entry) (proc (car entry) (cdr entry))) (hash-table->alist table)))
+
+;; レコード
+;; レコード型ごとにマクロ展開時にIDを割り当て、IRでもIDごとに別の型として扱う
+;; レコード自体は全ての型で共通の構造体で、IDと表示用のレコード型とフィールドのvectorを持つ
+;; レコード型は名前とフィールド名のリストのペアで表す
+;; stdlib内でも使うため、組み込み手続きのラッパーが定義される前に展開されても動くようにする
+(define-syntax define-record-type
+  (syntax-rules ()
+    ((_ type (constructor field ...) pred spec ...)
+      (%record-layout %define-record-type type (constructor field ...) pred spec ...))))
+
+;; %record-layoutがレコード型のIDとフィールドの数、各フィールドのインデックスを付け加える
+(define-syntax %define-record-type
+  (syntax-rules ()
+    ((_ id size type (constructor (field i) ...) pred (j field-name procs ...) ...)
+      (begin
+        (define type (%make-record-type 'type '(field-name ...)))
+        (define (constructor field ...)
+          (let ((record (%make-record id type size)))
+            (%record-set! id record i field) ...
+            record))
+        (define (pred obj) (%record-of? id obj))
+        (%define-record-field id j procs ...) ...))))
+
+;; 型検査に成功した分岐ではJITがレコード型を知っているので、%record-refと%record-set!の型検査は取り除かれる
+(define-syntax %define-record-field
+  (syntax-rules ()
+    ((_ id i accessor)
+      (define (accessor obj)
+        (if (%record-of? id obj)
+          (%record-ref id obj i)
+          (error "record type mismatch" 'accessor obj))))
+    ((_ id i accessor modifier)
+      (begin
+        (%define-record-field id i accessor)
+        (define (modifier obj value)
+          (if (%record-of? id obj)
+            (%record-set! id obj i value)
+            (error "record type mismatch" 'modifier obj)))))))
+
+(define (%make-record-type name fields) (cons name fields))
+(define (%record-type-name type) (car type))
+
+;; プロミス
+;; delay / delay-force はdesugarで%make-lazy-promiseの呼び出しになる
+;; 状態は (評価済みか . 値またはthunk) のペアで、delay-forceの連鎖ではプロミス同士でペアを共有する(R7RSの参照実装と同じ)
+(define-record-type %promise
+  (%make-promise state)
+  promise?
+  (state %promise-state %promise-set-state!))
+
+(define (%make-lazy-promise thunk) (%make-promise (cons #f thunk)))
+(define (%make-eager-promise obj) (%make-promise (cons #t obj)))
+
+(define (make-promise obj)
+  (if (promise? obj) obj (%make-eager-promise obj)))
+
+(define (force promise)
+  (if (promise? promise)
+    (let ((state (%promise-state promise)))
+      (if (car state)
+        (cdr state)
+        (let ((promise* ((cdr state))))
+          (if (not (promise? promise*))
+            (error "delay-force: expression did not return a promise" promise*)
+            #f)
+          ;; thunkの中で再帰的にforceされた場合は先に評価された値を優先する
+          (if (car (%promise-state promise))
+            #f
+            (let ((state (%promise-state promise)) (state* (%promise-state promise*)))
+              (set-car! state (car state*))
+              (set-cdr! state (cdr state*))
+              (%promise-set-state! promise* state)))
+          (force promise))))
+    promise))
+
+;; 条件分岐の構文
+(define-syntax when
+  (syntax-rules ()
+    ((_ test body ...) (if test (begin body ...) #f))))
+
+(define-syntax unless
+  (syntax-rules ()
+    ((_ test body ...) (if test #f (begin body ...)))))
+
+;; キーは一度だけ評価し、各節のデータとはeqv?で比較する
+(define-syntax case
+  (syntax-rules ()
+    ((_ key clause ...)
+      (let ((tmp key))
+        (%case-clauses tmp clause ...)))))
+
+(define-syntax %case-clauses
+  (syntax-rules (else =>)
+    ((_ tmp) #f)
+    ((_ tmp (else => proc)) (proc tmp))
+    ((_ tmp (else expr ...)) (begin expr ...))
+    ((_ tmp ((datum ...) => proc) clause ...)
+      (if (or (eqv? tmp 'datum) ...)
+        (proc tmp)
+        (%case-clauses tmp clause ...)))
+    ((_ tmp ((datum ...) expr ...) clause ...)
+      (if (or (eqv? tmp 'datum) ...)
+        (begin expr ...)
+        (%case-clauses tmp clause ...)))))
+
+;; 多値
+;; 値が1つのときはその値自体を返し、それ以外のときは値のリストを持つレコードを返す
+(define-record-type %values
+  (%make-values list)
+  %values?
+  (list %values-list))
+
+(define (values . objs)
+  (if (and (pair? objs) (null? (cdr objs)))
+    (car objs)
+    (%make-values objs)))
+
+(define (call-with-values producer consumer)
+  (let ((v (producer)))
+    (if (%values? v)
+      (apply consumer (%values-list v))
+      (consumer v))))
+
+(define-syntax let*-values
+  (syntax-rules ()
+    ((_ () body ...) (let () body ...))
+    ((_ ((formals expr) rest ...) body ...)
+      (call-with-values
+        (lambda () expr)
+        (lambda formals (let*-values (rest ...) body ...))))))
+
+;; 全ての式を評価してから束縛するため、値のリストのリストを作ってから順に束縛する
+(define-syntax let-values
+  (syntax-rules ()
+    ((_ ((formals expr) ...) body ...)
+      (let ((vals (list (call-with-values (lambda () expr) (lambda args args)) ...)))
+        (%let-values-bind vals (formals ...) body ...)))))
+
+(define-syntax %let-values-bind
+  (syntax-rules ()
+    ((_ vals () body ...) (let () body ...))
+    ((_ vals (formals rest ...) body ...)
+      (apply
+        (lambda formals (%let-values-bind (cdr vals) (rest ...) body ...))
+        (car vals)))))
+
+;; 内部定義では先に定義した変数を後の定義式から参照できないため、変数を定義してからset!する
+(define-syntax define-values
+  (syntax-rules ()
+    ((_ formals expr)
+      (begin
+        (%define-values-vars formals)
+        (define dummy
+          (call-with-values
+            (lambda () expr)
+            (lambda vals (%define-values-set! vals formals))))))))
+
+(define-syntax %define-values-vars
+  (syntax-rules ()
+    ((_ ()) (begin))
+    ((_ (var . rest))
+      (begin
+        (define var #f)
+        (%define-values-vars rest)))
+    ((_ var) (define var #f))))
+
+(define-syntax %define-values-set!
+  (syntax-rules ()
+    ((_ vals ()) (if (null? vals) #f (error "define-values: too many values" vals)))
+    ((_ vals (var . rest))
+      (begin
+        (set! var (car vals))
+        (%define-values-set! (cdr vals) rest)))
+    ((_ vals var) (set! var vals))))
+
+;; バイトベクタ
+;; bytevectorはu8vectorと同じ型で、書き込む値は下位8ビットのみ格納される
+(define (make-bytevector k . fill)
+  (let ((bytes (make-u8vector k)))
+    (if (not (null? fill))
+      (let loop ((i 0))
+        (if (< i k)
+          (begin
+            (bytevector-u8-set! bytes i (car fill))
+            (loop (+ i 1)))
+          #f))
+      #f)
+    bytes))
+
+(define (bytevector . bytes)
+  (let ((result (make-u8vector (length bytes))))
+    (let loop ((i 0) (bytes bytes))
+      (if (null? bytes)
+        result
+        (begin
+          (bytevector-u8-set! result i (car bytes))
+          (loop (+ i 1) (cdr bytes)))))))
+
+(define (%bytevector-range bytes rest)
+  (let* ((start (if (null? rest) 0 (car rest)))
+         (end (if (or (null? rest) (null? (cdr rest))) (bytevector-length bytes) (cadr rest))))
+    (if (or (< start 0) (< end start) (< (bytevector-length bytes) end))
+      (error "bytevector: index out of range" start end)
+      (cons start end))))
+
+(define (bytevector-copy! to at from . rest)
+  (let* ((range (%bytevector-range from rest))
+         (start (car range))
+         (end (cdr range)))
+    (if (or (< at 0) (< (bytevector-length to) (+ at (- end start))))
+      (error "bytevector-copy!: index out of range" at)
+      #f)
+    ;; 同じバイトベクタ内で重なる範囲をコピーする場合に備えて向きを選ぶ
+    (if (< at start)
+      (let loop ((i start))
+        (if (< i end)
+          (begin
+            (bytevector-u8-set! to (+ at (- i start)) (bytevector-u8-ref from i))
+            (loop (+ i 1)))
+          #f))
+      (let loop ((i (- end 1)))
+        (if (>= i start)
+          (begin
+            (bytevector-u8-set! to (+ at (- i start)) (bytevector-u8-ref from i))
+            (loop (- i 1)))
+          #f)))))
+
+(define (bytevector-copy bytes . rest)
+  (let* ((range (%bytevector-range bytes rest))
+         (result (make-u8vector (- (cdr range) (car range)))))
+    (bytevector-copy! result 0 bytes (car range) (cdr range))
+    result))
+
+(define (bytevector-append . bytevectors)
+  (let ((result (make-u8vector (apply + (map bytevector-length bytevectors)))))
+    (let loop ((at 0) (bytevectors bytevectors))
+      (if (null? bytevectors)
+        result
+        (begin
+          (bytevector-copy! result at (car bytevectors))
+          (loop (+ at (bytevector-length (car bytevectors))) (cdr bytevectors)))))))
+
+(define (utf8->string bytes . rest)
+  (%utf8->string (if (null? rest) bytes (apply bytevector-copy bytes rest))))
+
+;; 文字列のインデックスはバイト単位である
+(define (string->utf8 s . rest)
+  (%string->utf8
+    (if (null? rest)
+      s
+      (substring s (car rest) (if (null? (cdr rest)) (string-length s) (cadr rest))))))
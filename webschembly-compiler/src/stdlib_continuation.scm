@@ -0,0 +1,13 @@
+;; guardの本体でraiseされたらguardの継続で脱出する
+(define (%guard thunk handler)
+  ((call-with-current-continuation
+     (lambda (guard-k)
+       (with-exception-handler
+         (lambda (condition) (guard-k (lambda () (handler condition))))
+         (lambda ()
+           (let ((result (thunk)))
+             (lambda () result))))))))
+
+;; JSからクロージャを呼び出すときに渡す継続
+(define %identity-continuation #f)
+(%call/cc (lambda (k) (set! %identity-continuation k)))
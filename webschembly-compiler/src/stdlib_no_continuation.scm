@@ -0,0 +1,14 @@
+;; guardの本体でraiseされたらWasmの例外でguardまで脱出する
+(define (%guard thunk handler)
+  (let ((handlers %handlers)
+        (winders %winders))
+    (%with-raise-handler
+      (lambda ()
+        (set! %handlers (cons #f handlers))
+        (let ((result (thunk)))
+          (set! %handlers handlers)
+          result))
+      (lambda (condition)
+        (%unwind-to winders)
+        (set! %handlers handlers)
+        (handler condition)))))
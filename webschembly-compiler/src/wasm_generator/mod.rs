@@ -1,4 +1,5 @@
 mod module_generator;
 mod relooper;
+mod source_map;
 
-pub use module_generator::generate;
+pub use module_generator::{Config, Output, SourceMapUrl, generate, generate_with_config};
@@ -2,12 +2,14 @@ use rustc_hash::FxHashMap;
 use std::borrow::Cow;
 
 use crate::wasm_generator::relooper::{Structured, reloop};
+use crate::wasm_generator::source_map::{encode_base64, generate_source_map};
 use vec_map::VecMap;
 use wasm_encoder::{
-    AbstractHeapType, BlockType, CodeSection, CompositeInnerType, CompositeType, ConstExpr,
-    DataCountSection, DataSection, ElementSection, Elements, EntityType, ExportKind, ExportSection,
-    FieldType, Function, FunctionSection, GlobalSection, GlobalType, HeapType, ImportSection,
-    Instruction, MemoryType, Module, RefType, StorageType, StructType, SubType, TableSection,
+    AbstractHeapType, ArrayType, BlockType, Catch, CodeSection, CompositeInnerType, CompositeType,
+    ConstExpr, CustomSection, DataCountSection, DataSection, ElementSection, Elements, Encode,
+    EntityType, ExportKind, ExportSection, FieldType, Function, FunctionSection, GlobalSection,
+    GlobalType, HeapType, ImportSection, IndirectNameMap, Instruction, MemoryType, Module, NameMap,
+    NameSection, RefType, StorageType, StructType, SubType, TableSection, TagKind, TagType,
     TypeSection, ValType,
 };
 use webschembly_compiler_ir as ir;
@@ -18,15 +20,116 @@ pub struct WasmFuncType {
     pub results: Vec<ValType>,
 }
 
+// runtimeのinteger_binopに渡す演算の種類
+#[derive(Debug, Clone, Copy)]
+enum IntegerBinOp {
+    Add = 0,
+    Sub = 1,
+    Mul = 2,
+    Quotient = 3,
+    Remainder = 4,
+    Modulo = 5,
+    Gcd = 6,
+    Lcm = 7,
+}
+
+// runtimeのint_binop/int_binop_overflowに渡す演算の種類
+#[derive(Debug, Clone, Copy)]
+enum IntBinOp {
+    Gcd = 0,
+    Lcm = 1,
+    Expt = 2,
+}
+
+// runtimeのfloat_unopに渡す演算の種類
+#[derive(Debug, Clone, Copy)]
+enum FloatUnOp {
+    Exp = 0,
+    Log = 1,
+    Sin = 2,
+    Cos = 3,
+    Tan = 4,
+    Asin = 5,
+    Acos = 6,
+    Atan = 7,
+}
+
+// runtimeのfloat_binopに渡す演算の種類
+#[derive(Debug, Clone, Copy)]
+enum FloatBinOp {
+    Atan2 = 0,
+    Expt = 1,
+}
+
+// runtimeのchar_opに渡す演算の種類
+#[derive(Debug, Clone, Copy)]
+enum CharOp {
+    Upcase = 0,
+    Downcase = 1,
+    Alphabetic = 2,
+    Numeric = 3,
+    Whitespace = 4,
+    UpperCase = 5,
+    LowerCase = 6,
+}
+
+// runtimeのrational_binopに渡す演算の種類
+#[derive(Debug, Clone, Copy)]
+enum RationalBinOp {
+    Add = 0,
+    Sub = 1,
+    Mul = 2,
+    Div = 3,
+    Expt = 4,
+}
+
+// runtimeのrational_unopに渡す演算の種類
+#[derive(Debug, Clone, Copy)]
+enum RationalUnOp {
+    Floor = 0,
+    Round = 1,
+    Ceiling = 2,
+    Truncate = 3,
+    ExactIntegerSqrt = 4,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Config {
+    // 関数名とローカル変数名をnameセクションに出力するか
+    pub name_section: bool,
+    pub source_map: Option<SourceMapUrl>,
+    // スタックトレースの位置情報をモジュールに埋め込み、開始時にランタイムへ登録するか
+    // ランタイムのコンパイラなしで実行するAOTの出力で使う
+    pub embed_source_locs: bool,
+}
+
+#[derive(Debug, Clone)]
+pub enum SourceMapUrl {
+    // data URLとしてwasmに埋め込む
+    Inline,
+    // 指定したURLを参照する。Source Map本体はOutput::source_mapとして返す
+    External(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct Output {
+    pub wasm: Vec<u8>,
+    pub source_map: Option<String>,
+}
+
 pub fn generate(module: &ir::Module) -> Vec<u8> {
-    let module_gen = ModuleGenerator::new(module);
-    let module = module_gen.generate();
-    module.finish()
+    generate_with_config(module, &Config::default()).wasm
+}
+
+pub fn generate_with_config(module: &ir::Module, config: &Config) -> Output {
+    let module_gen = ModuleGenerator::new(module, config);
+    module_gen.generate()
 }
 
 #[derive(Debug)]
 struct ModuleGenerator<'a> {
     module: &'a ir::Module,
+    config: &'a Config,
     type_count: u32,
     func_count: u32,
     global_count: u32,
@@ -40,13 +143,65 @@ struct ModuleGenerator<'a> {
     string_set_func: u32,
     string_length_func: u32,
     string_copy_func: u32,
+    string_append_func: u32,
+    substring_func: u32,
+    make_string_func: u32,
+    list_to_string_func: u32,
+    string_to_list_func: u32,
+    string_cmp_func: u32,
+    string_cmp_ci_func: u32,
+    string_to_number_func: u32,
+    char_op_func: u32,
+    int_to_char_func: u32,
     write_char_func: u32,
     int_to_string_func: u32,
     float_to_string_func: u32,
+    bigint_to_string_func: u32,
+    string_to_bigint_func: u32,
+    integer_binop_func: u32,
+    integer_cmp_func: u32,
+    rational_to_string_func: u32,
+    rational_binop_func: u32,
+    rational_cmp_func: u32,
+    rational_unop_func: u32,
+    int_binop_func: u32,
+    int_binop_overflow_func: u32,
+    exact_integer_sqrt_int_func: u32,
+    float_unop_func: u32,
+    float_binop_func: u32,
+    rational_to_float_func: u32,
+    float_to_exact_func: u32,
     string_eq_func: u32,
     args_to_list_func: u32,
+    list_to_args_func: u32,
     increment_branch_counter_func: u32,
+    increment_bb_layout_counter_func: u32,
     throw_webassembly_exception: u32,
+    raise_func: u32,
+    with_raise_handler_func: u32,
+    set_current_output_fd_func: u32,
+    write_char_fd_func: u32,
+    open_input_string_func: u32,
+    open_output_string_func: u32,
+    standard_port_func: u32,
+    get_output_string_func: u32,
+    close_port_func: u32,
+    read_char_func: u32,
+    peek_char_func: u32,
+    read_func: u32,
+    eval_func: u32,
+    make_hash_table_func: u32,
+    hash_table_ref_func: u32,
+    hash_table_set_func: u32,
+    hash_table_delete_func: u32,
+    hash_table_to_alist_func: u32,
+    utf8_to_string_func: u32,
+    string_to_utf8_func: u32,
+    print_stack_frame_func: u32,
+    malloc_func: u32,
+    register_source_locs_func: u32,
+    // tags
+    webschembly_exception_tag: u32,
     // wasm section
     imports: ImportSection,
     types: TypeSection,
@@ -64,15 +219,28 @@ struct ModuleGenerator<'a> {
     bool_type: u32,
     int_type: u32,
     float_type: u32,
+    bigint_type: u32,
+    rational_type: u32,
     char_type: u32,
     cons_type: u32,
     buf_type: u32,
     string_buf_type: u32,
     string_type: u32,
     symbol_type: u32,
+    error_object_type: u32,
+    port_type: u32,
+    hash_table_type: u32,
+    record_type: u32,
     vector_type: u32,
     uvector_s64_type: u32,
     uvector_f64_type: u32,
+    uvector_u8_type: u32,
+    uvector_s8_type: u32,
+    uvector_u16_type: u32,
+    uvector_s16_type: u32,
+    uvector_u32_type: u32,
+    uvector_s32_type: u32,
+    uvector_f32_type: u32,
     closure_type: u32,
     closure_types: FxHashMap<Vec<ValType>, u32>, // env types -> type index
     func_types: FxHashMap<WasmFuncType, u32>,
@@ -81,16 +249,24 @@ struct ModuleGenerator<'a> {
     mut_func_ref_type: u32,
     entrypoint_table_type: u32,
     global_id_to_idx: FxHashMap<ir::GlobalId, u32>,
+    // debug info
+    func_names: NameMap,
+    local_names: IndirectNameMap,
+    // (コードセクションの関数本体列の先頭からのバイトオフセット, SourceLocId)
+    source_loc_offsets: Vec<(usize, ir::SourceLocId)>,
     // const
     nil_global: Option<u32>,
+    eof_global: Option<u32>,
+    eval_result_global: Option<u32>,
     true_global: Option<u32>,
     false_global: Option<u32>,
 }
 
 impl<'a> ModuleGenerator<'a> {
-    fn new(module: &'a ir::Module) -> Self {
+    fn new(module: &'a ir::Module, config: &'a Config) -> Self {
         Self {
             module,
+            config,
             type_count: 0,
             func_count: 0,
             global_count: 0,
@@ -102,14 +278,65 @@ impl<'a> ModuleGenerator<'a> {
             write_char_func: 0,
             int_to_string_func: 0,
             float_to_string_func: 0,
+            bigint_to_string_func: 0,
+            string_to_bigint_func: 0,
+            integer_binop_func: 0,
+            integer_cmp_func: 0,
+            rational_to_string_func: 0,
+            rational_binop_func: 0,
+            rational_cmp_func: 0,
+            rational_unop_func: 0,
+            int_binop_func: 0,
+            int_binop_overflow_func: 0,
+            exact_integer_sqrt_int_func: 0,
+            float_unop_func: 0,
+            float_binop_func: 0,
+            rational_to_float_func: 0,
+            float_to_exact_func: 0,
             string_eq_func: 0,
             string_ref_func: 0,
             string_copy_func: 0,
+            string_append_func: 0,
+            substring_func: 0,
+            make_string_func: 0,
+            list_to_string_func: 0,
+            string_to_list_func: 0,
+            string_cmp_func: 0,
+            string_cmp_ci_func: 0,
+            string_to_number_func: 0,
+            char_op_func: 0,
+            int_to_char_func: 0,
             string_set_func: 0,
             string_length_func: 0,
             args_to_list_func: 0,
+            list_to_args_func: 0,
             increment_branch_counter_func: 0,
+            increment_bb_layout_counter_func: 0,
             throw_webassembly_exception: 0,
+            raise_func: 0,
+            with_raise_handler_func: 0,
+            set_current_output_fd_func: 0,
+            write_char_fd_func: 0,
+            open_input_string_func: 0,
+            open_output_string_func: 0,
+            standard_port_func: 0,
+            get_output_string_func: 0,
+            close_port_func: 0,
+            read_char_func: 0,
+            peek_char_func: 0,
+            read_func: 0,
+            eval_func: 0,
+            make_hash_table_func: 0,
+            hash_table_ref_func: 0,
+            hash_table_set_func: 0,
+            hash_table_delete_func: 0,
+            hash_table_to_alist_func: 0,
+            utf8_to_string_func: 0,
+            string_to_utf8_func: 0,
+            print_stack_frame_func: 0,
+            malloc_func: 0,
+            register_source_locs_func: 0,
+            webschembly_exception_tag: 0,
             imports: ImportSection::new(),
             types: TypeSection::new(),
             functions: FunctionSection::new(),
@@ -118,6 +345,9 @@ impl<'a> ModuleGenerator<'a> {
             globals: GlobalSection::new(),
             elements: ElementSection::new(),
             func_indices: FxHashMap::default(),
+            func_names: NameMap::new(),
+            local_names: IndirectNameMap::new(),
+            source_loc_offsets: Vec::new(),
             datas: DataSection::new(),
             exports: ExportSection::new(),
             ref_types: FxHashMap::default(),
@@ -125,6 +355,12 @@ impl<'a> ModuleGenerator<'a> {
             bool_type: 0,
             int_type: 0,
             float_type: 0,
+            bigint_type: 0,
+            rational_type: 0,
+            error_object_type: 0,
+            port_type: 0,
+            hash_table_type: 0,
+            record_type: 0,
             char_type: 0,
             cons_type: 0,
             buf_type: 0,
@@ -134,6 +370,13 @@ impl<'a> ModuleGenerator<'a> {
             vector_type: 0,
             uvector_s64_type: 0,
             uvector_f64_type: 0,
+            uvector_u8_type: 0,
+            uvector_s8_type: 0,
+            uvector_u16_type: 0,
+            uvector_s16_type: 0,
+            uvector_u32_type: 0,
+            uvector_s32_type: 0,
+            uvector_f32_type: 0,
             closure_type: 0,
             closure_types: FxHashMap::default(),
             func_types: FxHashMap::default(),
@@ -143,6 +386,8 @@ impl<'a> ModuleGenerator<'a> {
             entrypoint_table_type: 0,
             global_id_to_idx: FxHashMap::default(),
             nil_global: None,
+            eof_global: None,
+            eval_result_global: None,
             true_global: None,
             false_global: None,
         }
@@ -224,10 +469,22 @@ impl<'a> ModuleGenerator<'a> {
     const CHAR_VALUE_FIELD: u32 = 0;
     const INT_VALUE_FIELD: u32 = 0;
     const FLOAT_VALUE_FIELD: u32 = 0;
+    const RATIONAL_NUM_FIELD: u32 = 0;
+    const RATIONAL_DEN_FIELD: u32 = 1;
     // const STRING_BUF_FIELD: u32 = 0;
     // const STRING_LEN_FIELD: u32 = 1;
     // const STRING_OFFSET_FIELD: u32 = 2;
     const SYMBOL_STRING_FIELD: u32 = 0;
+    const ERROR_OBJECT_MESSAGE_FIELD: u32 = 0;
+    const ERROR_OBJECT_IRRITANTS_FIELD: u32 = 1;
+    const ERROR_OBJECT_KIND_FIELD: u32 = 2;
+    const PORT_ID_FIELD: u32 = 0;
+    const PORT_INPUT_FIELD: u32 = 1;
+    const PORT_OUTPUT_FIELD: u32 = 2;
+    const HASH_TABLE_COUNT_FIELD: u32 = 2;
+    const RECORD_ID_FIELD: u32 = 0;
+    const RECORD_TYPE_FIELD: u32 = 1;
+    const RECORD_FIELDS_FIELD: u32 = 2;
     const CONS_CAR_FIELD: u32 = 0;
     const CONS_CDR_FIELD: u32 = 1;
     const CLOSURE_MODULE_ID_FIELD: u32 = 0;
@@ -243,7 +500,7 @@ impl<'a> ModuleGenerator<'a> {
 
     const MUT_FUNC_REF_FUNC_FIELD: u32 = 0;
 
-    pub fn generate(mut self) -> Module {
+    pub fn generate(mut self) -> Output {
         self.buf_type = self.type_count;
         self.type_count += 1;
         self.types.ty().array(&StorageType::I8, true);
@@ -357,6 +614,53 @@ impl<'a> ModuleGenerator<'a> {
             },
         });
 
+        self.bigint_type = self.type_count;
+        self.type_count += 1;
+        self.types.ty().subtype(&SubType {
+            is_final: true,
+            supertype_idx: None,
+            composite_type: CompositeType {
+                shared: false,
+                inner: CompositeInnerType::Struct(StructType {
+                    fields: {
+                        let mut fields = Vec::new();
+                        // 2の補数リトルエンディアンのバイト列
+                        fields.push(FieldType {
+                            element_type: StorageType::Val(ValType::Ref(RefType {
+                                nullable: false,
+                                heap_type: HeapType::Concrete(self.buf_type),
+                            })),
+                            mutable: false,
+                        });
+                        fields.into_boxed_slice()
+                    },
+                }),
+            },
+        });
+
+        self.rational_type = self.type_count;
+        self.type_count += 1;
+        self.types.ty().subtype(&SubType {
+            is_final: true,
+            supertype_idx: None,
+            composite_type: CompositeType {
+                shared: false,
+                inner: CompositeInnerType::Struct(StructType {
+                    fields: {
+                        let mut fields = Vec::new();
+                        // 分子と分母。どちらもintかbigintで、既約かつ分母は2以上
+                        for _ in 0..2 {
+                            fields.push(FieldType {
+                                element_type: StorageType::Val(ValType::Ref(RefType::EQREF)),
+                                mutable: false,
+                            });
+                        }
+                        fields.into_boxed_slice()
+                    },
+                }),
+            },
+        });
+
         self.string_type = self.type_count;
         self.type_count += 1;
         self.types.ty().subtype(&SubType {
@@ -411,6 +715,71 @@ impl<'a> ModuleGenerator<'a> {
             },
         });
 
+        self.error_object_type = self.type_count;
+        self.type_count += 1;
+        self.types.ty().subtype(&SubType {
+            is_final: true,
+            supertype_idx: None,
+            composite_type: CompositeType {
+                shared: false,
+                inner: CompositeInnerType::Struct(StructType {
+                    fields: {
+                        let mut fields = Vec::new();
+                        // message
+                        fields.push(FieldType {
+                            element_type: StorageType::Val(ValType::Ref(RefType {
+                                nullable: true,
+                                heap_type: HeapType::Concrete(self.string_type),
+                            })),
+                            mutable: false,
+                        });
+                        // irritants
+                        fields.push(FieldType {
+                            element_type: StorageType::Val(ValType::Ref(RefType::EQREF)),
+                            mutable: false,
+                        });
+                        // kind
+                        fields.push(FieldType {
+                            element_type: StorageType::I8,
+                            mutable: false,
+                        });
+                        fields.into_boxed_slice()
+                    },
+                }),
+            },
+        });
+
+        self.port_type = self.type_count;
+        self.type_count += 1;
+        self.types.ty().subtype(&SubType {
+            is_final: true,
+            supertype_idx: None,
+            composite_type: CompositeType {
+                shared: false,
+                inner: CompositeInnerType::Struct(StructType {
+                    fields: {
+                        let mut fields = Vec::new();
+                        // id
+                        fields.push(FieldType {
+                            element_type: StorageType::Val(ValType::I32),
+                            mutable: false,
+                        });
+                        // input
+                        fields.push(FieldType {
+                            element_type: StorageType::I8,
+                            mutable: false,
+                        });
+                        // output
+                        fields.push(FieldType {
+                            element_type: StorageType::I8,
+                            mutable: false,
+                        });
+                        fields.into_boxed_slice()
+                    },
+                }),
+            },
+        });
+
         self.cons_type = self.type_count;
         self.type_count += 1;
         self.types.ty().subtype(&SubType {
@@ -441,6 +810,74 @@ impl<'a> ModuleGenerator<'a> {
             .ty()
             .array(&StorageType::Val(ValType::Ref(RefType::EQREF)), true);
 
+        self.hash_table_type = self.type_count;
+        self.type_count += 1;
+        self.types.ty().subtype(&SubType {
+            is_final: true,
+            supertype_idx: None,
+            composite_type: CompositeType {
+                shared: false,
+                inner: CompositeInnerType::Struct(StructType {
+                    fields: {
+                        let mut fields = Vec::new();
+                        // kind
+                        fields.push(FieldType {
+                            element_type: StorageType::I8,
+                            mutable: false,
+                        });
+                        // buckets
+                        fields.push(FieldType {
+                            element_type: StorageType::Val(ValType::Ref(RefType {
+                                nullable: false,
+                                heap_type: HeapType::Concrete(self.vector_type),
+                            })),
+                            mutable: true,
+                        });
+                        // count
+                        fields.push(FieldType {
+                            element_type: StorageType::Val(ValType::I32),
+                            mutable: true,
+                        });
+                        fields.into_boxed_slice()
+                    },
+                }),
+            },
+        });
+
+        self.record_type = self.type_count;
+        self.type_count += 1;
+        self.types.ty().subtype(&SubType {
+            is_final: true,
+            supertype_idx: None,
+            composite_type: CompositeType {
+                shared: false,
+                inner: CompositeInnerType::Struct(StructType {
+                    fields: {
+                        let mut fields = Vec::new();
+                        // id
+                        fields.push(FieldType {
+                            element_type: StorageType::Val(ValType::I32),
+                            mutable: false,
+                        });
+                        // type
+                        fields.push(FieldType {
+                            element_type: StorageType::Val(ValType::Ref(RefType::EQREF)),
+                            mutable: false,
+                        });
+                        // fields
+                        fields.push(FieldType {
+                            element_type: StorageType::Val(ValType::Ref(RefType {
+                                nullable: true,
+                                heap_type: HeapType::Concrete(self.vector_type),
+                            })),
+                            mutable: false,
+                        });
+                        fields.into_boxed_slice()
+                    },
+                }),
+            },
+        });
+
         self.uvector_s64_type = self.type_count;
         self.type_count += 1;
         self.types.ty().array(&StorageType::Val(ValType::I64), true);
@@ -449,6 +886,38 @@ impl<'a> ModuleGenerator<'a> {
         self.type_count += 1;
         self.types.ty().array(&StorageType::Val(ValType::F64), true);
 
+        // 要素の型が同じでも種類が異なるuvectorを区別するため、1つのrecグループにまとめる
+        let uvector_storage_types = [
+            StorageType::I8,
+            StorageType::I8,
+            StorageType::I16,
+            StorageType::I16,
+            StorageType::Val(ValType::I32),
+            StorageType::Val(ValType::I32),
+            StorageType::Val(ValType::F32),
+        ];
+        self.uvector_u8_type = self.type_count;
+        self.uvector_s8_type = self.type_count + 1;
+        self.uvector_u16_type = self.type_count + 2;
+        self.uvector_s16_type = self.type_count + 3;
+        self.uvector_u32_type = self.type_count + 4;
+        self.uvector_s32_type = self.type_count + 5;
+        self.uvector_f32_type = self.type_count + 6;
+        self.type_count += uvector_storage_types.len() as u32;
+        self.types
+            .ty()
+            .rec(uvector_storage_types.map(|element_type| SubType {
+                is_final: true,
+                supertype_idx: None,
+                composite_type: CompositeType {
+                    shared: false,
+                    inner: CompositeInnerType::Array(ArrayType(FieldType {
+                        element_type,
+                        mutable: true,
+                    })),
+                },
+            }));
+
         self.args_type = self.type_count;
         self.type_count += 1;
         self.types
@@ -582,6 +1051,30 @@ impl<'a> ModuleGenerator<'a> {
         );
         self.global_count += 1;
 
+        self.eof_global = Some(self.global_count);
+        self.imports.import(
+            "runtime",
+            "eof",
+            EntityType::Global(GlobalType {
+                val_type: ValType::Ref(RefType::EQREF),
+                mutable: false,
+                shared: false,
+            }),
+        );
+        self.global_count += 1;
+
+        self.eval_result_global = Some(self.global_count);
+        self.imports.import(
+            "runtime",
+            "eval_result",
+            EntityType::Global(GlobalType {
+                val_type: ValType::Ref(RefType::EQREF),
+                mutable: true,
+                shared: false,
+            }),
+        );
+        self.global_count += 1;
+
         for import_global in self
             .module
             .globals
@@ -630,27 +1123,46 @@ impl<'a> ModuleGenerator<'a> {
             );
         }
 
-        self.instantiate_func_func = self.add_runtime_function(
-            "instantiate_func",
-            WasmFuncType {
-                params: vec![ValType::I32, ValType::I32, ValType::I32, ValType::I32],
-                results: vec![ValType::I32],
-            },
-        );
-        self.instantiate_bb_func = self.add_runtime_function(
-            "instantiate_bb",
-            WasmFuncType {
-                params: vec![
-                    ValType::I32,
-                    ValType::I32,
-                    ValType::I32,
-                    ValType::I32,
-                    ValType::I32,
-                    ValType::I32,
-                ],
-                results: vec![ValType::I32],
-            },
-        );
+        // JITの呼び出しを含まないモジュール(AOTの出力など)はランタイムのコンパイラを必要としないのでインポートしない
+        let uses_jit_runtime = self
+            .module
+            .funcs
+            .values()
+            .flat_map(|func| func.bbs.values())
+            .flat_map(|bb| &bb.instrs)
+            .any(|instr| {
+                matches!(
+                    instr.kind,
+                    ir::InstrKind::InstantiateFunc(..)
+                        | ir::InstrKind::InstantiateClosureFunc(..)
+                        | ir::InstrKind::InstantiateBB(..)
+                        | ir::InstrKind::IncrementBranchCounter(..)
+                        | ir::InstrKind::IncrementBBLayoutCounter(..)
+                )
+            });
+        if uses_jit_runtime {
+            self.instantiate_func_func = self.add_runtime_function(
+                "instantiate_func",
+                WasmFuncType {
+                    params: vec![ValType::I32, ValType::I32, ValType::I32, ValType::I32],
+                    results: vec![ValType::I32],
+                },
+            );
+            self.instantiate_bb_func = self.add_runtime_function(
+                "instantiate_bb",
+                WasmFuncType {
+                    params: vec![
+                        ValType::I32,
+                        ValType::I32,
+                        ValType::I32,
+                        ValType::I32,
+                        ValType::I32,
+                        ValType::I32,
+                    ],
+                    results: vec![ValType::I32],
+                },
+            );
+        }
 
         self.display_func = self.add_runtime_function(
             "display",
@@ -718,43 +1230,12 @@ impl<'a> ModuleGenerator<'a> {
             },
         );
 
-        self.string_eq_func = self.add_runtime_function(
-            "string_eq",
-            WasmFuncType {
-                params: vec![
-                    ValType::Ref(RefType {
-                        nullable: true,
-                        heap_type: HeapType::Concrete(self.string_type),
-                    }),
-                    ValType::Ref(RefType {
-                        nullable: true,
-                        heap_type: HeapType::Concrete(self.string_type),
-                    }),
-                ],
-                results: vec![ValType::I32],
-            },
-        );
-
-        self.string_ref_func = self.add_runtime_function(
-            "string_ref",
-            WasmFuncType {
-                params: vec![
-                    ValType::Ref(RefType {
-                        nullable: true,
-                        heap_type: HeapType::Concrete(self.string_type),
-                    }),
-                    ValType::I32,
-                ],
-                results: vec![ValType::I32],
-            },
-        );
-
-        self.string_copy_func = self.add_runtime_function(
-            "string_copy",
+        self.bigint_to_string_func = self.add_runtime_function(
+            "bigint_to_string",
             WasmFuncType {
                 params: vec![ValType::Ref(RefType {
                     nullable: true,
-                    heap_type: HeapType::Concrete(self.string_type),
+                    heap_type: HeapType::Concrete(self.bigint_type),
                 })],
                 results: vec![ValType::Ref(RefType {
                     nullable: true,
@@ -763,6 +1244,211 @@ impl<'a> ModuleGenerator<'a> {
             },
         );
 
+        self.string_to_bigint_func = self.add_runtime_function(
+            "string_to_bigint",
+            WasmFuncType {
+                params: vec![ValType::Ref(RefType {
+                    nullable: true,
+                    heap_type: HeapType::Concrete(self.string_type),
+                })],
+                results: vec![ValType::Ref(RefType {
+                    nullable: true,
+                    heap_type: HeapType::Concrete(self.bigint_type),
+                })],
+            },
+        );
+
+        self.integer_binop_func = self.add_runtime_function(
+            "integer_binop",
+            WasmFuncType {
+                params: vec![
+                    ValType::I32,
+                    ValType::Ref(RefType::EQREF),
+                    ValType::Ref(RefType::EQREF),
+                ],
+                results: vec![ValType::Ref(RefType::EQREF)],
+            },
+        );
+
+        self.integer_cmp_func = self.add_runtime_function(
+            "integer_cmp",
+            WasmFuncType {
+                params: vec![ValType::Ref(RefType::EQREF), ValType::Ref(RefType::EQREF)],
+                results: vec![ValType::I32],
+            },
+        );
+
+        self.rational_to_string_func = self.add_runtime_function(
+            "rational_to_string",
+            WasmFuncType {
+                params: vec![ValType::Ref(RefType {
+                    nullable: true,
+                    heap_type: HeapType::Concrete(self.rational_type),
+                })],
+                results: vec![ValType::Ref(RefType {
+                    nullable: true,
+                    heap_type: HeapType::Concrete(self.string_type),
+                })],
+            },
+        );
+
+        self.rational_binop_func = self.add_runtime_function(
+            "rational_binop",
+            WasmFuncType {
+                params: vec![
+                    ValType::I32,
+                    ValType::Ref(RefType::EQREF),
+                    ValType::Ref(RefType::EQREF),
+                ],
+                results: vec![ValType::Ref(RefType::EQREF)],
+            },
+        );
+
+        self.rational_cmp_func = self.add_runtime_function(
+            "rational_cmp",
+            WasmFuncType {
+                params: vec![ValType::Ref(RefType::EQREF), ValType::Ref(RefType::EQREF)],
+                results: vec![ValType::I32],
+            },
+        );
+
+        self.rational_unop_func = self.add_runtime_function(
+            "rational_unop",
+            WasmFuncType {
+                params: vec![ValType::I32, ValType::Ref(RefType::EQREF)],
+                results: vec![ValType::Ref(RefType::EQREF)],
+            },
+        );
+
+        self.rational_to_float_func = self.add_runtime_function(
+            "rational_to_float",
+            WasmFuncType {
+                params: vec![ValType::Ref(RefType::EQREF)],
+                results: vec![ValType::F64],
+            },
+        );
+
+        self.float_to_exact_func = self.add_runtime_function(
+            "float_to_exact",
+            WasmFuncType {
+                params: vec![ValType::F64],
+                results: vec![ValType::Ref(RefType::EQREF)],
+            },
+        );
+
+        self.int_binop_func = self.add_runtime_function(
+            "int_binop",
+            WasmFuncType {
+                params: vec![ValType::I32, ValType::I64, ValType::I64],
+                results: vec![ValType::I64],
+            },
+        );
+
+        self.int_binop_overflow_func = self.add_runtime_function(
+            "int_binop_overflow",
+            WasmFuncType {
+                params: vec![ValType::I32, ValType::I64, ValType::I64],
+                results: vec![ValType::I32],
+            },
+        );
+
+        self.exact_integer_sqrt_int_func = self.add_runtime_function(
+            "exact_integer_sqrt_int",
+            WasmFuncType {
+                params: vec![ValType::I64],
+                results: vec![ValType::I64],
+            },
+        );
+
+        self.float_unop_func = self.add_runtime_function(
+            "float_unop",
+            WasmFuncType {
+                params: vec![ValType::I32, ValType::F64],
+                results: vec![ValType::F64],
+            },
+        );
+
+        self.float_binop_func = self.add_runtime_function(
+            "float_binop",
+            WasmFuncType {
+                params: vec![ValType::I32, ValType::F64, ValType::F64],
+                results: vec![ValType::F64],
+            },
+        );
+
+        self.string_eq_func = self.add_runtime_function(
+            "string_eq",
+            WasmFuncType {
+                params: vec![
+                    ValType::Ref(RefType {
+                        nullable: true,
+                        heap_type: HeapType::Concrete(self.string_type),
+                    }),
+                    ValType::Ref(RefType {
+                        nullable: true,
+                        heap_type: HeapType::Concrete(self.string_type),
+                    }),
+                ],
+                results: vec![ValType::I32],
+            },
+        );
+
+        self.string_ref_func = self.add_runtime_function(
+            "string_ref",
+            WasmFuncType {
+                params: vec![
+                    ValType::Ref(RefType {
+                        nullable: true,
+                        heap_type: HeapType::Concrete(self.string_type),
+                    }),
+                    ValType::I32,
+                ],
+                results: vec![ValType::I32],
+            },
+        );
+
+        self.string_copy_func = self.add_runtime_function(
+            "string_copy",
+            WasmFuncType {
+                params: vec![ValType::Ref(RefType {
+                    nullable: true,
+                    heap_type: HeapType::Concrete(self.string_type),
+                })],
+                results: vec![ValType::Ref(RefType {
+                    nullable: true,
+                    heap_type: HeapType::Concrete(self.string_type),
+                })],
+            },
+        );
+
+        self.utf8_to_string_func = self.add_runtime_function(
+            "utf8_to_string",
+            WasmFuncType {
+                params: vec![ValType::Ref(RefType {
+                    nullable: true,
+                    heap_type: HeapType::Concrete(self.uvector_u8_type),
+                })],
+                results: vec![ValType::Ref(RefType {
+                    nullable: true,
+                    heap_type: HeapType::Concrete(self.string_type),
+                })],
+            },
+        );
+
+        self.string_to_utf8_func = self.add_runtime_function(
+            "string_to_utf8",
+            WasmFuncType {
+                params: vec![ValType::Ref(RefType {
+                    nullable: true,
+                    heap_type: HeapType::Concrete(self.string_type),
+                })],
+                results: vec![ValType::Ref(RefType {
+                    nullable: true,
+                    heap_type: HeapType::Concrete(self.uvector_u8_type),
+                })],
+            },
+        );
+
         self.string_set_func = self.add_runtime_function(
             "string_set",
             WasmFuncType {
@@ -789,37 +1475,201 @@ impl<'a> ModuleGenerator<'a> {
             },
         );
 
-        self.args_to_list_func = self.add_runtime_function(
-            "args_to_list",
+        self.string_append_func = self.add_runtime_function(
+            "string_append",
             WasmFuncType {
                 params: vec![
                     ValType::Ref(RefType {
                         nullable: true,
-                        heap_type: HeapType::Concrete(self.args_type),
+                        heap_type: HeapType::Concrete(self.string_type),
+                    }),
+                    ValType::Ref(RefType {
+                        nullable: true,
+                        heap_type: HeapType::Concrete(self.string_type),
                     }),
-                    ValType::I32,
                 ],
-                results: vec![ValType::Ref(RefType::EQREF)],
+                results: vec![ValType::Ref(RefType {
+                    nullable: true,
+                    heap_type: HeapType::Concrete(self.string_type),
+                })],
             },
         );
 
-        self.increment_branch_counter_func = self.add_runtime_function(
-            "increment_branch_counter",
+        self.substring_func = self.add_runtime_function(
+            "substring",
             WasmFuncType {
                 params: vec![
+                    ValType::Ref(RefType {
+                        nullable: true,
+                        heap_type: HeapType::Concrete(self.string_type),
+                    }),
                     ValType::I32,
                     ValType::I32,
+                ],
+                results: vec![ValType::Ref(RefType {
+                    nullable: true,
+                    heap_type: HeapType::Concrete(self.string_type),
+                })],
+            },
+        );
+
+        self.make_string_func = self.add_runtime_function(
+            "make_string",
+            WasmFuncType {
+                params: vec![ValType::I32, ValType::I32],
+                results: vec![ValType::Ref(RefType {
+                    nullable: true,
+                    heap_type: HeapType::Concrete(self.string_type),
+                })],
+            },
+        );
+
+        self.list_to_string_func = self.add_runtime_function(
+            "list_to_string",
+            WasmFuncType {
+                params: vec![ValType::Ref(RefType::EQREF)],
+                results: vec![ValType::Ref(RefType {
+                    nullable: true,
+                    heap_type: HeapType::Concrete(self.string_type),
+                })],
+            },
+        );
+
+        self.string_to_list_func = self.add_runtime_function(
+            "string_to_list",
+            WasmFuncType {
+                params: vec![ValType::Ref(RefType {
+                    nullable: true,
+                    heap_type: HeapType::Concrete(self.string_type),
+                })],
+                results: vec![ValType::Ref(RefType::EQREF)],
+            },
+        );
+
+        self.string_cmp_func = self.add_runtime_function(
+            "string_cmp",
+            WasmFuncType {
+                params: vec![
+                    ValType::Ref(RefType {
+                        nullable: true,
+                        heap_type: HeapType::Concrete(self.string_type),
+                    }),
+                    ValType::Ref(RefType {
+                        nullable: true,
+                        heap_type: HeapType::Concrete(self.string_type),
+                    }),
+                ],
+                results: vec![ValType::I32],
+            },
+        );
+
+        self.string_cmp_ci_func = self.add_runtime_function(
+            "string_cmp_ci",
+            WasmFuncType {
+                params: vec![
+                    ValType::Ref(RefType {
+                        nullable: true,
+                        heap_type: HeapType::Concrete(self.string_type),
+                    }),
+                    ValType::Ref(RefType {
+                        nullable: true,
+                        heap_type: HeapType::Concrete(self.string_type),
+                    }),
+                ],
+                results: vec![ValType::I32],
+            },
+        );
+
+        self.string_to_number_func = self.add_runtime_function(
+            "string_to_number",
+            WasmFuncType {
+                params: vec![
+                    ValType::Ref(RefType {
+                        nullable: true,
+                        heap_type: HeapType::Concrete(self.string_type),
+                    }),
                     ValType::I32,
-                    ValType::I32,
-                    ValType::I32,
-                    ValType::I32,
-                    ValType::I32,
+                ],
+                results: vec![ValType::Ref(RefType::EQREF)],
+            },
+        );
+
+        self.char_op_func = self.add_runtime_function(
+            "char_op",
+            WasmFuncType {
+                params: vec![ValType::I32, ValType::I32],
+                results: vec![ValType::I32],
+            },
+        );
+
+        self.int_to_char_func = self.add_runtime_function(
+            "int_to_char",
+            WasmFuncType {
+                params: vec![ValType::I64],
+                results: vec![ValType::I32],
+            },
+        );
+
+        self.args_to_list_func = self.add_runtime_function(
+            "args_to_list",
+            WasmFuncType {
+                params: vec![
+                    ValType::Ref(RefType {
+                        nullable: true,
+                        heap_type: HeapType::Concrete(self.args_type),
+                    }),
                     ValType::I32,
                 ],
-                results: vec![],
+                results: vec![ValType::Ref(RefType::EQREF)],
+            },
+        );
+
+        self.list_to_args_func = self.add_runtime_function(
+            "list_to_args",
+            WasmFuncType {
+                params: vec![ValType::Ref(RefType::EQREF)],
+                results: vec![ValType::Ref(RefType {
+                    nullable: true,
+                    heap_type: HeapType::Concrete(self.args_type),
+                })],
             },
         );
 
+        if uses_jit_runtime {
+            self.increment_branch_counter_func = self.add_runtime_function(
+                "increment_branch_counter",
+                WasmFuncType {
+                    params: vec![
+                        ValType::I32,
+                        ValType::I32,
+                        ValType::I32,
+                        ValType::I32,
+                        ValType::I32,
+                        ValType::I32,
+                        ValType::I32,
+                        ValType::I32,
+                    ],
+                    results: vec![],
+                },
+            );
+
+            self.increment_bb_layout_counter_func = self.add_runtime_function(
+                "increment_bb_layout_counter",
+                WasmFuncType {
+                    params: vec![
+                        ValType::I32,
+                        ValType::I32,
+                        ValType::I32,
+                        ValType::I32,
+                        ValType::I32,
+                        ValType::I32,
+                        ValType::I32,
+                    ],
+                    results: vec![],
+                },
+            );
+        }
+
         self.throw_webassembly_exception = self.add_runtime_function(
             "throw_webassembly_exception",
             WasmFuncType {
@@ -828,6 +1678,255 @@ impl<'a> ModuleGenerator<'a> {
             },
         );
 
+        self.raise_func = self.add_runtime_function(
+            "raise",
+            WasmFuncType {
+                params: vec![ValType::Ref(RefType::EQREF)],
+                results: vec![ValType::Ref(RefType::EQREF)],
+            },
+        );
+
+        self.with_raise_handler_func = self.add_runtime_function(
+            "with_raise_handler",
+            WasmFuncType {
+                params: vec![
+                    ValType::Ref(RefType {
+                        nullable: true,
+                        heap_type: HeapType::Concrete(self.closure_type),
+                    }),
+                    ValType::Ref(RefType {
+                        nullable: true,
+                        heap_type: HeapType::Concrete(self.closure_type),
+                    }),
+                ],
+                results: vec![ValType::Ref(RefType::EQREF)],
+            },
+        );
+
+        self.set_current_output_fd_func = self.add_runtime_function(
+            "set_current_output_fd",
+            WasmFuncType {
+                params: vec![ValType::I32],
+                results: vec![],
+            },
+        );
+
+        self.write_char_fd_func = self.add_runtime_function(
+            "write_char_fd",
+            WasmFuncType {
+                params: vec![ValType::I32, ValType::I32],
+                results: vec![],
+            },
+        );
+
+        self.open_input_string_func = self.add_runtime_function(
+            "open_input_string",
+            WasmFuncType {
+                params: vec![ValType::Ref(RefType {
+                    nullable: true,
+                    heap_type: HeapType::Concrete(self.string_type),
+                })],
+                results: vec![ValType::Ref(RefType {
+                    nullable: true,
+                    heap_type: HeapType::Concrete(self.port_type),
+                })],
+            },
+        );
+
+        self.open_output_string_func = self.add_runtime_function(
+            "open_output_string",
+            WasmFuncType {
+                params: vec![],
+                results: vec![ValType::Ref(RefType {
+                    nullable: true,
+                    heap_type: HeapType::Concrete(self.port_type),
+                })],
+            },
+        );
+
+        self.standard_port_func = self.add_runtime_function(
+            "standard_port",
+            WasmFuncType {
+                params: vec![ValType::I32],
+                results: vec![ValType::Ref(RefType {
+                    nullable: true,
+                    heap_type: HeapType::Concrete(self.port_type),
+                })],
+            },
+        );
+
+        self.get_output_string_func = self.add_runtime_function(
+            "get_output_string",
+            WasmFuncType {
+                params: vec![ValType::Ref(RefType {
+                    nullable: true,
+                    heap_type: HeapType::Concrete(self.port_type),
+                })],
+                results: vec![ValType::Ref(RefType {
+                    nullable: true,
+                    heap_type: HeapType::Concrete(self.string_type),
+                })],
+            },
+        );
+
+        self.close_port_func = self.add_runtime_function(
+            "close_port",
+            WasmFuncType {
+                params: vec![ValType::Ref(RefType {
+                    nullable: true,
+                    heap_type: HeapType::Concrete(self.port_type),
+                })],
+                results: vec![],
+            },
+        );
+
+        self.read_char_func = self.add_runtime_function(
+            "read_char",
+            WasmFuncType {
+                params: vec![ValType::Ref(RefType {
+                    nullable: true,
+                    heap_type: HeapType::Concrete(self.port_type),
+                })],
+                results: vec![ValType::Ref(RefType::EQREF)],
+            },
+        );
+
+        self.peek_char_func = self.add_runtime_function(
+            "peek_char",
+            WasmFuncType {
+                params: vec![ValType::Ref(RefType {
+                    nullable: true,
+                    heap_type: HeapType::Concrete(self.port_type),
+                })],
+                results: vec![ValType::Ref(RefType::EQREF)],
+            },
+        );
+
+        self.read_func = self.add_runtime_function(
+            "read",
+            WasmFuncType {
+                params: vec![ValType::Ref(RefType {
+                    nullable: true,
+                    heap_type: HeapType::Concrete(self.port_type),
+                })],
+                results: vec![ValType::Ref(RefType::EQREF)],
+            },
+        );
+
+        self.eval_func = self.add_runtime_function(
+            "eval",
+            WasmFuncType {
+                params: vec![ValType::Ref(RefType::EQREF)],
+                results: vec![ValType::Ref(RefType::EQREF)],
+            },
+        );
+
+        self.make_hash_table_func = self.add_runtime_function(
+            "make_hash_table",
+            WasmFuncType {
+                params: vec![ValType::I32],
+                results: vec![ValType::Ref(RefType {
+                    nullable: true,
+                    heap_type: HeapType::Concrete(self.hash_table_type),
+                })],
+            },
+        );
+
+        self.hash_table_ref_func = self.add_runtime_function(
+            "hash_table_ref",
+            WasmFuncType {
+                params: vec![
+                    ValType::Ref(RefType {
+                        nullable: true,
+                        heap_type: HeapType::Concrete(self.hash_table_type),
+                    }),
+                    ValType::Ref(RefType::EQREF),
+                    ValType::Ref(RefType::EQREF),
+                ],
+                results: vec![ValType::Ref(RefType::EQREF)],
+            },
+        );
+
+        self.hash_table_set_func = self.add_runtime_function(
+            "hash_table_set",
+            WasmFuncType {
+                params: vec![
+                    ValType::Ref(RefType {
+                        nullable: true,
+                        heap_type: HeapType::Concrete(self.hash_table_type),
+                    }),
+                    ValType::Ref(RefType::EQREF),
+                    ValType::Ref(RefType::EQREF),
+                ],
+                results: vec![],
+            },
+        );
+
+        self.hash_table_delete_func = self.add_runtime_function(
+            "hash_table_delete",
+            WasmFuncType {
+                params: vec![
+                    ValType::Ref(RefType {
+                        nullable: true,
+                        heap_type: HeapType::Concrete(self.hash_table_type),
+                    }),
+                    ValType::Ref(RefType::EQREF),
+                ],
+                results: vec![],
+            },
+        );
+
+        self.hash_table_to_alist_func = self.add_runtime_function(
+            "hash_table_to_alist",
+            WasmFuncType {
+                params: vec![ValType::Ref(RefType {
+                    nullable: true,
+                    heap_type: HeapType::Concrete(self.hash_table_type),
+                })],
+                results: vec![ValType::Ref(RefType::EQREF)],
+            },
+        );
+
+        self.print_stack_frame_func = self.add_runtime_function(
+            "print_stack_frame",
+            WasmFuncType {
+                params: vec![ValType::I32],
+                results: vec![],
+            },
+        );
+
+        if self.config.embed_source_locs {
+            self.malloc_func = self.add_runtime_function(
+                "malloc",
+                WasmFuncType {
+                    params: vec![ValType::I32],
+                    results: vec![ValType::I32],
+                },
+            );
+            self.register_source_locs_func = self.add_runtime_function(
+                "register_source_locs",
+                WasmFuncType {
+                    params: vec![ValType::I32, ValType::I32],
+                    results: vec![],
+                },
+            );
+        }
+
+        // スタックトレースを出力するために各関数で捕捉する
+        let exception_tag_type = self.func_type(WasmFuncType {
+            params: vec![],
+            results: vec![],
+        });
+        self.imports.import(
+            "runtime",
+            "WEBSCHEMBLY_EXCEPTION",
+            EntityType::Tag(TagType {
+                kind: TagKind::Exception,
+                func_type_idx: exception_tag_type,
+            }),
+        );
+        self.webschembly_exception_tag = 0;
+
         for func in self.module.funcs.values() {
             let func_idx = self.func_count;
             self.func_count += 1;
@@ -840,11 +1939,13 @@ impl<'a> ModuleGenerator<'a> {
             FuncGenerator::new(&mut self, func).gen_func();
         }
 
-        self.exports.export(
-            "start",
-            ExportKind::Func,
-            self.func_indices[&self.module.entry],
-        );
+        let start_func_idx = if self.config.embed_source_locs {
+            self.gen_register_source_locs_start()
+        } else {
+            self.func_indices[&self.module.entry]
+        };
+        self.exports
+            .export("start", ExportKind::Func, start_func_idx);
 
         let mut module = Module::new();
         module
@@ -858,10 +1959,92 @@ impl<'a> ModuleGenerator<'a> {
             .section(&DataCountSection {
                 count: self.datas.len(),
             })
-            .section(&self.code)
-            .section(&self.datas);
+            .section(&self.code);
+        // コードセクションの末尾は関数本体列
+        let code_offset = module.len() - self.code.byte_len();
+        module.section(&self.datas);
 
-        module
+        if self.config.name_section {
+            let mut names = NameSection::new();
+            names.functions(&self.func_names);
+            names.locals(&self.local_names);
+            module.section(&names);
+        }
+
+        let source_map = self.config.source_map.as_ref().map(|url| {
+            let offsets = self
+                .source_loc_offsets
+                .iter()
+                .map(|&(offset, id)| (code_offset + offset, id))
+                .collect::<Vec<_>>();
+            let source_map = generate_source_map(&self.module.meta, &offsets);
+            let url = match url {
+                SourceMapUrl::Inline => format!(
+                    "data:application/json;base64,{}",
+                    encode_base64(source_map.as_bytes())
+                ),
+                SourceMapUrl::External(url) => url.clone(),
+            };
+            let mut data = Vec::new();
+            url.encode(&mut data);
+            module.section(&CustomSection {
+                name: Cow::Borrowed("sourceMappingURL"),
+                data: Cow::Owned(data),
+            });
+            source_map
+        });
+
+        Output {
+            wasm: module.finish(),
+            source_map,
+        }
+    }
+
+    // 位置情報をランタイムに登録してからエントリーポイントを実行する関数を生成する
+    // 位置情報は [id, 関数名, ファイル名, 行, 列] の配列のJSONとして渡す
+    fn gen_register_source_locs_start(&mut self) -> u32 {
+        let source_locs = self
+            .module
+            .meta
+            .source_locs
+            .iter()
+            .map(|(id, source_loc)| {
+                (
+                    usize::from(*id),
+                    &source_loc.func_name,
+                    &source_loc.file_name,
+                    source_loc.span.start.line,
+                    source_loc.span.start.column,
+                )
+            })
+            .collect::<Vec<_>>();
+        let bs = serde_json::to_vec(&source_locs).unwrap();
+        let data_index = self.datas.len();
+        self.datas.passive(bs.iter().copied());
+
+        let entry = &self.module.funcs[self.module.entry];
+        let type_idx = self.func_type_from_ir(&entry.func_type());
+        let mut function = Function::new(vec![(1, ValType::I32)]);
+        let ptr_local = 0;
+        function.instruction(&Instruction::I32Const(bs.len() as i32));
+        function.instruction(&Instruction::Call(self.malloc_func));
+        function.instruction(&Instruction::LocalSet(ptr_local));
+        function.instruction(&Instruction::LocalGet(ptr_local));
+        function.instruction(&Instruction::I32Const(0));
+        function.instruction(&Instruction::I32Const(bs.len() as i32));
+        function.instruction(&Instruction::MemoryInit { mem: 0, data_index });
+        function.instruction(&Instruction::DataDrop(data_index));
+        function.instruction(&Instruction::LocalGet(ptr_local));
+        function.instruction(&Instruction::I32Const(bs.len() as i32));
+        function.instruction(&Instruction::Call(self.register_source_locs_func));
+        function.instruction(&Instruction::ReturnCall(self.func_indices[&entry.id]));
+        function.instruction(&Instruction::End);
+
+        let func_idx = self.func_count;
+        self.func_count += 1;
+        self.functions.function(type_idx);
+        self.code.function(&function);
+        func_idx
     }
 
     fn global_id_to_idx(&mut self, global: ir::GlobalId) -> u32 {
@@ -917,6 +2100,14 @@ impl<'a> ModuleGenerator<'a> {
                 ir::ValType::Bool => ValType::I32,
                 ir::ValType::Int => ValType::I64,
                 ir::ValType::Float => ValType::F64,
+                ir::ValType::BigInt => ValType::Ref(RefType {
+                    nullable: true,
+                    heap_type: HeapType::Concrete(self.bigint_type),
+                }),
+                ir::ValType::Rational => ValType::Ref(RefType {
+                    nullable: true,
+                    heap_type: HeapType::Concrete(self.rational_type),
+                }),
                 ir::ValType::Char => ValType::I32,
                 ir::ValType::String => ValType::Ref(RefType {
                     nullable: true,
@@ -939,6 +2130,22 @@ impl<'a> ModuleGenerator<'a> {
                     nullable: true,
                     heap_type: HeapType::Concrete(self.vector_type),
                 }),
+                ir::ValType::ErrorObject => ValType::Ref(RefType {
+                    nullable: true,
+                    heap_type: HeapType::Concrete(self.error_object_type),
+                }),
+                ir::ValType::Port => ValType::Ref(RefType {
+                    nullable: true,
+                    heap_type: HeapType::Concrete(self.port_type),
+                }),
+                ir::ValType::HashTable => ValType::Ref(RefType {
+                    nullable: true,
+                    heap_type: HeapType::Concrete(self.hash_table_type),
+                }),
+                ir::ValType::Record(_) => ValType::Ref(RefType {
+                    nullable: true,
+                    heap_type: HeapType::Concrete(self.record_type),
+                }),
                 ir::ValType::UVector(kind) => ValType::Ref(RefType {
                     nullable: true,
                     heap_type: HeapType::Concrete(self.uvector_kind_to_type_idx(kind)),
@@ -968,6 +2175,12 @@ impl<'a> ModuleGenerator<'a> {
             ir::LocalType::Type(ir::Type::Val(ir::ValType::Bool)) => Instruction::I32Const(0),
             ir::LocalType::Type(ir::Type::Val(ir::ValType::Int)) => Instruction::I64Const(0),
             ir::LocalType::Type(ir::Type::Val(ir::ValType::Float)) => Instruction::F64Const(0.0),
+            ir::LocalType::Type(ir::Type::Val(ir::ValType::BigInt)) => {
+                Instruction::RefNull(HeapType::Concrete(self.bigint_type))
+            }
+            ir::LocalType::Type(ir::Type::Val(ir::ValType::Rational)) => {
+                Instruction::RefNull(HeapType::Concrete(self.rational_type))
+            }
             ir::LocalType::Type(ir::Type::Val(ir::ValType::Char)) => Instruction::I32Const(0),
             ir::LocalType::Type(ir::Type::Val(ir::ValType::String)) => {
                 Instruction::RefNull(HeapType::Concrete(self.string_type))
@@ -985,6 +2198,18 @@ impl<'a> ModuleGenerator<'a> {
             ir::LocalType::Type(ir::Type::Val(ir::ValType::Vector)) => {
                 Instruction::RefNull(HeapType::Concrete(self.vector_type))
             }
+            ir::LocalType::Type(ir::Type::Val(ir::ValType::ErrorObject)) => {
+                Instruction::RefNull(HeapType::Concrete(self.error_object_type))
+            }
+            ir::LocalType::Type(ir::Type::Val(ir::ValType::Port)) => {
+                Instruction::RefNull(HeapType::Concrete(self.port_type))
+            }
+            ir::LocalType::Type(ir::Type::Val(ir::ValType::HashTable)) => {
+                Instruction::RefNull(HeapType::Concrete(self.hash_table_type))
+            }
+            ir::LocalType::Type(ir::Type::Val(ir::ValType::Record(_))) => {
+                Instruction::RefNull(HeapType::Concrete(self.record_type))
+            }
             ir::LocalType::Type(ir::Type::Val(ir::ValType::UVector(kind))) => {
                 Instruction::RefNull(HeapType::Concrete(self.uvector_kind_to_type_idx(kind)))
             }
@@ -995,6 +2220,13 @@ impl<'a> ModuleGenerator<'a> {
         match kind {
             ir::UVectorKind::S64 => self.uvector_s64_type,
             ir::UVectorKind::F64 => self.uvector_f64_type,
+            ir::UVectorKind::U8 => self.uvector_u8_type,
+            ir::UVectorKind::S8 => self.uvector_s8_type,
+            ir::UVectorKind::U16 => self.uvector_u16_type,
+            ir::UVectorKind::S16 => self.uvector_s16_type,
+            ir::UVectorKind::U32 => self.uvector_u32_type,
+            ir::UVectorKind::S32 => self.uvector_s32_type,
+            ir::UVectorKind::F32 => self.uvector_f32_type,
         }
     }
 }
@@ -1005,6 +2237,10 @@ struct FuncGenerator<'a, 'b> {
     func: &'a ir::Func,
     local_count: u32,
     local_ids: FxHashMap<ir::LocalId, u32>,
+    // 現在実行中の位置(SourceLocId)を保持するローカル変数
+    source_loc_local: Option<u32>,
+    // (関数本体の先頭からのバイトオフセット, SourceLocId)
+    source_loc_offsets: Vec<(usize, ir::SourceLocId)>,
 }
 
 impl<'a, 'b> FuncGenerator<'a, 'b> {
@@ -1014,6 +2250,8 @@ impl<'a, 'b> FuncGenerator<'a, 'b> {
             func,
             local_count: 0,
             local_ids: FxHashMap::default(),
+            source_loc_local: None,
+            source_loc_offsets: Vec::new(),
         }
     }
 
@@ -1048,6 +2286,17 @@ impl<'a, 'b> FuncGenerator<'a, 'b> {
             .module_generator
             .func_type_from_ir(&self.func.func_type());
 
+        let has_source_loc = self
+            .func
+            .bbs
+            .values()
+            .flat_map(|bb| &bb.instrs)
+            .any(|instr| matches!(instr.kind, ir::InstrKind::SourceLoc(_)));
+        if has_source_loc {
+            self.source_loc_local = Some(self.local_count);
+            self.local_count += 1;
+        }
+
         // TODO: ここでローカル変数を定義していたらgen_exprでローカル変数を追加できない
         let mut function = Function::new(
             self.func
@@ -1058,19 +2307,86 @@ impl<'a, 'b> FuncGenerator<'a, 'b> {
                     let ty = self.module_generator.convert_local_type(local.typ);
                     (1, ty)
                 })
+                .chain(self.source_loc_local.map(|_| (1, ValType::I32)))
                 .collect::<Vec<_>>(),
         );
 
+        if let Some(source_loc_local) = self.source_loc_local {
+            // 例外が通過したときに自身のフレームを出力して再送出する
+            function.instruction(&Instruction::I32Const(-1));
+            function.instruction(&Instruction::LocalSet(source_loc_local));
+            function.instruction(&Instruction::Block(BlockType::Result(ValType::EXNREF)));
+            function.instruction(&Instruction::TryTable(
+                BlockType::Empty,
+                Cow::Owned(vec![Catch::OneRef {
+                    tag: self.module_generator.webschembly_exception_tag,
+                    label: 0,
+                }]),
+            ));
+        }
+
         let structured_bbs = reloop(self.func);
         for structured_bb in &structured_bbs {
             self.gen_bb(&mut function, self.func, structured_bb);
         }
 
         function.instruction(&Instruction::Unreachable); // TODO: 型チェックを通すため
+        if let Some(source_loc_local) = self.source_loc_local {
+            function.instruction(&Instruction::End);
+            function.instruction(&Instruction::Unreachable);
+            function.instruction(&Instruction::End);
+            function.instruction(&Instruction::LocalGet(source_loc_local));
+            function.instruction(&Instruction::Call(
+                self.module_generator.print_stack_frame_func,
+            ));
+            function.instruction(&Instruction::ThrowRef);
+        }
         function.instruction(&Instruction::End);
 
         self.module_generator.functions.function(type_idx);
+        // 関数本体はサイズのLEB128が先頭に付く
+        let mut size = Vec::new();
+        function.byte_len().encode(&mut size);
+        let body_offset = self.module_generator.code.byte_len() + size.len();
+        self.module_generator.source_loc_offsets.extend(
+            self.source_loc_offsets
+                .iter()
+                .map(|&(offset, id)| (body_offset + offset, id)),
+        );
         self.module_generator.code.function(&function);
+
+        if self.module_generator.config.name_section {
+            self.gen_names();
+        }
+    }
+
+    fn gen_names(&mut self) {
+        let meta = &self.module_generator.module.meta;
+        let func_idx = self.module_generator.func_indices[&self.func.id];
+        self.module_generator
+            .func_names
+            .append(func_idx, &self.func.id.display(meta).to_string());
+
+        let mut local_names = self
+            .local_ids
+            .iter()
+            .map(|(&local_id, &idx)| {
+                (
+                    idx,
+                    local_id.display(meta.in_func(self.func.id)).to_string(),
+                )
+            })
+            .chain(
+                self.source_loc_local
+                    .map(|idx| (idx, "source_loc".to_string())),
+            )
+            .collect::<Vec<_>>();
+        local_names.sort_by_key(|(idx, _)| *idx);
+        let mut names = NameMap::new();
+        for (idx, name) in &local_names {
+            names.append(*idx, name);
+        }
+        self.module_generator.local_names.append(func_idx, &names);
     }
 
     fn gen_bb(&mut self, function: &mut Function, func: &ir::Func, structured_bb: &Structured) {
@@ -1147,6 +2463,13 @@ impl<'a, 'b> FuncGenerator<'a, 'b> {
             debug_assert!(expr.local.is_none());
             return;
         }
+        if let ir::InstrKind::SourceLoc(id) = expr.kind {
+            debug_assert!(expr.local.is_none());
+            self.source_loc_offsets.push((function.byte_len(), id));
+            function.instruction(&Instruction::I32Const(usize::from(id) as i32));
+            function.instruction(&Instruction::LocalSet(self.source_loc_local.unwrap()));
+            return;
+        }
         self.gen_expr(function, &expr.kind);
         if let Some(local) = &expr.local {
             function.instruction(&Instruction::LocalSet(self.local_id_to_idx(*local)));
@@ -1163,6 +2486,9 @@ impl<'a, 'b> FuncGenerator<'a, 'b> {
             ir::InstrKind::Phi { .. } => {
                 unreachable!("unexpected Phi");
             }
+            ir::InstrKind::SourceLoc(_) => {
+                unreachable!("unexpected SourceLoc");
+            }
             ir::InstrKind::Terminator(_) => {
                 unreachable!("unexpected Terminator");
             }
@@ -1227,12 +2553,50 @@ impl<'a, 'b> FuncGenerator<'a, 'b> {
                 ));
                 function.instruction(&Instruction::I32Const(0));
             }
+            ir::InstrKind::IncrementBBLayoutCounter(
+                counter,
+                module_id,
+                func_id,
+                env_index,
+                func_index,
+                bb_id,
+                index,
+                kind,
+            ) => {
+                // 毎回ランタイムを呼び出すと遅いので、一定回数ごとにまとめて通知する
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*counter)));
+                function.instruction(&Instruction::I64Const(ir::BB_LAYOUT_COUNTER_INTERVAL - 1));
+                function.instruction(&Instruction::I64And);
+                function.instruction(&Instruction::I64Eqz);
+                function.instruction(&Instruction::If(BlockType::Empty));
+                function.instruction(&Instruction::I32Const(usize::from(*module_id) as i32));
+                function.instruction(&Instruction::I32Const(usize::from(*func_id) as i32));
+                function.instruction(&Instruction::I32Const(*env_index as i32));
+                function.instruction(&Instruction::I32Const(*func_index as i32));
+                function.instruction(&Instruction::I32Const(usize::from(*bb_id) as i32));
+                function.instruction(&Instruction::I32Const(*index as i32));
+                function.instruction(&Instruction::I32Const(match kind {
+                    ir::BBLayoutCounterKind::Hit => 0,
+                    ir::BBLayoutCounterKind::GuardFailure => 1,
+                }));
+                function.instruction(&Instruction::Call(
+                    self.module_generator.increment_bb_layout_counter_func,
+                ));
+                function.instruction(&Instruction::End);
+                function.instruction(&Instruction::I32Const(0));
+            }
             ir::InstrKind::Bool(b) => {
                 function.instruction(&Instruction::I32Const(if *b { 1 } else { 0 }));
             }
             ir::InstrKind::Int(i) => {
                 function.instruction(&Instruction::I64Const(*i));
             }
+            ir::InstrKind::BigInt(s) => {
+                self.gen_string(function, s);
+                function.instruction(&Instruction::Call(
+                    self.module_generator.string_to_bigint_func,
+                ));
+            }
             ir::InstrKind::Float(f) => {
                 function.instruction(&Instruction::F64Const(f64::from(*f)));
             }
@@ -1243,33 +2607,7 @@ impl<'a, 'b> FuncGenerator<'a, 'b> {
                 function.instruction(&Instruction::I32Const(*c as i32));
             }
             ir::InstrKind::String(s) => {
-                // TODO: 重複リテラルを共有
-                let bs = s.as_bytes();
-                let data_index = self.module_generator.datas.len();
-                self.module_generator.datas.passive(bs.iter().copied());
-
-                // data offset
-                function.instruction(&Instruction::I32Const(0));
-                // data len
-                function.instruction(&Instruction::I32Const(bs.len() as i32));
-                // StringBuf.buf
-                function.instruction(&Instruction::ArrayNewData {
-                    array_type_index: self.module_generator.buf_type,
-                    array_data_index: data_index,
-                });
-
-                // StringBuf.shared
-                function.instruction(&Instruction::I32Const(0));
-                // String.buf
-                function.instruction(&Instruction::StructNew(
-                    self.module_generator.string_buf_type,
-                ));
-
-                // String.len
-                function.instruction(&Instruction::I32Const(bs.len() as i32));
-                // String.offset
-                function.instruction(&Instruction::I32Const(0));
-                function.instruction(&Instruction::StructNew(self.module_generator.string_type));
+                self.gen_string(function, s);
             }
             ir::InstrKind::StringToSymbol(s) => {
                 function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*s)));
@@ -1297,6 +2635,7 @@ impl<'a, 'b> FuncGenerator<'a, 'b> {
             ir::InstrKind::UVector(kind, vec) => {
                 for elem in vec.iter() {
                     function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*elem)));
+                    Self::gen_uvector_element_to_storage(function, *kind);
                 }
                 function.instruction(&Instruction::ArrayNewFixed {
                     array_type_index: self.module_generator.uvector_kind_to_type_idx(*kind),
@@ -1466,6 +2805,18 @@ impl<'a, 'b> FuncGenerator<'a, 'b> {
                         field_index: ModuleGenerator::CHAR_VALUE_FIELD,
                     });
                 }
+                ir::ValType::BigInt => {
+                    function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*val)));
+                    function.instruction(&Instruction::RefCastNonNull(HeapType::Concrete(
+                        self.module_generator.bigint_type,
+                    )));
+                }
+                ir::ValType::Rational => {
+                    function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*val)));
+                    function.instruction(&Instruction::RefCastNonNull(HeapType::Concrete(
+                        self.module_generator.rational_type,
+                    )));
+                }
                 ir::ValType::String => {
                     function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*val)));
                     function.instruction(&Instruction::RefCastNonNull(HeapType::Concrete(
@@ -1506,6 +2857,30 @@ impl<'a, 'b> FuncGenerator<'a, 'b> {
                         self.module_generator.uvector_kind_to_type_idx(*kind),
                     )));
                 }
+                ir::ValType::ErrorObject => {
+                    function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*val)));
+                    function.instruction(&Instruction::RefCastNonNull(HeapType::Concrete(
+                        self.module_generator.error_object_type,
+                    )));
+                }
+                ir::ValType::Port => {
+                    function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*val)));
+                    function.instruction(&Instruction::RefCastNonNull(HeapType::Concrete(
+                        self.module_generator.port_type,
+                    )));
+                }
+                ir::ValType::HashTable => {
+                    function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*val)));
+                    function.instruction(&Instruction::RefCastNonNull(HeapType::Concrete(
+                        self.module_generator.hash_table_type,
+                    )));
+                }
+                ir::ValType::Record(_) => {
+                    function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*val)));
+                    function.instruction(&Instruction::RefCastNonNull(HeapType::Concrete(
+                        self.module_generator.record_type,
+                    )));
+                }
             },
             ir::InstrKind::ToObj(typ, val) => match typ {
                 ir::ValType::Bool => {
@@ -1533,6 +2908,9 @@ impl<'a, 'b> FuncGenerator<'a, 'b> {
                     function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*val)));
                     function.instruction(&Instruction::StructNew(self.module_generator.float_type));
                 }
+                ir::ValType::BigInt | ir::ValType::Rational => {
+                    function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*val)));
+                }
                 ir::ValType::Char => {
                     function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*val)));
                     function.instruction(&Instruction::StructNew(self.module_generator.char_type));
@@ -1557,7 +2935,11 @@ impl<'a, 'b> FuncGenerator<'a, 'b> {
                 ir::ValType::Vector => {
                     function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*val)));
                 }
-                ir::ValType::UVector(_) => {
+                ir::ValType::UVector(_)
+                | ir::ValType::ErrorObject
+                | ir::ValType::Port
+                | ir::ValType::HashTable
+                | ir::ValType::Record(_) => {
                     function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*val)));
                 }
             },
@@ -1661,21 +3043,345 @@ impl<'a, 'b> FuncGenerator<'a, 'b> {
                 function.instruction(&Instruction::I64Const(0));
                 function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*lhs)));
                 function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*rhs)));
-                function.instruction(&Instruction::I64Xor);
-                function.instruction(&Instruction::I64Const(0));
-                function.instruction(&Instruction::I64LtS);
-
-                // rem != 0
+                function.instruction(&Instruction::I64Xor);
+                function.instruction(&Instruction::I64Const(0));
+                function.instruction(&Instruction::I64LtS);
+
+                // rem != 0
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*lhs)));
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*rhs)));
+                function.instruction(&Instruction::I64RemS);
+                function.instruction(&Instruction::I64Const(0));
+                function.instruction(&Instruction::I64Ne);
+
+                function.instruction(&Instruction::I32And);
+
+                function.instruction(&Instruction::Select);
+                function.instruction(&Instruction::I64Add);
+            }
+            ir::InstrKind::AddIntOverflow(lhs, rhs) => {
+                // ((lhs ^ (lhs + rhs)) & (rhs ^ (lhs + rhs))) < 0
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*lhs)));
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*lhs)));
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*rhs)));
+                function.instruction(&Instruction::I64Add);
+                function.instruction(&Instruction::I64Xor);
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*rhs)));
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*lhs)));
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*rhs)));
+                function.instruction(&Instruction::I64Add);
+                function.instruction(&Instruction::I64Xor);
+                function.instruction(&Instruction::I64And);
+                function.instruction(&Instruction::I64Const(0));
+                function.instruction(&Instruction::I64LtS);
+            }
+            ir::InstrKind::SubIntOverflow(lhs, rhs) => {
+                // ((lhs ^ rhs) & (lhs ^ (lhs - rhs))) < 0
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*lhs)));
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*rhs)));
+                function.instruction(&Instruction::I64Xor);
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*lhs)));
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*lhs)));
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*rhs)));
+                function.instruction(&Instruction::I64Sub);
+                function.instruction(&Instruction::I64Xor);
+                function.instruction(&Instruction::I64And);
+                function.instruction(&Instruction::I64Const(0));
+                function.instruction(&Instruction::I64LtS);
+            }
+            ir::InstrKind::MulIntOverflow(lhs, rhs) => {
+                // if lhs == 0 {
+                //     false
+                // } else if lhs == -1 {
+                //     rhs == i64::MIN
+                // } else {
+                //     (lhs * rhs) / lhs != rhs
+                // }
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*lhs)));
+                function.instruction(&Instruction::I64Eqz);
+                function.instruction(&Instruction::If(BlockType::Result(ValType::I32)));
+                function.instruction(&Instruction::I32Const(0));
+                function.instruction(&Instruction::Else);
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*lhs)));
+                function.instruction(&Instruction::I64Const(-1));
+                function.instruction(&Instruction::I64Eq);
+                function.instruction(&Instruction::If(BlockType::Result(ValType::I32)));
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*rhs)));
+                function.instruction(&Instruction::I64Const(i64::MIN));
+                function.instruction(&Instruction::I64Eq);
+                function.instruction(&Instruction::Else);
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*lhs)));
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*rhs)));
+                function.instruction(&Instruction::I64Mul);
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*lhs)));
+                function.instruction(&Instruction::I64DivS);
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*rhs)));
+                function.instruction(&Instruction::I64Ne);
+                function.instruction(&Instruction::End);
+                function.instruction(&Instruction::End);
+            }
+            ir::InstrKind::QuotientIntOverflow(lhs, rhs) => {
+                // rhs == 0 || (lhs == i64::MIN && rhs == -1)
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*rhs)));
+                function.instruction(&Instruction::I64Eqz);
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*lhs)));
+                function.instruction(&Instruction::I64Const(i64::MIN));
+                function.instruction(&Instruction::I64Eq);
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*rhs)));
+                function.instruction(&Instruction::I64Const(-1));
+                function.instruction(&Instruction::I64Eq);
+                function.instruction(&Instruction::I32And);
+                function.instruction(&Instruction::I32Or);
+            }
+            ir::InstrKind::AddInteger(lhs, rhs) => {
+                self.gen_integer_binop(function, IntegerBinOp::Add, *lhs, *rhs);
+            }
+            ir::InstrKind::SubInteger(lhs, rhs) => {
+                self.gen_integer_binop(function, IntegerBinOp::Sub, *lhs, *rhs);
+            }
+            ir::InstrKind::MulInteger(lhs, rhs) => {
+                self.gen_integer_binop(function, IntegerBinOp::Mul, *lhs, *rhs);
+            }
+            ir::InstrKind::QuotientInteger(lhs, rhs) => {
+                self.gen_integer_binop(function, IntegerBinOp::Quotient, *lhs, *rhs);
+            }
+            ir::InstrKind::RemainderInteger(lhs, rhs) => {
+                self.gen_integer_binop(function, IntegerBinOp::Remainder, *lhs, *rhs);
+            }
+            ir::InstrKind::ModuloInteger(lhs, rhs) => {
+                self.gen_integer_binop(function, IntegerBinOp::Modulo, *lhs, *rhs);
+            }
+            ir::InstrKind::CmpInteger(lhs, rhs) => {
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*lhs)));
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*rhs)));
+                function.instruction(&Instruction::Call(self.module_generator.integer_cmp_func));
+                function.instruction(&Instruction::I64ExtendI32S);
+            }
+            ir::InstrKind::Rational(num, den) => {
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*num)));
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*den)));
+                function.instruction(&Instruction::StructNew(self.module_generator.rational_type));
+            }
+            ir::InstrKind::Numerator(val) => {
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*val)));
+                function.instruction(&Instruction::StructGet {
+                    struct_type_index: self.module_generator.rational_type,
+                    field_index: ModuleGenerator::RATIONAL_NUM_FIELD,
+                });
+            }
+            ir::InstrKind::Denominator(val) => {
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*val)));
+                function.instruction(&Instruction::StructGet {
+                    struct_type_index: self.module_generator.rational_type,
+                    field_index: ModuleGenerator::RATIONAL_DEN_FIELD,
+                });
+            }
+            ir::InstrKind::AddRational(lhs, rhs) => {
+                self.gen_rational_binop(function, RationalBinOp::Add, *lhs, *rhs);
+            }
+            ir::InstrKind::SubRational(lhs, rhs) => {
+                self.gen_rational_binop(function, RationalBinOp::Sub, *lhs, *rhs);
+            }
+            ir::InstrKind::MulRational(lhs, rhs) => {
+                self.gen_rational_binop(function, RationalBinOp::Mul, *lhs, *rhs);
+            }
+            ir::InstrKind::DivRational(lhs, rhs) => {
+                self.gen_rational_binop(function, RationalBinOp::Div, *lhs, *rhs);
+            }
+            ir::InstrKind::CmpRational(lhs, rhs) => {
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*lhs)));
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*rhs)));
+                function.instruction(&Instruction::Call(self.module_generator.rational_cmp_func));
+                function.instruction(&Instruction::I64ExtendI32S);
+            }
+            ir::InstrKind::FloorRational(val) => {
+                function.instruction(&Instruction::I32Const(RationalUnOp::Floor as i32));
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*val)));
+                function.instruction(&Instruction::Call(self.module_generator.rational_unop_func));
+            }
+            ir::InstrKind::RoundRational(val) => {
+                function.instruction(&Instruction::I32Const(RationalUnOp::Round as i32));
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*val)));
+                function.instruction(&Instruction::Call(self.module_generator.rational_unop_func));
+            }
+            ir::InstrKind::RationalToFloat(val) => {
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*val)));
+                function.instruction(&Instruction::Call(
+                    self.module_generator.rational_to_float_func,
+                ));
+            }
+            ir::InstrKind::FloatToExact(val) => {
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*val)));
+                function.instruction(&Instruction::Call(
+                    self.module_generator.float_to_exact_func,
+                ));
+            }
+            ir::InstrKind::IntToFloat(val) => {
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*val)));
+                function.instruction(&Instruction::F64ConvertI64S);
+            }
+            ir::InstrKind::FloorFloat(val) => {
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*val)));
+                function.instruction(&Instruction::F64Floor);
+            }
+            ir::InstrKind::RoundFloat(val) => {
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*val)));
+                function.instruction(&Instruction::F64Nearest);
+            }
+            ir::InstrKind::CeilingRational(val) => {
+                function.instruction(&Instruction::I32Const(RationalUnOp::Ceiling as i32));
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*val)));
+                function.instruction(&Instruction::Call(self.module_generator.rational_unop_func));
+            }
+            ir::InstrKind::TruncateRational(val) => {
+                function.instruction(&Instruction::I32Const(RationalUnOp::Truncate as i32));
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*val)));
+                function.instruction(&Instruction::Call(self.module_generator.rational_unop_func));
+            }
+            ir::InstrKind::ExactIntegerSqrtInteger(val) => {
+                function.instruction(&Instruction::I32Const(
+                    RationalUnOp::ExactIntegerSqrt as i32,
+                ));
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*val)));
+                function.instruction(&Instruction::Call(self.module_generator.rational_unop_func));
+            }
+            ir::InstrKind::CeilingFloat(val) => {
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*val)));
+                function.instruction(&Instruction::F64Ceil);
+            }
+            ir::InstrKind::TruncateFloat(val) => {
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*val)));
+                function.instruction(&Instruction::F64Trunc);
+            }
+            ir::InstrKind::AbsInt(val) => {
+                // select(0 - x, x, x < 0)
+                function.instruction(&Instruction::I64Const(0));
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*val)));
+                function.instruction(&Instruction::I64Sub);
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*val)));
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*val)));
+                function.instruction(&Instruction::I64Const(0));
+                function.instruction(&Instruction::I64LtS);
+                function.instruction(&Instruction::Select);
+            }
+            ir::InstrKind::AbsFloat(val) => {
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*val)));
+                function.instruction(&Instruction::F64Abs);
+            }
+            ir::InstrKind::MinInt(lhs, rhs) => {
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*lhs)));
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*rhs)));
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*lhs)));
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*rhs)));
+                function.instruction(&Instruction::I64LtS);
+                function.instruction(&Instruction::Select);
+            }
+            ir::InstrKind::MaxInt(lhs, rhs) => {
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*lhs)));
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*rhs)));
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*lhs)));
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*rhs)));
+                function.instruction(&Instruction::I64GtS);
+                function.instruction(&Instruction::Select);
+            }
+            ir::InstrKind::MinFloat(lhs, rhs) => {
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*lhs)));
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*rhs)));
+                function.instruction(&Instruction::F64Min);
+            }
+            ir::InstrKind::MaxFloat(lhs, rhs) => {
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*lhs)));
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*rhs)));
+                function.instruction(&Instruction::F64Max);
+            }
+            ir::InstrKind::GcdInt(lhs, rhs) => {
+                self.gen_int_binop(function, IntBinOp::Gcd, *lhs, *rhs, false);
+            }
+            ir::InstrKind::GcdIntOverflow(lhs, rhs) => {
+                self.gen_int_binop(function, IntBinOp::Gcd, *lhs, *rhs, true);
+            }
+            ir::InstrKind::LcmInt(lhs, rhs) => {
+                self.gen_int_binop(function, IntBinOp::Lcm, *lhs, *rhs, false);
+            }
+            ir::InstrKind::LcmIntOverflow(lhs, rhs) => {
+                self.gen_int_binop(function, IntBinOp::Lcm, *lhs, *rhs, true);
+            }
+            ir::InstrKind::ExptInt(lhs, rhs) => {
+                self.gen_int_binop(function, IntBinOp::Expt, *lhs, *rhs, false);
+            }
+            ir::InstrKind::ExptIntOverflow(lhs, rhs) => {
+                self.gen_int_binop(function, IntBinOp::Expt, *lhs, *rhs, true);
+            }
+            ir::InstrKind::GcdInteger(lhs, rhs) => {
+                self.gen_integer_binop(function, IntegerBinOp::Gcd, *lhs, *rhs);
+            }
+            ir::InstrKind::LcmInteger(lhs, rhs) => {
+                self.gen_integer_binop(function, IntegerBinOp::Lcm, *lhs, *rhs);
+            }
+            ir::InstrKind::ExptRational(lhs, rhs) => {
+                self.gen_rational_binop(function, RationalBinOp::Expt, *lhs, *rhs);
+            }
+            ir::InstrKind::ExactIntegerSqrtInt(val) => {
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*val)));
+                function.instruction(&Instruction::Call(
+                    self.module_generator.exact_integer_sqrt_int_func,
+                ));
+            }
+            ir::InstrKind::SqrtFloat(val) => {
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*val)));
+                function.instruction(&Instruction::F64Sqrt);
+            }
+            ir::InstrKind::ExpFloat(val) => {
+                function.instruction(&Instruction::I32Const(FloatUnOp::Exp as i32));
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*val)));
+                function.instruction(&Instruction::Call(self.module_generator.float_unop_func));
+            }
+            ir::InstrKind::LogFloat(val) => {
+                function.instruction(&Instruction::I32Const(FloatUnOp::Log as i32));
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*val)));
+                function.instruction(&Instruction::Call(self.module_generator.float_unop_func));
+            }
+            ir::InstrKind::SinFloat(val) => {
+                function.instruction(&Instruction::I32Const(FloatUnOp::Sin as i32));
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*val)));
+                function.instruction(&Instruction::Call(self.module_generator.float_unop_func));
+            }
+            ir::InstrKind::CosFloat(val) => {
+                function.instruction(&Instruction::I32Const(FloatUnOp::Cos as i32));
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*val)));
+                function.instruction(&Instruction::Call(self.module_generator.float_unop_func));
+            }
+            ir::InstrKind::TanFloat(val) => {
+                function.instruction(&Instruction::I32Const(FloatUnOp::Tan as i32));
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*val)));
+                function.instruction(&Instruction::Call(self.module_generator.float_unop_func));
+            }
+            ir::InstrKind::AsinFloat(val) => {
+                function.instruction(&Instruction::I32Const(FloatUnOp::Asin as i32));
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*val)));
+                function.instruction(&Instruction::Call(self.module_generator.float_unop_func));
+            }
+            ir::InstrKind::AcosFloat(val) => {
+                function.instruction(&Instruction::I32Const(FloatUnOp::Acos as i32));
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*val)));
+                function.instruction(&Instruction::Call(self.module_generator.float_unop_func));
+            }
+            ir::InstrKind::AtanFloat(val) => {
+                function.instruction(&Instruction::I32Const(FloatUnOp::Atan as i32));
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*val)));
+                function.instruction(&Instruction::Call(self.module_generator.float_unop_func));
+            }
+            ir::InstrKind::Atan2Float(lhs, rhs) => {
+                function.instruction(&Instruction::I32Const(FloatBinOp::Atan2 as i32));
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*lhs)));
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*rhs)));
+                function.instruction(&Instruction::Call(self.module_generator.float_binop_func));
+            }
+            ir::InstrKind::ExptFloat(lhs, rhs) => {
+                function.instruction(&Instruction::I32Const(FloatBinOp::Expt as i32));
                 function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*lhs)));
                 function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*rhs)));
-                function.instruction(&Instruction::I64RemS);
-                function.instruction(&Instruction::I64Const(0));
-                function.instruction(&Instruction::I64Ne);
-
-                function.instruction(&Instruction::I32And);
-
-                function.instruction(&Instruction::Select);
-                function.instruction(&Instruction::I64Add);
+                function.instruction(&Instruction::Call(self.module_generator.float_binop_func));
             }
             ir::InstrKind::DivFloat(lhs, rhs) => {
                 function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*lhs)));
@@ -1687,6 +3393,27 @@ impl<'a, 'b> FuncGenerator<'a, 'b> {
                 function.instruction(&Instruction::Call(self.module_generator.write_char_func));
                 function.instruction(&Instruction::I32Const(0));
             }
+            ir::InstrKind::Is(ir::ValType::Record(id), val) => {
+                // 全てのレコード型で共通の構造体なので、IDも比較する
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*val)));
+                function.instruction(&Instruction::RefTestNonNull(HeapType::Concrete(
+                    self.module_generator.record_type,
+                )));
+                function.instruction(&Instruction::If(BlockType::Result(ValType::I32)));
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*val)));
+                function.instruction(&Instruction::RefCastNonNull(HeapType::Concrete(
+                    self.module_generator.record_type,
+                )));
+                function.instruction(&Instruction::StructGet {
+                    struct_type_index: self.module_generator.record_type,
+                    field_index: ModuleGenerator::RECORD_ID_FIELD,
+                });
+                function.instruction(&Instruction::I32Const(usize::from(*id) as i32));
+                function.instruction(&Instruction::I32Eq);
+                function.instruction(&Instruction::Else);
+                function.instruction(&Instruction::I32Const(0));
+                function.instruction(&Instruction::End);
+            }
             ir::InstrKind::Is(typ, val) => {
                 function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*val)));
                 function.instruction(&Instruction::RefTestNonNull(HeapType::Concrete(
@@ -1694,6 +3421,8 @@ impl<'a, 'b> FuncGenerator<'a, 'b> {
                         ir::ValType::Bool => self.module_generator.bool_type,
                         ir::ValType::Int => self.module_generator.int_type,
                         ir::ValType::Float => self.module_generator.float_type,
+                        ir::ValType::BigInt => self.module_generator.bigint_type,
+                        ir::ValType::Rational => self.module_generator.rational_type,
                         ir::ValType::Char => self.module_generator.char_type,
                         ir::ValType::String => self.module_generator.string_type,
                         ir::ValType::Symbol => self.module_generator.symbol_type,
@@ -1701,6 +3430,10 @@ impl<'a, 'b> FuncGenerator<'a, 'b> {
                         ir::ValType::Cons => self.module_generator.cons_type,
                         ir::ValType::Closure => self.module_generator.closure_type,
                         ir::ValType::Vector => self.module_generator.vector_type,
+                        ir::ValType::ErrorObject => self.module_generator.error_object_type,
+                        ir::ValType::Port => self.module_generator.port_type,
+                        ir::ValType::HashTable => self.module_generator.hash_table_type,
+                        ir::ValType::Record(_) => unreachable!(),
                         ir::ValType::UVector(kind) => {
                             self.module_generator.uvector_kind_to_type_idx(*kind)
                         }
@@ -1735,15 +3468,14 @@ impl<'a, 'b> FuncGenerator<'a, 'b> {
                 function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*vector)));
                 function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*index)));
                 function.instruction(&Instruction::I32WrapI64);
-                function.instruction(&Instruction::ArrayGet(
-                    self.module_generator.uvector_kind_to_type_idx(*kind),
-                ));
+                self.gen_uvector_get(function, *kind);
             }
             ir::InstrKind::UVectorSet(kind, vector, index, val) => {
                 function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*vector)));
                 function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*index)));
                 function.instruction(&Instruction::I32WrapI64);
                 function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*val)));
+                Self::gen_uvector_element_to_storage(function, *kind);
                 function.instruction(&Instruction::ArraySet(
                     self.module_generator.uvector_kind_to_type_idx(*kind),
                 ));
@@ -1811,6 +3543,18 @@ impl<'a, 'b> FuncGenerator<'a, 'b> {
                 function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*val)));
                 function.instruction(&Instruction::Call(self.module_generator.int_to_string_func));
             }
+            ir::InstrKind::BigIntToString(val) => {
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*val)));
+                function.instruction(&Instruction::Call(
+                    self.module_generator.bigint_to_string_func,
+                ));
+            }
+            ir::InstrKind::RationalToString(val) => {
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*val)));
+                function.instruction(&Instruction::Call(
+                    self.module_generator.rational_to_string_func,
+                ));
+            }
             ir::InstrKind::FloatToString(val) => {
                 function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*val)));
                 function.instruction(&Instruction::Call(
@@ -1847,6 +3591,18 @@ impl<'a, 'b> FuncGenerator<'a, 'b> {
                 function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*s)));
                 function.instruction(&Instruction::Call(self.module_generator.string_copy_func));
             }
+            ir::InstrKind::Utf8ToString(bytes) => {
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*bytes)));
+                function.instruction(&Instruction::Call(
+                    self.module_generator.utf8_to_string_func,
+                ));
+            }
+            ir::InstrKind::StringToUtf8(s) => {
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*s)));
+                function.instruction(&Instruction::Call(
+                    self.module_generator.string_to_utf8_func,
+                ));
+            }
             ir::InstrKind::StringSet(lhs, rhs, char_val) => {
                 function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*lhs)));
                 function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*rhs)));
@@ -1860,6 +3616,86 @@ impl<'a, 'b> FuncGenerator<'a, 'b> {
                 function.instruction(&Instruction::Call(self.module_generator.string_length_func));
                 function.instruction(&Instruction::I64ExtendI32U);
             }
+            ir::InstrKind::StringAppend(lhs, rhs) => {
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*lhs)));
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*rhs)));
+                function.instruction(&Instruction::Call(self.module_generator.string_append_func));
+            }
+            ir::InstrKind::Substring(s, start, end) => {
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*s)));
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*start)));
+                function.instruction(&Instruction::I32WrapI64);
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*end)));
+                function.instruction(&Instruction::I32WrapI64);
+                function.instruction(&Instruction::Call(self.module_generator.substring_func));
+            }
+            ir::InstrKind::MakeString(k, c) => {
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*k)));
+                function.instruction(&Instruction::I32WrapI64);
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*c)));
+                function.instruction(&Instruction::Call(self.module_generator.make_string_func));
+            }
+            ir::InstrKind::ListToString(list) => {
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*list)));
+                function.instruction(&Instruction::Call(
+                    self.module_generator.list_to_string_func,
+                ));
+            }
+            ir::InstrKind::StringToList(s) => {
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*s)));
+                function.instruction(&Instruction::Call(
+                    self.module_generator.string_to_list_func,
+                ));
+            }
+            ir::InstrKind::CmpString(lhs, rhs) => {
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*lhs)));
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*rhs)));
+                function.instruction(&Instruction::Call(self.module_generator.string_cmp_func));
+                function.instruction(&Instruction::I64ExtendI32S);
+            }
+            ir::InstrKind::CmpStringCi(lhs, rhs) => {
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*lhs)));
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*rhs)));
+                function.instruction(&Instruction::Call(self.module_generator.string_cmp_ci_func));
+                function.instruction(&Instruction::I64ExtendI32S);
+            }
+            ir::InstrKind::StringToNumber(s, radix) => {
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*s)));
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*radix)));
+                function.instruction(&Instruction::I32WrapI64);
+                function.instruction(&Instruction::Call(
+                    self.module_generator.string_to_number_func,
+                ));
+            }
+            ir::InstrKind::CharToInt(c) => {
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*c)));
+                function.instruction(&Instruction::I64ExtendI32U);
+            }
+            ir::InstrKind::IntToChar(i) => {
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*i)));
+                function.instruction(&Instruction::Call(self.module_generator.int_to_char_func));
+            }
+            ir::InstrKind::CharUpcase(c) => {
+                self.gen_char_op(function, CharOp::Upcase, *c);
+            }
+            ir::InstrKind::CharDowncase(c) => {
+                self.gen_char_op(function, CharOp::Downcase, *c);
+            }
+            ir::InstrKind::CharAlphabetic(c) => {
+                self.gen_char_op(function, CharOp::Alphabetic, *c);
+            }
+            ir::InstrKind::CharNumeric(c) => {
+                self.gen_char_op(function, CharOp::Numeric, *c);
+            }
+            ir::InstrKind::CharWhitespace(c) => {
+                self.gen_char_op(function, CharOp::Whitespace, *c);
+            }
+            ir::InstrKind::CharUpperCase(c) => {
+                self.gen_char_op(function, CharOp::UpperCase, *c);
+            }
+            ir::InstrKind::CharLowerCase(c) => {
+                self.gen_char_op(function, CharOp::LowerCase, *c);
+            }
             ir::InstrKind::LtInt(lhs, rhs) => {
                 function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*lhs)));
                 function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*rhs)));
@@ -1900,6 +3736,239 @@ impl<'a, 'b> FuncGenerator<'a, 'b> {
                 function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*rhs)));
                 function.instruction(&Instruction::F64Ge);
             }
+            ir::InstrKind::MakeErrorObject(message, irritants) => {
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*message)));
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*irritants)));
+                // kind: 一般のエラー
+                function.instruction(&Instruction::I32Const(0));
+                function.instruction(&Instruction::StructNew(
+                    self.module_generator.error_object_type,
+                ));
+            }
+            ir::InstrKind::ErrorObjectMessage(val) => {
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*val)));
+                function.instruction(&Instruction::StructGet {
+                    struct_type_index: self.module_generator.error_object_type,
+                    field_index: ModuleGenerator::ERROR_OBJECT_MESSAGE_FIELD,
+                });
+            }
+            ir::InstrKind::ErrorObjectIrritants(val) => {
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*val)));
+                function.instruction(&Instruction::StructGet {
+                    struct_type_index: self.module_generator.error_object_type,
+                    field_index: ModuleGenerator::ERROR_OBJECT_IRRITANTS_FIELD,
+                });
+            }
+            ir::InstrKind::Raise(val) => {
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*val)));
+                function.instruction(&Instruction::Call(self.module_generator.raise_func));
+            }
+            ir::InstrKind::WithRaiseHandler(thunk, handler) => {
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*thunk)));
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*handler)));
+                function.instruction(&Instruction::Call(
+                    self.module_generator.with_raise_handler_func,
+                ));
+            }
+            ir::InstrKind::ErrorObjectKind(val) => {
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*val)));
+                function.instruction(&Instruction::StructGetU {
+                    struct_type_index: self.module_generator.error_object_type,
+                    field_index: ModuleGenerator::ERROR_OBJECT_KIND_FIELD,
+                });
+                function.instruction(&Instruction::I64ExtendI32U);
+            }
+            ir::InstrKind::OpenInputString(s) => {
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*s)));
+                function.instruction(&Instruction::Call(
+                    self.module_generator.open_input_string_func,
+                ));
+            }
+            ir::InstrKind::OpenOutputString => {
+                function.instruction(&Instruction::Call(
+                    self.module_generator.open_output_string_func,
+                ));
+            }
+            ir::InstrKind::StandardPort(fd) => {
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*fd)));
+                function.instruction(&Instruction::I32WrapI64);
+                function.instruction(&Instruction::Call(self.module_generator.standard_port_func));
+            }
+            ir::InstrKind::GetOutputString(port) => {
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*port)));
+                function.instruction(&Instruction::Call(
+                    self.module_generator.get_output_string_func,
+                ));
+            }
+            ir::InstrKind::ClosePort(port) => {
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*port)));
+                function.instruction(&Instruction::Call(self.module_generator.close_port_func));
+                function.instruction(&Instruction::I32Const(0));
+            }
+            ir::InstrKind::IsInputPort(port) => {
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*port)));
+                function.instruction(&Instruction::StructGetU {
+                    struct_type_index: self.module_generator.port_type,
+                    field_index: ModuleGenerator::PORT_INPUT_FIELD,
+                });
+            }
+            ir::InstrKind::IsOutputPort(port) => {
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*port)));
+                function.instruction(&Instruction::StructGetU {
+                    struct_type_index: self.module_generator.port_type,
+                    field_index: ModuleGenerator::PORT_OUTPUT_FIELD,
+                });
+            }
+            ir::InstrKind::ReadChar(port) => {
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*port)));
+                function.instruction(&Instruction::Call(self.module_generator.read_char_func));
+            }
+            ir::InstrKind::PeekChar(port) => {
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*port)));
+                function.instruction(&Instruction::Call(self.module_generator.peek_char_func));
+            }
+            ir::InstrKind::Read(port) => {
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*port)));
+                function.instruction(&Instruction::Call(self.module_generator.read_func));
+            }
+            ir::InstrKind::EofObject => {
+                function.instruction(&Instruction::GlobalGet(
+                    self.module_generator.eof_global.unwrap(),
+                ));
+            }
+            ir::InstrKind::DisplayPort(val, port) => {
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*port)));
+                function.instruction(&Instruction::StructGet {
+                    struct_type_index: self.module_generator.port_type,
+                    field_index: ModuleGenerator::PORT_ID_FIELD,
+                });
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*val)));
+                function.instruction(&Instruction::Call(self.module_generator.display_fd_func));
+                function.instruction(&Instruction::I32Const(0));
+            }
+            ir::InstrKind::WriteCharPort(val, port) => {
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*port)));
+                function.instruction(&Instruction::StructGet {
+                    struct_type_index: self.module_generator.port_type,
+                    field_index: ModuleGenerator::PORT_ID_FIELD,
+                });
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*val)));
+                function.instruction(&Instruction::Call(self.module_generator.write_char_fd_func));
+                function.instruction(&Instruction::I32Const(0));
+            }
+            ir::InstrKind::SetCurrentOutputPort(port) => {
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*port)));
+                function.instruction(&Instruction::StructGet {
+                    struct_type_index: self.module_generator.port_type,
+                    field_index: ModuleGenerator::PORT_ID_FIELD,
+                });
+                function.instruction(&Instruction::Call(
+                    self.module_generator.set_current_output_fd_func,
+                ));
+                function.instruction(&Instruction::I32Const(0));
+            }
+            ir::InstrKind::Eval(val) => {
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*val)));
+                function.instruction(&Instruction::Call(self.module_generator.eval_func));
+            }
+            ir::InstrKind::SetEvalResult(val) => {
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*val)));
+                function.instruction(&Instruction::GlobalSet(
+                    self.module_generator.eval_result_global.unwrap(),
+                ));
+                function.instruction(&Instruction::I32Const(0));
+            }
+            ir::InstrKind::MakeHashTable(kind) => {
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*kind)));
+                function.instruction(&Instruction::I32WrapI64);
+                function.instruction(&Instruction::Call(
+                    self.module_generator.make_hash_table_func,
+                ));
+            }
+            ir::InstrKind::HashTableRef(table, key, default) => {
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*table)));
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*key)));
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*default)));
+                function.instruction(&Instruction::Call(
+                    self.module_generator.hash_table_ref_func,
+                ));
+            }
+            ir::InstrKind::HashTableSet(table, key, value) => {
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*table)));
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*key)));
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*value)));
+                function.instruction(&Instruction::Call(
+                    self.module_generator.hash_table_set_func,
+                ));
+                function.instruction(&Instruction::I32Const(0));
+            }
+            ir::InstrKind::HashTableDelete(table, key) => {
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*table)));
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*key)));
+                function.instruction(&Instruction::Call(
+                    self.module_generator.hash_table_delete_func,
+                ));
+                function.instruction(&Instruction::I32Const(0));
+            }
+            ir::InstrKind::HashTableCount(table) => {
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*table)));
+                function.instruction(&Instruction::StructGet {
+                    struct_type_index: self.module_generator.hash_table_type,
+                    field_index: ModuleGenerator::HASH_TABLE_COUNT_FIELD,
+                });
+                function.instruction(&Instruction::I64ExtendI32U);
+            }
+            ir::InstrKind::HashTableToAlist(table) => {
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*table)));
+                function.instruction(&Instruction::Call(
+                    self.module_generator.hash_table_to_alist_func,
+                ));
+            }
+            ir::InstrKind::MakeRecord(id, typ, size) => {
+                function.instruction(&Instruction::I32Const(usize::from(*id) as i32));
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*typ)));
+                function.instruction(&Instruction::I32Const(*size as i32));
+                function.instruction(&Instruction::ArrayNewDefault(
+                    self.module_generator.vector_type,
+                ));
+                function.instruction(&Instruction::StructNew(self.module_generator.record_type));
+            }
+            ir::InstrKind::IsRecord(val) => {
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*val)));
+                function.instruction(&Instruction::RefTestNonNull(HeapType::Concrete(
+                    self.module_generator.record_type,
+                )));
+            }
+            ir::InstrKind::RecordType(val) => {
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*val)));
+                function.instruction(&Instruction::RefCastNonNull(HeapType::Concrete(
+                    self.module_generator.record_type,
+                )));
+                function.instruction(&Instruction::StructGet {
+                    struct_type_index: self.module_generator.record_type,
+                    field_index: ModuleGenerator::RECORD_TYPE_FIELD,
+                });
+            }
+            ir::InstrKind::RecordRef(record, index) => {
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*record)));
+                function.instruction(&Instruction::StructGet {
+                    struct_type_index: self.module_generator.record_type,
+                    field_index: ModuleGenerator::RECORD_FIELDS_FIELD,
+                });
+                function.instruction(&Instruction::I32Const(*index as i32));
+                function.instruction(&Instruction::ArrayGet(self.module_generator.vector_type));
+            }
+            ir::InstrKind::RecordSet(record, index, val) => {
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*record)));
+                function.instruction(&Instruction::StructGet {
+                    struct_type_index: self.module_generator.record_type,
+                    field_index: ModuleGenerator::RECORD_FIELDS_FIELD,
+                });
+                function.instruction(&Instruction::I32Const(*index as i32));
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*val)));
+                function.instruction(&Instruction::ArraySet(self.module_generator.vector_type));
+                function.instruction(&Instruction::I32Const(0));
+            }
             ir::InstrKind::VariadicArgs(args) => {
                 for arg in args.iter() {
                     function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*arg)));
@@ -1924,6 +3993,10 @@ impl<'a, 'b> FuncGenerator<'a, 'b> {
                 function.instruction(&Instruction::I32Const(*start_idx as i32));
                 function.instruction(&Instruction::Call(self.module_generator.args_to_list_func));
             }
+            ir::InstrKind::ListToVariadicArgs(list) => {
+                function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*list)));
+                function.instruction(&Instruction::Call(self.module_generator.list_to_args_func));
+            }
             ir::InstrKind::CreateMutFuncRef(id) => {
                 function.instruction(&Instruction::LocalGet(self.local_id_to_idx(*id)));
                 function.instruction(&Instruction::StructNew(
@@ -2009,6 +4082,136 @@ impl<'a, 'b> FuncGenerator<'a, 'b> {
         }
     }
 
+    fn gen_string(&mut self, function: &mut Function, s: &str) {
+        // TODO: 重複リテラルを共有
+        let bs = s.as_bytes();
+        let data_index = self.module_generator.datas.len();
+        self.module_generator.datas.passive(bs.iter().copied());
+
+        // data offset
+        function.instruction(&Instruction::I32Const(0));
+        // data len
+        function.instruction(&Instruction::I32Const(bs.len() as i32));
+        // StringBuf.buf
+        function.instruction(&Instruction::ArrayNewData {
+            array_type_index: self.module_generator.buf_type,
+            array_data_index: data_index,
+        });
+
+        // StringBuf.shared
+        function.instruction(&Instruction::I32Const(0));
+        // String.buf
+        function.instruction(&Instruction::StructNew(
+            self.module_generator.string_buf_type,
+        ));
+
+        // String.len
+        function.instruction(&Instruction::I32Const(bs.len() as i32));
+        // String.offset
+        function.instruction(&Instruction::I32Const(0));
+        function.instruction(&Instruction::StructNew(self.module_generator.string_type));
+    }
+
+    fn gen_integer_binop(
+        &mut self,
+        function: &mut Function,
+        op: IntegerBinOp,
+        lhs: ir::LocalId,
+        rhs: ir::LocalId,
+    ) {
+        function.instruction(&Instruction::I32Const(op as i32));
+        function.instruction(&Instruction::LocalGet(self.local_id_to_idx(lhs)));
+        function.instruction(&Instruction::LocalGet(self.local_id_to_idx(rhs)));
+        function.instruction(&Instruction::Call(self.module_generator.integer_binop_func));
+    }
+
+    // 範囲外の整数は下位ビットのみ格納される
+    fn gen_uvector_element_to_storage(function: &mut Function, kind: ir::UVectorKind) {
+        match kind {
+            ir::UVectorKind::S64 | ir::UVectorKind::F64 => {}
+            ir::UVectorKind::U8
+            | ir::UVectorKind::S8
+            | ir::UVectorKind::U16
+            | ir::UVectorKind::S16
+            | ir::UVectorKind::U32
+            | ir::UVectorKind::S32 => {
+                function.instruction(&Instruction::I32WrapI64);
+            }
+            ir::UVectorKind::F32 => {
+                function.instruction(&Instruction::F32DemoteF64);
+            }
+        }
+    }
+
+    fn gen_uvector_get(&mut self, function: &mut Function, kind: ir::UVectorKind) {
+        let type_idx = self.module_generator.uvector_kind_to_type_idx(kind);
+        match kind {
+            ir::UVectorKind::S64 | ir::UVectorKind::F64 => {
+                function.instruction(&Instruction::ArrayGet(type_idx));
+            }
+            ir::UVectorKind::U8 | ir::UVectorKind::U16 => {
+                function.instruction(&Instruction::ArrayGetU(type_idx));
+                function.instruction(&Instruction::I64ExtendI32U);
+            }
+            ir::UVectorKind::S8 | ir::UVectorKind::S16 => {
+                function.instruction(&Instruction::ArrayGetS(type_idx));
+                function.instruction(&Instruction::I64ExtendI32S);
+            }
+            ir::UVectorKind::U32 => {
+                function.instruction(&Instruction::ArrayGet(type_idx));
+                function.instruction(&Instruction::I64ExtendI32U);
+            }
+            ir::UVectorKind::S32 => {
+                function.instruction(&Instruction::ArrayGet(type_idx));
+                function.instruction(&Instruction::I64ExtendI32S);
+            }
+            ir::UVectorKind::F32 => {
+                function.instruction(&Instruction::ArrayGet(type_idx));
+                function.instruction(&Instruction::F64PromoteF32);
+            }
+        }
+    }
+
+    fn gen_char_op(&mut self, function: &mut Function, op: CharOp, c: ir::LocalId) {
+        function.instruction(&Instruction::I32Const(op as i32));
+        function.instruction(&Instruction::LocalGet(self.local_id_to_idx(c)));
+        function.instruction(&Instruction::Call(self.module_generator.char_op_func));
+    }
+
+    // overflowが真ならint_binop_overflowを呼ぶ
+    fn gen_int_binop(
+        &mut self,
+        function: &mut Function,
+        op: IntBinOp,
+        lhs: ir::LocalId,
+        rhs: ir::LocalId,
+        overflow: bool,
+    ) {
+        function.instruction(&Instruction::I32Const(op as i32));
+        function.instruction(&Instruction::LocalGet(self.local_id_to_idx(lhs)));
+        function.instruction(&Instruction::LocalGet(self.local_id_to_idx(rhs)));
+        function.instruction(&Instruction::Call(if overflow {
+            self.module_generator.int_binop_overflow_func
+        } else {
+            self.module_generator.int_binop_func
+        }));
+    }
+
+    fn gen_rational_binop(
+        &mut self,
+        function: &mut Function,
+        op: RationalBinOp,
+        lhs: ir::LocalId,
+        rhs: ir::LocalId,
+    ) {
+        function.instruction(&Instruction::I32Const(op as i32));
+        function.instruction(&Instruction::LocalGet(self.local_id_to_idx(lhs)));
+        function.instruction(&Instruction::LocalGet(self.local_id_to_idx(rhs)));
+        function.instruction(&Instruction::Call(
+            self.module_generator.rational_binop_func,
+        ));
+    }
+
     fn gen_call(&mut self, function: &mut Function, is_tail: bool, call: &ir::InstrCall) {
         let func_idx = self.module_generator.func_indices[&call.func_id];
         for arg in &call.args {
@@ -0,0 +1,135 @@
+use rustc_hash::FxHashMap;
+use serde::Serialize;
+use webschembly_compiler_ir as ir;
+
+// Source Map v3
+// https://tc39.es/ecma426/
+// wasmの場合は生成側を1行とみなし、列にモジュール先頭からのバイトオフセットを用いる
+#[derive(Debug, Serialize)]
+struct SourceMapJson<'a> {
+    version: u32,
+    sources: Vec<&'a str>,
+    names: Vec<&'a str>,
+    mappings: String,
+}
+
+// ファイル名がないソースに使う名前
+const UNKNOWN_SOURCE: &str = "<input>";
+
+// entries: (モジュール先頭からのバイトオフセット, SourceLocId)
+pub fn generate_source_map(meta: &ir::Meta, entries: &[(usize, ir::SourceLocId)]) -> String {
+    let mut entries = entries
+        .iter()
+        .filter_map(|&(offset, id)| meta.source_locs.get(&id).map(|loc| (offset, loc)))
+        .collect::<Vec<_>>();
+    entries.sort_by_key(|(offset, _)| *offset);
+
+    let mut sources = Vec::new();
+    let mut source_indices = FxHashMap::default();
+    let mut mappings = String::new();
+    let mut prev_offset = 0;
+    let mut prev_source = 0;
+    let mut prev_line = 0;
+    let mut prev_column = 0;
+    for (offset, loc) in entries {
+        let file_name = loc.file_name.as_deref().unwrap_or(UNKNOWN_SOURCE);
+        let source = *source_indices.entry(file_name).or_insert_with(|| {
+            sources.push(file_name);
+            sources.len() - 1
+        });
+        // Posは1-indexed、Source Mapは0-indexed
+        let line = loc.span.start.line.saturating_sub(1);
+        let column = loc.span.start.column.saturating_sub(1);
+
+        if !mappings.is_empty() {
+            mappings.push(',');
+        }
+        encode_vlq(&mut mappings, offset as i64 - prev_offset as i64);
+        encode_vlq(&mut mappings, source as i64 - prev_source as i64);
+        encode_vlq(&mut mappings, line as i64 - prev_line as i64);
+        encode_vlq(&mut mappings, column as i64 - prev_column as i64);
+        prev_offset = offset;
+        prev_source = source;
+        prev_line = line;
+        prev_column = column;
+    }
+
+    serde_json::to_string(&SourceMapJson {
+        version: 3,
+        sources,
+        names: Vec::new(),
+        mappings,
+    })
+    .unwrap()
+}
+
+const BASE64_CHARS: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn encode_vlq(buf: &mut String, value: i64) {
+    // 最下位ビットを符号ビットにする
+    let mut value = if value < 0 {
+        ((-value) << 1) | 1
+    } else {
+        value << 1
+    } as u64;
+    loop {
+        let mut digit = value & 0b11111;
+        value >>= 5;
+        if value != 0 {
+            // 継続ビット
+            digit |= 0b100000;
+        }
+        buf.push(BASE64_CHARS[digit as usize] as char);
+        if value == 0 {
+            break;
+        }
+    }
+}
+
+pub fn encode_base64(bytes: &[u8]) -> String {
+    let mut buf = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &b)| n | (b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                buf.push(BASE64_CHARS[(n >> (18 - 6 * i) & 0b111111) as usize] as char);
+            } else {
+                buf.push('=');
+            }
+        }
+    }
+    buf
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_vlq() {
+        let encode = |value| {
+            let mut buf = String::new();
+            encode_vlq(&mut buf, value);
+            buf
+        };
+        assert_eq!(encode(0), "A");
+        assert_eq!(encode(1), "C");
+        assert_eq!(encode(-1), "D");
+        assert_eq!(encode(15), "e");
+        assert_eq!(encode(16), "gB");
+        assert_eq!(encode(-16), "hB");
+        assert_eq!(encode(1000), "w+B");
+    }
+
+    #[test]
+    fn test_encode_base64() {
+        assert_eq!(encode_base64(b""), "");
+        assert_eq!(encode_base64(b"f"), "Zg==");
+        assert_eq!(encode_base64(b"fo"), "Zm8=");
+        assert_eq!(encode_base64(b"foo"), "Zm9v");
+        assert_eq!(encode_base64(b"foobar"), "Zm9vYmFy");
+    }
+}
@@ -0,0 +1,50 @@
+(define (fact n)
+  (if (= n 0)
+      1
+      (* n (fact (- n 1)))))
+(write (fact 20))
+(newline)
+(write (fact 30))
+(newline)
+(write (+ 9223372036854775807 1))
+(newline)
+(write (- -9223372036854775808 1))
+(newline)
+(write (* 4294967296 4294967296))
+(newline)
+(write (* -1 -9223372036854775808))
+(newline)
+(write 123456789012345678901234567890)
+(newline)
+(write -123456789012345678901234567890)
+(newline)
+(write (list 99999999999999999999 -99999999999999999999))
+(newline)
+(write (- (+ 9223372036854775807 1) 1))
+(newline)
+(write (number? (fact 25)))
+(newline)
+(write (quotient (fact 30) (fact 28)))
+(newline)
+(write (remainder (fact 30) 1000000007))
+(newline)
+(write (modulo (- 0 (fact 25)) 1000000007))
+(newline)
+(write (remainder (- 0 (fact 25)) 1000000007))
+(newline)
+(write (modulo (fact 25) -1000000007))
+(newline)
+(write (list (< (fact 25) (fact 26)) (> (fact 25) 1) (= (fact 25) (fact 25)) (<= 1 (- 0 (fact 25))) (>= (fact 25) (fact 25))))
+(newline)
+(write (list (eqv? (fact 25) (fact 25)) (eqv? (fact 25) (fact 26)) (equal? (list (fact 25)) (list (fact 25)))))
+(newline)
+(define (loop i acc)
+  (if (= i 0)
+      acc
+      (loop (- i 1) (+ acc 1000000000000000))))
+(write (loop 20000 0))
+(newline)
+(define (fib-iter n a b)
+  (if (= n 0) a (fib-iter (- n 1) b (+ a b))))
+(write (fib-iter 100 0 1))
+(newline)
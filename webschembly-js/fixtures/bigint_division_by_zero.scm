@@ -0,0 +1,4 @@
+(define (r a b) (remainder a b))
+(write (r (expt 10 30) 7))
+(newline)
+(write (r (expt 10 30) 0))
@@ -0,0 +1,5 @@
+;; 組み込み関数の引数は型によらず一度だけ評価される
+(write (+ (begin (display "a") 1.5) 2.0))
+(newline)
+(write (< (begin (display "b") 1.5) (begin (display "c") 2.0)))
+(newline)
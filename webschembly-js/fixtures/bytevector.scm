@@ -0,0 +1,38 @@
+(write #u8(1 2 255)) (newline)
+(write '#u8(0 128)) (newline)
+(write #s8(-1 127 -128)) (newline)
+(write #u16(65535 0)) (newline)
+(write #s16(-32768 32767)) (newline)
+(write #u32(4294967295)) (newline)
+(write #s32(-2147483648)) (newline)
+(write #f32(1.5 -0.25)) (newline)
+(write (uvector? #u8())) (newline)
+(write (list (u8vector? #s8(1)) (s8vector? #s8(1)) (bytevector? #u8(1)) (bytevector? #s64(1)))) (newline)
+(define b (make-bytevector 4 7))
+(write b) (newline)
+(bytevector-u8-set! b 1 300)
+(write (bytevector-u8-ref b 1)) (newline)
+(write (bytevector-length b)) (newline)
+(define s (make-s16vector 2))
+(uvector-set! s 0 -5)
+(write (uvector-ref s 0)) (newline)
+(write (bytevector 1 2 3)) (newline)
+(write (bytevector-copy #u8(1 2 3 4 5) 1 3)) (newline)
+(write (bytevector-append #u8(1) #u8() #u8(2 3))) (newline)
+(define c (bytevector 1 2 3 4 5))
+(bytevector-copy! c 1 c 0 3)
+(write c) (newline)
+(write (string->utf8 "héllo")) (newline)
+(write (utf8->string #u8(104 105 33))) (newline)
+(write (utf8->string (string->utf8 "abcdef") 2 4)) (newline)
+(write (string->utf8 "abcdef" 3)) (newline)
+(write (read (open-input-string "#u8(1 2 3)"))) (newline)
+(write (eval '(bytevector-u8-ref #u8(9 8 7) 2) (interaction-environment))) (newline)
+(write (eval (list 'quote #f32(2.5)) (interaction-environment))) (newline)
+(write (read (open-input-string "#s16(-3 4)"))) (newline)
+(define (checksum bytes)
+  (let loop ((i 0) (sum 0))
+    (if (< i (bytevector-length bytes))
+      (loop (+ i 1) (modulo (+ (* sum 31) (bytevector-u8-ref bytes i)) 65521))
+      sum)))
+(write (checksum (string->utf8 "hello, world"))) (newline)
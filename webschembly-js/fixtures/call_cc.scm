@@ -0,0 +1,31 @@
+(define (find-first pred lst)
+  (call/cc
+    (lambda (return)
+      (for-each-elem (lambda (x) (if (pred x) (return x) #f)) lst)
+      #f)))
+(define (for-each-elem f lst)
+  (if (null? lst) #f (begin (f (car lst)) (for-each-elem f (cdr lst)))))
+(write (find-first (lambda (x) (> x 2)) '(1 2 3 4)))
+(newline)
+(write (find-first (lambda (x) (> x 10)) '(1 2 3 4)))
+(newline)
+(write (+ 1 (call-with-current-continuation (lambda (k) 10))))
+(newline)
+(write (call/cc (lambda (outer) (+ 1 (call/cc (lambda (inner) (outer 42)))))))
+(newline)
+(write
+  (call/cc
+    (lambda (k)
+      (dynamic-wind
+        (lambda () (display "before") (newline))
+        (lambda () (k 'escaped) (display "unreachable"))
+        (lambda () (display "after") (newline))))))
+(newline)
+(write (dynamic-wind (lambda () #f) (lambda () 'normal) (lambda () (display "after2") (newline))))
+(newline)
+(define saved #f)
+(write (call/cc (lambda (k) (set! saved k) 1)))
+(newline)
+; 継続はトップレベルの式ごとに区切られるので、writeの後は次の式に進む
+(saved 2)
+(newline)
@@ -0,0 +1,92 @@
+(define (show x)
+  (write x)
+  (newline))
+
+; 同じ継続に何度も戻る
+(define (count-up)
+  (let ((k #f)
+        (n 0))
+    (call/cc (lambda (c) (set! k c)))
+    (set! n (+ n 1))
+    (if (< n 3) (k #f) n)))
+(show (count-up))
+
+; 継続で再開するジェネレータ
+(define (make-generator lst)
+  (define return #f)
+  (define (resume-gen)
+    (for-each
+      (lambda (x)
+        (call/cc
+          (lambda (next)
+            (set! resume-gen (lambda () (next #f)))
+            (return x))))
+      lst)
+    (return 'done))
+  (lambda ()
+    (call/cc
+      (lambda (r)
+        (set! return r)
+        (resume-gen)))))
+(define gen (make-generator '(1 2 3)))
+(show (list (gen) (gen) (gen) (gen)))
+
+; 戻ってきたときは値の数に関わらず継続の呼び出し元に値が渡される
+(show
+  (call-with-values
+    (lambda () (call/cc (lambda (k) (k 1 2 3))))
+    list))
+
+; dynamic-windの中に戻るとbeforeが再び呼ばれる
+(show
+  (let ((k #f)
+        (trace '()))
+    (dynamic-wind
+      (lambda () (set! trace (cons 'before trace)))
+      (lambda () (call/cc (lambda (c) (set! k c))))
+      (lambda () (set! trace (cons 'after trace))))
+    (if (< (length trace) 4)
+      (k #f)
+      (reverse trace))))
+
+; 入れ子のdynamic-windから脱出するとafterが内側から呼ばれる
+(show
+  (let ((trace '()))
+    (call/cc
+      (lambda (k)
+        (dynamic-wind
+          (lambda () (set! trace (cons 'before1 trace)))
+          (lambda ()
+            (dynamic-wind
+              (lambda () (set! trace (cons 'before2 trace)))
+              (lambda () (k #f))
+              (lambda () (set! trace (cons 'after2 trace)))))
+          (lambda () (set! trace (cons 'after1 trace))))))
+    (reverse trace)))
+
+; guardで捕捉したときもafterが呼ばれる
+(show
+  (guard (e (#t (list 'caught e)))
+    (dynamic-wind
+      (lambda () (display "in") (newline))
+      (lambda () (raise 'oops))
+      (lambda () (display "out") (newline)))))
+
+(show
+  (guard (e ((string? e) 'string))
+    (guard (e ((symbol? e) 'symbol))
+      (raise "str"))))
+
+(show
+  (with-exception-handler
+    (lambda (e) (* e 2))
+    (lambda () (+ 1 (raise-continuable 20)))))
+
+; 深い再帰でもスタックを消費しない
+(define (sum n)
+  (if (= n 0)
+    0
+    (+ n (sum (- n 1)))))
+(show (sum 100000))
+
+(error "uncaught" 1 2)
@@ -0,0 +1,36 @@
+(define (show x)
+  (write x)
+  (newline))
+
+(define f
+  (case-lambda
+    ((x) (list 'one x))
+    ((x y) (list 'two x y))
+    ((x . rest) (list 'many x rest))))
+(show (f 1))
+(show (f 1 2))
+(show (f 1 2 3))
+(show (apply f '(4 5)))
+
+; 節の中から自分自身を呼び出す
+(define count
+  (case-lambda
+    ((n) (count n 0))
+    ((n acc) (if (= n 0) acc (count (- n 1) (+ acc 1))))))
+(show (count 1000000))
+
+(define (make-counter)
+  (let ((n 0))
+    (case-lambda
+      (() n)
+      ((k) (set! n (+ n k)) n))))
+(define counter (make-counter))
+(counter 3)
+(counter 4)
+(show (counter))
+
+(define g
+  (case-lambda
+    ((a) a)
+    ((a b) b)))
+(g 1 2 3)
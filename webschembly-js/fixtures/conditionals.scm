@@ -0,0 +1,50 @@
+(define (show x)
+  (write x)
+  (newline))
+
+(define (classify n)
+  (case n
+    ((1 2 3) 'small)
+    ((4 5 6) 'medium)
+    (else 'large)))
+(show (classify 2))
+(show (classify 5))
+(show (classify 100))
+
+(show (case 'b
+        ((a) 1)
+        ((b c) => (lambda (x) (list x x)))
+        (else 0)))
+(show (case 10
+        ((1) 1)
+        (else => (lambda (x) (* x 2)))))
+(show (case #\x
+        ((#\a #\b) 'ab)
+        ((#\x) 'x)
+        (else 'other)))
+
+; キーは一度だけ評価される
+(define evaluated 0)
+(show (case (begin (set! evaluated (+ evaluated 1)) 3)
+        ((1) 'one)
+        ((2) 'two)
+        ((3) 'three)))
+(show evaluated)
+
+(show (when (> 2 1) 'a 'b))
+(show (when (< 2 1) 'a))
+(show (unless (< 2 1) 'a 'b))
+(show (unless (> 2 1) 'a))
+
+(show (letrec* ((a 1)
+                (f (lambda () a))
+                (b (+ (f) 1))
+                (even? (lambda (n) (if (= n 0) #t (odd? (- n 1)))))
+                (odd? (lambda (n) (if (= n 0) #f (even? (- n 1))))))
+        (list a b (even? 10))))
+
+(show (do ((i 0 (+ i 1))
+           (acc '() (cons i acc))
+           (fixed 'x))
+          ((= i 5) (list acc fixed))
+        (set! fixed (if (= i 2) 'y fixed))))
@@ -0,0 +1,16 @@
+(write (eval '(+ 1 2) (interaction-environment))) (newline)
+(write (eval (list '* 6 7) (scheme-report-environment 5))) (newline)
+(eval '(define x 10) (interaction-environment))
+(write (eval 'x (interaction-environment))) (newline)
+(eval '(define (square n) (* n n)) (interaction-environment))
+(write (eval '(square 12) (interaction-environment))) (newline)
+(write (eval '(begin (define y 3) (+ y 1)) (interaction-environment))) (newline)
+(write (eval ''(a "b" #\c 1.5 1/3 123456789012345678901234567890 #(1 2) #s64(3 4) #f64(1.5)) (interaction-environment))) (newline)
+(write (eval '(eval '(quote nested) (interaction-environment)) (interaction-environment))) (newline)
+(write (eval '(let loop ((i 0) (acc '())) (if (= i 5) acc (loop (+ i 1) (cons i acc)))) (interaction-environment))) (newline)
+(write (guard (e (#t (list 'caught (error-object-message e)))) (eval '(error "boom") (interaction-environment)))) (newline)
+(write (eval (list 'quote (list 1 (list 2 3))) (interaction-environment))) (newline)
+(define counter 0)
+(eval '(set! counter (+ counter 1)) (interaction-environment))
+(write counter) (newline)
+(eval (list car '(1 2)) (interaction-environment))
@@ -0,0 +1,31 @@
+(define (show x) (write x) (newline))
+(show (guard (e (#t (list 'caught e))) (raise 'boom)))
+(show (guard (e ((symbol? e) (list 'sym e)) ((string? e) (list 'str e))) (raise "oops")))
+(show (guard (e ((error-object? e) (list (error-object-message e) (error-object-irritants e)))) (error "bad thing" 1 2 'x)))
+(show (with-exception-handler
+  (lambda (c) 42)
+  (lambda () (+ (raise-continuable 'c) 1))))
+(show (guard (e (#t (list 'outer e)))
+  (guard (e ((number? e) (list 'inner e)))
+    (raise 'not-a-number))))
+(show (guard (e ((string? e) e))
+  (with-exception-handler
+    (lambda (c) (raise "from handler"))
+    (lambda () (raise 'x)))))
+(show (guard (e (#t (error-object-message e)))
+  (with-exception-handler
+    (lambda (c) 'ignored)
+    (lambda () (raise 'x)))))
+(show (guard (e (else 'else-clause)) (raise 1)))
+(show (guard (e ((assq 'a e) => cdr) ((assq 'b e))) (raise (list (cons 'a 42)))))
+(show (guard (e ((assq 'a e) => cdr) ((assq 'b e))) (raise (list (cons 'b 23)))))
+(dynamic-wind
+  (lambda () (show 'before))
+  (lambda () (guard (e (#t (show (list 'caught e)))) (dynamic-wind (lambda () (show 'in)) (lambda () (raise 'z)) (lambda () (show 'out)))))
+  (lambda () (show 'after)))
+(define (safe-div a b) (guard (e (#t 'div-error)) (if (= b 0) (error "division by zero" a) (quotient a b))))
+(show (safe-div 10 2))
+(show (safe-div 10 0))
+(show (guard (e (#f 1)) 'no-raise))
+(error "fatal problem:" 42 "str" 'sym)
+(show 'unreachable)
@@ -0,0 +1,108 @@
+(define (print x) (write x) (newline))
+(define (sort-numbers lst)
+  (if (null? lst)
+    '()
+    (let ((pivot (car lst)))
+      (append
+        (sort-numbers (filter (lambda (x) (< x pivot)) (cdr lst)))
+        (list pivot)
+        (sort-numbers (filter (lambda (x) (>= x pivot)) (cdr lst)))))))
+(define (filter pred lst)
+  (cond ((null? lst) '())
+    ((pred (car lst)) (cons (car lst) (filter pred (cdr lst))))
+    (else (filter pred (cdr lst)))))
+
+(define h (make-hash-table))
+(print (hash-table? h))
+(print (hash-table? '()))
+(print h)
+(hash-table-set! h 'a 1)
+(hash-table-set! h "key" 2)
+(hash-table-set! h '(1 2) 3)
+(hash-table-set! h 10 4)
+(print (hash-table-ref h 'a))
+(print (hash-table-ref h (string-append "k" "ey")))
+(print (hash-table-ref h (list 1 2)))
+(print (hash-table-ref h 10))
+(print (hash-table-ref/default h 'b 'none))
+(print (hash-table-ref h 'b (lambda () 'failure)))
+(print (hash-table-ref h 'a (lambda () 'failure) (lambda (v) (* v 100))))
+(print (hash-table-count h))
+(hash-table-set! h 'a 5)
+(print (hash-table-ref h 'a))
+(print (hash-table-count h))
+(print (hash-table-contains? h "key"))
+(hash-table-delete! h "key")
+(print (hash-table-contains? h "key"))
+(hash-table-delete! h "key")
+(print (hash-table-size h))
+
+; eqv?はペアの内容を比較しない
+(define hv (make-hash-table eqv?))
+(hash-table-set! hv 1/2 'half)
+(hash-table-set! hv 2.5 'float)
+(hash-table-set! hv 100000000000000000000 'big)
+(hash-table-set! hv #\x 'char)
+(hash-table-set! hv (list 1) 'list)
+(print (hash-table-ref/default hv (/ 2 4) #f))
+(print (hash-table-ref/default hv (+ 2.0 0.5) #f))
+(print (hash-table-ref/default hv (* 10000000000 10000000000) #f))
+(print (hash-table-ref/default hv #\x #f))
+(print (hash-table-ref/default hv (list 1) #f))
+
+; eq?はシンボルを区別する
+(define hq (make-hash-table eq?))
+(define key (list 'k))
+(hash-table-set! hq key 'found)
+(hash-table-set! hq 'sym 'symbol)
+(print (hash-table-ref/default hq key #f))
+(print (hash-table-ref/default hq (list 'k) #f))
+(print (hash-table-ref/default hq (string->symbol "sym") #f))
+
+(define hs (make-hash-table string=?))
+(hash-table-set! hs "abc" 1)
+(print (hash-table-ref/default hs (list->string (list #\a #\b #\c)) #f))
+
+; 多数の要素を入れるとバケットが拡張される
+(define hn (make-hash-table eqv?))
+(let loop ((i 0))
+  (if (< i 1000)
+    (begin
+      (hash-table-set! hn i (* i i))
+      (loop (+ i 1)))
+    #f))
+(print (hash-table-count hn))
+(print (hash-table-ref hn 999))
+(let loop ((i 0))
+  (if (< i 1000)
+    (begin
+      (if (odd? i) (hash-table-delete! hn i) #f)
+      (loop (+ i 1)))
+    #f))
+(print (hash-table-count hn))
+(print (hash-table-ref/default hn 999 'deleted))
+(print (hash-table-ref hn 998))
+
+(define counts (make-hash-table))
+(for-each
+  (lambda (word) (hash-table-update!/default counts word (lambda (n) (+ n 1)) 0))
+  '(a b a c b a))
+(print (hash-table-ref counts 'a))
+(print (hash-table-ref counts 'b))
+(hash-table-update! counts 'c (lambda (n) (* n 10)))
+(print (hash-table-ref counts 'c))
+(hash-table-update! counts 'd (lambda (n) (+ n 1)) (lambda () 100))
+(print (hash-table-ref counts 'd))
+
+(define hk (make-hash-table))
+(hash-table-set! hk 3 'c)
+(hash-table-set! hk 1 'a)
+(hash-table-set! hk 2 'b)
+(print (sort-numbers (hash-table-keys hk)))
+(print (length (hash-table-values hk)))
+(print (length (hash-table->alist hk)))
+(define sum 0)
+(hash-table-walk hk (lambda (k v) (set! sum (+ sum k))))
+(print sum)
+
+(hash-table-ref h 'missing)
@@ -0,0 +1,23 @@
+(define (show x)
+  (write x)
+  (newline))
+
+; 後で定義される変数をキャプチャしたクロージャを、その変数の初期化後に呼び出す
+(show (letrec* ((f (lambda () (g 1)))
+                (g (lambda (x) (+ x 1)))
+                (c (f)))
+        c))
+
+; case-lambdaの環境も同じタイミングで設定される
+(show (letrec* ((f (case-lambda
+                     (() (f 0))
+                     ((x) (+ x y))))
+                (y 10)
+                (z (f)))
+        z))
+
+; 自身をset!するクロージャが型の決まった変数をキャプチャしても環境の型が揃う
+(define (call-once n)
+  (define (f) (set! f (lambda () 0)) n)
+  (+ (f) (f)))
+(show (call-once 5))
@@ -0,0 +1,35 @@
+(write (map (lambda (x) (* x x)) '(1 2 3))) (newline)
+(write (map + '(1 2 3) '(10 20 30 40))) (newline)
+(write (map (lambda (a b c) (list a b c)) '(1 2) '(x y) '("p" "q"))) (newline)
+(for-each (lambda (x) (write x) (write-char #\space)) '(a b c)) (newline)
+(for-each (lambda (x y) (write (+ x y)) (write-char #\space)) '(1 2 3) '(4 5 6)) (newline)
+(write (apply + '(1 2))) (newline)
+(write (apply list 1 2 '(3 4))) (newline)
+(write (apply (lambda args args) '())) (newline)
+(write (apply cons 3 '(7))) (newline)
+(write (assv 2 '((1 . one) (2 . two)))) (newline)
+(write (assoc "b" '(("a" . 1) ("b" . 2)))) (newline)
+(write (assoc 2 '((1 . one) (2 . two)) =)) (newline)
+(write (memq 'c '(a b c d))) (newline)
+(write (memv 3 '(1 2 3 4))) (newline)
+(write (member "x" '("a" "x" "y"))) (newline)
+(write (member 2 '(1 2 3) =)) (newline)
+(write (memq 'z '(a b))) (newline)
+(write (list-tail '(1 2 3 4) 2)) (newline)
+(write (list-ref '(a b c) 1)) (newline)
+(define l (list 1 2 3))
+(list-set! l 1 'two)
+(write l) (newline)
+(define c (list-copy l))
+(set-car! c 'changed)
+(write (list l c)) (newline)
+(write (list-copy '(1 2 . 3))) (newline)
+(write (list (list? '(1 2)) (list? '()) (list? '(1 . 2)) (list? 1))) (newline)
+(define circ (list 1 2 3))
+(set-cdr! (cddr circ) circ)
+(write (list? circ)) (newline)
+(write (make-list 3 'x)) (newline)
+(write (length (make-list 2))) (newline)
+(define (sum . xs) (if (null? xs) 0 (+ (car xs) (apply sum (cdr xs)))))
+(write (sum 1 2 3 4 5)) (newline)
+(apply car '(1 . 2))
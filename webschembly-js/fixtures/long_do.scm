@@ -0,0 +1,14 @@
+(define (sum-to n)
+  (do ((i 0 (+ i 1))
+       (acc 0 (+ acc i)))
+      ((= i n) acc)))
+(write (sum-to 20000))
+(newline)
+
+; トップレベルで始まったループが途中で多倍長整数に切り替わる
+(define x 1)
+(do ((i 0 (+ i 1)))
+    ((= i 100))
+    (set! x (* x 2)))
+(write x)
+(newline)
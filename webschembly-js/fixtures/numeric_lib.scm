@@ -0,0 +1,76 @@
+(define (show x)
+  (write x)
+  (newline))
+
+(show (abs -5))
+(show (abs 5))
+(show (abs -2.5))
+(show (abs -9223372036854775808))
+(show (abs -100000000000000000000))
+(show (abs -1/3))
+(show (min 3 7))
+(show (max 3 7))
+(show (min 1.5 -2.5))
+(show (max 1.5 -2.5))
+(show (max 100000000000000000000 1))
+(show (min 1/2 1/3))
+
+(show (gcd 12 18))
+(show (gcd -12 18))
+(show (gcd 0 5))
+(show (lcm 4 6))
+(show (lcm -4 6))
+(show (lcm 0 6))
+(show (gcd 100000000000000000000 30))
+(show (lcm 10000000000 10000000001))
+
+(show (expt 2 10))
+(show (expt 2 100))
+(show (expt 2 -2))
+(show (expt 0 0))
+(show (expt -1 1000000000000))
+(show (expt 2/3 3))
+(show (expt 2.0 0.5))
+(show (call-with-values (lambda () (exact-integer-sqrt 17)) list))
+(show (call-with-values (lambda () (exact-integer-sqrt 100000000000000000000)) list))
+
+(show (sqrt 16))
+(show (sqrt 2.0))
+(show (exp 0))
+(show (log 1))
+(show (log 8 2))
+(show (sin 0))
+(show (cos 0.0))
+(show (tan 0))
+(show (asin 1))
+(show (acos 1))
+(show (atan 1))
+(show (atan 1 1))
+(show (atan 1.0 -1.0))
+
+(show (floor 2.5))
+(show (ceiling 2.5))
+(show (truncate -2.5))
+(show (round 2.5))
+(show (round 3.5))
+(show (floor -7/2))
+(show (ceiling -7/2))
+(show (truncate -7/2))
+(show (round -7/2))
+(show (ceiling 5))
+(show (truncate 5))
+
+(define (sum-abs lst)
+  (let loop ((l lst) (acc 0))
+    (if (null? l)
+      acc
+      (loop (cdr l) (+ acc (abs (car l)))))))
+(show (sum-abs '(1 -2 3 -4)))
+(define (fmax lst)
+  (let loop ((l (cdr lst)) (m (car lst)))
+    (if (null? l)
+      m
+      (loop (cdr l) (max m (car l))))))
+(show (fmax '(1.0 5.5 -3.0 2.0)))
+
+(exact-integer-sqrt -1)
@@ -0,0 +1,52 @@
+(define (describe x)
+  (cond
+    ((and (number? x) (exact? x)) "int")
+    ((number? x) "real")
+    ((char? x) "char")
+    ((string? x) "string")
+    ((symbol? x) "symbol")
+    ((null? x) "null")
+    ((pair? x) "pair")
+    (else "bool")))
+
+; 同じBBに多数の型の組み合わせで合流する
+(define (combine i j k)
+  (let ((x (cond
+            ((= i 0) 1)
+            ((= i 1) 2.5)
+            ((= i 2) #\a)
+            ((= i 3) "str")
+            ((= i 4) 'sym)
+            ((= i 5) '())
+            ((= i 6) (cons 1 2))
+            (else #t))))
+    (let ((y (cond
+              ((= j 0) 1)
+              ((= j 1) 2.5)
+              ((= j 2) #\a)
+              ((= j 3) "str")
+              ((= j 4) 'sym)
+              ((= j 5) '())
+              ((= j 6) (cons 1 2))
+              (else #t))))
+      (let ((z (if (= k 0) 0 #f)))
+        (string-append (describe x) "/" (describe y) "/" (describe z))))))
+
+(define (run round)
+  (let loop ((i 0) (count 0))
+    (if (< i 128)
+      (let ((s (combine (quotient i 16) (remainder (quotient i 2) 8) (remainder i 2))))
+        (when (= round 0)
+          (display s)
+          (newline))
+        (loop (+ i 1) (+ count (string-length s))))
+      count)))
+
+(write (run 0))
+(newline)
+(let loop ((round 1) (total 0))
+  (if (< round 5)
+    (loop (+ round 1) (+ total (run round)))
+    (begin
+      (write total)
+      (newline))))
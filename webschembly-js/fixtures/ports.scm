@@ -0,0 +1,36 @@
+(define p (open-input-string "(a b . c) 42 #\x 1/3 #(1 2) 'q 123456789012345678901234567890 3.5 #t #s64(1 2)"))
+(write (read p)) (newline)
+(write (read p)) (newline)
+(write (read p)) (newline)
+(write (read p)) (newline)
+(write (read p)) (newline)
+(write (read p)) (newline)
+(write (read p)) (newline)
+(write (read p)) (newline)
+(write (read p)) (newline)
+(write (read p)) (newline)
+(write (eof-object? (read p))) (newline)
+(define o (open-output-string))
+(write 'hello o)
+(display " world" o)
+(write-char #\! o)
+(newline o)
+(write (get-output-string o)) (newline)
+(define i (open-input-string "aλb"))
+(write (peek-char i))
+(write (read-char i))
+(write (read-char i))
+(write (read-char i))
+(write (eof-object? (read-char i)))
+(newline)
+(write (list (port? i) (input-port? i) (output-port? i) (input-port? o) (output-port? o) (port? 1)))
+(newline)
+(write (guard (e ((read-error? e) (error-object-message e))) (read (open-input-string "(1 2"))))
+(newline)
+(write (current-output-port))
+(newline)
+(display "to stdout" (current-output-port))
+(newline (current-output-port))
+(write-string "to stderr" (current-error-port))
+(newline (current-error-port))
+(car (read (open-input-string "()")))
@@ -0,0 +1,67 @@
+(define count 0)
+(define p
+  (delay
+    (begin
+      (set! count (+ count 1))
+      (* 6 7))))
+(write (promise? p))
+(newline)
+(write (force p))
+(newline)
+(write (force p))
+(newline)
+(write count)
+(newline)
+
+(write (force (make-promise 5)))
+(newline)
+(write (eq? p (make-promise p)))
+(newline)
+(write (force 10))
+(newline)
+(write (promise? 10))
+(newline)
+(write (promise? (delay-force (delay 1))))
+(newline)
+(write (force (delay (delay 1))))
+(newline)
+(write (promise? (force (delay (delay 1)))))
+(newline)
+(write p)
+(newline)
+
+; R7RSの例: 再帰的にforceされた場合は最初に評価を終えた値になる
+(define x 5)
+(define p2
+  (delay
+    (begin
+      (set! x (+ x 1))
+      (if (> x 10)
+        x
+        (force p2)))))
+(write (force p2))
+(newline)
+(write (begin (set! x 20) (force p2)))
+(newline)
+
+; delay-forceによる反復的なストリーム
+(define (stream-from n) (delay (cons n (stream-from (+ n 1)))))
+(define (stream-filter pred s)
+  (delay-force
+    (let ((pair (force s)))
+      (if (pred (car pair))
+        (delay (cons (car pair) (stream-filter pred (cdr pair))))
+        (stream-filter pred (cdr pair))))))
+(define (stream-ref s n)
+  (let ((pair (force s)))
+    (if (= n 0)
+      (car pair)
+      (stream-ref (cdr pair) (- n 1)))))
+(write (stream-ref (stream-filter (lambda (n) (= (remainder n 1000000) 0)) (stream-from 1)) 0))
+(newline)
+
+(define (loop n) (delay-force (if (= n 0) (delay 'done) (loop (- n 1)))))
+(write (force (loop 1000000)))
+(newline)
+
+(force (delay-force 1))
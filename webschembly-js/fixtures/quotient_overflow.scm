@@ -0,0 +1,8 @@
+(define (q a b) (quotient a b))
+(write (quotient -9223372036854775808 -1))
+(newline)
+(write (q -9223372036854775808 -1))
+(newline)
+(write (+ (q 7 -2) 1))
+(newline)
+(write (q 7 0))
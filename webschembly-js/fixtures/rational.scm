@@ -0,0 +1,61 @@
+(write (/ 1 3))
+(newline)
+(write (/ 6 3))
+(newline)
+(write (/ -6 4))
+(newline)
+(write 2/4)
+(newline)
+(write -10/5)
+(newline)
+(write (+ 1/3 2/3))
+(newline)
+(write (+ 1/2 1))
+(newline)
+(write (- 1/2 1/3))
+(newline)
+(write (* 2/3 3/4))
+(newline)
+(write (/ 2/3 4))
+(newline)
+(write (/ 1 100000000000000000000))
+(newline)
+(write (* 1/3 300000000000000000000))
+(newline)
+(write (/ 1.0 4.0))
+(newline)
+(write (list (numerator 6/4) (denominator 6/4) (numerator 5) (denominator 5)))
+(newline)
+(write (list (floor 7/2) (floor -7/2) (floor 3) (floor 2.7) (floor -2.7)))
+(newline)
+(write (list (round 7/2) (round 5/2) (round -5/2) (round 1/3) (round 2.5) (round 3.5)))
+(newline)
+(write (list (exact? 1) (exact? 1/2) (exact? 100000000000000000000) (exact? 1.5)))
+(newline)
+(write (list (inexact? 1) (inexact? 1/2) (inexact? 1.5)))
+(newline)
+(write (list (exact->inexact 1/4) (exact->inexact 3) (exact->inexact 1.5)))
+(newline)
+(write (list (inexact->exact 0.25) (inexact->exact 3.0) (inexact->exact 7) (inexact->exact 1/3)))
+(newline)
+(write (list (< 1/3 1/2) (> 1/3 1/2) (<= 1/2 2/4) (>= 1 1/2) (= 1/2 2/4) (= 1/2 1)))
+(newline)
+(write (list (eqv? 1/2 (/ 2 4)) (eqv? 1/2 1/3) (eqv? 1/2 0.5)))
+(newline)
+(write (list (number? 1/2) (number->string 22/7)))
+(newline)
+
+;; 調和数 H(n) = 1 + 1/2 + ... + 1/n
+(define (harmonic n)
+  (let loop ((i 1) (acc 0))
+    (if (> i n)
+        acc
+        (loop (+ i 1) (+ acc (/ 1 i))))))
+(write (harmonic 10))
+(newline)
+(write (harmonic 30))
+(newline)
+(write (exact->inexact (harmonic 30)))
+(newline)
+(write (/ 1 0))
+(newline)
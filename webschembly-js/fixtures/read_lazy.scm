@@ -0,0 +1,11 @@
+(define p (open-input-string "(1 2) 'x #\abc (3) ) 4"))
+(write (read p)) (newline)
+(write (read p)) (newline)
+(define (read-message p)
+  (guard (e ((read-error? e) (error-object-message e))) (read p)))
+(write (read-message p)) (newline)
+(write (read p)) (newline)
+(write (read-message p)) (newline)
+(write (read p)) (newline)
+(write (eof-object? (read p))) (newline)
+(read-char)
@@ -0,0 +1,18 @@
+(define-record-type <point> (make-point x y) point? (x point-x set-point-x!) (y point-y))
+(define p (make-point 1 2))
+(write (point-x p)) (newline)
+(write (point-y p)) (newline)
+(set-point-x! p 10)
+(write (point-x p)) (newline)
+(write (point? p)) (newline)
+(write (point? 5)) (newline)
+(write p) (newline)
+(define-record-type node (make-node val) node? (next node-next set-node-next!) (val node-val))
+(define n (make-node 'a))
+(write (node-val n)) (newline)
+(write (point? n)) (newline)
+(define (sum-x ps acc) (if (null? ps) acc (sum-x (cdr ps) (+ acc (point-x (car ps))))))
+(write (sum-x (list (make-point 1 0) (make-point 2 0) (make-point 3 0)) 0)) (newline)
+(define (f) (let ((vector 1) (car 2)) (make-point vector car)))
+(write (point-y (f))) (newline)
+(point-x n)
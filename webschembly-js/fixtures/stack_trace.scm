@@ -0,0 +1,12 @@
+(define (first-of x) (car x))
+(define (inc-first x) (+ 1 (first-of x)))
+(define (double-first x) (* 2 (inc-first x)))
+(define apply-to-list
+  (lambda (f lst)
+    (let loop ((l lst))
+      (if (null? l)
+        '()
+        (cons (f (car l)) (loop (cdr l)))))))
+(display (number->string (double-first (list 1 2))))
+(newline)
+(apply-to-list double-first (list (list 1) 2))
@@ -0,0 +1,65 @@
+(define (show x)
+  (write x)
+  (newline))
+
+(show (string-append))
+(show (string-append "foo" "bar" "baz"))
+(show (substring "hello world" 6 11))
+(show (substring "hello" 0 0))
+(show (make-string 3 #\a))
+(show (string-length (make-string 2)))
+(show (string #\a #\b #\c))
+(show (string->list "abc"))
+(show (list->string (list #\x #\y)))
+(show (string->list "あいう"))
+(show (list->string (list #\あ #\い)))
+
+(define s (substring "abcdef" 1 4))
+(define t (string-copy s))
+(string-set! t 0 #\X)
+(show s)
+(show t)
+
+(show (string<? "abc" "abd"))
+(show (string<? "abc" "ab"))
+(show (string>? "b" "abc"))
+(show (string<=? "abc" "abc"))
+(show (string>=? "ab" "abc"))
+(show (string-ci=? "Hello" "hELLO"))
+(show (string-ci<? "apple" "BANANA"))
+(show (string-ci>? "apple" "BANANA"))
+(show (string-ci<=? "ABC" "abc"))
+(show (string-ci>=? "abc" "ABD"))
+
+(show (char->integer #\A))
+(show (integer->char 97))
+(show (char-upcase #\a))
+(show (char-downcase #\A))
+(show (char-upcase #\1))
+(show (char-alphabetic? #\a))
+(show (char-alphabetic? #\1))
+(show (char-numeric? #\7))
+(show (char-whitespace? #\space))
+(show (char-upper-case? #\A))
+(show (char-lower-case? #\A))
+(show (char=? #\a #\a))
+(show (char<? #\a #\b))
+(show (char-ci=? #\a #\A))
+(show (string-upcase "Hello"))
+(show (string-downcase "Hello"))
+
+(show (string->number "123"))
+(show (string->number "-45"))
+(show (string->number "1/2"))
+(show (string->number "4/2"))
+(show (string->number "1.5"))
+(show (string->number "123456789012345678901234567890"))
+(show (string->number "abc"))
+(show (string->number ""))
+(show (string->number "1 2"))
+(show (string->number "ff" 16))
+(show (string->number "-101" 2))
+(show (string->number "777" 8))
+(show (string->number "12" 2))
+
+(substring "abc" 2 5)
@@ -0,0 +1,70 @@
+(define-syntax swap!
+  (syntax-rules ()
+    ((_ a b) (let ((tmp a)) (set! a b) (set! b tmp)))))
+(define tmp 1)
+(define y 2)
+(swap! tmp y)
+(write (list tmp y))
+(newline)
+
+(define-syntax my-or
+  (syntax-rules ()
+    ((_) #f)
+    ((_ e) e)
+    ((_ e r ...) (let ((t e)) (if t t (my-or r ...))))))
+(define t 5)
+(write (my-or #f t))
+(newline)
+
+(define-syntax my-if
+  (syntax-rules (then else)
+    ((_ c then x else y) (if c x y))))
+(write (my-if #t then 'yes else 'no))
+(newline)
+
+(define-syntax kons
+  (syntax-rules ()
+    ((_ a b) (cons a b))))
+(define (f cons) (kons cons 2))
+(write (f 1))
+(newline)
+
+(define-syntax my-let*
+  (syntax-rules ()
+    ((_ () body ...) (let () body ...))
+    ((_ ((x v) rest ...) body ...) (let ((x v)) (my-let* (rest ...) body ...)))))
+(write (my-let* ((a 1) (b (+ a 1))) (* a b)))
+(newline)
+
+(define-syntax nested
+  (syntax-rules ()
+    ((_ (a ...) ...) '((a ...) ...))))
+(write (nested (1 2) (3) ()))
+(newline)
+
+(define-syntax last
+  (syntax-rules ()
+    ((_ a ... z) 'z)))
+(write (last 1 2 3))
+(newline)
+
+(define-syntax define-two
+  (syntax-rules ()
+    ((_ a b v) (begin (define a v) (define b v)))))
+(define (g)
+  (define-two m n 3)
+  (* m n))
+(write (g))
+(newline)
+
+(write
+  (let-syntax ((ten (syntax-rules () ((_) 10))))
+    (ten)))
+(newline)
+
+(write
+  (letrec-syntax
+      ((ev? (syntax-rules () ((_ n) (if (= n 0) #t (od? (- n 1))))))
+       (od? (syntax-rules () ((_ n) (if (= n 0) #f #t)))))
+    (ev? 2)))
+(newline)
@@ -0,0 +1,2 @@
+(define-syntax one (syntax-rules () ((_ x) x)))
+(one)
@@ -0,0 +1,28 @@
+; 相互再帰
+(define (my-even? n) (if (= n 0) #t (my-odd? (- n 1))))
+(define (my-odd? n) (if (= n 0) #f (my-even? (- n 1))))
+(write (my-even? 10000000))
+(newline)
+
+; apply経由の末尾呼び出し
+(define (ping n) (if (= n 0) 'ping (apply pong (list (- n 1)))))
+(define (pong n) (if (= n 0) 'pong (apply ping n '())))
+(write (ping 1000001))
+(newline)
+
+; 可変長引数のクロージャ
+(define (count-rest n . rest)
+  (if (= n 0)
+    (length rest)
+    (count-rest (- n 1) n n n)))
+(write (count-rest 1000000))
+(newline)
+
+; condとandの末尾位置
+(define (loop n acc)
+  (cond
+    ((= n 0) acc)
+    ((even? n) (and #t (loop (- n 1) (+ acc 1))))
+    (else (loop (- n 1) acc))))
+(write (loop 1000000 0))
+(newline)
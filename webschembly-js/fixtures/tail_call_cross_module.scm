@@ -0,0 +1,11 @@
+; モジュールをまたいだ相互再帰
+(define (my-even? n) (if (= n 0) #t (my-odd? (- n 1))))
+(eval '(define (my-odd? n) (if (= n 0) #f (my-even? (- n 1)))) (interaction-environment))
+(write (my-even? 10000000))
+(newline)
+
+; モジュールをまたいだapply経由の末尾呼び出し
+(define (ping n) (if (= n 0) 'ping (apply pong (list (- n 1)))))
+(eval '(define (pong n) (if (= n 0) 'pong (apply ping n '()))) (interaction-environment))
+(write (ping 1000001))
+(newline)
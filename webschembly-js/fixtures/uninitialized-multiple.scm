@@ -0,0 +1,6 @@
+(define (f)
+  (define a b)
+  (define b 1)
+  (define c (set! d 2))
+  (define d 3)
+  a)
@@ -0,0 +1,36 @@
+(define (show x)
+  (write x)
+  (newline))
+
+(show (call-with-values (lambda () (values 1 2 3)) list))
+(show (call-with-values (lambda () (values)) list))
+(show (call-with-values (lambda () 5) list))
+(show (call-with-values (lambda () (exact-integer-sqrt 17)) list))
+
+(let-values (((a b) (values 1 2))
+             ((c . d) (values 3 4 5))
+             (e (values 6 7)))
+  (show (list a b c d e)))
+
+; let-valuesの式は外側の環境で評価される
+(let ((a 'outer))
+  (let-values (((a) (values 1))
+               ((b) (values a)))
+    (show (list a b))))
+
+(let*-values (((a b) (values 1 2))
+              ((c) (values (+ a b))))
+  (show c))
+
+(define-values (q r) (exact-integer-sqrt 17))
+(show (list q r))
+(define-values (x . y) (values 1 2 3))
+(show (list x y))
+(define-values z (values 4 5))
+(show z)
+
+(define (f)
+  (define-values (a b) (values 10 20))
+  (define c (+ 1 2))
+  (list a b c))
+(show (f))
@@ -0,0 +1,61 @@
+(define (print x) (write x) (newline))
+
+(print (+))
+(print (+ 5))
+(print (+ 1 2 3 4))
+(print (+ 1.5 2.5 3.0))
+(print (+ 1/2 1/3 1/6))
+(print (+ 9223372036854775807 1 1))
+(print (*))
+(print (* 2))
+(print (* 2.5))
+(print (* 1 2 3 4 5))
+(print (- 5))
+(print (- 2.5))
+(print (- -9223372036854775808))
+(print (- 10 1 2 3))
+(print (/ 2))
+(print (/ 4.0))
+(print (/ 60 2 3 4))
+(print (max 3))
+(print (max 1 5 3 2))
+(print (min 4 2 8 -1 7))
+(print (max 1.5 0.5 2.5))
+
+(print (< 1 2 3))
+(print (< 1 3 2))
+(print (<= 1 1 2 2))
+(print (> 3 2 1))
+(print (>= 3 3 4))
+(print (= 1 1 1))
+(print (= 1 1 2))
+
+; 引数は一度だけ評価される
+(define count 0)
+(define (next!) (set! count (+ count 1)) count)
+(print (< 0 (next!) 5))
+(print count)
+
+; 第一級の値として使う
+(print (apply + '(1 2 3 4)))
+(print (apply + '()))
+(print (apply - '(5)))
+(print (apply - '(10 1 2)))
+(print (apply * '(1 2 3 4)))
+(print (apply max '(3 9 4)))
+(print (apply min '(3 9 4)))
+(print (apply < '(1 2 3)))
+(print (apply < '(1 3 2)))
+(print (apply = '(2 2)))
+(print (map + '(1 2) '(10 20) '(100 200)))
+(define (fold f acc xs)
+  (if (null? xs) acc (fold f (f acc (car xs)) (cdr xs))))
+(print (fold + 0 '(1 2 3)))
+(print (fold * 1 '(1 2 3 4)))
+(let ((out (open-output-string)))
+  (display "port" out)
+  (print (get-output-string out)))
+(print (apply atan '(1.0 1.0)))
+(print (apply log '(1.0)))
+
+(< 1 2 'a)
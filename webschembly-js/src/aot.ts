@@ -0,0 +1,64 @@
+import {
+  type RuntimeEnv,
+  type RuntimeExports,
+  type RuntimeImportsEnv,
+  type TypedWebAssemblyInstance,
+} from "./runtime.js";
+
+// ランタイムとwasm-mergeでまとめたモジュールを実行する
+export function runAot(
+  wasmModule: WebAssembly.Module,
+  { exit, logger, writeBuf }: RuntimeEnv,
+): void {
+  const runtimeImportObjects: RuntimeImportsEnv = {
+    js_instantiate: () => {
+      throw new Error(
+        "js_instantiate is not supported in run-aot. Use run instead.",
+      );
+    },
+    js_webschembly_log: (bufPtr, bufLen) => {
+      const s = new TextDecoder().decode(
+        new Uint8Array(wasmInstance.exports.memory.buffer, bufPtr, bufLen),
+      );
+      logger.log(s);
+    },
+    js_write_buf: (fd, bufPtr, bufLen) => {
+      const buf = new Uint8Array(
+        wasmInstance.exports.memory.buffer,
+        bufPtr,
+        bufLen,
+      );
+      writeBuf(fd, buf);
+    },
+    js_webschembly_jit_log: () => {},
+  };
+
+  const wasmInstance = new WebAssembly.Instance(wasmModule, {
+    env: runtimeImportObjects,
+    dynamic: {},
+  }) as TypedWebAssemblyInstance<RuntimeExports>;
+
+  let failed = false;
+  try {
+    // reverseしないと動かない(runtimeが後にexportされているため)
+    for (const key of Object.keys(wasmInstance.exports).reverse()) {
+      if (key.startsWith("start")) {
+        (wasmInstance.exports[key] as Function)();
+      }
+    }
+  } catch (e) {
+    if (
+      e instanceof WebAssembly.Exception
+      && e.is(wasmInstance.exports.WEBSCHEMBLY_EXCEPTION)
+    ) {
+      failed = true;
+    } else {
+      throw e;
+    }
+  }
+  // バッファに残った出力を書き出してから終了する
+  wasmInstance.exports.cleanup();
+  if (failed) {
+    exit(1);
+  }
+}
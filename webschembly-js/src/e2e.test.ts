@@ -1,6 +1,9 @@
+import { execFileSync } from "child_process";
 import * as fs from "fs/promises";
+import * as os from "os";
 import * as path from "path";
 import { beforeAll, describe, expect, test } from "vitest";
+import { runAot } from "./aot.js";
 import { createNodeRuntimeEnv } from "./node-runtime-env.js";
 import { type CompilerConfig, compilerConfigToString, createRuntime } from "./runtime.js";
 import * as testUtils from "./test-utils.js";
@@ -18,14 +21,100 @@ function concatBufs(bufs: Uint8Array[]) {
 
 const snapshotDir = "e2e_snapshots";
 
+// JITの設定は最初の呼び出しから特殊化するようにし、ベースライン層からの切り替えは別の設定で確認する
 const compilerConfigs: CompilerConfig[] = [
-  { enableJitOptimization: false },
+  { enableJitOptimization: false, jitTierUpThreshold: 0 },
   { enableJit: false },
-  { enableJitSmallBlockFusion: false, enableJitLargeBlockFusion: false },
-  { enableJitSmallBlockFusion: false, enableJitLargeBlockFusion: true },
-  { enableJitSmallBlockFusion: true, enableJitLargeBlockFusion: false },
+  {
+    enableJitSmallBlockFusion: false,
+    enableJitLargeBlockFusion: false,
+    jitTierUpThreshold: 0,
+  },
+  {
+    enableJitSmallBlockFusion: false,
+    enableJitLargeBlockFusion: true,
+    jitTierUpThreshold: 0,
+  },
+  {
+    enableJitSmallBlockFusion: true,
+    enableJitLargeBlockFusion: false,
+    jitTierUpThreshold: 0,
+  },
+  { jitTierUpThreshold: 0 },
+  { jitTierUpThreshold: 100 },
+  { enableDebugInfo: true, jitTierUpThreshold: 0 },
 ];
 
+async function runFixture(
+  runtimeModule: WebAssembly.Module,
+  srcBuf: Uint8Array,
+  filename: string,
+  compilerConfig: CompilerConfig,
+) {
+  let exitCode = 0;
+  const stdoutBufs: Uint8Array[] = [];
+  const stderrBufs: Uint8Array[] = [];
+  const runtime = await createRuntime(
+    await createNodeRuntimeEnv({
+      runtimeName: filename,
+      exit: (code) => {
+        exitCode = code;
+      },
+      writeBuf: (fd, buf) => {
+        switch (fd) {
+          case 1:
+            stdoutBufs.push(new Uint8Array(buf));
+            break;
+          case 2:
+            stderrBufs.push(new Uint8Array(buf));
+            break;
+          default:
+            throw new Error(`Unsupported file descriptor: ${fd}`);
+        }
+      },
+      loadRuntimeModule: async () => runtimeModule,
+    }),
+    {
+      compilerConfig: {
+        enableContinuation: testUtils.usesContinuation(
+          new TextDecoder().decode(srcBuf),
+        ),
+        ...compilerConfig,
+      },
+    },
+  );
+
+  runtime.loadStdlib();
+  runtime.loadSrc(srcBuf, filename);
+  runtime.cleanup();
+
+  return {
+    exitCode,
+    stdout: new TextDecoder().decode(concatBufs(stdoutBufs)),
+    stderr: new TextDecoder().decode(concatBufs(stderrBufs)),
+    jitProfile: runtime.dumpJitProfile(),
+  };
+}
+
+async function expectSnapshot(
+  filename: string,
+  {
+    exitCode,
+    stdout,
+    stderr,
+  }: { exitCode: number; stdout: string; stderr: string },
+) {
+  await expect(exitCode).toMatchFileSnapshot(
+    `${snapshotDir}/${filename}-exitCode`,
+  );
+  await expect(stdout).toMatchFileSnapshot(
+    `${snapshotDir}/${filename}-stdout`,
+  );
+  await expect(stderr).toMatchFileSnapshot(
+    `${snapshotDir}/${filename}-stderr`,
+  );
+}
+
 describe("E2E test", async () => {
   let runtimeModule: WebAssembly.Module;
   const filenames = await testUtils.getAllFixtureFilenames();
@@ -50,52 +139,44 @@ describe("E2E test", async () => {
       test(
         "snapshot test",
         async () => {
-          let exitCode = 0;
-          const stdoutBufs: Uint8Array[] = [];
-          const stderrBufs: Uint8Array[] = [];
-          const runtime = await createRuntime(
-            await createNodeRuntimeEnv({
-              runtimeName: filename,
-              exit: (code) => {
-                exitCode = code;
-              },
-              writeBuf: (fd, buf) => {
-                switch (fd) {
-                  case 1:
-                    stdoutBufs.push(new Uint8Array(buf));
-                    break;
-                  case 2:
-                    stderrBufs.push(new Uint8Array(buf));
-                    break;
-                  default:
-                    throw new Error(`Unsupported file descriptor: ${fd}`);
-                }
-              },
-              loadRuntimeModule: async () => runtimeModule,
-            }),
-            {
-              compilerConfig,
-            },
+          await expectSnapshot(
+            filename,
+            await runFixture(runtimeModule, srcBuf, filename, compilerConfig),
           );
+        },
+        60 * 1000,
+      );
+    });
 
-          runtime.loadStdlib();
-          runtime.loadSrc(srcBuf);
-          runtime.cleanup();
-
-          const stdout = new TextDecoder().decode(concatBufs(stdoutBufs));
-          const stderr = new TextDecoder().decode(concatBufs(stderrBufs));
+    if (filenames.length === 0) {
+      test("dummy test to avoid empty describe block", () => {});
+    }
+  });
 
-          await expect(exitCode).toMatchFileSnapshot(
-            `${snapshotDir}/${filename}-exitCode`,
+  // 1回目の実行で書き出したプロファイルを読み込んで再実行する
+  describe("jitProfile", () => {
+    describe.each(filenames)("%s", (filename) => {
+      test(
+        "snapshot test",
+        async () => {
+          const srcBuf = await fs.readFile(
+            path.join(testUtils.fixtureDir, filename),
           );
-          await expect(stdout).toMatchFileSnapshot(
-            `${snapshotDir}/${filename}-stdout`,
+          const { jitProfile } = await runFixture(
+            runtimeModule,
+            srcBuf,
+            filename,
+            {},
           );
-          await expect(stderr).toMatchFileSnapshot(
-            `${snapshotDir}/${filename}-stderr`,
+          expect(jitProfile).not.toBeNull();
+          await expectSnapshot(
+            filename,
+            await runFixture(runtimeModule, srcBuf, filename, {
+              jitProfile: jitProfile!,
+            }),
           );
         },
-        60 * 1000,
+        120 * 1000,
       );
     });
 
@@ -104,3 +185,241 @@ describe("E2E test", async () => {
     }
   });
 });
+
+// JITの実行で書き出したプロファイルからAOTでビルドし、run-aotと同じようにランタイムのコンパイラなしで実行する
+describe("AOT", async () => {
+  let runtimeModule: WebAssembly.Module;
+  // evalはランタイムのコンパイラが必要なので除く
+  const filenames = (
+    await Promise.all(
+      (await testUtils.getAllFixtureFilenames()).map(async (filename) => {
+        const src = await fs.readFile(
+          path.join(testUtils.fixtureDir, filename),
+          "utf-8",
+        );
+        return src.includes("(eval ") ? [] : [filename];
+      }),
+    )
+  ).flat();
+  beforeAll(async () => {
+    runtimeModule = new WebAssembly.Module(
+      await fs.readFile(process.env["WEBSCHEMBLY_RUNTIME"]!),
+    );
+  });
+
+  describe.each(filenames)("%s", (filename) => {
+    test(
+      "snapshot test",
+      async () => {
+        const srcBuf = await fs.readFile(
+          path.join(testUtils.fixtureDir, filename),
+        );
+        const jitResult = await runFixture(
+          runtimeModule,
+          srcBuf,
+          filename,
+          {},
+        );
+        const { jitProfile } = jitResult;
+        expect(jitProfile).not.toBeNull();
+
+        const tmpDir = await fs.mkdtemp(path.join(os.tmpdir(), "webschembly-aot-"));
+        const profilePath = path.join(tmpDir, "profile.json");
+        await fs.writeFile(profilePath, jitProfile!);
+        const wasmPath = path.join(
+          testUtils.fixtureDir,
+          filename.replace(/\.scm$/, ".aot.wasm"),
+        );
+        const continuation = testUtils.usesContinuation(
+          new TextDecoder().decode(srcBuf),
+        );
+        let buildError: string | null = null;
+        try {
+          execFileSync("make", [
+            wasmPath,
+            `AOT_PROFILE=${profilePath}`,
+            `CONTINUATION=${continuation ? "1" : ""}`,
+          ], {
+            stdio: ["ignore", "ignore", "pipe"],
+          });
+        } catch (e) {
+          buildError = String((e as { stderr: Buffer }).stderr);
+        }
+        await fs.rm(tmpDir, { recursive: true });
+
+        // コンパイルエラーはAOTではビルド時に同じメッセージで報告される
+        // CLIはfixtures/からのパスをファイル名として表示する
+        if (buildError !== null) {
+          expect(jitResult.exitCode).toBe(1);
+          expect(jitResult.stdout).toBe("");
+          expect(buildError).toContain(
+            jitResult.stderr.replaceAll(
+              filename,
+              path.join(testUtils.fixtureDir, filename),
+            ),
+          );
+          return;
+        }
+
+        let exitCode = 0;
+        const stdoutBufs: Uint8Array[] = [];
+        const stderrBufs: Uint8Array[] = [];
+        runAot(
+          new WebAssembly.Module(await fs.readFile(wasmPath)),
+          await createNodeRuntimeEnv({
+            runtimeName: filename,
+            exit: (code) => {
+              exitCode = code;
+            },
+            writeBuf: (fd, buf) => {
+              switch (fd) {
+                case 1:
+                  stdoutBufs.push(new Uint8Array(buf));
+                  break;
+                case 2:
+                  stderrBufs.push(new Uint8Array(buf));
+                  break;
+                default:
+                  throw new Error(`Unsupported file descriptor: ${fd}`);
+              }
+            },
+          }),
+        );
+
+        await expectSnapshot(filename, {
+          exitCode,
+          stdout: new TextDecoder().decode(concatBufs(stdoutBufs)),
+          stderr: new TextDecoder().decode(concatBufs(stderrBufs)),
+        });
+      },
+      180 * 1000,
+    );
+  });
+
+  if (filenames.length === 0) {
+    test("dummy test to avoid empty describe block", () => {});
+  }
+});
+
+// 自己末尾呼び出しのループは、後方エッジのOSRで実行中に特殊化された版に移行する
+describe("JIT log", async () => {
+  let runtimeModule: WebAssembly.Module;
+  beforeAll(async () => {
+    runtimeModule = new WebAssembly.Module(
+      await fs.readFile(process.env["WEBSCHEMBLY_RUNTIME"]!),
+    );
+  });
+
+  test(
+    "long do loop switches to the specialized version during its run",
+    async () => {
+      const events: any[] = [];
+      const stdoutBufs: Uint8Array[] = [];
+      const runtime = await createRuntime(
+        await createNodeRuntimeEnv({
+          exit: () => {},
+          writeBuf: (fd, buf) => {
+            if (fd === 1) {
+              stdoutBufs.push(new Uint8Array(buf));
+            }
+          },
+          logger: {
+            log: () => {},
+            instantiate: () => {},
+            logJit: (data) => events.push(data),
+          },
+          loadRuntimeModule: async () => runtimeModule,
+        }),
+        { compilerConfig: { enableJitLog: true } },
+      );
+
+      // count-upは1回しか呼ばれないので、特殊化はループの実行中に起こる
+      runtime.loadStdlib();
+      runtime.loadSrc(
+        new TextEncoder().encode(`
+(define (count-up n)
+  (do ((i 0 (+ i 1)))
+      ((= i n) i)))
+(write (count-up 20000))
+`),
+        "long_loop.scm",
+      );
+      runtime.cleanup();
+      expect(new TextDecoder().decode(concatBufs(stdoutBufs))).toBe("20000");
+
+      const bbs = events.filter((event) => event.type === "bb");
+      const key = (event: any) => `${event.module_id}:${event.func_id}`;
+      const genericBBs = new Set(
+        bbs
+          .filter((event) => event.func_index === 0)
+          .map((event) => `${key(event)}:${event.bb_id}`),
+      );
+      // ループは汎用の版で始まり、ループの出口には特殊化された版からしか到達していない
+      const exitedFromSpecialized = bbs.filter(
+        (event) =>
+          event.func_index > 0 &&
+          event.successors.length === 0 &&
+          !genericBBs.has(`${key(event)}:${event.bb_id}`) &&
+          bbs.some(
+            (other) => other.func_index === 0 && key(other) === key(event),
+          ),
+      );
+      expect(exitedFromSpecialized).not.toHaveLength(0);
+    },
+    60 * 1000,
+  );
+
+  test(
+    "loop moves to the specialized body through OSR",
+    async () => {
+      const events: any[] = [];
+      const stdoutBufs: Uint8Array[] = [];
+      const runtime = await createRuntime(
+        await createNodeRuntimeEnv({
+          exit: () => {},
+          writeBuf: (fd, buf) => {
+            if (fd === 1) {
+              stdoutBufs.push(new Uint8Array(buf));
+            }
+          },
+          logger: {
+            log: () => {},
+            instantiate: () => {},
+            logJit: (data) => events.push(data),
+          },
+          loadRuntimeModule: async () => runtimeModule,
+        }),
+        { compilerConfig: { enableJitLog: true } },
+      );
+
+      runtime.loadStdlib();
+      runtime.loadSrc(
+        new TextEncoder().encode(`
+(define (sum-float n)
+  (let loop ((i 0) (x 0.0))
+    (if (= i n)
+        x
+        (loop (+ i 1) (+ x 0.5)))))
+(write (sum-float 3000))
+`),
+        "osr_loop.scm",
+      );
+      runtime.cleanup();
+      expect(new TextDecoder().decode(concatBufs(stdoutBufs))).toBe("1500.0");
+
+      const key = (event: any) =>
+        `${event.module_id}:${event.func_id}:${event.env_index}:${event.func_index}:${event.bb_id}:${event.index}`;
+      const osrTargets = new Set(
+        events.filter((event) => event.type === "osr").map(key),
+      );
+      expect(osrTargets.size).not.toBe(0);
+      // 後方エッジの回数が閾値に達すると、OSRの移り先のループの本体がコンパイルされる
+      const enteredBodies = events.filter(
+        (event) => event.type === "bb" && osrTargets.has(key(event)),
+      );
+      expect(enteredBodies).not.toHaveLength(0);
+    },
+    60 * 1000,
+  );
+});
+
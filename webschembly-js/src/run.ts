@@ -7,15 +7,30 @@ if (!srcName) {
   console.error("Usage: run <src>");
   process.exit(1);
 }
+// 指定されたファイルからJITのプロファイルを読み込み、終了時に書き出す
+const jitProfilePath = process.env["WEBSCHEMBLY_JIT_PROFILE"];
 const runtime = await createRuntime(
   await createNodeRuntimeEnv({
     runtimeName: srcName,
   }),
-  {},
+  {
+    compilerConfig: {
+      jitProfile: jitProfilePath !== undefined && fs.existsSync(jitProfilePath)
+        ? fs.readFileSync(jitProfilePath, "utf-8")
+        : undefined,
+    },
+  },
 );
 
 const srcBuf = new Uint8Array(fs.readFileSync(srcName));
 
 runtime.loadStdlib();
-runtime.loadSrc(srcBuf);
+runtime.loadSrc(srcBuf, srcName);
 runtime.cleanup();
+
+if (jitProfilePath !== undefined) {
+  const jitProfile = runtime.dumpJitProfile();
+  if (jitProfile !== null) {
+    fs.writeFileSync(jitProfilePath, jitProfile);
+  }
+}
@@ -17,6 +17,12 @@ export type CompilerConfig = {
   enableJitSmallBlockFusion?: boolean;
   enableJitLargeBlockFusion?: boolean;
   enableJitLog?: boolean;
+  // 関数がこの回数呼び出されるまではJITコンパイルしない。0なら最初の呼び出しでコンパイルする
+  jitTierUpThreshold?: number;
+  // 前回の実行でRuntime.dumpJitProfileが返したJSON
+  jitProfile?: string;
+  enableContinuation?: boolean;
+  enableDebugInfo?: boolean;
 };
 
 export function compilerConfigToString(config: CompilerConfig): string {
@@ -34,10 +40,13 @@ export type RuntimeLogger = {
 
 export type Runtime = {
   loadStdlib: () => void;
-  loadSrc: (srcBuf: Uint8Array) => void;
+  // srcNameはスタックトレースやコンパイルエラーに表示される
+  loadSrc: (srcBuf: Uint8Array, srcName?: string) => void;
   flushAll: () => void;
   cleanup: () => void;
   mallocString: (s: string) => [ptr: number, len: number];
+  // JITが無効の場合はnull
+  dumpJitProfile: () => string | null;
   instance: TypedWebAssemblyInstance<RuntimeExports>;
   getGlobal(name: string): SchemeValue;
 };
@@ -71,7 +80,12 @@ export type RuntimeExports = {
   malloc: (size: number) => number;
   free: (ptr: number) => void;
   load_stdlib: () => void;
-  load_src: (srcPtr: number, srcLen: number) => void;
+  load_src: (
+    srcPtr: number,
+    srcLen: number,
+    namePtr: number,
+    nameLen: number,
+  ) => void;
   flush_all: () => void;
   cleanup: () => void;
   init: () => void;
@@ -80,6 +94,11 @@ export type RuntimeExports = {
   compiler_config_enable_jit_block_fusion: (enable: number) => void;
   compiler_config_enable_jit_large_block_fusion: (enable: number) => void;
   compiler_config_enable_jit_log: (enable: number) => void;
+  compiler_config_jit_tier_up_threshold: (threshold: number) => void;
+  compiler_config_jit_profile: (bufPtr: number, bufLen: number) => void;
+  jit_profile: () => bigint;
+  compiler_config_enable_continuation: (enable: number) => void;
+  compiler_config_enable_debug_info: (enable: number) => void;
 };
 
 export type ModuleImports = {
@@ -131,14 +150,8 @@ export async function createRuntime(
 
       const result = instance.exports.start();
       if (printEvalResult && fromSrc !== 0) {
-        const writeClosure = getGlobal("write");
-        const writeParams = runtimeInstance.exports.new_args(1);
-        runtimeInstance.exports.set_args(writeParams, 0, result);
-        runtimeInstance.exports.call_closure(writeClosure, writeParams);
-
-        const newlineClosure = getGlobal("newline");
-        const newlineParams = runtimeInstance.exports.new_args(0);
-        runtimeInstance.exports.call_closure(newlineClosure, newlineParams);
+        callGlobal("write", [result]);
+        callGlobal("newline", []);
       }
     },
     js_webschembly_log: (bufPtr, bufLen) => {
@@ -205,6 +218,30 @@ export async function createRuntime(
     );
   }
 
+  if (compilerConfig?.jitTierUpThreshold !== undefined) {
+    runtimeInstance.exports.compiler_config_jit_tier_up_threshold(
+      compilerConfig.jitTierUpThreshold,
+    );
+  }
+
+  if (compilerConfig?.jitProfile !== undefined) {
+    const [bufPtr, bufLen] = mallocString(compilerConfig.jitProfile);
+    runtimeInstance.exports.compiler_config_jit_profile(bufPtr, bufLen);
+    runtimeInstance.exports.free(bufPtr);
+  }
+
+  if (compilerConfig?.enableContinuation !== undefined) {
+    runtimeInstance.exports.compiler_config_enable_continuation(
+      Number(compilerConfig.enableContinuation),
+    );
+  }
+
+  if (compilerConfig?.enableDebugInfo !== undefined) {
+    runtimeInstance.exports.compiler_config_enable_debug_info(
+      Number(compilerConfig.enableDebugInfo),
+    );
+  }
+
   runtimeInstance.exports.init();
 
   const importObject: ModuleImports = {
@@ -236,6 +273,31 @@ export async function createRuntime(
     return [bufPtr, buf.length];
   }
 
+  function dumpJitProfile(): string | null {
+    // (ptr, len)がi64にまとめられている
+    const tuple = runtimeInstance.exports.jit_profile();
+    const bufPtr = Number(tuple & 0xffffffffn);
+    const bufLen = Number(tuple >> 32n);
+    if (bufPtr === 0) {
+      return null;
+    }
+    const profile = new TextDecoder().decode(
+      new Uint8Array(runtimeInstance.exports.memory.buffer, bufPtr, bufLen),
+    );
+    runtimeInstance.exports.free(bufPtr);
+    return profile;
+  }
+
+  // 継続が有効な場合、クロージャは第1引数に継続を受け取る
+  function callGlobal(name: string, args: SchemeValue[]): SchemeValue {
+    const closureArgs = compilerConfig?.enableContinuation
+      ? [getGlobal("%identity-continuation"), ...args]
+      : args;
+    const params = runtimeInstance.exports.new_args(closureArgs.length);
+    closureArgs.forEach((arg, i) => runtimeInstance.exports.set_args(params, i, arg));
+    return runtimeInstance.exports.call_closure(getGlobal(name), params);
+  }
+
   function getGlobal(name: string): SchemeValue {
     const [namePtr, nameLen] = mallocString(name);
     const id = runtimeInstance.exports.get_global_id(namePtr, nameLen);
@@ -251,14 +313,25 @@ export async function createRuntime(
     loadStdlib: errorHandle(() => {
       runtimeInstance.exports.load_stdlib();
     }),
-    loadSrc: errorHandle((srcBuf) => {
+    loadSrc: errorHandle((srcBuf, srcName) => {
       const srcBufPtr = runtimeInstance.exports.malloc(srcBuf.length);
       new Uint8Array(runtimeInstance.exports.memory.buffer).set(
         srcBuf,
         srcBufPtr,
       );
-      runtimeInstance.exports.load_src(srcBufPtr, srcBuf.length);
+      const [namePtr, nameLen] = srcName === undefined
+        ? [0, 0]
+        : mallocString(srcName);
+      runtimeInstance.exports.load_src(
+        srcBufPtr,
+        srcBuf.length,
+        namePtr,
+        nameLen,
+      );
       runtimeInstance.exports.free(srcBufPtr);
+      if (namePtr !== 0) {
+        runtimeInstance.exports.free(namePtr);
+      }
     }),
     flushAll: () => {
       runtimeInstance.exports.flush_all();
@@ -267,6 +340,7 @@ export async function createRuntime(
       runtimeInstance.exports.cleanup();
     },
     mallocString,
+    dumpJitProfile,
     instance: runtimeInstance,
     getGlobal,
   };
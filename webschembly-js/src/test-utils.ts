@@ -55,6 +55,11 @@ async function readDirRec(
   }
 }
 
+// call/ccを使うfixtureだけ継続を有効にする(CPS変換でスタックトレースが変わるため)
+export function usesContinuation(src: string): boolean {
+  return src.includes("call/cc") || src.includes("call-with-current-continuation");
+}
+
 export async function getAllFixtureFilenames(): Promise<string[]> {
   const result: string[] = [];
   await readDirRec(fixtureDir, "", result);
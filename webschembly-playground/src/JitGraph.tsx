@@ -28,17 +28,47 @@ function LayoutFitUpdater({ nodes }: { nodes: Node[] }) {
   return null;
 }
 
-export type JitLogEvent = {
-  type: "bb";
-  module_id: number;
-  func_id: number;
-  env_index: number;
-  func_index: number;
-  bb_id: number;
-  index: number;
-  successors: [number, number][];
-  display: string;
-};
+export type JitLogEvent =
+  | {
+    type: "bb";
+    module_id: number;
+    func_id: number;
+    env_index: number;
+    func_index: number;
+    bb_id: number;
+    index: number;
+    successors: [number, number][];
+    display: string;
+  }
+  | {
+    type: "bb_evict";
+    module_id: number;
+    func_id: number;
+    env_index: number;
+    func_index: number;
+    bb_id: number;
+    index: number;
+    hits: number;
+  }
+  | {
+    type: "bb_megamorphic";
+    module_id: number;
+    func_id: number;
+    env_index: number;
+    func_index: number;
+    bb_id: number;
+    evictions: number;
+  }
+  | {
+    type: "closure_megamorphic";
+    arg_types: string;
+  }
+  | {
+    type: "env_megamorphic";
+    module_id: number;
+    func_id: number;
+    env_types: string;
+  };
 
 type JitGraphProps = {
   logs: JitLogEvent[];
@@ -0,0 +1,68 @@
+// 整数はlib.watとの間で2の補数リトルエンディアンのバイト列としてやり取りする
+use crate::{cons_tuple_i32, malloc, runtime_error};
+use num_bigint::{BigInt, Sign};
+use num_integer::Integer;
+
+pub(crate) fn read_integer(ptr: i32, len: i32) -> BigInt {
+    let bytes = unsafe { std::slice::from_raw_parts(ptr as *const u8, len as usize) };
+    BigInt::from_signed_bytes_le(bytes)
+}
+
+pub(crate) fn write_bytes(bytes: &[u8]) -> i64 {
+    let ptr = unsafe { malloc(bytes.len() as i32) };
+    unsafe {
+        std::ptr::copy_nonoverlapping(bytes.as_ptr(), ptr as *mut u8, bytes.len());
+    }
+    cons_tuple_i32(ptr, bytes.len() as i32)
+}
+
+// opはwasm_generatorのIntegerBinOpと対応
+#[unsafe(no_mangle)]
+pub extern "C" fn _integer_binop(op: i32, a_ptr: i32, a_len: i32, b_ptr: i32, b_len: i32) -> i64 {
+    let a = read_integer(a_ptr, a_len);
+    let b = read_integer(b_ptr, b_len);
+    // num-bigintは0除算でパニックするので、その前にSchemeのエラーにする
+    if matches!(op, 3..=5) && b.sign() == Sign::NoSign {
+        let name = ["quotient", "remainder", "modulo"][op as usize - 3];
+        runtime_error(&format!("{}: division by zero", name));
+    }
+    let r = match op {
+        0 => a + b,
+        1 => a - b,
+        2 => a * b,
+        3 => a / b,
+        4 => a % b,
+        5 => {
+            let r = a % &b;
+            if r.sign() != Sign::NoSign && r.sign() != b.sign() {
+                r + b
+            } else {
+                r
+            }
+        }
+        6 => a.gcd(&b),
+        7 => a.lcm(&b),
+        _ => unreachable!("unknown integer binop: {}", op),
+    };
+    // 最短の表現になるので、i64に収まるかどうかは長さで判定できる
+    write_bytes(&r.to_signed_bytes_le())
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn _integer_cmp(a_ptr: i32, a_len: i32, b_ptr: i32, b_len: i32) -> i32 {
+    let a = read_integer(a_ptr, a_len);
+    let b = read_integer(b_ptr, b_len);
+    a.cmp(&b) as i32
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn _bigint_to_string(ptr: i32, len: i32) -> i64 {
+    write_bytes(read_integer(ptr, len).to_string().as_bytes())
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn _string_to_bigint(ptr: i32, len: i32) -> i64 {
+    let s = unsafe { std::slice::from_raw_parts(ptr as *const u8, len as usize) };
+    let x = BigInt::parse_bytes(s, 10).unwrap();
+    write_bytes(&x.to_signed_bytes_le())
+}
@@ -0,0 +1,443 @@
+// ランタイムのコンパイラを使う機能。JITとevalはこれに依存する
+// AOTの出力を実行するだけならcompiler featureを無効にしてコンパイラを含めずにビルドできる
+use crate::{STDERR_FD, WRITERS, cons_tuple_i32, env, get_writer, malloc, read_bytes, runtime};
+use core::cell::RefCell;
+use std::cell::{Ref, RefMut};
+use webschembly_compiler_ir::SourceLoc;
+
+thread_local!(
+    static COMPILER: RefCell<Option<webschembly_compiler::compiler::Compiler>> =
+        const { RefCell::new(None) };
+);
+
+fn load_src_inner(src: String, file_name: Option<String>, is_stdlib: bool) {
+    compile_and_instantiate(
+        |compiler| compiler.compile_module(&src, file_name.as_deref(), is_stdlib),
+        &src,
+        file_name.as_deref(),
+        true,
+    );
+}
+
+// コンパイルエラーの場合はsrcを使ってエラーを表示し、プログラムを終了する
+pub(crate) fn compile_and_instantiate(
+    compile: impl FnOnce(
+        &mut webschembly_compiler::compiler::Compiler,
+    ) -> webschembly_compiler_error::Result<webschembly_compiler_ir::Module>,
+    src: &str,
+    file_name: Option<&str>,
+    from_src: bool,
+) {
+    let result = COMPILER.with(|compiler| {
+        let mut compiler = RefMut::map(compiler.borrow_mut(), |c| c.as_mut().unwrap());
+        compile(&mut compiler).map(|module| {
+            let wasm = webschembly_compiler::wasm_generator::generate_with_config(
+                &module,
+                &compiler.wasm_generator_config(),
+            )
+            .wasm;
+            let ir = if cfg!(debug_assertions) {
+                let ir = format!("{}", module.display());
+                Some(ir.into_bytes())
+            } else {
+                None
+            };
+            (wasm, ir)
+        })
+    });
+
+    match result {
+        Ok((wasm, ir)) => unsafe {
+            env::js_instantiate(
+                wasm.as_ptr() as i32,
+                wasm.len() as i32,
+                ir.as_ref().map(|ir| ir.as_ptr() as i32).unwrap_or(0),
+                ir.as_ref().map(|ir| ir.len() as i32).unwrap_or(0),
+                from_src as i32,
+            )
+        },
+        Err(err) => {
+            let error_msg = err.render(src, file_name);
+            WRITERS.with(|writers| {
+                get_writer(&mut writers.borrow_mut(), STDERR_FD).write_buf(error_msg.as_bytes())
+            });
+            unsafe {
+                runtime::throw_webassembly_exception();
+            }
+        }
+    }
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn load_stdlib() {
+    log::debug!("loading stdlib");
+    let enable_continuation = COMPILER_CONFIG.with(|c| c.borrow().enable_continuation);
+    let stdlib = webschembly_compiler::stdlib::generate_stdlib(enable_continuation);
+    load_src_inner(stdlib, None, true);
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn load_src(buf_ptr: i32, buf_len: i32, name_ptr: i32, name_len: i32) {
+    log::debug!("loading src");
+    let src = String::from_utf8(read_bytes(buf_ptr, buf_len)).unwrap();
+    // name_ptrが0のときはファイル名なし
+    let file_name = if name_ptr == 0 {
+        None
+    } else {
+        Some(String::from_utf8(read_bytes(name_ptr, name_len)).unwrap())
+    };
+    load_src_inner(src, file_name, false);
+}
+
+thread_local!(
+    static COMPILER_CONFIG: RefCell<webschembly_compiler::compiler::FlatConfig> = const {
+        RefCell::new(webschembly_compiler::compiler::FlatConfig {
+            enable_jit: true,
+            enable_jit_optimization: true,
+            enable_jit_small_block_fusion: true,
+            enable_jit_large_block_fusion: true,
+            enable_jit_log: false,
+            jit_tier_up_threshold: webschembly_compiler::jit::JitConfig::new().tier_up_threshold,
+            enable_continuation: false,
+            enable_debug_info: false,
+        })
+    };
+);
+
+#[unsafe(no_mangle)]
+pub extern "C" fn compiler_config_enable_jit(enable: i32) {
+    let enable = enable != 0;
+    COMPILER_CONFIG.with(|c| {
+        c.borrow_mut().enable_jit = enable;
+    });
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn compiler_config_enable_jit_optimization(enable: i32) {
+    let enable = enable != 0;
+    COMPILER_CONFIG.with(|c| {
+        c.borrow_mut().enable_jit_optimization = enable;
+    });
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn compiler_config_enable_jit_block_fusion(enable: i32) {
+    let enable = enable != 0;
+    COMPILER_CONFIG.with(|c| {
+        c.borrow_mut().enable_jit_small_block_fusion = enable;
+    });
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn compiler_config_enable_jit_large_block_fusion(enable: i32) {
+    let enable = enable != 0;
+    COMPILER_CONFIG.with(|c| {
+        c.borrow_mut().enable_jit_large_block_fusion = enable;
+    });
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn compiler_config_enable_jit_log(enable: i32) {
+    let enable = enable != 0;
+    COMPILER_CONFIG.with(|c| {
+        c.borrow_mut().enable_jit_log = enable;
+    });
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn compiler_config_jit_tier_up_threshold(threshold: i32) {
+    COMPILER_CONFIG.with(|c| {
+        c.borrow_mut().jit_tier_up_threshold = threshold as usize;
+    });
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn compiler_config_enable_continuation(enable: i32) {
+    let enable = enable != 0;
+    COMPILER_CONFIG.with(|c| {
+        c.borrow_mut().enable_continuation = enable;
+    });
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn compiler_config_enable_debug_info(enable: i32) {
+    let enable = enable != 0;
+    COMPILER_CONFIG.with(|c| {
+        c.borrow_mut().enable_debug_info = enable;
+    });
+}
+
+thread_local!(
+    static JIT_PROFILE: RefCell<webschembly_compiler::jit::profile::JitProfile> =
+        RefCell::new(Default::default());
+);
+
+// 前回の実行でjit_profileが返したJSON
+#[unsafe(no_mangle)]
+pub extern "C" fn compiler_config_jit_profile(buf_ptr: i32, buf_len: i32) {
+    let profile =
+        serde_json::from_slice(&read_bytes(buf_ptr, buf_len)).expect("invalid JIT profile");
+    JIT_PROFILE.with(|p| {
+        *p.borrow_mut() = profile;
+    });
+}
+
+// JITのプロファイルをJSONで返す。JITが無効の場合は(0, 0)
+#[unsafe(no_mangle)]
+pub extern "C" fn jit_profile() -> i64 {
+    let profile = COMPILER.with(|compiler| {
+        let mut compiler = RefMut::map(compiler.borrow_mut(), |c| c.as_mut().unwrap());
+        compiler.jit_profile()
+    });
+    let Some(profile) = profile else {
+        return cons_tuple_i32(0, 0);
+    };
+    let s = serde_json::to_string(&profile).unwrap();
+    let s = s.as_bytes();
+    let s_ptr = unsafe { malloc(s.len() as i32) };
+    unsafe {
+        std::ptr::copy_nonoverlapping(s.as_ptr(), s_ptr as *mut u8, s.len());
+    }
+    cons_tuple_i32(s_ptr, s.len() as i32)
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn get_global_id(buf_ptr: i32, buf_len: i32) -> i32 {
+    let buf_ptr = buf_ptr as *const u8;
+    let mut bytes = Vec::with_capacity(buf_len as usize);
+    for i in 0..buf_len {
+        unsafe {
+            bytes.push(*buf_ptr.offset(i as isize));
+        }
+    }
+    let name = String::from_utf8(bytes).unwrap();
+    COMPILER.with(|compiler| {
+        let compiler = Ref::map(compiler.borrow(), |c| c.as_ref().unwrap());
+        compiler.get_global_id(&name).unwrap_or(-1)
+    })
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn instantiate_func(
+    module_id: i32,
+    func_id: i32,
+    env_index: i32,
+    func_index: i32,
+) -> i32 {
+    log::debug!(
+        "instantiate_func: module_id={}, func_id={}, env_index={}, func_index={}",
+        module_id,
+        func_id,
+        env_index,
+        func_index,
+    );
+    let (wasm, ir) = COMPILER.with(|compiler| {
+        let mut compiler = RefMut::map(compiler.borrow_mut(), |c| c.as_mut().unwrap());
+        let (module, jit_events) = compiler.instantiate_func(
+            module_id as usize,
+            func_id as usize,
+            env_index as usize,
+            func_index as usize,
+        );
+        process_jit_events(jit_events);
+        let wasm = webschembly_compiler::wasm_generator::generate_with_config(
+            &module,
+            &compiler.wasm_generator_config(),
+        )
+        .wasm;
+        let ir = if cfg!(debug_assertions) {
+            let ir = format!("{}", module.display());
+            Some(ir.into_bytes())
+        } else {
+            None
+        };
+        (wasm, ir)
+    });
+
+    unsafe {
+        env::js_instantiate(
+            wasm.as_ptr() as i32,
+            wasm.len() as i32,
+            ir.as_ref().map(|ir| ir.as_ptr() as i32).unwrap_or(0),
+            ir.as_ref().map(|ir| ir.len() as i32).unwrap_or(0),
+            0,
+        )
+    }
+
+    0
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn instantiate_bb(
+    module_id: i32,
+    func_id: i32,
+    env_index: i32,
+    func_index: i32,
+    bb_id: i32,
+    index: i32,
+) -> i32 {
+    log::debug!(
+        "instantiate_bb: module_id={}, func_id={}, env_index={}, func_index={}, bb_id={}, index={}",
+        module_id,
+        func_id,
+        env_index,
+        func_index,
+        bb_id,
+        index
+    );
+    let (wasm, ir) = COMPILER.with(|compiler| {
+        let mut compiler = RefMut::map(compiler.borrow_mut(), |c| c.as_mut().unwrap());
+        let (module, jit_events) = compiler.instantiate_bb(
+            module_id as usize,
+            func_id as usize,
+            env_index as usize,
+            func_index as usize,
+            bb_id as usize,
+            index as usize,
+        );
+        process_jit_events(jit_events);
+        let wasm = webschembly_compiler::wasm_generator::generate_with_config(
+            &module,
+            &compiler.wasm_generator_config(),
+        )
+        .wasm;
+        let ir = if cfg!(debug_assertions) {
+            let ir = format!("{}", module.display());
+            Some(ir.into_bytes())
+        } else {
+            None
+        };
+        (wasm, ir)
+    });
+
+    unsafe {
+        env::js_instantiate(
+            wasm.as_ptr() as i32,
+            wasm.len() as i32,
+            ir.as_ref().map(|ir| ir.as_ptr() as i32).unwrap_or(0),
+            ir.as_ref().map(|ir| ir.len() as i32).unwrap_or(0),
+            0,
+        )
+    }
+
+    0
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn increment_bb_layout_counter(
+    module_id: i32,
+    func_id: i32,
+    env_index: i32,
+    func_index: i32,
+    bb_id: i32,
+    index: i32,
+    kind: i32, // 0: Hit, 1: GuardFailure
+) {
+    COMPILER.with(|compiler| {
+        let mut compiler = RefMut::map(compiler.borrow_mut(), |c| c.as_mut().unwrap());
+        compiler.increment_bb_layout_counter(
+            module_id as usize,
+            func_id as usize,
+            env_index as usize,
+            func_index as usize,
+            bb_id as usize,
+            index as usize,
+            kind as usize,
+        );
+    });
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn increment_branch_counter(
+    module_id: i32,
+    func_id: i32,
+    env_index: i32,
+    func_index: i32,
+    bb_id: i32,
+    kind: i32, // 0: Then, 1: Else
+    source_bb_id: i32,
+    source_index: i32,
+) {
+    COMPILER.with(|compiler| {
+        let mut compiler = RefMut::map(compiler.borrow_mut(), |c| c.as_mut().unwrap());
+        let wasm_ir = compiler
+            .increment_branch_counter(
+                module_id as usize,
+                func_id as usize,
+                env_index as usize,
+                func_index as usize,
+                bb_id as usize,
+                kind as usize,
+                source_bb_id as usize,
+                source_index as usize,
+            )
+            .map(|(module, jit_events)| {
+                process_jit_events(jit_events);
+                let wasm = webschembly_compiler::wasm_generator::generate_with_config(
+                    &module,
+                    &compiler.wasm_generator_config(),
+                )
+                .wasm;
+                let ir = if cfg!(debug_assertions) {
+                    let ir = format!("{}", module.display());
+                    Some(ir.into_bytes())
+                } else {
+                    None
+                };
+                (wasm, ir)
+            });
+
+        if let Some((wasm, ir)) = wasm_ir {
+            log::debug!(
+                "branch specialize: module_id={}, func_id={}, env_index={}, func_index={}, bb_id={}, kind={}, source_bb_id={}, source_index={}",
+                module_id,
+                func_id,
+                env_index,
+                func_index,
+                bb_id,
+                kind,
+                source_bb_id,
+                source_index
+            );
+            unsafe {
+                env::js_instantiate(
+                    wasm.as_ptr() as i32,
+                    wasm.len() as i32,
+                    ir.as_ref().map(|ir| ir.as_ptr() as i32).unwrap_or(0),
+                    ir.as_ref().map(|ir| ir.len() as i32).unwrap_or(0),
+                    0,
+                )
+            }
+        }
+    });
+}
+
+fn process_jit_events(events: Vec<webschembly_compiler::jit::event::JitLogEvent>) {
+    for event in events {
+        let log_msg = serde_json::to_string(&event).unwrap();
+        unsafe {
+            crate::env::js_webschembly_jit_log(log_msg.as_ptr() as i32, log_msg.len() as i32);
+        }
+    }
+}
+
+pub(crate) fn init_compiler() {
+    let config = COMPILER_CONFIG.with(|c| *c.borrow());
+    let profile = JIT_PROFILE.with(|p| p.take());
+
+    COMPILER.with(|compiler| {
+        let mut compiler = compiler.borrow_mut();
+        *compiler = Some(webschembly_compiler::compiler::Compiler::with_jit_profile(
+            config.into(),
+            profile,
+        ));
+    });
+}
+
+// AOTコンパイルしたモジュールはランタイムのコンパイラを初期化しないのでNone
+pub(crate) fn source_loc(source_loc_id: usize) -> Option<SourceLoc> {
+    COMPILER.with(|compiler| {
+        let compiler = compiler.borrow();
+        compiler.as_ref()?.get_source_loc(source_loc_id).cloned()
+    })
+}
@@ -1,4 +1,5 @@
 unsafe extern "C" {
+    #[cfg(feature = "compiler")]
     pub fn js_instantiate(
         buf_ptr: i32,
         buf_size: i32,
@@ -8,5 +9,6 @@ unsafe extern "C" {
     );
     pub fn js_write_buf(fd: i32, buf_ptr: i32, buf_len: i32);
     pub fn js_webschembly_log(buf_ptr: i32, buf_len: i32);
+    #[cfg(feature = "compiler")]
     pub fn js_webschembly_jit_log(buf_ptr: i32, buf_len: i32);
 }
@@ -0,0 +1,170 @@
+// eval
+// lib.watが実行時の値を走査して_datum_*を呼び出し、スタック上でS式を組み立てる
+use crate::bigint::read_integer;
+#[cfg(feature = "compiler")]
+use crate::compiler::compile_and_instantiate;
+use crate::{read_bytes, runtime_error};
+use core::cell::RefCell;
+use num_bigint::BigInt;
+use ordered_float::NotNan;
+#[cfg(feature = "compiler")]
+use webschembly_compiler::compiler::EVAL_FILE_NAME;
+use webschembly_compiler_locate::{Pos, Span};
+use webschembly_compiler_sexpr::{Cons, LSExpr, SExpr, SUVectorKind};
+
+thread_local!(
+    static DATUM_STACK: RefCell<Vec<LSExpr>> = const { RefCell::new(Vec::new()) };
+);
+
+// 実行時の値には位置情報がないので、すべて先頭を指すことにする
+const DATUM_SPAN: Span = Span {
+    start: Pos { line: 1, column: 1 },
+    end: Pos { line: 1, column: 1 },
+};
+
+fn push(value: SExpr) {
+    DATUM_STACK.with(|stack| {
+        stack.borrow_mut().push(LSExpr {
+            value,
+            span: DATUM_SPAN,
+        })
+    });
+}
+
+fn pop_n(n: usize) -> Vec<LSExpr> {
+    DATUM_STACK.with(|stack| {
+        let mut stack = stack.borrow_mut();
+        let len = stack.len();
+        stack.split_off(len - n)
+    })
+}
+
+fn integer_to_sexpr(x: BigInt) -> SExpr {
+    match i64::try_from(&x) {
+        Ok(i) => SExpr::Int(i),
+        Err(_) => SExpr::BigInt(x.to_string()),
+    }
+}
+
+fn float_to_sexpr(f: f64) -> SExpr {
+    match NotNan::new(f) {
+        Ok(f) => SExpr::Float(f),
+        Err(_) => SExpr::NaN,
+    }
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn _datum_nil() {
+    push(SExpr::Nil);
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn _datum_bool(b: i32) {
+    push(SExpr::Bool(b != 0));
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn _datum_int(i: i64) {
+    push(SExpr::Int(i));
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn _datum_integer(ptr: i32, len: i32) {
+    push(integer_to_sexpr(read_integer(ptr, len)));
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn _datum_rational(num_ptr: i32, num_len: i32, den_ptr: i32, den_len: i32) {
+    push(SExpr::Rational(
+        read_integer(num_ptr, num_len).to_string(),
+        read_integer(den_ptr, den_len).to_string(),
+    ));
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn _datum_float(f: f64) {
+    push(float_to_sexpr(f));
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn _datum_char(c: i32) {
+    push(SExpr::Char(char::from_u32(c as u32).unwrap()));
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn _datum_string(ptr: i32, len: i32) {
+    push(SExpr::String(
+        String::from_utf8_lossy(&read_bytes(ptr, len)).into_owned(),
+    ));
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn _datum_symbol(ptr: i32, len: i32) {
+    push(SExpr::Symbol(
+        String::from_utf8_lossy(&read_bytes(ptr, len)).into_owned(),
+    ));
+}
+
+// スタックに[要素...][末尾]の順で積まれている
+#[unsafe(no_mangle)]
+pub extern "C" fn _datum_list(n: i32) {
+    let mut elements = pop_n(n as usize + 1);
+    let tail = elements.pop().unwrap();
+    let list = elements.into_iter().rfold(tail, |cdr, car| LSExpr {
+        value: SExpr::Cons(Box::new(Cons::new(car, cdr))),
+        span: DATUM_SPAN,
+    });
+    DATUM_STACK.with(|stack| stack.borrow_mut().push(list));
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn _datum_vector(n: i32) {
+    let elements = pop_n(n as usize);
+    push(SExpr::Vector(elements));
+}
+
+// kind: 0: s64, 1: f64, 2: u8, 3: s8, 4: u16, 5: s16, 6: u32, 7: s32, 8: f32
+#[unsafe(no_mangle)]
+pub extern "C" fn _datum_uvector(kind: i32, n: i32) {
+    let elements = pop_n(n as usize);
+    let kind = match kind {
+        0 => SUVectorKind::S64,
+        1 => SUVectorKind::F64,
+        2 => SUVectorKind::U8,
+        3 => SUVectorKind::S8,
+        4 => SUVectorKind::U16,
+        5 => SUVectorKind::S16,
+        6 => SUVectorKind::U32,
+        7 => SUVectorKind::S32,
+        _ => SUVectorKind::F32,
+    };
+    push(SExpr::UVector(kind, elements));
+}
+
+// 手続きなどS式で表せない値が含まれていた場合
+#[unsafe(no_mangle)]
+pub extern "C" fn _datum_invalid() {
+    DATUM_STACK.with(|stack| stack.borrow_mut().clear());
+    runtime_error("eval: expression contains a value that cannot be converted to a datum");
+}
+
+// 結果はコンパイルしたモジュールがlib.watのeval_resultに保存する
+#[cfg(feature = "compiler")]
+#[unsafe(no_mangle)]
+pub extern "C" fn _eval() {
+    let datum = DATUM_STACK.with(|stack| stack.borrow_mut().pop().unwrap());
+    // ソースコードがないのでエラーの位置は表示できない
+    compile_and_instantiate(
+        |compiler| compiler.compile_datum(datum),
+        "",
+        Some(EVAL_FILE_NAME),
+        false,
+    );
+}
+
+#[cfg(not(feature = "compiler"))]
+#[unsafe(no_mangle)]
+pub extern "C" fn _eval() {
+    DATUM_STACK.with(|stack| stack.borrow_mut().clear());
+    runtime_error("eval is not supported without the runtime compiler");
+}
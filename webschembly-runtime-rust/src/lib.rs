@@ -1,13 +1,23 @@
 #![feature(ptr_as_ref_unchecked, allocator_api, slice_ptr_get)]
 use core::cell::RefCell;
 use rustc_hash::FxHashMap;
-use std::cell::{Ref, RefMut};
+use std::cell::Cell;
 use std::vec;
 mod logger;
 use std::alloc::{Allocator, Global, Layout};
 use std::ptr::NonNull;
+use webschembly_compiler_ir::SourceLoc;
+use webschembly_compiler_locate::{Pos, Span};
+mod bigint;
+#[cfg(feature = "compiler")]
+mod compiler;
 mod env;
+mod eval;
+mod number;
+mod port;
+mod rational;
 mod runtime;
+mod string;
 
 #[allow(clippy::missing_safety_doc)]
 #[unsafe(no_mangle)]
@@ -78,62 +88,22 @@ impl SymbolManager {
     }
 }
 
-thread_local!(
-    static COMPILER: RefCell<Option<webschembly_compiler::compiler::Compiler>> =
-        const { RefCell::new(None) };
-);
-
-// const STDIN_FD: i32 = 0;
+const STDIN_FD: i32 = 0;
 const STDOUT_FD: i32 = 1;
 const STDERR_FD: i32 = 2;
 
-fn load_src_inner(src: String, is_stdlib: bool) {
-    let result = COMPILER.with(|compiler| {
-        let mut compiler = RefMut::map(compiler.borrow_mut(), |c| c.as_mut().unwrap());
-        compiler.compile_module(&src, is_stdlib).map(|module| {
-            let wasm = webschembly_compiler::wasm_generator::generate(&module);
-            let ir = if cfg!(debug_assertions) {
-                let ir = format!("{}", module.display());
-                Some(ir.into_bytes())
-            } else {
-                None
-            };
-            (wasm, ir)
-        })
+// Wasmの例外はRustのデストラクタを実行しないので、RefCellの借用を解放してから呼ぶ
+fn runtime_error(msg: &str) -> ! {
+    WRITERS.with(|writers| {
+        get_writer(&mut writers.borrow_mut(), STDERR_FD).write_buf(format!("{}\n", msg).as_bytes())
     });
-
-    match result {
-        Ok((wasm, ir)) => unsafe {
-            env::js_instantiate(
-                wasm.as_ptr() as i32,
-                wasm.len() as i32,
-                ir.as_ref().map(|ir| ir.as_ptr() as i32).unwrap_or(0),
-                ir.as_ref().map(|ir| ir.len() as i32).unwrap_or(0),
-                1,
-            )
-        },
-        Err(err) => {
-            let error_msg = format!("{}\n", err);
-            WRITERS.with(|writers| {
-                get_writer(&mut writers.borrow_mut(), STDERR_FD).write_buf(error_msg.as_bytes())
-            });
-            unsafe {
-                runtime::throw_webassembly_exception();
-            }
-        }
+    unsafe {
+        runtime::throw_webassembly_exception();
     }
+    unreachable!()
 }
 
-#[unsafe(no_mangle)]
-pub extern "C" fn load_stdlib() {
-    log::debug!("loading stdlib");
-    let stdlib = webschembly_compiler::stdlib::generate_stdlib();
-    load_src_inner(stdlib, true);
-}
-
-#[unsafe(no_mangle)]
-pub extern "C" fn load_src(buf_ptr: i32, buf_len: i32) {
-    log::debug!("loading src");
+fn read_bytes(buf_ptr: i32, buf_len: i32) -> Vec<u8> {
     let buf_ptr = buf_ptr as *const u8;
     let mut bytes = Vec::with_capacity(buf_len as usize);
     for i in 0..buf_len {
@@ -141,70 +111,74 @@ pub extern "C" fn load_src(buf_ptr: i32, buf_len: i32) {
             bytes.push(*buf_ptr.offset(i as isize));
         }
     }
-    let src = String::from_utf8(bytes).unwrap();
-    load_src_inner(src, false);
+    bytes
 }
 
 thread_local!(
-    static COMPILER_CONFIG: RefCell<webschembly_compiler::compiler::FlatConfig> = const {
-        RefCell::new(webschembly_compiler::compiler::FlatConfig {
-            enable_jit: true,
-            enable_jit_optimization: true,
-            enable_jit_small_block_fusion: true,
-            enable_jit_large_block_fusion: true,
-            enable_jit_log: false,
-        })
-    };
+    // ランタイムのコンパイラを使わないモジュール(AOTの出力)が登録した位置情報
+    static SOURCE_LOCS: RefCell<FxHashMap<usize, SourceLoc>> = RefCell::new(FxHashMap::default());
 );
 
+// [id, 関数名, ファイル名, 行, 列] の配列のJSONを受け取る。バッファはここで解放する
 #[unsafe(no_mangle)]
-pub extern "C" fn compiler_config_enable_jit(enable: i32) {
-    let enable = enable != 0;
-    COMPILER_CONFIG.with(|c| {
-        c.borrow_mut().enable_jit = enable;
-    });
-}
-
-#[unsafe(no_mangle)]
-pub extern "C" fn compiler_config_enable_jit_optimization(enable: i32) {
-    let enable = enable != 0;
-    COMPILER_CONFIG.with(|c| {
-        c.borrow_mut().enable_jit_optimization = enable;
-    });
-}
-
-#[unsafe(no_mangle)]
-pub extern "C" fn compiler_config_enable_jit_block_fusion(enable: i32) {
-    let enable = enable != 0;
-    COMPILER_CONFIG.with(|c| {
-        c.borrow_mut().enable_jit_small_block_fusion = enable;
+pub extern "C" fn register_source_locs(buf_ptr: i32, buf_len: i32) {
+    let bytes = read_bytes(buf_ptr, buf_len);
+    unsafe { free(buf_ptr) };
+    let source_locs: Vec<(usize, String, Option<String>, usize, usize)> =
+        serde_json::from_slice(&bytes).expect("invalid source locations");
+    SOURCE_LOCS.with(|locs| {
+        let mut locs = locs.borrow_mut();
+        for (id, func_name, file_name, line, column) in source_locs {
+            let pos = Pos::new(line, column);
+            locs.insert(
+                id,
+                SourceLoc {
+                    func_name,
+                    file_name,
+                    span: Span::new(pos, pos),
+                },
+            );
+        }
     });
 }
 
-#[unsafe(no_mangle)]
-pub extern "C" fn compiler_config_enable_jit_large_block_fusion(enable: i32) {
-    let enable = enable != 0;
-    COMPILER_CONFIG.with(|c| {
-        c.borrow_mut().enable_jit_large_block_fusion = enable;
-    });
+fn get_source_loc(source_loc_id: usize) -> Option<SourceLoc> {
+    let source_loc = SOURCE_LOCS.with(|locs| locs.borrow().get(&source_loc_id).cloned());
+    #[cfg(feature = "compiler")]
+    let source_loc = source_loc.or_else(|| compiler::source_loc(source_loc_id));
+    source_loc
 }
 
+// ランタイムエラーの例外が関数を通過するたびに呼ばれる
 #[unsafe(no_mangle)]
-pub extern "C" fn compiler_config_enable_jit_log(enable: i32) {
-    let enable = enable != 0;
-    COMPILER_CONFIG.with(|c| {
-        c.borrow_mut().enable_jit_log = enable;
+pub extern "C" fn print_stack_frame(source_loc_id: i32) {
+    if source_loc_id < 0 {
+        return;
+    }
+    let line = get_source_loc(source_loc_id as usize).map(|source_loc| {
+        let pos = source_loc.span.start;
+        match &source_loc.file_name {
+            Some(file_name) => format!(
+                "  at {} ({}:{}:{})\n",
+                source_loc.func_name, file_name, pos.line, pos.column
+            ),
+            None => format!(
+                "  at {} ({}:{})\n",
+                source_loc.func_name, pos.line, pos.column
+            ),
+        }
     });
+    if let Some(line) = line {
+        WRITERS.with(|writers| {
+            get_writer(&mut writers.borrow_mut(), STDERR_FD).write_buf(line.as_bytes())
+        });
+    }
 }
 
 #[unsafe(no_mangle)]
 pub extern "C" fn init() {
-    let config = COMPILER_CONFIG.with(|c| *c.borrow());
-
-    COMPILER.with(|compiler| {
-        let mut compiler = compiler.borrow_mut();
-        *compiler = Some(webschembly_compiler::compiler::Compiler::new(config.into()));
-    });
+    #[cfg(feature = "compiler")]
+    compiler::init_compiler();
     log::set_logger(&logger::WasmLogger).unwrap();
     log::set_max_level(log::LevelFilter::Debug);
     if cfg!(debug_assertions) {
@@ -270,26 +244,50 @@ thread_local!(
     static WRITERS: RefCell<FxHashMap<i32, WasmWriter>> = RefCell::new(FxHashMap::default());
 );
 
+thread_local!(
+    // display/write-charの出力先のポートID
+    static CURRENT_OUTPUT_FD: Cell<i32> = const { Cell::new(STDOUT_FD) };
+);
+
 fn get_writer(writers: &mut FxHashMap<i32, WasmWriter>, fd: i32) -> &mut WasmWriter {
     writers.entry(fd).or_insert_with(|| WasmWriter::new(fd))
 }
 
 #[unsafe(no_mangle)]
 pub extern "C" fn write_char_fd(fd: i32, c: i32) {
-    WRITERS.with(|writers| get_writer(&mut writers.borrow_mut(), fd).write_char(c));
+    if port::is_string_port(fd) {
+        let c = char::from_u32(u32::from_le_bytes(c.to_le_bytes())).unwrap_or('?');
+        port::write_string_port(fd, c.encode_utf8(&mut [0; 4]).as_bytes());
+    } else {
+        WRITERS.with(|writers| get_writer(&mut writers.borrow_mut(), fd).write_char(c));
+    }
 }
 
 // TODO: Rustのコード生成の都合で一旦
 #[unsafe(no_mangle)]
 pub extern "C" fn write_char(c: i32) {
-    write_char_fd(STDOUT_FD, c);
+    write_char_fd(current_output_fd(), c);
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn current_output_fd() -> i32 {
+    CURRENT_OUTPUT_FD.with(|fd| fd.get())
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn set_current_output_fd(fd: i32) {
+    CURRENT_OUTPUT_FD.with(|current| current.set(fd));
 }
 
 #[unsafe(no_mangle)]
 pub extern "C" fn write_buf(fd: i32, buf_ptr: i32, buf_len: i32) {
     let buf_ptr: *const u8 = buf_ptr as *const u8;
     let buf = unsafe { std::slice::from_raw_parts(buf_ptr, buf_len as usize) };
-    WRITERS.with(|writers| get_writer(&mut writers.borrow_mut(), fd).write_buf(buf));
+    if port::is_string_port(fd) {
+        port::write_string_port(fd, buf);
+    } else {
+        WRITERS.with(|writers| get_writer(&mut writers.borrow_mut(), fd).write_buf(buf));
+    }
 }
 
 #[unsafe(no_mangle)]
@@ -345,187 +343,3 @@ fn cons_tuple_i32(a: i32, b: i32) -> i64 {
     buf[4..].copy_from_slice(&b);
     i64::from_le_bytes(buf)
 }
-
-#[unsafe(no_mangle)]
-pub extern "C" fn get_global_id(buf_ptr: i32, buf_len: i32) -> i32 {
-    let buf_ptr = buf_ptr as *const u8;
-    let mut bytes = Vec::with_capacity(buf_len as usize);
-    for i in 0..buf_len {
-        unsafe {
-            bytes.push(*buf_ptr.offset(i as isize));
-        }
-    }
-    let name = String::from_utf8(bytes).unwrap();
-    COMPILER.with(|compiler| {
-        let compiler = Ref::map(compiler.borrow(), |c| c.as_ref().unwrap());
-        compiler.get_global_id(&name).unwrap_or(-1)
-    })
-}
-
-#[unsafe(no_mangle)]
-pub extern "C" fn instantiate_func(
-    module_id: i32,
-    func_id: i32,
-    env_index: i32,
-    func_index: i32,
-) -> i32 {
-    log::debug!(
-        "instantiate_func: module_id={}, func_id={}, env_index={}, func_index={}",
-        module_id,
-        func_id,
-        env_index,
-        func_index,
-    );
-    let (wasm, ir) = COMPILER.with(|compiler| {
-        let mut compiler = RefMut::map(compiler.borrow_mut(), |c| c.as_mut().unwrap());
-        let (module, jit_events) = compiler.instantiate_func(
-            module_id as usize,
-            func_id as usize,
-            env_index as usize,
-            func_index as usize,
-        );
-        process_jit_events(jit_events);
-        let wasm = webschembly_compiler::wasm_generator::generate(&module);
-        let ir = if cfg!(debug_assertions) {
-            let ir = format!("{}", module.display());
-            Some(ir.into_bytes())
-        } else {
-            None
-        };
-        (wasm, ir)
-    });
-
-    unsafe {
-        env::js_instantiate(
-            wasm.as_ptr() as i32,
-            wasm.len() as i32,
-            ir.as_ref().map(|ir| ir.as_ptr() as i32).unwrap_or(0),
-            ir.as_ref().map(|ir| ir.len() as i32).unwrap_or(0),
-            0,
-        )
-    }
-
-    0
-}
-
-#[unsafe(no_mangle)]
-pub extern "C" fn instantiate_bb(
-    module_id: i32,
-    func_id: i32,
-    env_index: i32,
-    func_index: i32,
-    bb_id: i32,
-    index: i32,
-) -> i32 {
-    log::debug!(
-        "instantiate_bb: module_id={}, func_id={}, env_index={}, func_index={}, bb_id={}, index={}",
-        module_id,
-        func_id,
-        env_index,
-        func_index,
-        bb_id,
-        index
-    );
-    let (wasm, ir) = COMPILER.with(|compiler| {
-        let mut compiler = RefMut::map(compiler.borrow_mut(), |c| c.as_mut().unwrap());
-        let (module, jit_events) = compiler.instantiate_bb(
-            module_id as usize,
-            func_id as usize,
-            env_index as usize,
-            func_index as usize,
-            bb_id as usize,
-            index as usize,
-        );
-        process_jit_events(jit_events);
-        let wasm = webschembly_compiler::wasm_generator::generate(&module);
-        let ir = if cfg!(debug_assertions) {
-            let ir = format!("{}", module.display());
-            Some(ir.into_bytes())
-        } else {
-            None
-        };
-        (wasm, ir)
-    });
-
-    unsafe {
-        env::js_instantiate(
-            wasm.as_ptr() as i32,
-            wasm.len() as i32,
-            ir.as_ref().map(|ir| ir.as_ptr() as i32).unwrap_or(0),
-            ir.as_ref().map(|ir| ir.len() as i32).unwrap_or(0),
-            0,
-        )
-    }
-
-    0
-}
-
-#[unsafe(no_mangle)]
-pub extern "C" fn increment_branch_counter(
-    module_id: i32,
-    func_id: i32,
-    env_index: i32,
-    func_index: i32,
-    bb_id: i32,
-    kind: i32, // 0: Then, 1: Else
-    source_bb_id: i32,
-    source_index: i32,
-) {
-    COMPILER.with(|compiler| {
-        let mut compiler = RefMut::map(compiler.borrow_mut(), |c| c.as_mut().unwrap());
-        let wasm_ir = compiler
-            .increment_branch_counter(
-                module_id as usize,
-                func_id as usize,
-                env_index as usize,
-                func_index as usize,
-                bb_id as usize,
-                kind as usize,
-                source_bb_id as usize,
-                source_index as usize,
-            )
-            .map(|(module, jit_events)| {
-                process_jit_events(jit_events);
-                let wasm = webschembly_compiler::wasm_generator::generate(&module);
-                let ir = if cfg!(debug_assertions) {
-                    let ir = format!("{}", module.display());
-                    Some(ir.into_bytes())
-                } else {
-                    None
-                };
-                (wasm, ir)
-            });
-
-        if let Some((wasm, ir)) = wasm_ir {
-            log::debug!(
-                "branch specialize: module_id={}, func_id={}, env_index={}, func_index={}, bb_id={}, kind={}, source_bb_id={}, source_index={}",
-                module_id,
-                func_id,
-                env_index,
-                func_index,
-                bb_id,
-                kind,
-                source_bb_id,
-                source_index
-            );
-            unsafe {
-                env::js_instantiate(
-                    wasm.as_ptr() as i32,
-                    wasm.len() as i32,
-                    ir.as_ref().map(|ir| ir.as_ptr() as i32).unwrap_or(0),
-                    ir.as_ref().map(|ir| ir.len() as i32).unwrap_or(0),
-                    0,
-                )
-            }
-        }
-    });
-}
-
-fn process_jit_events(events: Vec<webschembly_compiler::jit::event::JitLogEvent>) {
-    for event in events {
-        let log_msg = serde_json::to_string(&event).unwrap();
-        unsafe {
-            crate::env::js_webschembly_jit_log(log_msg.as_ptr() as i32, log_msg.len() as i32);
-        }
-    }
-}
@@ -0,0 +1,91 @@
+// int/floatの演算のうち、Wasmの命令にないもの
+use crate::runtime_error;
+
+fn gcd_u64(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+fn checked_gcd(a: i64, b: i64) -> Option<i64> {
+    i64::try_from(gcd_u64(a.unsigned_abs(), b.unsigned_abs())).ok()
+}
+
+fn checked_lcm(a: i64, b: i64) -> Option<i64> {
+    if a == 0 || b == 0 {
+        return Some(0);
+    }
+    let (a, b) = (a.unsigned_abs(), b.unsigned_abs());
+    (a / gcd_u64(a, b))
+        .checked_mul(b)
+        .and_then(|r| i64::try_from(r).ok())
+}
+
+// 指数が負の場合は結果が有理数になるのでNone
+fn checked_expt(a: i64, b: i64) -> Option<i64> {
+    if b < 0 {
+        return None;
+    }
+    match a {
+        0 => Some(if b == 0 { 1 } else { 0 }),
+        1 => Some(1),
+        -1 => Some(if b % 2 == 0 { 1 } else { -1 }),
+        _ => u32::try_from(b).ok().and_then(|b| a.checked_pow(b)),
+    }
+}
+
+fn checked_int_binop(op: i32, a: i64, b: i64) -> Option<i64> {
+    match op {
+        0 => checked_gcd(a, b),
+        1 => checked_lcm(a, b),
+        2 => checked_expt(a, b),
+        _ => unreachable!("unknown int binop: {}", op),
+    }
+}
+
+// opはwasm_generatorのIntBinOpと対応
+// int_binop_overflowで確認してから呼ばれるが、到達不能コードでパニックしないように0を返す
+#[unsafe(no_mangle)]
+pub extern "C" fn int_binop(op: i32, a: i64, b: i64) -> i64 {
+    checked_int_binop(op, a, b).unwrap_or(0)
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn int_binop_overflow(op: i32, a: i64, b: i64) -> i32 {
+    checked_int_binop(op, a, b).is_none() as i32
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn exact_integer_sqrt_int(a: i64) -> i64 {
+    if a < 0 {
+        runtime_error("exact-integer-sqrt: negative argument");
+    }
+    (a as u64).isqrt() as i64
+}
+
+// opはwasm_generatorのFloatUnOpと対応
+#[unsafe(no_mangle)]
+pub extern "C" fn float_unop(op: i32, x: f64) -> f64 {
+    match op {
+        0 => x.exp(),
+        1 => x.ln(),
+        2 => x.sin(),
+        3 => x.cos(),
+        4 => x.tan(),
+        5 => x.asin(),
+        6 => x.acos(),
+        7 => x.atan(),
+        _ => unreachable!("unknown float unop: {}", op),
+    }
+}
+
+// opはwasm_generatorのFloatBinOpと対応
+#[unsafe(no_mangle)]
+pub extern "C" fn float_binop(op: i32, a: f64, b: f64) -> f64 {
+    match op {
+        0 => a.atan2(b),
+        1 => a.powf(b),
+        _ => unreachable!("unknown float binop: {}", op),
+    }
+}
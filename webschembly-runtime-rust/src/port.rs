@@ -0,0 +1,320 @@
+// 文字列ポート
+// ポートIDはfdと同じ空間を使う。0, 1, 2は標準入出力で、文字列ポートには3以降を割り当てる
+use crate::bigint::write_bytes;
+use crate::{STDIN_FD, read_bytes, runtime_error};
+use core::cell::RefCell;
+use num_bigint::BigInt;
+use rustc_hash::FxHashMap;
+use std::cell::Cell;
+use webschembly_compiler_reader::{lexer, sexpr_parser};
+use webschembly_compiler_sexpr::{LSExpr, SExpr, SUVectorKind};
+
+const FIRST_STRING_PORT_ID: i32 = 3;
+
+#[derive(Debug)]
+enum StringPort {
+    // posは読み込み済みのバイト数
+    Input { text: String, pos: usize },
+    Output { buf: Vec<u8> },
+}
+
+thread_local!(
+    static STRING_PORTS: RefCell<FxHashMap<i32, StringPort>> = RefCell::new(FxHashMap::default());
+);
+
+thread_local!(
+    static NEXT_PORT_ID: Cell<i32> = const { Cell::new(FIRST_STRING_PORT_ID) };
+);
+
+fn add_port(port: StringPort) -> i32 {
+    let id = NEXT_PORT_ID.with(|id| {
+        let next = id.get();
+        id.set(next + 1);
+        next
+    });
+    STRING_PORTS.with(|ports| ports.borrow_mut().insert(id, port));
+    id
+}
+
+pub(crate) fn is_string_port(id: i32) -> bool {
+    id >= FIRST_STRING_PORT_ID
+}
+
+pub(crate) fn write_string_port(id: i32, bytes: &[u8]) {
+    let result = STRING_PORTS.with(|ports| match ports.borrow_mut().get_mut(&id) {
+        Some(StringPort::Output { buf }) => {
+            buf.extend_from_slice(bytes);
+            Ok(())
+        }
+        Some(StringPort::Input { .. }) => Err("not an output port"),
+        None => Err("port is closed"),
+    });
+    result.unwrap_or_else(|msg| runtime_error(msg))
+}
+
+fn with_input_port<R>(id: i32, f: impl FnOnce(&str, &mut usize) -> R) -> R {
+    if id == STDIN_FD {
+        runtime_error("reading from standard input is not supported");
+    }
+    let result = STRING_PORTS.with(|ports| match ports.borrow_mut().get_mut(&id) {
+        Some(StringPort::Input { text, pos }) => Ok(f(text, pos)),
+        Some(StringPort::Output { .. }) => Err("not an input port"),
+        None => Err("port is closed"),
+    });
+    result.unwrap_or_else(|msg| runtime_error(msg))
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn _open_input_string(ptr: i32, len: i32) -> i32 {
+    let text = String::from_utf8_lossy(&read_bytes(ptr, len)).into_owned();
+    add_port(StringPort::Input { text, pos: 0 })
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn _open_output_string() -> i32 {
+    add_port(StringPort::Output { buf: Vec::new() })
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn _get_output_string(id: i32) -> i64 {
+    let result = STRING_PORTS.with(|ports| match ports.borrow().get(&id) {
+        Some(StringPort::Output { buf }) => Ok(write_bytes(buf)),
+        Some(StringPort::Input { .. }) => Err("not an output port"),
+        None => Err("port is closed"),
+    });
+    result.unwrap_or_else(|msg| runtime_error(msg))
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn _close_port(id: i32) {
+    STRING_PORTS.with(|ports| ports.borrow_mut().remove(&id));
+}
+
+// EOFなら-1を返す
+#[unsafe(no_mangle)]
+pub extern "C" fn _read_char(id: i32) -> i32 {
+    with_input_port(id, |text, pos| match text[*pos..].chars().next() {
+        Some(c) => {
+            *pos += c.len_utf8();
+            c as i32
+        }
+        None => -1,
+    })
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn _peek_char(id: i32) -> i32 {
+    with_input_port(id, |text, pos| match text[*pos..].chars().next() {
+        Some(c) => c as i32,
+        None => -1,
+    })
+}
+
+// lib.watのdecode_datumと対応
+const TAG_NIL: u8 = 0;
+const TAG_TRUE: u8 = 1;
+pub(crate) const TAG_FALSE: u8 = 2;
+const TAG_INT: u8 = 3;
+const TAG_BIGINT: u8 = 4;
+const TAG_RATIONAL: u8 = 5;
+const TAG_FLOAT: u8 = 6;
+const TAG_CHAR: u8 = 7;
+const TAG_STRING: u8 = 8;
+const TAG_SYMBOL: u8 = 9;
+// [要素数(u32)][要素...][末尾]
+const TAG_LIST: u8 = 10;
+const TAG_VECTOR: u8 = 11;
+const TAG_S64VECTOR: u8 = 12;
+const TAG_F64VECTOR: u8 = 13;
+const TAG_EOF: u8 = 14;
+// [メッセージ]: read-errorを表す
+const TAG_ERROR: u8 = 15;
+// 要素はそれぞれの型の幅で格納する
+const TAG_U8VECTOR: u8 = 16;
+const TAG_S8VECTOR: u8 = 17;
+const TAG_U16VECTOR: u8 = 18;
+const TAG_S16VECTOR: u8 = 19;
+const TAG_U32VECTOR: u8 = 20;
+const TAG_S32VECTOR: u8 = 21;
+const TAG_F32VECTOR: u8 = 22;
+
+// 先頭のデータを1つ読み、lib.watで復元できる形式にエンコードして返す
+#[unsafe(no_mangle)]
+pub extern "C" fn _read(id: i32) -> i64 {
+    let mut buf = Vec::new();
+    with_input_port(id, |text, pos| {
+        let rest = &text[*pos..];
+        match read_datum(rest) {
+            Ok(Some(datum)) => {
+                *pos += byte_offset(rest, datum.span.end.line, datum.span.end.column);
+                if let Err(msg) = encode_datum(&datum, &mut buf) {
+                    buf.clear();
+                    encode_error(&msg, &mut buf);
+                }
+            }
+            Ok(None) => {
+                *pos = text.len();
+                buf.push(TAG_EOF);
+            }
+            Err((msg, skip)) => {
+                // 同じエラーを繰り返さないように、エラーになったトークンまでは読み飛ばす
+                *pos += skip;
+                encode_error(&msg, &mut buf);
+            }
+        }
+    });
+    write_bytes(&buf)
+}
+
+// 失敗した場合はメッセージと読み飛ばすバイト数を返す
+fn read_datum(text: &str) -> Result<Option<LSExpr>, (String, usize)> {
+    lexer::lex_datum(text)
+        .and_then(|tokens| sexpr_parser::parse_datum(&tokens))
+        .map_err(|err| {
+            let diagnostic = &err.diagnostics[0];
+            let skip = match &diagnostic.primary {
+                Some(label) => {
+                    let start = byte_offset(text, label.span.end.line, label.span.end.column);
+                    // 字句エラーは1文字分のspanなので、トークンの残りも区切り文字まで読み飛ばす
+                    start
+                        + text[start..]
+                            .find(|c: char| c.is_ascii_whitespace() || "()\";".contains(c))
+                            .unwrap_or(text.len() - start)
+                }
+                None => text.len(),
+            };
+            (diagnostic.message.clone(), skip)
+        })
+}
+
+// (line, column)は1-indexedで、columnは文字単位
+fn byte_offset(text: &str, line: usize, column: usize) -> usize {
+    let line_start = text
+        .split_inclusive('\n')
+        .take(line - 1)
+        .map(|line| line.len())
+        .sum::<usize>();
+    line_start
+        + text[line_start..]
+            .chars()
+            .take(column - 1)
+            .map(|c| c.len_utf8())
+            .sum::<usize>()
+}
+
+fn encode_bytes(bytes: &[u8], buf: &mut Vec<u8>) {
+    buf.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+    buf.extend_from_slice(bytes);
+}
+
+fn encode_error(msg: &str, buf: &mut Vec<u8>) {
+    buf.push(TAG_ERROR);
+    encode_bytes(msg.as_bytes(), buf);
+}
+
+// 10進数表記の整数をint/bigintとしてエンコードする
+fn encode_integer(digits: &str, buf: &mut Vec<u8>) {
+    if let Ok(i) = digits.parse::<i64>() {
+        buf.push(TAG_INT);
+        buf.extend_from_slice(&i.to_le_bytes());
+    } else {
+        let x = BigInt::parse_bytes(digits.as_bytes(), 10).unwrap();
+        buf.push(TAG_BIGINT);
+        encode_bytes(&x.to_signed_bytes_le(), buf);
+    }
+}
+
+pub(crate) fn encode_datum(datum: &LSExpr, buf: &mut Vec<u8>) -> Result<(), String> {
+    match &datum.value {
+        SExpr::Nil => buf.push(TAG_NIL),
+        SExpr::Bool(true) => buf.push(TAG_TRUE),
+        SExpr::Bool(false) => buf.push(TAG_FALSE),
+        SExpr::Int(i) => {
+            buf.push(TAG_INT);
+            buf.extend_from_slice(&i.to_le_bytes());
+        }
+        SExpr::BigInt(digits) => encode_integer(digits, buf),
+        SExpr::Rational(num, den) => {
+            buf.push(TAG_RATIONAL);
+            encode_integer(num, buf);
+            encode_integer(den, buf);
+        }
+        SExpr::Float(f) => {
+            buf.push(TAG_FLOAT);
+            buf.extend_from_slice(&f.into_inner().to_le_bytes());
+        }
+        SExpr::NaN => {
+            buf.push(TAG_FLOAT);
+            buf.extend_from_slice(&f64::NAN.to_le_bytes());
+        }
+        SExpr::Char(c) => {
+            buf.push(TAG_CHAR);
+            buf.extend_from_slice(&(*c as u32).to_le_bytes());
+        }
+        SExpr::String(s) => {
+            buf.push(TAG_STRING);
+            encode_bytes(s.as_bytes(), buf);
+        }
+        SExpr::Symbol(s) => {
+            buf.push(TAG_SYMBOL);
+            encode_bytes(s.as_bytes(), buf);
+        }
+        SExpr::Cons(_) => {
+            let (elements, tail) = SExpr::to_vec_and_cdr(datum.clone());
+            buf.push(TAG_LIST);
+            buf.extend_from_slice(&(elements.len() as u32).to_le_bytes());
+            for element in &elements {
+                encode_datum(element, buf)?;
+            }
+            encode_datum(&tail, buf)?;
+        }
+        SExpr::Vector(elements) => {
+            buf.push(TAG_VECTOR);
+            buf.extend_from_slice(&(elements.len() as u32).to_le_bytes());
+            for element in elements {
+                encode_datum(element, buf)?;
+            }
+        }
+        SExpr::UVector(kind, elements) => {
+            buf.push(match kind {
+                SUVectorKind::S64 => TAG_S64VECTOR,
+                SUVectorKind::F64 => TAG_F64VECTOR,
+                SUVectorKind::U8 => TAG_U8VECTOR,
+                SUVectorKind::S8 => TAG_S8VECTOR,
+                SUVectorKind::U16 => TAG_U16VECTOR,
+                SUVectorKind::S16 => TAG_S16VECTOR,
+                SUVectorKind::U32 => TAG_U32VECTOR,
+                SUVectorKind::S32 => TAG_S32VECTOR,
+                SUVectorKind::F32 => TAG_F32VECTOR,
+            });
+            buf.extend_from_slice(&(elements.len() as u32).to_le_bytes());
+            for element in elements {
+                match (kind, &element.value) {
+                    (SUVectorKind::S64, SExpr::Int(i)) => buf.extend_from_slice(&i.to_le_bytes()),
+                    (SUVectorKind::F64, SExpr::Float(f)) => {
+                        buf.extend_from_slice(&f.into_inner().to_le_bytes())
+                    }
+                    (SUVectorKind::F64, SExpr::NaN) => {
+                        buf.extend_from_slice(&f64::NAN.to_le_bytes())
+                    }
+                    // 範囲外の整数は下位ビットのみ格納する
+                    (SUVectorKind::U8 | SUVectorKind::S8, SExpr::Int(i)) => buf.push(*i as u8),
+                    (SUVectorKind::U16 | SUVectorKind::S16, SExpr::Int(i)) => {
+                        buf.extend_from_slice(&(*i as u16).to_le_bytes())
+                    }
+                    (SUVectorKind::U32 | SUVectorKind::S32, SExpr::Int(i)) => {
+                        buf.extend_from_slice(&(*i as u32).to_le_bytes())
+                    }
+                    (SUVectorKind::F32, SExpr::Float(f)) => {
+                        buf.extend_from_slice(&(f.into_inner() as f32).to_le_bytes())
+                    }
+                    (SUVectorKind::F32, SExpr::NaN) => {
+                        buf.extend_from_slice(&f32::NAN.to_le_bytes())
+                    }
+                    _ => return Err("invalid uvector element".to_string()),
+                }
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,156 @@
+// 有理数は分子と分母をそれぞれ整数のバイト列としてやり取りする(bigint.rsを参照)
+// 結果は [分子の長さ(u32)][分子][分母] の形式で返し、分母が1のときは分母を空にする
+use crate::bigint::{read_integer, write_bytes};
+use crate::{cons_tuple_i32, malloc, runtime_error};
+use num_bigint::BigInt;
+use num_integer::Integer;
+use num_rational::BigRational;
+use num_traits::{One, Pow, Signed, ToPrimitive, Zero};
+
+fn read_rational(num_ptr: i32, num_len: i32, den_ptr: i32, den_len: i32) -> BigRational {
+    BigRational::new(
+        read_integer(num_ptr, num_len),
+        read_integer(den_ptr, den_len),
+    )
+}
+
+fn write_rational(r: &BigRational) -> i64 {
+    let num = r.numer().to_signed_bytes_le();
+    let den = if r.denom().is_one() {
+        Vec::new()
+    } else {
+        r.denom().to_signed_bytes_le()
+    };
+    let len = 4 + num.len() + den.len();
+    let ptr = unsafe { malloc(len as i32) };
+    unsafe {
+        let ptr = ptr as *mut u8;
+        std::ptr::copy_nonoverlapping((num.len() as u32).to_le_bytes().as_ptr(), ptr, 4);
+        std::ptr::copy_nonoverlapping(num.as_ptr(), ptr.add(4), num.len());
+        std::ptr::copy_nonoverlapping(den.as_ptr(), ptr.add(4 + num.len()), den.len());
+    }
+    cons_tuple_i32(ptr, len as i32)
+}
+
+// opはwasm_generatorのRationalBinOpと対応
+#[allow(clippy::too_many_arguments)]
+#[unsafe(no_mangle)]
+pub extern "C" fn _rational_binop(
+    op: i32,
+    a_num_ptr: i32,
+    a_num_len: i32,
+    a_den_ptr: i32,
+    a_den_len: i32,
+    b_num_ptr: i32,
+    b_num_len: i32,
+    b_den_ptr: i32,
+    b_den_len: i32,
+) -> i64 {
+    let a = read_rational(a_num_ptr, a_num_len, a_den_ptr, a_den_len);
+    let b = read_rational(b_num_ptr, b_num_len, b_den_ptr, b_den_len);
+    let r = match op {
+        0 => a + b,
+        1 => a - b,
+        2 => a * b,
+        3 => a / b,
+        4 => expt(a, b),
+        _ => unreachable!("unknown rational binop: {}", op),
+    };
+    write_rational(&r)
+}
+
+fn expt(base: BigRational, exponent: BigRational) -> BigRational {
+    if !exponent.is_integer() {
+        runtime_error("expt: exponent must be an integer");
+    }
+    let Some(exponent) = exponent.numer().to_i32() else {
+        runtime_error("expt: exponent is too large");
+    };
+    if base.is_zero() && exponent < 0 {
+        runtime_error("division by zero");
+    }
+    base.pow(exponent)
+}
+
+#[allow(clippy::too_many_arguments)]
+#[unsafe(no_mangle)]
+pub extern "C" fn _rational_cmp(
+    a_num_ptr: i32,
+    a_num_len: i32,
+    a_den_ptr: i32,
+    a_den_len: i32,
+    b_num_ptr: i32,
+    b_num_len: i32,
+    b_den_ptr: i32,
+    b_den_len: i32,
+) -> i32 {
+    let a = read_rational(a_num_ptr, a_num_len, a_den_ptr, a_den_len);
+    let b = read_rational(b_num_ptr, b_num_len, b_den_ptr, b_den_len);
+    a.cmp(&b) as i32
+}
+
+// opはwasm_generatorのRationalUnOpと対応。結果は整数のバイト列
+#[unsafe(no_mangle)]
+pub extern "C" fn _rational_unop(
+    op: i32,
+    num_ptr: i32,
+    num_len: i32,
+    den_ptr: i32,
+    den_len: i32,
+) -> i64 {
+    let x = read_rational(num_ptr, num_len, den_ptr, den_len);
+    let r = match op {
+        0 => x.floor().to_integer(),
+        1 => {
+            // r5rsのroundは偶数丸め
+            let floor = x.floor().to_integer();
+            let diff = x - BigRational::from_integer(floor.clone());
+            let half = BigRational::new(BigInt::one(), BigInt::from(2));
+            if diff > half || (diff == half && floor.is_odd()) {
+                floor + 1
+            } else {
+                floor
+            }
+        }
+        2 => x.ceil().to_integer(),
+        3 => x.trunc().to_integer(),
+        // 整数のみ渡される
+        4 => {
+            if x.is_negative() {
+                runtime_error("exact-integer-sqrt: negative argument");
+            }
+            x.to_integer().sqrt()
+        }
+        _ => unreachable!("unknown rational unop: {}", op),
+    };
+    write_bytes(&r.to_signed_bytes_le())
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn _rational_to_float(
+    num_ptr: i32,
+    num_len: i32,
+    den_ptr: i32,
+    den_len: i32,
+) -> f64 {
+    read_rational(num_ptr, num_len, den_ptr, den_len)
+        .to_f64()
+        .unwrap_or(f64::NAN)
+}
+
+// 有限の値のみ渡される
+#[unsafe(no_mangle)]
+pub extern "C" fn _float_to_exact(f: f64) -> i64 {
+    write_rational(&BigRational::from_float(f).unwrap())
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn _rational_to_string(
+    num_ptr: i32,
+    num_len: i32,
+    den_ptr: i32,
+    den_len: i32,
+) -> i64 {
+    let x = read_rational(num_ptr, num_len, den_ptr, den_len);
+    write_bytes(x.to_string().as_bytes())
+}
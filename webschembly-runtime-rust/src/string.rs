@@ -0,0 +1,148 @@
+// 文字と文字列の手続きのうち、Unicodeの情報が必要なもの
+use crate::bigint::write_bytes;
+use crate::port::{TAG_FALSE, encode_datum};
+use crate::{read_bytes, runtime_error};
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use webschembly_compiler_locate::{Pos, Span};
+use webschembly_compiler_reader::{lexer, sexpr_parser};
+use webschembly_compiler_sexpr::{LSExpr, SExpr};
+
+fn to_char(c: i32) -> char {
+    char::from_u32(c as u32).unwrap_or(char::REPLACEMENT_CHARACTER)
+}
+
+// 1文字に対応しない場合(ß -> SSなど)は変換しない
+fn upcase(c: char) -> char {
+    let mut upper = c.to_uppercase();
+    match (upper.next(), upper.next()) {
+        (Some(u), None) => u,
+        _ => c,
+    }
+}
+
+fn downcase(c: char) -> char {
+    let mut lower = c.to_lowercase();
+    match (lower.next(), lower.next()) {
+        (Some(l), None) => l,
+        _ => c,
+    }
+}
+
+// opはwasm_generatorのCharOpと対応
+#[unsafe(no_mangle)]
+pub extern "C" fn char_op(op: i32, c: i32) -> i32 {
+    let c = to_char(c);
+    match op {
+        0 => upcase(c) as i32,
+        1 => downcase(c) as i32,
+        2 => c.is_alphabetic() as i32,
+        3 => c.is_numeric() as i32,
+        4 => c.is_whitespace() as i32,
+        5 => c.is_uppercase() as i32,
+        6 => c.is_lowercase() as i32,
+        _ => unreachable!("unknown char op: {}", op),
+    }
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn int_to_char(i: i64) -> i32 {
+    match u32::try_from(i).ok().and_then(char::from_u32) {
+        Some(c) => c as i32,
+        None => runtime_error(&format!("integer->char: invalid code point: {}", i)),
+    }
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn _string_cmp_ci(ptr1: i32, len1: i32, ptr2: i32, len2: i32) -> i32 {
+    let s1 = String::from_utf8_lossy(&read_bytes(ptr1, len1)).into_owned();
+    let s2 = String::from_utf8_lossy(&read_bytes(ptr2, len2)).into_owned();
+    s1.chars().map(downcase).cmp(s2.chars().map(downcase)) as i32
+}
+
+// 数値をport.rsと同じ形式でエンコードして返す。数値として解釈できなければ#fを返す
+#[unsafe(no_mangle)]
+pub extern "C" fn _string_to_number(ptr: i32, len: i32, radix: i32) -> i64 {
+    if !matches!(radix, 2 | 8 | 10 | 16) {
+        runtime_error(&format!("string->number: invalid radix: {}", radix));
+    }
+    let s = String::from_utf8_lossy(&read_bytes(ptr, len)).into_owned();
+    let mut buf = Vec::new();
+    let number = if radix == 10 {
+        parse_decimal(&s)
+    } else {
+        parse_radix(&s, radix as u32)
+    };
+    match number.map(|value| {
+        encode_datum(
+            &LSExpr {
+                value,
+                span: Span {
+                    start: Pos { line: 1, column: 1 },
+                    end: Pos { line: 1, column: 1 },
+                },
+            },
+            &mut buf,
+        )
+    }) {
+        Some(Ok(())) => {}
+        _ => {
+            buf.clear();
+            buf.push(TAG_FALSE);
+        }
+    }
+    write_bytes(&buf)
+}
+
+// ソースコードの数値リテラルと同じ規則で解釈する
+fn parse_decimal(s: &str) -> Option<SExpr> {
+    if s.is_empty() || s.contains(char::is_whitespace) {
+        return None;
+    }
+    let tokens = lexer::lex(s).ok()?;
+    let mut sexprs = sexpr_parser::parse(&tokens).ok()?;
+    if sexprs.len() != 1 {
+        return None;
+    }
+    match sexprs.pop().unwrap().value {
+        value @ (SExpr::Int(_)
+        | SExpr::BigInt(_)
+        | SExpr::Rational(_, _)
+        | SExpr::Float(_)
+        | SExpr::NaN) => Some(value),
+        _ => None,
+    }
+}
+
+// 10進数以外は整数と分数のみ対応する
+fn parse_radix(s: &str, radix: u32) -> Option<SExpr> {
+    let parse_digits = |digits: &str| {
+        if digits.is_empty() || digits.starts_with(['+', '-']) {
+            None
+        } else {
+            BigInt::parse_bytes(digits.as_bytes(), radix)
+        }
+    };
+    let (sign, rest) = match s.strip_prefix('-') {
+        Some(rest) => (-1, rest),
+        None => (1, s.strip_prefix('+').unwrap_or(s)),
+    };
+    let r = match rest.split_once('/') {
+        Some((num, den)) => {
+            let den = parse_digits(den)?;
+            if den == BigInt::ZERO {
+                return None;
+            }
+            BigRational::new(parse_digits(num)? * sign, den)
+        }
+        None => BigRational::from_integer(parse_digits(rest)? * sign),
+    };
+    Some(if r.is_integer() {
+        match i64::try_from(r.numer()) {
+            Ok(i) => SExpr::Int(i),
+            Err(_) => SExpr::BigInt(r.numer().to_string()),
+        }
+    } else {
+        SExpr::Rational(r.numer().to_string(), r.denom().to_string())
+    })
+}
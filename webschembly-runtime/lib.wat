@@ -7,14 +7,41 @@
   (type $Char (sub final (struct (field $value i32))))
   (type $Int (sub final (struct (field i64))))
   (type $Float (sub final (struct (field f64))))
+  ;; i64に収まらない整数。2の補数リトルエンディアンのバイト列
+  (type $BigInt (sub final (struct (field $bytes (ref $Buf)))))
+  ;; 分子と分母はint/bigint。既約かつ分母は2以上
+  (type $Rational (sub final (struct (field $num eqref) (field $den eqref))))
   (type $String (sub final (struct
                             (field $buf (mut (ref $StringBuf)))
                             (field $len i32)
                             (field $offset i32))))
   (type $Symbol (sub final (struct (field $name (ref null $String)))))
+  ;; kind: 0: 一般のエラー, 1: read-error
+  (type $ErrorObject (sub final (struct (field $message (ref null $String)) (field $irritants eqref) (field $kind i8))))
+  ;; ポートの実体はRust側で管理し、ここではIDのみ持つ
+  (type $Port (sub final (struct (field $id i32) (field $input i8) (field $output i8))))
+  ;; Nilと区別するためfinalにしない
+  (type $Eof (sub (struct)))
   (type $Cons (sub final (struct (field $car (mut eqref)) (field $cdr (mut eqref)))))
   (type $Vector (array (mut eqref)))
+  ;; kind: 0: eq?, 1: eqv?, 2: equal?
+  ;; bucketsの各要素は(key . value)の連想リスト
+  (type $HashTable (sub final (struct (field $kind i8) (field $buckets (mut (ref $Vector))) (field $count (mut i32)))))
+  ;; define-record-typeで定義されるレコード
+  ;; 全てのレコード型で共通の構造体を使い、$idでレコード型を区別する。$typeは表示に使うレコード型を表すオブジェクト
+  (type $Record (sub final (struct (field $id i32) (field $type eqref) (field $fields (ref null $Vector)))))
   (type $Args (array (mut eqref)))
+  (type $S64Vector (array (mut i64)))
+  (type $F64Vector (array (mut f64)))
+  ;; 要素の型が同じでも種類が異なるuvectorを区別するため、1つのrecグループにまとめる
+  (rec
+    (type $U8Vector (array (mut i8)))
+    (type $S8Vector (array (mut i8)))
+    (type $U16Vector (array (mut i16)))
+    (type $S16Vector (array (mut i16)))
+    (type $U32Vector (array (mut i32)))
+    (type $S32Vector (array (mut i32)))
+    (type $F32Vector (array (mut f32))))
   (type $MutFuncRef (sub final (struct (field $func (mut funcref)))))
   (type $EntrypointTable (array (mut (ref null $MutFuncRef))))
   (type $Closure (sub (struct
@@ -32,14 +59,60 @@
   (import "runtime" "_string_to_symbol" (func $_string_to_symbol (param i32) (param i32) (result i32)))
   (import "runtime" "_int_to_string" (func $_int_to_string (param i64) (result i64)))
   (import "runtime" "_float_to_string" (func $_float_to_string (param f64) (result i64)))
+  (import "runtime" "_integer_binop" (func $_integer_binop (param i32) (param i32) (param i32) (param i32) (param i32) (result i64)))
+  (import "runtime" "_integer_cmp" (func $_integer_cmp (param i32) (param i32) (param i32) (param i32) (result i32)))
+  (import "runtime" "_bigint_to_string" (func $_bigint_to_string (param i32) (param i32) (result i64)))
+  (import "runtime" "_string_to_bigint" (func $_string_to_bigint (param i32) (param i32) (result i64)))
+  (import "runtime" "_rational_binop" (func $_rational_binop (param i32) (param i32) (param i32) (param i32) (param i32) (param i32) (param i32) (param i32) (param i32) (result i64)))
+  (import "runtime" "_rational_cmp" (func $_rational_cmp (param i32) (param i32) (param i32) (param i32) (param i32) (param i32) (param i32) (param i32) (result i32)))
+  (import "runtime" "_rational_unop" (func $_rational_unop (param i32) (param i32) (param i32) (param i32) (param i32) (result i64)))
+  (import "runtime" "_rational_to_float" (func $_rational_to_float (param i32) (param i32) (param i32) (param i32) (result f64)))
+  (import "runtime" "_float_to_exact" (func $_float_to_exact (param f64) (result i64)))
+  (import "runtime" "_rational_to_string" (func $_rational_to_string (param i32) (param i32) (param i32) (param i32) (result i64)))
   (import "runtime" "write_buf" (func $write_buf (param i32) (param i32) (param i32)))
   (import "runtime" "write_char" (func $write_char (param i32)))
-  (import "runtime" "get_global_id" (func $get_global_id (param i32) (param i32) (result i32)))
+  (import "runtime" "current_output_fd" (func $current_output_fd (result i32)))
+  (import "runtime" "_open_input_string" (func $_open_input_string (param i32) (param i32) (result i32)))
+  (import "runtime" "_open_output_string" (func $_open_output_string (result i32)))
+  (import "runtime" "_get_output_string" (func $_get_output_string (param i32) (result i64)))
+  (import "runtime" "_close_port" (func $_close_port (param i32)))
+  (import "runtime" "_read_char" (func $_read_char (param i32) (result i32)))
+  (import "runtime" "_peek_char" (func $_peek_char (param i32) (result i32)))
+  (import "runtime" "_read" (func $_read (param i32) (result i64)))
+  (import "runtime" "_datum_nil" (func $_datum_nil))
+  (import "runtime" "_datum_bool" (func $_datum_bool (param i32)))
+  (import "runtime" "_datum_int" (func $_datum_int (param i64)))
+  (import "runtime" "_datum_integer" (func $_datum_integer (param i32) (param i32)))
+  (import "runtime" "_datum_rational" (func $_datum_rational (param i32) (param i32) (param i32) (param i32)))
+  (import "runtime" "_datum_float" (func $_datum_float (param f64)))
+  (import "runtime" "_datum_char" (func $_datum_char (param i32)))
+  (import "runtime" "_datum_string" (func $_datum_string (param i32) (param i32)))
+  (import "runtime" "_datum_symbol" (func $_datum_symbol (param i32) (param i32)))
+  (import "runtime" "_datum_list" (func $_datum_list (param i32)))
+  (import "runtime" "_datum_vector" (func $_datum_vector (param i32)))
+  (import "runtime" "_datum_uvector" (func $_datum_uvector (param i32) (param i32)))
+  (import "runtime" "_datum_invalid" (func $_datum_invalid))
+  (import "runtime" "_eval" (func $_eval))
+  (import "runtime" "_string_cmp_ci" (func $_string_cmp_ci (param i32) (param i32) (param i32) (param i32) (result i32)))
+  (import "runtime" "_string_to_number" (func $_string_to_number (param i32) (param i32) (param i32) (result i64)))
   (global $nil (export "nil") (ref null $Nil) (struct.new $Nil))
   (global $true (export "true") (ref null $Bool) (struct.new $Bool (i32.const 1)))
   (global $false (export "false") (ref null $Bool) (struct.new $Bool (i32.const 0)))
+  (global $eof (export "eof") eqref (struct.new $Eof))
+  ;; evalでコンパイルしたモジュールがトップレベルの値を保存する
+  (global $eval_result (export "eval_result") (mut eqref) (ref.null eq))
   (table $symbols 1 (ref null $Symbol))
   (tag $WEBSCHEMBLY_EXCEPTION (export "WEBSCHEMBLY_EXCEPTION"))
+  ;; raiseされたオブジェクト: guardで捕捉する
+  (tag $RAISE (param eqref))
+  ;; 実行中のwith_raise_handlerの数
+  (global $raise_handler_depth (mut i32) (i32.const 0))
+  (data $division_by_zero_msg "division by zero\n")
+  (data $non_finite_to_exact_msg "cannot convert non-finite number to exact\n")
+  (data $apply_non_list_msg "apply: last argument must be a list\n")
+  (data $substring_range_msg "substring: index out of range\n")
+  (data $make_string_negative_msg "make-string: negative length\n")
+  (data $list_to_string_msg "list->string: argument must be a list of characters\n")
 
   (func $display_fd (export "display_fd") (param $fd i32) (param $s (ref null $String))
     (local $s_ptr i32)
@@ -50,7 +123,531 @@
     (call $write_buf (local.get $fd) (local.get $s_ptr) (local.get $s_len))
     (call $free (local.get $s_ptr)))
   (func $display (export "display") (param $s (ref null $String))
-    (call $display_fd (i32.const 1) (local.get $s)))
+    (call $display_fd (call $current_output_fd) (local.get $s)))
+  (func $open_input_string (export "open_input_string") (param $s (ref null $String)) (result (ref null $Port))
+    (local $s_ptr i32)
+    (local $s_len i32)
+    (local $id i32)
+    (call $string_to_memory (local.get $s))
+    (local.set $s_ptr)
+    (local.set $s_len)
+    (local.set $id (call $_open_input_string (local.get $s_ptr) (local.get $s_len)))
+    (call $free (local.get $s_ptr))
+    (struct.new $Port (local.get $id) (i32.const 1) (i32.const 0)))
+
+  (func $open_output_string (export "open_output_string") (result (ref null $Port))
+    (struct.new $Port (call $_open_output_string) (i32.const 0) (i32.const 1)))
+
+  ;; 0: 標準入力, 1: 標準出力, 2: 標準エラー出力
+  (func $standard_port (export "standard_port") (param $fd i32) (result (ref null $Port))
+    (struct.new $Port
+      (local.get $fd)
+      (i32.eqz (local.get $fd))
+      (i32.ne (local.get $fd) (i32.const 0))))
+
+  (func $get_output_string (export "get_output_string") (param $port (ref null $Port)) (result (ref null $String))
+    (local $s_ptr i32)
+    (local $s_len i32)
+    (local $s (ref null $String))
+    (call $uncos_tuple_i32 (call $_get_output_string (struct.get $Port $id (local.get $port))))
+    (local.set $s_ptr)
+    (local.set $s_len)
+    (local.set $s (call $memory_to_string (local.get $s_ptr) (local.get $s_len)))
+    (call $free (local.get $s_ptr))
+    (local.get $s))
+
+  (func $close_port (export "close_port") (param $port (ref null $Port))
+    (call $_close_port (struct.get $Port $id (local.get $port))))
+
+  (func $read_char (export "read_char") (param $port (ref null $Port)) (result eqref)
+    (call $char_or_eof (call $_read_char (struct.get $Port $id (local.get $port)))))
+
+  (func $peek_char (export "peek_char") (param $port (ref null $Port)) (result eqref)
+    (call $char_or_eof (call $_peek_char (struct.get $Port $id (local.get $port)))))
+
+  ;; 負の値はEOFを表す
+  (func $char_or_eof (param $c i32) (result eqref)
+    (if (result eqref) (i32.lt_s (local.get $c) (i32.const 0))
+      (then (global.get $eof))
+      (else (struct.new $Char (local.get $c)))))
+
+  ;; データ、EOFまたはread-errorのエラーオブジェクトを返す
+  (func $read (export "read") (param $port (ref null $Port)) (result eqref)
+    (local $ptr i32)
+    (local $len i32)
+    (local $datum eqref)
+    (call $uncos_tuple_i32 (call $_read (struct.get $Port $id (local.get $port))))
+    (local.set $ptr)
+    (local.set $len)
+    (call $decode_datum (local.get $ptr))
+    (drop)
+    (local.set $datum)
+    (call $free (local.get $ptr))
+    (local.get $datum))
+
+  ;; port.rsのencode_datumでエンコードされたデータを復元する
+  ;; 復元した値と続きの位置を返す
+  (func $decode_datum (param $ptr i32) (result eqref i32)
+    (local $tag i32)
+    (local $len i32)
+    (local $i i32)
+    (local $value eqref)
+    (local $den eqref)
+    (local $elements (ref null $Args))
+    (local $vector (ref null $Vector))
+    (local $s64vector (ref null $S64Vector))
+    (local $f64vector (ref null $F64Vector))
+    (local $u8vector (ref null $U8Vector))
+    (local $s8vector (ref null $S8Vector))
+    (local $u16vector (ref null $U16Vector))
+    (local $s16vector (ref null $S16Vector))
+    (local $u32vector (ref null $U32Vector))
+    (local $s32vector (ref null $S32Vector))
+    (local $f32vector (ref null $F32Vector))
+
+    (local.set $tag (i32.load8_u (local.get $ptr)))
+    (local.set $ptr (i32.add (local.get $ptr) (i32.const 1)))
+
+    ;; nil
+    (if (i32.eq (local.get $tag) (i32.const 0))
+      (then (return (global.get $nil) (local.get $ptr))))
+    ;; #t
+    (if (i32.eq (local.get $tag) (i32.const 1))
+      (then (return (global.get $true) (local.get $ptr))))
+    ;; #f
+    (if (i32.eq (local.get $tag) (i32.const 2))
+      (then (return (global.get $false) (local.get $ptr))))
+    ;; int
+    (if (i32.eq (local.get $tag) (i32.const 3))
+      (then
+        (return
+          (struct.new $Int (i64.load (local.get $ptr)))
+          (i32.add (local.get $ptr) (i32.const 8)))))
+    ;; eof
+    (if (i32.eq (local.get $tag) (i32.const 14))
+      (then (return (global.get $eof) (local.get $ptr))))
+    ;; rational: [分子][分母]
+    (if (i32.eq (local.get $tag) (i32.const 5))
+      (then
+        (call $decode_datum (local.get $ptr))
+        (local.set $ptr)
+        (local.set $value)
+        (call $decode_datum (local.get $ptr))
+        (local.set $ptr)
+        (local.set $den)
+        (return (struct.new $Rational (local.get $value) (local.get $den)) (local.get $ptr))))
+    ;; float
+    (if (i32.eq (local.get $tag) (i32.const 6))
+      (then
+        (return
+          (struct.new $Float (f64.load (local.get $ptr)))
+          (i32.add (local.get $ptr) (i32.const 8)))))
+    ;; char
+    (if (i32.eq (local.get $tag) (i32.const 7))
+      (then
+        (return
+          (struct.new $Char (i32.load (local.get $ptr)))
+          (i32.add (local.get $ptr) (i32.const 4)))))
+
+    ;; 以降は [長さ(u32)][...] の形式
+    (local.set $len (i32.load (local.get $ptr)))
+    (local.set $ptr (i32.add (local.get $ptr) (i32.const 4)))
+
+    ;; bigint
+    (if (i32.eq (local.get $tag) (i32.const 4))
+      (then
+        (return
+          (struct.new $BigInt (call $memory_to_buf (local.get $ptr) (local.get $len)))
+          (i32.add (local.get $ptr) (local.get $len)))))
+    ;; string
+    (if (i32.eq (local.get $tag) (i32.const 8))
+      (then
+        (return
+          (call $memory_to_string (local.get $ptr) (local.get $len))
+          (i32.add (local.get $ptr) (local.get $len)))))
+    ;; symbol
+    (if (i32.eq (local.get $tag) (i32.const 9))
+      (then
+        (return
+          (call $string_to_symbol (call $memory_to_string (local.get $ptr) (local.get $len)))
+          (i32.add (local.get $ptr) (local.get $len)))))
+    ;; read-error
+    (if (i32.eq (local.get $tag) (i32.const 15))
+      (then
+        (return
+          (struct.new $ErrorObject
+            (call $memory_to_string (local.get $ptr) (local.get $len))
+            (global.get $nil)
+            (i32.const 1))
+          (i32.add (local.get $ptr) (local.get $len)))))
+    ;; list: [要素...][末尾]
+    (if (i32.eq (local.get $tag) (i32.const 10))
+      (then
+        (local.set $elements (array.new $Args (ref.null eq) (local.get $len)))
+        (block $break
+          (loop $loop
+            (br_if $break (i32.ge_u (local.get $i) (local.get $len)))
+            (call $decode_datum (local.get $ptr))
+            (local.set $ptr)
+            (local.set $value)
+            (array.set $Args (local.get $elements) (local.get $i) (local.get $value))
+            (local.set $i (i32.add (local.get $i) (i32.const 1)))
+            (br $loop)))
+        (call $decode_datum (local.get $ptr))
+        (local.set $ptr)
+        (local.set $value)
+        (block $break
+          (loop $loop
+            (br_if $break (i32.eqz (local.get $i)))
+            (local.set $i (i32.sub (local.get $i) (i32.const 1)))
+            (local.set $value
+              (struct.new $Cons
+                (array.get $Args (local.get $elements) (local.get $i))
+                (local.get $value)))
+            (br $loop)))
+        (return (local.get $value) (local.get $ptr))))
+    ;; vector
+    (if (i32.eq (local.get $tag) (i32.const 11))
+      (then
+        (local.set $vector (array.new $Vector (ref.null eq) (local.get $len)))
+        (block $break
+          (loop $loop
+            (br_if $break (i32.ge_u (local.get $i) (local.get $len)))
+            (call $decode_datum (local.get $ptr))
+            (local.set $ptr)
+            (local.set $value)
+            (array.set $Vector (local.get $vector) (local.get $i) (local.get $value))
+            (local.set $i (i32.add (local.get $i) (i32.const 1)))
+            (br $loop)))
+        (return (local.get $vector) (local.get $ptr))))
+    ;; s64vector
+    (if (i32.eq (local.get $tag) (i32.const 12))
+      (then
+        (local.set $s64vector (array.new $S64Vector (i64.const 0) (local.get $len)))
+        (block $break
+          (loop $loop
+            (br_if $break (i32.ge_u (local.get $i) (local.get $len)))
+            (array.set $S64Vector (local.get $s64vector) (local.get $i) (i64.load (local.get $ptr)))
+            (local.set $ptr (i32.add (local.get $ptr) (i32.const 8)))
+            (local.set $i (i32.add (local.get $i) (i32.const 1)))
+            (br $loop)))
+        (return (local.get $s64vector) (local.get $ptr))))
+    ;; f64vector
+    (if (i32.eq (local.get $tag) (i32.const 13))
+      (then
+        (local.set $f64vector (array.new $F64Vector (f64.const 0) (local.get $len)))
+        (block $break
+          (loop $loop
+            (br_if $break (i32.ge_u (local.get $i) (local.get $len)))
+            (array.set $F64Vector (local.get $f64vector) (local.get $i) (f64.load (local.get $ptr)))
+            (local.set $ptr (i32.add (local.get $ptr) (i32.const 8)))
+            (local.set $i (i32.add (local.get $i) (i32.const 1)))
+            (br $loop)))
+        (return (local.get $f64vector) (local.get $ptr))))
+    ;; u8vector
+    (if (i32.eq (local.get $tag) (i32.const 16))
+      (then
+        (local.set $u8vector (array.new_default $U8Vector (local.get $len)))
+        (block $break
+          (loop $loop
+            (br_if $break (i32.ge_u (local.get $i) (local.get $len)))
+            (array.set $U8Vector (local.get $u8vector) (local.get $i) (i32.load8_u (local.get $ptr)))
+            (local.set $ptr (i32.add (local.get $ptr) (i32.const 1)))
+            (local.set $i (i32.add (local.get $i) (i32.const 1)))
+            (br $loop)))
+        (return (local.get $u8vector) (local.get $ptr))))
+    ;; s8vector
+    (if (i32.eq (local.get $tag) (i32.const 17))
+      (then
+        (local.set $s8vector (array.new_default $S8Vector (local.get $len)))
+        (block $break
+          (loop $loop
+            (br_if $break (i32.ge_u (local.get $i) (local.get $len)))
+            (array.set $S8Vector (local.get $s8vector) (local.get $i) (i32.load8_s (local.get $ptr)))
+            (local.set $ptr (i32.add (local.get $ptr) (i32.const 1)))
+            (local.set $i (i32.add (local.get $i) (i32.const 1)))
+            (br $loop)))
+        (return (local.get $s8vector) (local.get $ptr))))
+    ;; u16vector
+    (if (i32.eq (local.get $tag) (i32.const 18))
+      (then
+        (local.set $u16vector (array.new_default $U16Vector (local.get $len)))
+        (block $break
+          (loop $loop
+            (br_if $break (i32.ge_u (local.get $i) (local.get $len)))
+            (array.set $U16Vector (local.get $u16vector) (local.get $i) (i32.load16_u (local.get $ptr)))
+            (local.set $ptr (i32.add (local.get $ptr) (i32.const 2)))
+            (local.set $i (i32.add (local.get $i) (i32.const 1)))
+            (br $loop)))
+        (return (local.get $u16vector) (local.get $ptr))))
+    ;; s16vector
+    (if (i32.eq (local.get $tag) (i32.const 19))
+      (then
+        (local.set $s16vector (array.new_default $S16Vector (local.get $len)))
+        (block $break
+          (loop $loop
+            (br_if $break (i32.ge_u (local.get $i) (local.get $len)))
+            (array.set $S16Vector (local.get $s16vector) (local.get $i) (i32.load16_s (local.get $ptr)))
+            (local.set $ptr (i32.add (local.get $ptr) (i32.const 2)))
+            (local.set $i (i32.add (local.get $i) (i32.const 1)))
+            (br $loop)))
+        (return (local.get $s16vector) (local.get $ptr))))
+    ;; u32vector
+    (if (i32.eq (local.get $tag) (i32.const 20))
+      (then
+        (local.set $u32vector (array.new_default $U32Vector (local.get $len)))
+        (block $break
+          (loop $loop
+            (br_if $break (i32.ge_u (local.get $i) (local.get $len)))
+            (array.set $U32Vector (local.get $u32vector) (local.get $i) (i32.load (local.get $ptr)))
+            (local.set $ptr (i32.add (local.get $ptr) (i32.const 4)))
+            (local.set $i (i32.add (local.get $i) (i32.const 1)))
+            (br $loop)))
+        (return (local.get $u32vector) (local.get $ptr))))
+    ;; s32vector
+    (if (i32.eq (local.get $tag) (i32.const 21))
+      (then
+        (local.set $s32vector (array.new_default $S32Vector (local.get $len)))
+        (block $break
+          (loop $loop
+            (br_if $break (i32.ge_u (local.get $i) (local.get $len)))
+            (array.set $S32Vector (local.get $s32vector) (local.get $i) (i32.load (local.get $ptr)))
+            (local.set $ptr (i32.add (local.get $ptr) (i32.const 4)))
+            (local.set $i (i32.add (local.get $i) (i32.const 1)))
+            (br $loop)))
+        (return (local.get $s32vector) (local.get $ptr))))
+    ;; f32vector
+    (if (i32.eq (local.get $tag) (i32.const 22))
+      (then
+        (local.set $f32vector (array.new_default $F32Vector (local.get $len)))
+        (block $break
+          (loop $loop
+            (br_if $break (i32.ge_u (local.get $i) (local.get $len)))
+            (array.set $F32Vector (local.get $f32vector) (local.get $i) (f32.load (local.get $ptr)))
+            (local.set $ptr (i32.add (local.get $ptr) (i32.const 4)))
+            (local.set $i (i32.add (local.get $i) (i32.const 1)))
+            (br $loop)))
+        (return (local.get $f32vector) (local.get $ptr))))
+    (unreachable))
+
+  (func $eval (export "eval") (param $x eqref) (result eqref)
+    (local $result eqref)
+    (call $push_datum (local.get $x))
+    (call $_eval)
+    (local.set $result (global.get $eval_result))
+    ;; 結果を保持し続けないようにする
+    (global.set $eval_result (ref.null eq))
+    (local.get $result))
+
+  ;; 値をS式に変換してeval.rsのスタックに積む
+  (func $push_datum (param $x eqref)
+    (local $ptr i32)
+    (local $len i32)
+    (local $den_ptr i32)
+    (local $den_len i32)
+    (local $i i32)
+    (local $n i32)
+    (local $vector (ref null $Vector))
+    (local $s64vector (ref null $S64Vector))
+    (local $f64vector (ref null $F64Vector))
+    (local $u8vector (ref null $U8Vector))
+    (local $s8vector (ref null $S8Vector))
+    (local $u16vector (ref null $U16Vector))
+    (local $s16vector (ref null $S16Vector))
+    (local $u32vector (ref null $U32Vector))
+    (local $s32vector (ref null $S32Vector))
+    (local $f32vector (ref null $F32Vector))
+    (if (ref.test (ref $Nil) (local.get $x))
+      (then
+        (call $_datum_nil)
+        (return)))
+    (if (ref.test (ref $Bool) (local.get $x))
+      (then
+        (call $_datum_bool (struct.get_u $Bool 0 (ref.cast (ref $Bool) (local.get $x))))
+        (return)))
+    (if (ref.test (ref $Int) (local.get $x))
+      (then
+        (call $_datum_int (struct.get $Int 0 (ref.cast (ref $Int) (local.get $x))))
+        (return)))
+    (if (ref.test (ref $BigInt) (local.get $x))
+      (then
+        (call $integer_to_memory (local.get $x))
+        (local.set $ptr)
+        (local.set $len)
+        (call $_datum_integer (local.get $ptr) (local.get $len))
+        (call $free (local.get $ptr))
+        (return)))
+    (if (ref.test (ref $Rational) (local.get $x))
+      (then
+        (call $integer_to_memory (struct.get $Rational $num (ref.cast (ref $Rational) (local.get $x))))
+        (local.set $ptr)
+        (local.set $len)
+        (call $integer_to_memory (struct.get $Rational $den (ref.cast (ref $Rational) (local.get $x))))
+        (local.set $den_ptr)
+        (local.set $den_len)
+        (call $_datum_rational (local.get $ptr) (local.get $len) (local.get $den_ptr) (local.get $den_len))
+        (call $free (local.get $ptr))
+        (call $free (local.get $den_ptr))
+        (return)))
+    (if (ref.test (ref $Float) (local.get $x))
+      (then
+        (call $_datum_float (struct.get $Float 0 (ref.cast (ref $Float) (local.get $x))))
+        (return)))
+    (if (ref.test (ref $Char) (local.get $x))
+      (then
+        (call $_datum_char (struct.get $Char $value (ref.cast (ref $Char) (local.get $x))))
+        (return)))
+    (if (ref.test (ref $String) (local.get $x))
+      (then
+        (call $string_to_memory (ref.cast (ref $String) (local.get $x)))
+        (local.set $ptr)
+        (local.set $len)
+        (call $_datum_string (local.get $ptr) (local.get $len))
+        (call $free (local.get $ptr))
+        (return)))
+    (if (ref.test (ref $Symbol) (local.get $x))
+      (then
+        (call $string_to_memory (struct.get $Symbol $name (ref.cast (ref $Symbol) (local.get $x))))
+        (local.set $ptr)
+        (local.set $len)
+        (call $_datum_symbol (local.get $ptr) (local.get $len))
+        (call $free (local.get $ptr))
+        (return)))
+    (if (ref.test (ref $Cons) (local.get $x))
+      (then
+        ;; 長いリストで再帰が深くならないようにcdr方向はループで辿る
+        (block $break
+          (loop $loop
+            (br_if $break (i32.eqz (ref.test (ref $Cons) (local.get $x))))
+            (call $push_datum (struct.get $Cons $car (ref.cast (ref $Cons) (local.get $x))))
+            (local.set $x (struct.get $Cons $cdr (ref.cast (ref $Cons) (local.get $x))))
+            (local.set $n (i32.add (local.get $n) (i32.const 1)))
+            (br $loop)))
+        (call $push_datum (local.get $x))
+        (call $_datum_list (local.get $n))
+        (return)))
+    (if (ref.test (ref $Vector) (local.get $x))
+      (then
+        (local.set $vector (ref.cast (ref $Vector) (local.get $x)))
+        (local.set $n (array.len (local.get $vector)))
+        (block $break
+          (loop $loop
+            (br_if $break (i32.ge_u (local.get $i) (local.get $n)))
+            (call $push_datum (array.get $Vector (local.get $vector) (local.get $i)))
+            (local.set $i (i32.add (local.get $i) (i32.const 1)))
+            (br $loop)))
+        (call $_datum_vector (local.get $n))
+        (return)))
+    (if (ref.test (ref $S64Vector) (local.get $x))
+      (then
+        (local.set $s64vector (ref.cast (ref $S64Vector) (local.get $x)))
+        (local.set $n (array.len (local.get $s64vector)))
+        (block $break
+          (loop $loop
+            (br_if $break (i32.ge_u (local.get $i) (local.get $n)))
+            (call $_datum_int (array.get $S64Vector (local.get $s64vector) (local.get $i)))
+            (local.set $i (i32.add (local.get $i) (i32.const 1)))
+            (br $loop)))
+        (call $_datum_uvector (i32.const 0) (local.get $n))
+        (return)))
+    (if (ref.test (ref $F64Vector) (local.get $x))
+      (then
+        (local.set $f64vector (ref.cast (ref $F64Vector) (local.get $x)))
+        (local.set $n (array.len (local.get $f64vector)))
+        (block $break
+          (loop $loop
+            (br_if $break (i32.ge_u (local.get $i) (local.get $n)))
+            (call $_datum_float (array.get $F64Vector (local.get $f64vector) (local.get $i)))
+            (local.set $i (i32.add (local.get $i) (i32.const 1)))
+            (br $loop)))
+        (call $_datum_uvector (i32.const 1) (local.get $n))
+        (return)))
+    (if (ref.test (ref $U8Vector) (local.get $x))
+      (then
+        (local.set $u8vector (ref.cast (ref $U8Vector) (local.get $x)))
+        (local.set $n (array.len (local.get $u8vector)))
+        (block $break
+          (loop $loop
+            (br_if $break (i32.ge_u (local.get $i) (local.get $n)))
+            (call $_datum_int (i64.extend_i32_u (array.get_u $U8Vector (local.get $u8vector) (local.get $i))))
+            (local.set $i (i32.add (local.get $i) (i32.const 1)))
+            (br $loop)))
+        (call $_datum_uvector (i32.const 2) (local.get $n))
+        (return)))
+    (if (ref.test (ref $S8Vector) (local.get $x))
+      (then
+        (local.set $s8vector (ref.cast (ref $S8Vector) (local.get $x)))
+        (local.set $n (array.len (local.get $s8vector)))
+        (block $break
+          (loop $loop
+            (br_if $break (i32.ge_u (local.get $i) (local.get $n)))
+            (call $_datum_int (i64.extend_i32_s (array.get_s $S8Vector (local.get $s8vector) (local.get $i))))
+            (local.set $i (i32.add (local.get $i) (i32.const 1)))
+            (br $loop)))
+        (call $_datum_uvector (i32.const 3) (local.get $n))
+        (return)))
+    (if (ref.test (ref $U16Vector) (local.get $x))
+      (then
+        (local.set $u16vector (ref.cast (ref $U16Vector) (local.get $x)))
+        (local.set $n (array.len (local.get $u16vector)))
+        (block $break
+          (loop $loop
+            (br_if $break (i32.ge_u (local.get $i) (local.get $n)))
+            (call $_datum_int (i64.extend_i32_u (array.get_u $U16Vector (local.get $u16vector) (local.get $i))))
+            (local.set $i (i32.add (local.get $i) (i32.const 1)))
+            (br $loop)))
+        (call $_datum_uvector (i32.const 4) (local.get $n))
+        (return)))
+    (if (ref.test (ref $S16Vector) (local.get $x))
+      (then
+        (local.set $s16vector (ref.cast (ref $S16Vector) (local.get $x)))
+        (local.set $n (array.len (local.get $s16vector)))
+        (block $break
+          (loop $loop
+            (br_if $break (i32.ge_u (local.get $i) (local.get $n)))
+            (call $_datum_int (i64.extend_i32_s (array.get_s $S16Vector (local.get $s16vector) (local.get $i))))
+            (local.set $i (i32.add (local.get $i) (i32.const 1)))
+            (br $loop)))
+        (call $_datum_uvector (i32.const 5) (local.get $n))
+        (return)))
+    (if (ref.test (ref $U32Vector) (local.get $x))
+      (then
+        (local.set $u32vector (ref.cast (ref $U32Vector) (local.get $x)))
+        (local.set $n (array.len (local.get $u32vector)))
+        (block $break
+          (loop $loop
+            (br_if $break (i32.ge_u (local.get $i) (local.get $n)))
+            (call $_datum_int (i64.extend_i32_u (array.get $U32Vector (local.get $u32vector) (local.get $i))))
+            (local.set $i (i32.add (local.get $i) (i32.const 1)))
+            (br $loop)))
+        (call $_datum_uvector (i32.const 6) (local.get $n))
+        (return)))
+    (if (ref.test (ref $S32Vector) (local.get $x))
+      (then
+        (local.set $s32vector (ref.cast (ref $S32Vector) (local.get $x)))
+        (local.set $n (array.len (local.get $s32vector)))
+        (block $break
+          (loop $loop
+            (br_if $break (i32.ge_u (local.get $i) (local.get $n)))
+            (call $_datum_int (i64.extend_i32_s (array.get $S32Vector (local.get $s32vector) (local.get $i))))
+            (local.set $i (i32.add (local.get $i) (i32.const 1)))
+            (br $loop)))
+        (call $_datum_uvector (i32.const 7) (local.get $n))
+        (return)))
+    (if (ref.test (ref $F32Vector) (local.get $x))
+      (then
+        (local.set $f32vector (ref.cast (ref $F32Vector) (local.get $x)))
+        (local.set $n (array.len (local.get $f32vector)))
+        (block $break
+          (loop $loop
+            (br_if $break (i32.ge_u (local.get $i) (local.get $n)))
+            (call $_datum_float (f64.promote_f32 (array.get $F32Vector (local.get $f32vector) (local.get $i))))
+            (local.set $i (i32.add (local.get $i) (i32.const 1)))
+            (br $loop)))
+        (call $_datum_uvector (i32.const 8) (local.get $n))
+        (return)))
+    (call $_datum_invalid))
+
   (func $string_to_symbol (export "string_to_symbol") (param $s (ref null $String)) (result (ref null $Symbol))
     (local $s_ptr i32)
     (local $s_len i32)
@@ -104,6 +701,359 @@
     (call $free (local.get $s_ptr))
     (local.get $s))
 
+  (func $bigint_to_string (export "bigint_to_string") (param $x (ref null $BigInt)) (result (ref null $String))
+    (local $x_ptr i32)
+    (local $x_len i32)
+    (local $s_ptr i32)
+    (local $s_len i32)
+    (local $s (ref null $String))
+    (call $integer_to_memory (local.get $x))
+    (local.set $x_ptr)
+    (local.set $x_len)
+    (call $uncos_tuple_i32 (call $_bigint_to_string (local.get $x_ptr) (local.get $x_len)))
+    (local.set $s_ptr)
+    (local.set $s_len)
+    (local.set $s (call $memory_to_string (local.get $s_ptr) (local.get $s_len)))
+    (call $free (local.get $x_ptr))
+    (call $free (local.get $s_ptr))
+    (local.get $s))
+
+  ;; 10進数表記の文字列からbigintを生成する。i64に収まらない値のみ渡される
+  (func $string_to_bigint (export "string_to_bigint") (param $s (ref null $String)) (result (ref null $BigInt))
+    (local $s_ptr i32)
+    (local $s_len i32)
+    (local $x_ptr i32)
+    (local $x_len i32)
+    (local $x (ref null $BigInt))
+    (call $string_to_memory (local.get $s))
+    (local.set $s_ptr)
+    (local.set $s_len)
+    (call $uncos_tuple_i32 (call $_string_to_bigint (local.get $s_ptr) (local.get $s_len)))
+    (local.set $x_ptr)
+    (local.set $x_len)
+    (local.set $x (struct.new $BigInt (call $memory_to_buf (local.get $x_ptr) (local.get $x_len))))
+    (call $free (local.get $s_ptr))
+    (call $free (local.get $x_ptr))
+    (local.get $x))
+
+  ;; $a, $bはint/bigint
+  ;; op: 0: +, 1: -, 2: *, 3: quotient, 4: remainder, 5: modulo, 6: gcd, 7: lcm
+  (func $integer_binop (export "integer_binop") (param $op i32) (param $a eqref) (param $b eqref) (result eqref)
+    (local $a_ptr i32)
+    (local $a_len i32)
+    (local $b_ptr i32)
+    (local $b_len i32)
+    (local $r_ptr i32)
+    (local $r_len i32)
+    (local $r eqref)
+    (call $integer_to_memory (local.get $a))
+    (local.set $a_ptr)
+    (local.set $a_len)
+    (call $integer_to_memory (local.get $b))
+    (local.set $b_ptr)
+    (local.set $b_len)
+    (call $uncos_tuple_i32
+      (call $_integer_binop
+        (local.get $op)
+        (local.get $a_ptr)
+        (local.get $a_len)
+        (local.get $b_ptr)
+        (local.get $b_len)))
+    (local.set $r_ptr)
+    (local.set $r_len)
+    (local.set $r (call $memory_to_integer (local.get $r_ptr) (local.get $r_len)))
+    (call $free (local.get $a_ptr))
+    (call $free (local.get $b_ptr))
+    (call $free (local.get $r_ptr))
+    (local.get $r))
+
+  ;; $a < $b なら-1, $a = $b なら0, $a > $b なら1
+  (func $integer_cmp (export "integer_cmp") (param $a eqref) (param $b eqref) (result i32)
+    (local $a_ptr i32)
+    (local $a_len i32)
+    (local $b_ptr i32)
+    (local $b_len i32)
+    (local $r i32)
+    (call $integer_to_memory (local.get $a))
+    (local.set $a_ptr)
+    (local.set $a_len)
+    (call $integer_to_memory (local.get $b))
+    (local.set $b_ptr)
+    (local.set $b_len)
+    (local.set $r
+      (call $_integer_cmp
+        (local.get $a_ptr)
+        (local.get $a_len)
+        (local.get $b_ptr)
+        (local.get $b_len)))
+    (call $free (local.get $a_ptr))
+    (call $free (local.get $b_ptr))
+    (local.get $r))
+
+  ;; int/bigintを2の補数リトルエンディアンのバイト列としてメモリに書き出す
+  (func $integer_to_memory (param $x eqref) (result i32) (result i32)
+    (local $ptr i32)
+    (local $len i32)
+    (local $bytes (ref null $Buf))
+    (if (ref.test (ref $Int) (local.get $x))
+      (then
+        (local.set $len (i32.const 8))
+        (local.set $ptr (call $malloc (local.get $len)))
+        (i64.store (local.get $ptr) (struct.get $Int 0 (ref.cast (ref $Int) (local.get $x)))))
+      (else
+        (local.set $bytes (struct.get $BigInt $bytes (ref.cast (ref $BigInt) (local.get $x))))
+        (local.set $len (array.len (local.get $bytes)))
+        (local.set $ptr (call $buf_to_memory (ref.as_non_null (local.get $bytes)) (local.get $len) (i32.const 0)))))
+    (local.get $len)
+    (local.get $ptr))
+
+  ;; バイト列をint/bigintに変換する。i64に収まる場合はintになる
+  ;; バイト列は最短の表現であることを仮定している
+  (func $memory_to_integer (param $ptr i32) (param $len i32) (result eqref)
+    (local $x i64)
+    (local $i i32)
+    (local $shift i64)
+    (if (i32.gt_u (local.get $len) (i32.const 8))
+      (then
+        (return (struct.new $BigInt (call $memory_to_buf (local.get $ptr) (local.get $len))))))
+
+    (local.set $i (local.get $len))
+    (block $break
+      (loop $loop
+        (br_if $break
+          (i32.eqz (local.get $i)))
+        (local.set $i (i32.sub (local.get $i) (i32.const 1)))
+        (local.set $x
+          (i64.or
+            (i64.shl (local.get $x) (i64.const 8))
+            (i64.load8_u (i32.add (local.get $ptr) (local.get $i)))))
+        (br $loop)))
+
+    ;; 符号拡張
+    (local.set $shift (i64.extend_i32_u (i32.mul (i32.sub (i32.const 8) (local.get $len)) (i32.const 8))))
+    (struct.new $Int (i64.shr_s (i64.shl (local.get $x) (local.get $shift)) (local.get $shift))))
+
+  ;; $a, $bはint/bigint/rational
+  ;; op: 0: +, 1: -, 2: *, 3: /, 4: expt
+  (func $rational_binop (export "rational_binop") (param $op i32) (param $a eqref) (param $b eqref) (result eqref)
+    (local $a_num_ptr i32)
+    (local $a_num_len i32)
+    (local $a_den_ptr i32)
+    (local $a_den_len i32)
+    (local $b_num_ptr i32)
+    (local $b_num_len i32)
+    (local $b_den_ptr i32)
+    (local $b_den_len i32)
+    (local $r_ptr i32)
+    (local $r_len i32)
+    (local $r eqref)
+    ;; 正規化されているので正確な0は常にint
+    (if (i32.and
+          (i32.eq (local.get $op) (i32.const 3))
+          (ref.test (ref $Int) (local.get $b)))
+      (then
+        (if (i64.eqz (struct.get $Int 0 (ref.cast (ref $Int) (local.get $b))))
+          (then
+            (call $display_data_fd (i32.const 2) (array.new_data $Buf $division_by_zero_msg (i32.const 0) (i32.const 17)))
+            (call $throw_webassembly_exception)))))
+    (call $integer_to_memory (call $exact_numerator (local.get $a)))
+    (local.set $a_num_ptr)
+    (local.set $a_num_len)
+    (call $integer_to_memory (call $exact_denominator (local.get $a)))
+    (local.set $a_den_ptr)
+    (local.set $a_den_len)
+    (call $integer_to_memory (call $exact_numerator (local.get $b)))
+    (local.set $b_num_ptr)
+    (local.set $b_num_len)
+    (call $integer_to_memory (call $exact_denominator (local.get $b)))
+    (local.set $b_den_ptr)
+    (local.set $b_den_len)
+    (call $uncos_tuple_i32
+      (call $_rational_binop
+        (local.get $op)
+        (local.get $a_num_ptr)
+        (local.get $a_num_len)
+        (local.get $a_den_ptr)
+        (local.get $a_den_len)
+        (local.get $b_num_ptr)
+        (local.get $b_num_len)
+        (local.get $b_den_ptr)
+        (local.get $b_den_len)))
+    (local.set $r_ptr)
+    (local.set $r_len)
+    (local.set $r (call $memory_to_exact (local.get $r_ptr) (local.get $r_len)))
+    (call $free (local.get $a_num_ptr))
+    (call $free (local.get $a_den_ptr))
+    (call $free (local.get $b_num_ptr))
+    (call $free (local.get $b_den_ptr))
+    (call $free (local.get $r_ptr))
+    (local.get $r))
+
+  ;; $a < $b なら-1, $a = $b なら0, $a > $b なら1
+  (func $rational_cmp (export "rational_cmp") (param $a eqref) (param $b eqref) (result i32)
+    (local $a_num_ptr i32)
+    (local $a_num_len i32)
+    (local $a_den_ptr i32)
+    (local $a_den_len i32)
+    (local $b_num_ptr i32)
+    (local $b_num_len i32)
+    (local $b_den_ptr i32)
+    (local $b_den_len i32)
+    (local $r i32)
+    (call $integer_to_memory (call $exact_numerator (local.get $a)))
+    (local.set $a_num_ptr)
+    (local.set $a_num_len)
+    (call $integer_to_memory (call $exact_denominator (local.get $a)))
+    (local.set $a_den_ptr)
+    (local.set $a_den_len)
+    (call $integer_to_memory (call $exact_numerator (local.get $b)))
+    (local.set $b_num_ptr)
+    (local.set $b_num_len)
+    (call $integer_to_memory (call $exact_denominator (local.get $b)))
+    (local.set $b_den_ptr)
+    (local.set $b_den_len)
+    (local.set $r
+      (call $_rational_cmp
+        (local.get $a_num_ptr)
+        (local.get $a_num_len)
+        (local.get $a_den_ptr)
+        (local.get $a_den_len)
+        (local.get $b_num_ptr)
+        (local.get $b_num_len)
+        (local.get $b_den_ptr)
+        (local.get $b_den_len)))
+    (call $free (local.get $a_num_ptr))
+    (call $free (local.get $a_den_ptr))
+    (call $free (local.get $b_num_ptr))
+    (call $free (local.get $b_den_ptr))
+    (local.get $r))
+
+  ;; op: 0: floor, 1: round, 2: ceiling, 3: truncate, 4: exact-integer-sqrt
+  ;; 結果はint/bigint
+  (func $rational_unop (export "rational_unop") (param $op i32) (param $x eqref) (result eqref)
+    (local $num_ptr i32)
+    (local $num_len i32)
+    (local $den_ptr i32)
+    (local $den_len i32)
+    (local $r_ptr i32)
+    (local $r_len i32)
+    (local $r eqref)
+    (call $integer_to_memory (call $exact_numerator (local.get $x)))
+    (local.set $num_ptr)
+    (local.set $num_len)
+    (call $integer_to_memory (call $exact_denominator (local.get $x)))
+    (local.set $den_ptr)
+    (local.set $den_len)
+    (call $uncos_tuple_i32
+      (call $_rational_unop
+        (local.get $op)
+        (local.get $num_ptr)
+        (local.get $num_len)
+        (local.get $den_ptr)
+        (local.get $den_len)))
+    (local.set $r_ptr)
+    (local.set $r_len)
+    (local.set $r (call $memory_to_integer (local.get $r_ptr) (local.get $r_len)))
+    (call $free (local.get $num_ptr))
+    (call $free (local.get $den_ptr))
+    (call $free (local.get $r_ptr))
+    (local.get $r))
+
+  (func $rational_to_float (export "rational_to_float") (param $x eqref) (result f64)
+    (local $num_ptr i32)
+    (local $num_len i32)
+    (local $den_ptr i32)
+    (local $den_len i32)
+    (local $r f64)
+    (call $integer_to_memory (call $exact_numerator (local.get $x)))
+    (local.set $num_ptr)
+    (local.set $num_len)
+    (call $integer_to_memory (call $exact_denominator (local.get $x)))
+    (local.set $den_ptr)
+    (local.set $den_len)
+    (local.set $r
+      (call $_rational_to_float
+        (local.get $num_ptr)
+        (local.get $num_len)
+        (local.get $den_ptr)
+        (local.get $den_len)))
+    (call $free (local.get $num_ptr))
+    (call $free (local.get $den_ptr))
+    (local.get $r))
+
+  (func $float_to_exact (export "float_to_exact") (param $x f64) (result eqref)
+    (local $r_ptr i32)
+    (local $r_len i32)
+    (local $r eqref)
+    ;; inf, nanは x - x がnanになる
+    (if (f64.ne (f64.sub (local.get $x) (local.get $x)) (f64.const 0))
+      (then
+        (call $display_data_fd (i32.const 2) (array.new_data $Buf $non_finite_to_exact_msg (i32.const 0) (i32.const 42)))
+        (call $throw_webassembly_exception)))
+    (call $uncos_tuple_i32 (call $_float_to_exact (local.get $x)))
+    (local.set $r_ptr)
+    (local.set $r_len)
+    (local.set $r (call $memory_to_exact (local.get $r_ptr) (local.get $r_len)))
+    (call $free (local.get $r_ptr))
+    (local.get $r))
+
+  (func $rational_to_string (export "rational_to_string") (param $x (ref null $Rational)) (result (ref null $String))
+    (local $num_ptr i32)
+    (local $num_len i32)
+    (local $den_ptr i32)
+    (local $den_len i32)
+    (local $s_ptr i32)
+    (local $s_len i32)
+    (local $s (ref null $String))
+    (call $integer_to_memory (struct.get $Rational $num (local.get $x)))
+    (local.set $num_ptr)
+    (local.set $num_len)
+    (call $integer_to_memory (struct.get $Rational $den (local.get $x)))
+    (local.set $den_ptr)
+    (local.set $den_len)
+    (call $uncos_tuple_i32
+      (call $_rational_to_string
+        (local.get $num_ptr)
+        (local.get $num_len)
+        (local.get $den_ptr)
+        (local.get $den_len)))
+    (local.set $s_ptr)
+    (local.set $s_len)
+    (local.set $s (call $memory_to_string (local.get $s_ptr) (local.get $s_len)))
+    (call $free (local.get $num_ptr))
+    (call $free (local.get $den_ptr))
+    (call $free (local.get $s_ptr))
+    (local.get $s))
+
+  ;; int/bigint/rationalの分子
+  (func $exact_numerator (param $x eqref) (result eqref)
+    (if (result eqref) (ref.test (ref $Rational) (local.get $x))
+      (then (struct.get $Rational $num (ref.cast (ref $Rational) (local.get $x))))
+      (else (local.get $x))))
+
+  ;; int/bigint/rationalの分母
+  (func $exact_denominator (param $x eqref) (result eqref)
+    (if (result eqref) (ref.test (ref $Rational) (local.get $x))
+      (then (struct.get $Rational $den (ref.cast (ref $Rational) (local.get $x))))
+      (else (struct.new $Int (i64.const 1)))))
+
+  ;; [分子の長さ(i32)][分子][分母] の形式のバイト列をint/bigint/rationalに変換する
+  ;; 分母が空なら整数になる
+  (func $memory_to_exact (param $ptr i32) (param $len i32) (result eqref)
+    (local $num_len i32)
+    (local $den_len i32)
+    (local $num eqref)
+    (local.set $num_len (i32.load (local.get $ptr)))
+    (local.set $den_len (i32.sub (i32.sub (local.get $len) (i32.const 4)) (local.get $num_len)))
+    (local.set $num (call $memory_to_integer (i32.add (local.get $ptr) (i32.const 4)) (local.get $num_len)))
+    (if (i32.eqz (local.get $den_len))
+      (then (return (local.get $num))))
+    (struct.new $Rational
+      (local.get $num)
+      (call $memory_to_integer
+        (i32.add (i32.add (local.get $ptr) (i32.const 4)) (local.get $num_len))
+        (local.get $den_len))))
+
   ;; i64を(i32, i32)として解釈する
   (func $uncos_tuple_i32 (param $x i64) (result i32) (result i32)
     (i32.wrap_i64 (i64.shr_u (local.get $x) (i64.const 32)))
@@ -189,7 +1139,42 @@
     (ref.cast (ref $ClosureFunc))
     (local.set $func)
 
-    (call_ref $ClosureFunc (local.get $closure) (local.get $params) (local.get $func)))
+    (return_call_ref $ClosureFunc (local.get $closure) (local.get $params) (local.get $func)))
+
+  (func $raise (export "raise") (param $obj eqref) (result eqref)
+    ;; 捕捉するwith_raise_handlerがなければプログラムを終了する
+    (if (i32.eqz (global.get $raise_handler_depth))
+      (then
+        (call $throw_webassembly_exception)))
+    (throw $RAISE (local.get $obj)))
+
+  ;; thunkの実行中にraiseされたらhandlerをそのオブジェクトで呼ぶ
+  (func $with_raise_handler (export "with_raise_handler") (param $thunk (ref null $Closure)) (param $handler (ref null $Closure)) (result eqref)
+    (local $result eqref)
+    (local $exn exnref)
+
+    (global.set $raise_handler_depth (i32.add (global.get $raise_handler_depth) (i32.const 1)))
+    (block $other (result exnref)
+      (block $raised (result eqref)
+        (try_table (result eqref) (catch $RAISE $raised) (catch_all_ref $other)
+          (call $call_closure (local.get $thunk) (array.new_fixed $Args 0)))
+        (local.set $result)
+        (global.set $raise_handler_depth (i32.sub (global.get $raise_handler_depth) (i32.const 1)))
+        (return (local.get $result)))
+      (local.set $result)
+      (global.set $raise_handler_depth (i32.sub (global.get $raise_handler_depth) (i32.const 1)))
+      (return_call $call_closure (local.get $handler) (array.new_fixed $Args 1 (local.get $result))))
+    (local.set $exn)
+    (global.set $raise_handler_depth (i32.sub (global.get $raise_handler_depth) (i32.const 1)))
+    (throw_ref (local.get $exn)))
+
+  (func $display_data_fd (param $fd i32) (param $buf (ref $Buf))
+    (call $display_fd
+      (local.get $fd)
+      (struct.new $String
+        (struct.new $StringBuf (local.get $buf) (i32.const 0))
+        (array.len (local.get $buf))
+        (i32.const 0))))
 
   (func $args_to_list (export "args_to_list") (param $args (ref null $Args)) (param $start_idx i32) (result eqref)
     (local $len i32)
@@ -213,6 +1198,36 @@
 
     (local.get $cons))
 
+  ;; applyで使う
+  (func $list_to_args (export "list_to_args") (param $list eqref) (result (ref null $Args))
+    (local $len i32)
+    (local $i i32)
+    (local $rest eqref)
+    (local $args (ref null $Args))
+
+    (local.set $rest (local.get $list))
+    (block $break
+      (loop $loop
+        (br_if $break (i32.eqz (ref.test (ref $Cons) (local.get $rest))))
+        (local.set $rest (struct.get $Cons $cdr (ref.cast (ref $Cons) (local.get $rest))))
+        (local.set $len (i32.add (local.get $len) (i32.const 1)))
+        (br $loop)))
+    (if (i32.eqz (ref.test (ref $Nil) (local.get $rest)))
+      (then
+        (call $display_data_fd (i32.const 2) (array.new_data $Buf $apply_non_list_msg (i32.const 0) (i32.const 36)))
+        (call $throw_webassembly_exception)))
+
+    (local.set $args (array.new $Args (ref.null eq) (local.get $len)))
+    (local.set $rest (local.get $list))
+    (block $break
+      (loop $loop
+        (br_if $break (i32.ge_u (local.get $i) (local.get $len)))
+        (array.set $Args (local.get $args) (local.get $i) (struct.get $Cons $car (ref.cast (ref $Cons) (local.get $rest))))
+        (local.set $rest (struct.get $Cons $cdr (ref.cast (ref $Cons) (local.get $rest))))
+        (local.set $i (i32.add (local.get $i) (i32.const 1)))
+        (br $loop)))
+    (local.get $args))
+
   (func $copy_buf (param $buf (ref $Buf)) (param $offset i32) (param $len i32) (result (ref $Buf))
     (local $new_buf (ref $Buf))
     (local $i i32)
@@ -284,14 +1299,656 @@
     (local.set $s_buf (struct.get $String $buf (local.get $s)))
     (if (i32.eq (struct.get $StringBuf $shared (local.get $s_buf)) (i32.const 1))
       (then
+        ;; offsetは変更できないのでバッファ全体をコピーする
         (local.set $s_buf
           (struct.new $StringBuf
             (call $copy_buf
               (struct.get $StringBuf $buf (local.get $s_buf))
-              (struct.get $String $offset (local.get $s))
-              (struct.get $String $len (local.get $s)))
+              (i32.const 0)
+              (array.len (struct.get $StringBuf $buf (local.get $s_buf))))
             (i32.const 0)))
         (struct.set $String $buf (local.get $s) (local.get $s_buf)))))
 
   (func $string_length (export "string_length") (param $s (ref null $String)) (result i32)
-    (struct.get $String $len (local.get $s))))
+    (struct.get $String $len (local.get $s)))
+
+  (func $string_append (export "string_append") (param $s1 (ref null $String)) (param $s2 (ref null $String)) (result (ref null $String))
+    (local $len1 i32)
+    (local $len2 i32)
+    (local $buf (ref $Buf))
+
+    (local.set $len1 (struct.get $String $len (local.get $s1)))
+    (local.set $len2 (struct.get $String $len (local.get $s2)))
+    (local.set $buf (array.new $Buf (i32.const 0) (i32.add (local.get $len1) (local.get $len2))))
+    (array.copy $Buf $Buf
+      (local.get $buf)
+      (i32.const 0)
+      (struct.get $StringBuf $buf (struct.get $String $buf (local.get $s1)))
+      (struct.get $String $offset (local.get $s1))
+      (local.get $len1))
+    (array.copy $Buf $Buf
+      (local.get $buf)
+      (local.get $len1)
+      (struct.get $StringBuf $buf (struct.get $String $buf (local.get $s2)))
+      (struct.get $String $offset (local.get $s2))
+      (local.get $len2))
+    (struct.new $String
+      (struct.new $StringBuf (local.get $buf) (i32.const 0))
+      (i32.add (local.get $len1) (local.get $len2))
+      (i32.const 0)))
+
+  ;; string_copyと同様にバッファを共有する
+  (func $substring (export "substring") (param $s (ref null $String)) (param $start i32) (param $end i32) (result (ref null $String))
+    (local $s_buf (ref $StringBuf))
+
+    ;; 負のインデックスも符号なし比較で弾く
+    (if (i32.or
+          (i32.gt_u (local.get $start) (local.get $end))
+          (i32.gt_u (local.get $end) (struct.get $String $len (local.get $s))))
+      (then
+        (call $display_data_fd (i32.const 2) (array.new_data $Buf $substring_range_msg (i32.const 0) (i32.const 30)))
+        (call $throw_webassembly_exception)))
+    (local.set $s_buf (struct.get $String $buf (local.get $s)))
+    (struct.set $StringBuf $shared (local.get $s_buf) (i32.const 1))
+    (struct.new $String
+      (local.get $s_buf)
+      (i32.sub (local.get $end) (local.get $start))
+      (i32.add (struct.get $String $offset (local.get $s)) (local.get $start))))
+
+  (func $char_utf8_len (param $c i32) (result i32)
+    (if (i32.lt_u (local.get $c) (i32.const 0x80))
+      (then (return (i32.const 1))))
+    (if (i32.lt_u (local.get $c) (i32.const 0x800))
+      (then (return (i32.const 2))))
+    (if (i32.lt_u (local.get $c) (i32.const 0x10000))
+      (then (return (i32.const 3))))
+    (i32.const 4))
+
+  ;; 書き込んだ後の位置を返す
+  (func $write_char_utf8 (param $buf (ref $Buf)) (param $pos i32) (param $c i32) (result i32)
+    (local $len i32)
+    (local $i i32)
+
+    (local.set $len (call $char_utf8_len (local.get $c)))
+    (if (i32.eq (local.get $len) (i32.const 1))
+      (then
+        (array.set $Buf (local.get $buf) (local.get $pos) (local.get $c))
+        (return (i32.add (local.get $pos) (i32.const 1)))))
+    ;; 継続バイトを後ろから書き込む
+    (local.set $i (i32.sub (local.get $len) (i32.const 1)))
+    (block $break
+      (loop $loop
+        (br_if $break (i32.eqz (local.get $i)))
+        (array.set $Buf
+          (local.get $buf)
+          (i32.add (local.get $pos) (local.get $i))
+          (i32.or (i32.const 0x80) (i32.and (local.get $c) (i32.const 0x3F))))
+        (local.set $c (i32.shr_u (local.get $c) (i32.const 6)))
+        (local.set $i (i32.sub (local.get $i) (i32.const 1)))
+        (br $loop)))
+    ;; 先頭バイト: 2バイトなら0xC0, 3バイトなら0xE0, 4バイトなら0xF0
+    (array.set $Buf
+      (local.get $buf)
+      (local.get $pos)
+      (i32.or
+        (i32.and (i32.shr_u (i32.const 0xFF00) (local.get $len)) (i32.const 0xFF))
+        (local.get $c)))
+    (i32.add (local.get $pos) (local.get $len)))
+
+  ;; posから1文字読み、文字とバイト数を返す
+  ;; 不正なバイト列は1バイトを1文字として扱う
+  (func $read_char_utf8 (param $buf (ref $Buf)) (param $pos i32) (param $end i32) (result i32 i32)
+    (local $b i32)
+    (local $c i32)
+    (local $len i32)
+    (local $i i32)
+
+    (local.set $b (array.get_u $Buf (local.get $buf) (local.get $pos)))
+    (if (i32.lt_u (local.get $b) (i32.const 0xC0))
+      (then (return (local.get $b) (i32.const 1))))
+    (if (i32.lt_u (local.get $b) (i32.const 0xE0))
+      (then
+        (local.set $c (i32.and (local.get $b) (i32.const 0x1F)))
+        (local.set $len (i32.const 2)))
+      (else
+        (if (i32.lt_u (local.get $b) (i32.const 0xF0))
+          (then
+            (local.set $c (i32.and (local.get $b) (i32.const 0x0F)))
+            (local.set $len (i32.const 3)))
+          (else
+            (local.set $c (i32.and (local.get $b) (i32.const 0x07)))
+            (local.set $len (i32.const 4))))))
+    (if (i32.gt_u (i32.add (local.get $pos) (local.get $len)) (local.get $end))
+      (then (return (local.get $b) (i32.const 1))))
+    (local.set $i (i32.const 1))
+    (block $break
+      (loop $loop
+        (br_if $break (i32.ge_u (local.get $i) (local.get $len)))
+        (local.set $c
+          (i32.or
+            (i32.shl (local.get $c) (i32.const 6))
+            (i32.and
+              (array.get_u $Buf (local.get $buf) (i32.add (local.get $pos) (local.get $i)))
+              (i32.const 0x3F))))
+        (local.set $i (i32.add (local.get $i) (i32.const 1)))
+        (br $loop)))
+    (local.get $c)
+    (local.get $len))
+
+  (func $make_string (export "make_string") (param $k i32) (param $c i32) (result (ref null $String))
+    (local $buf (ref $Buf))
+    (local $len i32)
+    (local $pos i32)
+    (local $i i32)
+
+    (if (i32.lt_s (local.get $k) (i32.const 0))
+      (then
+        (call $display_data_fd (i32.const 2) (array.new_data $Buf $make_string_negative_msg (i32.const 0) (i32.const 29)))
+        (call $throw_webassembly_exception)))
+    (local.set $len (i32.mul (local.get $k) (call $char_utf8_len (local.get $c))))
+    (local.set $buf (array.new $Buf (i32.const 0) (local.get $len)))
+    (block $break
+      (loop $loop
+        (br_if $break (i32.ge_u (local.get $i) (local.get $k)))
+        (local.set $pos (call $write_char_utf8 (local.get $buf) (local.get $pos) (local.get $c)))
+        (local.set $i (i32.add (local.get $i) (i32.const 1)))
+        (br $loop)))
+    (struct.new $String
+      (struct.new $StringBuf (local.get $buf) (i32.const 0))
+      (local.get $len)
+      (i32.const 0)))
+
+  (func $list_to_string (export "list_to_string") (param $list eqref) (result (ref null $String))
+    (local $rest eqref)
+    (local $car eqref)
+    (local $len i32)
+    (local $pos i32)
+    (local $buf (ref $Buf))
+
+    ;; 1周目で検査とバイト数の計算をする
+    (local.set $rest (local.get $list))
+    (block $break
+      (loop $loop
+        (br_if $break (i32.eqz (ref.test (ref $Cons) (local.get $rest))))
+        (local.set $car (struct.get $Cons $car (ref.cast (ref $Cons) (local.get $rest))))
+        (if (i32.eqz (ref.test (ref $Char) (local.get $car)))
+          (then
+            (call $display_data_fd (i32.const 2) (array.new_data $Buf $list_to_string_msg (i32.const 0) (i32.const 52)))
+            (call $throw_webassembly_exception)))
+        (local.set $len
+          (i32.add
+            (local.get $len)
+            (call $char_utf8_len (struct.get $Char $value (ref.cast (ref $Char) (local.get $car))))))
+        (local.set $rest (struct.get $Cons $cdr (ref.cast (ref $Cons) (local.get $rest))))
+        (br $loop)))
+    (if (i32.eqz (ref.test (ref $Nil) (local.get $rest)))
+      (then
+        (call $display_data_fd (i32.const 2) (array.new_data $Buf $list_to_string_msg (i32.const 0) (i32.const 52)))
+        (call $throw_webassembly_exception)))
+
+    (local.set $buf (array.new $Buf (i32.const 0) (local.get $len)))
+    (local.set $rest (local.get $list))
+    (block $break
+      (loop $loop
+        (br_if $break (i32.eqz (ref.test (ref $Cons) (local.get $rest))))
+        (local.set $pos
+          (call $write_char_utf8
+            (local.get $buf)
+            (local.get $pos)
+            (struct.get $Char $value (ref.cast (ref $Char) (struct.get $Cons $car (ref.cast (ref $Cons) (local.get $rest)))))))
+        (local.set $rest (struct.get $Cons $cdr (ref.cast (ref $Cons) (local.get $rest))))
+        (br $loop)))
+    (struct.new $String
+      (struct.new $StringBuf (local.get $buf) (i32.const 0))
+      (local.get $len)
+      (i32.const 0)))
+
+  (func $string_to_list (export "string_to_list") (param $s (ref null $String)) (result eqref)
+    (local $buf (ref $Buf))
+    (local $pos i32)
+    (local $end i32)
+    (local $c i32)
+    (local $len i32)
+    (local $head (ref $Cons))
+    (local $last (ref $Cons))
+    (local $cons (ref $Cons))
+
+    (local.set $buf (struct.get $StringBuf $buf (struct.get $String $buf (local.get $s))))
+    (local.set $pos (struct.get $String $offset (local.get $s)))
+    (local.set $end (i32.add (local.get $pos) (struct.get $String $len (local.get $s))))
+    ;; 先頭のダミーのセルに繋げていく
+    (local.set $head (struct.new $Cons (global.get $nil) (global.get $nil)))
+    (local.set $last (local.get $head))
+    (block $break
+      (loop $loop
+        (br_if $break (i32.ge_u (local.get $pos) (local.get $end)))
+        (call $read_char_utf8 (local.get $buf) (local.get $pos) (local.get $end))
+        (local.set $len)
+        (local.set $c)
+        (local.set $cons (struct.new $Cons (struct.new $Char (local.get $c)) (global.get $nil)))
+        (struct.set $Cons $cdr (local.get $last) (local.get $cons))
+        (local.set $last (local.get $cons))
+        (local.set $pos (i32.add (local.get $pos) (local.get $len)))
+        (br $loop)))
+    (struct.get $Cons $cdr (local.get $head)))
+
+  ;; バイト列の辞書順で比較する。UTF-8ではコードポイント順と一致する
+  (func $string_cmp (export "string_cmp") (param $s1 (ref null $String)) (param $s2 (ref null $String)) (result i32)
+    (local $len1 i32)
+    (local $len2 i32)
+    (local $i i32)
+    (local $b1 i32)
+    (local $b2 i32)
+    (local $buf1 (ref $Buf))
+    (local $buf2 (ref $Buf))
+
+    (local.set $len1 (struct.get $String $len (local.get $s1)))
+    (local.set $len2 (struct.get $String $len (local.get $s2)))
+    (local.set $buf1 (struct.get $StringBuf $buf (struct.get $String $buf (local.get $s1))))
+    (local.set $buf2 (struct.get $StringBuf $buf (struct.get $String $buf (local.get $s2))))
+
+    (block $break
+      (loop $loop
+        (br_if $break
+          (i32.or
+            (i32.ge_u (local.get $i) (local.get $len1))
+            (i32.ge_u (local.get $i) (local.get $len2))))
+        (local.set $b1 (array.get_u $Buf (local.get $buf1) (i32.add (struct.get $String $offset (local.get $s1)) (local.get $i))))
+        (local.set $b2 (array.get_u $Buf (local.get $buf2) (i32.add (struct.get $String $offset (local.get $s2)) (local.get $i))))
+        (if (i32.ne (local.get $b1) (local.get $b2))
+          (then
+            (return (select (i32.const -1) (i32.const 1) (i32.lt_u (local.get $b1) (local.get $b2))))))
+        (local.set $i (i32.add (local.get $i) (i32.const 1)))
+        (br $loop)))
+
+    (i32.sub
+      (i32.gt_u (local.get $len1) (local.get $len2))
+      (i32.lt_u (local.get $len1) (local.get $len2))))
+
+  (func $string_cmp_ci (export "string_cmp_ci") (param $s1 (ref null $String)) (param $s2 (ref null $String)) (result i32)
+    (local $s1_ptr i32)
+    (local $s1_len i32)
+    (local $s2_ptr i32)
+    (local $s2_len i32)
+    (local $result i32)
+    (call $string_to_memory (local.get $s1))
+    (local.set $s1_ptr)
+    (local.set $s1_len)
+    (call $string_to_memory (local.get $s2))
+    (local.set $s2_ptr)
+    (local.set $s2_len)
+    (local.set $result
+      (call $_string_cmp_ci (local.get $s1_ptr) (local.get $s1_len) (local.get $s2_ptr) (local.get $s2_len)))
+    (call $free (local.get $s1_ptr))
+    (call $free (local.get $s2_ptr))
+    (local.get $result))
+
+  ;; 数値または#fを返す
+  (func $string_to_number (export "string_to_number") (param $s (ref null $String)) (param $radix i32) (result eqref)
+    (local $s_ptr i32)
+    (local $s_len i32)
+    (local $ptr i32)
+    (local $len i32)
+    (local $result eqref)
+    (call $string_to_memory (local.get $s))
+    (local.set $s_ptr)
+    (local.set $s_len)
+    (call $uncos_tuple_i32 (call $_string_to_number (local.get $s_ptr) (local.get $s_len) (local.get $radix)))
+    (local.set $ptr)
+    (local.set $len)
+    (call $decode_datum (local.get $ptr))
+    (drop)
+    (local.set $result)
+    (call $free (local.get $s_ptr))
+    (call $free (local.get $ptr))
+    (local.get $result))
+
+  (func $make_hash_table (export "make_hash_table") (param $kind i32) (result (ref null $HashTable))
+    (struct.new $HashTable
+      (local.get $kind)
+      (array.new $Vector (global.get $nil) (i32.const 16))
+      (i32.const 0)))
+
+  ;; FNV-1a
+  (func $buf_hash (param $buf (ref $Buf)) (param $start i32) (param $end i32) (result i32)
+    (local $h i32)
+    (local.set $h (i32.const 0x811c9dc5))
+    (block $break
+      (loop $loop
+        (br_if $break (i32.ge_u (local.get $start) (local.get $end)))
+        (local.set $h
+          (i32.mul
+            (i32.xor (local.get $h) (array.get_u $Buf (local.get $buf) (local.get $start)))
+            (i32.const 16777619)))
+        (local.set $start (i32.add (local.get $start) (i32.const 1)))
+        (br $loop)))
+    (local.get $h))
+
+  (func $i64_hash (param $x i64) (result i32)
+    (i32.wrap_i64 (i64.xor (local.get $x) (i64.shr_u (local.get $x) (i64.const 32)))))
+
+  ;; $deepが1ならequal?、0ならeqv?で等しい値が同じハッシュ値になる
+  ;; ペアなど同一性で比較される値は型が同じなら同じハッシュ値になる
+  ;; 循環構造で止まるように$depthより深いペアは見ない
+  (func $hash (param $x eqref) (param $deep i32) (param $depth i32) (result i32)
+    (local $f f64)
+    (local $s (ref null $String))
+    (if (ref.test (ref $Int) (local.get $x))
+      (then
+        (return (call $i64_hash (struct.get $Int 0 (ref.cast (ref $Int) (local.get $x)))))))
+    (if (ref.test (ref $Float) (local.get $x))
+      (then
+        (local.set $f (struct.get $Float 0 (ref.cast (ref $Float) (local.get $x))))
+        ;; eqv?では0.0と-0.0が等しい
+        (if (f64.eq (local.get $f) (f64.const 0))
+          (then
+            (return (i32.const 0))))
+        (return (call $i64_hash (i64.reinterpret_f64 (local.get $f))))))
+    (if (ref.test (ref $Char) (local.get $x))
+      (then
+        (return (struct.get $Char $value (ref.cast (ref $Char) (local.get $x))))))
+    (if (ref.test (ref $Symbol) (local.get $x))
+      (then
+        (local.set $s (struct.get $Symbol $name (ref.cast (ref $Symbol) (local.get $x))))
+        (return (call $string_hash (local.get $s)))))
+    (if (ref.test (ref $BigInt) (local.get $x))
+      (then
+        (return
+          (call $buf_hash
+            (struct.get $BigInt $bytes (ref.cast (ref $BigInt) (local.get $x)))
+            (i32.const 0)
+            (array.len (struct.get $BigInt $bytes (ref.cast (ref $BigInt) (local.get $x))))))))
+    (if (ref.test (ref $Rational) (local.get $x))
+      (then
+        (return
+          (i32.add
+            (i32.mul
+              (call $hash (struct.get $Rational $num (ref.cast (ref $Rational) (local.get $x))) (i32.const 0) (i32.const 0))
+              (i32.const 31))
+            (call $hash (struct.get $Rational $den (ref.cast (ref $Rational) (local.get $x))) (i32.const 0) (i32.const 0))))))
+    (if (i32.and (local.get $deep) (ref.test (ref $String) (local.get $x)))
+      (then
+        (return (call $string_hash (ref.cast (ref $String) (local.get $x))))))
+    (if (i32.and (local.get $deep) (ref.test (ref $Cons) (local.get $x)))
+      (then
+        (if (i32.eqz (local.get $depth))
+          (then
+            (return (i32.const 1))))
+        (return
+          (i32.add
+            (i32.mul
+              (call $hash
+                (struct.get $Cons $car (ref.cast (ref $Cons) (local.get $x)))
+                (i32.const 1)
+                (i32.sub (local.get $depth) (i32.const 1)))
+              (i32.const 31))
+            (call $hash
+              (struct.get $Cons $cdr (ref.cast (ref $Cons) (local.get $x)))
+              (i32.const 1)
+              (i32.sub (local.get $depth) (i32.const 1)))))))
+    (i32.const 0))
+
+  (func $string_hash (param $s (ref null $String)) (result i32)
+    (call $buf_hash
+      (struct.get $StringBuf $buf (struct.get $String $buf (local.get $s)))
+      (struct.get $String $offset (local.get $s))
+      (i32.add (struct.get $String $offset (local.get $s)) (struct.get $String $len (local.get $s)))))
+
+  ;; IRのeqv?と同じ規則で比較する
+  (func $eqv (param $a eqref) (param $b eqref) (result i32)
+    (if (ref.eq (local.get $a) (local.get $b))
+      (then
+        (return (i32.const 1))))
+    (if (i32.and (ref.test (ref $Int) (local.get $a)) (ref.test (ref $Int) (local.get $b)))
+      (then
+        (return
+          (i64.eq
+            (struct.get $Int 0 (ref.cast (ref $Int) (local.get $a)))
+            (struct.get $Int 0 (ref.cast (ref $Int) (local.get $b)))))))
+    (if (i32.and (ref.test (ref $Float) (local.get $a)) (ref.test (ref $Float) (local.get $b)))
+      (then
+        (return
+          (f64.eq
+            (struct.get $Float 0 (ref.cast (ref $Float) (local.get $a)))
+            (struct.get $Float 0 (ref.cast (ref $Float) (local.get $b)))))))
+    (if (i32.and (ref.test (ref $Char) (local.get $a)) (ref.test (ref $Char) (local.get $b)))
+      (then
+        (return
+          (i32.eq
+            (struct.get $Char $value (ref.cast (ref $Char) (local.get $a)))
+            (struct.get $Char $value (ref.cast (ref $Char) (local.get $b)))))))
+    (if (i32.and (ref.test (ref $BigInt) (local.get $a)) (ref.test (ref $BigInt) (local.get $b)))
+      (then
+        (return (i32.eqz (call $integer_cmp (local.get $a) (local.get $b))))))
+    (if (i32.and (ref.test (ref $Rational) (local.get $a)) (ref.test (ref $Rational) (local.get $b)))
+      (then
+        (return (i32.eqz (call $rational_cmp (local.get $a) (local.get $b))))))
+    (i32.const 0))
+
+  ;; stdlibのequal?と同じ規則で比較する
+  (func $equal (param $a eqref) (param $b eqref) (result i32)
+    (if (call $eqv (local.get $a) (local.get $b))
+      (then
+        (return (i32.const 1))))
+    (if (i32.and (ref.test (ref $Cons) (local.get $a)) (ref.test (ref $Cons) (local.get $b)))
+      (then
+        (if (i32.eqz
+              (call $equal
+                (struct.get $Cons $car (ref.cast (ref $Cons) (local.get $a)))
+                (struct.get $Cons $car (ref.cast (ref $Cons) (local.get $b)))))
+          (then
+            (return (i32.const 0))))
+        (return
+          (call $equal
+            (struct.get $Cons $cdr (ref.cast (ref $Cons) (local.get $a)))
+            (struct.get $Cons $cdr (ref.cast (ref $Cons) (local.get $b)))))))
+    (if (i32.and (ref.test (ref $String) (local.get $a)) (ref.test (ref $String) (local.get $b)))
+      (then
+        (return
+          (call $string_eq
+            (ref.cast (ref $String) (local.get $a))
+            (ref.cast (ref $String) (local.get $b))))))
+    (i32.const 0))
+
+  (func $hash_table_key_eq (param $kind i32) (param $a eqref) (param $b eqref) (result i32)
+    (if (i32.eqz (local.get $kind))
+      (then
+        (return (ref.eq (local.get $a) (local.get $b)))))
+    (if (i32.eq (local.get $kind) (i32.const 1))
+      (then
+        (return (call $eqv (local.get $a) (local.get $b)))))
+    (call $equal (local.get $a) (local.get $b)))
+
+  (func $hash_table_index (param $table (ref null $HashTable)) (param $key eqref) (result i32)
+    (local $h i32)
+    (local.set $h
+      (call $hash
+        (local.get $key)
+        (i32.eq (struct.get_u $HashTable $kind (local.get $table)) (i32.const 2))
+        (i32.const 4)))
+    ;; バケット数は2の冪なので下位ビットに上位ビットを混ぜる
+    (local.set $h (i32.xor (local.get $h) (i32.shr_u (local.get $h) (i32.const 16))))
+    (local.set $h (i32.mul (local.get $h) (i32.const 0x45d9f3b)))
+    (local.set $h (i32.xor (local.get $h) (i32.shr_u (local.get $h) (i32.const 16))))
+    (i32.rem_u (local.get $h) (array.len (struct.get $HashTable $buckets (local.get $table)))))
+
+  ;; (key . value)を返す。見つからなければnull
+  (func $hash_table_find (param $table (ref null $HashTable)) (param $key eqref) (result (ref null $Cons))
+    (local $kind i32)
+    (local $l eqref)
+    (local $entry (ref $Cons))
+    (local.set $kind (struct.get_u $HashTable $kind (local.get $table)))
+    (local.set $l
+      (array.get $Vector
+        (struct.get $HashTable $buckets (local.get $table))
+        (call $hash_table_index (local.get $table) (local.get $key))))
+    (block $break
+      (loop $loop
+        (br_if $break (i32.eqz (ref.test (ref $Cons) (local.get $l))))
+        (local.set $entry (ref.cast (ref $Cons) (struct.get $Cons $car (ref.cast (ref $Cons) (local.get $l)))))
+        (if (call $hash_table_key_eq (local.get $kind) (struct.get $Cons $car (local.get $entry)) (local.get $key))
+          (then
+            (return (local.get $entry))))
+        (local.set $l (struct.get $Cons $cdr (ref.cast (ref $Cons) (local.get $l))))
+        (br $loop)))
+    (ref.null $Cons))
+
+  (func $hash_table_ref (export "hash_table_ref") (param $table (ref null $HashTable)) (param $key eqref) (param $default eqref) (result eqref)
+    (local $entry (ref null $Cons))
+    (local.set $entry (call $hash_table_find (local.get $table) (local.get $key)))
+    (if (result eqref) (ref.is_null (local.get $entry))
+      (then
+        (local.get $default))
+      (else
+        (struct.get $Cons $cdr (local.get $entry)))))
+
+  (func $hash_table_set (export "hash_table_set") (param $table (ref null $HashTable)) (param $key eqref) (param $value eqref)
+    (local $entry (ref null $Cons))
+    (local $buckets (ref $Vector))
+    (local $index i32)
+    (local.set $entry (call $hash_table_find (local.get $table) (local.get $key)))
+    (if (i32.eqz (ref.is_null (local.get $entry)))
+      (then
+        (struct.set $Cons $cdr (local.get $entry) (local.get $value))
+        (return)))
+    ;; 要素数がバケット数の2倍に達したら拡張する
+    (if (i32.ge_u
+          (struct.get $HashTable $count (local.get $table))
+          (i32.shl (array.len (struct.get $HashTable $buckets (local.get $table))) (i32.const 1)))
+      (then
+        (call $hash_table_grow (local.get $table))))
+    (local.set $buckets (struct.get $HashTable $buckets (local.get $table)))
+    (local.set $index (call $hash_table_index (local.get $table) (local.get $key)))
+    (array.set $Vector
+      (local.get $buckets)
+      (local.get $index)
+      (struct.new $Cons
+        (struct.new $Cons (local.get $key) (local.get $value))
+        (array.get $Vector (local.get $buckets) (local.get $index))))
+    (struct.set $HashTable $count
+      (local.get $table)
+      (i32.add (struct.get $HashTable $count (local.get $table)) (i32.const 1))))
+
+  (func $hash_table_grow (param $table (ref null $HashTable))
+    (local $old (ref $Vector))
+    (local $new (ref $Vector))
+    (local $i i32)
+    (local $l eqref)
+    (local $cell (ref $Cons))
+    (local $index i32)
+    (local.set $old (struct.get $HashTable $buckets (local.get $table)))
+    (local.set $new
+      (array.new $Vector (global.get $nil) (i32.shl (array.len (local.get $old)) (i32.const 1))))
+    (struct.set $HashTable $buckets (local.get $table) (local.get $new))
+    ;; セルを作り直さずに新しいバケットに繋ぎ替える
+    (block $break
+      (loop $loop
+        (br_if $break (i32.ge_u (local.get $i) (array.len (local.get $old))))
+        (local.set $l (array.get $Vector (local.get $old) (local.get $i)))
+        (block $bucket_break
+          (loop $bucket_loop
+            (br_if $bucket_break (i32.eqz (ref.test (ref $Cons) (local.get $l))))
+            (local.set $cell (ref.cast (ref $Cons) (local.get $l)))
+            (local.set $l (struct.get $Cons $cdr (local.get $cell)))
+            (local.set $index
+              (call $hash_table_index
+                (local.get $table)
+                (struct.get $Cons $car (ref.cast (ref $Cons) (struct.get $Cons $car (local.get $cell))))))
+            (struct.set $Cons $cdr (local.get $cell) (array.get $Vector (local.get $new) (local.get $index)))
+            (array.set $Vector (local.get $new) (local.get $index) (local.get $cell))
+            (br $bucket_loop)))
+        (local.set $i (i32.add (local.get $i) (i32.const 1)))
+        (br $loop))))
+
+  (func $hash_table_delete (export "hash_table_delete") (param $table (ref null $HashTable)) (param $key eqref)
+    (local $kind i32)
+    (local $buckets (ref $Vector))
+    (local $index i32)
+    (local $prev (ref null $Cons))
+    (local $l eqref)
+    (local $cell (ref $Cons))
+    (local.set $kind (struct.get_u $HashTable $kind (local.get $table)))
+    (local.set $buckets (struct.get $HashTable $buckets (local.get $table)))
+    (local.set $index (call $hash_table_index (local.get $table) (local.get $key)))
+    (local.set $l (array.get $Vector (local.get $buckets) (local.get $index)))
+    (block $break
+      (loop $loop
+        (br_if $break (i32.eqz (ref.test (ref $Cons) (local.get $l))))
+        (local.set $cell (ref.cast (ref $Cons) (local.get $l)))
+        (if (call $hash_table_key_eq
+              (local.get $kind)
+              (struct.get $Cons $car (ref.cast (ref $Cons) (struct.get $Cons $car (local.get $cell))))
+              (local.get $key))
+          (then
+            (if (ref.is_null (local.get $prev))
+              (then
+                (array.set $Vector (local.get $buckets) (local.get $index) (struct.get $Cons $cdr (local.get $cell))))
+              (else
+                (struct.set $Cons $cdr (local.get $prev) (struct.get $Cons $cdr (local.get $cell)))))
+            (struct.set $HashTable $count
+              (local.get $table)
+              (i32.sub (struct.get $HashTable $count (local.get $table)) (i32.const 1)))
+            (return)))
+        (local.set $prev (local.get $cell))
+        (local.set $l (struct.get $Cons $cdr (local.get $cell)))
+        (br $loop))))
+
+  ;; 新しい(key . value)のリストを返す。順序は不定
+  (func $hash_table_to_alist (export "hash_table_to_alist") (param $table (ref null $HashTable)) (result eqref)
+    (local $buckets (ref $Vector))
+    (local $i i32)
+    (local $l eqref)
+    (local $entry (ref $Cons))
+    (local $result eqref)
+    (local.set $buckets (struct.get $HashTable $buckets (local.get $table)))
+    (local.set $result (global.get $nil))
+    (block $break
+      (loop $loop
+        (br_if $break (i32.ge_u (local.get $i) (array.len (local.get $buckets))))
+        (local.set $l (array.get $Vector (local.get $buckets) (local.get $i)))
+        (block $bucket_break
+          (loop $bucket_loop
+            (br_if $bucket_break (i32.eqz (ref.test (ref $Cons) (local.get $l))))
+            (local.set $entry (ref.cast (ref $Cons) (struct.get $Cons $car (ref.cast (ref $Cons) (local.get $l)))))
+            (local.set $result
+              (struct.new $Cons
+                (struct.new $Cons (struct.get $Cons $car (local.get $entry)) (struct.get $Cons $cdr (local.get $entry)))
+                (local.get $result)))
+            (local.set $l (struct.get $Cons $cdr (ref.cast (ref $Cons) (local.get $l))))
+            (br $bucket_loop)))
+        (local.set $i (i32.add (local.get $i) (i32.const 1)))
+        (br $loop)))
+    (local.get $result))
+
+  ;; 文字列はUTF-8のバイト列として保持しているので、バイト列をそのままコピーする
+  (func $string_to_utf8 (export "string_to_utf8") (param $s (ref null $String)) (result (ref null $U8Vector))
+    (local $len i32)
+    (local $bytes (ref $U8Vector))
+
+    (local.set $len (struct.get $String $len (local.get $s)))
+    (local.set $bytes (array.new_default $U8Vector (local.get $len)))
+    (array.copy $U8Vector $Buf
+      (local.get $bytes)
+      (i32.const 0)
+      (struct.get $StringBuf $buf (struct.get $String $buf (local.get $s)))
+      (struct.get $String $offset (local.get $s))
+      (local.get $len))
+    (local.get $bytes))
+
+  ;; 不正なUTF-8のバイト列は検査しない
+  (func $utf8_to_string (export "utf8_to_string") (param $bytes (ref null $U8Vector)) (result (ref null $String))
+    (local $len i32)
+    (local $buf (ref $Buf))
+
+    (local.set $len (array.len (local.get $bytes)))
+    (local.set $buf (array.new_default $Buf (local.get $len)))
+    (array.copy $Buf $U8Vector
+      (local.get $buf)
+      (i32.const 0)
+      (local.get $bytes)
+      (i32.const 0)
+      (local.get $len))
+    (struct.new $String
+      (struct.new $StringBuf (local.get $buf) (i32.const 0))
+      (local.get $len)
+      (i32.const 0))))